@@ -25,6 +25,10 @@ fn generate_c_bindings() {
             ("target_os = windows".to_string(), "_WIN32".to_string()),
             ("target_os = macos".to_string(), "__APPLE__".to_string()),
             ("target_os = linux".to_string(), "__linux__".to_string()),
+            (
+                "feature = simulator".to_string(),
+                "RFE_SIMULATOR".to_string(),
+            ),
         ]),
         ..Default::default()
     };
@@ -36,6 +40,9 @@ fn generate_c_bindings() {
 
 fn generate_csharp_bindings() {
     csbindgen::Builder::default()
+        .input_extern_file("src/common/baud_rate.rs")
+        .input_extern_file("src/common/connection_state.rs")
+        .input_extern_file("src/common/link_health.rs")
         .input_extern_file("src/common/mod.rs")
         .input_extern_file("src/common/result.rs")
         .input_extern_file("src/common/screen_data.rs")
@@ -0,0 +1,73 @@
+#include "common.h"
+#include "rfe.h"
+#include <inttypes.h>
+#include <stdatomic.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+void sweep_callback(const float *sweep, uintptr_t sweep_len, uint64_t start_hz, uint64_t stop_hz, void *received_sweep) {
+    print_sweep(sweep, sweep_len, start_hz, stop_hz);
+    printf("\n");
+    atomic_store_explicit((atomic_bool *)received_sweep, true, memory_order_relaxed);
+}
+
+void config_callback(SpectrumAnalyzerConfig config, void *received_sweep) {
+    printf("Config: %" PRIu64 "-%" PRIu64 " Hz, %u points\n", config.start_freq_hz, config.stop_freq_hz,
+           config.sweep_len);
+}
+
+void screen_data_callback(const ScreenData *screen_data, void *received_sweep) {
+    int64_t timestamp = 0;
+    if (rfe_screen_data_timestamp(screen_data, &timestamp) == RESULT_SUCCESS) {
+        printf("Screen data captured at %" PRId64 "\n", timestamp);
+    }
+}
+
+void connection_state_callback(DeviceConnectionState state, void *received_sweep) {
+    switch (state) {
+    case DEVICE_CONNECTION_STATE_CONNECTED:
+        printf("Connected\n");
+        break;
+    case DEVICE_CONNECTION_STATE_BOOTLOADER:
+        printf("Entered firmware update mode\n");
+        break;
+    case DEVICE_CONNECTION_STATE_DISCONNECTED:
+        printf("Disconnected\n");
+        break;
+    case DEVICE_CONNECTION_STATE_DEGRADED:
+        printf("Connected, but the serial link looks unhealthy\n");
+        break;
+    }
+}
+
+int main() {
+    SpectrumAnalyzer *rfe = rfe_spectrum_analyzer_connect();
+    if (!rfe) {
+        fprintf(stderr, "Failed to connect to an RF Explorer\n");
+        return EXIT_FAILURE;
+    }
+
+    atomic_bool received_sweep = false;
+    SpectrumAnalyzerCallbacks callbacks = {
+        .sweep = sweep_callback,
+        .config = config_callback,
+        .screen_data = screen_data_callback,
+        .connection_state = connection_state_callback,
+        .user_data = (void *)&received_sweep,
+    };
+    if (rfe_spectrum_analyzer_set_callbacks(rfe, &callbacks) != RESULT_SUCCESS) {
+        fprintf(stderr, "Failed to set callbacks\n");
+        rfe_spectrum_analyzer_free(rfe);
+        return EXIT_FAILURE;
+    }
+
+    // Wait to receive a sweep before exiting
+    while (!atomic_load_explicit(&received_sweep, memory_order_relaxed)) {
+    }
+
+    rfe_spectrum_analyzer_clear_callbacks(rfe);
+    rfe_spectrum_analyzer_free(rfe);
+    return EXIT_SUCCESS;
+}
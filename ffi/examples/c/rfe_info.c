@@ -77,6 +77,11 @@ void print_signal_generator_info(const SignalGenerator *rfe) {
         printf("\tSweep delay: %" PRIu64 " ms\n", config.sweep_delay_ms);
     }
 
+    uint64_t min_freq_hz, max_freq_hz;
+    if (rfe_signal_generator_freq_range_hz(rfe, &min_freq_hz, &max_freq_hz) == RESULT_SUCCESS) {
+        printf("\tFrequency range: %" PRIu64 " to %" PRIu64 " Hz\n", min_freq_hz, max_freq_hz);
+    }
+
     printf("\n");
 }
 
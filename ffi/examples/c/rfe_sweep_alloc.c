@@ -0,0 +1,54 @@
+#include "common.h"
+#include "rfe.h"
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+// CTest treats this exit code as a skipped test
+#define EXIT_SKIP 77
+
+int main() {
+    // Freeing NULL is allowed, so callers don't need to check whether a sweep was returned
+    rfe_sweep_free(NULL, 0);
+
+#if defined(RFE_SIMULATOR)
+    // Connect to a simulated RF Explorer so the test runs without hardware
+    SpectrumAnalyzer *rfe = rfe_spectrum_analyzer_connect_simulator();
+#else
+    SpectrumAnalyzer *rfe = rfe_spectrum_analyzer_connect();
+#endif
+    if (!rfe) {
+        fprintf(stderr, "Failed to connect to an RF Explorer\n");
+        return EXIT_SKIP;
+    }
+
+    uint64_t start_hz = rfe_spectrum_analyzer_start_freq_hz(rfe);
+    uint64_t stop_hz = rfe_spectrum_analyzer_stop_freq_hz(rfe);
+
+    // Retune, wait for a sweep measured with the new frequencies, and take ownership of it
+    float *sweep = NULL;
+    uintptr_t sweep_len = 0;
+    Result rc = rfe_spectrum_analyzer_measure_once(rfe, start_hz, stop_hz, 5000, &sweep, &sweep_len);
+    if (rc == RESULT_SUCCESS) {
+        print_sweep(sweep, sweep_len, start_hz, stop_hz);
+        printf("\n");
+        rfe_sweep_free(sweep, sweep_len);
+    } else {
+        fprintf(stderr, "Failed to measure an RF Explorer sweep\n");
+    }
+
+    // Copy the same sweep again without retuning
+    if (rc == RESULT_SUCCESS) {
+        rc = rfe_spectrum_analyzer_get_sweep_alloc(rfe, &sweep, &sweep_len);
+        if (rc == RESULT_SUCCESS) {
+            print_sweep(sweep, sweep_len, start_hz, stop_hz);
+            printf("\n");
+            rfe_sweep_free(sweep, sweep_len);
+        } else {
+            fprintf(stderr, "Failed to get the most recent RF Explorer sweep\n");
+        }
+    }
+
+    rfe_spectrum_analyzer_free(rfe);
+    return (rc == RESULT_SUCCESS) ? EXIT_SUCCESS : EXIT_FAILURE;
+}
@@ -38,6 +38,23 @@ public sealed class ScreenData : IDisposable
         }
     }
 
+    /// <summary>Encodes the screen capture as a PNG image with each pixel scaled up <paramref name="scale"/> times.</summary>
+    public byte[] ToPng(byte scale = 1)
+    {
+        unsafe
+        {
+            // The first call only returns the PNG's length, so its result is ignored
+            nuint length = 0;
+            NativeMethods.rfe_screen_data_to_png(_ptr, scale, null, 0, &length);
+            var png = new byte[(int)length];
+            fixed (byte* ptr = png)
+            {
+                RfeException.ThrowIfError(NativeMethods.rfe_screen_data_to_png(_ptr, scale, ptr, (nuint)png.Length, &length));
+            }
+            return png;
+        }
+    }
+
     /// <inheritdoc/>
     public void Dispose()
     {
@@ -18,6 +18,12 @@ namespace CsBindgen
 
 
 
+        /// <summary>
+        ///  Returns the baud rate in bits per second.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_baud_rate_bps", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern uint rfe_baud_rate_bps(SerialBaudRate baud_rate);
+
         /// <summary>
         ///  Returns whether the platform RF Explorer USB serial driver appears to be installed.
         /// </summary>
@@ -70,6 +76,25 @@ namespace CsBindgen
         [DllImport(__DllName, EntryPoint = "rfe_screen_data_timestamp", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern Result rfe_screen_data_timestamp(ScreenData* screen_data, long* timestamp);
 
+        /// <summary>
+        ///  Writes the screen capture's position among the captures received since connecting, starting
+        ///  at 1.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_screen_data_sequence", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern Result rfe_screen_data_sequence(ScreenData* screen_data, ulong* sequence);
+
+        /// <summary>
+        ///  Encodes an RF Explorer LCD screen capture as a PNG image.
+        ///
+        ///  Each pixel is scaled up `scale` times, and a `scale` of 0 is treated as 1.
+        ///  `buf` must point to at least `buf_len` bytes. If `out_len` is non-NULL, it
+        ///  is set to the length of the PNG even if it doesn't fit, so passing a `NULL`
+        ///  `buf` and a `buf_len` of 0 returns the size of the buffer to allocate.
+        ///  Returns `RESULT_INVALID_INPUT_ERROR` if the buffer is too small.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_screen_data_to_png", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern Result rfe_screen_data_to_png(ScreenData* screen_data, byte scale, byte* buf, nuint buf_len, nuint* out_len);
+
         /// <summary>
         ///  Frees screen data returned by an `rfe_*_screen_data` function.
         ///
@@ -121,6 +146,17 @@ namespace CsBindgen
         [DllImport(__DllName, EntryPoint = "rfe_signal_generator_connect_with_name_and_baud_rate", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern SignalGenerator* rfe_signal_generator_connect_with_name_and_baud_rate(byte* name, uint baud_rate);
 
+        /// <summary>
+        ///  Connects to a named serial port using one of the baud rates an RF Explorer supports.
+        ///
+        ///  `name` must be a valid null-terminated UTF-8 string. Returns `NULL` if the
+        ///  pointer is null, the string is invalid, or the device cannot be opened and
+        ///  initialized. The returned pointer is owned by the caller and must be freed
+        ///  with `rfe_signal_generator_free`.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_signal_generator_connect_with_name_and_serial_baud_rate", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern SignalGenerator* rfe_signal_generator_connect_with_name_and_serial_baud_rate(byte* name, SerialBaudRate baud_rate);
+
         /// <summary>
         ///  Frees a signal generator returned by `rfe_signal_generator_connect`.
         ///
@@ -300,6 +336,17 @@ namespace CsBindgen
         [DllImport(__DllName, EntryPoint = "rfe_signal_generator_wait_for_next_screen_data_with_timeout", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern Result rfe_signal_generator_wait_for_next_screen_data_with_timeout(SignalGenerator* rfe, ulong timeout_secs, ScreenData** screen_data);
 
+        /// <summary>
+        ///  Waits up to `timeout_secs` seconds for an LCD screen capture whose pixels differ from the
+        ///  most recent one.
+        ///
+        ///  Captures that look the same as the most recent one are skipped. On success, `screen_data`
+        ///  receives a heap-allocated `ScreenData` pointer owned by the caller. Free it with
+        ///  `rfe_screen_data_free`.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_signal_generator_wait_for_changed_screen_data", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern Result rfe_signal_generator_wait_for_changed_screen_data(SignalGenerator* rfe, ulong timeout_secs, ScreenData** screen_data);
+
         /// <summary>
         ///  Writes the most recent temperature range to `temperature`.
         ///
@@ -338,6 +385,14 @@ namespace CsBindgen
         [DllImport(__DllName, EntryPoint = "rfe_signal_generator_inactive_radio_model", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern Result rfe_signal_generator_inactive_radio_model(SignalGenerator* rfe, SignalGeneratorModel* model);
 
+        /// <summary>
+        ///  Writes the output frequency range of the active radio module in Hz to `min_out` and `max_out`.
+        ///
+        ///  Returns `RESULT_NO_DATA` if the signal generator's setup info has not been received.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_signal_generator_freq_range_hz", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern Result rfe_signal_generator_freq_range_hz(SignalGenerator* rfe, ulong* min_out, ulong* max_out);
+
         /// <summary>
         ///  Starts amplitude sweep mode.
         ///
@@ -476,6 +531,42 @@ namespace CsBindgen
         [DllImport(__DllName, EntryPoint = "rfe_signal_generator_remove_config_freq_sweep_callback", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern void rfe_signal_generator_remove_config_freq_sweep_callback(SignalGenerator* rfe);
 
+        /// <summary>
+        ///  Sets the callback called when an LCD screen capture is received.
+        ///
+        ///  The callback may be invoked from a background thread, and multiple callback
+        ///  invocations may overlap. `screen_data` is only valid until the callback returns.
+        ///  `user_data`, if non-NULL, must remain valid until the callback is removed or the
+        ///  signal generator is freed.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_signal_generator_set_screen_data_callback", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void rfe_signal_generator_set_screen_data_callback(SignalGenerator* rfe, delegate* unmanaged[Cdecl]<ScreenData*, void*, void> callback, void* user_data);
+
+        /// <summary>
+        ///  Removes the LCD screen capture callback.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_signal_generator_remove_screen_data_callback", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void rfe_signal_generator_remove_screen_data_callback(SignalGenerator* rfe);
+
+        /// <summary>
+        ///  Sets every non-NULL callback in `callbacks` in one call.
+        ///
+        ///  NULL callbacks leave the callbacks that are already set unchanged. Nothing is set if `rfe` or
+        ///  `callbacks` is NULL. See `SignalGeneratorCallbacks` for when the callbacks are called.
+        ///
+        ///  Registration isn't atomic: the callbacks are replaced one after another, so a message that's
+        ///  received during the call can be passed to an old callback while another message is passed to
+        ///  a new one.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_signal_generator_set_callbacks", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern Result rfe_signal_generator_set_callbacks(SignalGenerator* rfe, SignalGeneratorCallbacks* callbacks);
+
+        /// <summary>
+        ///  Removes every callback that can be set by `rfe_signal_generator_set_callbacks`.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_signal_generator_clear_callbacks", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void rfe_signal_generator_clear_callbacks(SignalGenerator* rfe);
+
         /// <summary>
         ///  Turns RF output power on.
         /// </summary>
@@ -565,6 +656,28 @@ namespace CsBindgen
         [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_connect_with_name_and_baud_rate", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern SpectrumAnalyzer* rfe_spectrum_analyzer_connect_with_name_and_baud_rate(byte* name, uint baud_rate);
 
+        /// <summary>
+        ///  Connects to a named serial port using one of the baud rates an RF Explorer supports.
+        ///
+        ///  `name` must be a valid null-terminated UTF-8 string. Returns `NULL` if the
+        ///  pointer is null, the string is invalid, or the device cannot be opened and
+        ///  initialized. The returned pointer is owned by the caller and must be freed
+        ///  with `rfe_spectrum_analyzer_free`.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_connect_with_name_and_serial_baud_rate", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern SpectrumAnalyzer* rfe_spectrum_analyzer_connect_with_name_and_serial_baud_rate(byte* name, SerialBaudRate baud_rate);
+
+        /// <summary>
+        ///  Connects to a simulated spectrum analyzer that's served on a background
+        ///  thread, so the library can be used and tested without an RF Explorer.
+        ///
+        ///  Only exported when the library is built with the `simulator` feature.
+        ///  Returns `NULL` if the simulator can't be started. The returned pointer is
+        ///  owned by the caller and must be freed with `rfe_spectrum_analyzer_free`.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_connect_simulator", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern SpectrumAnalyzer* rfe_spectrum_analyzer_connect_simulator();
+
         /// <summary>
         ///  Frees a spectrum analyzer returned by `rfe_spectrum_analyzer_connect`.
         ///
@@ -637,6 +750,14 @@ namespace CsBindgen
         [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_serial_number_len", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern nuint rfe_spectrum_analyzer_serial_number_len(SpectrumAnalyzer* rfe);
 
+        /// <summary>
+        ///  Writes the health of the serial link to the spectrum analyzer.
+        ///
+        ///  This doesn't wait for the device, so it can be polled while sweeps are being received.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_link_health", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern Result rfe_spectrum_analyzer_link_health(SpectrumAnalyzer* rfe, SerialLinkHealth* link_health);
+
         /// <summary>
         ///  Turns the spectrum analyzer LCD on.
         /// </summary>
@@ -792,6 +913,20 @@ namespace CsBindgen
         [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_sweep", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern Result rfe_spectrum_analyzer_sweep(SpectrumAnalyzer* rfe, float* sweep_buf, nuint buf_len, nuint* sweep_len);
 
+        /// <summary>
+        ///  Copies the most recent sweep and the frequency range it was measured over
+        ///  into caller-provided outputs.
+        ///
+        ///  Unlike calling `rfe_spectrum_analyzer_sweep` followed by
+        ///  `rfe_spectrum_analyzer_start_freq_hz` and `rfe_spectrum_analyzer_stop_freq_hz`,
+        ///  the frequencies always match the sweep even if the config changes between
+        ///  calls. `sweep_buf` must point to at least `buf_len` `float` values. Each
+        ///  non-NULL output pointer is set to the number of values written or the start
+        ///  and stop frequencies in hertz.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_sweep_with_range", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern Result rfe_spectrum_analyzer_sweep_with_range(SpectrumAnalyzer* rfe, float* sweep_buf, nuint buf_len, nuint* sweep_len, ulong* start_hz, ulong* stop_hz);
+
         /// <summary>
         ///  Waits for the next sweep and copies it into a caller-provided buffer.
         ///
@@ -810,6 +945,39 @@ namespace CsBindgen
         [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_wait_for_next_sweep_with_timeout", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern Result rfe_spectrum_analyzer_wait_for_next_sweep_with_timeout(SpectrumAnalyzer* rfe, ulong timeout_secs, float* sweep_buf, nuint buf_len, nuint* sweep_len);
 
+        /// <summary>
+        ///  Returns a copy of the most recent sweep in a newly allocated array.
+        ///
+        ///  On success, `sweep` receives a heap-allocated array of `sweep_len` `float`
+        ///  values owned by the caller. Free it with `rfe_sweep_free`, passing the same
+        ///  length. Returns `RESULT_NO_DATA` if no sweep has been received, in which case
+        ///  nothing is allocated.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_get_sweep_alloc", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern Result rfe_spectrum_analyzer_get_sweep_alloc(SpectrumAnalyzer* rfe, float** sweep, nuint* sweep_len);
+
+        /// <summary>
+        ///  Sets the sweep start and stop frequencies in hertz, then waits up to
+        ///  `timeout_ms` milliseconds for a sweep measured with them and returns it in a
+        ///  newly allocated array.
+        ///
+        ///  On success, `sweep` receives a heap-allocated array of `sweep_len` `float`
+        ///  values owned by the caller. Free it with `rfe_sweep_free`, passing the same
+        ///  length. Nothing is allocated if the function fails.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_measure_once", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern Result rfe_spectrum_analyzer_measure_once(SpectrumAnalyzer* rfe, ulong start_hz, ulong stop_hz, ulong timeout_ms, float** sweep, nuint* sweep_len);
+
+        /// <summary>
+        ///  Frees a sweep returned by `rfe_spectrum_analyzer_get_sweep_alloc` or
+        ///  `rfe_spectrum_analyzer_measure_once`.
+        ///
+        ///  `sweep_len` must be the length returned with the sweep. Passing `NULL` is
+        ///  allowed and has no effect.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_sweep_free", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void rfe_sweep_free(float* sweep, nuint sweep_len);
+
         /// <summary>
         ///  Returns the most recent LCD screen capture.
         ///
@@ -837,6 +1005,17 @@ namespace CsBindgen
         [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_wait_for_next_screen_data_with_timeout", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern Result rfe_spectrum_analyzer_wait_for_next_screen_data_with_timeout(SpectrumAnalyzer* rfe, ulong timeout_secs, ScreenData** screen_data);
 
+        /// <summary>
+        ///  Waits up to `timeout_secs` seconds for an LCD screen capture whose pixels differ from the
+        ///  most recent one.
+        ///
+        ///  Captures that look the same as the most recent one are skipped. On success, `screen_data`
+        ///  receives a heap-allocated `ScreenData` pointer owned by the caller. Free it with
+        ///  `rfe_screen_data_free`.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_wait_for_changed_screen_data", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern Result rfe_spectrum_analyzer_wait_for_changed_screen_data(SpectrumAnalyzer* rfe, ulong timeout_secs, ScreenData** screen_data);
+
         /// <summary>
         ///  Writes the current DSP mode to `dsp_mode`.
         ///
@@ -895,6 +1074,9 @@ namespace CsBindgen
 
         /// <summary>
         ///  Starts Wi-Fi analyzer mode for the requested Wi-Fi band.
+        ///
+        ///  Returns `RESULT_INVALID_OPERATION_ERROR` if the active radio module cannot
+        ///  measure the Wi-Fi band.
         /// </summary>
         [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_start_wifi_analyzer", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern Result rfe_spectrum_analyzer_start_wifi_analyzer(SpectrumAnalyzer* rfe, WifiBand wifi_band);
@@ -989,6 +1171,42 @@ namespace CsBindgen
         [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_remove_config_callback", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
         internal static extern void rfe_spectrum_analyzer_remove_config_callback(SpectrumAnalyzer* rfe);
 
+        /// <summary>
+        ///  Sets the callback called when an LCD screen capture is received.
+        ///
+        ///  The callback may be invoked from a background thread, and multiple callback
+        ///  invocations may overlap. `screen_data` is only valid until the callback returns.
+        ///  `user_data`, if non-NULL, must remain valid until the callback is removed or the
+        ///  analyzer is freed.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_set_screen_data_callback", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void rfe_spectrum_analyzer_set_screen_data_callback(SpectrumAnalyzer* rfe, delegate* unmanaged[Cdecl]<ScreenData*, void*, void> callback, void* user_data);
+
+        /// <summary>
+        ///  Removes the LCD screen capture callback.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_remove_screen_data_callback", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void rfe_spectrum_analyzer_remove_screen_data_callback(SpectrumAnalyzer* rfe);
+
+        /// <summary>
+        ///  Sets every non-NULL callback in `callbacks` in one call.
+        ///
+        ///  NULL callbacks leave the callbacks that are already set unchanged. Nothing is set if `rfe` or
+        ///  `callbacks` is NULL. See `SpectrumAnalyzerCallbacks` for when the callbacks are called.
+        ///
+        ///  Registration isn't atomic: the callbacks are replaced one after another, so a message that's
+        ///  received during the call can be passed to an old callback while another message is passed to
+        ///  a new one.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_set_callbacks", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern Result rfe_spectrum_analyzer_set_callbacks(SpectrumAnalyzer* rfe, SpectrumAnalyzerCallbacks* callbacks);
+
+        /// <summary>
+        ///  Removes every callback that can be set by `rfe_spectrum_analyzer_set_callbacks`.
+        /// </summary>
+        [DllImport(__DllName, EntryPoint = "rfe_spectrum_analyzer_clear_callbacks", CallingConvention = CallingConvention.Cdecl, ExactSpelling = true)]
+        internal static extern void rfe_spectrum_analyzer_clear_callbacks(SpectrumAnalyzer* rfe);
+
         /// <summary>
         ///  Sets the number of points in each sweep.
         ///
@@ -1036,6 +1254,52 @@ namespace CsBindgen
 
     }
 
+    /// <summary>
+    ///  Health of the serial link to an RF Explorer.
+    ///
+    ///  Durations are represented in milliseconds.
+    /// </summary>
+    [StructLayout(LayoutKind.Sequential)]
+    internal unsafe partial struct SerialLinkHealth
+    {
+        /// <summary>
+        ///  Baud rate of the serial port, or zero if it isn't known.
+        /// </summary>
+        public uint baud_rate;
+        /// <summary>
+        ///  Time a read from the serial port waits for data before timing out.
+        /// </summary>
+        public ulong read_timeout_ms;
+        /// <summary>
+        ///  Bytes received per second over the last few seconds.
+        /// </summary>
+        public double bytes_per_sec;
+        /// <summary>
+        ///  Number of reads that timed out waiting for data.
+        /// </summary>
+        public ulong read_timeouts;
+        /// <summary>
+        ///  Number of received lines that couldn't be parsed as a message.
+        /// </summary>
+        public ulong parse_errors;
+        /// <summary>
+        ///  Number of reads that failed with an I/O error.
+        /// </summary>
+        public ulong io_errors;
+        /// <summary>
+        ///  Whether a message has been received from the device.
+        /// </summary>
+        [MarshalAs(UnmanagedType.U1)] public bool has_received_message;
+        /// <summary>
+        ///  Time since the last message was received, or zero if no message has been received.
+        /// </summary>
+        public ulong ms_since_last_message;
+        /// <summary>
+        ///  Most likely cause of the problems seen recently on the link.
+        /// </summary>
+        public SerialLinkDiagnosis diagnosis;
+    }
+
     /// <summary>
     ///  Signal generator configuration.
     ///
@@ -1215,6 +1479,49 @@ namespace CsBindgen
         public ulong sweep_delay_ms;
     }
 
+    /// <summary>
+    ///  The callbacks set by `rfe_signal_generator_set_callbacks`.
+    ///
+    ///  Every callback is called from a background thread, not the thread that set it, and
+    ///  callbacks may be called at the same time as each other and as themselves, so they must be
+    ///  thread-safe. Pointers passed to a callback, like `screen_data`, are only valid until it
+    ///  returns. `user_data` is passed to every callback and, if non-NULL, must remain valid until the
+    ///  callbacks are cleared or the signal generator is freed.
+    /// </summary>
+    [StructLayout(LayoutKind.Sequential)]
+    internal unsafe partial struct SignalGeneratorCallbacks
+    {
+        /// <summary>
+        ///  Called when the main configuration is received.
+        /// </summary>
+        public delegate* unmanaged[Cdecl]<SignalGeneratorConfig, void*, void> config;
+        /// <summary>
+        ///  Called when an amplitude sweep configuration is received.
+        /// </summary>
+        public delegate* unmanaged[Cdecl]<SignalGeneratorConfigAmpSweep, void*, void> config_amp_sweep;
+        /// <summary>
+        ///  Called when a CW configuration is received.
+        /// </summary>
+        public delegate* unmanaged[Cdecl]<SignalGeneratorConfigCw, void*, void> config_cw;
+        /// <summary>
+        ///  Called when a frequency sweep configuration is received.
+        /// </summary>
+        public delegate* unmanaged[Cdecl]<SignalGeneratorConfigFreqSweep, void*, void> config_freq_sweep;
+        /// <summary>
+        ///  Called when a screen capture is received.
+        /// </summary>
+        public delegate* unmanaged[Cdecl]<ScreenData*, void*, void> screen_data;
+        /// <summary>
+        ///  Called when the state of the connection changes, like when the RF Explorer enters
+        ///  firmware update mode or is unplugged.
+        /// </summary>
+        public delegate* unmanaged[Cdecl]<DeviceConnectionState, void*, void> connection_state;
+        /// <summary>
+        ///  Passed to every callback.
+        /// </summary>
+        public void* user_data;
+    }
+
     /// <summary>
     ///  Spectrum analyzer configuration.
     ///
@@ -1290,6 +1597,132 @@ namespace CsBindgen
         public CalcMode calc_mode;
     }
 
+    /// <summary>
+    ///  The callbacks set by `rfe_spectrum_analyzer_set_callbacks`.
+    ///
+    ///  Every callback is called from a background thread, not the thread that set it, and
+    ///  callbacks may be called at the same time as each other and as themselves, so they must be
+    ///  thread-safe. Pointers passed to a callback, like `sweep` and `screen_data`, are only valid
+    ///  until it returns. `user_data` is passed to every callback and, if non-NULL, must remain valid
+    ///  until the callbacks are cleared or the analyzer is freed.
+    /// </summary>
+    [StructLayout(LayoutKind.Sequential)]
+    internal unsafe partial struct SpectrumAnalyzerCallbacks
+    {
+        /// <summary>
+        ///  Called when a sweep is received.
+        /// </summary>
+        public delegate* unmanaged[Cdecl]<float*, nuint, ulong, ulong, void*, void> sweep;
+        /// <summary>
+        ///  Called when a configuration is received.
+        /// </summary>
+        public delegate* unmanaged[Cdecl]<SpectrumAnalyzerConfig, void*, void> config;
+        /// <summary>
+        ///  Called when a screen capture is received.
+        /// </summary>
+        public delegate* unmanaged[Cdecl]<ScreenData*, void*, void> screen_data;
+        /// <summary>
+        ///  Called when the state of the connection changes, like when the RF Explorer enters
+        ///  firmware update mode or is unplugged.
+        /// </summary>
+        public delegate* unmanaged[Cdecl]<DeviceConnectionState, void*, void> connection_state;
+        /// <summary>
+        ///  Passed to every callback.
+        /// </summary>
+        public void* user_data;
+    }
+
+
+    /// <summary>
+    ///  A baud rate an RF Explorer can be switched to.
+    /// </summary>
+    internal enum SerialBaudRate : uint
+    {
+        /// <summary>
+        ///  1,200 bps.
+        /// </summary>
+        Bps1200,
+        /// <summary>
+        ///  2,400 bps.
+        /// </summary>
+        Bps2400,
+        /// <summary>
+        ///  4,800 bps.
+        /// </summary>
+        Bps4800,
+        /// <summary>
+        ///  9,600 bps.
+        /// </summary>
+        Bps9600,
+        /// <summary>
+        ///  19,200 bps.
+        /// </summary>
+        Bps19200,
+        /// <summary>
+        ///  38,400 bps.
+        /// </summary>
+        Bps38400,
+        /// <summary>
+        ///  57,600 bps.
+        /// </summary>
+        Bps57600,
+        /// <summary>
+        ///  115,200 bps.
+        /// </summary>
+        Bps115200,
+        /// <summary>
+        ///  500,000 bps, which RF Explorers connected over USB use by default.
+        /// </summary>
+        Bps500000,
+    }
+
+    /// <summary>
+    ///  The state of the connection to an RF Explorer.
+    /// </summary>
+    internal enum DeviceConnectionState : uint
+    {
+        /// <summary>
+        ///  The RF Explorer is running its firmware and accepts commands.
+        /// </summary>
+        Connected,
+        /// <summary>
+        ///  The RF Explorer is in firmware update mode and refuses commands.
+        /// </summary>
+        Bootloader,
+        /// <summary>
+        ///  Messages can no longer be read from the RF Explorer, like after its USB cable is unplugged.
+        /// </summary>
+        Disconnected,
+        /// <summary>
+        ///  The RF Explorer is running its firmware and accepts commands, but the serial link to it
+        ///  shows a pattern of problems. Its link health has the diagnosis and the evidence.
+        /// </summary>
+        Degraded,
+    }
+
+    /// <summary>
+    ///  Most likely cause of the problems seen on the serial link to an RF Explorer.
+    /// </summary>
+    internal enum SerialLinkDiagnosis : uint
+    {
+        /// <summary>
+        ///  No problems have been seen recently.
+        /// </summary>
+        Healthy,
+        /// <summary>
+        ///  The RF Explorer keeps restarting, like when an underpowered USB hub browns out.
+        /// </summary>
+        SuspectedPowerIssue,
+        /// <summary>
+        ///  Data is corrupted or lost without the RF Explorer restarting, like with a damaged USB
+        ///  cable.
+        /// </summary>
+        SuspectedCableIssue,
+        /// <summary>
+        ///  Problems have been seen recently, but they don't match a known pattern.
+        /// </summary>
+        Unknown,
+    }
 
     /// <summary>
     ///  Result code returned by fallible `rfe-ffi` functions.
@@ -1328,6 +1761,10 @@ namespace CsBindgen
         ///  The device did not respond before the operation timed out.
         /// </summary>
         TimeoutError,
+        /// <summary>
+        ///  The connection to the device was lost, such as after its USB cable was unplugged.
+        /// </summary>
+        DisconnectedError,
     }
 
     /// <summary>
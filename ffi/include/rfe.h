@@ -6,6 +6,18 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * The length of the longest message an RF Explorer can send, a sweep with 65,535 points,
+ * rounded up to a power of two.
+ */
+#define MemoryBudget_DEFAULT_MAX_LINE_LEN 131072
+
+/**
+ * The smallest [`MemoryBudget::max_line_len`], which leaves room for configs and the other
+ * text messages an RF Explorer sends, but not for sweeps with more than about 250 points.
+ */
+#define MemoryBudget_MIN_MAX_LINE_LEN 256
+
 /**
  * Screen width in pixels.
  */
@@ -16,6 +28,53 @@
  */
 #define ScreenData_HEIGHT_PX 64
 
+/**
+ * The magnitude written for the S-parameters tracking mode can't measure.
+ */
+#define TrackingResult_UNMEASURED_DB -200.
+
+/**
+ * A baud rate an RF Explorer can be switched to.
+ */
+typedef enum SerialBaudRate {
+  /**
+   * 1,200 bps.
+   */
+  SERIAL_BAUD_RATE_BPS1200,
+  /**
+   * 2,400 bps.
+   */
+  SERIAL_BAUD_RATE_BPS2400,
+  /**
+   * 4,800 bps.
+   */
+  SERIAL_BAUD_RATE_BPS4800,
+  /**
+   * 9,600 bps.
+   */
+  SERIAL_BAUD_RATE_BPS9600,
+  /**
+   * 19,200 bps.
+   */
+  SERIAL_BAUD_RATE_BPS19200,
+  /**
+   * 38,400 bps.
+   */
+  SERIAL_BAUD_RATE_BPS38400,
+  /**
+   * 57,600 bps.
+   */
+  SERIAL_BAUD_RATE_BPS57600,
+  /**
+   * 115,200 bps.
+   */
+  SERIAL_BAUD_RATE_BPS115200,
+  /**
+   * 500,000 bps, which RF Explorers connected over USB use by default.
+   */
+  SERIAL_BAUD_RATE_BPS500000,
+} SerialBaudRate;
+
 /**
  * Result code returned by fallible `rfe-ffi` functions.
  */
@@ -52,6 +111,10 @@ typedef enum Result {
    * The device did not respond before the operation timed out.
    */
   RESULT_TIMEOUT_ERROR,
+  /**
+   * The connection to the device was lost, such as after its USB cable was unplugged.
+   */
+  RESULT_DISCONNECTED_ERROR,
 } Result;
 
 /**
@@ -187,6 +250,29 @@ enum Temperature
 typedef uint8_t Temperature;
 #endif // __cplusplus
 
+/**
+ * The state of the connection to an RF Explorer.
+ */
+typedef enum DeviceConnectionState {
+  /**
+   * The RF Explorer is running its firmware and accepts commands.
+   */
+  DEVICE_CONNECTION_STATE_CONNECTED,
+  /**
+   * The RF Explorer is in firmware update mode and refuses commands.
+   */
+  DEVICE_CONNECTION_STATE_BOOTLOADER,
+  /**
+   * Messages can no longer be read from the RF Explorer, like after its USB cable is unplugged.
+   */
+  DEVICE_CONNECTION_STATE_DISCONNECTED,
+  /**
+   * The RF Explorer is running its firmware and accepts commands, but the serial link to it
+   * shows a pattern of problems. Its link health has the diagnosis and the evidence.
+   */
+  DEVICE_CONNECTION_STATE_DEGRADED,
+} DeviceConnectionState;
+
 /**
  * RF Explorer spectrum analyzer model.
  */
@@ -264,6 +350,29 @@ enum SpectrumAnalyzerModel
 typedef uint8_t SpectrumAnalyzerModel;
 #endif // __cplusplus
 
+/**
+ * Most likely cause of the problems seen on the serial link to an RF Explorer.
+ */
+typedef enum SerialLinkDiagnosis {
+  /**
+   * No problems have been seen recently.
+   */
+  SERIAL_LINK_DIAGNOSIS_HEALTHY,
+  /**
+   * The RF Explorer keeps restarting, like when an underpowered USB hub browns out.
+   */
+  SERIAL_LINK_DIAGNOSIS_SUSPECTED_POWER_ISSUE,
+  /**
+   * Data is corrupted or lost without the RF Explorer restarting, like with a damaged USB
+   * cable.
+   */
+  SERIAL_LINK_DIAGNOSIS_SUSPECTED_CABLE_ISSUE,
+  /**
+   * Problems have been seen recently, but they don't match a known pattern.
+   */
+  SERIAL_LINK_DIAGNOSIS_UNKNOWN,
+} SerialLinkDiagnosis;
+
 /**
  * Operating mode reported by an RF Explorer device.
  */
@@ -648,6 +757,91 @@ typedef struct SignalGeneratorConfigFreqSweep {
   uint64_t sweep_delay_ms;
 } SignalGeneratorConfigFreqSweep;
 
+/**
+ * The callbacks set by `rfe_signal_generator_set_callbacks`.
+ *
+ * Every callback is called from a background thread, not the thread that set it, and
+ * callbacks may be called at the same time as each other and as themselves, so they must be
+ * thread-safe. Pointers passed to a callback, like `screen_data`, are only valid until it
+ * returns. `user_data` is passed to every callback and, if non-NULL, must remain valid until the
+ * callbacks are cleared or the signal generator is freed.
+ */
+typedef struct SignalGeneratorCallbacks {
+  /**
+   * Called when the main configuration is received.
+   */
+  void (*config)(struct SignalGeneratorConfig config, void *user_data);
+  /**
+   * Called when an amplitude sweep configuration is received.
+   */
+  void (*config_amp_sweep)(struct SignalGeneratorConfigAmpSweep config, void *user_data);
+  /**
+   * Called when a CW configuration is received.
+   */
+  void (*config_cw)(struct SignalGeneratorConfigCw config, void *user_data);
+  /**
+   * Called when a frequency sweep configuration is received.
+   */
+  void (*config_freq_sweep)(struct SignalGeneratorConfigFreqSweep config, void *user_data);
+  /**
+   * Called when a screen capture is received.
+   */
+  void (*screen_data)(const struct ScreenData *screen_data, void *user_data);
+  /**
+   * Called when the state of the connection changes, like when the RF Explorer enters
+   * firmware update mode or is unplugged.
+   */
+  void (*connection_state)(enum DeviceConnectionState state, void *user_data);
+  /**
+   * Passed to every callback.
+   */
+  void *user_data;
+} SignalGeneratorCallbacks;
+
+/**
+ * Health of the serial link to an RF Explorer.
+ *
+ * Durations are represented in milliseconds.
+ */
+typedef struct SerialLinkHealth {
+  /**
+   * Baud rate of the serial port, or zero if it isn't known.
+   */
+  uint32_t baud_rate;
+  /**
+   * Time a read from the serial port waits for data before timing out.
+   */
+  uint64_t read_timeout_ms;
+  /**
+   * Bytes received per second over the last few seconds.
+   */
+  double bytes_per_sec;
+  /**
+   * Number of reads that timed out waiting for data.
+   */
+  uint64_t read_timeouts;
+  /**
+   * Number of received lines that couldn't be parsed as a message.
+   */
+  uint64_t parse_errors;
+  /**
+   * Number of reads that failed with an I/O error.
+   */
+  uint64_t io_errors;
+  /**
+   * Whether a message has been received from the device.
+   */
+  bool has_received_message;
+  /**
+   * Time since the last message was received, or zero if no message has been received.
+   */
+  uint64_t ms_since_last_message;
+  /**
+   * Most likely cause of the problems seen recently on the link.
+   */
+  enum SerialLinkDiagnosis diagnosis;
+} SerialLinkHealth;
+
 /**
  * Spectrum analyzer configuration.
  *
@@ -721,6 +915,43 @@ typedef struct SpectrumAnalyzerConfig {
   CalcMode calc_mode;
 } SpectrumAnalyzerConfig;
 
+/**
+ * The callbacks set by `rfe_spectrum_analyzer_set_callbacks`.
+ *
+ * Every callback is called from a background thread, not the thread that set it, and
+ * callbacks may be called at the same time as each other and as themselves, so they must be
+ * thread-safe. Pointers passed to a callback, like `sweep` and `screen_data`, are only valid
+ * until it returns. `user_data` is passed to every callback and, if non-NULL, must remain valid
+ * until the callbacks are cleared or the analyzer is freed.
+ */
+typedef struct SpectrumAnalyzerCallbacks {
+  /**
+   * Called when a sweep is received.
+   */
+  void (*sweep)(const float *sweep,
+                uintptr_t sweep_len,
+                uint64_t start_hz,
+                uint64_t stop_hz,
+                void *user_data);
+  /**
+   * Called when a configuration is received.
+   */
+  void (*config)(struct SpectrumAnalyzerConfig config, void *user_data);
+  /**
+   * Called when a screen capture is received.
+   */
+  void (*screen_data)(const struct ScreenData *screen_data, void *user_data);
+  /**
+   * Called when the state of the connection changes, like when the RF Explorer enters
+   * firmware update mode or is unplugged.
+   */
+  void (*connection_state)(enum DeviceConnectionState state, void *user_data);
+  /**
+   * Passed to every callback.
+   */
+  void *user_data;
+} SpectrumAnalyzerCallbacks;
+
 #ifdef __cplusplus
 extern "C" {
 #endif // __cplusplus
@@ -749,6 +980,11 @@ char **rfe_port_names(uintptr_t *len);
  */
 void rfe_free_port_names(char **port_names_ptr, uintptr_t len);
 
+/**
+ * Returns the baud rate in bits per second.
+ */
+uint32_t rfe_baud_rate_bps(enum SerialBaudRate baud_rate);
+
 /**
  * Gets one pixel from an RF Explorer LCD screen capture.
  *
@@ -778,6 +1014,27 @@ enum Result rfe_screen_data_get_pixel_checked(const struct ScreenData *screen_da
  */
 enum Result rfe_screen_data_timestamp(const struct ScreenData *screen_data, int64_t *timestamp);
 
+/**
+ * Writes the screen capture's position among the captures received since connecting, starting
+ * at 1.
+ */
+enum Result rfe_screen_data_sequence(const struct ScreenData *screen_data, uint64_t *sequence);
+
+/**
+ * Encodes an RF Explorer LCD screen capture as a PNG image.
+ *
+ * Each pixel is scaled up `scale` times, and a `scale` of 0 is treated as 1.
+ * `buf` must point to at least `buf_len` bytes. If `out_len` is non-NULL, it
+ * is set to the length of the PNG even if it doesn't fit, so passing a `NULL`
+ * `buf` and a `buf_len` of 0 returns the size of the buffer to allocate.
+ * Returns `RESULT_INVALID_INPUT_ERROR` if the buffer is too small.
+ */
+enum Result rfe_screen_data_to_png(const struct ScreenData *screen_data,
+                                   uint8_t scale,
+                                   uint8_t *buf,
+                                   uintptr_t buf_len,
+                                   uintptr_t *out_len);
+
 /**
  * Frees screen data returned by an `rfe_*_screen_data` function.
  *
@@ -826,6 +1083,17 @@ struct SignalGenerator *rfe_signal_generator_connect(void);
 struct SignalGenerator *rfe_signal_generator_connect_with_name_and_baud_rate(const char *name,
                                                                              uint32_t baud_rate);
 
+/**
+ * Connects to a named serial port using one of the baud rates an RF Explorer supports.
+ *
+ * `name` must be a valid null-terminated UTF-8 string. Returns `NULL` if the
+ * pointer is null, the string is invalid, or the device cannot be opened and
+ * initialized. The returned pointer is owned by the caller and must be freed
+ * with `rfe_signal_generator_free`.
+ */
+struct SignalGenerator *rfe_signal_generator_connect_with_name_and_serial_baud_rate(const char *name,
+                                                                                    enum SerialBaudRate baud_rate);
+
 /**
  * Frees a signal generator returned by `rfe_signal_generator_connect`.
  *
@@ -999,6 +1267,18 @@ enum Result rfe_signal_generator_wait_for_next_screen_data_with_timeout(const st
                                                                         uint64_t timeout_secs,
                                                                         const struct ScreenData **screen_data);
 
+/**
+ * Waits up to `timeout_secs` seconds for an LCD screen capture whose pixels differ from the
+ * most recent one.
+ *
+ * Captures that look the same as the most recent one are skipped. On success, `screen_data`
+ * receives a heap-allocated `ScreenData` pointer owned by the caller. Free it with
+ * `rfe_screen_data_free`.
+ */
+enum Result rfe_signal_generator_wait_for_changed_screen_data(const struct SignalGenerator *rfe,
+                                                              uint64_t timeout_secs,
+                                                              const struct ScreenData **screen_data);
+
 /**
  * Writes the most recent temperature range to `temperature`.
  *
@@ -1037,6 +1317,15 @@ enum Result rfe_signal_generator_active_radio_model(const struct SignalGenerator
 enum Result rfe_signal_generator_inactive_radio_model(const struct SignalGenerator *rfe,
                                                       SignalGeneratorModel *model);
 
+/**
+ * Writes the output frequency range of the active radio module in Hz to `min_out` and `max_out`.
+ *
+ * Returns `RESULT_NO_DATA` if the signal generator's setup info has not been received.
+ */
+enum Result rfe_signal_generator_freq_range_hz(const struct SignalGenerator *rfe,
+                                               uint64_t *min_out,
+                                               uint64_t *max_out);
+
 /**
  * Starts amplitude sweep mode.
  *
@@ -1206,6 +1495,42 @@ void rfe_signal_generator_set_config_freq_sweep_callback(const struct SignalGene
  */
 void rfe_signal_generator_remove_config_freq_sweep_callback(const struct SignalGenerator *rfe);
 
+/**
+ * Sets the callback called when an LCD screen capture is received.
+ *
+ * The callback may be invoked from a background thread, and multiple callback
+ * invocations may overlap. `screen_data` is only valid until the callback returns.
+ * `user_data`, if non-NULL, must remain valid until the callback is removed or the
+ * signal generator is freed.
+ */
+void rfe_signal_generator_set_screen_data_callback(const struct SignalGenerator *rfe,
+                                                   void (*callback)(const struct ScreenData *screen_data,
+                                                                    void *user_data),
+                                                   void *user_data);
+
+/**
+ * Removes the LCD screen capture callback.
+ */
+void rfe_signal_generator_remove_screen_data_callback(const struct SignalGenerator *rfe);
+
+/**
+ * Sets every non-NULL callback in `callbacks` in one call.
+ *
+ * NULL callbacks leave the callbacks that are already set unchanged. Nothing is set if `rfe` or
+ * `callbacks` is NULL. See `SignalGeneratorCallbacks` for when the callbacks are called.
+ *
+ * Registration isn't atomic: the callbacks are replaced one after another, so a message that's
+ * received during the call can be passed to an old callback while another message is passed to
+ * a new one.
+ */
+enum Result rfe_signal_generator_set_callbacks(const struct SignalGenerator *rfe,
+                                               const struct SignalGeneratorCallbacks *callbacks);
+
+/**
+ * Removes every callback that can be set by `rfe_signal_generator_set_callbacks`.
+ */
+void rfe_signal_generator_clear_callbacks(const struct SignalGenerator *rfe);
+
 /**
  * Turns RF output power on.
  */
@@ -1285,6 +1610,29 @@ struct SpectrumAnalyzer *rfe_spectrum_analyzer_connect(void);
 struct SpectrumAnalyzer *rfe_spectrum_analyzer_connect_with_name_and_baud_rate(const char *name,
                                                                                uint32_t baud_rate);
 
+/**
+ * Connects to a named serial port using one of the baud rates an RF Explorer supports.
+ *
+ * `name` must be a valid null-terminated UTF-8 string. Returns `NULL` if the
+ * pointer is null, the string is invalid, or the device cannot be opened and
+ * initialized. The returned pointer is owned by the caller and must be freed
+ * with `rfe_spectrum_analyzer_free`.
+ */
+struct SpectrumAnalyzer *rfe_spectrum_analyzer_connect_with_name_and_serial_baud_rate(const char *name,
+                                                                                      enum SerialBaudRate baud_rate);
+
+#if defined(RFE_SIMULATOR)
+/**
+ * Connects to a simulated spectrum analyzer that's served on a background
+ * thread, so the library can be used and tested without an RF Explorer.
+ *
+ * Only exported when the library is built with the `simulator` feature.
+ * Returns `NULL` if the simulator can't be started. The returned pointer is
+ * owned by the caller and must be freed with `rfe_spectrum_analyzer_free`.
+ */
+struct SpectrumAnalyzer *rfe_spectrum_analyzer_connect_simulator(void);
+#endif
+
 /**
  * Frees a spectrum analyzer returned by `rfe_spectrum_analyzer_connect`.
  *
@@ -1357,6 +1705,14 @@ enum Result rfe_spectrum_analyzer_serial_number(const struct SpectrumAnalyzer *r
  */
 uintptr_t rfe_spectrum_analyzer_serial_number_len(const struct SpectrumAnalyzer *rfe);
 
+/**
+ * Writes the health of the serial link to the spectrum analyzer.
+ *
+ * This doesn't wait for the device, so it can be polled while sweeps are being received.
+ */
+enum Result rfe_spectrum_analyzer_link_health(const struct SpectrumAnalyzer *rfe,
+                                              struct SerialLinkHealth *link_health);
+
 /**
  * Turns the spectrum analyzer LCD on.
  */
@@ -1492,6 +1848,24 @@ enum Result rfe_spectrum_analyzer_sweep(const struct SpectrumAnalyzer *rfe,
                                         uintptr_t buf_len,
                                         uintptr_t *sweep_len);
 
+/**
+ * Copies the most recent sweep and the frequency range it was measured over
+ * into caller-provided outputs.
+ *
+ * Unlike calling `rfe_spectrum_analyzer_sweep` followed by
+ * `rfe_spectrum_analyzer_start_freq_hz` and `rfe_spectrum_analyzer_stop_freq_hz`,
+ * the frequencies always match the sweep even if the config changes between
+ * calls. `sweep_buf` must point to at least `buf_len` `float` values. Each
+ * non-NULL output pointer is set to the number of values written or the start
+ * and stop frequencies in hertz.
+ */
+enum Result rfe_spectrum_analyzer_sweep_with_range(const struct SpectrumAnalyzer *rfe,
+                                                   float *sweep_buf,
+                                                   uintptr_t buf_len,
+                                                   uintptr_t *sweep_len,
+                                                   uint64_t *start_hz,
+                                                   uint64_t *stop_hz);
+
 /**
  * Waits for the next sweep and copies it into a caller-provided buffer.
  *
@@ -1515,6 +1889,43 @@ enum Result rfe_spectrum_analyzer_wait_for_next_sweep_with_timeout(const struct
                                                                    uintptr_t buf_len,
                                                                    uintptr_t *sweep_len);
 
+/**
+ * Returns a copy of the most recent sweep in a newly allocated array.
+ *
+ * On success, `sweep` receives a heap-allocated array of `sweep_len` `float`
+ * values owned by the caller. Free it with `rfe_sweep_free`, passing the same
+ * length. Returns `RESULT_NO_DATA` if no sweep has been received, in which case
+ * nothing is allocated.
+ */
+enum Result rfe_spectrum_analyzer_get_sweep_alloc(const struct SpectrumAnalyzer *rfe,
+                                                  float **sweep,
+                                                  uintptr_t *sweep_len);
+
+/**
+ * Sets the sweep start and stop frequencies in hertz, then waits up to
+ * `timeout_ms` milliseconds for a sweep measured with them and returns it in a
+ * newly allocated array.
+ *
+ * On success, `sweep` receives a heap-allocated array of `sweep_len` `float`
+ * values owned by the caller. Free it with `rfe_sweep_free`, passing the same
+ * length. Nothing is allocated if the function fails.
+ */
+enum Result rfe_spectrum_analyzer_measure_once(const struct SpectrumAnalyzer *rfe,
+                                               uint64_t start_hz,
+                                               uint64_t stop_hz,
+                                               uint64_t timeout_ms,
+                                               float **sweep,
+                                               uintptr_t *sweep_len);
+
+/**
+ * Frees a sweep returned by `rfe_spectrum_analyzer_get_sweep_alloc` or
+ * `rfe_spectrum_analyzer_measure_once`.
+ *
+ * `sweep_len` must be the length returned with the sweep. Passing `NULL` is
+ * allowed and has no effect.
+ */
+void rfe_sweep_free(float *sweep, uintptr_t sweep_len);
+
 /**
  * Returns the most recent LCD screen capture.
  *
@@ -1543,6 +1954,18 @@ enum Result rfe_spectrum_analyzer_wait_for_next_screen_data_with_timeout(const s
                                                                          uint64_t timeout_secs,
                                                                          const struct ScreenData **screen_data);
 
+/**
+ * Waits up to `timeout_secs` seconds for an LCD screen capture whose pixels differ from the
+ * most recent one.
+ *
+ * Captures that look the same as the most recent one are skipped. On success, `screen_data`
+ * receives a heap-allocated `ScreenData` pointer owned by the caller. Free it with
+ * `rfe_screen_data_free`.
+ */
+enum Result rfe_spectrum_analyzer_wait_for_changed_screen_data(const struct SpectrumAnalyzer *rfe,
+                                                               uint64_t timeout_secs,
+                                                               const struct ScreenData **screen_data);
+
 /**
  * Writes the current DSP mode to `dsp_mode`.
  *
@@ -1596,6 +2019,9 @@ SpectrumAnalyzerModel rfe_spectrum_analyzer_inactive_radio_model(const struct Sp
 
 /**
  * Starts Wi-Fi analyzer mode for the requested Wi-Fi band.
+ *
+ * Returns `RESULT_INVALID_OPERATION_ERROR` if the active radio module cannot
+ * measure the Wi-Fi band.
  */
 enum Result rfe_spectrum_analyzer_start_wifi_analyzer(const struct SpectrumAnalyzer *rfe,
                                                       WifiBand wifi_band);
@@ -1701,6 +2127,42 @@ void rfe_spectrum_analyzer_set_config_callback(const struct SpectrumAnalyzer *rf
  */
 void rfe_spectrum_analyzer_remove_config_callback(const struct SpectrumAnalyzer *rfe);
 
+/**
+ * Sets the callback called when an LCD screen capture is received.
+ *
+ * The callback may be invoked from a background thread, and multiple callback
+ * invocations may overlap. `screen_data` is only valid until the callback returns.
+ * `user_data`, if non-NULL, must remain valid until the callback is removed or the
+ * analyzer is freed.
+ */
+void rfe_spectrum_analyzer_set_screen_data_callback(const struct SpectrumAnalyzer *rfe,
+                                                    void (*callback)(const struct ScreenData *screen_data,
+                                                                     void *user_data),
+                                                    void *user_data);
+
+/**
+ * Removes the LCD screen capture callback.
+ */
+void rfe_spectrum_analyzer_remove_screen_data_callback(const struct SpectrumAnalyzer *rfe);
+
+/**
+ * Sets every non-NULL callback in `callbacks` in one call.
+ *
+ * NULL callbacks leave the callbacks that are already set unchanged. Nothing is set if `rfe` or
+ * `callbacks` is NULL. See `SpectrumAnalyzerCallbacks` for when the callbacks are called.
+ *
+ * Registration isn't atomic: the callbacks are replaced one after another, so a message that's
+ * received during the call can be passed to an old callback while another message is passed to
+ * a new one.
+ */
+enum Result rfe_spectrum_analyzer_set_callbacks(const struct SpectrumAnalyzer *rfe,
+                                                const struct SpectrumAnalyzerCallbacks *callbacks);
+
+/**
+ * Removes every callback that can be set by `rfe_spectrum_analyzer_set_callbacks`.
+ */
+void rfe_spectrum_analyzer_clear_callbacks(const struct SpectrumAnalyzer *rfe);
+
 /**
  * Sets the number of points in each sweep.
  *
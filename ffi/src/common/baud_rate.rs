@@ -0,0 +1,63 @@
+use rfe::BaudRate;
+
+/// A baud rate an RF Explorer can be switched to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum SerialBaudRate {
+    /// 1,200 bps.
+    Bps1200,
+    /// 2,400 bps.
+    Bps2400,
+    /// 4,800 bps.
+    Bps4800,
+    /// 9,600 bps.
+    Bps9600,
+    /// 19,200 bps.
+    Bps19200,
+    /// 38,400 bps.
+    Bps38400,
+    /// 57,600 bps.
+    Bps57600,
+    /// 115,200 bps.
+    Bps115200,
+    /// 500,000 bps, which RF Explorers connected over USB use by default.
+    Bps500000,
+}
+
+impl From<SerialBaudRate> for BaudRate {
+    fn from(baud_rate: SerialBaudRate) -> Self {
+        match baud_rate {
+            SerialBaudRate::Bps1200 => BaudRate::Bps1200,
+            SerialBaudRate::Bps2400 => BaudRate::Bps2400,
+            SerialBaudRate::Bps4800 => BaudRate::Bps4800,
+            SerialBaudRate::Bps9600 => BaudRate::Bps9600,
+            SerialBaudRate::Bps19200 => BaudRate::Bps19200,
+            SerialBaudRate::Bps38400 => BaudRate::Bps38400,
+            SerialBaudRate::Bps57600 => BaudRate::Bps57600,
+            SerialBaudRate::Bps115200 => BaudRate::Bps115200,
+            SerialBaudRate::Bps500000 => BaudRate::Bps500000,
+        }
+    }
+}
+
+impl From<BaudRate> for SerialBaudRate {
+    fn from(baud_rate: BaudRate) -> Self {
+        match baud_rate {
+            BaudRate::Bps1200 => SerialBaudRate::Bps1200,
+            BaudRate::Bps2400 => SerialBaudRate::Bps2400,
+            BaudRate::Bps4800 => SerialBaudRate::Bps4800,
+            BaudRate::Bps9600 => SerialBaudRate::Bps9600,
+            BaudRate::Bps19200 => SerialBaudRate::Bps19200,
+            BaudRate::Bps38400 => SerialBaudRate::Bps38400,
+            BaudRate::Bps57600 => SerialBaudRate::Bps57600,
+            BaudRate::Bps115200 => SerialBaudRate::Bps115200,
+            BaudRate::Bps500000 => SerialBaudRate::Bps500000,
+        }
+    }
+}
+
+/// Returns the baud rate in bits per second.
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_baud_rate_bps(baud_rate: SerialBaudRate) -> u32 {
+    BaudRate::from(baud_rate).bps()
+}
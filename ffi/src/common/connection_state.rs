@@ -0,0 +1,25 @@
+/// The state of the connection to an RF Explorer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum DeviceConnectionState {
+    /// The RF Explorer is running its firmware and accepts commands.
+    Connected,
+    /// The RF Explorer is in firmware update mode and refuses commands.
+    Bootloader,
+    /// Messages can no longer be read from the RF Explorer, like after its USB cable is unplugged.
+    Disconnected,
+    /// The RF Explorer is running its firmware and accepts commands, but the serial link to it
+    /// shows a pattern of problems. Its link health has the diagnosis and the evidence.
+    Degraded,
+}
+
+impl From<rfe::ConnectionState> for DeviceConnectionState {
+    fn from(state: rfe::ConnectionState) -> Self {
+        match state {
+            rfe::ConnectionState::Connected => DeviceConnectionState::Connected,
+            rfe::ConnectionState::Bootloader => DeviceConnectionState::Bootloader,
+            rfe::ConnectionState::Disconnected => DeviceConnectionState::Disconnected,
+            rfe::ConnectionState::Degraded(_) => DeviceConnectionState::Degraded,
+        }
+    }
+}
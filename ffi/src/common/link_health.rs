@@ -0,0 +1,71 @@
+use rfe::{LinkDiagnosis, LinkHealth};
+
+/// Health of the serial link to an RF Explorer.
+///
+/// Durations are represented in milliseconds.
+#[repr(C)]
+pub struct SerialLinkHealth {
+    /// Baud rate of the serial port, or zero if it isn't known.
+    baud_rate: u32,
+    /// Time a read from the serial port waits for data before timing out.
+    read_timeout_ms: u64,
+    /// Bytes received per second over the last few seconds.
+    bytes_per_sec: f64,
+    /// Number of reads that timed out waiting for data.
+    read_timeouts: u64,
+    /// Number of received lines that couldn't be parsed as a message.
+    parse_errors: u64,
+    /// Number of reads that failed with an I/O error.
+    io_errors: u64,
+    /// Whether a message has been received from the device.
+    has_received_message: bool,
+    /// Time since the last message was received, or zero if no message has been received.
+    ms_since_last_message: u64,
+    /// Most likely cause of the problems seen recently on the link.
+    diagnosis: SerialLinkDiagnosis,
+}
+
+/// Most likely cause of the problems seen on the serial link to an RF Explorer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum SerialLinkDiagnosis {
+    /// No problems have been seen recently.
+    Healthy,
+    /// The RF Explorer keeps restarting, like when an underpowered USB hub browns out.
+    SuspectedPowerIssue,
+    /// Data is corrupted or lost without the RF Explorer restarting, like with a damaged USB
+    /// cable.
+    SuspectedCableIssue,
+    /// Problems have been seen recently, but they don't match a known pattern.
+    Unknown,
+}
+
+impl From<LinkDiagnosis> for SerialLinkDiagnosis {
+    fn from(diagnosis: LinkDiagnosis) -> Self {
+        match diagnosis {
+            LinkDiagnosis::Healthy => SerialLinkDiagnosis::Healthy,
+            LinkDiagnosis::SuspectedPowerIssue => SerialLinkDiagnosis::SuspectedPowerIssue,
+            LinkDiagnosis::SuspectedCableIssue => SerialLinkDiagnosis::SuspectedCableIssue,
+            LinkDiagnosis::Unknown => SerialLinkDiagnosis::Unknown,
+        }
+    }
+}
+
+impl From<LinkHealth> for SerialLinkHealth {
+    fn from(link_health: LinkHealth) -> Self {
+        SerialLinkHealth {
+            baud_rate: link_health.baud_rate.unwrap_or_default(),
+            read_timeout_ms: link_health.read_timeout.as_millis() as u64,
+            bytes_per_sec: link_health.bytes_per_sec,
+            read_timeouts: link_health.read_errors.timeouts,
+            parse_errors: link_health.read_errors.parse_errors,
+            io_errors: link_health.read_errors.io_errors,
+            has_received_message: link_health.since_last_message.is_some(),
+            ms_since_last_message: link_health
+                .since_last_message
+                .map(|duration| duration.as_millis() as u64)
+                .unwrap_or_default(),
+            diagnosis: link_health.diagnosis.into(),
+        }
+    }
+}
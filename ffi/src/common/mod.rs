@@ -1,11 +1,20 @@
+mod baud_rate;
 mod callback;
+mod connection_state;
+mod link_health;
 mod result;
 mod screen_data;
 
+pub use baud_rate::SerialBaudRate;
 pub(crate) use callback::UserDataWrapper;
+pub use connection_state::DeviceConnectionState;
+pub use link_health::SerialLinkHealth;
 pub use result::Result;
 
-use std::ffi::{CString, c_char};
+use std::{
+    ffi::{CString, c_char},
+    slice,
+};
 
 /// Returns whether the platform RF Explorer USB serial driver appears to be installed.
 #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
@@ -52,3 +61,30 @@ pub unsafe extern "C" fn rfe_free_port_names(port_names_ptr: *mut *mut c_char, l
         drop(port_name);
     }
 }
+
+/// Copies `s` and a terminating null byte into a caller-provided buffer without allocating.
+///
+/// `s` ends at its first null byte, if it has one. Returns `Result::InvalidInputError` if the
+/// buffer is shorter than `c_str_len(s)`.
+pub(crate) unsafe fn copy_str_to_buf(s: &str, buf: &mut c_char, buf_len: usize) -> Result {
+    let bytes = c_str_bytes(s);
+    if buf_len < bytes.len() + 1 {
+        return Result::InvalidInputError;
+    }
+
+    let buf = unsafe { slice::from_raw_parts_mut(std::ptr::from_mut(buf).cast::<u8>(), buf_len) };
+    buf[..bytes.len()].copy_from_slice(bytes);
+    buf[bytes.len()] = 0;
+    Result::Success
+}
+
+/// Returns the buffer size `copy_str_to_buf` needs for `s`, including the terminating null byte.
+pub(crate) fn c_str_len(s: &str) -> usize {
+    c_str_bytes(s).len() + 1
+}
+
+fn c_str_bytes(s: &str) -> &[u8] {
+    let bytes = s.as_bytes();
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    &bytes[..len]
+}
@@ -17,6 +17,8 @@ pub enum Result {
     NullPtrError,
     /// The device did not respond before the operation timed out.
     TimeoutError,
+    /// The connection to the device was lost, such as after its USB cable was unplugged.
+    DisconnectedError,
 }
 
 impl<T> From<rfe::Result<T>> for Result {
@@ -33,9 +35,13 @@ impl From<rfe::Error> for Result {
         match error {
             rfe::Error::IncompatibleFirmware(_) => Result::IncompatibleFirmwareError,
             rfe::Error::InvalidInput(_) => Result::InvalidInputError,
-            rfe::Error::InvalidOperation(_) => Result::InvalidOperationError,
+            rfe::Error::InvalidOperation(_)
+            | rfe::Error::NoDeviceFound
+            | rfe::Error::NoExpansionModule
+            | rfe::Error::AlreadyActive(_) => Result::InvalidOperationError,
             rfe::Error::Io(_) => Result::IoError,
             rfe::Error::TimedOut(_) => Result::TimeoutError,
+            rfe::Error::Disconnected(_) => Result::DisconnectedError,
         }
     }
 }
@@ -1,3 +1,5 @@
+use std::slice;
+
 use rfe::ScreenData;
 
 use super::Result;
@@ -55,6 +57,55 @@ pub extern "C" fn rfe_screen_data_timestamp(
     }
 }
 
+/// Writes the screen capture's position among the captures received since connecting, starting
+/// at 1.
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_screen_data_sequence(
+    screen_data: Option<&ScreenData>,
+    sequence: Option<&mut u64>,
+) -> Result {
+    if let (Some(screen_data), Some(sequence)) = (screen_data, sequence) {
+        *sequence = screen_data.sequence();
+        Result::Success
+    } else {
+        Result::NullPtrError
+    }
+}
+
+/// Encodes an RF Explorer LCD screen capture as a PNG image.
+///
+/// Each pixel is scaled up `scale` times, and a `scale` of 0 is treated as 1.
+/// `buf` must point to at least `buf_len` bytes. If `out_len` is non-NULL, it
+/// is set to the length of the PNG even if it doesn't fit, so passing a `NULL`
+/// `buf` and a `buf_len` of 0 returns the size of the buffer to allocate.
+/// Returns `RESULT_INVALID_INPUT_ERROR` if the buffer is too small.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rfe_screen_data_to_png(
+    screen_data: Option<&ScreenData>,
+    scale: u8,
+    buf: Option<&mut u8>,
+    buf_len: usize,
+    out_len: Option<&mut usize>,
+) -> Result {
+    let Some(screen_data) = screen_data else {
+        return Result::NullPtrError;
+    };
+
+    let png = screen_data.to_png(scale);
+    if let Some(out_len) = out_len {
+        *out_len = png.len();
+    }
+    if buf_len < png.len() {
+        return Result::InvalidInputError;
+    }
+    let Some(buf) = buf else {
+        return Result::NullPtrError;
+    };
+    let buf = unsafe { slice::from_raw_parts_mut(buf, buf_len) };
+    buf[..png.len()].copy_from_slice(&png);
+    Result::Success
+}
+
 /// Frees screen data returned by an `rfe_*_screen_data` function.
 ///
 /// Passing `NULL` is allowed and has no effect.
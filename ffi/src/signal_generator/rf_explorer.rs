@@ -1,11 +1,11 @@
 use std::{
-    ffi::{CStr, CString, c_char, c_void},
+    ffi::{CStr, c_char, c_void},
     ptr, slice,
     time::Duration,
 };
 
 use rfe::{
-    ScreenData,
+    BaudRate, ScreenData,
     signal_generator::{
         Attenuation, Config, ConfigAmpSweep, ConfigCw, ConfigFreqSweep, PowerLevel,
         SignalGenerator, Temperature,
@@ -16,7 +16,9 @@ use super::{
     SignalGeneratorConfig, SignalGeneratorConfigAmpSweep, SignalGeneratorConfigCw,
     SignalGeneratorConfigFreqSweep, SignalGeneratorModel,
 };
-use crate::common::{Result, UserDataWrapper};
+use crate::common::{
+    DeviceConnectionState, Result, SerialBaudRate, UserDataWrapper, c_str_len, copy_str_to_buf,
+};
 
 /// Connects to the first RF Explorer signal generator found on a CP210x USB serial port.
 ///
@@ -50,6 +52,26 @@ pub unsafe extern "C" fn rfe_signal_generator_connect_with_name_and_baud_rate(
         .unwrap_or(ptr::null_mut())
 }
 
+/// Connects to a named serial port using one of the baud rates an RF Explorer supports.
+///
+/// `name` must be a valid null-terminated UTF-8 string. Returns `NULL` if the
+/// pointer is null, the string is invalid, or the device cannot be opened and
+/// initialized. The returned pointer is owned by the caller and must be freed
+/// with `rfe_signal_generator_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rfe_signal_generator_connect_with_name_and_serial_baud_rate(
+    name: Option<&c_char>,
+    baud_rate: SerialBaudRate,
+) -> *mut SignalGenerator {
+    let Some(Ok(name)) = name.map(|name| unsafe { CStr::from_ptr(name).to_str() }) else {
+        return ptr::null_mut();
+    };
+
+    SignalGenerator::connect_with_name_and_baud_rate(name, BaudRate::from(baud_rate))
+        .map(|rfe| Box::into_raw(Box::new(rfe)))
+        .unwrap_or(ptr::null_mut())
+}
+
 /// Frees a signal generator returned by `rfe_signal_generator_connect`.
 ///
 /// Passing `NULL` is allowed and has no effect.
@@ -92,17 +114,7 @@ pub unsafe extern "C" fn rfe_signal_generator_port_name(
         return Result::NullPtrError;
     };
 
-    let name = CString::new(rfe.port_name()).unwrap_or_default();
-    let name = unsafe { slice::from_raw_parts(name.as_ptr(), name.as_bytes_with_nul().len()) };
-
-    if buf_len < name.len() {
-        return Result::InvalidInputError;
-    }
-
-    let name_buf = unsafe { slice::from_raw_parts_mut(port_name_buf, buf_len) };
-    name_buf[..name.len()].copy_from_slice(name);
-
-    Result::Success
+    unsafe { copy_str_to_buf(rfe.port_name(), port_name_buf, buf_len) }
 }
 
 /// Returns the buffer size required for `rfe_signal_generator_port_name`.
@@ -113,7 +125,8 @@ pub unsafe extern "C" fn rfe_signal_generator_port_name(
 pub unsafe extern "C" fn rfe_signal_generator_port_name_len(
     rfe: Option<&SignalGenerator>,
 ) -> usize {
-    rfe.map(|rfe| rfe.port_name().len() + 1).unwrap_or_default()
+    rfe.map(|rfe| c_str_len(rfe.port_name()))
+        .unwrap_or_default()
 }
 
 /// Writes the firmware version to a caller-provided buffer.
@@ -130,22 +143,7 @@ pub unsafe extern "C" fn rfe_signal_generator_firmware_version(
         return Result::NullPtrError;
     };
 
-    let firmware_version = CString::new(rfe.firmware_version()).unwrap_or_default();
-    let firmware_version = unsafe {
-        slice::from_raw_parts(
-            firmware_version.as_ptr(),
-            firmware_version.as_bytes_with_nul().len(),
-        )
-    };
-
-    if buf_len < firmware_version.len() {
-        return Result::InvalidInputError;
-    }
-
-    let firmware_version_buf = unsafe { slice::from_raw_parts_mut(firmware_version_buf, buf_len) };
-    firmware_version_buf[..firmware_version.len()].copy_from_slice(firmware_version);
-
-    Result::Success
+    unsafe { copy_str_to_buf(&rfe.firmware_version(), firmware_version_buf, buf_len) }
 }
 
 /// Returns the buffer size required for `rfe_signal_generator_firmware_version`.
@@ -156,7 +154,7 @@ pub unsafe extern "C" fn rfe_signal_generator_firmware_version(
 pub extern "C" fn rfe_signal_generator_firmware_version_len(
     rfe: Option<&SignalGenerator>,
 ) -> usize {
-    rfe.map(|rfe| rfe.firmware_version().len() + 1)
+    rfe.map(|rfe| c_str_len(&rfe.firmware_version()))
         .unwrap_or_default()
 }
 
@@ -179,21 +177,7 @@ pub unsafe extern "C" fn rfe_signal_generator_serial_number(
         return Result::NoData;
     };
 
-    let serial_number = CString::new(serial_number).unwrap_or_default();
-    let serial_number = unsafe {
-        slice::from_raw_parts(
-            serial_number.as_ptr(),
-            serial_number.as_bytes_with_nul().len(),
-        )
-    };
-
-    if buf_len < serial_number.len() {
-        return Result::InvalidInputError;
-    }
-
-    let serial_number_buf = unsafe { slice::from_raw_parts_mut(serial_number_buf, buf_len) };
-    serial_number_buf[..serial_number.len()].copy_from_slice(serial_number);
-    Result::Success
+    unsafe { copy_str_to_buf(&serial_number, serial_number_buf, buf_len) }
 }
 
 /// Returns the buffer size required for `rfe_signal_generator_serial_number`.
@@ -203,7 +187,7 @@ pub unsafe extern "C" fn rfe_signal_generator_serial_number(
 #[unsafe(no_mangle)]
 pub extern "C" fn rfe_signal_generator_serial_number_len(rfe: Option<&SignalGenerator>) -> usize {
     rfe.and_then(SignalGenerator::serial_number)
-        .map(|sn| sn.len() + 1)
+        .map(|sn| c_str_len(&sn))
         .unwrap_or_default()
 }
 
@@ -433,6 +417,31 @@ pub extern "C" fn rfe_signal_generator_wait_for_next_screen_data_with_timeout(
     }
 }
 
+/// Waits up to `timeout_secs` seconds for an LCD screen capture whose pixels differ from the
+/// most recent one.
+///
+/// Captures that look the same as the most recent one are skipped. On success, `screen_data`
+/// receives a heap-allocated `ScreenData` pointer owned by the caller. Free it with
+/// `rfe_screen_data_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_signal_generator_wait_for_changed_screen_data(
+    rfe: Option<&SignalGenerator>,
+    timeout_secs: u64,
+    screen_data: Option<&mut *const ScreenData>,
+) -> Result {
+    let (Some(rfe), Some(screen_data)) = (rfe, screen_data) else {
+        return Result::NullPtrError;
+    };
+
+    match rfe.wait_for_changed_screen_data(Duration::from_secs(timeout_secs)) {
+        Ok(data) => {
+            *screen_data = Box::into_raw(Box::new(data));
+            Result::Success
+        }
+        Err(error) => error.into(),
+    }
+}
+
 /// Writes the most recent temperature range to `temperature`.
 ///
 /// Returns `RESULT_NO_DATA` if the device has not reported a temperature range.
@@ -527,6 +536,29 @@ pub extern "C" fn rfe_signal_generator_inactive_radio_model(
     }
 }
 
+/// Writes the output frequency range of the active radio module in Hz to `min_out` and `max_out`.
+///
+/// Returns `RESULT_NO_DATA` if the signal generator's setup info has not been received.
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_signal_generator_freq_range_hz(
+    rfe: Option<&SignalGenerator>,
+    min_out: Option<&mut u64>,
+    max_out: Option<&mut u64>,
+) -> Result {
+    let (Some(rfe), Some(min_out), Some(max_out)) = (rfe, min_out, max_out) else {
+        return Result::NullPtrError;
+    };
+
+    if rfe.main_radio_model().is_none() {
+        return Result::NoData;
+    }
+
+    let freq_range = rfe.capabilities().freq_range;
+    *min_out = freq_range.start().as_hz();
+    *max_out = freq_range.end().as_hz();
+    Result::Success
+}
+
 /// Starts amplitude sweep mode.
 ///
 /// `cw_hz` is the CW frequency in hertz and `step_delay_sec` is the delay
@@ -741,8 +773,14 @@ pub extern "C" fn rfe_signal_generator_set_config_callback(
 
     // Wrap the pointer to user_data in our own struct that implements Send so it can be
     // sent across threads
-    let user_data = UserDataWrapper(user_data);
+    set_config_callback(rfe, callback, UserDataWrapper(user_data));
+}
 
+fn set_config_callback(
+    rfe: &SignalGenerator,
+    callback: extern "C" fn(config: SignalGeneratorConfig, user_data: *mut c_void),
+    user_data: UserDataWrapper,
+) {
     // Convert the C function pointer to a Rust closure
     let cb = move |config: Config| {
         callback(SignalGeneratorConfig::from(config), user_data.clone().0);
@@ -776,8 +814,14 @@ pub extern "C" fn rfe_signal_generator_set_config_amp_sweep_callback(
 
     // Wrap the pointer to user_data in our own struct that implements Send so it can be
     // sent across threads
-    let user_data = UserDataWrapper(user_data);
+    set_config_amp_sweep_callback(rfe, callback, UserDataWrapper(user_data));
+}
 
+fn set_config_amp_sweep_callback(
+    rfe: &SignalGenerator,
+    callback: extern "C" fn(config: SignalGeneratorConfigAmpSweep, user_data: *mut c_void),
+    user_data: UserDataWrapper,
+) {
     // Convert the C function pointer to a Rust closure
     let cb = move |config: ConfigAmpSweep| {
         callback(
@@ -816,8 +860,14 @@ pub extern "C" fn rfe_signal_generator_set_config_cw_callback(
 
     // Wrap the pointer to user_data in our own struct that implements Send so it can be
     // sent across threads
-    let user_data = UserDataWrapper(user_data);
+    set_config_cw_callback(rfe, callback, UserDataWrapper(user_data));
+}
 
+fn set_config_cw_callback(
+    rfe: &SignalGenerator,
+    callback: extern "C" fn(config: SignalGeneratorConfigCw, user_data: *mut c_void),
+    user_data: UserDataWrapper,
+) {
     // Convert the C function pointer to a Rust closure
     let cb = move |config: ConfigCw| {
         callback(SignalGeneratorConfigCw::from(config), user_data.clone().0);
@@ -851,8 +901,14 @@ pub extern "C" fn rfe_signal_generator_set_config_freq_sweep_callback(
 
     // Wrap the pointer to user_data in our own struct that implements Send so it can be
     // sent across threads
-    let user_data = UserDataWrapper(user_data);
+    set_config_freq_sweep_callback(rfe, callback, UserDataWrapper(user_data));
+}
 
+fn set_config_freq_sweep_callback(
+    rfe: &SignalGenerator,
+    callback: extern "C" fn(config: SignalGeneratorConfigFreqSweep, user_data: *mut c_void),
+    user_data: UserDataWrapper,
+) {
     // Convert the C function pointer to a Rust closure
     let cb = move |config: ConfigFreqSweep| {
         callback(
@@ -874,6 +930,128 @@ pub extern "C" fn rfe_signal_generator_remove_config_freq_sweep_callback(
     }
 }
 
+/// Sets the callback called when an LCD screen capture is received.
+///
+/// The callback may be invoked from a background thread, and multiple callback
+/// invocations may overlap. `screen_data` is only valid until the callback returns.
+/// `user_data`, if non-NULL, must remain valid until the callback is removed or the
+/// signal generator is freed.
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_signal_generator_set_screen_data_callback(
+    rfe: Option<&SignalGenerator>,
+    callback: Option<extern "C" fn(screen_data: *const ScreenData, user_data: *mut c_void)>,
+    user_data: *mut c_void,
+) {
+    let (Some(rfe), Some(callback)) = (rfe, callback) else {
+        return;
+    };
+
+    // Wrap the pointer to user_data in our own struct that implements Send so it can be
+    // sent across threads
+    set_screen_data_callback(rfe, callback, UserDataWrapper(user_data));
+}
+
+fn set_screen_data_callback(
+    rfe: &SignalGenerator,
+    callback: extern "C" fn(screen_data: *const ScreenData, user_data: *mut c_void),
+    user_data: UserDataWrapper,
+) {
+    rfe.set_screen_data_callback(move |screen_data: ScreenData| {
+        callback(&raw const screen_data, user_data.clone().0);
+    });
+}
+
+/// Removes the LCD screen capture callback.
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_signal_generator_remove_screen_data_callback(rfe: Option<&SignalGenerator>) {
+    if let Some(rfe) = rfe {
+        rfe.remove_screen_data_callback();
+    }
+}
+
+/// The callbacks set by `rfe_signal_generator_set_callbacks`.
+///
+/// Every callback is called from a background thread, not the thread that set it, and
+/// callbacks may be called at the same time as each other and as themselves, so they must be
+/// thread-safe. Pointers passed to a callback, like `screen_data`, are only valid until it
+/// returns. `user_data` is passed to every callback and, if non-NULL, must remain valid until the
+/// callbacks are cleared or the signal generator is freed.
+#[repr(C)]
+pub struct SignalGeneratorCallbacks {
+    /// Called when the main configuration is received.
+    pub config: Option<extern "C" fn(config: SignalGeneratorConfig, user_data: *mut c_void)>,
+    /// Called when an amplitude sweep configuration is received.
+    pub config_amp_sweep:
+        Option<extern "C" fn(config: SignalGeneratorConfigAmpSweep, user_data: *mut c_void)>,
+    /// Called when a CW configuration is received.
+    pub config_cw: Option<extern "C" fn(config: SignalGeneratorConfigCw, user_data: *mut c_void)>,
+    /// Called when a frequency sweep configuration is received.
+    pub config_freq_sweep:
+        Option<extern "C" fn(config: SignalGeneratorConfigFreqSweep, user_data: *mut c_void)>,
+    /// Called when a screen capture is received.
+    pub screen_data: Option<extern "C" fn(screen_data: *const ScreenData, user_data: *mut c_void)>,
+    /// Called when the state of the connection changes, like when the RF Explorer enters
+    /// firmware update mode or is unplugged.
+    pub connection_state:
+        Option<extern "C" fn(state: DeviceConnectionState, user_data: *mut c_void)>,
+    /// Passed to every callback.
+    pub user_data: *mut c_void,
+}
+
+/// Sets every non-NULL callback in `callbacks` in one call.
+///
+/// NULL callbacks leave the callbacks that are already set unchanged. Nothing is set if `rfe` or
+/// `callbacks` is NULL. See `SignalGeneratorCallbacks` for when the callbacks are called.
+///
+/// Registration isn't atomic: the callbacks are replaced one after another, so a message that's
+/// received during the call can be passed to an old callback while another message is passed to
+/// a new one.
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_signal_generator_set_callbacks(
+    rfe: Option<&SignalGenerator>,
+    callbacks: Option<&SignalGeneratorCallbacks>,
+) -> Result {
+    let (Some(rfe), Some(callbacks)) = (rfe, callbacks) else {
+        return Result::NullPtrError;
+    };
+
+    let user_data = UserDataWrapper(callbacks.user_data);
+    if let Some(callback) = callbacks.config {
+        set_config_callback(rfe, callback, user_data.clone());
+    }
+    if let Some(callback) = callbacks.config_amp_sweep {
+        set_config_amp_sweep_callback(rfe, callback, user_data.clone());
+    }
+    if let Some(callback) = callbacks.config_cw {
+        set_config_cw_callback(rfe, callback, user_data.clone());
+    }
+    if let Some(callback) = callbacks.config_freq_sweep {
+        set_config_freq_sweep_callback(rfe, callback, user_data.clone());
+    }
+    if let Some(callback) = callbacks.screen_data {
+        set_screen_data_callback(rfe, callback, user_data.clone());
+    }
+    if let Some(callback) = callbacks.connection_state {
+        rfe.set_connection_state_callback(move |state| {
+            callback(DeviceConnectionState::from(state), user_data.clone().0);
+        });
+    }
+    Result::Success
+}
+
+/// Removes every callback that can be set by `rfe_signal_generator_set_callbacks`.
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_signal_generator_clear_callbacks(rfe: Option<&SignalGenerator>) {
+    if let Some(rfe) = rfe {
+        rfe.remove_config_callback();
+        rfe.remove_config_amp_sweep_callback();
+        rfe.remove_config_cw_callback();
+        rfe.remove_config_freq_sweep_callback();
+        rfe.remove_screen_data_callback();
+        rfe.remove_connection_state_callback();
+    }
+}
+
 /// Turns RF output power on.
 #[unsafe(no_mangle)]
 pub extern "C" fn rfe_signal_generator_rf_power_on(rfe: Option<&SignalGenerator>) -> Result {
@@ -1,18 +1,21 @@
 use std::{
-    ffi::{CStr, CString, c_char, c_void},
+    ffi::{CStr, c_char, c_void},
     ptr, slice,
     time::Duration,
 };
 
 use rfe::{
-    Frequency, ScreenData, SpectrumAnalyzer,
+    BaudRate, Frequency, ScreenData, SpectrumAnalyzer,
     spectrum_analyzer::{
         CalcMode, Config, DspMode, InputStage, Mode, Model, TrackingStatus, WifiBand,
     },
 };
 
 use super::{SpectrumAnalyzerConfig, SpectrumAnalyzerModel};
-use crate::common::{Result, UserDataWrapper};
+use crate::common::{
+    DeviceConnectionState, Result, SerialBaudRate, SerialLinkHealth, UserDataWrapper, c_str_len,
+    copy_str_to_buf,
+};
 
 /// Connects to the first RF Explorer spectrum analyzer found on a CP210x USB serial port.
 ///
@@ -46,6 +49,59 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_connect_with_name_and_baud_rate(
         .unwrap_or(ptr::null_mut())
 }
 
+/// Connects to a named serial port using one of the baud rates an RF Explorer supports.
+///
+/// `name` must be a valid null-terminated UTF-8 string. Returns `NULL` if the
+/// pointer is null, the string is invalid, or the device cannot be opened and
+/// initialized. The returned pointer is owned by the caller and must be freed
+/// with `rfe_spectrum_analyzer_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rfe_spectrum_analyzer_connect_with_name_and_serial_baud_rate(
+    name: Option<&c_char>,
+    baud_rate: SerialBaudRate,
+) -> *mut SpectrumAnalyzer {
+    let Some(Ok(name)) = name.map(|name| unsafe { CStr::from_ptr(name).to_str() }) else {
+        return ptr::null_mut();
+    };
+
+    SpectrumAnalyzer::connect_with_name_and_baud_rate(name, BaudRate::from(baud_rate))
+        .map(|rfe| Box::into_raw(Box::new(rfe)))
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Connects to a simulated spectrum analyzer that's served on a background
+/// thread, so the library can be used and tested without an RF Explorer.
+///
+/// Only exported when the library is built with the `simulator` feature.
+/// Returns `NULL` if the simulator can't be started. The returned pointer is
+/// owned by the caller and must be freed with `rfe_spectrum_analyzer_free`.
+#[cfg(feature = "simulator")]
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_spectrum_analyzer_connect_simulator() -> *mut SpectrumAnalyzer {
+    use std::{net::TcpListener, thread};
+
+    use rfe::simulator::{self, Scenario, Simulator, TcpPort};
+
+    let connect = || -> std::result::Result<SpectrumAnalyzer, Box<dyn std::error::Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        thread::spawn(move || {
+            let (stream, _) = listener.accept()?;
+            simulator::serve(
+                Simulator::new(Scenario::default()),
+                stream.try_clone()?,
+                stream,
+            )
+        });
+        Ok(SpectrumAnalyzer::connect_with_transport(Box::new(
+            TcpPort::connect(addr)?,
+        ))?)
+    };
+    connect()
+        .map(|rfe| Box::into_raw(Box::new(rfe)))
+        .unwrap_or(ptr::null_mut())
+}
+
 /// Frees a spectrum analyzer returned by `rfe_spectrum_analyzer_connect`.
 ///
 /// Passing `NULL` is allowed and has no effect.
@@ -88,17 +144,7 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_port_name(
         return Result::NullPtrError;
     };
 
-    let name = CString::new(rfe.port_name()).unwrap_or_default();
-    let name = unsafe { slice::from_raw_parts(name.as_ptr(), name.as_bytes_with_nul().len()) };
-
-    if buf_len < name.len() {
-        return Result::InvalidInputError;
-    }
-
-    let name_buf = unsafe { slice::from_raw_parts_mut(port_name_buf, buf_len) };
-    name_buf[..name.len()].copy_from_slice(name);
-
-    Result::Success
+    unsafe { copy_str_to_buf(rfe.port_name(), port_name_buf, buf_len) }
 }
 
 /// Returns the buffer size required for `rfe_spectrum_analyzer_port_name`.
@@ -109,7 +155,8 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_port_name(
 pub unsafe extern "C" fn rfe_spectrum_analyzer_port_name_len(
     rfe: Option<&SpectrumAnalyzer>,
 ) -> usize {
-    rfe.map(|rfe| rfe.port_name().len() + 1).unwrap_or_default()
+    rfe.map(|rfe| c_str_len(rfe.port_name()))
+        .unwrap_or_default()
 }
 
 /// Writes the firmware version to a caller-provided buffer.
@@ -126,22 +173,7 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_firmware_version(
         return Result::NullPtrError;
     };
 
-    let firmware_version = CString::new(rfe.firmware_version()).unwrap_or_default();
-    let firmware_version = unsafe {
-        slice::from_raw_parts(
-            firmware_version.as_ptr(),
-            firmware_version.as_bytes_with_nul().len(),
-        )
-    };
-
-    if buf_len < firmware_version.len() {
-        return Result::InvalidInputError;
-    }
-
-    let firmware_version_buf = unsafe { slice::from_raw_parts_mut(firmware_version_buf, buf_len) };
-    firmware_version_buf[..firmware_version.len()].copy_from_slice(firmware_version);
-
-    Result::Success
+    unsafe { copy_str_to_buf(&rfe.firmware_version(), firmware_version_buf, buf_len) }
 }
 
 /// Returns the buffer size required for `rfe_spectrum_analyzer_firmware_version`.
@@ -152,7 +184,7 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_firmware_version(
 pub extern "C" fn rfe_spectrum_analyzer_firmware_version_len(
     rfe: Option<&SpectrumAnalyzer>,
 ) -> usize {
-    rfe.map(|rfe| rfe.firmware_version().len() + 1)
+    rfe.map(|rfe| c_str_len(&rfe.firmware_version()))
         .unwrap_or_default()
 }
 
@@ -175,21 +207,7 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_serial_number(
         return Result::NoData;
     };
 
-    let serial_number = CString::new(serial_number).unwrap_or_default();
-    let serial_number = unsafe {
-        slice::from_raw_parts(
-            serial_number.as_ptr(),
-            serial_number.as_bytes_with_nul().len(),
-        )
-    };
-
-    if buf_len < serial_number.len() {
-        return Result::InvalidInputError;
-    }
-
-    let serial_number_buf = unsafe { slice::from_raw_parts_mut(serial_number_buf, buf_len) };
-    serial_number_buf[..serial_number.len()].copy_from_slice(serial_number);
-    Result::Success
+    unsafe { copy_str_to_buf(&serial_number, serial_number_buf, buf_len) }
 }
 
 /// Returns the buffer size required for `rfe_spectrum_analyzer_serial_number`.
@@ -199,10 +217,26 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_serial_number(
 #[unsafe(no_mangle)]
 pub extern "C" fn rfe_spectrum_analyzer_serial_number_len(rfe: Option<&SpectrumAnalyzer>) -> usize {
     rfe.and_then(SpectrumAnalyzer::serial_number)
-        .map(|sn| sn.len() + 1)
+        .map(|sn| c_str_len(&sn))
         .unwrap_or_default()
 }
 
+/// Writes the health of the serial link to the spectrum analyzer.
+///
+/// This doesn't wait for the device, so it can be polled while sweeps are being received.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rfe_spectrum_analyzer_link_health(
+    rfe: Option<&SpectrumAnalyzer>,
+    link_health: Option<&mut SerialLinkHealth>,
+) -> Result {
+    let (Some(rfe), Some(link_health)) = (rfe, link_health) else {
+        return Result::NullPtrError;
+    };
+
+    *link_health = rfe.link_health().into();
+    Result::Success
+}
+
 /// Turns the spectrum analyzer LCD on.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn rfe_spectrum_analyzer_lcd_on(rfe: Option<&SpectrumAnalyzer>) -> Result {
@@ -429,6 +463,47 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_sweep(
     }
 }
 
+/// Copies the most recent sweep and the frequency range it was measured over
+/// into caller-provided outputs.
+///
+/// Unlike calling `rfe_spectrum_analyzer_sweep` followed by
+/// `rfe_spectrum_analyzer_start_freq_hz` and `rfe_spectrum_analyzer_stop_freq_hz`,
+/// the frequencies always match the sweep even if the config changes between
+/// calls. `sweep_buf` must point to at least `buf_len` `float` values. Each
+/// non-NULL output pointer is set to the number of values written or the start
+/// and stop frequencies in hertz.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rfe_spectrum_analyzer_sweep_with_range(
+    rfe: Option<&SpectrumAnalyzer>,
+    sweep_buf: Option<&mut f32>,
+    buf_len: usize,
+    sweep_len: Option<&mut usize>,
+    start_hz: Option<&mut u64>,
+    stop_hz: Option<&mut u64>,
+) -> Result {
+    let (Some(rfe), Some(sweep_buf)) = (rfe, sweep_buf) else {
+        return Result::NullPtrError;
+    };
+
+    match rfe.fill_buf_with_sweep_and_range(unsafe {
+        std::slice::from_raw_parts_mut(sweep_buf, buf_len)
+    }) {
+        Ok((sweep_length, start_freq, stop_freq)) => {
+            if let Some(sweep_len) = sweep_len {
+                *sweep_len = sweep_length;
+            }
+            if let Some(start_hz) = start_hz {
+                *start_hz = start_freq.as_hz();
+            }
+            if let Some(stop_hz) = stop_hz {
+                *stop_hz = stop_freq.as_hz();
+            }
+            Result::Success
+        }
+        Err(error) => error.into(),
+    }
+}
+
 /// Waits for the next sweep and copies it into a caller-provided buffer.
 ///
 /// `sweep_buf` must point to at least `buf_len` `float` values. If `sweep_len`
@@ -487,6 +562,85 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_wait_for_next_sweep_with_timeout(
     }
 }
 
+/// Returns a copy of the most recent sweep in a newly allocated array.
+///
+/// On success, `sweep` receives a heap-allocated array of `sweep_len` `float`
+/// values owned by the caller. Free it with `rfe_sweep_free`, passing the same
+/// length. Returns `RESULT_NO_DATA` if no sweep has been received, in which case
+/// nothing is allocated.
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_spectrum_analyzer_get_sweep_alloc(
+    rfe: Option<&SpectrumAnalyzer>,
+    sweep: Option<&mut *mut f32>,
+    sweep_len: Option<&mut usize>,
+) -> Result {
+    let (Some(rfe), Some(sweep), Some(sweep_len)) = (rfe, sweep, sweep_len) else {
+        return Result::NullPtrError;
+    };
+
+    match rfe.sweep() {
+        Some(sweep_data) => {
+            (*sweep, *sweep_len) = into_raw_sweep(sweep_data);
+            Result::Success
+        }
+        None => Result::NoData,
+    }
+}
+
+/// Sets the sweep start and stop frequencies in hertz, then waits up to
+/// `timeout_ms` milliseconds for a sweep measured with them and returns it in a
+/// newly allocated array.
+///
+/// On success, `sweep` receives a heap-allocated array of `sweep_len` `float`
+/// values owned by the caller. Free it with `rfe_sweep_free`, passing the same
+/// length. Nothing is allocated if the function fails.
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_spectrum_analyzer_measure_once(
+    rfe: Option<&SpectrumAnalyzer>,
+    start_hz: u64,
+    stop_hz: u64,
+    timeout_ms: u64,
+    sweep: Option<&mut *mut f32>,
+    sweep_len: Option<&mut usize>,
+) -> Result {
+    let (Some(rfe), Some(sweep), Some(sweep_len)) = (rfe, sweep, sweep_len) else {
+        return Result::NullPtrError;
+    };
+
+    let sweep_data = rfe.set_start_stop(start_hz, stop_hz).and_then(|_| {
+        rfe.wait_for_sweep_matching_current_config(Duration::from_millis(timeout_ms))
+    });
+    match sweep_data {
+        Ok(sweep_data) => {
+            (*sweep, *sweep_len) = into_raw_sweep(sweep_data);
+            Result::Success
+        }
+        Err(error) => error.into(),
+    }
+}
+
+/// Frees a sweep returned by `rfe_spectrum_analyzer_get_sweep_alloc` or
+/// `rfe_spectrum_analyzer_measure_once`.
+///
+/// `sweep_len` must be the length returned with the sweep. Passing `NULL` is
+/// allowed and has no effect.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rfe_sweep_free(sweep: *mut f32, sweep_len: usize) {
+    if sweep.is_null() {
+        return;
+    }
+
+    drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(sweep, sweep_len)) });
+}
+
+/// Hands a sweep over to the caller as a boxed slice, which `rfe_sweep_free` can
+/// rebuild from just its pointer and length.
+fn into_raw_sweep(sweep: Vec<f32>) -> (*mut f32, usize) {
+    let sweep = sweep.into_boxed_slice();
+    let len = sweep.len();
+    (Box::into_raw(sweep).cast::<f32>(), len)
+}
+
 /// Returns the most recent LCD screen capture.
 ///
 /// On success, `screen_data` receives a heap-allocated `ScreenData` pointer
@@ -553,6 +707,31 @@ pub extern "C" fn rfe_spectrum_analyzer_wait_for_next_screen_data_with_timeout(
     }
 }
 
+/// Waits up to `timeout_secs` seconds for an LCD screen capture whose pixels differ from the
+/// most recent one.
+///
+/// Captures that look the same as the most recent one are skipped. On success, `screen_data`
+/// receives a heap-allocated `ScreenData` pointer owned by the caller. Free it with
+/// `rfe_screen_data_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn rfe_spectrum_analyzer_wait_for_changed_screen_data(
+    rfe: Option<&SpectrumAnalyzer>,
+    timeout_secs: u64,
+    screen_data: Option<&mut *const ScreenData>,
+) -> Result {
+    let (Some(rfe), Some(screen_data)) = (rfe, screen_data) else {
+        return Result::NullPtrError;
+    };
+
+    match rfe.wait_for_changed_screen_data(Duration::from_secs(timeout_secs)) {
+        Ok(data) => {
+            *screen_data = Box::into_raw(Box::new(data));
+            Result::Success
+        }
+        Err(error) => error.into(),
+    }
+}
+
 /// Writes the current DSP mode to `dsp_mode`.
 ///
 /// Returns `RESULT_NO_DATA` if the device has not reported a DSP mode.
@@ -661,6 +840,9 @@ pub extern "C" fn rfe_spectrum_analyzer_inactive_radio_model(
 }
 
 /// Starts Wi-Fi analyzer mode for the requested Wi-Fi band.
+///
+/// Returns `RESULT_INVALID_OPERATION_ERROR` if the active radio module cannot
+/// measure the Wi-Fi band.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn rfe_spectrum_analyzer_start_wifi_analyzer(
     rfe: Option<&SpectrumAnalyzer>,
@@ -821,8 +1003,20 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_set_sweep_callback(
 
     // Wrap the pointer to user_data in our own struct that implements Send so it can be
     // sent across threads
-    let user_data = UserDataWrapper(user_data);
-
+    set_sweep_callback(rfe, callback, UserDataWrapper(user_data));
+}
+
+fn set_sweep_callback(
+    rfe: &SpectrumAnalyzer,
+    callback: extern "C" fn(
+        sweep: *const f32,
+        sweep_len: usize,
+        start_hz: u64,
+        stop_hz: u64,
+        user_data: *mut c_void,
+    ),
+    user_data: UserDataWrapper,
+) {
     // Convert the C function pointer to a Rust closure
     let cb = move |sweep: &[f32], start_freq: Frequency, stop_freq: Frequency| {
         callback(
@@ -864,8 +1058,14 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_set_config_callback(
 
     // Wrap the pointer to user_data in our own struct that implements Send so it can be
     // sent across threads
-    let user_data = UserDataWrapper(user_data);
+    set_config_callback(rfe, callback, UserDataWrapper(user_data));
+}
 
+fn set_config_callback(
+    rfe: &SpectrumAnalyzer,
+    callback: extern "C" fn(config: SpectrumAnalyzerConfig, user_data: *mut c_void),
+    user_data: UserDataWrapper,
+) {
     // Convert the C function pointer to a Rust closure
     let cb = move |config: Config| {
         callback(SpectrumAnalyzerConfig::from(config), user_data.clone().0);
@@ -884,6 +1084,124 @@ pub unsafe extern "C" fn rfe_spectrum_analyzer_remove_config_callback(
     }
 }
 
+/// Sets the callback called when an LCD screen capture is received.
+///
+/// The callback may be invoked from a background thread, and multiple callback
+/// invocations may overlap. `screen_data` is only valid until the callback returns.
+/// `user_data`, if non-NULL, must remain valid until the callback is removed or the
+/// analyzer is freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rfe_spectrum_analyzer_set_screen_data_callback(
+    rfe: Option<&SpectrumAnalyzer>,
+    callback: Option<extern "C" fn(screen_data: *const ScreenData, user_data: *mut c_void)>,
+    user_data: *mut c_void,
+) {
+    let (Some(rfe), Some(callback)) = (rfe, callback) else {
+        return;
+    };
+
+    // Wrap the pointer to user_data in our own struct that implements Send so it can be
+    // sent across threads
+    set_screen_data_callback(rfe, callback, UserDataWrapper(user_data));
+}
+
+fn set_screen_data_callback(
+    rfe: &SpectrumAnalyzer,
+    callback: extern "C" fn(screen_data: *const ScreenData, user_data: *mut c_void),
+    user_data: UserDataWrapper,
+) {
+    rfe.set_screen_data_callback(move |screen_data: ScreenData| {
+        callback(&raw const screen_data, user_data.clone().0);
+    });
+}
+
+/// Removes the LCD screen capture callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rfe_spectrum_analyzer_remove_screen_data_callback(
+    rfe: Option<&SpectrumAnalyzer>,
+) {
+    if let Some(rfe) = rfe {
+        rfe.remove_screen_data_callback();
+    }
+}
+
+/// The callbacks set by `rfe_spectrum_analyzer_set_callbacks`.
+///
+/// Every callback is called from a background thread, not the thread that set it, and
+/// callbacks may be called at the same time as each other and as themselves, so they must be
+/// thread-safe. Pointers passed to a callback, like `sweep` and `screen_data`, are only valid
+/// until it returns. `user_data` is passed to every callback and, if non-NULL, must remain valid
+/// until the callbacks are cleared or the analyzer is freed.
+#[repr(C)]
+pub struct SpectrumAnalyzerCallbacks {
+    /// Called when a sweep is received.
+    pub sweep: Option<
+        extern "C" fn(
+            sweep: *const f32,
+            sweep_len: usize,
+            start_hz: u64,
+            stop_hz: u64,
+            user_data: *mut c_void,
+        ),
+    >,
+    /// Called when a configuration is received.
+    pub config: Option<extern "C" fn(config: SpectrumAnalyzerConfig, user_data: *mut c_void)>,
+    /// Called when a screen capture is received.
+    pub screen_data: Option<extern "C" fn(screen_data: *const ScreenData, user_data: *mut c_void)>,
+    /// Called when the state of the connection changes, like when the RF Explorer enters
+    /// firmware update mode or is unplugged.
+    pub connection_state:
+        Option<extern "C" fn(state: DeviceConnectionState, user_data: *mut c_void)>,
+    /// Passed to every callback.
+    pub user_data: *mut c_void,
+}
+
+/// Sets every non-NULL callback in `callbacks` in one call.
+///
+/// NULL callbacks leave the callbacks that are already set unchanged. Nothing is set if `rfe` or
+/// `callbacks` is NULL. See `SpectrumAnalyzerCallbacks` for when the callbacks are called.
+///
+/// Registration isn't atomic: the callbacks are replaced one after another, so a message that's
+/// received during the call can be passed to an old callback while another message is passed to
+/// a new one.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rfe_spectrum_analyzer_set_callbacks(
+    rfe: Option<&SpectrumAnalyzer>,
+    callbacks: Option<&SpectrumAnalyzerCallbacks>,
+) -> Result {
+    let (Some(rfe), Some(callbacks)) = (rfe, callbacks) else {
+        return Result::NullPtrError;
+    };
+
+    let user_data = UserDataWrapper(callbacks.user_data);
+    if let Some(callback) = callbacks.sweep {
+        set_sweep_callback(rfe, callback, user_data.clone());
+    }
+    if let Some(callback) = callbacks.config {
+        set_config_callback(rfe, callback, user_data.clone());
+    }
+    if let Some(callback) = callbacks.screen_data {
+        set_screen_data_callback(rfe, callback, user_data.clone());
+    }
+    if let Some(callback) = callbacks.connection_state {
+        rfe.set_connection_state_callback(move |state| {
+            callback(DeviceConnectionState::from(state), user_data.clone().0);
+        });
+    }
+    Result::Success
+}
+
+/// Removes every callback that can be set by `rfe_spectrum_analyzer_set_callbacks`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rfe_spectrum_analyzer_clear_callbacks(rfe: Option<&SpectrumAnalyzer>) {
+    if let Some(rfe) = rfe {
+        rfe.remove_sweep_callback();
+        rfe.remove_config_callback();
+        rfe.remove_screen_data_callback();
+        rfe.remove_connection_state_callback();
+    }
+}
+
 /// Sets the number of points in each sweep.
 ///
 /// Only Plus models support changing the sweep length.
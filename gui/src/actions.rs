@@ -0,0 +1,127 @@
+use egui::{Context, Key, KeyboardShortcut, Modifiers};
+use strum::{EnumIter, IntoEnumIterator};
+
+/// An operation that can be run with a keyboard shortcut or from the command palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Action {
+    ToggleScanning,
+    PlaceMarker,
+    PeakSearch,
+    ClearMarker,
+    ToggleMarkerDelta,
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    ExportScreenshot,
+    ExportPlotImage,
+    ExportCurrentTrace,
+    ExportAverageTrace,
+    ExportMaxTrace,
+    ToggleRfeSettingsPanel,
+    TogglePlotSettingsPanel,
+    ToggleEventLog,
+}
+
+impl Action {
+    /// The shortcut that opens the command palette.
+    pub const COMMAND_PALETTE_SHORTCUT: KeyboardShortcut =
+        KeyboardShortcut::new(Modifiers::COMMAND, Key::K);
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::ToggleScanning => "Pause/Resume Scanning",
+            Action::PlaceMarker => "Place Marker",
+            Action::PeakSearch => "Peak Search",
+            Action::ClearMarker => "Clear Marker",
+            Action::ToggleMarkerDelta => "Toggle Marker Delta",
+            Action::PanLeft => "Pan Left",
+            Action::PanRight => "Pan Right",
+            Action::ZoomIn => "Zoom In",
+            Action::ZoomOut => "Zoom Out",
+            Action::ExportScreenshot => "Export Screenshot as PNG",
+            Action::ExportPlotImage => "Export Plot Image as PNG",
+            Action::ExportCurrentTrace => "Export Current Trace as CSV",
+            Action::ExportAverageTrace => "Export Average Trace as CSV",
+            Action::ExportMaxTrace => "Export Max Trace as CSV",
+            Action::ToggleRfeSettingsPanel => "Show/Hide RF Explorer Settings",
+            Action::TogglePlotSettingsPanel => "Show/Hide Plot Settings",
+            Action::ToggleEventLog => "Show/Hide Event Log",
+        }
+    }
+
+    /// The keys that run the action. The first one is shown in the command palette.
+    pub fn shortcuts(self) -> &'static [KeyboardShortcut] {
+        // Shortcuts are built in const blocks so they can be returned as static slices
+        const fn key(key: Key) -> KeyboardShortcut {
+            KeyboardShortcut::new(Modifiers::NONE, key)
+        }
+
+        match self {
+            Action::ToggleScanning => const { &[key(Key::Space)] },
+            Action::PlaceMarker => const { &[key(Key::M)] },
+            Action::PeakSearch => const { &[key(Key::P)] },
+            Action::ClearMarker => const { &[key(Key::Escape)] },
+            Action::ToggleMarkerDelta => const { &[key(Key::D)] },
+            Action::PanLeft => const { &[key(Key::ArrowLeft)] },
+            Action::PanRight => const { &[key(Key::ArrowRight)] },
+            // Plus needs Shift on some keyboards, so the key it shares also zooms in
+            Action::ZoomIn => const { &[key(Key::Plus), key(Key::Equals)] },
+            Action::ZoomOut => const { &[key(Key::Minus)] },
+            Action::ExportScreenshot => const { &[key(Key::S)] },
+            Action::ExportPlotImage
+            | Action::ExportCurrentTrace
+            | Action::ExportAverageTrace
+            | Action::ExportMaxTrace
+            | Action::ToggleRfeSettingsPanel
+            | Action::TogglePlotSettingsPanel
+            | Action::ToggleEventLog => &[],
+        }
+    }
+
+    /// Consumes the shortcuts pressed this frame and returns the actions they run.
+    ///
+    /// Shortcuts are ignored while a text field is focused so typing doesn't trigger them.
+    pub fn pressed(ctx: &Context) -> Vec<Action> {
+        if ctx.text_edit_focused() {
+            return Vec::new();
+        }
+        ctx.input_mut(|input| {
+            Action::iter()
+                .filter(|action| {
+                    action
+                        .shortcuts()
+                        .iter()
+                        .any(|shortcut| input.consume_shortcut(shortcut))
+                })
+                .collect()
+        })
+    }
+
+    /// Scores how well the action's name matches a search query, or returns `None` if it doesn't.
+    ///
+    /// The query's characters must appear in the name in order, but not necessarily next to each
+    /// other. Matches at the start of words and runs of consecutive characters score higher.
+    pub fn fuzzy_score(self, query: &str) -> Option<u32> {
+        let name: Vec<char> = self.name().to_lowercase().chars().collect();
+        let mut score = 0;
+        let mut name_index = 0;
+        let mut previous_match = None;
+        for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+            let match_index = name_index
+                + name[name_index..]
+                    .iter()
+                    .position(|&name_char| name_char == query_char)?;
+            score += 1;
+            if match_index == 0 || !name[match_index - 1].is_alphanumeric() {
+                score += 2;
+            }
+            if previous_match.is_some_and(|previous| previous + 1 == match_index) {
+                score += 1;
+            }
+            previous_match = Some(match_index);
+            name_index = match_index + 1;
+        }
+        Some(score)
+    }
+}
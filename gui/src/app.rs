@@ -1,34 +1,80 @@
 use std::{
     default::Default,
-    num::ParseFloatError,
-    str::FromStr,
-    sync::{Arc, Mutex, atomic::Ordering},
+    io,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::Ordering,
+        mpsc::{self, Receiver, TryRecvError},
+    },
 };
 
 use csv::Writer;
-use egui::Ui;
+use eframe::egui_wgpu::RenderState;
+use egui::{ColorImage, Context, Event, Ui, UserData, ViewportCommand};
+use image::ExtendedColorType;
 use rfd::FileDialog;
-use rfe::{Frequency, SpectrumAnalyzer, spectrum_analyzer::Config};
+use rfe::{
+    Frequency, LinkHealth, SpectrumAnalyzer,
+    spectrum_analyzer::{CsvDirSink, RecordingSession},
+};
 
 use crate::{
-    data::{RfeInfo, SpectrogramData, TraceData},
+    actions::Action,
+    data::{
+        ComparedDevices, ConnectAttempt, DataWorker, EventKind, EventLog, Playback, RfeInfo,
+        SharedData, TraceData,
+    },
     panels::{
         AppSettingsBottomPanel, AppSettingsPanelResponse, PlotCentralPanel,
-        PlotSettingsPanelResponse, PlotSettingsSidePanel, RfeNotConnectedCentralPanel,
-        RfeSettingsChange, RfeSettingsSidePanel,
+        PlotSettingsPanelResponse, PlotSettingsSidePanel, RecordingStatus,
+        RfeNotConnectedCentralPanel, RfeSettingsChange, RfeSettingsSidePanel,
     },
-    settings::{AppSettings, FrequencyUnits, SpectrogramSettings, SweepSettings, TraceSettings},
+    plot_image::PlotImage,
+    settings::{
+        AppSettings, ExternalOffset, FrequencyUnits, SettingsFile, SpectrogramSettings,
+        SweepSettings, TraceLayerKind, TraceSettings,
+    },
+    widgets::{CommandPalette, PlaybackBar, ReadoutBar, ReadoutField, Toasts},
 };
 
 pub struct App {
     rfe: Option<Arc<Mutex<SpectrumAnalyzer>>>,
+    /// The RF Explorer being connected to on a background thread.
+    connect_attempt: Option<ConnectAttempt>,
     rfe_info: Arc<Mutex<RfeInfo>>,
+    link_health: Option<LinkHealth>,
+    sweep_rate_hz: Option<f64>,
     trace_data: Arc<Mutex<TraceData>>,
-    spectrogram_data: Arc<Mutex<SpectrogramData>>,
+    data_worker: Option<DataWorker>,
+    /// Other spectrum analyzers whose traces are compared with the RF Explorer's.
+    compared_devices: ComparedDevices,
     app_settings: AppSettings,
     sweep_settings: Arc<Mutex<SweepSettings>>,
+    /// The offset that makes up for the gain or loss of what's connected to the RF Explorer.
+    external_offset: ExternalOffset,
     trace_settings: TraceSettings,
+    /// The trace layers the worker computes, which are the ones that are shown.
+    visible_trace_layers: Arc<Mutex<Vec<TraceLayerKind>>>,
     spectrogram_settings: Arc<Mutex<SpectrogramSettings>>,
+    event_log: EventLog,
+    command_palette: CommandPalette,
+    readout_bar: ReadoutBar,
+    /// The setting that was clicked in the readout bar, which the RF Explorer settings panel
+    /// jumps to the next time it's shown.
+    jump_to_setting: Option<ReadoutField>,
+    /// The frequency under the pointer when it was last over the trace.
+    trace_hover_freq: Option<Frequency>,
+    /// The session recording the RF Explorer's sweeps and configs.
+    recording: Option<RecordingSession>,
+    /// The recording session being started on a background thread, along with its folder.
+    starting_recording: Option<Receiver<(PathBuf, rfe::Result<RecordingSession>)>>,
+    /// The recording being played back instead of the RF Explorer's sweeps.
+    playback: Option<Playback>,
+    /// The recording being loaded for playback on a background thread.
+    opening_playback: Option<Receiver<io::Result<Playback>>>,
+    /// The GPU the app is drawn with, which plot images are also rendered with.
+    render_state: Option<RenderState>,
 }
 
 impl App {
@@ -36,163 +82,525 @@ impl App {
     pub fn new(cc: &eframe::CreationContext<'_>, rfe: Option<rfe::SpectrumAnalyzer>) -> Self {
         egui_extras::install_image_loaders(&cc.egui_ctx);
         let rfe_info = rfe.as_ref().map(RfeInfo::new).unwrap_or_default();
-        let app_settings = AppSettings::default();
-        let sweep_settings = rfe
-            .as_ref()
-            .map(|rfe| SweepSettings::new(rfe, app_settings.frequency_units))
-            .unwrap_or_default();
+        let mut app_settings = AppSettings::default();
+        let sweep_settings = rfe.as_ref().map(SweepSettings::new).unwrap_or_default();
+
+        let event_log = EventLog::default();
+        match rfe {
+            Some(ref rfe) => event_log.push(
+                EventKind::Connection,
+                format!("Connected to an RF Explorer on {}", rfe.port_name()),
+            ),
+            None => event_log.push(EventKind::Connection, "No RF Explorer was found"),
+        }
+
+        let mut trace_settings = TraceSettings::default();
+        if let Some(Some(settings_file)) =
+            event_log.log_result("Failed to load the saved settings", SettingsFile::load())
+        {
+            settings_file.apply(&mut app_settings, &mut trace_settings);
+        }
+        cc.egui_ctx.set_theme(app_settings.theme);
+        let visible_trace_layers = Arc::new(Mutex::new(trace_settings.visible_layers()));
 
-        let app = App {
+        let mut app = App {
             rfe: rfe.map(|rfe| Arc::new(Mutex::new(rfe))),
+            connect_attempt: None,
             rfe_info: Arc::new(Mutex::new(rfe_info)),
+            link_health: None,
+            sweep_rate_hz: None,
             trace_data: Arc::new(Mutex::new(TraceData::default())),
-            spectrogram_data: Arc::new(Mutex::new(SpectrogramData::new(&cc.egui_ctx))),
+            data_worker: None,
+            compared_devices: ComparedDevices::default(),
             app_settings,
             sweep_settings: Arc::new(Mutex::new(sweep_settings)),
-            trace_settings: TraceSettings::default(),
+            external_offset: ExternalOffset::default(),
+            trace_settings,
+            visible_trace_layers,
             spectrogram_settings: Arc::new(Mutex::new(SpectrogramSettings::default())),
+            event_log,
+            command_palette: CommandPalette::default(),
+            readout_bar: ReadoutBar::default(),
+            jump_to_setting: None,
+            trace_hover_freq: None,
+            recording: None,
+            starting_recording: None,
+            playback: None,
+            opening_playback: None,
+            render_state: cc.wgpu_render_state.clone(),
         };
 
-        app.init_callbacks(&cc.egui_ctx);
+        app.start_data_worker(&cc.egui_ctx);
+        app.restore_external_offset();
         app
     }
 
-    fn init_callbacks(&self, egui_ctx: &egui::Context) {
+    /// Starts a worker that processes the RF Explorer's data off of the UI thread.
+    fn start_data_worker(&mut self, egui_ctx: &egui::Context) {
+        // Drop the previous worker before starting a new one so that it shuts down
+        self.data_worker = None;
         let Some(ref rfe) = self.rfe else {
             return;
         };
+        self.data_worker = Some(DataWorker::spawn(rfe, egui_ctx, self.shared_data()));
+    }
 
-        // Register a callback that updates our `SweepSettings` and `RfeInfo` when the RF Explorer's
-        // config changes
-        let sweep_settings_clone = self.sweep_settings.clone();
-        let rfe_info_clone = self.rfe_info.clone();
-        let ctx = egui_ctx.clone();
-        rfe.lock()
-            .unwrap()
-            .set_config_callback(move |config: Config| {
-                sweep_settings_clone.lock().unwrap().update(&config);
-                rfe_info_clone.lock().unwrap().update(&config);
-                ctx.request_repaint();
-            });
-
-        // Register a callback that updates our data for the trace and the spectrogram when we receive
-        // a new sweep
-        let trace_data_clone = self.trace_data.clone();
-        let spectrogram_data_clone = self.spectrogram_data.clone();
-        let spectrogram_settings_clone = self.spectrogram_settings.clone();
-        let pause_sweeps_clone = self.app_settings.pause_sweeps.clone();
-        let ctx = egui_ctx.clone();
-        rfe.lock()
-            .unwrap()
-            .set_sweep_callback(move |amps, start_freq, stop_freq| {
-                if !pause_sweeps_clone.load(Ordering::Relaxed) {
-                    trace_data_clone
-                        .lock()
-                        .unwrap()
-                        .update(amps, start_freq, stop_freq);
-                    spectrogram_data_clone.lock().unwrap().update(
-                        amps,
-                        start_freq,
-                        stop_freq,
-                        spectrogram_settings_clone.lock().as_ref().unwrap(),
-                    );
-                    ctx.request_repaint();
+    fn shared_data(&self) -> SharedData {
+        SharedData {
+            trace_data: self.trace_data.clone(),
+            rfe_info: self.rfe_info.clone(),
+            sweep_settings: self.sweep_settings.clone(),
+            spectrogram_settings: self.spectrogram_settings.clone(),
+            visible_trace_layers: self.visible_trace_layers.clone(),
+            pause_sweeps: self.app_settings.pause_sweeps.clone(),
+            event_log: self.event_log.clone(),
+        }
+    }
+
+    /// Shows the RF Explorer's sweeps and settings, like after it connects or playback is closed.
+    fn start_live_view(&mut self, egui_ctx: &egui::Context) {
+        *self.trace_data.lock().unwrap() = TraceData::default();
+        self.start_data_worker(egui_ctx);
+        *self.sweep_settings.lock().unwrap() = self
+            .rfe
+            .as_ref()
+            .map(|rfe| SweepSettings::new(&rfe.lock().unwrap()))
+            .unwrap_or_default();
+        *self.rfe_info.lock().unwrap() = self
+            .rfe
+            .as_ref()
+            .map(|rfe| RfeInfo::new(&rfe.lock().unwrap()))
+            .unwrap_or_default();
+        self.restore_external_offset();
+    }
+
+    /// Restores the external offset saved for the RF Explorer's serial number, or shows the
+    /// offset it already has if none was saved.
+    fn restore_external_offset(&mut self) {
+        let Some(ref rfe) = self.rfe else {
+            return;
+        };
+        let rfe = rfe.lock().unwrap();
+        let saved_offset_db = rfe.serial_number().and_then(|serial_number| {
+            self.app_settings
+                .external_offsets_db
+                .get(&*serial_number)
+                .copied()
+        });
+        self.external_offset = ExternalOffset {
+            total_db: saved_offset_db
+                .unwrap_or_else(|| rfe.amp_offset_db().map(i32::from).unwrap_or_default()),
+        };
+        self.trace_settings.external_app_offset = self.external_offset.app_db();
+        if saved_offset_db.is_some()
+            && rfe.amp_offset_db() != Some(self.external_offset.device_db())
+        {
+            self.event_log.log_result(
+                "Failed to set the amplitude offset",
+                rfe.set_offset_db(self.external_offset.device_db()),
+            );
+        }
+    }
+
+    /// Starts showing the RF Explorer once the attempt to connect to it has finished.
+    fn receive_connection(&mut self, egui_ctx: &egui::Context) {
+        let Some(result) = self
+            .connect_attempt
+            .as_ref()
+            .and_then(ConnectAttempt::try_finish)
+        else {
+            return;
+        };
+        self.connect_attempt = None;
+        let Some(spectrum_analyzer) = result else {
+            self.event_log
+                .push(EventKind::Connection, "Failed to connect to an RF Explorer");
+            return;
+        };
+        self.event_log.push(
+            EventKind::Connection,
+            format!(
+                "Connected to an RF Explorer on {}",
+                spectrum_analyzer.port_name()
+            ),
+        );
+        self.rfe = Some(Arc::new(Mutex::new(spectrum_analyzer)));
+        self.start_live_view(egui_ctx);
+    }
+
+    /// Replaces the RF Explorer's sweeps with a recording's.
+    ///
+    /// The RF Explorer keeps sweeping, so a recording that's in progress isn't interrupted.
+    fn start_playback(&mut self, playback: Playback, egui_ctx: &egui::Context) {
+        self.event_log.push(
+            EventKind::Recording,
+            format!("Playing back {}", playback.name()),
+        );
+        // Drop the RF Explorer's worker first so its callbacks stop updating the settings
+        self.data_worker = None;
+        *self.trace_data.lock().unwrap() = TraceData::default();
+        *self.sweep_settings.lock().unwrap() = SweepSettings::default();
+        *self.rfe_info.lock().unwrap() = RfeInfo::default();
+        self.sweep_rate_hz = None;
+        // The recording's sweeps may not be from the RF Explorer the offset was entered for
+        self.trace_settings.external_app_offset = 0;
+        self.data_worker = Some(DataWorker::spawn_playback(egui_ctx, self.shared_data()));
+        self.playback = Some(playback);
+    }
+
+    fn stop_playback(&mut self, egui_ctx: &egui::Context) {
+        self.playback = None;
+        self.start_live_view(egui_ctx);
+    }
+
+    /// Asks the user for a folder to record to and starts recording there on a background
+    /// thread, or stops the recording that's in progress.
+    fn toggle_recording(&mut self) {
+        if let Some(session) = self.recording.take() {
+            stop_recording(session, &self.event_log);
+            return;
+        }
+        let Some(rfe) = self.rfe.clone() else {
+            return;
+        };
+        if self.starting_recording.is_some() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let Some(dir) = FileDialog::new()
+                .set_title("Record to Folder")
+                .pick_folder()
+            else {
+                return;
+            };
+            let result = CsvDirSink::create(&dir)
+                .map_err(rfe::Error::from)
+                .and_then(|sink| RecordingSession::start(&rfe.lock().unwrap(), sink));
+            _ = sender.send((dir, result));
+        });
+        self.starting_recording = Some(receiver);
+    }
+
+    /// Asks the user for a recording's folder and loads it on a background thread.
+    fn open_recording(&mut self) {
+        if self.opening_playback.is_some() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let Some(dir) = FileDialog::new().set_title("Open Recording").pick_folder() else {
+                return;
+            };
+            _ = sender.send(Playback::load(&dir));
+        });
+        self.opening_playback = Some(receiver);
+    }
+
+    /// Picks up recordings that have started or been opened on background threads.
+    ///
+    /// This never blocks, so it can be called every frame.
+    fn receive_recordings(&mut self, egui_ctx: &egui::Context) {
+        if let Some(ref starting_recording) = self.starting_recording {
+            match starting_recording.try_recv() {
+                Ok((dir, result)) => {
+                    self.recording = self
+                        .event_log
+                        .log_result("Failed to start recording", result);
+                    if self.recording.is_some() {
+                        self.event_log.push(
+                            EventKind::Recording,
+                            format!("Recording to {}", dir.display()),
+                        );
+                    }
+                    self.starting_recording = None;
                 }
-            });
+                Err(TryRecvError::Empty) => (),
+                Err(TryRecvError::Disconnected) => self.starting_recording = None,
+            }
+        }
+
+        if let Some(ref opening_playback) = self.opening_playback {
+            match opening_playback.try_recv() {
+                Ok(result) => {
+                    self.opening_playback = None;
+                    if let Some(playback) = self
+                        .event_log
+                        .log_result("Failed to open the recording", result)
+                    {
+                        self.start_playback(playback, egui_ctx);
+                    }
+                }
+                Err(TryRecvError::Empty) => (),
+                Err(TryRecvError::Disconnected) => self.opening_playback = None,
+            }
+        }
     }
 
-    fn on_rfe_settings_changed(&self, panel_response: RfeSettingsChange) {
+    /// Runs an action picked from the command palette or triggered by its keyboard shortcut.
+    ///
+    /// Actions go through the same handlers as the panels' buttons and settings.
+    fn run_action(&mut self, action: Action, ctx: &Context) {
+        match action {
+            Action::ToggleScanning if self.playback.is_some() => {
+                if let Some(ref mut playback) = self.playback {
+                    playback.toggle_playing();
+                }
+            }
+            Action::ToggleScanning => {
+                self.app_settings
+                    .pause_sweeps
+                    .fetch_xor(true, Ordering::Relaxed);
+            }
+            Action::PlaceMarker => {
+                self.trace_settings.marker_freq = self
+                    .trace_hover_freq
+                    .or_else(|| Some(self.sweep_settings.lock().unwrap().center_freq));
+            }
+            Action::PeakSearch => {
+                self.trace_settings.marker_freq = self
+                    .trace_data
+                    .lock()
+                    .unwrap()
+                    .current()
+                    .iter()
+                    .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(freq, _)| *freq);
+            }
+            Action::ClearMarker => self.trace_settings.clear_markers(),
+            Action::ToggleMarkerDelta => self.trace_settings.toggle_marker_delta(),
+            Action::PanLeft | Action::PanRight | Action::ZoomIn | Action::ZoomOut => {
+                self.pan_or_zoom(action);
+            }
+            Action::ExportScreenshot => {
+                ctx.send_viewport_cmd(ViewportCommand::Screenshot(UserData::default()));
+            }
+            Action::ExportPlotImage => self
+                .on_app_settings_changed(AppSettingsPanelResponse::ExportPlotImageClicked(2), ctx),
+            Action::ExportCurrentTrace => self
+                .on_app_settings_changed(AppSettingsPanelResponse::ExportCurrentTraceClicked, ctx),
+            Action::ExportAverageTrace => self
+                .on_app_settings_changed(AppSettingsPanelResponse::ExportAverageTraceClicked, ctx),
+            Action::ExportMaxTrace => {
+                self.on_app_settings_changed(AppSettingsPanelResponse::ExportMaxTraceClicked, ctx)
+            }
+            Action::ToggleRfeSettingsPanel => {
+                self.app_settings.show_rfe_settings_panel =
+                    !self.app_settings.show_rfe_settings_panel;
+            }
+            Action::TogglePlotSettingsPanel => {
+                self.app_settings.show_plot_settings_panel =
+                    !self.app_settings.show_plot_settings_panel;
+            }
+            Action::ToggleEventLog => {
+                self.app_settings.show_event_log = !self.app_settings.show_event_log;
+            }
+        }
+    }
+
+    /// Moves the span by a tenth of its width or halves or doubles it, keeping it within the
+    /// RF Explorer's frequency range.
+    fn pan_or_zoom(&mut self, action: Action) {
+        if self.rfe.is_none() || self.playback.is_some() {
+            return;
+        }
+        let rfe_info = self.rfe_info.lock().unwrap().clone();
+        {
+            let mut sweep_settings = self.sweep_settings.lock().unwrap();
+            let (center, span) = (sweep_settings.center_freq, sweep_settings.span);
+            let pan_step = span / 10;
+            let (center, span) = match action {
+                Action::PanLeft => (center.max(pan_step) - pan_step, span),
+                Action::PanRight => (center + pan_step, span),
+                Action::ZoomIn => (center, span / 2),
+                Action::ZoomOut => (center, (span * 2).min(rfe_info.max_span)),
+                _ => return,
+            };
+            let half_span = span / 2;
+            let min_center = rfe_info.min_freq + half_span;
+            let max_center =
+                (rfe_info.max_freq.max(min_center + half_span) - half_span).max(min_center);
+            sweep_settings.center_freq = center.clamp(min_center, max_center);
+            sweep_settings.span = span;
+        }
+        self.on_rfe_settings_changed(RfeSettingsChange::CenterSpan);
+    }
+
+    fn on_rfe_settings_changed(&mut self, panel_response: RfeSettingsChange) {
         let Some(ref rfe) = self.rfe else {
             return;
         };
+        // The sweep settings show the recording's while it's played back
+        if self.playback.is_some() {
+            return;
+        }
         // We clone the sweep settings here so that we don't hold on to the lock
         // which would cause a deadlock when the RF Explorer sends a new `Config`
         // and our config callback gets called
         let sweep_settings = self.sweep_settings.lock().unwrap().clone();
-        let units = self.app_settings.frequency_units;
         match panel_response {
             RfeSettingsChange::CenterSpan => {
-                let center_freq = str_to_freq(&sweep_settings.center_freq, units);
-                let span = str_to_freq(&sweep_settings.span, units);
-                let (Ok(center), Ok(span)) = (center_freq, span) else {
-                    return;
-                };
+                let (center, span) = (sweep_settings.center_freq, sweep_settings.span);
                 // Call rfe.set_center_span on a non-UI thread because it would cause
                 // the UI to freeze while it waits for a response from the RF Explorer
                 let rfe_clone = rfe.clone();
+                let event_log = self.event_log.clone();
                 std::thread::spawn(move || {
-                    _ = rfe_clone.lock().unwrap().set_center_span(center, span);
+                    event_log.log_result(
+                        "Failed to set the center frequency and span",
+                        rfe_clone.lock().unwrap().set_center_span(center, span),
+                    );
                 });
             }
             RfeSettingsChange::StartStop => {
-                let start_freq = str_to_freq(&sweep_settings.start_freq, units);
-                let stop_freq = str_to_freq(&sweep_settings.stop_freq, units);
-                let (Ok(start), Ok(stop)) = (start_freq, stop_freq) else {
-                    return;
-                };
+                let (start, stop) = (sweep_settings.start_freq, sweep_settings.stop_freq);
                 // Call rfe.set_start_stop on a non-UI thread because it would cause
                 // the UI to freeze while it waits for a response from the RF Explorer
                 let rfe_clone = rfe.clone();
+                let event_log = self.event_log.clone();
                 std::thread::spawn(move || {
-                    _ = rfe_clone.lock().unwrap().set_start_stop(start, stop);
+                    event_log.log_result(
+                        "Failed to set the start and stop frequencies",
+                        rfe_clone.lock().unwrap().set_start_stop(start, stop),
+                    );
                 });
             }
             RfeSettingsChange::SweepLen => {
-                let center_freq = str_to_freq(&sweep_settings.center_freq, units);
-                let span = str_to_freq(&sweep_settings.span, units);
+                let (center, span) = (sweep_settings.center_freq, sweep_settings.span);
                 let sweep_len = sweep_settings.len;
-                let (Ok(center), Ok(span)) = (center_freq, span) else {
-                    return;
-                };
                 // Call rfe.set_center_span_sweep_len on a non-UI thread because it would cause
                 // the UI to freeze while it waits for a response from the RF Explorer
                 let rfe_clone = rfe.clone();
+                let event_log = self.event_log.clone();
                 std::thread::spawn(move || {
-                    _ = rfe_clone
-                        .lock()
-                        .unwrap()
-                        .set_center_span_sweep_len(center, span, sweep_len);
+                    event_log.log_result(
+                        "Failed to set the number of sweep points",
+                        rfe_clone
+                            .lock()
+                            .unwrap()
+                            .set_center_span_sweep_len(center, span, sweep_len),
+                    );
+                });
+            }
+            RfeSettingsChange::ExternalOffset => {
+                self.trace_settings.external_app_offset = self.external_offset.app_db();
+                if let Some(serial_number) = &self.rfe_info.lock().unwrap().serial_number {
+                    self.app_settings
+                        .external_offsets_db
+                        .insert(serial_number.to_string(), self.external_offset.total_db);
+                }
+                let offset_db = self.external_offset.device_db();
+                let rfe_clone = rfe.clone();
+                let event_log = self.event_log.clone();
+                std::thread::spawn(move || {
+                    event_log.log_result(
+                        "Failed to set the amplitude offset",
+                        rfe_clone.lock().unwrap().set_offset_db(offset_db),
+                    );
                 });
             }
         }
     }
 
-    fn on_app_settings_changed(&self, panel_response: AppSettingsPanelResponse) {
+    fn on_app_settings_changed(&mut self, panel_response: AppSettingsPanelResponse, ctx: &Context) {
         match panel_response {
             AppSettingsPanelResponse::ExportCurrentTraceClicked => export_csv(
                 self.trace_data.lock().unwrap().current(),
                 self.app_settings.frequency_units,
+                &self.event_log,
             ),
             AppSettingsPanelResponse::ExportAverageTraceClicked => export_csv(
-                self.trace_data.lock().unwrap().average(),
+                self.trace_data
+                    .lock()
+                    .unwrap()
+                    .layer(TraceLayerKind::Average),
                 self.app_settings.frequency_units,
+                &self.event_log,
             ),
             AppSettingsPanelResponse::ExportMaxTraceClicked => export_csv(
-                self.trace_data.lock().unwrap().max(),
+                self.trace_data
+                    .lock()
+                    .unwrap()
+                    .layer(TraceLayerKind::MaxHold),
                 self.app_settings.frequency_units,
+                &self.event_log,
             ),
-            AppSettingsPanelResponse::FrequencyUnitsChanged => {
-                // If the units setting was changed, recreate our record of the RF Explorer's settings
-                *self.sweep_settings.lock().unwrap() = self
-                    .rfe
-                    .as_ref()
-                    .map(|rfe| {
-                        SweepSettings::new(&rfe.lock().unwrap(), self.app_settings.frequency_units)
-                    })
-                    .unwrap_or_default()
+            AppSettingsPanelResponse::ExportEventLogClicked => export_event_log(&self.event_log),
+            AppSettingsPanelResponse::ExportPlotImageClicked(scale) => {
+                self.export_plot_image(scale, ctx);
             }
+            AppSettingsPanelResponse::ThemeChanged => ctx.set_theme(self.app_settings.theme),
+            AppSettingsPanelResponse::RecordClicked => self.toggle_recording(),
+            AppSettingsPanelResponse::OpenRecordingClicked => self.open_recording(),
+            // The frequency inputs format their frequencies in the current units when they're shown
+            AppSettingsPanelResponse::FrequencyUnitsChanged => (),
         }
     }
 
-    fn on_plot_settings_changed(&self, panel_response: PlotSettingsPanelResponse) {
+    /// Renders the traces into an image with the app's theme and saves it on a new thread.
+    fn export_plot_image(&self, scale: u8, ctx: &Context) {
+        let Some(ref render_state) = self.render_state else {
+            self.event_log.push(
+                EventKind::Error,
+                "Plot images can't be exported without a GPU renderer",
+            );
+            return;
+        };
+        let rfe_info = self.rfe_info.lock().unwrap().clone();
+        let units = self.app_settings.frequency_units;
+        let mut footer = vec![
+            format!("RF Explorer {}", rfe_info.active_radio_model),
+            format!(
+                "Serial {}",
+                rfe_info.serial_number.as_deref().unwrap_or("unknown")
+            ),
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        ];
+        if let Some(rbw) = self.sweep_settings.lock().unwrap().rbw {
+            footer.push(format!("RBW {} {units}", freq_to_string(rbw, units)));
+        }
+        let plot_image = PlotImage {
+            trace_data: self.trace_data.lock().unwrap().clone(),
+            compared_traces: self.compared_devices.visible_traces(),
+            trace_settings: self.trace_settings,
+            units,
+            theme: self.app_settings.theme.resolve(ctx),
+            footer: footer.join("  |  "),
+            scale,
+        };
+        plot_image.export(
+            render_state.device.clone(),
+            render_state.queue.clone(),
+            &self.event_log,
+        );
+    }
+
+    fn on_plot_settings_changed(&mut self, panel_response: PlotSettingsPanelResponse) {
         match panel_response {
             PlotSettingsPanelResponse::SpectrogramSettingsChanged => {
-                self.spectrogram_data
-                    .lock()
-                    .unwrap()
-                    .recreate_image(&self.spectrogram_settings.lock().unwrap());
+                if let Some(ref data_worker) = self.data_worker {
+                    data_worker.recreate_spectrogram();
+                }
+            }
+            PlotSettingsPanelResponse::ExportSpectrogramClicked(format) => {
+                if let Some(ref data_worker) = self.data_worker {
+                    data_worker.export_spectrogram(format);
+                }
+            }
+            PlotSettingsPanelResponse::ConnectComparedDevicesClicked => {
+                self.compared_devices.connect_all();
+            }
+            PlotSettingsPanelResponse::RemoveComparedDeviceClicked(key) => {
+                self.compared_devices.remove(&key);
+            }
+            PlotSettingsPanelResponse::TraceSettingsChanged => {
+                *self.visible_trace_layers.lock().unwrap() = self.trace_settings.visible_layers();
+            }
+            PlotSettingsPanelResponse::SetBaselineClicked => {
+                self.trace_data.lock().unwrap().set_baseline();
             }
-            PlotSettingsPanelResponse::TraceSettingsChanged => (),
         }
     }
 }
@@ -200,25 +608,91 @@ impl App {
 impl eframe::App for App {
     /// Called each time the UI needs repainting, which may be many times per second.
     fn ui(&mut self, ui: &mut Ui, _frame: &mut eframe::Frame) {
-        let panel_response = AppSettingsBottomPanel::new().show(ui, &mut self.app_settings);
+        // Only the latest snapshot from the worker is drawn, so drain any that are waiting
+        if let Some(ref mut data_worker) = self.data_worker {
+            data_worker.receive_snapshots();
+        }
+
+        let ctx = ui.ctx().clone();
+        self.receive_recordings(&ctx);
+        self.receive_connection(&ctx);
+        if let (Some(playback), Some(data_worker)) = (&mut self.playback, &self.data_worker) {
+            playback.update(data_worker);
+            // Keep repainting so playback advances even when no new snapshots arrive
+            if playback.is_playing() {
+                ctx.request_repaint();
+            }
+        }
+        self.compared_devices.update(&ctx, &self.event_log);
+        if ctx.input_mut(|input| input.consume_shortcut(&Action::COMMAND_PALETTE_SHORTCUT)) {
+            self.command_palette.toggle();
+        }
+        let mut actions = Action::pressed(&ctx);
+        actions.extend(self.command_palette.show(&ctx));
+        for action in actions {
+            self.run_action(action, &ctx);
+        }
+        let screenshot = ctx.input(|input| {
+            input.raw.events.iter().find_map(|event| match event {
+                Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
+        });
+        if let Some(screenshot) = screenshot {
+            export_screenshot(screenshot, &self.event_log);
+        }
+        let frames_behind = self
+            .data_worker
+            .as_ref()
+            .map_or(0, DataWorker::frames_behind);
+
+        let recording_status = if self.recording.is_some() {
+            RecordingStatus::Recording
+        } else if self.rfe.is_some() && self.starting_recording.is_none() {
+            RecordingStatus::Stopped
+        } else {
+            RecordingStatus::Unavailable
+        };
+        let panel_response = AppSettingsBottomPanel::new()
+            .recording_status(recording_status)
+            .show(ui, &mut self.app_settings, frames_behind, &self.event_log);
         if let Some(panel_response) = panel_response {
-            self.on_app_settings_changed(panel_response);
+            self.on_app_settings_changed(panel_response, &ctx);
         }
 
-        if self.app_settings.show_rfe_settings_panel {
-            let can_change_sweep_len = self
-                .rfe_info
-                .lock()
-                .unwrap()
-                .active_radio_model
-                .is_plus_model();
-            let panel_response = RfeSettingsSidePanel::new().show(
-                ui,
-                can_change_sweep_len,
-                &mut self.sweep_settings.lock().unwrap(),
-                &self.rfe_info.lock().unwrap(),
-                self.app_settings.frequency_units,
+        if let Some(ref mut playback) = self.playback
+            && PlaybackBar::show(ui, playback)
+        {
+            self.event_log.push(
+                EventKind::Recording,
+                format!("Stopped playing back {}", playback.name()),
             );
+            self.stop_playback(&ctx);
+        }
+
+        // Settings are sent to the RF Explorer while its lock is held, so keep showing the
+        // previous values instead of waiting for them to finish. The recording's settings are
+        // shown while it's played back instead.
+        if self.playback.is_none()
+            && let Some(rfe) = self.rfe.as_ref().and_then(|rfe| rfe.try_lock().ok())
+        {
+            self.link_health = Some(rfe.link_health());
+            self.sweep_rate_hz = rfe.sweep_rate_hz();
+            self.rfe_info.lock().unwrap().update_from_rfe(&rfe);
+        }
+
+        if self.app_settings.show_rfe_settings_panel {
+            let panel_response = RfeSettingsSidePanel::new()
+                .jump_to(self.jump_to_setting.take())
+                .enabled(self.playback.is_none())
+                .show(
+                    ui,
+                    &mut self.sweep_settings.lock().unwrap(),
+                    &mut self.external_offset,
+                    &self.rfe_info.lock().unwrap(),
+                    self.link_health.as_ref(),
+                    self.app_settings.frequency_units,
+                );
             if let Some(panel_response) = panel_response {
                 self.on_rfe_settings_changed(panel_response);
             }
@@ -229,50 +703,49 @@ impl eframe::App for App {
                 ui,
                 &mut self.trace_settings,
                 &mut self.spectrogram_settings.lock().unwrap(),
+                &mut self.compared_devices,
             );
             if let Some(panel_response) = panel_response {
                 self.on_plot_settings_changed(panel_response);
             }
         }
 
-        if self.rfe.is_some() {
-            PlotCentralPanel::new().show(
+        Toasts::show(ui, &self.event_log);
+
+        if let Some(ref data_worker) = self.data_worker {
+            let clicked_field = self.readout_bar.show(
                 ui,
-                &self.trace_data.lock().unwrap(),
+                &self.sweep_settings.lock().unwrap(),
+                &self.rfe_info.lock().unwrap(),
+                self.sweep_rate_hz,
+                self.app_settings.frequency_units,
+            );
+            if clicked_field.is_some() {
+                self.app_settings.show_rfe_settings_panel = true;
+                self.jump_to_setting = clicked_field;
+                ctx.request_repaint();
+            }
+            self.trace_hover_freq = PlotCentralPanel::new().show(
+                ui,
+                data_worker.snapshot(),
+                &self.compared_devices.visible_traces(),
                 &self.trace_settings,
-                &mut self.spectrogram_data.lock().unwrap(),
                 &self.spectrogram_settings.lock().unwrap(),
                 self.app_settings.frequency_units,
             );
         } else {
-            RfeNotConnectedCentralPanel::new().show(ui, &mut self.rfe);
-            // If an RF Explorer is now connected, set the required callbacks
-            if self.rfe.is_some() {
-                self.init_callbacks(ui.ctx());
-                *self.sweep_settings.lock().unwrap() = self
-                    .rfe
-                    .as_ref()
-                    .map(|rfe| {
-                        SweepSettings::new(&rfe.lock().unwrap(), self.app_settings.frequency_units)
-                    })
-                    .unwrap_or_default();
-                *self.rfe_info.lock().unwrap() = self
-                    .rfe
-                    .as_ref()
-                    .map(|rfe| RfeInfo::new(&rfe.lock().unwrap()))
-                    .unwrap_or_default();
+            if RfeNotConnectedCentralPanel::new().show(ui, self.connect_attempt.as_ref()) {
+                self.connect_attempt = Some(ConnectAttempt::start(&ctx, &self.event_log));
             }
         }
     }
-}
 
-fn str_to_freq(str: &str, units: FrequencyUnits) -> Result<Frequency, ParseFloatError> {
-    Ok(match units {
-        FrequencyUnits::Hz => Frequency::from_hz(f64::from_str(str)? as u64),
-        FrequencyUnits::Khz => Frequency::from_khz_f64(f64::from_str(str)?),
-        FrequencyUnits::Mhz => Frequency::from_mhz_f64(f64::from_str(str)?),
-        FrequencyUnits::Ghz => Frequency::from_ghz_f64(f64::from_str(str)?),
-    })
+    /// Called once when the app is closing.
+    fn on_exit(&mut self) {
+        if let Err(error) = SettingsFile::new(&self.app_settings, &self.trace_settings).save() {
+            tracing::error!("Failed to save the settings: {error}");
+        }
+    }
 }
 
 fn freq_to_string(freq: Frequency, units: FrequencyUnits) -> String {
@@ -284,29 +757,89 @@ fn freq_to_string(freq: Frequency, units: FrequencyUnits) -> String {
     }
 }
 
-fn export_csv(trace: &[(Frequency, f64)], units: FrequencyUnits) {
+fn export_csv(trace: &[(Frequency, f64)], units: FrequencyUnits, event_log: &EventLog) {
     if trace.is_empty() {
         return;
     }
 
     // Open the save file dialog in a new thread so we don't block the UI thread from updating
     let trace = trace.to_vec();
+    let event_log = event_log.clone();
     std::thread::spawn(move || {
-        let Some(Ok(mut writer)) = FileDialog::new()
+        let Some(path) = FileDialog::new()
             .set_title("Export CSV")
             .add_filter("CSV", &["csv"])
             .set_file_name("trace.csv")
             .save_file()
-            .map(Writer::from_path)
         else {
             return;
         };
-        for (freq, amp) in trace.iter().map(|point| (point.0, point.1)) {
-            let record = [freq_to_string(freq, units), amp.to_string()];
-            if writer.write_record(record).is_err() {
-                break;
+        let result = Writer::from_path(path).and_then(|mut writer| {
+            for (freq, amp) in trace.iter().map(|point| (point.0, point.1)) {
+                writer.write_record([freq_to_string(freq, units), amp.to_string()])?;
             }
+            writer.flush()?;
+            Ok(())
+        });
+        event_log.log_result("Failed to export the trace", result);
+    });
+}
+
+fn export_screenshot(screenshot: Arc<ColorImage>, event_log: &EventLog) {
+    // Open the save file dialog in a new thread so we don't block the UI thread from updating
+    let event_log = event_log.clone();
+    std::thread::spawn(move || {
+        let Some(path) = FileDialog::new()
+            .set_title("Export Screenshot")
+            .add_filter("PNG", &["png"])
+            .set_file_name("rfe-screenshot.png")
+            .save_file()
+        else {
+            return;
+        };
+        let [width, height] = screenshot.size;
+        let result = image::save_buffer(
+            path,
+            screenshot.as_raw(),
+            u32::try_from(width).unwrap_or(u32::MAX),
+            u32::try_from(height).unwrap_or(u32::MAX),
+            ExtendedColorType::Rgba8,
+        );
+        event_log.log_result("Failed to export the screenshot", result);
+    });
+}
+
+fn stop_recording(session: RecordingSession, event_log: &EventLog) {
+    // Stop on a new thread because it waits for the queued records to be written
+    let event_log = event_log.clone();
+    std::thread::spawn(move || {
+        if let Some(summary) =
+            event_log.log_result("Failed to finish the recording", session.stop())
+        {
+            event_log.push(
+                EventKind::Recording,
+                format!(
+                    "Stopped recording after {} sweeps ({} dropped)",
+                    summary.sweeps_recorded, summary.sweeps_dropped
+                ),
+            );
         }
-        _ = writer.flush();
+    });
+}
+
+fn export_event_log(event_log: &EventLog) {
+    // Open the save file dialog in a new thread so we don't block the UI thread from updating
+    let event_log = event_log.clone();
+    std::thread::spawn(move || {
+        let Some(path) = FileDialog::new()
+            .set_title("Export Event Log")
+            .add_filter("Text", &["txt"])
+            .set_file_name("rfe-event-log.txt")
+            .save_file()
+        else {
+            return;
+        };
+        let result = event_log.export(path);
+        event_log.log_result("Failed to export the event log", result);
     });
 }
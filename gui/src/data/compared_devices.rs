@@ -0,0 +1,220 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, TryRecvError},
+    },
+    thread,
+};
+
+use egui::{Color32, Context};
+use rfe::SpectrumAnalyzer;
+
+use super::{DataWorker, EventKind, EventLog, TraceData};
+use crate::settings::TraceLayerKind;
+
+/// The current trace of a compared spectrum analyzer, ready to be plotted.
+#[derive(Debug, Clone)]
+pub struct ComparedTrace {
+    pub name: String,
+    pub color: Color32,
+    pub trace_data: TraceData,
+}
+
+impl ComparedTrace {
+    /// Whether the trace covers the same frequencies as `trace_data`, so they can share an axis.
+    pub fn has_same_span(&self, trace_data: &TraceData) -> bool {
+        self.trace_data.freq_range() == trace_data.freq_range()
+    }
+}
+
+/// A spectrum analyzer whose trace is compared with the main RF Explorer's, like one measuring
+/// the spectrum after a filter.
+pub struct ComparedDevice {
+    rfe: SpectrumAnalyzer,
+    trace_data: Arc<Mutex<TraceData>>,
+    is_connected: bool,
+    pub is_visible: bool,
+    pub color: Color32,
+}
+
+impl ComparedDevice {
+    fn new(rfe: SpectrumAnalyzer, color: Color32, ctx: &Context) -> Self {
+        let trace_data = Arc::new(Mutex::new(TraceData::default()));
+        let sweep_trace_data = trace_data.clone();
+        let ctx = ctx.clone();
+        rfe.set_sweep_callback(move |amps, start_freq, stop_freq| {
+            // Only the live trace of compared spectrum analyzers is shown
+            sweep_trace_data.lock().unwrap().update(
+                amps,
+                start_freq,
+                stop_freq,
+                &[TraceLayerKind::Live],
+            );
+            ctx.request_repaint();
+        });
+
+        Self {
+            rfe,
+            trace_data,
+            is_connected: true,
+            is_visible: true,
+            color,
+        }
+    }
+
+    /// The name of the serial port the spectrum analyzer is connected through.
+    pub fn port_name(&self) -> &str {
+        self.rfe.port_name()
+    }
+
+    /// Whether the spectrum analyzer was still connected the last time it was checked.
+    ///
+    /// The last trace received from a disconnected spectrum analyzer is kept so it can still be
+    /// compared.
+    pub fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+
+    /// Gets a copy of the traces that's small enough to plot every frame.
+    fn trace_data(&self) -> TraceData {
+        self.trace_data
+            .lock()
+            .unwrap()
+            .decimated(DataWorker::MAX_TRACE_POINTS)
+    }
+}
+
+impl Drop for ComparedDevice {
+    fn drop(&mut self) {
+        self.rfe.remove_sweep_callback();
+    }
+}
+
+/// A newly connected spectrum analyzer and the key it's stored under.
+type KeyedDevice = (Arc<str>, SpectrumAnalyzer);
+
+/// The spectrum analyzers compared with the main RF Explorer, keyed by serial number.
+///
+/// Spectrum analyzers that don't report a serial number are keyed by their port name instead.
+/// Each one has its own trace data, so one disconnecting doesn't affect the others.
+#[derive(Default)]
+pub struct ComparedDevices {
+    devices: BTreeMap<Arc<str>, ComparedDevice>,
+    /// The spectrum analyzers being connected to on a background thread.
+    connecting: Option<Receiver<Vec<KeyedDevice>>>,
+}
+
+impl ComparedDevices {
+    /// The colors given to the compared traces in the order they're connected.
+    const COLORS: [Color32; 4] = [
+        Color32::from_rgb(255, 133, 27),
+        Color32::from_rgb(177, 13, 201),
+        Color32::from_rgb(57, 204, 204),
+        Color32::from_rgb(240, 18, 190),
+    ];
+
+    /// Connects to every spectrum analyzer that isn't already connected on a background thread.
+    ///
+    /// The spectrum analyzers are added the next time [`ComparedDevices::update`] is called after
+    /// they've connected.
+    pub fn connect_all(&mut self) {
+        if self.is_connecting() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let devices = SpectrumAnalyzer::connect_all()
+                .into_iter()
+                .map(|rfe| {
+                    let key = rfe
+                        .serial_number()
+                        .unwrap_or_else(|| Arc::from(rfe.port_name()));
+                    (key, rfe)
+                })
+                .collect();
+            _ = sender.send(devices);
+        });
+        self.connecting = Some(receiver);
+    }
+
+    /// Whether spectrum analyzers are being connected to.
+    pub fn is_connecting(&self) -> bool {
+        self.connecting.is_some()
+    }
+
+    /// Adds newly connected spectrum analyzers and logs the ones that have disconnected.
+    ///
+    /// This never blocks, so it can be called every frame.
+    pub fn update(&mut self, ctx: &Context, event_log: &EventLog) {
+        if let Some(connecting) = &self.connecting {
+            match connecting.try_recv() {
+                Ok(devices) => {
+                    if devices.is_empty() {
+                        event_log.push(
+                            EventKind::Connection,
+                            "No other RF Explorers were found to compare",
+                        );
+                    }
+                    for (key, rfe) in devices {
+                        event_log.push(
+                            EventKind::Connection,
+                            format!(
+                                "Connected to an RF Explorer on {} to compare",
+                                rfe.port_name()
+                            ),
+                        );
+                        let color = Self::COLORS[self.devices.len() % Self::COLORS.len()];
+                        // A device that reconnected replaces its old, disconnected entry
+                        self.devices
+                            .insert(key, ComparedDevice::new(rfe, color, ctx));
+                    }
+                    self.connecting = None;
+                }
+                Err(TryRecvError::Empty) => (),
+                Err(TryRecvError::Disconnected) => self.connecting = None,
+            }
+        }
+
+        for device in self.devices.values_mut() {
+            // The reader thread stops after the first I/O error, which is how unplugging shows up
+            if device.is_connected && device.rfe.link_health().read_errors.io_errors > 0 {
+                device.is_connected = false;
+                event_log.push(
+                    EventKind::Connection,
+                    format!(
+                        "The compared RF Explorer on {} was disconnected",
+                        device.port_name()
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Stops comparing a spectrum analyzer and closes its port.
+    pub fn remove(&mut self, key: &str) {
+        self.devices.remove(key);
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Arc<str>, &mut ComparedDevice)> {
+        self.devices.iter_mut()
+    }
+
+    /// Gets the traces of the visible spectrum analyzers.
+    pub fn visible_traces(&self) -> Vec<ComparedTrace> {
+        self.devices
+            .iter()
+            .filter(|(_, device)| device.is_visible)
+            .map(|(key, device)| ComparedTrace {
+                name: if device.is_connected {
+                    key.to_string()
+                } else {
+                    format!("{key} (Disconnected)")
+                },
+                color: device.color,
+                trace_data: device.trace_data(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, TryRecvError},
+    },
+    thread,
+};
+
+use egui::Context;
+use rfe::{ConnectProgress, SpectrumAnalyzer};
+
+use super::{EventKind, EventLog};
+
+/// A connection to an RF Explorer being made on a background thread.
+pub struct ConnectAttempt {
+    /// The latest step of connecting, which is shown while connecting.
+    progress: Arc<Mutex<Option<ConnectProgress>>>,
+    result: Receiver<Option<SpectrumAnalyzer>>,
+}
+
+impl ConnectAttempt {
+    /// Starts connecting to the first RF Explorer that responds.
+    ///
+    /// Ports that don't respond are logged as they fail.
+    pub fn start(ctx: &Context, event_log: &EventLog) -> Self {
+        let progress = Arc::new(Mutex::new(None));
+        let (sender, receiver) = mpsc::channel();
+        let observer_progress = progress.clone();
+        let ctx = ctx.clone();
+        let event_log = event_log.clone();
+        thread::spawn(move || {
+            let observer_ctx = ctx.clone();
+            let rfe = SpectrumAnalyzer::connect_with_progress(move |progress| {
+                if let ConnectProgress::Failed { port, error } = &progress {
+                    event_log.push(
+                        EventKind::Connection,
+                        format!("No RF Explorer responded on {port}: {error}"),
+                    );
+                }
+                *observer_progress.lock().unwrap() = Some(progress);
+                observer_ctx.request_repaint();
+            });
+            _ = sender.send(rfe);
+            ctx.request_repaint();
+        });
+
+        Self {
+            progress,
+            result: receiver,
+        }
+    }
+
+    /// Describes the latest step of connecting.
+    pub fn status(&self) -> String {
+        match &*self.progress.lock().unwrap() {
+            None => "Looking for RF Explorers...".to_string(),
+            Some(ConnectProgress::ScanningPorts { found: 1 }) => "Found 1 serial port".to_string(),
+            Some(ConnectProgress::ScanningPorts { found }) => format!("Found {found} serial ports"),
+            Some(ConnectProgress::Probing { port }) => format!("Probing {port}..."),
+            Some(ConnectProgress::WaitingForConfig { port, elapsed }) => format!(
+                "Waiting for the RF Explorer on {port} to respond ({:.1} s)",
+                elapsed.as_secs_f32()
+            ),
+            Some(ConnectProgress::Connected { port }) => format!("Connected on {port}"),
+            Some(ConnectProgress::Failed { port, .. }) => {
+                format!("No RF Explorer responded on {port}")
+            }
+        }
+    }
+
+    /// Returns the RF Explorer that was connected to, or `None` if none was found, once
+    /// connecting has finished.
+    ///
+    /// This never blocks, so it can be called every frame.
+    pub fn try_finish(&self) -> Option<Option<SpectrumAnalyzer>> {
+        match self.result.try_recv() {
+            Ok(rfe) => Some(rfe),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(None),
+        }
+    }
+}
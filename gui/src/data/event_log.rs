@@ -0,0 +1,126 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+use strum::Display;
+
+/// The kinds of events shown in the event log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum EventKind {
+    /// An operation on the RF Explorer failed.
+    Error,
+    /// The RF Explorer was connected or disconnected.
+    Connection,
+    /// A recording was started, stopped, or opened for playback.
+    Recording,
+    /// A file finished exporting in the background.
+    Export,
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub timestamp: DateTime<Local>,
+    pub kind: EventKind,
+    pub message: String,
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}",
+            self.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+            self.kind,
+            self.message
+        )
+    }
+}
+
+/// A log of errors and connection changes that can be shared with the threads talking to the
+/// RF Explorer.
+///
+/// Cloning the log is cheap because every clone shares the same events.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    events: Arc<Mutex<Vec<Event>>>,
+}
+
+impl EventLog {
+    /// The maximum number of events kept before the oldest are dropped.
+    const MAX_EVENTS: usize = 1000;
+
+    /// How long events are shown as toasts.
+    pub const TOAST_DURATION: Duration = Duration::from_secs(5);
+
+    /// Adds an event to the log.
+    pub fn push(&self, kind: EventKind, message: impl Into<String>) {
+        let event = Event {
+            timestamp: Local::now(),
+            kind,
+            message: message.into(),
+        };
+        match kind {
+            EventKind::Error => tracing::error!("{}", event.message),
+            EventKind::Connection | EventKind::Recording | EventKind::Export => {
+                tracing::info!("{}", event.message)
+            }
+        }
+
+        let mut events = self.events.lock().unwrap();
+        if events.len() >= Self::MAX_EVENTS {
+            events.remove(0);
+        }
+        events.push(event);
+    }
+
+    /// Adds an error event to the log if the result is an error.
+    pub fn log_result<T, E: Display>(&self, context: &str, result: Result<T, E>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.push(EventKind::Error, format!("{context}: {error}"));
+                None
+            }
+        }
+    }
+
+    /// Gets the events in the log, oldest first.
+    pub fn events(&self) -> MutexGuard<'_, Vec<Event>> {
+        self.events.lock().unwrap()
+    }
+
+    /// Gets the events that are recent enough to be shown as toasts.
+    pub fn toasts(&self) -> Vec<Event> {
+        let now = Local::now();
+        self.events()
+            .iter()
+            .rev()
+            .take_while(|event| {
+                (now - event.timestamp)
+                    .to_std()
+                    .is_ok_and(|age| age < Self::TOAST_DURATION)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Removes every event from the log.
+    pub fn clear(&self) {
+        self.events().clear();
+    }
+
+    /// Writes every event in the log to a text file, one per line.
+    pub fn export(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for event in self.events().iter() {
+            writeln!(writer, "{event}")?;
+        }
+        writer.flush()
+    }
+}
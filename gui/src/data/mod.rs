@@ -1,7 +1,17 @@
+mod compared_devices;
+mod connect_attempt;
+mod event_log;
+mod playback;
 mod rfe_info;
 mod spectrogram_data;
 mod trace_data;
+mod worker;
 
+pub use compared_devices::{ComparedDevices, ComparedTrace};
+pub use connect_attempt::ConnectAttempt;
+pub use event_log::{EventKind, EventLog};
+pub use playback::Playback;
 pub use rfe_info::RfeInfo;
 pub use spectrogram_data::SpectrogramData;
 pub use trace_data::TraceData;
+pub use worker::{DataSnapshot, DataWorker, SharedData, SpectrogramExportFormat};
@@ -0,0 +1,189 @@
+use std::{
+    io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use rfe::spectrum_analyzer::{Record, read_csv_dir};
+
+use super::{DataWorker, SpectrogramData};
+
+/// Plays back a recording made with a `CsvDirSink` by sending its records to a [`DataWorker`] at
+/// the times they were recorded.
+pub struct Playback {
+    name: String,
+    /// The records and how long after the start of the recording each one was received.
+    records: Vec<(Duration, Record)>,
+    duration: Duration,
+    position: Duration,
+    /// The index of the first record that hasn't been sent to the worker yet.
+    next_record: usize,
+    is_playing: bool,
+    is_scrubbing: bool,
+    needs_seek: bool,
+    speed: f32,
+    last_update: Option<Instant>,
+}
+
+impl Playback {
+    /// The playback speeds that can be picked.
+    pub const SPEEDS: [f32; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+    /// The maximum number of sweeps replayed after seeking, which is enough to fill the
+    /// spectrogram.
+    const SEEK_SWEEPS: usize = SpectrogramData::HEIGHT;
+
+    /// Loads the recording in a directory written by a `CsvDirSink`.
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let records = read_csv_dir(dir)?;
+        if !records
+            .iter()
+            .any(|record| matches!(record, Record::Sweep { .. }))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The recording doesn't contain any sweeps",
+            ));
+        }
+
+        let start = records[0].timestamp();
+        let records: Vec<(Duration, Record)> = records
+            .into_iter()
+            .map(|record| {
+                (
+                    (record.timestamp() - start).to_std().unwrap_or_default(),
+                    record,
+                )
+            })
+            .collect();
+        let duration = records
+            .last()
+            .map(|(offset, _)| *offset)
+            .unwrap_or_default();
+        Ok(Self {
+            name: dir.file_name().map_or_else(
+                || dir.display().to_string(),
+                |name| name.to_string_lossy().into(),
+            ),
+            records,
+            duration,
+            position: Duration::ZERO,
+            next_record: 0,
+            is_playing: true,
+            is_scrubbing: false,
+            needs_seek: false,
+            speed: 1.0,
+            last_update: None,
+        })
+    }
+
+    /// The name of the recording's directory.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// How long the recording is.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// How far into the recording playback is.
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.is_playing
+    }
+
+    /// Plays or pauses the recording, restarting it if it has finished.
+    pub fn toggle_playing(&mut self) {
+        if !self.is_playing && self.position >= self.duration {
+            self.seek(Duration::ZERO);
+        }
+        self.is_playing = !self.is_playing;
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Jumps to a different time in the recording.
+    pub fn seek(&mut self, position: Duration) {
+        self.position = position.min(self.duration);
+        self.needs_seek = true;
+    }
+
+    /// Sets whether the timeline is being dragged, which holds off replaying sweeps until it's
+    /// let go.
+    pub fn set_scrubbing(&mut self, is_scrubbing: bool) {
+        self.is_scrubbing = is_scrubbing;
+    }
+
+    /// Advances playback and sends the worker every record that's due.
+    ///
+    /// This never blocks, so it can be called every frame.
+    pub fn update(&mut self, data_worker: &DataWorker) {
+        let now = Instant::now();
+        if self.is_playing
+            && !self.is_scrubbing
+            && let Some(last_update) = self.last_update
+        {
+            self.position =
+                (self.position + (now - last_update).mul_f32(self.speed)).min(self.duration);
+            if self.position >= self.duration {
+                self.is_playing = false;
+            }
+        }
+        self.last_update = Some(now);
+
+        if self.is_scrubbing {
+            return;
+        }
+        if self.needs_seek {
+            self.replay_up_to_position(data_worker);
+            self.needs_seek = false;
+            return;
+        }
+        while let Some((offset, record)) = self.records.get(self.next_record)
+            && *offset <= self.position
+        {
+            data_worker.play_record(record);
+            self.next_record += 1;
+        }
+    }
+
+    /// Clears the worker and sends it the config and latest sweeps at the current position, so the
+    /// plot looks like it did at that time.
+    fn replay_up_to_position(&mut self, data_worker: &DataWorker) {
+        let end = self
+            .records
+            .partition_point(|(offset, _)| *offset <= self.position);
+        let config = self.records[..end]
+            .iter()
+            .rposition(|(_, record)| matches!(record, Record::Config { .. }));
+        // Sweeps from before the latest config were measured with different settings
+        let first_sweep = config.map_or(0, |config| config + 1);
+
+        data_worker.reset();
+        if let Some(config) = config {
+            data_worker.play_record(&self.records[config].1);
+        }
+        for (_, record) in self.records[first_sweep..end]
+            .iter()
+            .filter(|(_, record)| matches!(record, Record::Sweep { .. }))
+            .rev()
+            .take(Self::SEEK_SWEEPS)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+        {
+            data_worker.play_record(record);
+        }
+        self.next_record = end;
+    }
+}
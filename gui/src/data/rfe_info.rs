@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use rfe::{
     Frequency, SpectrumAnalyzer,
     spectrum_analyzer::{CalcMode, Config, DspMode, InputStage, Model},
@@ -12,6 +14,10 @@ pub struct RfeInfo {
     pub max_freq: Frequency,
     /// The maximum supported frequency span of an RF Explorer.
     pub max_span: Frequency,
+    /// The amplitude at the bottom of an RF Explorer's screen.
+    pub min_amp_dbm: i16,
+    /// The amplitude at the top of an RF Explorer's screen, which is its reference level.
+    pub max_amp_dbm: i16,
     /// The model of an RF Explorer's active radio.
     pub active_radio_model: Model,
     /// The model of an RF Explorer's inactive radio.
@@ -21,9 +27,9 @@ pub struct RfeInfo {
     pub dsp_mode: Option<DspMode>,
     pub port_name: String,
     /// The firmware version of an RF Explorer.
-    pub firmware_version: String,
+    pub firmware_version: Arc<str>,
     /// The serial number of an RF Explorer.
-    pub serial_number: Option<String>,
+    pub serial_number: Option<Arc<str>>,
     is_expansion_radio_active: bool,
 }
 
@@ -33,6 +39,8 @@ impl RfeInfo {
             min_freq: rfe.min_freq(),
             max_freq: rfe.max_freq(),
             max_span: rfe.max_span(),
+            min_amp_dbm: rfe.min_amp_dbm(),
+            max_amp_dbm: rfe.max_amp_dbm(),
             active_radio_model: rfe.active_radio_model(),
             inactive_radio_model: rfe.inactive_radio_model(),
             calc_mode: rfe.calc_mode(),
@@ -50,6 +58,8 @@ impl RfeInfo {
         self.min_freq = config.min_freq;
         self.max_freq = config.max_freq;
         self.max_span = config.max_span;
+        self.min_amp_dbm = config.min_amp_dbm;
+        self.max_amp_dbm = config.max_amp_dbm;
         self.calc_mode = config.calc_mode;
 
         // Swap the active and inactive radio models if the status of the expansion radio module has changed
@@ -62,4 +72,10 @@ impl RfeInfo {
             self.is_expansion_radio_active = config.is_expansion_radio_module_active;
         }
     }
+
+    /// Updates the values that the RF Explorer reports outside of its config.
+    pub fn update_from_rfe(&mut self, rfe: &SpectrumAnalyzer) {
+        self.dsp_mode = rfe.dsp_mode();
+        self.input_stage = rfe.input_stage();
+    }
 }
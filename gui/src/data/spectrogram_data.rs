@@ -1,16 +1,25 @@
-use std::sync::Arc;
-
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use csv::Writer;
 use egui::{Color32, ColorImage, Context, ImageData, TextureHandle, TextureOptions};
+use image::{ExtendedColorType, ImageEncoder, ImageResult, codecs::png::PngEncoder};
 use rfe::Frequency;
 use ringbuffer::{AllocRingBuffer, RingBuffer};
 
 use crate::settings::SpectrogramSettings;
 
-/// The image data and sweep history needed to display a spectrogram.
+/// The texture and frequency range needed to display a spectrogram.
+///
+/// Cloning the data is cheap because the texture is reference counted.
+#[derive(Clone)]
 pub struct SpectrogramData {
     texture: TextureHandle,
-    image: ColorImage,
-    sweep_history: AllocRingBuffer<Vec<f32>>,
     start_freq: Frequency,
     stop_freq: Frequency,
 }
@@ -18,17 +27,49 @@ pub struct SpectrogramData {
 impl SpectrogramData {
     pub const HEIGHT: usize = 100;
 
+    /// Gets the start frequency of the spectrogram data.
+    pub fn start_freq(&self) -> Frequency {
+        self.start_freq
+    }
+
+    /// Gets the stop frequency of the spectrogram data.
+    pub fn stop_freq(&self) -> Frequency {
+        self.stop_freq
+    }
+
+    /// Gets a reference to the spectrogram's texture.
+    pub fn texture(&self) -> &TextureHandle {
+        &self.texture
+    }
+}
+
+/// Builds the spectrogram's image from new sweeps and keeps the sweep history needed to
+/// recreate or export it.
+pub struct SpectrogramBuilder {
+    data: SpectrogramData,
+    image: ColorImage,
+    sweep_history: AllocRingBuffer<(SystemTime, Vec<f32>)>,
+}
+
+impl SpectrogramBuilder {
     pub fn new(ctx: &Context) -> Self {
         let image = ColorImage::new([1, 1], vec![Color32::TRANSPARENT; 1]);
         Self {
-            texture: ctx.load_texture("spectrogram", image.clone(), TextureOptions::default()),
+            data: SpectrogramData {
+                texture: ctx.load_texture("spectrogram", image.clone(), TextureOptions::default()),
+                start_freq: Frequency::default(),
+                stop_freq: Frequency::default(),
+            },
             image,
-            sweep_history: AllocRingBuffer::new(Self::HEIGHT),
-            start_freq: Frequency::default(),
-            stop_freq: Frequency::default(),
+            sweep_history: AllocRingBuffer::new(SpectrogramData::HEIGHT),
         }
     }
 
+    /// Gets the data needed to display the spectrogram.
+    pub fn data(&self) -> &SpectrogramData {
+        &self.data
+    }
+
     /// Updates the spectrogram data by adding a new sweep.
     pub fn update(
         &mut self,
@@ -39,8 +80,8 @@ impl SpectrogramData {
     ) {
         // If the sweep's parameters have changed then reset the data
         if self.image.width() != sweep_amps.len()
-            || self.start_freq != start_freq
-            || self.stop_freq != stop_freq
+            || self.data.start_freq != start_freq
+            || self.data.stop_freq != stop_freq
         {
             self.reset_data(start_freq, stop_freq, sweep_amps.len());
         }
@@ -60,10 +101,11 @@ impl SpectrogramData {
         }
 
         // Save the sweep in case we need to recreate the image later
-        self.sweep_history.enqueue(sweep_amps.to_vec());
+        self.sweep_history
+            .enqueue((SystemTime::now(), sweep_amps.to_vec()));
 
         // Set the updated image to the spectrogram texture
-        self.texture.set(
+        self.data.texture.set(
             ImageData::Color(Arc::new(self.image.clone())),
             TextureOptions::default(),
         );
@@ -71,43 +113,105 @@ impl SpectrogramData {
 
     fn reset_data(&mut self, start_freq: Frequency, stop_freq: Frequency, sweep_len: usize) {
         self.image = ColorImage::new(
-            [sweep_len, Self::HEIGHT],
-            vec![Color32::TRANSPARENT; sweep_len * Self::HEIGHT],
+            [sweep_len, SpectrogramData::HEIGHT],
+            vec![Color32::TRANSPARENT; sweep_len * SpectrogramData::HEIGHT],
         );
         self.sweep_history.clear();
-        self.start_freq = start_freq;
-        self.stop_freq = stop_freq;
-    }
-
-    /// Gets the start frequency of the spectrogram data.
-    pub fn start_freq(&self) -> Frequency {
-        self.start_freq
-    }
-
-    /// Gets the stop frequency of the spectrogram data.
-    pub fn stop_freq(&self) -> Frequency {
-        self.stop_freq
+        self.data.start_freq = start_freq;
+        self.data.stop_freq = stop_freq;
     }
 
-    /// Gets a reference to the spectrogram's texture.
-    pub fn texture(&self) -> &TextureHandle {
-        &self.texture
+    /// Removes every sweep from the spectrogram.
+    pub fn clear(&mut self) {
+        self.image = ColorImage::new([1, 1], vec![Color32::TRANSPARENT; 1]);
+        self.sweep_history.clear();
+        self.data.start_freq = Frequency::default();
+        self.data.stop_freq = Frequency::default();
+        self.data.texture.set(
+            ImageData::Color(Arc::new(self.image.clone())),
+            TextureOptions::default(),
+        );
     }
 
     /// Recreates the spectrogram's image using a saved history of sweeps.
     pub fn recreate_image(&mut self, spectrogram_settings: &SpectrogramSettings) {
         // Recalculate the color of each pixel in the image using the sweep history
         let image_width = self.image.width();
-        for (row, sweep) in self.sweep_history.iter().enumerate() {
+        for (row, (_, sweep)) in self.sweep_history.iter().enumerate() {
             for (i, amp) in sweep.iter().map(|amp| f64::from(*amp)).enumerate() {
                 self.image.pixels[row * image_width + i] = spectrogram_settings.amp_to_color(amp);
             }
         }
 
         // Set the updated image to the spectrogram texture
-        self.texture.set(
+        self.data.texture.set(
             ImageData::Color(Arc::new(self.image.clone())),
             TextureOptions::default(),
         );
     }
+
+    /// Writes the sweep history to a CSV file.
+    ///
+    /// The first row contains the frequency of each bin in Hz and each following row contains a
+    /// sweep, oldest first, beginning with the number of seconds since the Unix epoch when it was
+    /// received.
+    pub fn export_csv(&self, path: impl AsRef<Path>) -> csv::Result<()> {
+        let mut writer = Writer::from_path(path)?;
+
+        writer.write_record(
+            std::iter::once("timestamp".to_string())
+                .chain(self.bin_freqs().map(|freq| freq.as_hz().to_string())),
+        )?;
+        for (timestamp, sweep) in self.sweep_history.iter() {
+            let secs = timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64();
+            writer.write_record(
+                std::iter::once(format!("{secs:.3}")).chain(sweep.iter().map(f32::to_string)),
+            )?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Renders the sweep history to a PNG heatmap with one pixel per bin and one row per sweep.
+    ///
+    /// The newest sweep is in the top row, just like the spectrogram in the plot.
+    pub fn export_png(
+        &self,
+        path: impl AsRef<Path>,
+        spectrogram_settings: &SpectrogramSettings,
+    ) -> ImageResult<()> {
+        let width = self.image.width();
+        let height = self.sweep_history.len();
+        let mut pixels = Vec::with_capacity(width * height * 3);
+        for (_, sweep) in self.sweep_history.iter().rev() {
+            for amp in sweep {
+                let color = spectrogram_settings.amp_to_color(f64::from(*amp));
+                pixels.extend_from_slice(&[color.r(), color.g(), color.b()]);
+            }
+        }
+
+        let writer = BufWriter::new(File::create(path)?);
+        PngEncoder::new(writer).write_image(
+            &pixels,
+            u32::try_from(width).unwrap_or(u32::MAX),
+            u32::try_from(height).unwrap_or(u32::MAX),
+            ExtendedColorType::Rgb8,
+        )
+    }
+
+    /// Gets the frequency of each bin in the spectrogram.
+    fn bin_freqs(&self) -> impl Iterator<Item = Frequency> {
+        let len = self.image.width();
+        let start_freq = self.data.start_freq;
+        let step_size = if len > 1 {
+            (self.data.stop_freq - start_freq) / u64::try_from(len - 1).unwrap_or(1)
+        } else {
+            Frequency::default()
+        };
+        (0..len).map(move |i| start_freq + step_size * i as u64)
+    }
 }
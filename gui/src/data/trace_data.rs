@@ -1,12 +1,20 @@
 use rfe::Frequency;
 
-/// The current, average, and max traces measured by the RF Explorer.
-#[derive(Debug, Clone)]
+use crate::settings::TraceLayerKind;
+
+/// The traces computed from the sweeps measured by the RF Explorer.
+///
+/// Only the layers that are shown are computed. The live trace is always kept because the marker
+/// and the baseline are read from it.
+#[derive(Debug, Clone, Default)]
 pub struct TraceData {
     current: Vec<(Frequency, f64)>,
     average: Vec<(Frequency, f64)>,
     max: Vec<(Frequency, f64)>,
-    is_first_trace: bool,
+    min: Vec<(Frequency, f64)>,
+    baseline_delta: Vec<(Frequency, f64)>,
+    /// The amplitudes of the sweep the baseline delta is measured against.
+    baseline: Vec<f64>,
     start_freq: Frequency,
     stop_freq: Frequency,
     step_size: Frequency,
@@ -15,8 +23,16 @@ pub struct TraceData {
 impl TraceData {
     const AVERAGE_ITERATIONS: f64 = 5.0;
 
-    /// Updates the current, average, and max traces using a new sweep.
-    pub fn update(&mut self, amps_dbm: &[f32], start_freq: Frequency, stop_freq: Frequency) {
+    /// Updates the live trace and the visible layers using a new sweep.
+    ///
+    /// Hidden layers are cleared, so they start over from the next sweep once they're shown.
+    pub fn update(
+        &mut self,
+        amps_dbm: &[f32],
+        start_freq: Frequency,
+        stop_freq: Frequency,
+        visible_layers: &[TraceLayerKind],
+    ) {
         // If the sweep's parameters have changed then reset the data
         if self.current.len() != amps_dbm.len()
             || self.start_freq != start_freq
@@ -25,22 +41,46 @@ impl TraceData {
             self.reset_data(start_freq, stop_freq, amps_dbm.len());
         }
 
-        for (i, amp_dbm) in amps_dbm.iter().enumerate() {
-            self.current[i].1 = f64::from(*amp_dbm);
+        for (point, amp_dbm) in self.current.iter_mut().zip(amps_dbm) {
+            point.1 = f64::from(*amp_dbm);
+        }
 
-            // If this is the first trace, set the average trace to be the same as the new trace
-            // Otherwise, calculate a new average trace using the old average trace and the new trace
-            if self.is_first_trace {
-                self.average[i].1 = f64::from(*amp_dbm);
+        let is_visible = |kind| visible_layers.contains(&kind);
+        if is_visible(TraceLayerKind::Average) {
+            if self.average.is_empty() {
+                self.average = self.current.clone();
             } else {
-                self.average[i].1 -= self.average[i].1 / Self::AVERAGE_ITERATIONS;
-                self.average[i].1 += f64::from(*amp_dbm) / Self::AVERAGE_ITERATIONS;
+                for (average, (_, amp)) in self.average.iter_mut().zip(&self.current) {
+                    average.1 -= average.1 / Self::AVERAGE_ITERATIONS;
+                    average.1 += amp / Self::AVERAGE_ITERATIONS;
+                }
             }
+        } else {
+            self.average.clear();
+        }
+
+        if is_visible(TraceLayerKind::MaxHold) {
+            hold(&mut self.max, &self.current, f64::max);
+        } else {
+            self.max.clear();
+        }
 
-            self.max[i].1 = self.max[i].1.max(f64::from(*amp_dbm));
+        if is_visible(TraceLayerKind::MinHold) {
+            hold(&mut self.min, &self.current, f64::min);
+        } else {
+            self.min.clear();
         }
 
-        self.is_first_trace = false;
+        if is_visible(TraceLayerKind::BaselineDelta) && !self.baseline.is_empty() {
+            self.baseline_delta = self
+                .current
+                .iter()
+                .zip(&self.baseline)
+                .map(|((freq, amp), baseline)| (*freq, amp - baseline))
+                .collect();
+        } else {
+            self.baseline_delta.clear();
+        }
     }
 
     fn reset_data(&mut self, start_freq: Frequency, stop_freq: Frequency, len: usize) {
@@ -49,45 +89,89 @@ impl TraceData {
         } else {
             Frequency::default()
         };
-        let mut points = Vec::new();
-        for i in 0..u64::try_from(len).unwrap_or_default() {
-            points.push((start_freq + step_size * i, f64::MIN));
-        }
-        self.current = points.clone();
-        self.average = points.clone();
-        self.max = points;
-        self.is_first_trace = true;
+        self.current = (0..u64::try_from(len).unwrap_or_default())
+            .map(|i| (start_freq + step_size * i, f64::MIN))
+            .collect();
+        self.average.clear();
+        self.max.clear();
+        self.min.clear();
+        self.baseline_delta.clear();
+        // A baseline measured over other frequencies can't be compared with the new sweeps
+        self.baseline.clear();
         self.start_freq = start_freq;
         self.stop_freq = stop_freq;
         self.step_size = step_size;
     }
 
+    /// Measures the baseline delta against the live trace from now on.
+    pub fn set_baseline(&mut self) {
+        self.baseline = self.current.iter().map(|(_, amp)| *amp).collect();
+    }
+
     /// Gets the current trace.
     pub fn current(&self) -> &[(Frequency, f64)] {
         &self.current
     }
 
-    /// Gets the average trace.
-    pub fn average(&self) -> &[(Frequency, f64)] {
-        &self.average
+    /// Gets one of the traces, which is empty if it isn't being computed.
+    pub fn layer(&self, kind: TraceLayerKind) -> &[(Frequency, f64)] {
+        match kind {
+            TraceLayerKind::Live => &self.current,
+            TraceLayerKind::MaxHold => &self.max,
+            TraceLayerKind::MinHold => &self.min,
+            TraceLayerKind::Average => &self.average,
+            TraceLayerKind::BaselineDelta => &self.baseline_delta,
+        }
     }
 
-    /// Gets the max trace.
-    pub fn max(&self) -> &[(Frequency, f64)] {
-        &self.max
+    /// Gets the start and stop frequencies of the traces.
+    pub fn freq_range(&self) -> (Frequency, Frequency) {
+        (self.start_freq, self.stop_freq)
     }
-}
 
-impl Default for TraceData {
-    fn default() -> Self {
-        Self {
-            current: Vec::default(),
-            average: Vec::default(),
-            max: Vec::default(),
-            is_first_trace: true,
-            start_freq: Frequency::default(),
-            stop_freq: Frequency::default(),
-            step_size: Frequency::default(),
+    /// Returns a copy of the traces that contains at most `max_points` points per trace.
+    ///
+    /// Neighboring points are grouped together and only the highest point in each group is kept
+    /// so that narrow peaks remain visible. The min hold trace keeps the lowest point instead.
+    pub fn decimated(&self, max_points: usize) -> TraceData {
+        if self.current.len() <= max_points {
+            return self.clone();
+        }
+
+        TraceData {
+            current: decimate(&self.current, max_points, f64::total_cmp),
+            average: decimate(&self.average, max_points, f64::total_cmp),
+            max: decimate(&self.max, max_points, f64::total_cmp),
+            min: decimate(&self.min, max_points, |a, b| b.total_cmp(a)),
+            baseline_delta: decimate(&self.baseline_delta, max_points, f64::total_cmp),
+            baseline: self.baseline.clone(),
+            start_freq: self.start_freq,
+            stop_freq: self.stop_freq,
+            step_size: self.step_size,
         }
     }
 }
+
+/// Keeps the point picked by `pick` from each hold point and the matching live point.
+fn hold(held: &mut Vec<(Frequency, f64)>, current: &[(Frequency, f64)], pick: fn(f64, f64) -> f64) {
+    if held.is_empty() {
+        held.extend_from_slice(current);
+        return;
+    }
+    for (held, (_, amp)) in held.iter_mut().zip(current) {
+        held.1 = pick(held.1, *amp);
+    }
+}
+
+/// Keeps the greatest point in each group according to `compare`.
+fn decimate(
+    trace: &[(Frequency, f64)],
+    max_points: usize,
+    compare: fn(&f64, &f64) -> std::cmp::Ordering,
+) -> Vec<(Frequency, f64)> {
+    let group_len = trace.len().div_ceil(max_points.max(1));
+    trace
+        .chunks(group_len)
+        .filter_map(|group| group.iter().copied().max_by(|a, b| compare(&a.1, &b.1)))
+        .collect()
+}
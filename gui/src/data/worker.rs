@@ -0,0 +1,399 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use egui::Context;
+use rfd::FileDialog;
+use rfe::{
+    Frequency, SpectrumAnalyzer,
+    spectrum_analyzer::{Config, Record},
+};
+
+use super::{EventLog, RfeInfo, SpectrogramData, TraceData, spectrogram_data::SpectrogramBuilder};
+use crate::settings::{SpectrogramSettings, SweepSettings, TraceLayerKind};
+
+/// The ready-to-render trace and spectrogram data produced by the worker.
+#[derive(Clone)]
+pub struct DataSnapshot {
+    pub trace_data: TraceData,
+    pub spectrogram_data: SpectrogramData,
+}
+
+/// The settings and data shared between the UI and the worker.
+pub struct SharedData {
+    pub trace_data: Arc<Mutex<TraceData>>,
+    /// The traces that are shown, which are the only ones the worker computes.
+    pub visible_trace_layers: Arc<Mutex<Vec<TraceLayerKind>>>,
+    pub rfe_info: Arc<Mutex<RfeInfo>>,
+    pub sweep_settings: Arc<Mutex<SweepSettings>>,
+    pub spectrogram_settings: Arc<Mutex<SpectrogramSettings>>,
+    pub pause_sweeps: Arc<AtomicBool>,
+    pub event_log: EventLog,
+}
+
+/// The file formats the spectrogram can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectrogramExportFormat {
+    Csv,
+    Png,
+}
+
+impl SpectrogramExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Png => "png",
+        }
+    }
+}
+
+enum WorkerMessage {
+    Sweep {
+        amps: Vec<f32>,
+        start_freq: Frequency,
+        stop_freq: Frequency,
+    },
+    Config(Config),
+    Reset,
+    RecreateSpectrogram,
+    ExportSpectrogram {
+        path: PathBuf,
+        format: SpectrogramExportFormat,
+    },
+    Shutdown,
+}
+
+/// Processes the RF Explorer's sweeps and configs on a background thread so the UI thread only
+/// has to draw the latest snapshot.
+///
+/// A worker without an RF Explorer plays back the records it's sent instead.
+pub struct DataWorker {
+    rfe: Option<Weak<Mutex<SpectrumAnalyzer>>>,
+    messages: Sender<WorkerMessage>,
+    snapshots: Receiver<DataSnapshot>,
+    dropped_snapshots: Arc<AtomicUsize>,
+    snapshot: DataSnapshot,
+    frames_behind: usize,
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl DataWorker {
+    /// The number of snapshots that can be waiting for the UI before new ones are dropped.
+    const SNAPSHOT_CAPACITY: usize = 4;
+
+    /// The maximum number of points in each trace sent to the UI.
+    pub(crate) const MAX_TRACE_POINTS: usize = 4096;
+
+    /// How often the worker checks whether the RF Explorer has been dropped.
+    const DISCONNECT_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Starts the worker and registers the RF Explorer callbacks that feed it.
+    pub fn spawn(rfe: &Arc<Mutex<SpectrumAnalyzer>>, ctx: &Context, shared: SharedData) -> Self {
+        let (message_sender, message_receiver) = mpsc::channel();
+        {
+            let rfe = rfe.lock().unwrap();
+
+            let sender = message_sender.clone();
+            rfe.set_config_callback(move |config: Config| {
+                _ = sender.send(WorkerMessage::Config(config));
+            });
+
+            // Copy the sweep out of the callback so the RF Explorer's reader thread isn't held up
+            // by the worker
+            let sender = message_sender.clone();
+            let pause_sweeps = shared.pause_sweeps.clone();
+            rfe.set_sweep_callback(move |amps, start_freq, stop_freq| {
+                if !pause_sweeps.load(Ordering::Relaxed) {
+                    _ = sender.send(WorkerMessage::Sweep {
+                        amps: amps.to_vec(),
+                        start_freq,
+                        stop_freq,
+                    });
+                }
+            });
+        }
+
+        Self::start(
+            Some(Arc::downgrade(rfe)),
+            message_sender,
+            message_receiver,
+            ctx,
+            shared,
+        )
+    }
+
+    /// Starts a worker that isn't fed by an RF Explorer, so recorded sweeps and configs can be
+    /// played back with [`DataWorker::play_record`].
+    pub fn spawn_playback(ctx: &Context, shared: SharedData) -> Self {
+        let (message_sender, message_receiver) = mpsc::channel();
+        Self::start(None, message_sender, message_receiver, ctx, shared)
+    }
+
+    fn start(
+        rfe: Option<Weak<Mutex<SpectrumAnalyzer>>>,
+        message_sender: Sender<WorkerMessage>,
+        message_receiver: Receiver<WorkerMessage>,
+        ctx: &Context,
+        shared: SharedData,
+    ) -> Self {
+        let (snapshot_sender, snapshot_receiver) = mpsc::sync_channel(Self::SNAPSHOT_CAPACITY);
+        let dropped_snapshots = Arc::new(AtomicUsize::new(0));
+        let spectrogram = SpectrogramBuilder::new(ctx);
+        let snapshot = DataSnapshot {
+            trace_data: TraceData::default(),
+            spectrogram_data: spectrogram.data().clone(),
+        };
+
+        let worker = Worker {
+            rfe: rfe.clone(),
+            config: None,
+            ctx: ctx.clone(),
+            shared,
+            spectrogram,
+            snapshots: snapshot_sender,
+            dropped_snapshots: dropped_snapshots.clone(),
+        };
+        let thread_handle = thread::spawn(move || worker.run(message_receiver));
+
+        Self {
+            rfe,
+            messages: message_sender,
+            snapshots: snapshot_receiver,
+            dropped_snapshots,
+            snapshot,
+            frames_behind: 0,
+            thread_handle: Some(thread_handle),
+        }
+    }
+
+    /// Drains the snapshots sent by the worker and keeps the latest one.
+    ///
+    /// This never blocks, so it can be called every frame.
+    pub fn receive_snapshots(&mut self) {
+        let mut received: usize = 0;
+        while let Ok(snapshot) = self.snapshots.try_recv() {
+            self.snapshot = snapshot;
+            received += 1;
+        }
+        self.frames_behind =
+            received.saturating_sub(1) + self.dropped_snapshots.swap(0, Ordering::Relaxed);
+    }
+
+    /// Gets the latest snapshot received from the worker.
+    pub fn snapshot(&self) -> &DataSnapshot {
+        &self.snapshot
+    }
+
+    /// Gets the number of snapshots that were skipped the last time snapshots were received.
+    pub fn frames_behind(&self) -> usize {
+        self.frames_behind
+    }
+
+    /// Sends a recorded config or sweep to the worker as if the RF Explorer had just sent it.
+    pub fn play_record(&self, record: &Record) {
+        let message = match record {
+            Record::Config { config, .. } => WorkerMessage::Config(config.clone()),
+            Record::Sweep {
+                start_freq,
+                stop_freq,
+                amplitudes_dbm,
+                ..
+            } => WorkerMessage::Sweep {
+                amps: amplitudes_dbm.clone(),
+                start_freq: *start_freq,
+                stop_freq: *stop_freq,
+            },
+            _ => return,
+        };
+        _ = self.messages.send(message);
+    }
+
+    /// Asks the worker to clear the traces and the spectrogram, like when playback jumps to a
+    /// different time.
+    pub fn reset(&self) {
+        _ = self.messages.send(WorkerMessage::Reset);
+    }
+
+    /// Asks the worker to recreate the spectrogram's image using the current spectrogram settings.
+    pub fn recreate_spectrogram(&self) {
+        _ = self.messages.send(WorkerMessage::RecreateSpectrogram);
+    }
+
+    /// Asks the user where to save the spectrogram and has the worker export it there.
+    ///
+    /// The worker writes the file straight from its sweep history so the history is never copied.
+    pub fn export_spectrogram(&self, format: SpectrogramExportFormat) {
+        // Open the save file dialog in a new thread so we don't block the UI thread from updating
+        let messages = self.messages.clone();
+        thread::spawn(move || {
+            let extension = format.extension();
+            let Some(path) = FileDialog::new()
+                .set_title("Export Spectrogram")
+                .add_filter(extension.to_uppercase(), &[extension])
+                .set_file_name(format!("spectrogram.{extension}"))
+                .save_file()
+            else {
+                return;
+            };
+            _ = messages.send(WorkerMessage::ExportSpectrogram { path, format });
+        });
+    }
+}
+
+impl Drop for DataWorker {
+    fn drop(&mut self) {
+        // Stop the RF Explorer from sending the worker new messages
+        if let Some(rfe) = self.rfe.as_ref().and_then(Weak::upgrade)
+            && let Ok(rfe) = rfe.lock()
+        {
+            rfe.remove_config_callback();
+            rfe.remove_sweep_callback();
+        }
+
+        _ = self.messages.send(WorkerMessage::Shutdown);
+        if let Some(thread_handle) = self.thread_handle.take() {
+            _ = thread_handle.join();
+        }
+    }
+}
+
+struct Worker {
+    rfe: Option<Weak<Mutex<SpectrumAnalyzer>>>,
+    /// The most recent config received from the RF Explorer.
+    config: Option<Config>,
+    ctx: Context,
+    shared: SharedData,
+    spectrogram: SpectrogramBuilder,
+    snapshots: SyncSender<DataSnapshot>,
+    dropped_snapshots: Arc<AtomicUsize>,
+}
+
+impl Worker {
+    fn run(mut self, messages: Receiver<WorkerMessage>) {
+        loop {
+            let message = match messages.recv_timeout(DataWorker::DISCONNECT_CHECK_INTERVAL) {
+                Ok(message) => message,
+                // Stop once the RF Explorer has been dropped because no more data will arrive
+                Err(RecvTimeoutError::Timeout)
+                    if self.rfe.as_ref().is_some_and(|rfe| rfe.strong_count() == 0) =>
+                {
+                    break;
+                }
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            match message {
+                WorkerMessage::Sweep {
+                    amps,
+                    start_freq,
+                    stop_freq,
+                } => {
+                    // Sweeps that were received before a retune don't belong to the current
+                    // frequency range, so plotting them would mix two different axes
+                    if !self.is_sweep_from_current_config(&amps, start_freq, stop_freq) {
+                        continue;
+                    }
+                    let visible_layers = self.shared.visible_trace_layers.lock().unwrap().clone();
+                    self.shared.trace_data.lock().unwrap().update(
+                        &amps,
+                        start_freq,
+                        stop_freq,
+                        &visible_layers,
+                    );
+                    let spectrogram_settings = *self.shared.spectrogram_settings.lock().unwrap();
+                    self.spectrogram
+                        .update(&amps, start_freq, stop_freq, &spectrogram_settings);
+                }
+                WorkerMessage::Config(config) => {
+                    self.shared.sweep_settings.lock().unwrap().update(&config);
+                    self.shared.rfe_info.lock().unwrap().update(&config);
+                    self.config = Some(config);
+                    self.ctx.request_repaint();
+                    continue;
+                }
+                WorkerMessage::Reset => {
+                    *self.shared.trace_data.lock().unwrap() = TraceData::default();
+                    self.spectrogram.clear();
+                    self.config = None;
+                }
+                WorkerMessage::RecreateSpectrogram => {
+                    let spectrogram_settings = *self.shared.spectrogram_settings.lock().unwrap();
+                    self.spectrogram.recreate_image(&spectrogram_settings);
+                }
+                WorkerMessage::ExportSpectrogram { path, format } => {
+                    self.export_spectrogram(&path, format);
+                    continue;
+                }
+                WorkerMessage::Shutdown => break,
+            }
+
+            if !self.send_snapshot() {
+                break;
+            }
+        }
+    }
+
+    /// Returns whether a sweep has the frequency range and length of the most recent config.
+    fn is_sweep_from_current_config(
+        &self,
+        amps: &[f32],
+        start_freq: Frequency,
+        stop_freq: Frequency,
+    ) -> bool {
+        self.config.as_ref().is_none_or(|config| {
+            config.start_freq == start_freq
+                && config.stop_freq == stop_freq
+                && usize::from(config.sweep_len) == amps.len()
+        })
+    }
+
+    fn export_spectrogram(&self, path: &Path, format: SpectrogramExportFormat) {
+        let result: Result<(), Box<dyn Error>> = match format {
+            SpectrogramExportFormat::Csv => self.spectrogram.export_csv(path).map_err(Into::into),
+            SpectrogramExportFormat::Png => {
+                let spectrogram_settings = *self.shared.spectrogram_settings.lock().unwrap();
+                self.spectrogram
+                    .export_png(path, &spectrogram_settings)
+                    .map_err(Into::into)
+            }
+        };
+        self.shared
+            .event_log
+            .log_result("Failed to export the spectrogram", result);
+    }
+
+    /// Sends a snapshot of the current data to the UI.
+    ///
+    /// Returns `false` if the UI is no longer receiving snapshots.
+    fn send_snapshot(&self) -> bool {
+        let snapshot = DataSnapshot {
+            trace_data: self
+                .shared
+                .trace_data
+                .lock()
+                .unwrap()
+                .decimated(DataWorker::MAX_TRACE_POINTS),
+            spectrogram_data: self.spectrogram.data().clone(),
+        };
+
+        match self.snapshots.try_send(snapshot) {
+            Ok(()) => (),
+            // The UI is behind, so drop this snapshot and let it catch up with the next one
+            Err(TrySendError::Full(_)) => {
+                self.dropped_snapshots.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(TrySendError::Disconnected(_)) => return false,
+        }
+        self.ctx.request_repaint();
+        true
+    }
+}
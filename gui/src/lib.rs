@@ -1,8 +1,10 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+mod actions;
 mod app;
 mod data;
 mod panels;
+mod plot_image;
 mod settings;
 mod widgets;
 
@@ -1,57 +1,131 @@
 use std::sync::atomic::Ordering;
 
-use egui::{Align, Layout, Panel, Ui};
+use egui::{Align, Color32, Layout, Panel, RichText, ScrollArea, Ui};
 
 use crate::{
+    data::{EventKind, EventLog},
     settings::AppSettings,
     widgets::{
-        PauseScanningButton, PlotSettingsToggleButton, ResumeScanningButton,
-        RfeSettingsToggleButton, UnitsComboBox,
+        EventLogToggleButton, PauseScanningButton, PlotSettingsToggleButton, RecordButton,
+        ResumeScanningButton, RfeSettingsToggleButton, ThemeComboBox, UnitsComboBox, event_color,
     },
 };
 
 pub struct AppSettingsBottomPanel {
     panel: Panel,
+    recording_status: RecordingStatus,
+}
+
+/// Whether the RF Explorer's sweeps are being recorded, which sets how the record button is shown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingStatus {
+    /// There's no RF Explorer to record.
+    #[default]
+    Unavailable,
+    Stopped,
+    Recording,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppSettingsPanelResponse {
     FrequencyUnitsChanged,
+    ThemeChanged,
     ExportAverageTraceClicked,
     ExportCurrentTraceClicked,
     ExportMaxTraceClicked,
+    ExportEventLogClicked,
+    /// The plot image should be exported at this many pixels per point.
+    ExportPlotImageClicked(u8),
+    RecordClicked,
+    OpenRecordingClicked,
 }
 
 impl AppSettingsBottomPanel {
     pub fn new() -> Self {
         Self {
             panel: Panel::bottom("bottom-panel").default_size(30.0),
+            recording_status: RecordingStatus::default(),
         }
     }
 
+    pub fn recording_status(mut self, recording_status: RecordingStatus) -> Self {
+        self.recording_status = recording_status;
+        self
+    }
+
     pub fn show(
         self,
         ui: &mut Ui,
         app_settings: &mut AppSettings,
+        frames_behind: usize,
+        event_log: &EventLog,
     ) -> Option<AppSettingsPanelResponse> {
+        let recording_status = self.recording_status;
         self.panel
             .show_inside(ui, |ui| {
-                ui.columns(2, |columns| {
-                    columns[0].with_layout(Layout::left_to_right(Align::Center), |ui| {
-                        show_bottom_left(ui, app_settings);
-                    });
-                    columns[1]
+                let event_log_response = if app_settings.show_event_log {
+                    let response = show_event_log(ui, event_log);
+                    ui.separator();
+                    response
+                } else {
+                    None
+                };
+
+                let settings_response = ui.columns(2, |columns| {
+                    let left_response = columns[0]
+                        .with_layout(Layout::left_to_right(Align::Center), |ui| {
+                            show_bottom_left(
+                                ui,
+                                app_settings,
+                                recording_status,
+                                frames_behind,
+                                event_log,
+                            )
+                        })
+                        .inner;
+                    let right_response = columns[1]
                         .with_layout(Layout::right_to_left(Align::Center), |ui| {
                             show_bottom_right(ui, app_settings)
                         })
-                        .inner
-                })
+                        .inner;
+                    left_response.or(right_response)
+                });
+                settings_response.or(event_log_response)
             })
             .inner
     }
 }
 
-fn show_bottom_left(ui: &mut Ui, app_settings: &mut AppSettings) {
+fn show_event_log(ui: &mut Ui, event_log: &EventLog) -> Option<AppSettingsPanelResponse> {
+    let mut response = None;
+    ui.horizontal(|ui| {
+        ui.strong("Event Log");
+        if ui.button("Export...").clicked() {
+            response = Some(AppSettingsPanelResponse::ExportEventLogClicked);
+        }
+        if ui.button("Clear").clicked() {
+            event_log.clear();
+        }
+    });
+    ScrollArea::vertical()
+        .max_height(150.0)
+        .auto_shrink([false, true])
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for event in event_log.events().iter() {
+                ui.label(RichText::new(event.to_string()).color(event_color(event.kind)));
+            }
+        });
+    response
+}
+
+fn show_bottom_left(
+    ui: &mut Ui,
+    app_settings: &mut AppSettings,
+    recording_status: RecordingStatus,
+    frames_behind: usize,
+    event_log: &EventLog,
+) -> Option<AppSettingsPanelResponse> {
     if ui
         .add(RfeSettingsToggleButton::new(
             app_settings.show_rfe_settings_panel,
@@ -69,6 +143,35 @@ fn show_bottom_left(ui: &mut Ui, app_settings: &mut AppSettings) {
             app_settings.pause_sweeps.store(true, Ordering::Relaxed);
         }
     }
+    let mut response = None;
+    if ui
+        .add_enabled(
+            recording_status != RecordingStatus::Unavailable,
+            RecordButton::new(recording_status == RecordingStatus::Recording),
+        )
+        .clicked()
+    {
+        response = Some(AppSettingsPanelResponse::RecordClicked);
+    }
+    let error_count = event_log
+        .events()
+        .iter()
+        .filter(|event| event.kind == EventKind::Error)
+        .count();
+    if ui
+        .add(EventLogToggleButton::new(
+            app_settings.show_event_log,
+            error_count,
+        ))
+        .clicked()
+    {
+        app_settings.show_event_log = !app_settings.show_event_log;
+    }
+    if frames_behind > 0 {
+        ui.label(RichText::new(format!("{frames_behind} frames behind")).color(Color32::YELLOW))
+            .on_hover_text("Sweeps are arriving faster than they can be drawn");
+    }
+    response
 }
 
 fn show_bottom_right(
@@ -84,9 +187,15 @@ fn show_bottom_right(
         app_settings.show_plot_settings_panel = !app_settings.show_plot_settings_panel;
     }
     let mut response = None;
+    if ui.button("Open Recording...").clicked() {
+        response = Some(AppSettingsPanelResponse::OpenRecordingClicked);
+    }
     if UnitsComboBox::show_ui(ui, &mut app_settings.frequency_units).is_some_and(|r| r.changed()) {
         response = Some(AppSettingsPanelResponse::FrequencyUnitsChanged);
     }
+    if ThemeComboBox::show_ui(ui, &mut app_settings.theme).is_some_and(|r| r.changed()) {
+        response = Some(AppSettingsPanelResponse::ThemeChanged);
+    }
     ui.menu_button("Export Trace as CSV...", |ui| {
         if ui.button("Average").clicked() {
             response = Some(AppSettingsPanelResponse::ExportAverageTraceClicked);
@@ -101,5 +210,13 @@ fn show_bottom_right(
             ui.close();
         }
     });
+    ui.menu_button("Export Plot Image...", |ui| {
+        for scale in [2, 3, 4] {
+            if ui.button(format!("{scale}x")).clicked() {
+                response = Some(AppSettingsPanelResponse::ExportPlotImageClicked(scale));
+                ui.close();
+            }
+        }
+    });
     response
 }
@@ -5,7 +5,9 @@ mod rfe_not_connected_central_panel;
 mod rfe_settings_side_panel;
 mod settings_side_panel;
 
-pub use app_settings_bottom_panel::{AppSettingsBottomPanel, AppSettingsPanelResponse};
+pub use app_settings_bottom_panel::{
+    AppSettingsBottomPanel, AppSettingsPanelResponse, RecordingStatus,
+};
 pub use plot_central_panel::PlotCentralPanel;
 pub use plot_settings_side_panel::{PlotSettingsPanelResponse, PlotSettingsSidePanel};
 pub use rfe_not_connected_central_panel::RfeNotConnectedCentralPanel;
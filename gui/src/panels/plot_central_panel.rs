@@ -1,8 +1,11 @@
+use std::slice;
+
 use egui::{CentralPanel, Panel, Ui};
+use rfe::Frequency;
 
 use crate::{
-    data::{SpectrogramData, TraceData},
-    settings::{FrequencyUnits, SpectrogramSettings, TraceSettings},
+    data::{ComparedTrace, DataSnapshot, TraceData},
+    settings::{ComparisonView, FrequencyUnits, SpectrogramSettings, TraceSettings},
     widgets::{Spectrogram, Trace},
 };
 
@@ -21,30 +24,136 @@ impl PlotCentralPanel {
         }
     }
 
+    /// Shows the plots and returns the frequency under the pointer if it's over a trace.
     pub fn show(
         self,
         ui: &mut Ui,
-        trace_data: &TraceData,
+        snapshot: &DataSnapshot,
+        compared_traces: &[ComparedTrace],
         trace_settings: &TraceSettings,
-        spectrogram_data: &mut SpectrogramData,
         spectrogram_settings: &SpectrogramSettings,
         units: FrequencyUnits,
-    ) {
+    ) -> Option<Frequency> {
         // Only put the spectrogram in the bottom panel if the trace is being shown in the central panel
         if !spectrogram_settings.hide_spectrogram && !trace_settings.hide_trace {
             self.bottom_panel.show_inside(ui, |ui| {
-                Spectrogram::show(ui, spectrogram_data, units);
+                Spectrogram::show(ui, &snapshot.spectrogram_data, units);
             });
         }
 
+        self.central_panel
+            .show_inside(ui, |ui| {
+                let hovered_freq = if trace_settings.hide_trace {
+                    None
+                } else {
+                    show_traces(
+                        ui,
+                        &snapshot.trace_data,
+                        compared_traces,
+                        trace_settings,
+                        units,
+                    )
+                };
+                // Put the spectrogram in the central panel if the trace is hidden
+                if trace_settings.hide_trace && !spectrogram_settings.hide_spectrogram {
+                    Spectrogram::show(ui, &snapshot.spectrogram_data, units);
+                }
+                hovered_freq
+            })
+            .inner
+    }
+
+    /// Shows only the traces, even if the spectrogram is shown next to them on screen.
+    pub fn show_traces_only(
+        self,
+        ui: &mut Ui,
+        trace_data: &TraceData,
+        compared_traces: &[ComparedTrace],
+        trace_settings: &TraceSettings,
+        units: FrequencyUnits,
+    ) {
         self.central_panel.show_inside(ui, |ui| {
-            if !trace_settings.hide_trace {
-                Trace::show(ui, trace_data, trace_settings, units);
-            }
-            // Put the spectrogram in the central panel if the trace is hidden
-            if trace_settings.hide_trace && !spectrogram_settings.hide_spectrogram {
-                Spectrogram::show(ui, spectrogram_data, units);
-            }
+            show_traces(ui, trace_data, compared_traces, trace_settings, units);
         });
     }
 }
+
+/// Shows the main RF Explorer's traces and the compared traces overlaid or split, and returns the
+/// frequency under the pointer if it's over one of them.
+fn show_traces(
+    ui: &mut Ui,
+    trace_data: &TraceData,
+    compared_traces: &[ComparedTrace],
+    trace_settings: &TraceSettings,
+    units: FrequencyUnits,
+) -> Option<Frequency> {
+    let main_trace_data = (!trace_settings.hide_main_device).then_some(trace_data);
+    // Traces with different spans can't share an axis without squeezing each other, so they're
+    // split even when they're supposed to be overlaid
+    let is_split = trace_settings.comparison_view == ComparisonView::Split
+        || compared_traces
+            .iter()
+            .any(|compared_trace| !compared_trace.has_same_span(trace_data));
+    if compared_traces.is_empty() || !is_split {
+        return show_trace(
+            ui,
+            "trace",
+            main_trace_data,
+            compared_traces,
+            trace_settings,
+            units,
+        );
+    }
+
+    let plot_count = compared_traces.len() + usize::from(main_trace_data.is_some());
+    ui.columns(plot_count, |columns| {
+        let mut columns = columns.iter_mut();
+        let mut hovered_freq = None;
+        if let Some(main_trace_data) = main_trace_data
+            && let Some(ui) = columns.next()
+        {
+            hovered_freq = show_trace(
+                ui,
+                "trace",
+                Some(main_trace_data),
+                &[],
+                trace_settings,
+                units,
+            );
+        }
+        for (compared_trace, ui) in compared_traces.iter().zip(columns) {
+            hovered_freq = show_trace(
+                ui,
+                ("compared-trace", &compared_trace.name),
+                None,
+                slice::from_ref(compared_trace),
+                trace_settings,
+                units,
+            )
+            .or(hovered_freq);
+        }
+        hovered_freq
+    })
+}
+
+fn show_trace(
+    ui: &mut Ui,
+    id_salt: impl std::hash::Hash,
+    trace_data: Option<&TraceData>,
+    compared_traces: &[ComparedTrace],
+    trace_settings: &TraceSettings,
+    units: FrequencyUnits,
+) -> Option<Frequency> {
+    let plot_response = Trace::show(
+        ui,
+        id_salt,
+        trace_data,
+        compared_traces,
+        trace_settings,
+        units,
+    );
+    plot_response
+        .response
+        .hover_pos()
+        .map(|pos| units.freq_from_f64(plot_response.transform.value_from_position(pos).x))
+}
@@ -1,22 +1,33 @@
+use std::sync::Arc;
+
 use egui::{
-    Image, Panel, ScrollArea, Slider, Ui,
+    Button, Checkbox, DragValue, Image, Label, Panel, ScrollArea, Slider, Ui,
     color_picker::{self, Alpha},
 };
+use rfe::Frequency;
+use strum::IntoEnumIterator;
 
 use super::{Setting, SettingsCategory};
 use crate::{
-    settings::{SpectrogramSettings, TraceSettings},
-    widgets::SpectrogramColorGradientComboBox,
+    data::{ComparedDevices, SpectrogramExportFormat},
+    settings::{ComparisonView, SpectrogramSettings, TraceLayer, TraceSettings},
+    widgets::{SmoothingMethodComboBox, SpectrogramColorGradientComboBox},
 };
 
 pub struct PlotSettingsSidePanel {
     side_panel: Panel,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PlotSettingsPanelResponse {
     TraceSettingsChanged,
+    /// The live trace should become the baseline the baseline delta is measured against.
+    SetBaselineClicked,
     SpectrogramSettingsChanged,
+    ExportSpectrogramClicked(SpectrogramExportFormat),
+    ConnectComparedDevicesClicked,
+    /// The compared spectrum analyzer with the given key should be disconnected.
+    RemoveComparedDeviceClicked(Arc<str>),
 }
 
 impl PlotSettingsSidePanel {
@@ -31,24 +42,44 @@ impl PlotSettingsSidePanel {
         ui: &mut Ui,
         trace_settings: &mut TraceSettings,
         spectrogram_settings: &mut SpectrogramSettings,
+        compared_devices: &mut ComparedDevices,
     ) -> Option<PlotSettingsPanelResponse> {
         // Save copies of the settings before they can be changed
         let old_trace_settings = *trace_settings;
         let old_spectrogram_settings = *spectrogram_settings;
 
-        self.side_panel.show_inside(ui, |ui| {
-            ScrollArea::vertical()
-                .show(ui, |ui| {
-                    ui.add_space(5.0);
-                    show_trace_settings(ui, trace_settings);
-                    ui.add_space(10.0);
-                    show_spectrogram_settings(ui, spectrogram_settings);
-                })
-                .inner
-        });
+        let (set_baseline_clicked, export_format, devices_response) = self
+            .side_panel
+            .show_inside(ui, |ui| {
+                ScrollArea::vertical()
+                    .show(ui, |ui| {
+                        ui.add_space(5.0);
+                        show_trace_settings(ui, trace_settings);
+                        ui.add_space(10.0);
+                        show_markers(ui, trace_settings);
+                        ui.add_space(10.0);
+                        let set_baseline_clicked = show_trace_layers(ui, trace_settings);
+                        ui.add_space(10.0);
+                        let export_format = show_spectrogram_settings(ui, spectrogram_settings);
+                        ui.add_space(10.0);
+                        let devices_response =
+                            show_compared_devices(ui, trace_settings, compared_devices);
+                        (set_baseline_clicked, export_format, devices_response)
+                    })
+                    .inner
+            })
+            .inner;
 
         // Check to see if the settings have been changed
-        if old_trace_settings != *trace_settings {
+        if devices_response.is_some() {
+            devices_response
+        } else if set_baseline_clicked {
+            Some(PlotSettingsPanelResponse::SetBaselineClicked)
+        } else if let Some(export_format) = export_format {
+            Some(PlotSettingsPanelResponse::ExportSpectrogramClicked(
+                export_format,
+            ))
+        } else if old_trace_settings != *trace_settings {
             Some(PlotSettingsPanelResponse::TraceSettingsChanged)
         } else if old_spectrogram_settings != *spectrogram_settings {
             Some(PlotSettingsPanelResponse::SpectrogramSettingsChanged)
@@ -59,31 +90,8 @@ impl PlotSettingsSidePanel {
 }
 
 fn show_trace_settings(ui: &mut Ui, trace_settings: &mut TraceSettings) {
-    SettingsCategory::new("Trace").show(ui, 6, |row| match row.index() {
+    SettingsCategory::new("Trace").show(ui, 7, |row| match row.index() {
         0 => {
-            Setting::new("Line Colors", |ui| {
-                color_picker::color_edit_button_srgba(
-                    ui,
-                    &mut trace_settings.max_trace_color,
-                    Alpha::Opaque,
-                )
-                .on_hover_text("Max");
-                color_picker::color_edit_button_srgba(
-                    ui,
-                    &mut trace_settings.current_trace_color,
-                    Alpha::Opaque,
-                )
-                .on_hover_text("Current");
-                color_picker::color_edit_button_srgba(
-                    ui,
-                    &mut trace_settings.average_trace_color,
-                    Alpha::Opaque,
-                )
-                .on_hover_text("Average");
-            })
-            .add_to_row(row);
-        }
-        1 => {
             Setting::new("Amp Offset", |ui| {
                 ui.add(
                     Slider::new(&mut trace_settings.amp_offset, -50..=50)
@@ -93,7 +101,7 @@ fn show_trace_settings(ui: &mut Ui, trace_settings: &mut TraceSettings) {
             })
             .add_to_row(row);
         }
-        2 => {
+        1 => {
             Setting::new("Y-Axis Max", |ui| {
                 ui.add_enabled(
                     !trace_settings.autoscale_y_axis,
@@ -104,7 +112,7 @@ fn show_trace_settings(ui: &mut Ui, trace_settings: &mut TraceSettings) {
             })
             .add_to_row(row);
         }
-        3 => {
+        2 => {
             Setting::new("Y-Axis Min", |ui| {
                 ui.add_enabled(
                     !trace_settings.autoscale_y_axis,
@@ -115,13 +123,30 @@ fn show_trace_settings(ui: &mut Ui, trace_settings: &mut TraceSettings) {
             })
             .add_to_row(row);
         }
-        4 => {
+        3 => {
             Setting::new("Autoscale Y-Axis", |ui| {
                 ui.checkbox(&mut trace_settings.autoscale_y_axis, "");
             })
             .add_to_row(row);
         }
+        4 => {
+            Setting::new("Smoothing", |ui| {
+                SmoothingMethodComboBox::show_ui(ui, &mut trace_settings.smoothing_method);
+            })
+            .add_to_row(row);
+        }
         5 => {
+            Setting::new("Smoothing Window", |ui| {
+                ui.add_enabled(
+                    trace_settings.smoothing_method.is_some(),
+                    Slider::new(&mut trace_settings.smoothing_window, 2..=25)
+                        .step_by(1.0)
+                        .suffix(" pts"),
+                );
+            })
+            .add_to_row(row);
+        }
+        6 => {
             Setting::new("Hide", |ui| {
                 ui.checkbox(&mut trace_settings.hide_trace, "");
             })
@@ -131,8 +156,108 @@ fn show_trace_settings(ui: &mut Ui, trace_settings: &mut TraceSettings) {
     });
 }
 
-fn show_spectrogram_settings(ui: &mut Ui, spectrogram_settings: &mut SpectrogramSettings) {
-    SettingsCategory::new("Spectrogram").show(ui, 4, |row| match row.index() {
+fn show_markers(ui: &mut Ui, trace_settings: &mut TraceSettings) {
+    let format_freq = |freq: Option<Frequency>| {
+        freq.map_or_else(
+            || "Not placed".to_string(),
+            |freq| freq.format_engineering(3),
+        )
+    };
+    SettingsCategory::new("Markers").show(ui, 3, |row| match row.index() {
+        0 => {
+            Setting::new("Marker", |ui| {
+                ui.label(format_freq(trace_settings.marker_freq));
+            })
+            .add_to_row(row);
+        }
+        1 => {
+            Setting::new("Delta", |ui| {
+                let mut is_delta_on = trace_settings.reference_marker_freq.is_some();
+                if ui
+                    .add_enabled(
+                        trace_settings.marker_freq.is_some(),
+                        Checkbox::without_text(&mut is_delta_on),
+                    )
+                    .on_hover_text(
+                        "Measure the marker from a reference marker placed where it is now",
+                    )
+                    .on_disabled_hover_text("Place a marker first")
+                    .changed()
+                {
+                    trace_settings.toggle_marker_delta();
+                }
+            })
+            .add_to_row(row);
+        }
+        2 => {
+            Setting::new("Reference", |ui| {
+                // Right-to-left layout, so the button is added before the label
+                if trace_settings.reference_marker_freq.is_some()
+                    && ui
+                        .button("Remove")
+                        .on_hover_text("Remove the reference marker")
+                        .clicked()
+                {
+                    trace_settings.reference_marker_freq = None;
+                }
+                ui.add_enabled(
+                    trace_settings.reference_marker_freq.is_some(),
+                    Label::new(format_freq(trace_settings.reference_marker_freq)),
+                );
+            })
+            .add_to_row(row);
+        }
+        _ => (),
+    });
+}
+
+/// Shows each trace's appearance and returns whether the baseline should be set.
+fn show_trace_layers(ui: &mut Ui, trace_settings: &mut TraceSettings) -> bool {
+    let mut set_baseline_clicked = false;
+    let layer_count = trace_settings.layers.len();
+    // List the layers from the top of the plot down, like the legend
+    SettingsCategory::new("Trace Layers").show(ui, layer_count + 1, |row| {
+        let index = row.index();
+        if index == layer_count {
+            Setting::new("Baseline", |ui| {
+                set_baseline_clicked = ui
+                    .button("Set")
+                    .on_hover_text("Measure the baseline delta against the current trace")
+                    .clicked();
+            })
+            .add_to_row(row);
+            return;
+        }
+        let layer = &mut trace_settings.layers[layer_count - 1 - index];
+        let name = layer.kind.to_string();
+        Setting::new(&name, |ui| show_trace_layer(ui, layer)).add_to_row(row);
+    });
+    set_baseline_clicked
+}
+
+fn show_trace_layer(ui: &mut Ui, layer: &mut TraceLayer) {
+    // Right-to-left layout, so the controls are added in reverse
+    ui.checkbox(&mut layer.fill, "")
+        .on_hover_text("Fill under the trace");
+    ui.add(
+        DragValue::new(&mut layer.width)
+            .range(TraceLayer::MIN_WIDTH..=TraceLayer::MAX_WIDTH)
+            .speed(0.1)
+            .fixed_decimals(1)
+            .suffix(" px"),
+    )
+    .on_hover_text("Line width");
+    color_picker::color_edit_button_srgba(ui, &mut layer.color, Alpha::Opaque);
+    ui.checkbox(&mut layer.is_visible, "").on_hover_text("Show");
+}
+
+/// Shows the spectrogram settings and returns the export format if an export button was clicked.
+fn show_spectrogram_settings(
+    ui: &mut Ui,
+    spectrogram_settings: &mut SpectrogramSettings,
+) -> Option<SpectrogramExportFormat> {
+    let mut export_format = None;
+    SettingsCategory::new("Spectrogram").show(ui, 5, |row| match row.index() {
         0 => {
             Setting::new("Color Gradient", |ui| {
                 SpectrogramColorGradientComboBox::show_ui(
@@ -177,6 +302,91 @@ fn show_spectrogram_settings(ui: &mut Ui, spectrogram_settings: &mut Spectrogram
             })
             .add_to_row(row);
         }
+        4 => {
+            Setting::new("Export", |ui| {
+                if ui.button("CSV...").clicked() {
+                    export_format = Some(SpectrogramExportFormat::Csv);
+                }
+                if ui.button("PNG...").clicked() {
+                    export_format = Some(SpectrogramExportFormat::Png);
+                }
+            })
+            .add_to_row(row);
+        }
         _ => (),
     });
+    export_format
+}
+
+/// Shows the spectrum analyzers compared with the main RF Explorer and returns a response if one
+/// should be connected or disconnected.
+fn show_compared_devices(
+    ui: &mut Ui,
+    trace_settings: &mut TraceSettings,
+    compared_devices: &mut ComparedDevices,
+) -> Option<PlotSettingsPanelResponse> {
+    let mut response = None;
+    let is_connecting = compared_devices.is_connecting();
+    let mut devices: Vec<_> = compared_devices.iter_mut().collect();
+    SettingsCategory::new("Compare").show(ui, 3 + devices.len(), |row| match row.index() {
+        0 => {
+            Setting::new("Other Analyzers", |ui| {
+                let text = if is_connecting {
+                    "Connecting..."
+                } else {
+                    "Connect"
+                };
+                if ui.add_enabled(!is_connecting, Button::new(text)).clicked() {
+                    response = Some(PlotSettingsPanelResponse::ConnectComparedDevicesClicked);
+                }
+            })
+            .add_to_row(row);
+        }
+        1 => {
+            Setting::new("View", |ui| {
+                // Right-to-left layout, so the views are added in reverse
+                for view in ComparisonView::iter().rev() {
+                    ui.selectable_value(
+                        &mut trace_settings.comparison_view,
+                        view,
+                        view.to_string(),
+                    );
+                }
+            })
+            .add_to_row(row);
+        }
+        2 => {
+            Setting::new("Main Device", |ui| {
+                let mut is_visible = !trace_settings.hide_main_device;
+                ui.checkbox(&mut is_visible, "");
+                trace_settings.hide_main_device = !is_visible;
+            })
+            .add_to_row(row);
+        }
+        index => {
+            let Some((key, device)) = devices.get_mut(index - 3) else {
+                return;
+            };
+            Setting::new(key, |ui| {
+                if ui
+                    .button("Disconnect")
+                    .on_hover_text(device.port_name())
+                    .clicked()
+                {
+                    response = Some(PlotSettingsPanelResponse::RemoveComparedDeviceClicked(
+                        Arc::clone(key),
+                    ));
+                }
+                color_picker::color_edit_button_srgba(ui, &mut device.color, Alpha::Opaque);
+                ui.checkbox(&mut device.is_visible, "")
+                    .on_hover_text(if device.is_connected() {
+                        "Show"
+                    } else {
+                        "Show the last trace received before it was disconnected"
+                    });
+            })
+            .add_to_row(row);
+        }
+    });
+    response
 }
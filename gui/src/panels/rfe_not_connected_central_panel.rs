@@ -1,7 +1,8 @@
-use std::sync::{Arc, Mutex};
+use egui::{
+    Button, CentralPanel, Color32, CornerRadius, Image, RichText, Spinner, Ui, Vec2, include_image,
+};
 
-use egui::{Button, CentralPanel, Color32, CornerRadius, Image, RichText, Ui, Vec2, include_image};
-use rfe::SpectrumAnalyzer;
+use crate::data::ConnectAttempt;
 
 #[derive(Default)]
 pub struct RfeNotConnectedCentralPanel {
@@ -15,7 +16,11 @@ impl RfeNotConnectedCentralPanel {
         }
     }
 
-    pub fn show(self, ui: &mut Ui, rfe: &mut Option<Arc<Mutex<SpectrumAnalyzer>>>) {
+    /// Shows the panel and returns whether the user asked to connect again.
+    ///
+    /// While `connect_attempt` is in progress, its latest step is shown instead of the button.
+    pub fn show(self, ui: &mut Ui, connect_attempt: Option<&ConnectAttempt>) -> bool {
+        let mut connect_clicked = false;
         self.central_panel.show_inside(ui, |ui| {
             ui.vertical_centered(|ui| {
                 ui.add_space((ui.available_height() / 2.0) - 120.0);
@@ -31,18 +36,20 @@ impl RfeNotConnectedCentralPanel {
                         .size(28.0),
                 );
                 ui.add_space(5.0);
+                if let Some(connect_attempt) = connect_attempt {
+                    ui.add(Spinner::new().size(24.0));
+                    ui.label(RichText::new(connect_attempt.status()).size(18.0));
+                    return;
+                }
                 ui.style_mut().spacing.button_padding = Vec2::new(8.0, 8.0);
-                if ui
+                connect_clicked = ui
                     .add(
                         Button::new(RichText::new("Try to Connect Again").size(24.0))
                             .corner_radius(CornerRadius::default().at_least(5)),
                     )
-                    .clicked()
-                    && let Some(spectrum_analyzer) = SpectrumAnalyzer::connect()
-                {
-                    *rfe = Some(Arc::new(Mutex::new(spectrum_analyzer)));
-                }
+                    .clicked();
             });
         });
+        connect_clicked
     }
 }
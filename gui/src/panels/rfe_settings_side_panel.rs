@@ -1,14 +1,19 @@
-use egui::{Align, Key, Panel, ScrollArea, TextEdit, Ui, Vec2};
+use std::time::Duration;
+
+use egui::{DragValue, Panel, ScrollArea, Ui};
+use rfe::{Frequency, LinkHealth};
 
 use super::{InfoCategory, InfoItem, Setting, SettingsCategory};
 use crate::{
     data::RfeInfo,
-    settings::{FrequencyUnits, SweepSettings},
-    widgets::SweepLengthComboBox,
+    settings::{ExternalOffset, FrequencyUnits, SweepSettings},
+    widgets::{FrequencyInput, ReadoutField, SweepLengthComboBox},
 };
 
 pub struct RfeSettingsSidePanel {
     side_panel: Panel,
+    jump_to: Option<ReadoutField>,
+    enabled: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -16,32 +21,63 @@ pub enum RfeSettingsChange {
     CenterSpan,
     StartStop,
     SweepLen,
+    ExternalOffset,
 }
 
 impl RfeSettingsSidePanel {
     pub fn new() -> Self {
         Self {
             side_panel: Panel::left("rfe-settings-panel").resizable(false),
+            jump_to: None,
+            enabled: true,
         }
     }
 
+    /// Scrolls the panel to the setting that a readout bar field shows.
+    pub fn jump_to(mut self, field: Option<ReadoutField>) -> Self {
+        self.jump_to = field;
+        self
+    }
+
+    /// Sets whether the sweep settings can be changed, which they can't while a recording is
+    /// played back.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
     pub fn show(
         self,
         ui: &mut Ui,
-        can_change_sweep_len: bool,
         sweep_settings: &mut SweepSettings,
+        external_offset: &mut ExternalOffset,
         rfe_info: &RfeInfo,
+        link_health: Option<&LinkHealth>,
         units: FrequencyUnits,
     ) -> Option<RfeSettingsChange> {
+        let jump_to = self.jump_to;
+        let enabled = self.enabled;
         self.side_panel
             .show_inside(ui, |ui| {
                 ScrollArea::vertical()
                     .show(ui, |ui| {
                         ui.add_space(5.0);
-                        let response =
-                            show_sweep_settings(ui, can_change_sweep_len, sweep_settings, units);
+                        let response = ui
+                            .add_enabled_ui(enabled, |ui| {
+                                let sweep_response = show_sweep_settings(
+                                    ui,
+                                    sweep_settings,
+                                    rfe_info,
+                                    units,
+                                    jump_to,
+                                );
+                                ui.add_space(10.0);
+                                let amp_response = show_amp_settings(ui, external_offset);
+                                sweep_response.or(amp_response)
+                            })
+                            .inner;
                         ui.add_space(10.0);
-                        show_rfe_info(ui, rfe_info, units);
+                        show_rfe_info(ui, rfe_info, link_health, units, jump_to);
                         response
                     })
                     .inner
@@ -52,89 +88,116 @@ impl RfeSettingsSidePanel {
 
 fn show_sweep_settings(
     ui: &mut Ui,
-    can_change_sweep_len: bool,
     sweep_settings: &mut SweepSettings,
+    rfe_info: &RfeInfo,
     units: FrequencyUnits,
+    jump_to: Option<ReadoutField>,
 ) -> Option<RfeSettingsChange> {
     let mut rfe_settings_changed = None;
+    // Only the Plus models can change how many points they sweep
+    let can_change_sweep_len = rfe_info.active_radio_model.is_plus_model();
+    let step = sweep_settings.freq_step();
+    let freq_range = rfe_info.min_freq..=rfe_info.max_freq;
     let rows = if sweep_settings.rbw.is_some() { 7 } else { 6 };
-    SettingsCategory::new("Sweep").show(ui, rows, |row| match row.index() {
-        0 => {
-            Setting::new("Center", |ui| {
-                ui.label(units.to_string());
-                if ui
-                    .add(
-                        TextEdit::singleline(&mut sweep_settings.center_freq)
-                            .min_size(Vec2::new(120.0, 20.0))
-                            .horizontal_align(Align::RIGHT),
+    // The RBW's row shows the step size when the RF Explorer doesn't report its RBW, and the
+    // sweep rate depends on how many points are swept
+    let jump_to_row = match jump_to {
+        Some(ReadoutField::Rbw) => Some(4),
+        Some(ReadoutField::SweepPoints | ReadoutField::SweepRate) => Some(rows - 1),
+        _ => None,
+    };
+    SettingsCategory::new("Sweep")
+        .scroll_to_row(jump_to_row)
+        .show(ui, rows, |row| match row.index() {
+            0 => {
+                Setting::new("Center", |ui| {
+                    if FrequencyInput::new(
+                        "center-freq-input",
+                        &mut sweep_settings.center_freq,
+                        units,
                     )
-                    .lost_focus()
-                    && ui.input(|i| i.key_pressed(Key::Enter))
-                {
-                    rfe_settings_changed = Some(RfeSettingsChange::CenterSpan);
-                }
-            })
-            .add_to_row(row);
-        }
-        1 => {
-            Setting::new("Span", |ui| {
-                ui.label(units.to_string());
-                if ui
-                    .add(
-                        TextEdit::singleline(&mut sweep_settings.span)
-                            .horizontal_align(Align::RIGHT),
-                    )
-                    .lost_focus()
-                    && ui.input(|i| i.key_pressed(Key::Enter))
-                {
-                    rfe_settings_changed = Some(RfeSettingsChange::CenterSpan);
-                }
-            })
-            .add_to_row(row);
-        }
-        2 => {
-            Setting::new("Start", |ui| {
-                ui.label(units.to_string());
-                if ui
-                    .add(
-                        TextEdit::singleline(&mut sweep_settings.start_freq)
-                            .horizontal_align(Align::RIGHT),
-                    )
-                    .lost_focus()
-                    && ui.input(|i| i.key_pressed(Key::Enter))
-                {
-                    rfe_settings_changed = Some(RfeSettingsChange::StartStop);
-                }
-            })
-            .add_to_row(row);
-        }
-        3 => {
-            Setting::new("Stop", |ui| {
-                ui.label(units.to_string());
-                if ui
-                    .add(
-                        TextEdit::singleline(&mut sweep_settings.stop_freq)
-                            .horizontal_align(Align::RIGHT),
+                    .range(freq_range.clone())
+                    .step(step)
+                    .min_width(120.0)
+                    .show_ui(ui)
+                    {
+                        rfe_settings_changed = Some(RfeSettingsChange::CenterSpan);
+                    }
+                })
+                .add_to_row(row);
+            }
+            1 => {
+                Setting::new("Span", |ui| {
+                    if FrequencyInput::new("span-freq-input", &mut sweep_settings.span, units)
+                        .range(Frequency::default()..=rfe_info.max_span)
+                        .step(step)
+                        .show_ui(ui)
+                    {
+                        rfe_settings_changed = Some(RfeSettingsChange::CenterSpan);
+                    }
+                })
+                .add_to_row(row);
+            }
+            2 => {
+                Setting::new("Start", |ui| {
+                    if FrequencyInput::new(
+                        "start-freq-input",
+                        &mut sweep_settings.start_freq,
+                        units,
                     )
-                    .lost_focus()
-                    && ui.input(|i| i.key_pressed(Key::Enter))
-                {
-                    rfe_settings_changed = Some(RfeSettingsChange::StartStop);
+                    .range(freq_range.clone())
+                    .step(step)
+                    .show_ui(ui)
+                    {
+                        rfe_settings_changed = Some(RfeSettingsChange::StartStop);
+                    }
+                })
+                .add_to_row(row);
+            }
+            3 => {
+                Setting::new("Stop", |ui| {
+                    if FrequencyInput::new("stop-freq-input", &mut sweep_settings.stop_freq, units)
+                        .range(freq_range.clone())
+                        .step(step)
+                        .show_ui(ui)
+                    {
+                        rfe_settings_changed = Some(RfeSettingsChange::StartStop);
+                    }
+                })
+                .add_to_row(row);
+            }
+            4 => {
+                if rows == 6 {
+                    InfoItem::new_freq("Step Size", sweep_settings.step_size, units)
+                        .add_to_row(row);
+                } else {
+                    if let Some(rbw) = sweep_settings.rbw {
+                        InfoItem::new_freq("RBW", rbw, units).add_to_row(row);
+                    }
                 }
-            })
-            .add_to_row(row);
-        }
-        4 => {
-            if rows == 6 {
-                InfoItem::new_freq("Step Size", sweep_settings.step_size, units).add_to_row(row);
-            } else {
-                if let Some(rbw) = sweep_settings.rbw {
-                    InfoItem::new_freq("RBW", rbw, units).add_to_row(row);
+            }
+            5 => {
+                if rows == 6 {
+                    if can_change_sweep_len {
+                        Setting::new("Length", |ui| {
+                            ui.label("Points");
+                            if SweepLengthComboBox::show_ui(ui, &mut sweep_settings.len)
+                                .is_some_and(|r| r.changed())
+                            {
+                                rfe_settings_changed = Some(RfeSettingsChange::SweepLen);
+                            }
+                        })
+                        .add_to_row(row);
+                    } else {
+                        InfoItem::new("Length", sweep_settings.len.to_string() + "  Points")
+                            .add_to_row(row);
+                    }
+                } else {
+                    InfoItem::new_freq("Step Size", sweep_settings.step_size, units)
+                        .add_to_row(row);
                 }
             }
-        }
-        5 => {
-            if rows == 6 {
+            6 => {
                 if can_change_sweep_len {
                     Setting::new("Length", |ui| {
                         ui.label("Points");
@@ -149,36 +212,71 @@ fn show_sweep_settings(
                     InfoItem::new("Length", sweep_settings.len.to_string() + "  Points")
                         .add_to_row(row);
                 }
-            } else {
-                InfoItem::new_freq("Step Size", sweep_settings.step_size, units).add_to_row(row);
             }
+            _ => {}
+        });
+    rfe_settings_changed
+}
+
+fn show_amp_settings(
+    ui: &mut Ui,
+    external_offset: &mut ExternalOffset,
+) -> Option<RfeSettingsChange> {
+    let mut rfe_settings_changed = None;
+    SettingsCategory::new("Amplitude").show(ui, 2, |row| match row.index() {
+        0 => {
+            Setting::new("External Offset", |ui| {
+                let response = ui
+                    .add(
+                        DragValue::new(&mut external_offset.total_db)
+                            .range(ExternalOffset::RANGE)
+                            .suffix(" dB"),
+                    )
+                    .on_hover_text(
+                        "Added to every amplitude to make up for what's connected to the RF \
+                         Explorer's input, like 10 dB for a cable with 10 dB of loss or -20 dB \
+                         for an amplifier with 20 dB of gain",
+                    );
+                // Wait until the value is dragged to so the RF Explorer isn't sent every value
+                // in between
+                if response.drag_stopped() || (response.changed() && !response.dragged()) {
+                    rfe_settings_changed = Some(RfeSettingsChange::ExternalOffset);
+                }
+            })
+            .add_to_row(row);
         }
-        6 => {
-            if can_change_sweep_len {
-                Setting::new("Length", |ui| {
-                    ui.label("Points");
-                    if SweepLengthComboBox::show_ui(ui, &mut sweep_settings.len)
-                        .is_some_and(|r| r.changed())
-                    {
-                        rfe_settings_changed = Some(RfeSettingsChange::SweepLen);
-                    }
-                })
-                .add_to_row(row);
-            } else {
-                InfoItem::new("Length", sweep_settings.len.to_string() + "  Points")
-                    .add_to_row(row);
-            }
+        1 => {
+            // The RF Explorer's offset only goes from -128 to 127 dB, so the app adds the rest
+            InfoItem::new(
+                "Applied By",
+                format!(
+                    "RF Explorer {} dB, App {} dB",
+                    external_offset.device_db(),
+                    external_offset.app_db()
+                ),
+            )
+            .add_to_row(row);
         }
         _ => {}
     });
     rfe_settings_changed
 }
 
-fn show_rfe_info(ui: &mut Ui, rfe_info: &RfeInfo, units: FrequencyUnits) {
+fn show_rfe_info(
+    ui: &mut Ui,
+    rfe_info: &RfeInfo,
+    link_health: Option<&LinkHealth>,
+    units: FrequencyUnits,
+    jump_to: Option<ReadoutField>,
+) {
     let mut info_items = vec![
         InfoItem::new_freq("Min Freq", rfe_info.min_freq, units),
         InfoItem::new_freq("Max Freq", rfe_info.max_freq, units),
         InfoItem::new_freq("Max Span", rfe_info.max_freq, units),
+        InfoItem::new(
+            "Amp Range",
+            format!("{} to {} dBm", rfe_info.min_amp_dbm, rfe_info.max_amp_dbm),
+        ),
         InfoItem::new("Active Radio", rfe_info.active_radio_model.to_string()),
     ];
     if let Some(inactive_radio_model) = &rfe_info.inactive_radio_model {
@@ -199,10 +297,55 @@ fn show_rfe_info(ui: &mut Ui, rfe_info: &RfeInfo, units: FrequencyUnits) {
     info_items.push(InfoItem::new("Port Name", rfe_info.port_name.clone()));
     info_items.push(InfoItem::new(
         "Firmware Version",
-        rfe_info.firmware_version.clone(),
+        rfe_info.firmware_version.to_string(),
     ));
     if let Some(serial_number) = &rfe_info.serial_number {
-        info_items.push(InfoItem::new("Serial Number", serial_number.clone()));
+        info_items.push(InfoItem::new("Serial Number", serial_number.to_string()));
+    }
+    if let Some(link_health) = link_health {
+        if let Some(baud_rate) = link_health.baud_rate {
+            info_items.push(InfoItem::new("Baud Rate", baud_rate.to_string()));
+        }
+        info_items.push(InfoItem::new(
+            "Throughput",
+            format!("{:.1} KB/s", link_health.bytes_per_sec / 1000.0),
+        ));
+        let read_errors = link_health.read_errors;
+        info_items.push(InfoItem::new(
+            "Read Errors",
+            format!(
+                "{} timeouts, {} parse, {} I/O",
+                read_errors.timeouts, read_errors.parse_errors, read_errors.io_errors
+            ),
+        ));
+        info_items.push(InfoItem::new(
+            "Last Message",
+            link_health
+                .since_last_message
+                .map_or_else(|| "Never".to_string(), format_elapsed),
+        ));
+        info_items.push(InfoItem::new(
+            "Link Diagnosis",
+            link_health.diagnosis.to_string(),
+        ));
+    }
+    let jump_to_title = match jump_to {
+        Some(ReadoutField::RefLevel) => Some("Amp Range"),
+        Some(ReadoutField::DspMode) => Some("DSP Mode"),
+        Some(ReadoutField::InputStage) => Some("Input Stage"),
+        _ => None,
+    };
+    let jump_to_row =
+        jump_to_title.and_then(|title| info_items.iter().position(|item| item.title() == title));
+    InfoCategory::new("RF Explorer Info")
+        .scroll_to_row(jump_to_row)
+        .show(ui, &info_items);
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(1) {
+        format!("{} ms ago", elapsed.as_millis())
+    } else {
+        format!("{:.1} s ago", elapsed.as_secs_f64())
     }
-    InfoCategory::new("RF Explorer Info").show(ui, &info_items);
 }
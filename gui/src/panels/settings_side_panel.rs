@@ -1,9 +1,11 @@
-use egui::{Align, Layout, RichText, Ui};
+use egui::{Align, Layout, Rect, RichText, Ui, vec2};
 use egui_extras::{Column, TableBuilder, TableRow};
 use rfe::Frequency;
 
 use crate::settings::FrequencyUnits;
 
+const ROW_HEIGHT: f32 = 30.0;
+
 #[derive(Debug, Default, Clone)]
 pub struct InfoItem<'a> {
     title: &'a str,
@@ -34,6 +36,10 @@ impl<'a> InfoItem<'a> {
         }
     }
 
+    pub fn title(&self) -> &str {
+        self.title
+    }
+
     pub fn add_to_row(&self, mut row: TableRow<'_, '_>) {
         row.col(|ui| {
             ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
@@ -53,23 +59,34 @@ impl<'a> InfoItem<'a> {
 
 pub struct InfoCategory<'a> {
     title: &'a str,
+    scroll_to_row: Option<usize>,
 }
 
 impl<'a> InfoCategory<'a> {
     pub fn new(title: &'a str) -> Self {
-        Self { title }
+        Self {
+            title,
+            scroll_to_row: None,
+        }
+    }
+
+    /// Scrolls the panel so the row is visible.
+    pub fn scroll_to_row(mut self, row: Option<usize>) -> Self {
+        self.scroll_to_row = row;
+        self
     }
 
     pub fn show(self, ui: &mut Ui, info_items: &[InfoItem<'a>]) {
         ui.label(RichText::new(self.title).size(16.0).strong());
         ui.add_space(5.0);
+        scroll_to_row(ui, self.scroll_to_row);
         TableBuilder::new(ui)
             .id_salt(self.title)
             .striped(true)
             .column(Column::remainder())
             .column(Column::auto())
             .body(|body| {
-                body.rows(30.0, info_items.len(), |row| {
+                body.rows(ROW_HEIGHT, info_items.len(), |row| {
                     if let Some(info_item) = info_items.get(row.index()) {
                         info_item.add_to_row(row);
                     }
@@ -103,11 +120,21 @@ impl<'a, F: FnOnce(&mut Ui)> Setting<'a, F> {
 
 pub struct SettingsCategory<'a> {
     title: &'a str,
+    scroll_to_row: Option<usize>,
 }
 
 impl<'a> SettingsCategory<'a> {
     pub fn new(title: &'a str) -> Self {
-        SettingsCategory { title }
+        SettingsCategory {
+            title,
+            scroll_to_row: None,
+        }
+    }
+
+    /// Scrolls the panel so the row is visible.
+    pub fn scroll_to_row(mut self, row: Option<usize>) -> Self {
+        self.scroll_to_row = row;
+        self
     }
 
     fn show_internal(
@@ -119,6 +146,7 @@ impl<'a> SettingsCategory<'a> {
     ) {
         ui.label(RichText::new(self.title).size(16.0).strong());
         ui.add_space(5.0);
+        scroll_to_row(ui, self.scroll_to_row);
         ui.push_id(self.title, |ui| {
             TableBuilder::new(ui)
                 .id_salt(self.title)
@@ -126,7 +154,7 @@ impl<'a> SettingsCategory<'a> {
                 .column(Column::remainder())
                 .column(Column::auto())
                 .body(|body| {
-                    body.rows(30.0, rows, add_row_content);
+                    body.rows(ROW_HEIGHT, rows, add_row_content);
                 });
         });
         if let Some(add_bottom_content) = add_bottom_content {
@@ -138,3 +166,15 @@ impl<'a> SettingsCategory<'a> {
         self.show_internal(ui, rows, add_row_content, None::<fn(&mut Ui)>);
     }
 }
+
+/// Scrolls to a row of the table that's about to be shown.
+///
+/// Tables only lay out the rows that are visible, so the row's position is worked out from the
+/// table's top instead.
+fn scroll_to_row(ui: &Ui, row: Option<usize>) {
+    if let Some(row) = row {
+        let top = ui.cursor().min + vec2(0.0, ROW_HEIGHT * row as f32);
+        let rect = Rect::from_min_size(top, vec2(ui.available_width(), ROW_HEIGHT));
+        ui.scroll_to_rect(rect, Some(Align::Center));
+    }
+}
@@ -0,0 +1,227 @@
+use std::sync::mpsc;
+
+use eframe::egui_wgpu::{Renderer, RendererOptions, ScreenDescriptor};
+use egui::{Context, Panel, Pos2, RawInput, Rect, Vec2, ViewportId, ViewportInfo};
+use image::ExtendedColorType;
+use rfd::FileDialog;
+
+use crate::{
+    data::{ComparedTrace, EventKind, EventLog, TraceData},
+    panels::PlotCentralPanel,
+    settings::{FrequencyUnits, TraceSettings},
+};
+
+/// The traces and settings needed to draw the plot into an image, away from the app's window.
+pub struct PlotImage {
+    pub trace_data: TraceData,
+    pub compared_traces: Vec<ComparedTrace>,
+    pub trace_settings: TraceSettings,
+    pub units: FrequencyUnits,
+    pub theme: egui::Theme,
+    /// The line under the plot that describes the device and the sweep.
+    pub footer: String,
+    /// How many pixels the image has per point of the plot's size.
+    pub scale: u8,
+}
+
+impl PlotImage {
+    /// The size of the plot in points, which is the same no matter the scale so that the text and
+    /// lines keep their proportions.
+    const SIZE: Vec2 = Vec2::new(1280.0, 720.0);
+
+    /// The format of the texture the plot is rendered into. It isn't sRGB, so egui writes colors
+    /// that are already gamma encoded, which is what PNGs expect.
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+    /// Asks the user where to save the image, then renders it and saves it as a PNG on a new
+    /// thread.
+    ///
+    /// The GPU is shared with the app's window, which keeps drawing while the image is rendered.
+    pub fn export(self, device: wgpu::Device, queue: wgpu::Queue, event_log: &EventLog) {
+        let event_log = event_log.clone();
+        std::thread::spawn(move || {
+            let Some(path) = FileDialog::new()
+                .set_title("Export Plot Image")
+                .add_filter("PNG", &["png"])
+                .set_file_name("rfe-plot.png")
+                .save_file()
+            else {
+                return;
+            };
+            let result = self
+                .render(&device, &queue)
+                .and_then(|([width, height], pixels)| {
+                    image::save_buffer(&path, &pixels, width, height, ExtendedColorType::Rgba8)
+                        .map_err(|error| error.to_string())
+                });
+            if event_log
+                .log_result("Failed to export the plot image", result)
+                .is_some()
+            {
+                event_log.push(
+                    EventKind::Export,
+                    format!("Exported the plot image to {}", path.display()),
+                );
+            }
+        });
+    }
+
+    /// Draws the plot and its footer with a context of its own and renders them into a texture,
+    /// returning the image's size and its RGBA pixels.
+    fn render(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<([u32; 2], Vec<u8>), String> {
+        let pixels_per_point = f32::from(self.scale.max(1));
+        let size = (Self::SIZE * pixels_per_point).round();
+        let size_in_pixels = [size.x as u32, size.y as u32];
+        let max_size = device.limits().max_texture_dimension_2d;
+        if size_in_pixels.iter().any(|&len| len > max_size) {
+            return Err(format!(
+                "{}x{} pixels is larger than the GPU supports",
+                size_in_pixels[0], size_in_pixels[1]
+            ));
+        }
+
+        let ctx = Context::default();
+        ctx.set_theme(self.theme);
+        let raw_input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, Self::SIZE)),
+            viewports: std::iter::once((
+                ViewportId::ROOT,
+                ViewportInfo {
+                    native_pixels_per_point: Some(pixels_per_point),
+                    ..ViewportInfo::default()
+                },
+            ))
+            .collect(),
+            ..RawInput::default()
+        };
+        // The first pass only measures things like the legend's size, so the second one is drawn.
+        // Both passes' textures are uploaded because the fonts are only sent by the first.
+        let mut renderer = Renderer::new(device, Self::FORMAT, RendererOptions::default());
+        let mut output = None;
+        for _ in 0..2 {
+            let full_output = ctx.run_ui(raw_input.clone(), |ui| self.show(ui));
+            for (id, image_delta) in &full_output.textures_delta.set {
+                renderer.update_texture(device, queue, *id, image_delta);
+            }
+            output = Some(full_output);
+        }
+        let Some(output) = output else {
+            return Err("The plot wasn't drawn".to_string());
+        };
+        let paint_jobs = ctx.tessellate(output.shapes, output.pixels_per_point);
+        let screen_descriptor = ScreenDescriptor {
+            size_in_pixels,
+            pixels_per_point: output.pixels_per_point,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("plot image"),
+            size: wgpu::Extent3d {
+                width: size_in_pixels[0],
+                height: size_in_pixels[1],
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // Rows copied out of a texture must be padded to a multiple of 256 bytes
+        let row_len = size_in_pixels[0] * 4;
+        let padded_row_len = row_len.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("plot image"),
+            size: u64::from(padded_row_len) * u64::from(size_in_pixels[1]),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("plot image"),
+        });
+        let mut command_buffers =
+            renderer.update_buffers(device, queue, &mut encoder, &paint_jobs, &screen_descriptor);
+        let [r, g, b, a] = ctx
+            .global_style()
+            .visuals
+            .panel_fill
+            .to_normalized_gamma_f32();
+        {
+            let mut render_pass = encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("plot image"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &view,
+                        depth_slice: None,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color {
+                                r: f64::from(r),
+                                g: f64::from(g),
+                                b: f64::from(b),
+                                a: f64::from(a),
+                            }),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    ..wgpu::RenderPassDescriptor::default()
+                })
+                .forget_lifetime();
+            renderer.render(&mut render_pass, &paint_jobs, &screen_descriptor);
+        }
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_len),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+        command_buffers.push(encoder.finish());
+        queue.submit(command_buffers);
+
+        let (sender, receiver) = mpsc::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| _ = sender.send(result));
+        device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|error| error.to_string())?;
+        receiver
+            .recv()
+            .map_err(|error| error.to_string())?
+            .map_err(|error| error.to_string())?;
+        let pixels = buffer
+            .slice(..)
+            .get_mapped_range()
+            .chunks(padded_row_len as usize)
+            .flat_map(|row| &row[..row_len as usize])
+            .copied()
+            .collect();
+        Ok((size_in_pixels, pixels))
+    }
+
+    fn show(&self, ui: &mut egui::Ui) {
+        Panel::bottom("plot-image-footer").show_inside(ui, |ui| {
+            ui.label(&self.footer);
+        });
+        PlotCentralPanel::new().show_traces_only(
+            ui,
+            &self.trace_data,
+            &self.compared_traces,
+            &self.trace_settings,
+            self.units,
+        );
+    }
+}
@@ -1,13 +1,20 @@
-use std::sync::{Arc, atomic::AtomicBool};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, atomic::AtomicBool},
+};
 
-use super::FrequencyUnits;
+use super::{FrequencyUnits, Theme};
 
 #[derive(Debug, Clone)]
 pub struct AppSettings {
     pub show_rfe_settings_panel: bool,
     pub show_plot_settings_panel: bool,
+    pub show_event_log: bool,
     pub pause_sweeps: Arc<AtomicBool>,
     pub frequency_units: FrequencyUnits,
+    pub theme: Theme,
+    /// The external offset of each RF Explorer that's had one entered, by serial number.
+    pub external_offsets_db: BTreeMap<String, i32>,
 }
 
 impl Default for AppSettings {
@@ -15,8 +22,11 @@ impl Default for AppSettings {
         Self {
             show_rfe_settings_panel: true,
             show_plot_settings_panel: true,
+            show_event_log: false,
             pause_sweeps: Arc::new(AtomicBool::new(false)),
             frequency_units: FrequencyUnits::Mhz,
+            theme: Theme::default(),
+            external_offsets_db: BTreeMap::new(),
         }
     }
 }
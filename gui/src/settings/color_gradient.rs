@@ -1,4 +1,5 @@
 use egui::{ImageSource, include_image};
+use rfe::render::colormap::Colormap;
 use strum::{Display, EnumIter};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default, EnumIter, Display)]
@@ -17,17 +18,17 @@ pub enum ColorGradient {
 }
 
 impl ColorGradient {
-    pub const fn gradient(&self) -> colorous::Gradient {
+    pub const fn colormap(&self) -> Colormap {
         match self {
-            Self::Cividis => colorous::CIVIDIS,
-            Self::Cool => colorous::COOL,
-            Self::CubeHelix => colorous::CUBEHELIX,
-            Self::Inferno => colorous::INFERNO,
-            Self::Magma => colorous::MAGMA,
-            Self::Plasma => colorous::PLASMA,
-            Self::Turbo => colorous::TURBO,
-            Self::Viridis => colorous::VIRIDIS,
-            Self::Warm => colorous::WARM,
+            Self::Cividis => Colormap::Cividis,
+            Self::Cool => Colormap::Cool,
+            Self::CubeHelix => Colormap::CubeHelix,
+            Self::Inferno => Colormap::Inferno,
+            Self::Magma => Colormap::Magma,
+            Self::Plasma => Colormap::Plasma,
+            Self::Turbo => Colormap::Turbo,
+            Self::Viridis => Colormap::Viridis,
+            Self::Warm => Colormap::Warm,
         }
     }
 
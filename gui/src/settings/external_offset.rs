@@ -0,0 +1,25 @@
+use std::ops::RangeInclusive;
+
+/// The offset added to an RF Explorer's amplitudes to make up for the gain or loss of what's
+/// connected to its input, like an amplifier, attenuator, or long cable.
+///
+/// The RF Explorer can only offset its amplitudes by as much as an `i8`, so the part of the
+/// offset outside of that range is added by the app instead.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExternalOffset {
+    pub total_db: i32,
+}
+
+impl ExternalOffset {
+    pub const RANGE: RangeInclusive<i32> = -250..=250;
+
+    /// The part of the offset that the RF Explorer adds to its amplitudes.
+    pub fn device_db(self) -> i8 {
+        self.total_db.clamp(i32::from(i8::MIN), i32::from(i8::MAX)) as i8
+    }
+
+    /// The part of the offset that the app adds to the RF Explorer's amplitudes.
+    pub fn app_db(self) -> i32 {
+        self.total_db - i32::from(self.device_db())
+    }
+}
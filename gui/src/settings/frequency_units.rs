@@ -19,6 +19,18 @@ impl FrequencyUnits {
             FrequencyUnits::Ghz => freq.as_ghz_f64(),
         }
     }
+
+    /// Converts a value in these units, like a coordinate on the plot's frequency axis, to a
+    /// frequency. Negative values are treated as 0 Hz.
+    pub fn freq_from_f64(&self, value: f64) -> Frequency {
+        let value = value.max(0.0);
+        match self {
+            FrequencyUnits::Hz => Frequency::from_hz(value as u64),
+            FrequencyUnits::Khz => Frequency::from_khz_f64(value),
+            FrequencyUnits::Mhz => Frequency::from_mhz_f64(value),
+            FrequencyUnits::Ghz => Frequency::from_ghz_f64(value),
+        }
+    }
 }
 
 impl Display for FrequencyUnits {
@@ -1,13 +1,19 @@
 mod app_settings;
 mod color_gradient;
+mod external_offset;
 mod frequency_units;
+mod settings_file;
 mod spectrogram_settings;
 mod sweep_settings;
+mod theme;
 mod trace_settings;
 
 pub use app_settings::AppSettings;
 pub use color_gradient::ColorGradient;
+pub use external_offset::ExternalOffset;
 pub use frequency_units::FrequencyUnits;
+pub use settings_file::SettingsFile;
 pub use spectrogram_settings::SpectrogramSettings;
 pub use sweep_settings::SweepSettings;
-pub use trace_settings::TraceSettings;
+pub use theme::Theme;
+pub use trace_settings::{ComparisonView, TraceLayer, TraceLayerKind, TraceSettings};
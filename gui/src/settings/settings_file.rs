@@ -0,0 +1,86 @@
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{AppSettings, Theme, TraceLayer, TraceSettings};
+
+/// The settings that are saved when the app closes and restored when it's next opened.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SettingsFile {
+    pub theme: Theme,
+    pub trace_layers: Vec<TraceLayer>,
+    /// The external offset of each RF Explorer in dB, by serial number.
+    pub external_offsets_db: BTreeMap<String, i32>,
+}
+
+impl SettingsFile {
+    const FILE_NAME: &str = "settings.toml";
+
+    /// Collects the settings that are saved.
+    pub fn new(app_settings: &AppSettings, trace_settings: &TraceSettings) -> Self {
+        Self {
+            theme: app_settings.theme,
+            trace_layers: trace_settings.layers.to_vec(),
+            external_offsets_db: app_settings.external_offsets_db.clone(),
+        }
+    }
+
+    /// Loads the saved settings, or returns `None` if there aren't any.
+    pub fn load() -> io::Result<Option<Self>> {
+        let Some(path) = Self::path() else {
+            return Ok(None);
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents)
+                .map(Some)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = toml::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, contents)
+    }
+
+    /// Applies the saved settings on top of the current ones.
+    pub fn apply(&self, app_settings: &mut AppSettings, trace_settings: &mut TraceSettings) {
+        app_settings.theme = self.theme;
+        app_settings
+            .external_offsets_db
+            .clone_from(&self.external_offsets_db);
+        trace_settings.restore_layers(&self.trace_layers);
+    }
+
+    /// The path of the settings file in the platform's config directory, if it can be found.
+    fn path() -> Option<PathBuf> {
+        Some(config_dir()?.join("rfe-gui").join(Self::FILE_NAME))
+    }
+}
+
+fn config_dir() -> Option<PathBuf> {
+    let env_dir = |name| {
+        env::var_os(name)
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+    };
+    if cfg!(target_os = "windows") {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join(Path::new("Library/Application Support")))
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+    }
+}
@@ -1,4 +1,5 @@
 use egui::Color32;
+use rfe::render::colormap;
 
 use super::ColorGradient;
 
@@ -16,11 +17,14 @@ impl SpectrogramSettings {
 
     /// Converts an amplitude to a color in the color gradient.
     pub fn amp_to_color(&self, amp: f64) -> Color32 {
-        let color = self.color_gradient.gradient().eval_continuous(
-            (amp - self.gradient_min_dbm as f64)
-                / (self.gradient_max_dbm as f64 - self.gradient_min_dbm as f64).abs(),
+        let value = colormap::normalize_dbm(
+            amp as f32,
+            f32::from(self.gradient_min_dbm),
+            f32::from(self.gradient_max_dbm),
+            None,
         );
-        Color32::from_rgb(color.r, color.g, color.b)
+        let [r, g, b] = self.color_gradient.colormap().map(value);
+        Color32::from_rgb(r, g, b)
     }
 }
 
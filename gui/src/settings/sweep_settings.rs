@@ -1,65 +1,43 @@
 use rfe::{Frequency, SpectrumAnalyzer, spectrum_analyzer::Config};
 
-use super::FrequencyUnits;
-
 /// The settings of an RF Explorer's sweep.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SweepSettings {
-    pub center_freq: String,
-    pub span: String,
-    pub start_freq: String,
-    pub stop_freq: String,
+    pub center_freq: Frequency,
+    pub span: Frequency,
+    pub start_freq: Frequency,
+    pub stop_freq: Frequency,
     pub rbw: Option<Frequency>,
     pub step_size: Frequency,
     pub len: u16,
-    units: FrequencyUnits,
 }
 
 impl SweepSettings {
-    pub fn new(rfe: &SpectrumAnalyzer, units: FrequencyUnits) -> Self {
+    pub fn new(rfe: &SpectrumAnalyzer) -> Self {
         Self {
-            start_freq: freq_to_string(rfe.start_freq(), units),
-            stop_freq: freq_to_string(rfe.stop_freq(), units),
-            center_freq: freq_to_string(rfe.center_freq(), units),
-            span: freq_to_string(rfe.span(), units),
+            start_freq: rfe.start_freq(),
+            stop_freq: rfe.stop_freq(),
+            center_freq: rfe.center_freq(),
+            span: rfe.span(),
             rbw: rfe.rbw(),
             step_size: rfe.step_size(),
             len: rfe.sweep_len(),
-            units,
         }
     }
 
     pub fn update(&mut self, config: &Config) {
-        self.start_freq = freq_to_string(config.start_freq, self.units);
-        self.stop_freq = freq_to_string(config.stop_freq, self.units);
-        self.center_freq = freq_to_string(config.center_freq, self.units);
-        self.span = freq_to_string(config.span, self.units);
+        self.start_freq = config.start_freq;
+        self.stop_freq = config.stop_freq;
+        self.center_freq = config.center_freq;
+        self.span = config.span;
         self.rbw = config.rbw;
         self.step_size = config.step_size;
         self.len = config.sweep_len;
     }
-}
-
-impl Default for SweepSettings {
-    fn default() -> Self {
-        SweepSettings {
-            center_freq: "0".to_string(),
-            span: "0".to_string(),
-            start_freq: "0".to_string(),
-            stop_freq: "0".to_string(),
-            rbw: Some(Frequency::default()),
-            step_size: Frequency::default(),
-            len: u16::default(),
-            units: FrequencyUnits::Mhz,
-        }
-    }
-}
 
-fn freq_to_string(freq: Frequency, units: FrequencyUnits) -> String {
-    match units {
-        FrequencyUnits::Hz => freq.as_hz().to_string(),
-        FrequencyUnits::Khz => freq.as_khz_f64().to_string(),
-        FrequencyUnits::Mhz => format!("{:.2}", freq.as_mhz_f64()),
-        FrequencyUnits::Ghz => format!("{:.5}", freq.as_ghz_f64()),
+    /// The amount the frequency inputs step by, which is the RBW or the step size if the RF
+    /// Explorer doesn't report its RBW.
+    pub fn freq_step(&self) -> Frequency {
+        self.rbw.unwrap_or(self.step_size)
     }
 }
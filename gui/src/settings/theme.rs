@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
+
+/// Whether the app is dark or light.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Display, EnumIter, Serialize, Deserialize)]
+pub enum Theme {
+    /// Follows the operating system's theme.
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl Theme {
+    /// Resolves the theme to dark or light, using the theme the context is currently showing for
+    /// [`Theme::System`].
+    pub fn resolve(self, ctx: &egui::Context) -> egui::Theme {
+        match self {
+            Theme::System => ctx.theme(),
+            Theme::Dark => egui::Theme::Dark,
+            Theme::Light => egui::Theme::Light,
+        }
+    }
+}
+
+impl From<Theme> for egui::ThemePreference {
+    fn from(theme: Theme) -> Self {
+        match theme {
+            Theme::System => egui::ThemePreference::System,
+            Theme::Dark => egui::ThemePreference::Dark,
+            Theme::Light => egui::ThemePreference::Light,
+        }
+    }
+}
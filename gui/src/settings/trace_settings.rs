@@ -1,4 +1,68 @@
 use egui::Color32;
+use rfe::{Frequency, spectrum_analyzer::SmoothingMethod};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use strum::{Display, EnumCount, EnumIter};
+
+/// How the traces of compared spectrum analyzers are shown next to the main RF Explorer's.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Display, EnumIter)]
+pub enum ComparisonView {
+    /// Every trace is drawn on one plot. Traces with a different span than the main RF
+    /// Explorer's are shown split instead so they don't stretch its axis.
+    #[default]
+    Overlay,
+    /// Each spectrum analyzer's trace is drawn on its own plot with linked cursors.
+    Split,
+}
+
+/// A trace computed from the RF Explorer's sweeps.
+#[derive(
+    Debug, PartialEq, Eq, Hash, Clone, Copy, Display, EnumCount, EnumIter, Serialize, Deserialize,
+)]
+pub enum TraceLayerKind {
+    /// The most recent sweep.
+    Live,
+    /// The highest amplitude measured at each frequency.
+    #[strum(to_string = "Max Hold")]
+    MaxHold,
+    /// The lowest amplitude measured at each frequency.
+    #[strum(to_string = "Min Hold")]
+    MinHold,
+    /// The exponential moving average of the sweeps.
+    Average,
+    /// The difference between the most recent sweep and the baseline sweep.
+    #[strum(to_string = "Baseline Delta")]
+    BaselineDelta,
+}
+
+/// How one of the traces is drawn.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct TraceLayer {
+    pub kind: TraceLayerKind,
+    #[serde(
+        serialize_with = "serialize_color",
+        deserialize_with = "deserialize_color"
+    )]
+    pub color: Color32,
+    pub width: f32,
+    /// Whether the area under the trace is shaded.
+    pub fill: bool,
+    pub is_visible: bool,
+}
+
+impl TraceLayer {
+    pub const MIN_WIDTH: f32 = 0.5;
+    pub const MAX_WIDTH: f32 = 5.0;
+
+    fn new(kind: TraceLayerKind, color: Color32, is_visible: bool) -> Self {
+        Self {
+            kind,
+            color,
+            width: 1.0,
+            fill: false,
+            is_visible,
+        }
+    }
+}
 
 /// The settings of the sweep plot's appearance.
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -7,11 +71,23 @@ pub struct TraceSettings {
     pub y_axis_max: i32,
     pub y_axis_min: i32,
     pub amp_offset: i32,
-    pub current_trace_color: Color32,
-    pub average_trace_color: Color32,
-    pub max_trace_color: Color32,
+    /// The part of the RF Explorer's external offset that the app adds because the RF Explorer
+    /// can't.
+    pub external_app_offset: i32,
+    /// The traces in the order they're drawn, from bottom to top.
+    pub layers: [TraceLayer; TraceLayerKind::COUNT],
     pub average_iterations: u8,
+    pub smoothing_method: Option<SmoothingMethod>,
+    pub smoothing_window: u8,
     pub hide_trace: bool,
+    /// The frequency of the marker that shows the current trace's amplitude, if it's placed.
+    pub marker_freq: Option<Frequency>,
+    /// The frequency of the reference marker the marker's delta is measured from, if delta mode
+    /// is on.
+    pub reference_marker_freq: Option<Frequency>,
+    pub comparison_view: ComparisonView,
+    /// Whether the main RF Explorer's traces are hidden so only compared traces are shown.
+    pub hide_main_device: bool,
 }
 
 impl Default for TraceSettings {
@@ -21,11 +97,97 @@ impl Default for TraceSettings {
             y_axis_max: -40,
             y_axis_min: -120,
             amp_offset: 0,
-            current_trace_color: Color32::from_rgb(46, 204, 64),
-            average_trace_color: Color32::from_rgb(0, 116, 217),
+            external_app_offset: 0,
+            layers: [
+                TraceLayer::new(
+                    TraceLayerKind::BaselineDelta,
+                    Color32::from_rgb(177, 13, 201),
+                    false,
+                ),
+                TraceLayer::new(
+                    TraceLayerKind::MinHold,
+                    Color32::from_rgb(255, 133, 27),
+                    false,
+                ),
+                TraceLayer::new(
+                    TraceLayerKind::MaxHold,
+                    Color32::from_rgb(255, 65, 54),
+                    true,
+                ),
+                TraceLayer::new(
+                    TraceLayerKind::Average,
+                    Color32::from_rgb(0, 116, 217),
+                    true,
+                ),
+                TraceLayer::new(TraceLayerKind::Live, Color32::from_rgb(46, 204, 64), true),
+            ],
             average_iterations: 5,
-            max_trace_color: Color32::from_rgb(255, 65, 54),
+            smoothing_method: None,
+            smoothing_window: 5,
             hide_trace: false,
+            marker_freq: None,
+            reference_marker_freq: None,
+            comparison_view: ComparisonView::default(),
+            hide_main_device: false,
+        }
+    }
+}
+
+impl TraceSettings {
+    /// The offset added to the RF Explorer's amplitudes before they're drawn.
+    pub fn total_amp_offset(&self) -> i32 {
+        self.amp_offset + self.external_app_offset
+    }
+
+    /// Turns delta mode on with the reference marker at the marker's frequency, or turns it off.
+    ///
+    /// Delta mode can't be turned on until the marker is placed.
+    pub fn toggle_marker_delta(&mut self) {
+        self.reference_marker_freq = match self.reference_marker_freq {
+            Some(_) => None,
+            None => self.marker_freq,
+        };
+    }
+
+    /// Removes the marker and the reference marker, which turns delta mode off.
+    pub fn clear_markers(&mut self) {
+        self.marker_freq = None;
+        self.reference_marker_freq = None;
+    }
+
+    /// Returns the traces that are shown.
+    pub fn visible_layers(&self) -> Vec<TraceLayerKind> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.is_visible)
+            .map(|layer| layer.kind)
+            .collect()
+    }
+
+    /// Replaces the layers with saved ones, keeping the defaults of any that weren't saved.
+    pub fn restore_layers(&mut self, saved_layers: &[TraceLayer]) {
+        for layer in &mut self.layers {
+            if let Some(saved_layer) = saved_layers
+                .iter()
+                .find(|saved_layer| saved_layer.kind == layer.kind)
+            {
+                *layer = TraceLayer {
+                    width: saved_layer
+                        .width
+                        .clamp(TraceLayer::MIN_WIDTH, TraceLayer::MAX_WIDTH),
+                    ..*saved_layer
+                };
+            }
         }
     }
 }
+
+/// Saves colors as hex strings like `#2ecc40ff` so settings files are easy to edit by hand.
+fn serialize_color<S: Serializer>(color: &Color32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&color.to_hex())
+}
+
+fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color32, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    Color32::from_hex(&hex).map_err(|_| serde::de::Error::custom(format!("invalid color {hex}")))
+}
@@ -67,3 +67,56 @@ impl Widget for PlotSettingsToggleButton {
             .on_hover_text("Plot Settings")
     }
 }
+
+#[derive(Debug, Default)]
+pub struct EventLogToggleButton {
+    selected: bool,
+    error_count: usize,
+}
+
+impl EventLogToggleButton {
+    pub fn new(selected: bool, error_count: usize) -> Self {
+        Self {
+            selected,
+            error_count,
+        }
+    }
+}
+
+impl Widget for EventLogToggleButton {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let text = if self.error_count > 0 {
+            RichText::new(format!("🗊 {}", self.error_count)).color(Color32::LIGHT_RED)
+        } else {
+            RichText::new("🗊")
+        };
+        Button::selectable(self.selected, text)
+            .ui(ui)
+            .on_hover_text("Event Log")
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RecordButton {
+    is_recording: bool,
+}
+
+impl RecordButton {
+    pub fn new(is_recording: bool) -> Self {
+        Self { is_recording }
+    }
+}
+
+impl Widget for RecordButton {
+    fn ui(self, ui: &mut Ui) -> Response {
+        if self.is_recording {
+            Button::selectable(true, RichText::new("⏺").color(Color32::LIGHT_RED))
+                .ui(ui)
+                .on_hover_text("Stop Recording")
+        } else {
+            Button::selectable(false, "⏺")
+                .ui(ui)
+                .on_hover_text("Record to a Folder...")
+        }
+    }
+}
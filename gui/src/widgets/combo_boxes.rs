@@ -1,7 +1,8 @@
 use egui::{Color32, ComboBox, Response, Ui};
+use rfe::spectrum_analyzer::SmoothingMethod;
 use strum::IntoEnumIterator;
 
-use crate::settings::{ColorGradient, FrequencyUnits};
+use crate::settings::{ColorGradient, FrequencyUnits, Theme};
 
 #[derive(Debug, Default)]
 pub struct UnitsComboBox;
@@ -67,3 +68,48 @@ impl SweepLengthComboBox {
             .inner
     }
 }
+
+#[derive(Debug, Default)]
+pub struct SmoothingMethodComboBox;
+
+impl SmoothingMethodComboBox {
+    pub fn show_ui(
+        ui: &mut Ui,
+        smoothing_method: &mut Option<SmoothingMethod>,
+    ) -> Option<Response> {
+        let method_text =
+            |method: Option<SmoothingMethod>| method.map_or("Off".to_string(), |m| m.to_string());
+        ComboBox::from_id_salt("smoothing-method-combo-box")
+            .selected_text(method_text(*smoothing_method))
+            .show_ui(ui, |ui| {
+                [
+                    None,
+                    Some(SmoothingMethod::MovingAverage),
+                    Some(SmoothingMethod::Median),
+                ]
+                .iter()
+                .map(|method| ui.selectable_value(smoothing_method, *method, method_text(*method)))
+                .reduce(|acc, e| acc | e)
+                .unwrap()
+            })
+            .inner
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ThemeComboBox;
+
+impl ThemeComboBox {
+    pub fn show_ui(ui: &mut Ui, theme: &mut Theme) -> Option<Response> {
+        ComboBox::from_id_salt("theme-combo-box")
+            .selected_text(theme.to_string())
+            .width(60.0)
+            .show_ui(ui, |ui| {
+                Theme::iter()
+                    .map(|option| ui.selectable_value(theme, option, option.to_string()))
+                    .reduce(|acc, e| acc | e)
+                    .unwrap()
+            })
+            .inner
+    }
+}
@@ -0,0 +1,99 @@
+use std::cmp::Reverse;
+
+use egui::{Align, Align2, Context, Key, Layout, Modifiers, TextEdit, Window};
+use strum::IntoEnumIterator;
+
+use crate::actions::Action;
+
+/// A searchable list of the actions that can be run from the keyboard.
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    is_open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        self.is_open = !self.is_open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Shows the palette if it's open and returns the action that was picked.
+    pub fn show(&mut self, ctx: &Context) -> Option<Action> {
+        if !self.is_open {
+            return None;
+        }
+
+        // Actions that match the query equally well stay in the order they're declared in
+        let mut actions: Vec<(Action, u32)> = Action::iter()
+            .filter_map(|action| Some((action, action.fuzzy_score(&self.query)?)))
+            .collect();
+        actions.sort_by_key(|(_, score)| Reverse(*score));
+
+        // The keys are consumed before the text field is shown so it doesn't handle them too
+        let (up, down, enter, escape) = ctx.input_mut(|input| {
+            (
+                input.consume_key(Modifiers::NONE, Key::ArrowUp),
+                input.consume_key(Modifiers::NONE, Key::ArrowDown),
+                input.consume_key(Modifiers::NONE, Key::Enter),
+                input.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down {
+            self.selected += 1;
+        }
+        self.selected = self.selected.min(actions.len().saturating_sub(1));
+
+        let mut picked = None;
+        Window::new("Command Palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, [0.0, 60.0])
+            .fixed_size([360.0, 0.0])
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    TextEdit::singleline(&mut self.query)
+                        .hint_text("Search actions...")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.selected = 0;
+                }
+                ui.separator();
+
+                if actions.is_empty() {
+                    ui.weak("No matching actions");
+                }
+                for (i, (action, _)) in actions.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .selectable_label(i == self.selected, action.name())
+                            .clicked()
+                        {
+                            picked = Some(*action);
+                        }
+                        if let Some(shortcut) = action.shortcuts().first() {
+                            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                ui.weak(ctx.format_shortcut(shortcut));
+                            });
+                        }
+                    });
+                }
+            });
+
+        if enter {
+            picked = actions.get(self.selected).map(|(action, _)| *action);
+        }
+        if picked.is_some() || escape {
+            self.toggle();
+        }
+        picked
+    }
+}
@@ -0,0 +1,207 @@
+use std::ops::RangeInclusive;
+
+use egui::{Align, Color32, Event, Key, Modifiers, TextEdit, Ui, Vec2};
+use rfe::{Frequency, ParseFrequencyError};
+
+use crate::settings::FrequencyUnits;
+
+/// A text field for entering a frequency.
+///
+/// Frequencies can be entered with a unit suffix like "2.44G", "915M", or "433.92 MHz". Numbers
+/// without a unit are in the app's frequency units. While the field doesn't have focus it shows
+/// the current frequency formatted in the app's frequency units.
+///
+/// The arrow keys and the scroll wheel step the frequency up and down. Entered frequencies are
+/// clamped to the allowed range and invalid entries are rejected with a warning next to the field.
+pub struct FrequencyInput<'a> {
+    id_salt: &'a str,
+    freq: &'a mut Frequency,
+    units: FrequencyUnits,
+    range: RangeInclusive<Frequency>,
+    step: Frequency,
+    min_width: f32,
+}
+
+/// The state of a `FrequencyInput` that is kept between frames.
+#[derive(Debug, Clone, Default)]
+struct State {
+    /// The text being edited while the field has focus.
+    text: String,
+    /// A warning about the most recent entry.
+    warning: Option<String>,
+}
+
+impl<'a> FrequencyInput<'a> {
+    pub fn new(id_salt: &'a str, freq: &'a mut Frequency, units: FrequencyUnits) -> Self {
+        Self {
+            id_salt,
+            freq,
+            units,
+            range: Frequency::default()..=Frequency::from_hz(u64::MAX),
+            step: Frequency::from_khz(100),
+            min_width: 0.0,
+        }
+    }
+
+    /// Sets the range that entered frequencies are clamped to.
+    ///
+    /// Ranges that end at 0 Hz, like the range of an RF Explorer that hasn't sent its config yet,
+    /// are ignored.
+    pub fn range(mut self, range: RangeInclusive<Frequency>) -> Self {
+        if *range.end() > Frequency::default() && range.start() <= range.end() {
+            self.range = range;
+        }
+        self
+    }
+
+    /// Sets how much the arrow keys and scroll wheel change the frequency.
+    ///
+    /// A step of 0 Hz is ignored.
+    pub fn step(mut self, step: Frequency) -> Self {
+        if step > Frequency::default() {
+            self.step = step;
+        }
+        self
+    }
+
+    pub fn min_width(mut self, min_width: f32) -> Self {
+        self.min_width = min_width;
+        self
+    }
+
+    /// Shows the field and returns whether a new frequency was entered.
+    pub fn show_ui(self, ui: &mut Ui) -> bool {
+        let id = ui.make_persistent_id(self.id_salt);
+        let mut state = ui
+            .data_mut(|data| data.get_temp::<State>(id))
+            .unwrap_or_default();
+        if !ui.memory(|memory| memory.has_focus(id)) {
+            state.text = format_freq(*self.freq, self.units);
+        }
+
+        let text_color = state.warning.as_ref().map(|_| Color32::YELLOW);
+        let response = ui.add(
+            TextEdit::singleline(&mut state.text)
+                .id(id)
+                .text_color_opt(text_color)
+                .min_size(Vec2::new(self.min_width, 20.0))
+                .horizontal_align(Align::RIGHT),
+        );
+        if let Some(warning) = &state.warning {
+            ui.colored_label(Color32::YELLOW, "⚠")
+                .on_hover_text(warning);
+        }
+
+        let mut entered = None;
+        if response.changed() {
+            // Check the entry as it's typed so mistakes are pointed out before it's sent
+            state.warning = match parse_freq(&state.text, self.units) {
+                Ok(freq) => self.range_warning(freq),
+                Err(error) => Some(error.to_string()),
+            };
+        }
+        if response.has_focus() {
+            let step_up = ui.input_mut(|input| input.consume_key(Modifiers::NONE, Key::ArrowUp));
+            let step_down =
+                ui.input_mut(|input| input.consume_key(Modifiers::NONE, Key::ArrowDown));
+            if step_up || step_down {
+                entered = Some(self.step_freq(&state.text, step_up));
+            }
+        }
+        if response.hovered() {
+            let scroll: f32 = ui.input(|input| {
+                input
+                    .events
+                    .iter()
+                    .filter_map(|event| match event {
+                        Event::MouseWheel { delta, .. } => Some(delta.y),
+                        _ => None,
+                    })
+                    .sum()
+            });
+            if scroll != 0.0 {
+                // Keep the panel from scrolling while the frequency is being stepped
+                ui.input_mut(|input| input.smooth_scroll_delta.y = 0.0);
+                entered = Some(self.step_freq(&state.text, scroll > 0.0));
+            }
+        }
+        if response.lost_focus() {
+            if ui.input(|input| input.key_pressed(Key::Enter)) {
+                match parse_freq(&state.text, self.units) {
+                    Ok(freq) => entered = Some(freq),
+                    Err(error) => {
+                        // Keep the entry so it can be corrected
+                        state.warning = Some(error.to_string());
+                        response.request_focus();
+                    }
+                }
+            } else {
+                state.warning = None;
+            }
+        }
+
+        let is_entered = if let Some(freq) = entered {
+            let clamped_freq = freq.clamp(*self.range.start(), *self.range.end());
+            state.warning = self.range_warning(freq);
+            state.text = format_freq(clamped_freq, self.units);
+            *self.freq = clamped_freq;
+            true
+        } else {
+            false
+        };
+
+        ui.data_mut(|data| data.insert_temp(id, state));
+        is_entered
+    }
+
+    /// Returns the frequency one step above or below the entered frequency.
+    fn step_freq(&self, text: &str, up: bool) -> Frequency {
+        let freq = parse_freq(text, self.units).unwrap_or(*self.freq);
+        if up {
+            Frequency::from_hz(freq.as_hz().saturating_add(self.step.as_hz()))
+        } else {
+            Frequency::from_hz(freq.as_hz().saturating_sub(self.step.as_hz()))
+        }
+    }
+
+    fn range_warning(&self, freq: Frequency) -> Option<String> {
+        if freq < *self.range.start() {
+            Some(format!(
+                "The minimum is {}",
+                format_freq(*self.range.start(), self.units)
+            ))
+        } else if freq > *self.range.end() {
+            Some(format!(
+                "The maximum is {}",
+                format_freq(*self.range.end(), self.units)
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Parses a frequency, using the given units if the text doesn't include any.
+fn parse_freq(text: &str, units: FrequencyUnits) -> Result<Frequency, ParseFrequencyError> {
+    if text.trim().is_empty() || text.contains(|c: char| c.is_ascii_alphabetic()) {
+        text.parse()
+    } else {
+        format!("{text} {units}").parse()
+    }
+}
+
+/// Formats a frequency in the given units without any trailing zeros.
+fn format_freq(freq: Frequency, units: FrequencyUnits) -> String {
+    let value = match units {
+        FrequencyUnits::Hz => freq.as_hz().to_string(),
+        FrequencyUnits::Khz => format!("{:.3}", freq.as_khz_f64()),
+        FrequencyUnits::Mhz => format!("{:.6}", freq.as_mhz_f64()),
+        FrequencyUnits::Ghz => format!("{:.9}", freq.as_ghz_f64()),
+    };
+    let value = if value.contains('.') {
+        value.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &value
+    };
+    format!("{value} {units}")
+}
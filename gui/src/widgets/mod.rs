@@ -1,11 +1,25 @@
 mod buttons;
 mod combo_boxes;
+mod command_palette;
+mod frequency_input;
+mod playback_bar;
+mod readout_bar;
 mod spectrogram;
+mod toasts;
 mod trace;
 
 pub use buttons::{
-    PauseScanningButton, PlotSettingsToggleButton, ResumeScanningButton, RfeSettingsToggleButton,
+    EventLogToggleButton, PauseScanningButton, PlotSettingsToggleButton, RecordButton,
+    ResumeScanningButton, RfeSettingsToggleButton,
 };
-pub use combo_boxes::{SpectrogramColorGradientComboBox, SweepLengthComboBox, UnitsComboBox};
+pub use combo_boxes::{
+    SmoothingMethodComboBox, SpectrogramColorGradientComboBox, SweepLengthComboBox, ThemeComboBox,
+    UnitsComboBox,
+};
+pub use command_palette::CommandPalette;
+pub use frequency_input::FrequencyInput;
+pub use playback_bar::PlaybackBar;
+pub use readout_bar::{ReadoutBar, ReadoutField};
 pub use spectrogram::Spectrogram;
+pub use toasts::{Toasts, event_color};
 pub use trace::Trace;
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use egui::{ComboBox, Panel, Slider, Ui};
+
+use super::{PauseScanningButton, ResumeScanningButton};
+use crate::data::Playback;
+
+/// The controls for a recording being played back: play/pause, a timeline to scrub through it,
+/// and the playback speed.
+pub struct PlaybackBar;
+
+impl PlaybackBar {
+    /// Shows the bar and returns whether playback was closed.
+    pub fn show(ui: &mut Ui, playback: &mut Playback) -> bool {
+        Panel::bottom("playback-bar")
+            .show_inside(ui, |ui| {
+                ui.horizontal(|ui| show_controls(ui, playback)).inner
+            })
+            .inner
+    }
+}
+
+fn show_controls(ui: &mut Ui, playback: &mut Playback) -> bool {
+    let play_pause = if playback.is_playing() {
+        ui.add(PauseScanningButton)
+    } else {
+        ui.add(ResumeScanningButton)
+    };
+    if play_pause.clicked() {
+        playback.toggle_playing();
+    }
+
+    ui.label(format!(
+        "{} / {}",
+        format_duration(playback.position()),
+        format_duration(playback.duration())
+    ));
+
+    let mut speed = playback.speed();
+    ComboBox::from_id_salt("playback-speed-combo-box")
+        .selected_text(format!("{speed}x"))
+        .width(60.0)
+        .show_ui(ui, |ui| {
+            for option in Playback::SPEEDS {
+                ui.selectable_value(&mut speed, option, format!("{option}x"));
+            }
+        });
+    playback.set_speed(speed);
+
+    let close_clicked = ui
+        .button("Close")
+        .on_hover_text(format!("Stop playing {}", playback.name()))
+        .clicked();
+
+    // Let the timeline fill the rest of the bar
+    ui.spacing_mut().slider_width = ui.available_width().max(100.0);
+    let mut position_secs = playback.position().as_secs_f64();
+    let timeline = ui.add(
+        Slider::new(&mut position_secs, 0.0..=playback.duration().as_secs_f64()).show_value(false),
+    );
+    if timeline.changed() {
+        playback.seek(Duration::from_secs_f64(position_secs));
+    }
+    playback.set_scrubbing(timeline.dragged());
+
+    close_clicked
+}
+
+/// Formats a duration as minutes and seconds, like `12:05`.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -0,0 +1,204 @@
+use std::time::{Duration, Instant};
+
+use egui::{Color32, Panel, RichText, Sense, TextStyle, Ui};
+
+use crate::{
+    data::RfeInfo,
+    settings::{FrequencyUnits, SweepSettings},
+};
+
+/// How long a value stays highlighted after it changes.
+const HIGHLIGHT_DURATION: Duration = Duration::from_secs(1);
+const HIGHLIGHT_COLOR: Color32 = Color32::YELLOW;
+const FIELD_SPACING: f32 = 16.0;
+
+/// A value in the readout bar, which can be jumped to in the RF Explorer settings panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadoutField {
+    Rbw,
+    DspMode,
+    SweepPoints,
+    SweepRate,
+    RefLevel,
+    InputStage,
+}
+
+impl ReadoutField {
+    /// The fields in the order they're shown from left to right.
+    const ALL: [ReadoutField; 6] = [
+        ReadoutField::Rbw,
+        ReadoutField::DspMode,
+        ReadoutField::SweepPoints,
+        ReadoutField::SweepRate,
+        ReadoutField::RefLevel,
+        ReadoutField::InputStage,
+    ];
+
+    /// Fields with lower priorities are elided first when the bar is too narrow to show them all.
+    fn priority(self) -> u8 {
+        match self {
+            ReadoutField::Rbw => 5,
+            ReadoutField::RefLevel => 4,
+            ReadoutField::SweepRate => 3,
+            ReadoutField::SweepPoints => 2,
+            ReadoutField::DspMode => 1,
+            ReadoutField::InputStage => 0,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ReadoutField::Rbw => "RBW",
+            ReadoutField::DspMode => "DSP",
+            ReadoutField::SweepPoints => "Points",
+            ReadoutField::SweepRate => "Rate",
+            ReadoutField::RefLevel => "Ref",
+            ReadoutField::InputStage => "Input",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FieldState {
+    field: ReadoutField,
+    value: String,
+    changed_at: Option<Instant>,
+}
+
+/// A strip of the RF Explorer's current settings shown under the plot, like the status bar of a
+/// bench spectrum analyzer.
+#[derive(Debug, Default)]
+pub struct ReadoutBar {
+    fields: Vec<FieldState>,
+}
+
+impl ReadoutBar {
+    /// Shows the bar and returns the field that was clicked.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        sweep_settings: &SweepSettings,
+        rfe_info: &RfeInfo,
+        sweep_rate_hz: Option<f64>,
+        units: FrequencyUnits,
+    ) -> Option<ReadoutField> {
+        self.update(sweep_settings, rfe_info, sweep_rate_hz, units);
+        Panel::bottom("readout-bar")
+            .show_inside(ui, |ui| ui.horizontal(|ui| self.show_fields(ui)).inner)
+            .inner
+    }
+
+    fn update(
+        &mut self,
+        sweep_settings: &SweepSettings,
+        rfe_info: &RfeInfo,
+        sweep_rate_hz: Option<f64>,
+        units: FrequencyUnits,
+    ) {
+        let now = Instant::now();
+        for field in ReadoutField::ALL {
+            let value = match field {
+                ReadoutField::Rbw => sweep_settings.rbw.map_or_else(
+                    || "N/A".to_string(),
+                    |rbw| format!("{} {units}", units.freq_f64(rbw)),
+                ),
+                ReadoutField::DspMode => option_to_string(rfe_info.dsp_mode),
+                ReadoutField::SweepPoints => sweep_settings.len.to_string(),
+                ReadoutField::SweepRate => {
+                    sweep_rate_hz.map_or_else(|| "N/A".to_string(), |hz| format!("{hz:.1} Hz"))
+                }
+                ReadoutField::RefLevel => format!("{} dBm", rfe_info.max_amp_dbm),
+                ReadoutField::InputStage => option_to_string(rfe_info.input_stage),
+            };
+            match self.fields.iter_mut().find(|state| state.field == field) {
+                Some(state) if state.value != value => {
+                    // The sweep rate is measured rather than set, so it drifts a little with every
+                    // sweep and highlighting it would only be distracting
+                    if field != ReadoutField::SweepRate {
+                        state.changed_at = Some(now);
+                    }
+                    state.value = value;
+                }
+                Some(_) => (),
+                None => self.fields.push(FieldState {
+                    field,
+                    value,
+                    changed_at: None,
+                }),
+            }
+        }
+    }
+
+    fn show_fields(&self, ui: &mut Ui) -> Option<ReadoutField> {
+        let font_id = TextStyle::Body.resolve(ui.style());
+        let widths: Vec<f32> = self
+            .fields
+            .iter()
+            .map(|state| {
+                ui.painter()
+                    .layout_no_wrap(field_text(state), font_id.clone(), Color32::WHITE)
+                    .size()
+                    .x
+                    + FIELD_SPACING
+            })
+            .collect();
+
+        // Drop the lowest priority fields until the rest fit, keeping them in their usual order
+        let mut shown = vec![true; self.fields.len()];
+        let mut by_priority: Vec<usize> = (0..self.fields.len()).collect();
+        by_priority.sort_by_key(|&i| self.fields[i].field.priority());
+        let mut total_width: f32 = widths.iter().sum();
+        for i in by_priority {
+            if total_width <= ui.available_width() {
+                break;
+            }
+            shown[i] = false;
+            total_width -= widths[i];
+        }
+
+        let mut clicked = None;
+        let mut is_highlighted = false;
+        for (state, _) in self.fields.iter().zip(&shown).filter(|(_, shown)| **shown) {
+            let mut text = RichText::new(field_text(state));
+            if state
+                .changed_at
+                .is_some_and(|changed_at| changed_at.elapsed() < HIGHLIGHT_DURATION)
+            {
+                text = text.color(HIGHLIGHT_COLOR);
+                is_highlighted = true;
+            }
+            let response = ui
+                .add(egui::Label::new(text).sense(Sense::click()))
+                .on_hover_text("Show in the RF Explorer settings");
+            if response.clicked() {
+                clicked = Some(state.field);
+            }
+            ui.add_space(FIELD_SPACING - ui.spacing().item_spacing.x);
+        }
+
+        let elided: Vec<String> = self
+            .fields
+            .iter()
+            .zip(&shown)
+            .filter(|(_, shown)| !**shown)
+            .map(|(state, _)| field_text(state))
+            .collect();
+        if !elided.is_empty() {
+            ui.weak("…").on_hover_text(elided.join("\n"));
+        }
+
+        // Keep repainting so the highlights fade once they expire
+        if is_highlighted {
+            ui.ctx().request_repaint_after(HIGHLIGHT_DURATION);
+        }
+        clicked
+    }
+}
+
+fn field_text(state: &FieldState) -> String {
+    format!("{} {}", state.field.name(), state.value)
+}
+
+fn option_to_string(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| "N/A".to_string(), |value| value.to_string())
+}
@@ -8,7 +8,7 @@ pub struct Spectrogram;
 impl Spectrogram {
     pub fn show(
         ui: &mut Ui,
-        spectrogram_data: &mut SpectrogramData,
+        spectrogram_data: &SpectrogramData,
         units: FrequencyUnits,
     ) -> PlotResponse<()> {
         let start = units.freq_f64(spectrogram_data.start_freq());
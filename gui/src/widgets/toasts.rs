@@ -0,0 +1,40 @@
+use egui::{Align2, Color32, Frame, Id, RichText, Ui};
+
+use crate::data::{EventKind, EventLog};
+
+/// Shows recent events from the event log in the bottom-right corner of the window.
+pub struct Toasts;
+
+impl Toasts {
+    pub fn show(ui: &mut Ui, event_log: &EventLog) {
+        let toasts = event_log.toasts();
+        if toasts.is_empty() {
+            return;
+        }
+
+        egui::Area::new(Id::new("event-toasts"))
+            .anchor(Align2::RIGHT_BOTTOM, [-10.0, -40.0])
+            .interactable(false)
+            .show(ui.ctx(), |ui| {
+                for event in toasts.iter().rev() {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(RichText::new(&event.message).color(event_color(event.kind)));
+                    });
+                }
+            });
+
+        // Keep repainting so the toasts disappear once they expire
+        ui.ctx()
+            .request_repaint_after(std::time::Duration::from_millis(500));
+    }
+}
+
+/// Gets the color used to display events of the given kind.
+pub fn event_color(kind: EventKind) -> Color32 {
+    match kind {
+        EventKind::Error => Color32::LIGHT_RED,
+        EventKind::Connection => Color32::LIGHT_GRAY,
+        EventKind::Recording => Color32::LIGHT_BLUE,
+        EventKind::Export => Color32::LIGHT_GREEN,
+    }
+}
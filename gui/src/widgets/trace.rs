@@ -1,25 +1,45 @@
-use egui::{Ui, Vec2};
-use egui_plot::{Legend, Line, Plot, PlotBounds, PlotPoint, PlotPoints, PlotResponse};
+use egui::{Align2, Color32, Id, Ui, Vec2, Vec2b};
+use egui_plot::{
+    Corner, Legend, Line, LineStyle, Plot, PlotBounds, PlotPoint, PlotPoints, PlotResponse, PlotUi,
+    Points, Text, VLine,
+};
 use rfe::Frequency;
 
 use crate::{
-    data::TraceData,
-    settings::{FrequencyUnits, TraceSettings},
+    data::{ComparedTrace, TraceData},
+    settings::{FrequencyUnits, TraceLayer, TraceLayerKind, TraceSettings},
 };
 
+/// How opaque the area under a filled trace is.
+const FILL_ALPHA: f32 = 0.15;
+const REFERENCE_MARKER_COLOR: Color32 = Color32::LIGHT_BLUE;
+/// The color of markers, and the delta between them, that are outside of the current span.
+const OUT_OF_SPAN_COLOR: Color32 = Color32::GRAY;
+
 pub struct Trace;
 
 impl Trace {
+    /// Shows the main RF Explorer's traces, if there are any, and the compared traces on one plot.
+    ///
+    /// Plots shown with the same `cursor_group` have linked cursors, so the split view's plots
+    /// point at the same frequency.
     pub fn show(
         ui: &mut Ui,
-        trace_data: &TraceData,
+        id_salt: impl std::hash::Hash,
+        trace_data: Option<&TraceData>,
+        compared_traces: &[ComparedTrace],
         trace_settings: &TraceSettings,
         units: FrequencyUnits,
     ) -> PlotResponse<()> {
-        Plot::new("trace")
+        Plot::new(id_salt)
+            .link_cursor(Id::new("trace-cursor-group"), Vec2b::new(true, false))
             .x_axis_label(format!("Frequency ({units})"))
             .y_axis_label("Amplitude (dBm)")
-            .legend(Legend::default())
+            .legend(
+                Legend::default()
+                    .position(Corner::RightTop)
+                    .follow_insertion_order(true),
+            )
             .allow_drag(false)
             .allow_zoom(false)
             .allow_scroll(false)
@@ -35,44 +55,234 @@ impl Trace {
                     x: true,
                     y: trace_settings.autoscale_y_axis,
                 });
-                plot_ui.line(
-                    Line::new(
-                        "Max",
-                        sweep_to_plot_points(trace_data.max(), trace_settings.amp_offset, units),
-                    )
-                    .color(trace_settings.max_trace_color),
-                );
-                plot_ui.line(
-                    Line::new(
-                        "Average",
-                        sweep_to_plot_points(
-                            trace_data.average(),
-                            trace_settings.amp_offset,
-                            units,
-                        ),
-                    )
-                    .color(trace_settings.average_trace_color),
-                );
-                plot_ui.line(
-                    Line::new(
-                        "Current",
-                        sweep_to_plot_points(
-                            trace_data.current(),
-                            trace_settings.amp_offset,
-                            units,
-                        ),
-                    )
-                    .color(trace_settings.current_trace_color),
-                );
+                for compared_trace in compared_traces {
+                    plot_ui.line(
+                        Line::new(
+                            compared_trace.name.as_str(),
+                            sweep_to_plot_points(
+                                &smooth_trace(compared_trace.trace_data.current(), trace_settings),
+                                trace_settings.amp_offset,
+                                units,
+                            ),
+                        )
+                        .color(compared_trace.color),
+                    );
+                }
+                let Some(trace_data) = trace_data else {
+                    return;
+                };
+                for layer in trace_settings
+                    .layers
+                    .iter()
+                    .filter(|layer| layer.is_visible)
+                {
+                    show_layer(plot_ui, layer, trace_data, trace_settings, units);
+                }
+                let marker = trace_settings
+                    .marker_freq
+                    .and_then(|freq| MarkerPoint::new(freq, trace_data, trace_settings, units));
+                let reference_marker = trace_settings
+                    .reference_marker_freq
+                    .and_then(|freq| MarkerPoint::new(freq, trace_data, trace_settings, units));
+                if let Some(reference_marker) = &reference_marker {
+                    show_marker(
+                        plot_ui,
+                        "Reference Marker",
+                        REFERENCE_MARKER_COLOR,
+                        reference_marker,
+                        units,
+                    );
+                }
+                if let Some(marker) = &marker {
+                    show_marker(plot_ui, "Marker", Color32::YELLOW, marker, units);
+                }
+                if let (Some(marker), Some(reference_marker)) = (&marker, &reference_marker) {
+                    show_marker_delta(plot_ui, marker, reference_marker);
+                }
             })
     }
 }
 
+/// Draws one of the main RF Explorer's traces.
+fn show_layer(
+    plot_ui: &mut PlotUi<'_>,
+    layer: &TraceLayer,
+    trace_data: &TraceData,
+    trace_settings: &TraceSettings,
+    units: FrequencyUnits,
+) {
+    let points = match layer.kind {
+        TraceLayerKind::Live => sweep_to_plot_points(
+            &smooth_trace(trace_data.current(), trace_settings),
+            trace_settings.total_amp_offset(),
+            units,
+        ),
+        // The delta is a difference between two amplitudes, so the offset cancels out
+        TraceLayerKind::BaselineDelta => {
+            sweep_to_plot_points(trace_data.layer(layer.kind), 0, units)
+        }
+        kind => sweep_to_plot_points(
+            trace_data.layer(kind),
+            trace_settings.total_amp_offset(),
+            units,
+        ),
+    };
+    let name = match layer.kind {
+        TraceLayerKind::BaselineDelta => format!("{} (dB)", layer.kind),
+        kind => kind.to_string(),
+    };
+    let mut line = Line::new(name, points)
+        .color(layer.color)
+        .width(layer.width);
+    if layer.fill {
+        // Fill down to the bottom of the y-axis, or to 0 dB for the delta
+        let fill_to = match layer.kind {
+            TraceLayerKind::BaselineDelta => 0.0,
+            _ => trace_settings.y_axis_min as f32,
+        };
+        line = line.fill(fill_to).fill_alpha(FILL_ALPHA);
+    }
+    plot_ui.line(line);
+}
+
+/// The point of the current trace that's closest to a marker's frequency.
+struct MarkerPoint {
+    freq: Frequency,
+    point: PlotPoint,
+    /// Whether the marker's frequency is within the current trace's span.
+    ///
+    /// Markers outside of the span are drawn at the nearest edge of the trace.
+    is_in_span: bool,
+}
+
+impl MarkerPoint {
+    fn new(
+        marker_freq: Frequency,
+        trace_data: &TraceData,
+        trace_settings: &TraceSettings,
+        units: FrequencyUnits,
+    ) -> Option<Self> {
+        let trace = trace_data.current();
+        let (freq, amp) = trace
+            .iter()
+            .min_by_key(|(freq, _)| freq.abs_diff(marker_freq))?;
+        let is_in_span = trace
+            .first()
+            .zip(trace.last())
+            .is_some_and(|(first, last)| (first.0..=last.0).contains(&marker_freq));
+        Some(MarkerPoint {
+            freq: *freq,
+            point: PlotPoint::new(
+                units.freq_f64(*freq),
+                *amp + f64::from(trace_settings.total_amp_offset()),
+            ),
+            is_in_span,
+        })
+    }
+
+    fn color(&self, color: Color32) -> Color32 {
+        if self.is_in_span {
+            color
+        } else {
+            OUT_OF_SPAN_COLOR
+        }
+    }
+}
+
+/// Draws a marker at the point of the current trace that's closest to the marker's frequency.
+fn show_marker(
+    plot_ui: &mut PlotUi<'_>,
+    name: &str,
+    color: Color32,
+    marker: &MarkerPoint,
+    units: FrequencyUnits,
+) {
+    let color = marker.color(color);
+    let point = marker.point;
+    plot_ui.vline(VLine::new(name, point.x).color(color));
+    plot_ui.points(
+        Points::new(name, vec![[point.x, point.y]])
+            .color(color)
+            .radius(4.0),
+    );
+    plot_ui.text(
+        Text::new(
+            name,
+            point,
+            format!("{:.3} {units}\n{:.1} dBm", point.x, point.y),
+        )
+        .color(color)
+        .anchor(Align2::LEFT_BOTTOM),
+    );
+}
+
+/// Connects the reference marker to the marker and labels the line with the differences in
+/// frequency and amplitude between them.
+///
+/// The differences are greyed out if either marker is outside of the current span, because the
+/// marker is then drawn at the edge of the trace instead of at its frequency.
+fn show_marker_delta(plot_ui: &mut PlotUi<'_>, marker: &MarkerPoint, reference: &MarkerPoint) {
+    let color = if marker.is_in_span && reference.is_in_span {
+        Color32::YELLOW
+    } else {
+        OUT_OF_SPAN_COLOR
+    };
+    let (from, to) = (reference.point, marker.point);
+    plot_ui.line(
+        Line::new("Marker Delta", vec![[from.x, from.y], [to.x, to.y]])
+            .color(color)
+            .style(LineStyle::dashed_dense()),
+    );
+    plot_ui.text(
+        Text::new(
+            "Marker Delta",
+            PlotPoint::new((from.x + to.x) / 2.0, from.y.max(to.y)),
+            format_marker_delta(marker, reference),
+        )
+        .color(color)
+        .anchor(Align2::CENTER_BOTTOM),
+    );
+}
+
+/// Formats the frequency difference in engineering units and the amplitude difference in dB,
+/// both signed.
+fn format_marker_delta(marker: &MarkerPoint, reference: &MarkerPoint) -> String {
+    let freq_sign = if marker.freq < reference.freq {
+        '-'
+    } else {
+        '+'
+    };
+    let freq_delta = marker.freq.abs_diff(reference.freq).format_engineering(3);
+    let amp_delta = marker.point.y - reference.point.y;
+    format!("Δf {freq_sign}{freq_delta}\nΔ {amp_delta:+.1} dB")
+}
+
+/// Smooths the trace using the smoothing method selected in the trace settings.
+///
+/// The trace is returned unchanged if smoothing is off or the trace is shorter than the window.
+fn smooth_trace(
+    trace: &[(Frequency, f64)],
+    trace_settings: &TraceSettings,
+) -> Vec<(Frequency, f64)> {
+    let Some(smoothing_method) = trace_settings.smoothing_method else {
+        return trace.to_vec();
+    };
+    let amps_dbm: Vec<f32> = trace.iter().map(|(_, amp)| *amp as f32).collect();
+    match smoothing_method.apply(&amps_dbm, usize::from(trace_settings.smoothing_window)) {
+        Ok(smoothed_amps_dbm) => trace
+            .iter()
+            .zip(smoothed_amps_dbm)
+            .map(|((freq, _), amp)| (*freq, f64::from(amp)))
+            .collect(),
+        Err(_) => trace.to_vec(),
+    }
+}
+
 fn sweep_to_plot_points(
     sweep: &[(Frequency, f64)],
     offset: i32,
     units: FrequencyUnits,
-) -> PlotPoints<'_> {
+) -> PlotPoints<'static> {
     PlotPoints::Owned(
         sweep
             .iter()
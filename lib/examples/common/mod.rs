@@ -0,0 +1,128 @@
+//! Helpers shared by the examples.
+//!
+//! Every example connects to real hardware by default and exits without an error when none is
+//! found. Spectrum analyzer examples can be run against a simulated RF Explorer instead by
+//! passing `--simulate` and enabling the `simulator` feature:
+//!
+//! ```text
+//! cargo run --example scan_band --features simulator -- --simulate
+//! ```
+
+#![allow(dead_code)]
+
+use std::env;
+
+use rfe::SpectrumAnalyzer;
+
+/// Returns whether the example was run with `--simulate`.
+pub fn simulate() -> bool {
+    env::args().skip(1).any(|arg| arg == "--simulate")
+}
+
+/// Returns the example's arguments, leaving out flags like `--simulate`.
+pub fn args() -> Vec<String> {
+    env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect()
+}
+
+/// Connects to the first RF Explorer spectrum analyzer, or to a simulated one if the example was
+/// run with `--simulate`.
+///
+/// Prints why and returns `None` if there's nothing to connect to.
+pub fn connect_spectrum_analyzer() -> Option<SpectrumAnalyzer> {
+    if simulate() {
+        return simulated_spectrum_analyzer("SIMULATOR0000001");
+    }
+
+    let rfe = SpectrumAnalyzer::connect();
+    if rfe.is_none() {
+        eprintln!(
+            "No RF Explorer spectrum analyzer was found. Pass --simulate to use a simulated one."
+        );
+    }
+    rfe
+}
+
+/// Returns `true` and prints why if the example was run with `--simulate`.
+///
+/// The simulator only emulates spectrum analyzers, so examples that use a signal generator can't
+/// be simulated.
+pub fn simulation_unsupported() -> bool {
+    let simulate = simulate();
+    if simulate {
+        eprintln!(
+            "The simulator only emulates spectrum analyzers, so this example needs an RF Explorer signal generator."
+        );
+    }
+    simulate
+}
+
+/// Starts a simulated spectrum analyzer with a couple of Wi-Fi-like signals on a local TCP socket
+/// and connects to it.
+#[cfg(feature = "simulator")]
+pub fn simulated_spectrum_analyzer(serial_number: &str) -> Option<SpectrumAnalyzer> {
+    use rfe::{
+        Frequency,
+        simulator::{DeviceScenario, Scenario, SweepScenario, Tone},
+    };
+
+    let scenario = Scenario {
+        device: DeviceScenario {
+            serial_number: serial_number.to_string(),
+            ..DeviceScenario::default()
+        },
+        sweeps: SweepScenario {
+            tones: vec![
+                Tone {
+                    freq: Frequency::from_mhz(2_437),
+                    amp_dbm: -45.0,
+                    width: Frequency::from_mhz(1),
+                },
+                Tone {
+                    freq: Frequency::from_mhz(2_462),
+                    amp_dbm: -60.0,
+                    width: Frequency::from_mhz(1),
+                },
+            ],
+            ..SweepScenario::default()
+        },
+        ..Scenario::default()
+    };
+
+    match start_simulator(scenario) {
+        Ok(rfe) => Some(rfe),
+        Err(error) => {
+            eprintln!("Failed to start a simulated RF Explorer: {error}");
+            None
+        }
+    }
+}
+
+/// Serves a simulator on a local TCP socket and connects to it like it's an RF Explorer.
+#[cfg(feature = "simulator")]
+fn start_simulator(
+    scenario: rfe::simulator::Scenario,
+) -> Result<SpectrumAnalyzer, Box<dyn std::error::Error>> {
+    use std::{net::TcpListener, thread};
+
+    use rfe::simulator::{self, Simulator, TcpPort};
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    thread::spawn(move || {
+        let (stream, _) = listener.accept()?;
+        simulator::serve(Simulator::new(scenario), stream.try_clone()?, stream)
+    });
+    let port = TcpPort::connect(addr)?;
+    Ok(SpectrumAnalyzer::connect_with_transport(Box::new(port))?)
+}
+
+#[cfg(not(feature = "simulator"))]
+pub fn simulated_spectrum_analyzer(_serial_number: &str) -> Option<SpectrumAnalyzer> {
+    eprintln!(
+        "Simulating an RF Explorer requires the simulator feature, like `cargo run --example <name> --features simulator -- --simulate`."
+    );
+    None
+}
@@ -0,0 +1,142 @@
+//! Talks to an RF Explorer running custom firmware that sends a message this crate doesn't know
+//! about, by implementing [`MessageContainer`] for a message enum that wraps the standard messages
+//! and the custom one.
+//!
+//! The firmware in this example reports its temperature with lines like `#TEMP:+23.5`.
+//!
+//! ```text
+//! cargo run --example custom_device
+//! ```
+
+mod common;
+
+use std::{
+    borrow::Cow,
+    error::Error,
+    str,
+    sync::{Condvar, Mutex},
+    time::Duration,
+};
+
+use rfe::{
+    Command, ConnectionError, ConnectionResult, Device, MessageContainer, MessageParseError,
+    spectrum_analyzer::Config,
+};
+
+const TEMPERATURE_PREFIX: &[u8] = b"#TEMP:";
+
+/// The messages sent by the custom firmware.
+#[derive(Debug)]
+enum Message {
+    Config(Config),
+    /// The custom message, with the temperature in °C.
+    Temperature(f32),
+}
+
+impl<'a> TryFrom<&'a [u8]> for Message {
+    type Error = MessageParseError<'a>;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self, Self::Error> {
+        if bytes.starts_with(Config::PREFIX) {
+            Ok(Message::Config(Config::try_from(bytes)?))
+        } else if let Some(temperature) = bytes.strip_prefix(TEMPERATURE_PREFIX) {
+            // Wait for the rest of the line so a temperature isn't parsed from part of it
+            let temperature = temperature
+                .strip_suffix(b"\r\n")
+                .ok_or(MessageParseError::Incomplete)?;
+            str::from_utf8(temperature)
+                .ok()
+                .and_then(|temperature| temperature.parse().ok())
+                .map(Message::Temperature)
+                .ok_or(MessageParseError::Invalid)
+        } else {
+            // Sweeps and the other messages aren't needed by this example, so they're skipped
+            Err(MessageParseError::UnknownMessageType)
+        }
+    }
+}
+
+/// Keeps the latest of each message and wakes the threads waiting for them.
+#[derive(Debug, Default)]
+struct Messages {
+    config: Mutex<Option<Config>>,
+    temperature: Mutex<Option<f32>>,
+    /// Notified whenever a message is cached.
+    received: Condvar,
+}
+
+impl Messages {
+    /// Waits until a message different from the one that's stored now is received and returns it.
+    fn wait_for_next<T: Copy + PartialEq>(
+        &self,
+        message: &Mutex<Option<T>>,
+        timeout: Duration,
+    ) -> Option<T> {
+        let previous = *message.lock().unwrap();
+        let (latest, _) = self
+            .received
+            .wait_timeout_while(message.lock().unwrap(), timeout, |latest| {
+                *latest == previous
+            })
+            .unwrap();
+        *latest
+    }
+}
+
+impl MessageContainer for Messages {
+    type Message = Message;
+
+    // Called on the device's reader thread, so it only stores the message
+    fn cache_message(&self, message: Message) {
+        match message {
+            Message::Config(config) => *self.config.lock().unwrap() = Some(config),
+            Message::Temperature(temperature) => {
+                *self.temperature.lock().unwrap() = Some(temperature);
+            }
+        }
+        self.received.notify_all();
+    }
+
+    // Every RF Explorer replies to the initialization command with its config
+    fn wait_for_device_info(&self) -> ConnectionResult<()> {
+        let (config, _) = self
+            .received
+            .wait_timeout_while(
+                self.config.lock().unwrap(),
+                Duration::from_secs(2),
+                |config| config.is_none(),
+            )
+            .unwrap();
+        if config.is_some() {
+            Ok(())
+        } else {
+            Err(ConnectionError::DeviceInfoNotReceived)
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    if common::simulation_unsupported() {
+        return Ok(());
+    }
+    let Some(device) = Device::<Messages>::connect(Cow::from(Command::RequestConfig)) else {
+        eprintln!("No RF Explorer was found.");
+        return Ok(());
+    };
+
+    let messages = device.messages();
+    if let Some(config) = messages.config.lock().unwrap().as_ref() {
+        println!(
+            "Connected on {}: {:.3}-{:.3} MHz",
+            device.port_name(),
+            config.start_freq.as_mhz_f64(),
+            config.stop_freq.as_mhz_f64()
+        );
+    }
+
+    match messages.wait_for_next(&messages.temperature, Duration::from_secs(5)) {
+        Some(temperature) => println!("Temperature: {temperature:.1} °C"),
+        None => println!("The firmware didn't report its temperature"),
+    }
+    Ok(())
+}
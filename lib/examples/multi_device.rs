@@ -0,0 +1,46 @@
+//! Connects to every spectrum analyzer at once and prints the strongest signal each one sees.
+//!
+//! ```text
+//! cargo run --example multi_device
+//! ```
+
+mod common;
+
+use std::{error::Error, time::Duration};
+
+use rfe::SpectrumAnalyzer;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let analyzers: Vec<SpectrumAnalyzer> = if common::simulate() {
+        ["SIMULATOR0000001", "SIMULATOR0000002"]
+            .into_iter()
+            .filter_map(common::simulated_spectrum_analyzer)
+            .collect()
+    } else {
+        SpectrumAnalyzer::connect_all()
+    };
+    if analyzers.is_empty() {
+        eprintln!(
+            "No RF Explorer spectrum analyzers were found. Pass --simulate to use simulated ones."
+        );
+        return Ok(());
+    }
+
+    for rfe in &analyzers {
+        let id = rfe
+            .device_id()
+            .map_or_else(|| "Unknown device".to_string(), |id| id.to_string());
+        let sweep = rfe.wait_for_next_sweep_with_timeout(Duration::from_secs(5))?;
+        let Some((i, amp_dbm)) = sweep
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            continue;
+        };
+        let freq = rfe.start_freq() + rfe.step_size() * i as u64;
+        println!("{id}: {amp_dbm:.1} dBm at {:.3} MHz", freq.as_mhz_f64());
+    }
+
+    Ok(())
+}
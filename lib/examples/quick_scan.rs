@@ -0,0 +1,17 @@
+use std::time::Duration;
+
+use rfe::Frequency;
+
+fn main() -> rfe::Result<()> {
+    let points = rfe::measure_blocking(
+        Frequency::from_mhz(88),
+        Frequency::from_mhz(108),
+        Duration::from_secs(5),
+    )?;
+
+    for (freq, amp_dbm) in points {
+        println!("{:.3} MHz: {amp_dbm} dBm", freq.as_mhz_f64());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+//! Configures a spectrum analyzer to scan a band and prints the strongest signals in it.
+//!
+//! ```text
+//! cargo run --example scan_band -- [START] [STOP]
+//! cargo run --example scan_band -- 2.4G 2.5G
+//! ```
+
+mod common;
+
+use std::{error::Error, time::Duration};
+
+use rfe::Frequency;
+
+const PEAKS: usize = 5;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = common::args();
+    let start = match args.first() {
+        Some(start) => start.parse()?,
+        None => Frequency::from_mhz(2_400),
+    };
+    let stop = match args.get(1) {
+        Some(stop) => stop.parse()?,
+        None => Frequency::from_mhz(2_500),
+    };
+
+    let Some(rfe) = common::connect_spectrum_analyzer() else {
+        return Ok(());
+    };
+    rfe.set_start_stop(start, stop)?;
+    let sweep = rfe.wait_for_sweep_matching_current_config(Duration::from_secs(10))?;
+    let (start, step, stop) = (rfe.start_freq(), rfe.step_size(), rfe.stop_freq());
+
+    // Keep the points that are louder than their neighbors, strongest first
+    let mut peaks: Vec<(usize, f32)> = sweep
+        .iter()
+        .enumerate()
+        .filter(|&(i, amp_dbm)| {
+            let left = i.checked_sub(1).map_or(f32::MIN, |i| sweep[i]);
+            let right = sweep.get(i + 1).copied().unwrap_or(f32::MIN);
+            *amp_dbm > left && *amp_dbm >= right
+        })
+        .map(|(i, amp_dbm)| (i, *amp_dbm))
+        .collect();
+    peaks.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    println!(
+        "Strongest signals from {:.3} MHz to {:.3} MHz:",
+        start.as_mhz_f64(),
+        stop.as_mhz_f64()
+    );
+    for (i, amp_dbm) in peaks.into_iter().take(PEAKS) {
+        let freq = start + step * i as u64;
+        println!("{:>12.3} MHz: {amp_dbm:.1} dBm", freq.as_mhz_f64());
+    }
+
+    Ok(())
+}
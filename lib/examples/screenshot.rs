@@ -0,0 +1,49 @@
+//! Captures the RF Explorer's screen and prints it to the terminal.
+//!
+//! ```text
+//! cargo run --example screenshot
+//! ```
+
+mod common;
+
+use std::{error::Error, time::Duration};
+
+use rfe::ScreenData;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let Some(rfe) = common::connect_spectrum_analyzer() else {
+        return Ok(());
+    };
+
+    rfe.enable_dump_screen()?;
+    let screen_data = rfe.wait_for_next_screen_data_with_timeout(Duration::from_secs(5));
+    rfe.disable_dump_screen()?;
+
+    match screen_data {
+        Ok(screen_data) => print_screen(&screen_data),
+        Err(rfe::Error::TimedOut(_)) => {
+            eprintln!(
+                "The RF Explorer didn't send its screen. The simulator doesn't emulate the screen."
+            )
+        }
+        Err(error) => return Err(error.into()),
+    }
+    Ok(())
+}
+
+/// Prints two rows of pixels per line using half block characters.
+fn print_screen(screen_data: &ScreenData) {
+    for y in (0..ScreenData::HEIGHT_PX).step_by(2) {
+        let line: String = (0..ScreenData::WIDTH_PX)
+            .map(
+                |x| match (screen_data.get_pixel(x, y), screen_data.get_pixel(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                },
+            )
+            .collect();
+        println!("{line}");
+    }
+}
@@ -0,0 +1,37 @@
+//! Outputs a CW signal from a signal generator for a few seconds.
+//!
+//! ```text
+//! cargo run --example signal_generator_cw -- [FREQUENCY]
+//! cargo run --example signal_generator_cw -- 433.92M
+//! ```
+
+mod common;
+
+use std::{error::Error, thread, time::Duration};
+
+use rfe::{
+    Frequency, SignalGenerator,
+    signal_generator::{Attenuation, PowerLevel},
+};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let freq = match common::args().first() {
+        Some(freq) => freq.parse()?,
+        None => Frequency::from_mhz(915),
+    };
+
+    if common::simulation_unsupported() {
+        return Ok(());
+    }
+    let Some(rfe) = SignalGenerator::connect() else {
+        eprintln!("No RF Explorer signal generator was found.");
+        return Ok(());
+    };
+
+    println!("Outputting {:.3} MHz for 5 seconds", freq.as_mhz_f64());
+    rfe.start_cw(freq, Attenuation::On, PowerLevel::Lowest)?;
+    thread::sleep(Duration::from_secs(5));
+    rfe.rf_power_off()?;
+
+    Ok(())
+}
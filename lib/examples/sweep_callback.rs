@@ -0,0 +1,38 @@
+//! Receives sweeps through a callback instead of waiting for them.
+//!
+//! ```text
+//! cargo run --example sweep_callback
+//! ```
+
+mod common;
+
+use std::{error::Error, sync::mpsc, time::Duration};
+
+const SWEEPS: usize = 10;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let Some(rfe) = common::connect_spectrum_analyzer() else {
+        return Ok(());
+    };
+
+    // The callback runs on the thread that reads from the RF Explorer, so hand each sweep off to
+    // the main thread instead of doing the work in the callback
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_sweep_callback(move |sweep, start_freq, stop_freq| {
+        let _ = sender.send((sweep.to_vec(), start_freq, stop_freq));
+    });
+
+    for _ in 0..SWEEPS {
+        let (sweep, start_freq, stop_freq) = receiver.recv_timeout(Duration::from_secs(5))?;
+        let peak_dbm = sweep.iter().copied().fold(f32::MIN, f32::max);
+        println!(
+            "{:.3}-{:.3} MHz: {} points, peak {peak_dbm:.1} dBm",
+            start_freq.as_mhz_f64(),
+            stop_freq.as_mhz_f64(),
+            sweep.len()
+        );
+    }
+
+    rfe.remove_sweep_callback();
+    Ok(())
+}
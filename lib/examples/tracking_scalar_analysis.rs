@@ -0,0 +1,74 @@
+//! Measures how a device under test passes signals across a band using a signal generator in
+//! tracking mode, like a scalar network analyzer.
+//!
+//! Connect the signal generator's output to the device's input and the device's output to the
+//! spectrum analyzer's input.
+//!
+//! ```text
+//! cargo run --example tracking_scalar_analysis -- [START] [STOP]
+//! cargo run --example tracking_scalar_analysis -- 800M 1G
+//! ```
+
+mod common;
+
+use std::{error::Error, time::Duration};
+
+use rfe::{
+    Frequency, SignalGenerator, SpectrumAnalyzer,
+    measurements::TrackingStepPlan,
+    signal_generator::{Attenuation, PowerLevel},
+    spectrum_analyzer::TrackingStatus,
+};
+
+const STEPS: u16 = 50;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = common::args();
+    let start: Frequency = match args.first() {
+        Some(start) => start.parse()?,
+        None => Frequency::from_mhz(800),
+    };
+    let stop: Frequency = match args.get(1) {
+        Some(stop) => stop.parse()?,
+        None => Frequency::from_mhz(1_000),
+    };
+    // The first step is at the start frequency, so the last of the steps is at the stop frequency
+    let step = Frequency::from_hz((stop.as_hz() - start.as_hz()) / u64::from(STEPS - 1));
+
+    if common::simulation_unsupported() {
+        return Ok(());
+    }
+    let Some(analyzer) = SpectrumAnalyzer::connect() else {
+        eprintln!("No RF Explorer spectrum analyzer was found.");
+        return Ok(());
+    };
+    let Some(generator) = SignalGenerator::connect() else {
+        eprintln!("No RF Explorer signal generator was found.");
+        return Ok(());
+    };
+
+    // Check that both devices can reach every step before configuring either of them
+    let plan = TrackingStepPlan {
+        start,
+        step,
+        steps: STEPS,
+    };
+    if analyzer.request_tracking_plan(&plan, Some(&generator))? != TrackingStatus::Enabled {
+        eprintln!("The spectrum analyzer didn't enter tracking mode.");
+        return Ok(());
+    }
+    generator.start_tracking(start, Attenuation::On, PowerLevel::Lowest, STEPS, step)?;
+
+    for (i, freq) in (0..STEPS).zip(plan.freqs()) {
+        generator.tracking_step(i)?;
+        analyzer.tracking_step(i)?;
+        // The analyzer measures at the generator's frequency, so the strongest point is the
+        // signal that made it through the device under test
+        let sweep = analyzer.wait_for_next_sweep_with_timeout(Duration::from_secs(2))?;
+        let amp_dbm = sweep.iter().copied().fold(f32::MIN, f32::max);
+        println!("{:>12.3} MHz: {amp_dbm:.1} dBm", freq.as_mhz_f64());
+    }
+
+    generator.rf_power_off()?;
+    Ok(())
+}
@@ -0,0 +1,265 @@
+use std::fmt::{Debug, Display};
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+/// An absolute power level in decibel-milliwatts.
+///
+/// Only sensible arithmetic is allowed: adding or subtracting a [`Db`] gives a `Dbm`, and
+/// subtracting two `Dbm`s gives the [`Db`] between them. Adding two absolute levels doesn't
+/// compile:
+///
+/// ```compile_fail
+/// use rfe::Dbm;
+///
+/// let total = Dbm(-50.) + Dbm(-60.);
+/// ```
+///
+/// Neither does mixing a `Dbm` with a raw `f32`, because the `f32` could be a level or an offset:
+///
+/// ```compile_fail
+/// use rfe::Dbm;
+///
+/// let level = Dbm(-50.) + 10.;
+/// ```
+///
+/// `Dbm` has the same layout as `f32`, so slices of amplitudes can be viewed as `Dbm` without
+/// copying them with [`Dbm::from_f32_slice`].
+///
+/// # Examples
+///
+/// ```
+/// use rfe::{Db, Dbm};
+///
+/// let noise_floor = Dbm(-100.);
+/// let signal = noise_floor + Db(30.);
+/// assert_eq!(signal - noise_floor, Db(30.));
+/// ```
+#[derive(Default, Clone, Copy, PartialEq, PartialOrd)]
+#[repr(transparent)]
+pub struct Dbm(pub f32);
+
+/// A relative power level in decibels, like a gain, loss, or offset.
+///
+/// `Db`s can be added to and subtracted from each other, scaled by an `f32`, and added to or
+/// subtracted from a [`Dbm`].
+#[derive(Default, Clone, Copy, PartialEq, PartialOrd)]
+#[repr(transparent)]
+pub struct Db(pub f32);
+
+impl Dbm {
+    /// Creates a power level from milliwatts.
+    pub fn from_mw(mw: f32) -> Dbm {
+        Dbm(10. * mw.log10())
+    }
+
+    /// Returns the power level in milliwatts.
+    pub fn as_mw(self) -> f32 {
+        10f32.powf(self.0 / 10.)
+    }
+
+    /// Views amplitudes in dBm as `Dbm`s without copying them.
+    pub fn from_f32_slice(amplitudes_dbm: &[f32]) -> &[Dbm] {
+        // SAFETY: `Dbm` is `repr(transparent)` over `f32`, so they have the same layout
+        unsafe { &*(std::ptr::from_ref(amplitudes_dbm) as *const [Dbm]) }
+    }
+
+    /// Views `Dbm`s as amplitudes in dBm without copying them.
+    pub fn as_f32_slice(amplitudes: &[Dbm]) -> &[f32] {
+        // SAFETY: `Dbm` is `repr(transparent)` over `f32`, so they have the same layout
+        unsafe { &*(std::ptr::from_ref(amplitudes) as *const [f32]) }
+    }
+
+    /// Converts amplitudes in dBm to `Dbm`s, reusing the `Vec`'s buffer.
+    pub fn from_f32_vec(amplitudes_dbm: Vec<f32>) -> Vec<Dbm> {
+        // Collecting into a `Vec` of a type with the same layout reuses the buffer
+        amplitudes_dbm.into_iter().map(Dbm).collect()
+    }
+}
+
+impl Add<Db> for Dbm {
+    type Output = Dbm;
+
+    fn add(self, rhs: Db) -> Self::Output {
+        Dbm(self.0 + rhs.0)
+    }
+}
+
+impl Add<Dbm> for Db {
+    type Output = Dbm;
+
+    fn add(self, rhs: Dbm) -> Self::Output {
+        Dbm(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign<Db> for Dbm {
+    fn add_assign(&mut self, rhs: Db) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub<Db> for Dbm {
+    type Output = Dbm;
+
+    fn sub(self, rhs: Db) -> Self::Output {
+        Dbm(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign<Db> for Dbm {
+    fn sub_assign(&mut self, rhs: Db) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Sub for Dbm {
+    type Output = Db;
+
+    fn sub(self, rhs: Dbm) -> Self::Output {
+        Db(self.0 - rhs.0)
+    }
+}
+
+impl Add for Db {
+    type Output = Db;
+
+    fn add(self, rhs: Db) -> Self::Output {
+        Db(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Db {
+    fn add_assign(&mut self, rhs: Db) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Db {
+    type Output = Db;
+
+    fn sub(self, rhs: Db) -> Self::Output {
+        Db(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Db {
+    fn sub_assign(&mut self, rhs: Db) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Db {
+    type Output = Db;
+
+    fn neg(self) -> Self::Output {
+        Db(-self.0)
+    }
+}
+
+impl Mul<f32> for Db {
+    type Output = Db;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Db(self.0 * rhs)
+    }
+}
+
+impl Div<f32> for Db {
+    type Output = Db;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        Db(self.0 / rhs)
+    }
+}
+
+impl From<f32> for Dbm {
+    fn from(dbm: f32) -> Self {
+        Dbm(dbm)
+    }
+}
+
+impl From<Dbm> for f32 {
+    fn from(dbm: Dbm) -> Self {
+        dbm.0
+    }
+}
+
+impl From<f32> for Db {
+    fn from(db: f32) -> Self {
+        Db(db)
+    }
+}
+
+impl From<Db> for f32 {
+    fn from(db: Db) -> Self {
+        db.0
+    }
+}
+
+impl Display for Dbm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)?;
+        write!(f, " dBm")
+    }
+}
+
+impl Debug for Dbm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+impl Display for Db {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)?;
+        write!(f, " dB")
+    }
+}
+
+impl Debug for Db {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combine_levels_and_offsets() {
+        let mut level = Dbm(-80.) + Db(20.) - Db(5.);
+        assert_eq!(level, Dbm(-65.));
+        level += Db(-5.);
+        assert_eq!(level, Dbm(-70.));
+        assert_eq!(Dbm(-40.) - Dbm(-70.), Db(30.));
+        assert_eq!(-(Db(3.) + Db(3.)) * 2., Db(-12.));
+    }
+
+    #[test]
+    fn convert_to_and_from_mw() {
+        assert_eq!(Dbm(0.).as_mw(), 1.);
+        assert_eq!(Dbm(20.).as_mw(), 100.);
+        assert_eq!(Dbm::from_mw(100.), Dbm(20.));
+    }
+
+    #[test]
+    fn view_slices_without_copying() {
+        let amplitudes_dbm = [-100., -50.5, -75.];
+        let typed = Dbm::from_f32_slice(&amplitudes_dbm);
+        assert_eq!(typed, [Dbm(-100.), Dbm(-50.5), Dbm(-75.)]);
+        assert_eq!(typed.as_ptr().cast::<f32>(), amplitudes_dbm.as_ptr());
+        assert_eq!(Dbm::as_f32_slice(typed), amplitudes_dbm);
+
+        let amplitudes_dbm = vec![-100., -50.];
+        let ptr = amplitudes_dbm.as_ptr();
+        let typed = Dbm::from_f32_vec(amplitudes_dbm);
+        assert_eq!(typed.as_ptr().cast::<f32>(), ptr);
+    }
+
+    #[test]
+    fn format_with_units() {
+        assert_eq!(Dbm(-42.5).to_string(), "-42.5 dBm");
+        assert_eq!(format!("{:.1}", Db(3.)), "3.0 dB");
+        assert_eq!(format!("{:?}", Dbm(-1.)), "-1 dBm");
+    }
+}
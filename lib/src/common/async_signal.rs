@@ -0,0 +1,100 @@
+#[cfg(feature = "async")]
+use std::{pin::pin, time::Duration};
+
+#[cfg(feature = "async")]
+use super::{Error, Result};
+
+/// Wakes async waiters when a cached value changes, alongside the `Condvar` that wakes blocking
+/// waiters.
+///
+/// Without the `async` feature it does nothing, so values can notify it whether or not anything
+/// can wait for it.
+#[derive(Debug, Default)]
+pub(crate) struct AsyncSignal {
+    #[cfg(feature = "async")]
+    notify: tokio::sync::Notify,
+}
+
+impl AsyncSignal {
+    /// Wakes every async waiter so they check whether what they're waiting for has arrived.
+    ///
+    /// The value must be updated before notifying, like with a `Condvar`.
+    pub(crate) fn notify(&self) {
+        #[cfg(feature = "async")]
+        self.notify.notify_waiters();
+    }
+
+    /// Calls `poll` every time the signal is notified until it returns a result or the timeout
+    /// elapses, in which case [`Error::TimedOut`] is returned.
+    ///
+    /// Has to be awaited within a Tokio runtime with its time driver enabled.
+    #[cfg(feature = "async")]
+    pub(crate) async fn wait_until<T>(
+        &self,
+        timeout: Duration,
+        mut poll: impl FnMut() -> Option<Result<T>>,
+    ) -> Result<T> {
+        let wait = async {
+            loop {
+                // Start listening before polling so a notification in between isn't missed
+                let mut notified = pin!(self.notify.notified());
+                notified.as_mut().enable();
+                if let Some(result) = poll() {
+                    return result;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .unwrap_or(Err(Error::TimedOut(timeout)))
+    }
+}
+
+#[cfg(all(test, feature = "async"))]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicU32, Ordering},
+        },
+        thread,
+    };
+
+    use super::*;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn wake_when_notified_from_another_thread() {
+        let signal = Arc::new(AsyncSignal::default());
+        let value = Arc::new(AtomicU32::new(0));
+        let (thread_signal, thread_value) = (signal.clone(), value.clone());
+        let notifier = thread::spawn(move || {
+            for i in 1..=3 {
+                thread::sleep(Duration::from_millis(10));
+                thread_value.store(i, Ordering::Relaxed);
+                thread_signal.notify();
+            }
+        });
+
+        let result = block_on(signal.wait_until(Duration::from_secs(2), || {
+            (value.load(Ordering::Relaxed) == 3).then_some(Ok(3))
+        }));
+        assert_eq!(result.unwrap(), 3);
+        notifier.join().unwrap();
+    }
+
+    #[test]
+    fn time_out_without_notifications() {
+        let signal = AsyncSignal::default();
+        let result = block_on(signal.wait_until(Duration::from_millis(20), || None::<Result<()>>));
+        assert!(matches!(result, Err(Error::TimedOut(_))));
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use super::ConnectionError;
+
+/// A step of connecting to an RF Explorer, reported while connecting so a user interface can show
+/// what's taking time.
+///
+/// See [`SpectrumAnalyzer::connect_with_progress`](crate::SpectrumAnalyzer::connect_with_progress).
+#[derive(Debug)]
+pub enum ConnectProgress {
+    /// The serial ports that could be RF Explorers were listed.
+    ScanningPorts {
+        /// The number of ports that will be probed.
+        found: usize,
+    },
+    /// A port was opened and sent the initialization command.
+    Probing {
+        /// The name of the port.
+        port: String,
+    },
+    /// A port is still waiting for the RF Explorer's config and setup info.
+    ///
+    /// It's reported about every [`ConnectProgress::WAITING_INTERVAL`] until they arrive or the
+    /// wait times out.
+    WaitingForConfig {
+        /// The name of the port.
+        port: String,
+        /// How long the port has been waiting.
+        elapsed: Duration,
+    },
+    /// An RF Explorer was connected through a port.
+    Connected {
+        /// The name of the port.
+        port: String,
+    },
+    /// A port didn't connect at any baud rate.
+    Failed {
+        /// The name of the port.
+        port: String,
+        /// Why the last baud rate tried failed.
+        error: ConnectionError,
+    },
+}
+
+impl ConnectProgress {
+    /// How often [`ConnectProgress::WaitingForConfig`] is reported while waiting.
+    pub const WAITING_INTERVAL: Duration = Duration::from_millis(250);
+
+    /// The name of the port the progress is about, or `None` for
+    /// [`ConnectProgress::ScanningPorts`].
+    pub fn port(&self) -> Option<&str> {
+        match self {
+            ConnectProgress::ScanningPorts { .. } => None,
+            ConnectProgress::Probing { port }
+            | ConnectProgress::WaitingForConfig { port, .. }
+            | ConnectProgress::Connected { port }
+            | ConnectProgress::Failed { port, .. } => Some(port),
+        }
+    }
+}
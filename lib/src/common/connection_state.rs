@@ -0,0 +1,302 @@
+use std::sync::{Arc, Mutex};
+
+use tracing::{info, warn};
+
+use super::{Error, LinkDiagnosis, Result};
+use crate::rf_explorer::ReaderCallback;
+
+/// The start of the line an RF Explorer is expected to send when it starts in firmware update
+/// (bootloader) mode.
+///
+/// The bootloader doesn't speak the RF Explorer's usual protocol, so this is the only way to tell
+/// that the device isn't running its firmware.
+///
+/// These bytes haven't been confirmed against a capture from a device or a vendor document yet.
+/// Until they are, a bootloader that announces itself differently is only seen as unparseable
+/// lines, and the tests use a synthetic transcript.
+pub(crate) const BOOTLOADER_BANNER: &[u8] = b"RF Explorer Bootloader";
+
+/// The state of the connection to an RF Explorer.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum ConnectionState {
+    /// The RF Explorer is running its firmware and accepts commands.
+    #[default]
+    Connected,
+    /// The RF Explorer is running its firmware and accepts commands, but the serial link to it
+    /// shows a pattern of problems, like an underpowered USB hub or a damaged cable.
+    ///
+    /// The events the diagnosis was made from are in the link's health's `evidence`.
+    Degraded(LinkDiagnosis),
+    /// The RF Explorer is in firmware update mode.
+    ///
+    /// Commands could corrupt the update, so they're refused with
+    /// [`Error::InvalidOperation`] until the RF Explorer sends a message from its firmware again.
+    Bootloader,
+    /// Messages can no longer be read from the RF Explorer, like after its USB cable is unplugged.
+    Disconnected,
+}
+
+pub(crate) type ConnectionStateCallback = Arc<dyn Fn(ConnectionState) + Send + Sync>;
+pub(crate) type DisconnectCallback = dyn FnMut() + Send;
+
+/// Tracks a connection's state and calls a callback when it changes.
+#[derive(Default)]
+pub(crate) struct ConnectionStatus {
+    state: Mutex<ConnectionState>,
+    /// The link's diagnosis, which degrades the connected state when the link isn't healthy.
+    diagnosis: Mutex<LinkDiagnosis>,
+    callback: Mutex<Option<ConnectionStateCallback>>,
+    disconnect_callback: ReaderCallback<DisconnectCallback>,
+}
+
+impl ConnectionStatus {
+    pub(crate) fn get(&self) -> ConnectionState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Changes the state and calls the callback if it's different from the current one.
+    ///
+    /// A disconnected connection stays disconnected until it's reconnected with
+    /// [`ConnectionStatus::reconnect`].
+    pub(crate) fn set(&self, state: ConnectionState) {
+        let state = if state == ConnectionState::Connected {
+            connected_state(*self.diagnosis.lock().unwrap())
+        } else {
+            state
+        };
+        {
+            let mut current = self.state.lock().unwrap();
+            if *current == state || *current == ConnectionState::Disconnected {
+                return;
+            }
+            *current = state;
+        }
+        match state {
+            ConnectionState::Bootloader => warn!("The device entered bootloader mode"),
+            ConnectionState::Connected | ConnectionState::Degraded(_) => {
+                info!("The device left bootloader mode")
+            }
+            ConnectionState::Disconnected => (),
+        }
+        self.call_callback(state);
+        if state == ConnectionState::Disconnected {
+            self.disconnect_callback.call(|cb| cb());
+        }
+    }
+
+    /// Records the link's new diagnosis and calls the callback if it changes the state.
+    ///
+    /// A device that's in bootloader mode or disconnected keeps its state, and the diagnosis is
+    /// reported once it's connected again.
+    pub(crate) fn set_diagnosis(&self, diagnosis: LinkDiagnosis) {
+        let state = {
+            let mut current = self.state.lock().unwrap();
+            *self.diagnosis.lock().unwrap() = diagnosis;
+            let state = connected_state(diagnosis);
+            if *current == state
+                || !matches!(
+                    *current,
+                    ConnectionState::Connected | ConnectionState::Degraded(_)
+                )
+            {
+                return;
+            }
+            *current = state;
+            state
+        };
+        self.call_callback(state);
+    }
+
+    /// Marks a disconnected connection as connected again after its port was reopened.
+    pub(crate) fn reconnect(&self) {
+        let state = {
+            let mut current = self.state.lock().unwrap();
+            if *current != ConnectionState::Disconnected {
+                return;
+            }
+            *current = connected_state(*self.diagnosis.lock().unwrap());
+            *current
+        };
+        info!("Reconnected to the device");
+        self.call_callback(state);
+    }
+
+    /// Marks a connection as disconnected again after a reconnect failed, without calling the
+    /// disconnect callback.
+    ///
+    /// The disconnect callback already ran for the disconnection the reconnect was recovering
+    /// from, and the reconnect's caller learns that it failed from its result instead.
+    pub(crate) fn fail_reconnect(&self) {
+        {
+            let mut current = self.state.lock().unwrap();
+            if *current == ConnectionState::Disconnected {
+                return;
+            }
+            *current = ConnectionState::Disconnected;
+        }
+        self.call_callback(ConnectionState::Disconnected);
+    }
+
+    fn call_callback(&self, state: ConnectionState) {
+        // Call the callback without holding the lock so it can set a new callback
+        let callback = self.callback.lock().unwrap().clone();
+        if let Some(callback) = callback {
+            callback(state);
+        }
+    }
+
+    pub(crate) fn set_callback(&self, callback: Option<ConnectionStateCallback>) {
+        *self.callback.lock().unwrap() = callback;
+    }
+
+    pub(crate) fn set_disconnect_callback(&self, callback: Option<Box<DisconnectCallback>>) {
+        match callback {
+            Some(callback) => self.disconnect_callback.set(callback),
+            None => self.disconnect_callback.remove(),
+        }
+    }
+
+    /// Returns [`Error::InvalidOperation`] if commands can't be sent to the device in its current
+    /// state.
+    pub(crate) fn check_can_send(&self) -> Result<()> {
+        if self.get() == ConnectionState::Bootloader {
+            Err(Error::InvalidOperation(
+                "device is in bootloader mode".to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl std::fmt::Debug for ConnectionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionStatus")
+            .field("state", &self.get())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the state of a device that's running its firmware over a link with the diagnosis.
+fn connected_state(diagnosis: LinkDiagnosis) -> ConnectionState {
+    if diagnosis == LinkDiagnosis::Healthy {
+        ConnectionState::Connected
+    } else {
+        ConnectionState::Degraded(diagnosis)
+    }
+}
+
+/// Returns whether a line read from a device is the banner sent by its bootloader.
+pub(crate) fn is_bootloader_banner(line: &[u8]) -> bool {
+    line.starts_with(BOOTLOADER_BANNER)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn refuse_commands_in_bootloader_mode() {
+        let status = ConnectionStatus::default();
+        assert!(status.check_can_send().is_ok());
+
+        status.set(ConnectionState::Bootloader);
+        let error = status.check_can_send().unwrap_err();
+        assert!(
+            matches!(&error, Error::InvalidOperation(message) if message == "device is in bootloader mode"),
+            "{error:?}"
+        );
+
+        status.set(ConnectionState::Connected);
+        assert!(status.check_can_send().is_ok());
+    }
+
+    #[test]
+    fn call_callback_only_when_state_changes() {
+        let status = ConnectionStatus::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let callback_calls = calls.clone();
+        status.set_callback(Some(Arc::new(move |_| {
+            callback_calls.fetch_add(1, Ordering::Relaxed);
+        })));
+
+        status.set(ConnectionState::Connected);
+        status.set(ConnectionState::Bootloader);
+        status.set(ConnectionState::Bootloader);
+        status.set(ConnectionState::Disconnected);
+        // Only reopening the port can reconnect a disconnected port
+        status.set(ConnectionState::Connected);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert_eq!(status.get(), ConnectionState::Disconnected);
+
+        status.reconnect();
+        status.reconnect();
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+        assert_eq!(status.get(), ConnectionState::Connected);
+    }
+
+    #[test]
+    fn degrade_connected_state_by_diagnosis() {
+        let status = ConnectionStatus::default();
+        let states = Arc::new(Mutex::new(Vec::new()));
+        let callback_states = states.clone();
+        status.set_callback(Some(Arc::new(move |state| {
+            callback_states.lock().unwrap().push(state);
+        })));
+
+        let power_issue = ConnectionState::Degraded(LinkDiagnosis::SuspectedPowerIssue);
+        status.set_diagnosis(LinkDiagnosis::Unknown);
+        status.set(ConnectionState::Bootloader);
+        // The diagnosis is only reported once the device leaves bootloader mode
+        status.set_diagnosis(LinkDiagnosis::SuspectedPowerIssue);
+        status.set(ConnectionState::Connected);
+        status.set(ConnectionState::Connected);
+        assert!(status.check_can_send().is_ok());
+        status.set(ConnectionState::Disconnected);
+        status.reconnect();
+        status.set_diagnosis(LinkDiagnosis::Healthy);
+        assert_eq!(
+            *states.lock().unwrap(),
+            [
+                ConnectionState::Degraded(LinkDiagnosis::Unknown),
+                ConnectionState::Bootloader,
+                power_issue,
+                ConnectionState::Disconnected,
+                power_issue,
+                ConnectionState::Connected,
+            ]
+        );
+    }
+
+    #[test]
+    fn call_disconnect_callback_once_per_disconnection() {
+        let status = ConnectionStatus::default();
+        let disconnections = Arc::new(AtomicUsize::new(0));
+        let callback_disconnections = disconnections.clone();
+        status.set_disconnect_callback(Some(Box::new(move || {
+            callback_disconnections.fetch_add(1, Ordering::Relaxed);
+        })));
+
+        status.set(ConnectionState::Bootloader);
+        status.set(ConnectionState::Disconnected);
+        status.set(ConnectionState::Disconnected);
+        assert_eq!(disconnections.load(Ordering::Relaxed), 1);
+
+        status.reconnect();
+        status.fail_reconnect();
+        assert_eq!(status.get(), ConnectionState::Disconnected);
+        assert_eq!(disconnections.load(Ordering::Relaxed), 1);
+
+        status.reconnect();
+        status.set(ConnectionState::Disconnected);
+        assert_eq!(disconnections.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn detect_bootloader_banner() {
+        assert!(is_bootloader_banner(b"RF Explorer Bootloader v1.12\r\n"));
+        assert!(!is_bootloader_banner(b"#C2-M:014,255,03.41\r\n"));
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    fmt,
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Runs jobs on a single timer thread once no newer job with the same key has been scheduled
+/// for their debounce duration.
+///
+/// The thread is started when the first job is scheduled and is stopped when the debouncer is
+/// stopped or dropped. Jobs that haven't run by then are dropped without running.
+#[derive(Default)]
+pub(crate) struct Debouncer {
+    shared: Arc<Shared>,
+    thread_handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    scheduled: Vec<Scheduled>,
+    is_stopped: bool,
+}
+
+struct Scheduled {
+    key: &'static str,
+    due: Instant,
+    job: Job,
+}
+
+impl Debouncer {
+    /// Schedules the job to run after the debounce duration, replacing the scheduled job with
+    /// the same key, which is dropped without running.
+    pub(crate) fn schedule(
+        &self,
+        key: &'static str,
+        debounce: Duration,
+        job: impl FnOnce() + Send + 'static,
+    ) {
+        let mut thread_handle = self.thread_handle.lock().unwrap();
+        let mut state = self.shared.state.lock().unwrap();
+        if state.is_stopped {
+            return;
+        }
+        state.scheduled.retain(|scheduled| scheduled.key != key);
+        state.scheduled.push(Scheduled {
+            key,
+            due: Instant::now() + debounce,
+            job: Box::new(job),
+        });
+        drop(state);
+        self.shared.condvar.notify_all();
+
+        if thread_handle.is_none() {
+            let shared = self.shared.clone();
+            *thread_handle = Some(thread::spawn(move || run_jobs(&shared)));
+        }
+    }
+
+    /// Drops the scheduled jobs and waits for the timer thread to finish the job it's running.
+    pub(crate) fn stop(&self) {
+        let scheduled = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.is_stopped = true;
+            std::mem::take(&mut state.scheduled)
+        };
+        self.shared.condvar.notify_all();
+        drop(scheduled);
+
+        // A job that drops the last handle to the debouncer stops it from the timer thread
+        let thread_handle = self.thread_handle.lock().unwrap().take();
+        if let Some(thread_handle) = thread_handle
+            && thread_handle.thread().id() != thread::current().id()
+        {
+            let _ = thread_handle.join();
+        }
+    }
+}
+
+impl Drop for Debouncer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl fmt::Debug for Debouncer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.shared.state.lock().unwrap();
+        f.debug_struct("Debouncer")
+            .field(
+                "scheduled",
+                &state
+                    .scheduled
+                    .iter()
+                    .map(|scheduled| scheduled.key)
+                    .collect::<Vec<_>>(),
+            )
+            .field("is_stopped", &state.is_stopped)
+            .finish()
+    }
+}
+
+fn run_jobs(shared: &Shared) {
+    let mut state = shared.state.lock().unwrap();
+    loop {
+        if state.is_stopped {
+            return;
+        }
+        let now = Instant::now();
+        let next = state
+            .scheduled
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, scheduled)| scheduled.due)
+            .map(|(i, scheduled)| (i, scheduled.due));
+        match next {
+            Some((i, due)) if due <= now => {
+                let scheduled = state.scheduled.swap_remove(i);
+                // Run the job without holding the lock so it can schedule more jobs
+                drop(state);
+                (scheduled.job)();
+                state = shared.state.lock().unwrap();
+            }
+            Some((_, due)) => state = shared.condvar.wait_timeout(state, due - now).unwrap().0,
+            None => state = shared.condvar.wait(state).unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn only_run_the_latest_job_with_each_key() {
+        let debouncer = Debouncer::default();
+        let (tx, rx) = mpsc::channel();
+        for i in 0..5 {
+            let tx = tx.clone();
+            debouncer.schedule("freq", Duration::from_millis(50), move || {
+                tx.send(("freq", i)).unwrap()
+            });
+        }
+        let tx_amp = tx.clone();
+        debouncer.schedule("amp", Duration::from_millis(10), move || {
+            tx_amp.send(("amp", 0)).unwrap()
+        });
+
+        let timeout = Duration::from_secs(1);
+        assert_eq!(rx.recv_timeout(timeout), Ok(("amp", 0)));
+        assert_eq!(rx.recv_timeout(timeout), Ok(("freq", 4)));
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn drop_scheduled_jobs_when_stopped() {
+        let debouncer = Debouncer::default();
+        let (tx, rx) = mpsc::channel::<()>();
+        debouncer.schedule("freq", Duration::from_secs(60), move || {
+            tx.send(()).unwrap()
+        });
+
+        let start = Instant::now();
+        drop(debouncer);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        // The job was dropped along with its sender without running
+        assert_eq!(rx.recv(), Err(mpsc::RecvError));
+    }
+
+    #[test]
+    fn ignore_jobs_scheduled_after_stopping() {
+        let debouncer = Debouncer::default();
+        debouncer.stop();
+        debouncer.schedule("freq", Duration::ZERO, || {});
+        assert!(debouncer.shared.state.lock().unwrap().scheduled.is_empty());
+        assert!(debouncer.thread_handle.lock().unwrap().is_none());
+    }
+}
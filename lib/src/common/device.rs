@@ -3,16 +3,26 @@ use std::{
     fmt::Debug,
     io::{self, ErrorKind},
     sync::{
-        Arc,
+        Arc, Mutex, Weak,
         atomic::{AtomicBool, Ordering},
     },
     thread::{self, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant, SystemTime},
 };
 
-use tracing::debug;
+use serialport::SerialPortInfo;
+use tracing::{debug, warn};
 
-use super::{ConnectionResult, MessageContainer, MessageParseError, SerialPort, serial_port};
+use crate::rf_explorer::{
+    Command, RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT, ScreenDumpMode, ScreenDumpTimer,
+};
+
+use super::{
+    ConnectProgress, ConnectionError, ConnectionResult, ConnectionState, Debouncer, DebugSnapshot,
+    LinkEventKind, LinkHealth, LinkStats, MemoryBudget, MessageContainer, MessageParseError,
+    SerialPort, WriteStats, classify_unknown_line, is_bootloader_banner, is_setup_info_line,
+    log_command, serial_port,
+};
 
 #[derive(Debug)]
 /// Low-level serial device wrapper for RF Explorer-like devices.
@@ -21,63 +31,101 @@ use super::{ConnectionResult, MessageContainer, MessageParseError, SerialPort, s
 /// stores parsed messages in the supplied [`MessageContainer`].
 pub struct Device<M: MessageContainer + 'static> {
     serial_port: Arc<SerialPort>,
-    is_reading: Arc<AtomicBool>,
-    read_thread_handle: Option<JoinHandle<()>>,
+    reader: Mutex<Option<ReaderThread>>,
+    /// Held while reconnecting so that concurrent reconnects can't each start a reader.
+    reconnecting: Mutex<()>,
+    is_holding: AtomicBool,
+    quiesce_on_drop: AtomicBool,
     messages: Arc<M>,
+    link_stats: Arc<LinkStats>,
+    screen_dump: Arc<ScreenDumpTimer>,
+    debouncer: Debouncer,
 }
 
 impl<M: MessageContainer> Device<M> {
+    /// How long each write made while quiescing a dropped device can take.
+    const QUIESCE_WRITE_TIMEOUT: Duration = Duration::from_millis(50);
+
     fn connect_internal(
         serial_port: SerialPort,
         device_init_command: impl AsRef<[u8]> + Debug,
+        on_waiting: &dyn Fn(Duration),
     ) -> ConnectionResult<Self> {
-        let mut device = Self {
+        Self::connect_with_messages(serial_port, device_init_command, M::default(), |messages| {
+            messages.wait_for_device_info_with_progress(on_waiting)
+        })
+    }
+
+    /// Connects to a device whose message container was filled in before the port was opened,
+    /// like from a cache, and returns once `wait_until_connected` does.
+    ///
+    /// Messages read from the device replace the ones already in the container.
+    pub(crate) fn connect_with_messages(
+        serial_port: SerialPort,
+        device_init_command: impl AsRef<[u8]> + Debug,
+        messages: M,
+        wait_until_connected: impl FnOnce(&M) -> ConnectionResult<()>,
+    ) -> ConnectionResult<Self> {
+        let device = Self {
             serial_port: Arc::new(serial_port),
-            is_reading: Arc::new(AtomicBool::new(true)),
-            read_thread_handle: None,
-            messages: Arc::new(M::default()),
+            reader: Mutex::new(None),
+            reconnecting: Mutex::new(()),
+            is_holding: AtomicBool::new(false),
+            quiesce_on_drop: AtomicBool::new(false),
+            messages: Arc::new(messages),
+            link_stats: Arc::new(LinkStats::new(Instant::now())),
+            screen_dump: Arc::new(ScreenDumpTimer::default()),
+            debouncer: Debouncer::default(),
         };
 
-        // Read messages from the device on a background thread
-        let messages = device.messages.clone();
-        let serial_port = device.serial_port.clone();
-        let is_reading = device.is_reading.clone();
-        device.read_thread_handle = Some(thread::spawn(move || {
-            Self::read_messages(serial_port, messages, is_reading)
-        }));
-
-        if let Err(err) = device.serial_port.send_bytes(device_init_command) {
+        device.start_reading_messages();
+        if let Err(err) = device.serial_port.write_bytes(device_init_command) {
             device.stop_reading_messages();
             return Err(err.into());
         }
 
-        if let Err(err) = device.messages().wait_for_device_info() {
+        if let Err(err) = wait_until_connected(device.messages()) {
             device.stop_reading_messages();
             return Err(err);
         }
 
         // The largest sweep we could receive contains 65,535 (2^16) points
         // To be safe, set the maximum message length to 131,072 (2^17)
-        device.serial_port.set_max_message_len(131_072);
+        device.set_max_line_len(MemoryBudget::DEFAULT_MAX_LINE_LEN);
         Ok(device)
     }
 
+    /// The default maximum number of serial ports that are probed at the same time while connecting.
+    pub const DEFAULT_MAX_CONCURRENT_PROBES: usize = 4;
+
     /// Connects to the first Silicon Labs CP210x serial port that responds to the initialization command.
+    ///
+    /// Ports are probed in parallel and any other ports that respond are closed before returning.
     pub fn connect(device_init_command: impl AsRef<[u8]>) -> Option<Self> {
-        // For every Silabs CP210X port, we first try to connect using the RF Explorer's fast
-        // default baud rate (500 kbps) and then try to connect using its slow default baud rate
-        // (2.4 kbps)
-        serial_port::silabs_cp210x_ports()
-            .flat_map(|port_info| {
-                [
-                    (port_info.clone(), serial_port::FAST_BAUD_RATE),
-                    (port_info, serial_port::SLOW_BAUD_RATE),
-                ]
-            })
-            .find_map(|(port_info, baud_rate)| {
-                let serial_port = SerialPort::open(&port_info, baud_rate).ok()?;
-                Self::connect_internal(serial_port, device_init_command.as_ref()).ok()
-            })
+        Self::connect_with_progress(device_init_command, &|_| {})
+    }
+
+    /// Connects to the first Silicon Labs CP210x serial port that responds to the initialization
+    /// command while reporting each step to `progress`.
+    ///
+    /// `progress` is called from the threads probing the ports, so it's called concurrently when
+    /// more than one port is probed and it should return quickly, like by storing the progress
+    /// and waking up a user interface.
+    pub fn connect_with_progress(
+        device_init_command: impl AsRef<[u8]>,
+        progress: &(dyn Fn(ConnectProgress) + Sync),
+    ) -> Option<Self> {
+        let mut devices = Self::connect_to_ports(
+            &[serial_port::FAST_BAUD_RATE, serial_port::SLOW_BAUD_RATE],
+            device_init_command.as_ref(),
+            Self::DEFAULT_MAX_CONCURRENT_PROBES,
+            true,
+            progress,
+        );
+        let device = devices.pop();
+        // Close the ports of any other devices that connected so they can be connected to later
+        drop(devices);
+        device
     }
 
     /// Connects to the first Silicon Labs CP210x serial port using the given baud rate.
@@ -85,10 +133,107 @@ impl<M: MessageContainer> Device<M> {
         baud_rate: u32,
         device_init_command: impl AsRef<[u8]>,
     ) -> Option<Self> {
-        serial_port::silabs_cp210x_ports().find_map(|port_info| {
-            let serial_port = SerialPort::open(&port_info, baud_rate).ok()?;
-            Self::connect_internal(serial_port, device_init_command.as_ref()).ok()
-        })
+        let mut devices = Self::connect_to_ports(
+            &[baud_rate],
+            device_init_command.as_ref(),
+            Self::DEFAULT_MAX_CONCURRENT_PROBES,
+            true,
+            &|_| {},
+        );
+        let device = devices.pop();
+        drop(devices);
+        device
+    }
+
+    /// Connects to every Silicon Labs CP210x serial port that responds to the initialization command.
+    pub fn connect_all(device_init_command: impl AsRef<[u8]>) -> Vec<Self> {
+        Self::connect_all_with_max_concurrent_probes(
+            Self::DEFAULT_MAX_CONCURRENT_PROBES,
+            device_init_command,
+        )
+    }
+
+    /// Connects to every Silicon Labs CP210x serial port that responds to the initialization command
+    /// while probing at most `max_concurrent_probes` ports at the same time.
+    pub fn connect_all_with_max_concurrent_probes(
+        max_concurrent_probes: usize,
+        device_init_command: impl AsRef<[u8]>,
+    ) -> Vec<Self> {
+        Self::connect_to_ports(
+            &[serial_port::FAST_BAUD_RATE, serial_port::SLOW_BAUD_RATE],
+            device_init_command.as_ref(),
+            max_concurrent_probes,
+            false,
+            &|_| {},
+        )
+    }
+
+    fn connect_to_ports(
+        baud_rates: &[u32],
+        device_init_command: &[u8],
+        max_concurrent_probes: usize,
+        stop_after_first_connection: bool,
+        progress: &(dyn Fn(ConnectProgress) + Sync),
+    ) -> Vec<Self> {
+        let ports: Vec<_> = serial_port::rf_explorer_ports().collect();
+        progress(ConnectProgress::ScanningPorts { found: ports.len() });
+        let probe_thread_count = max_concurrent_probes.clamp(1, ports.len().max(1));
+        let ports = Mutex::new(ports.into_iter());
+        let devices = Mutex::new(Vec::new());
+        let is_connected = AtomicBool::new(false);
+        let should_stop = || stop_after_first_connection && is_connected.load(Ordering::Relaxed);
+
+        // Each probe thread takes the next unprobed port and tries each baud rate until the
+        // device responds. The scope guarantees every port that isn't returned has been closed.
+        thread::scope(|scope| {
+            for _ in 0..probe_thread_count {
+                scope.spawn(|| {
+                    while !should_stop() {
+                        let Some(port_info) = ports.lock().unwrap().next() else {
+                            break;
+                        };
+                        let port = &port_info.port_name;
+                        progress(ConnectProgress::Probing { port: port.clone() });
+                        let on_waiting = |elapsed| {
+                            progress(ConnectProgress::WaitingForConfig {
+                                port: port.clone(),
+                                elapsed,
+                            })
+                        };
+                        let mut last_error = None;
+                        // The baud rates are tried in order, so the RF Explorer's fast default baud
+                        // rate (500 kbps) is tried before its slow default baud rate (2.4 kbps)
+                        let device = baud_rates.iter().find_map(|&baud_rate| {
+                            if should_stop() {
+                                return None;
+                            }
+                            SerialPort::open(&port_info, baud_rate)
+                                .and_then(|serial_port| {
+                                    Self::connect_internal(
+                                        serial_port,
+                                        device_init_command,
+                                        &on_waiting,
+                                    )
+                                })
+                                .map_err(|error| last_error = Some(error))
+                                .ok()
+                        });
+                        if let Some(device) = device {
+                            is_connected.store(true, Ordering::Relaxed);
+                            progress(ConnectProgress::Connected { port: port.clone() });
+                            devices.lock().unwrap().push(device);
+                        } else if let Some(error) = last_error {
+                            progress(ConnectProgress::Failed {
+                                port: port.clone(),
+                                error,
+                            });
+                        }
+                    }
+                });
+            }
+        });
+
+        devices.into_inner().unwrap()
     }
 
     /// Connects to a named serial port using the given baud rate.
@@ -100,39 +245,317 @@ impl<M: MessageContainer> Device<M> {
         device_init_command: impl AsRef<[u8]>,
     ) -> ConnectionResult<Self> {
         let serial_port = SerialPort::open_with_name(name, baud_rate)?;
-        Self::connect_internal(serial_port, device_init_command.as_ref())
+        Self::connect_internal(serial_port, device_init_command.as_ref(), &|_| {})
+    }
+
+    /// Connects to a device through an already opened transport.
+    ///
+    /// This is useful for devices that aren't attached to a local serial port or for replaying
+    /// recorded sessions in tests.
+    pub fn connect_with_transport(
+        transport: Box<dyn serialport::SerialPort>,
+        device_init_command: impl AsRef<[u8]>,
+    ) -> ConnectionResult<Self> {
+        Self::connect_internal(
+            SerialPort::from_transport(transport),
+            device_init_command.as_ref(),
+            &|_| {},
+        )
+    }
+
+    /// Reopens the serial port the device was connected through, like after its USB cable was
+    /// unplugged and plugged back in, and sends the initialization command again.
+    ///
+    /// The message container and every callback are kept, so the messages read from the
+    /// reopened port update the same values. Returns once any message is read from the device,
+    /// or [`ConnectionError::DeviceInfoNotReceived`](crate::ConnectionError::DeviceInfoNotReceived)
+    /// if it doesn't respond within a few seconds, in which case the device stays disconnected.
+    ///
+    /// Reconnects called from several threads at once run one at a time.
+    pub fn reconnect(&self, device_init_command: impl AsRef<[u8]>) -> ConnectionResult<()> {
+        self.reconnect_internal(device_init_command.as_ref(), || self.serial_port.reopen())
+    }
+
+    /// Reconnects like [`Device::reconnect`] through an already opened transport instead of
+    /// reopening the serial port.
+    pub fn reconnect_with_transport(
+        &self,
+        transport: Box<dyn serialport::SerialPort>,
+        device_init_command: impl AsRef<[u8]>,
+    ) -> ConnectionResult<()> {
+        self.reconnect_internal(device_init_command.as_ref(), || {
+            self.serial_port.replace_port(transport);
+            Ok(())
+        })
+    }
+
+    fn reconnect_internal(
+        &self,
+        device_init_command: &[u8],
+        reopen: impl FnOnce() -> ConnectionResult<()>,
+    ) -> ConnectionResult<()> {
+        // Another reconnect's reader is only stopped once that reconnect has finished
+        let _reconnecting = self.reconnecting.lock().unwrap();
+        // The reader is stopped first so it can't read from the port while it's replaced
+        self.stop_reading_messages();
+        let result = reopen().and_then(|()| {
+            self.messages.reconnect();
+            self.serial_port.connection_status().reconnect();
+            let reconnected_at = Instant::now();
+            self.start_reading_messages();
+            self.serial_port.write_bytes(device_init_command)?;
+            self.wait_for_message_since(reconnected_at)
+        });
+        if let Err(error) = &result {
+            warn!(%error, "Failed to reconnect to the device");
+            self.stop_reading_messages();
+            self.messages.disconnect(io::Error::new(
+                ErrorKind::NotConnected,
+                format!("Failed to reconnect: {error}"),
+            ));
+            self.serial_port.connection_status().fail_reconnect();
+        }
+        result
+    }
+
+    /// Waits until a message that was read after `since` shows the device is alive.
+    fn wait_for_message_since(&self, since: Instant) -> ConnectionResult<()> {
+        let deadline = since + RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT;
+        loop {
+            let now = Instant::now();
+            if self
+                .link_stats
+                .since_last_message(now)
+                .is_some_and(|elapsed| elapsed <= now.saturating_duration_since(since))
+            {
+                return Ok(());
+            }
+            if now >= deadline {
+                return Err(ConnectionError::DeviceInfoNotReceived);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Returns whether messages can still be read from the device.
+    ///
+    /// The device is disconnected once reading fails with an error other than a timeout, like
+    /// after its USB cable is unplugged, until it's reconnected with [`Device::reconnect`].
+    pub fn is_connected(&self) -> bool {
+        self.connection_state() != ConnectionState::Disconnected
     }
 
-    fn read_messages(serial_port: Arc<SerialPort>, messages: Arc<M>, is_reading: Arc<AtomicBool>) {
+    /// Sets a callback that's called when messages can no longer be read from the device, like
+    /// after its USB cable is unplugged.
+    ///
+    /// The callback is called on the thread that read from the device right before it stops, so
+    /// it should return quickly, like by telling another thread to call [`Device::reconnect`].
+    /// It's kept when the device is reconnected, and isn't called again when a reconnect fails
+    /// since [`Device::reconnect`] returns the error instead.
+    pub fn set_disconnect_callback(&self, cb: impl FnMut() + Send + 'static) {
+        self.serial_port
+            .connection_status()
+            .set_disconnect_callback(Some(Box::new(cb)));
+    }
+
+    /// Removes the callback that's called when the device is disconnected.
+    pub fn remove_disconnect_callback(&self) {
+        self.serial_port
+            .connection_status()
+            .set_disconnect_callback(None);
+    }
+
+    /// Starts reading messages from the device on a background thread.
+    fn start_reading_messages(&self) {
+        let is_reading = Arc::new(AtomicBool::new(true));
+        let messages = self.messages.clone();
+        let serial_port = self.serial_port.clone();
+        let link_stats = self.link_stats.clone();
+        let screen_dump = self.screen_dump.clone();
+        let thread_is_reading = is_reading.clone();
+        let handle = thread::spawn(move || {
+            Self::read_messages(
+                serial_port,
+                messages,
+                thread_is_reading,
+                link_stats,
+                screen_dump,
+            )
+        });
+        *self.reader.lock().unwrap() = Some(ReaderThread { is_reading, handle });
+    }
+
+    fn read_messages(
+        serial_port: Arc<SerialPort>,
+        messages: Arc<M>,
+        is_reading: Arc<AtomicBool>,
+        link_stats: Arc<LinkStats>,
+        screen_dump: Arc<ScreenDumpTimer>,
+    ) {
         debug!("Started reading messages from device");
         let mut message_buf = Vec::new();
+        let mut is_skipping_line = false;
         while is_reading.load(Ordering::Relaxed) {
+            if let Some(command) = screen_dump.tick(Instant::now()) {
+                Self::send_screen_dump_command(&serial_port, command);
+            }
+
             // Messages from devices are delimited by \r\n, so we try to read a line from
             // the serial port into the message buffer
-            if let Err(error) = serial_port.read_line(&mut message_buf) {
-                // Time out errors are recoverable so we try to read again
-                // Other errors are not recoverable so we break out of the loop
-                if error.kind() == ErrorKind::TimedOut {
-                    thread::sleep(Duration::from_millis(100));
-                    continue;
+            match serial_port.read_line(&mut message_buf) {
+                Ok(len) => link_stats.record_read(len, Instant::now()),
+                Err(error) => {
+                    link_stats.record_read_error(&error);
+                    // Time out errors are recoverable so we try to read again
+                    // Other errors are not recoverable so we break out of the loop
+                    if error.kind() == ErrorKind::TimedOut {
+                        thread::sleep(Duration::from_millis(100));
+                        continue;
+                    }
+                    warn!(%error, "Stopped reading messages from device after an error");
+                    Self::record_link_event(
+                        &serial_port,
+                        &link_stats,
+                        LinkEventKind::PortLost,
+                        error.to_string().as_bytes(),
+                        Instant::now(),
+                    );
+                    messages.disconnect(error);
+                    serial_port
+                        .connection_status()
+                        .set(ConnectionState::Disconnected);
+                    break;
                 }
-                break;
             }
 
-            match find_message_in_buf(&message_buf) {
-                Ok(message) => {
-                    messages.cache_message(message);
-                    message_buf.clear()
+            // Lines that reach the limit are dropped instead of growing the buffer, along with the
+            // rest of the line when its end hasn't been read yet
+            let max_line_len = link_stats.max_line_len();
+            if is_skipping_line || message_buf.len() >= max_line_len {
+                if !is_skipping_line {
+                    warn!(
+                        len = message_buf.len(),
+                        max_line_len, "Dropped a line that was too long"
+                    );
+                    link_stats.record_dropped_line();
+                    messages.on_partial_message(&[]);
+                }
+                is_skipping_line = !message_buf.ends_with(b"\n");
+                message_buf.clear();
+                message_buf.shrink_to(max_line_len);
+            } else {
+                match find_message_in_buf(&message_buf) {
+                    Ok(message) => {
+                        let now = Instant::now();
+                        if let Some(command) = screen_dump.on_message(&message_buf, now) {
+                            Self::send_screen_dump_command(&serial_port, command);
+                        }
+                        if Self::is_unprompted_restart(&serial_port, &link_stats, &message_buf, now)
+                        {
+                            Self::record_link_event(
+                                &serial_port,
+                                &link_stats,
+                                LinkEventKind::Restarted,
+                                &message_buf,
+                                now,
+                            );
+                        }
+                        messages.cache_message(message);
+                        for command in messages.take_commands() {
+                            if let Err(error) = serial_port.send_command(command) {
+                                warn!(%error, "Failed to send a command requested by a message");
+                            }
+                        }
+                        link_stats.record_message(now);
+                        // Only the device's firmware sends messages we can parse, so it must have
+                        // left bootloader mode
+                        serial_port
+                            .connection_status()
+                            .set(ConnectionState::Connected);
+                        message_buf.clear();
+                        messages.on_partial_message(&[]);
+                    }
+                    Err(MessageParseError::Incomplete) => messages.on_partial_message(&message_buf),
+                    Err(_) => {
+                        if is_bootloader_banner(&message_buf) {
+                            Self::record_link_event(
+                                &serial_port,
+                                &link_stats,
+                                LinkEventKind::BootloaderBanner,
+                                &message_buf,
+                                Instant::now(),
+                            );
+                            serial_port
+                                .connection_status()
+                                .set(ConnectionState::Bootloader);
+                        } else {
+                            link_stats.record_parse_error();
+                            // The bootloader doesn't send messages, so only lines from the firmware
+                            // point to a problem with the link
+                            if serial_port.connection_status().get() != ConnectionState::Bootloader
+                            {
+                                Self::record_link_event(
+                                    &serial_port,
+                                    &link_stats,
+                                    classify_unknown_line(&message_buf),
+                                    &message_buf,
+                                    Instant::now(),
+                                );
+                            }
+                        }
+                        message_buf.clear();
+                        messages.on_partial_message(&[]);
+                    }
                 }
-                Err(MessageParseError::Incomplete) => (),
-                Err(_) => message_buf.clear(),
             }
+            link_stats.record_line_buf_capacity(message_buf.capacity());
 
             thread::sleep(Duration::from_millis(10));
         }
         debug!("Stopped reading messages from device");
     }
 
+    /// Returns whether a message shows the device restarted without being asked to, like after
+    /// losing power.
+    ///
+    /// Devices send their model and firmware version when they start and when they're asked for
+    /// their config, so it only points to a restart when messages were already being received
+    /// and no command was sent recently.
+    fn is_unprompted_restart(
+        serial_port: &SerialPort,
+        link_stats: &LinkStats,
+        message_buf: &[u8],
+        now: Instant,
+    ) -> bool {
+        const COMMAND_RESPONSE_WINDOW: Duration = Duration::from_secs(2);
+
+        is_setup_info_line(message_buf)
+            && link_stats.since_last_message(now).is_some()
+            && serial_port
+                .since_last_command(now)
+                .is_none_or(|since_last_command| since_last_command > COMMAND_RESPONSE_WINDOW)
+    }
+
+    /// Records something seen on the link and degrades the connection's state when it changes
+    /// the link's diagnosis.
+    fn record_link_event(
+        serial_port: &SerialPort,
+        link_stats: &LinkStats,
+        kind: LinkEventKind,
+        raw: &[u8],
+        now: Instant,
+    ) {
+        if let Some(diagnosis) = link_stats.record_event(kind, raw, now) {
+            serial_port.connection_status().set_diagnosis(diagnosis);
+        }
+    }
+
+    fn send_screen_dump_command(serial_port: &SerialPort, command: Command) {
+        if let Err(error) = serial_port.send_command(command) {
+            warn!(%error, "Failed to toggle screen dumping");
+        }
+    }
+
     /// Returns the message container populated by the background reader thread.
     pub fn messages(&self) -> &M {
         &self.messages
@@ -142,13 +565,43 @@ impl<M: MessageContainer> Device<M> {
         &self.serial_port
     }
 
+    /// Returns a handle to the serial port that doesn't keep it open after the device is dropped.
+    pub(crate) fn weak_serial_port(&self) -> Weak<SerialPort> {
+        Arc::downgrade(&self.serial_port)
+    }
+
+    /// Returns a handle to the message container that doesn't keep it alive after the device is
+    /// dropped.
+    pub(crate) fn weak_messages(&self) -> Weak<M> {
+        Arc::downgrade(&self.messages)
+    }
+
+    /// Runs the job on the device's timer thread once no newer job with the same key has been
+    /// scheduled for the debounce duration.
+    ///
+    /// Jobs that haven't run when the device is dropped are dropped without running.
+    pub(crate) fn debounce(
+        &self,
+        key: &'static str,
+        debounce: Duration,
+        job: impl FnOnce() + Send + 'static,
+    ) {
+        self.debouncer.schedule(key, debounce, job);
+    }
+
     /// Sends raw bytes to the device.
-    pub fn send_bytes(&self, bytes: impl AsRef<[u8]>) -> io::Result<()> {
+    ///
+    /// Returns [`Error::InvalidOperation`](crate::Error::InvalidOperation) if the device is in
+    /// bootloader mode.
+    pub fn send_bytes(&self, bytes: impl AsRef<[u8]>) -> crate::Result<()> {
         self.serial_port.send_bytes(bytes.as_ref())
     }
 
     /// Sends a command to the device.
-    pub fn send_command(&self, command: impl Into<Cow<'static, [u8]>>) -> io::Result<()> {
+    ///
+    /// Returns [`Error::InvalidOperation`](crate::Error::InvalidOperation) if the device is in
+    /// bootloader mode.
+    pub fn send_command(&self, command: impl Into<Cow<'static, [u8]>>) -> crate::Result<()> {
         self.serial_port.send_command(command.into())
     }
 
@@ -157,22 +610,212 @@ impl<M: MessageContainer> Device<M> {
         &self.serial_port.port_info().port_name
     }
 
+    /// Returns information about the connected serial port, such as the USB adapter's serial
+    /// number.
+    pub fn port_info(&self) -> &SerialPortInfo {
+        self.serial_port.port_info()
+    }
+
     /// Returns the serial connection's current baud rate.
     pub fn baud_rate(&self) -> io::Result<u32> {
         self.serial_port.baud_rate()
     }
 
-    fn stop_reading_messages(&mut self) {
-        self.is_reading.store(false, Ordering::Relaxed);
-        if let Some(read_thread_handle) = self.read_thread_handle.take() {
-            let _ = read_thread_handle.join();
+    /// Sets the minimum time between commands sent to the device.
+    ///
+    /// Use `Duration::ZERO` to send commands without any pacing.
+    pub fn set_min_command_interval(&self, min_command_interval: Duration) {
+        self.serial_port
+            .set_min_command_interval(min_command_interval);
+    }
+
+    /// Sets the number of attempts made for each write to the device and the delay between them.
+    ///
+    /// Writes that fail with an interrupted, timed out, or would block error are retried until
+    /// `attempts` writes have been made. Other errors are returned immediately.
+    pub fn set_write_retry(&self, attempts: u32, delay: Duration) {
+        self.serial_port.set_write_retry(attempts, delay);
+    }
+
+    /// Sets a callback that receives the exact bytes of every successful write to the device.
+    ///
+    /// The callback is called after the write completes with the time it completed.
+    pub fn set_command_log_callback(&self, cb: impl FnMut(&[u8], SystemTime) + Send + 'static) {
+        self.serial_port
+            .set_command_log_callback(Some(Box::new(cb)));
+    }
+
+    /// Removes the callback that receives the bytes written to the device.
+    pub fn remove_command_log_callback(&self) {
+        self.serial_port.set_command_log_callback(None);
+    }
+
+    /// Emits a hex-encoded `debug!` event for every write to the device.
+    ///
+    /// Events use the [`COMMAND_LOG_TARGET`](crate::COMMAND_LOG_TARGET) target. This replaces any
+    /// callback set with [`Device::set_command_log_callback`].
+    pub fn enable_command_logging(&self) {
+        self.set_command_log_callback(log_command);
+    }
+
+    /// Sets the length of the lines read from the device, in bytes, at which they're dropped.
+    pub(crate) fn set_max_line_len(&self, max_line_len: usize) {
+        self.link_stats.set_max_line_len(max_line_len);
+        self.serial_port
+            .set_max_message_len(max_line_len.try_into().unwrap_or(u64::MAX));
+    }
+
+    /// Returns the capacity of the reader's line buffer, in bytes.
+    pub(crate) fn line_buf_capacity(&self) -> usize {
+        self.link_stats.line_buf_capacity()
+    }
+
+    /// Returns the number of lines that were dropped because they were too long.
+    pub(crate) fn dropped_lines(&self) -> u64 {
+        self.link_stats.dropped_lines()
+    }
+
+    /// Returns counts of the writes made to the device.
+    pub fn write_stats(&self) -> WriteStats {
+        self.serial_port.write_stats()
+    }
+
+    /// Returns a snapshot of the connection's state for debugging.
+    pub fn debug_snapshot(&self) -> DebugSnapshot {
+        let write_retry = self.serial_port.write_retry();
+        DebugSnapshot {
+            port_name: self.port_name().to_string(),
+            baud_rate: self.baud_rate().ok(),
+            max_message_len: self.serial_port.max_message_len(),
+            min_command_interval: self.serial_port.min_command_interval(),
+            write_attempts: write_retry.attempts,
+            write_retry_delay: write_retry.delay,
+            is_holding: self.is_holding(),
+            write_stats: self.write_stats(),
+        }
+    }
+
+    /// Returns the health of the serial link to the device.
+    ///
+    /// The baud rate and read timeout are the ones the port was opened with or last set to, so
+    /// they can be read without waiting for the reader thread.
+    pub fn link_health(&self) -> LinkHealth {
+        link_health(&self.serial_port, &self.link_stats)
+    }
+
+    /// Returns a function that reads the link's health from another thread, without keeping the
+    /// device's serial port or statistics alive after it's dropped.
+    pub(crate) fn link_health_sampler(
+        &self,
+    ) -> impl Fn() -> Option<LinkHealth> + Send + 'static + use<M> {
+        let serial_port = Arc::downgrade(&self.serial_port);
+        let link_stats = Arc::downgrade(&self.link_stats);
+        move || {
+            let serial_port = serial_port.upgrade()?;
+            let link_stats = link_stats.upgrade()?;
+            Some(link_health(&serial_port, &link_stats))
         }
     }
+
+    /// Returns the state of the connection to the device.
+    pub fn connection_state(&self) -> ConnectionState {
+        self.serial_port.connection_status().get()
+    }
+
+    /// Sets a callback that's called when the state of the connection to the device changes.
+    ///
+    /// The callback is called on the thread that reads messages from the device, except for the
+    /// changes made by [`Device::reconnect`], which are reported on its caller's thread. A change
+    /// of the link's diagnosis is reported right after the event that changed it is read as
+    /// [`ConnectionState::Degraded`], or as [`ConnectionState::Connected`] once the link is
+    /// healthy again. A diagnosis that changes because its events have become too old is only
+    /// reported by [`Device::link_health`].
+    pub fn set_connection_state_callback(
+        &self,
+        cb: impl Fn(ConnectionState) + Send + Sync + 'static,
+    ) {
+        self.serial_port
+            .connection_status()
+            .set_callback(Some(Arc::new(cb)));
+    }
+
+    /// Removes the callback that's called when the state of the connection to the device changes.
+    pub fn remove_connection_state_callback(&self) {
+        self.serial_port.connection_status().set_callback(None);
+    }
+
+    /// Returns whether the device was last told to hold (stop collecting data).
+    pub fn is_holding(&self) -> bool {
+        self.is_holding.load(Ordering::Relaxed)
+    }
+
+    /// Records whether the device was told to hold or to resume collecting data.
+    pub fn set_holding(&self, is_holding: bool) {
+        self.is_holding.store(is_holding, Ordering::Relaxed);
+    }
+
+    /// Returns whether the device is told to hold and stop sending its screen when it's dropped.
+    pub fn quiesce_on_drop(&self) -> bool {
+        self.quiesce_on_drop.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether the device is told to hold and stop sending its screen when it's dropped, so
+    /// it isn't still streaming data when its port is next opened.
+    ///
+    /// Each command is written once with a short write timeout and failures are ignored, so
+    /// dropping the device takes at most a few hundred milliseconds longer.
+    pub fn set_quiesce_on_drop(&self, quiesce_on_drop: bool) {
+        self.quiesce_on_drop
+            .store(quiesce_on_drop, Ordering::Relaxed);
+    }
+
+    /// Returns how the device was last told to send its screen.
+    pub(crate) fn screen_dump_mode(&self) -> ScreenDumpMode {
+        self.screen_dump.mode()
+    }
+
+    /// Tells the device how to send its screen.
+    ///
+    /// In [`ScreenDumpMode::Interval`], the reader thread toggles screen dumping on and off to
+    /// capture one frame per interval.
+    pub(crate) fn set_screen_dump_mode(&self, mode: ScreenDumpMode) -> crate::Result<()> {
+        let command = self.screen_dump.set_mode(mode, Instant::now());
+        self.send_command(command)
+    }
+
+    fn stop_reading_messages(&self) {
+        let Some(reader) = self.reader.lock().unwrap().take() else {
+            return;
+        };
+        reader.is_reading.store(false, Ordering::Relaxed);
+        // A callback called by the reader, like the disconnect callback, can't wait for the
+        // reader to stop, but the reader stops on its own once the callback returns
+        if reader.handle.thread().id() != thread::current().id() {
+            let _ = reader.handle.join();
+        }
+    }
+}
+
+/// The thread that reads messages from a device and the flag that tells it to stop.
+#[derive(Debug)]
+struct ReaderThread {
+    is_reading: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
 }
 
 impl<M: MessageContainer> Drop for Device<M> {
     fn drop(&mut self) {
-        self.stop_reading_messages()
+        // Drop the debounced commands that haven't been sent so they can't be sent while quiescing
+        self.debouncer.stop();
+        // Stop reading first so the screen dump timer can't turn screen dumping back on and the
+        // writes don't wait for a read to time out
+        self.stop_reading_messages();
+        if self.quiesce_on_drop() {
+            self.serial_port.send_commands_before_close(
+                [Command::Hold, Command::DisableDumpScreen].map(Cow::from),
+                Self::QUIESCE_WRITE_TIMEOUT,
+            );
+        }
     }
 }
 
@@ -187,3 +830,17 @@ where
         error => Err(error),
     })
 }
+
+fn link_health(serial_port: &SerialPort, link_stats: &LinkStats) -> LinkHealth {
+    let now = Instant::now();
+    let (diagnosis, evidence) = link_stats.diagnose(now);
+    LinkHealth {
+        baud_rate: serial_port.known_baud_rate(),
+        read_timeout: serial_port.read_timeout(),
+        bytes_per_sec: link_stats.bytes_per_sec(now),
+        read_errors: link_stats.read_errors(),
+        since_last_message: link_stats.since_last_message(now),
+        diagnosis,
+        evidence,
+    }
+}
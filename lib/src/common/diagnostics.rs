@@ -0,0 +1,308 @@
+use std::{
+    io::{self, ErrorKind},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use tracing::debug;
+
+use super::{LinkDiagnosis, LinkEvent, LinkEventKind, LinkEvents, MemoryBudget};
+
+/// The tracing target of the events emitted for commands once command logging is enabled.
+pub const COMMAND_LOG_TARGET: &str = "rfe::commands";
+
+/// Callback that receives the exact bytes of every write made to a device and when it completed.
+pub(crate) type CommandLogCallback = Box<dyn FnMut(&[u8], SystemTime) + Send>;
+
+/// Emits a debug event containing the hex-encoded bytes of a command.
+pub(crate) fn log_command(bytes: &[u8], sent_at: SystemTime) {
+    debug!(target: COMMAND_LOG_TARGET, bytes = %hex(bytes), ?sent_at, "Sent command");
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Counts of the writes made to a device's serial port.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct WriteStats {
+    /// The number of writes that succeeded, including those that succeeded after a retry.
+    pub successful_writes: u64,
+    /// The number of writes that failed after every allowed attempt.
+    pub failed_writes: u64,
+    /// The number of times a write was retried after a retriable error.
+    pub retries: u64,
+}
+
+/// A snapshot of a device connection's state for debugging.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugSnapshot {
+    /// The name of the serial port the device is connected through.
+    pub port_name: String,
+    /// The serial connection's baud rate, if it could be read.
+    pub baud_rate: Option<u32>,
+    /// The maximum length of a message read from the device.
+    pub max_message_len: u64,
+    /// The minimum time between commands sent to the device.
+    pub min_command_interval: Duration,
+    /// The maximum number of attempts made for each write.
+    pub write_attempts: u32,
+    /// The delay between write attempts.
+    pub write_retry_delay: Duration,
+    /// Whether the device was last told to hold.
+    pub is_holding: bool,
+    /// Counts of the writes made to the device.
+    pub write_stats: WriteStats,
+}
+
+/// Counts of the problems seen while reading messages from a device.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct ReadErrorCounts {
+    /// The number of reads that timed out because the device didn't send anything.
+    pub timeouts: u64,
+    /// The number of lines that weren't a known message, like lines corrupted by a noisy link.
+    pub parse_errors: u64,
+    /// The number of reads that failed with any other error. The device is disconnected after
+    /// the first of these.
+    pub io_errors: u64,
+}
+
+/// The health of the serial link to a device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkHealth {
+    /// The serial connection's baud rate, if it could be read.
+    pub baud_rate: Option<u32>,
+    /// How long a read waits for the device to send data before it times out.
+    pub read_timeout: Duration,
+    /// The average number of bytes received per second over the last few seconds.
+    pub bytes_per_sec: f64,
+    /// Counts of the problems seen while reading messages from the device.
+    pub read_errors: ReadErrorCounts,
+    /// The time since a message was last parsed, or `None` if no message has been parsed.
+    pub since_last_message: Option<Duration>,
+    /// The most likely cause of the problems seen recently on the link.
+    pub diagnosis: LinkDiagnosis,
+    /// The events the diagnosis was made from, oldest first.
+    pub evidence: Vec<LinkEvent>,
+}
+
+/// Counters of a device's reads that are updated by its reader thread.
+pub(crate) struct LinkStats {
+    started_at: Instant,
+    /// The bytes read during each second of the throughput window, keyed by the number of
+    /// seconds since `started_at`. A fixed number of buckets keeps reads from allocating.
+    read_buckets: Mutex<[(u64, u64); Self::THROUGHPUT_WINDOW_SECS as usize + 1]>,
+    timeouts: AtomicU64,
+    parse_errors: AtomicU64,
+    io_errors: AtomicU64,
+    /// Lines longer than this are dropped instead of growing the line buffer.
+    max_line_len: AtomicUsize,
+    line_buf_capacity: AtomicUsize,
+    dropped_lines: AtomicU64,
+    last_message_at: Mutex<Option<Instant>>,
+    events: Mutex<LinkEvents>,
+}
+
+impl LinkStats {
+    /// How many seconds back reads are counted when calculating the throughput.
+    const THROUGHPUT_WINDOW_SECS: u64 = 5;
+
+    pub(crate) fn new(started_at: Instant) -> Self {
+        LinkStats {
+            started_at,
+            read_buckets: Mutex::new(Default::default()),
+            timeouts: AtomicU64::new(0),
+            parse_errors: AtomicU64::new(0),
+            io_errors: AtomicU64::new(0),
+            max_line_len: AtomicUsize::new(MemoryBudget::DEFAULT_MAX_LINE_LEN),
+            line_buf_capacity: AtomicUsize::new(0),
+            dropped_lines: AtomicU64::new(0),
+            last_message_at: Mutex::new(None),
+            events: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn record_read(&self, len: usize, now: Instant) {
+        let sec = now.saturating_duration_since(self.started_at).as_secs();
+        let mut read_buckets = self.read_buckets.lock().unwrap();
+        let bucket_count = read_buckets.len() as u64;
+        let (bucket_sec, bytes) = &mut read_buckets[(sec % bucket_count) as usize];
+        if *bucket_sec != sec {
+            *bucket_sec = sec;
+            *bytes = 0;
+        }
+        *bytes += len as u64;
+    }
+
+    pub(crate) fn record_read_error(&self, error: &io::Error) {
+        let counter = if error.kind() == ErrorKind::TimedOut {
+            &self.timeouts
+        } else {
+            &self.io_errors
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_parse_error(&self) {
+        self.parse_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn max_line_len(&self) -> usize {
+        self.max_line_len.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_max_line_len(&self, max_line_len: usize) {
+        self.max_line_len.store(max_line_len, Ordering::Relaxed);
+    }
+
+    /// Records the capacity of the reader's line buffer so its memory usage can be estimated.
+    pub(crate) fn record_line_buf_capacity(&self, capacity: usize) {
+        self.line_buf_capacity.store(capacity, Ordering::Relaxed);
+    }
+
+    pub(crate) fn line_buf_capacity(&self) -> usize {
+        self.line_buf_capacity.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_dropped_line(&self) {
+        self.dropped_lines.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn dropped_lines(&self) -> u64 {
+        self.dropped_lines.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_message(&self, now: Instant) {
+        *self.last_message_at.lock().unwrap() = Some(now);
+    }
+
+    /// Records something seen on the link and returns the link's new diagnosis if it changed.
+    pub(crate) fn record_event(
+        &self,
+        kind: LinkEventKind,
+        raw: &[u8],
+        now: Instant,
+    ) -> Option<LinkDiagnosis> {
+        let (diagnosis, evidence) = self.events.lock().unwrap().push(kind, raw, now)?;
+        debug!(
+            ?diagnosis,
+            ?kind,
+            evidence = evidence.len(),
+            "The serial link's diagnosis changed"
+        );
+        Some(diagnosis)
+    }
+
+    pub(crate) fn diagnose(&self, now: Instant) -> (LinkDiagnosis, Vec<LinkEvent>) {
+        self.events.lock().unwrap().diagnose(now)
+    }
+
+    pub(crate) fn read_errors(&self) -> ReadErrorCounts {
+        ReadErrorCounts {
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            parse_errors: self.parse_errors.load(Ordering::Relaxed),
+            io_errors: self.io_errors.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The average number of bytes read per second within the throughput window.
+    pub(crate) fn bytes_per_sec(&self, now: Instant) -> f64 {
+        let age = now.saturating_duration_since(self.started_at);
+        let first_sec = age.as_secs().saturating_sub(Self::THROUGHPUT_WINDOW_SECS);
+        let bytes: u64 = self
+            .read_buckets
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(sec, _)| (first_sec..=age.as_secs()).contains(sec))
+            .map(|(_, bytes)| bytes)
+            .sum();
+        // The bytes are averaged over the time since the start of the oldest counted second,
+        // which includes the part of the current second that has passed, or over the lifetime of
+        // connections younger than the window
+        let span = age
+            .saturating_sub(Duration::from_secs(first_sec))
+            .max(Duration::from_secs(1));
+        bytes as f64 / span.as_secs_f64()
+    }
+
+    pub(crate) fn since_last_message(&self, now: Instant) -> Option<Duration> {
+        self.last_message_at
+            .lock()
+            .unwrap()
+            .map(|last_message_at| now.saturating_duration_since(last_message_at))
+    }
+}
+
+impl std::fmt::Debug for LinkStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LinkStats")
+            .field("started_at", &self.started_at)
+            .field("read_errors", &self.read_errors())
+            .field("events", &self.events.lock().unwrap())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_link_stats() {
+        let started_at = Instant::now();
+        let stats = LinkStats::new(started_at);
+        assert_eq!(stats.bytes_per_sec(started_at), 0.0);
+        assert_eq!(stats.since_last_message(started_at), None);
+
+        stats.record_read(1_000, started_at + Duration::from_secs(1));
+        stats.record_read(1_000, started_at + Duration::from_secs(2));
+        stats.record_message(started_at + Duration::from_secs(2));
+        // Young connections are averaged over their lifetime
+        assert_eq!(
+            stats.bytes_per_sec(started_at + Duration::from_secs(2)),
+            1_000.0
+        );
+        assert_eq!(
+            stats.since_last_message(started_at + Duration::from_secs(3)),
+            Some(Duration::from_secs(1))
+        );
+
+        // Reads older than the window aren't counted
+        stats.record_read(500, started_at + Duration::from_secs(7));
+        assert_eq!(
+            stats.bytes_per_sec(started_at + Duration::from_secs(7)),
+            300.0
+        );
+        // Reads in the current second are averaged over the part of it that has passed
+        assert_eq!(
+            stats.bytes_per_sec(started_at + Duration::from_millis(7_500)),
+            1_500.0 / 5.5
+        );
+
+        stats.record_read_error(&io::Error::from(ErrorKind::TimedOut));
+        stats.record_read_error(&io::Error::from(ErrorKind::BrokenPipe));
+        stats.record_parse_error();
+        assert_eq!(
+            stats.read_errors(),
+            ReadErrorCounts {
+                timeouts: 1,
+                parse_errors: 1,
+                io_errors: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn hex_encode_command() {
+        assert_eq!(hex(b"#\x04C0"), "23 04 43 30");
+        assert_eq!(hex(&[]), "");
+    }
+}
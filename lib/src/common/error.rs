@@ -1,7 +1,14 @@
-use std::{io, time::Duration};
+use std::{
+    convert::Infallible,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use thiserror::Error;
 
+use super::RadioModule;
+
 #[derive(Error, Debug)]
 /// Error returned by high-level RF Explorer operations.
 pub enum Error {
@@ -17,9 +24,30 @@ pub enum Error {
     #[error("Invalid operation: {}", .0)]
     InvalidOperation(String),
 
+    /// No RF Explorer could be found to connect to.
+    #[error(
+        "No RF Explorer was found. Make sure it's plugged in and not in use by another program."
+    )]
+    NoDeviceFound,
+
+    /// The RF Explorer does not contain an expansion radio module.
+    #[error("Invalid operation: This RF Explorer does not contain an expansion radio module.")]
+    NoExpansionModule,
+
+    /// The radio module being activated is already active.
+    #[error("Invalid operation: {} radio module is already active.", .0)]
+    AlreadyActive(RadioModule),
+
     /// An underlying I/O operation failed.
     #[error(transparent)]
-    Io(#[from] io::Error),
+    Io(io::Error),
+
+    /// The connection to the device was lost, so it can't respond.
+    ///
+    /// Contains the error that stopped messages from being read from the device, like the one
+    /// returned after its USB cable is unplugged.
+    #[error("The RF Explorer was disconnected: {}", .0)]
+    Disconnected(#[source] Arc<io::Error>),
 
     /// The device did not respond before the timeout elapsed.
     #[error("Failed to complete the operation within the timeout duration ({} ms)", .0.as_millis())]
@@ -28,3 +56,68 @@ pub enum Error {
 
 /// Result type returned by high-level RF Explorer operations.
 pub type Result<T> = std::result::Result<T, Error>;
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<Infallible> for Error {
+    fn from(infallible: Infallible) -> Self {
+        match infallible {}
+    }
+}
+
+/// The error that stopped a device's messages from being read, if any.
+#[derive(Debug, Default)]
+pub(crate) struct Disconnection(Mutex<Option<Arc<io::Error>>>);
+
+impl Disconnection {
+    pub(crate) fn set(&self, error: io::Error) {
+        self.0.lock().unwrap().get_or_insert(Arc::new(error));
+    }
+
+    /// Forgets the error after the device was reconnected.
+    pub(crate) fn clear(&self) {
+        self.0.lock().unwrap().take();
+    }
+
+    pub(crate) fn is_disconnected(&self) -> bool {
+        self.0.lock().unwrap().is_some()
+    }
+
+    /// Returns the error that disconnected the device, if it has been disconnected.
+    pub(crate) fn error(&self) -> Option<Arc<io::Error>> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Returns `Error::Disconnected` if the device has been disconnected.
+    pub(crate) fn check(&self) -> Result<()> {
+        match self.0.lock().unwrap().as_ref() {
+            Some(error) => Err(Error::Disconnected(error.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radio_module_error_messages() {
+        assert_eq!(
+            Error::NoExpansionModule.to_string(),
+            "Invalid operation: This RF Explorer does not contain an expansion radio module."
+        );
+        assert_eq!(
+            Error::AlreadyActive(RadioModule::Main).to_string(),
+            "Invalid operation: Main radio module is already active."
+        );
+        assert_eq!(
+            Error::AlreadyActive(RadioModule::Expansion).to_string(),
+            "Invalid operation: Expansion radio module is already active."
+        );
+    }
+}
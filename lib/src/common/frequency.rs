@@ -1,5 +1,8 @@
-use std::fmt::Debug;
-use std::ops::{Add, Div, Mul, Sub};
+use std::fmt::{Debug, Display};
+use std::ops::{Add, Div, Mul, RangeInclusive, Sub};
+use std::str::FromStr;
+
+use thiserror::Error;
 use uom::si::{
     f32, f64,
     frequency::{gigahertz, hertz, kilohertz, megahertz},
@@ -20,6 +23,18 @@ impl Frequency {
         }
     }
 
+    /// Creates a frequency from hertz, rounded to the nearest hertz.
+    ///
+    /// Rounding instead of truncating keeps values like 433.92 MHz, which floats can't represent
+    /// exactly, from coming out a hertz low.
+    fn from_hz_f64(hz: f64) -> Frequency {
+        Frequency::from_hz(hz.round() as u64)
+    }
+
+    fn from_hz_f32(hz: f32) -> Frequency {
+        Frequency::from_hz(hz.round() as u64)
+    }
+
     /// Creates a frequency from kilohertz.
     pub fn from_khz(khz: u64) -> Frequency {
         Frequency {
@@ -27,30 +42,26 @@ impl Frequency {
         }
     }
 
-    /// Creates a frequency from kilohertz, returning zero for negative or overflowing values.
+    /// Creates a frequency from kilohertz, rounded to the nearest hertz.
+    ///
+    /// Returns zero for negative or overflowing values.
     pub fn from_khz_f32(khz: f32) -> Frequency {
         if khz.is_sign_negative() || (u64::MAX as f32) < khz {
             return Frequency::default();
         }
 
-        Frequency {
-            freq: u64::Frequency::new::<hertz>(
-                f32::Frequency::new::<kilohertz>(khz).get::<hertz>() as u64,
-            ),
-        }
+        Frequency::from_hz_f32(f32::Frequency::new::<kilohertz>(khz).get::<hertz>())
     }
 
-    /// Creates a frequency from kilohertz, returning zero for negative or overflowing values.
+    /// Creates a frequency from kilohertz, rounded to the nearest hertz.
+    ///
+    /// Returns zero for negative or overflowing values.
     pub fn from_khz_f64(khz: f64) -> Frequency {
         if khz.is_sign_negative() || (u64::MAX as f64) < khz {
             return Frequency::default();
         }
 
-        Frequency {
-            freq: u64::Frequency::new::<hertz>(
-                f64::Frequency::new::<kilohertz>(khz).get::<hertz>() as u64,
-            ),
-        }
+        Frequency::from_hz_f64(f64::Frequency::new::<kilohertz>(khz).get::<hertz>())
     }
 
     /// Creates a frequency from megahertz.
@@ -60,30 +71,26 @@ impl Frequency {
         }
     }
 
-    /// Creates a frequency from megahertz, returning zero for negative or overflowing values.
+    /// Creates a frequency from megahertz, rounded to the nearest hertz.
+    ///
+    /// Returns zero for negative or overflowing values.
     pub fn from_mhz_f32(mhz: f32) -> Frequency {
         if mhz.is_sign_negative() || (u64::MAX as f32) < mhz {
             return Frequency::default();
         }
 
-        Frequency {
-            freq: u64::Frequency::new::<hertz>(
-                f32::Frequency::new::<megahertz>(mhz).get::<hertz>() as u64,
-            ),
-        }
+        Frequency::from_hz_f32(f32::Frequency::new::<megahertz>(mhz).get::<hertz>())
     }
 
-    /// Creates a frequency from megahertz, returning zero for negative or overflowing values.
+    /// Creates a frequency from megahertz, rounded to the nearest hertz.
+    ///
+    /// Returns zero for negative or overflowing values.
     pub fn from_mhz_f64(mhz: f64) -> Frequency {
         if mhz.is_sign_negative() || (u64::MAX as f64) < mhz {
             return Frequency::default();
         }
 
-        Frequency {
-            freq: u64::Frequency::new::<hertz>(
-                f64::Frequency::new::<megahertz>(mhz).get::<hertz>() as u64,
-            ),
-        }
+        Frequency::from_hz_f64(f64::Frequency::new::<megahertz>(mhz).get::<hertz>())
     }
 
     /// Creates a frequency from gigahertz.
@@ -93,30 +100,26 @@ impl Frequency {
         }
     }
 
-    /// Creates a frequency from gigahertz, returning zero for negative or overflowing values.
+    /// Creates a frequency from gigahertz, rounded to the nearest hertz.
+    ///
+    /// Returns zero for negative or overflowing values.
     pub fn from_ghz_f32(ghz: f32) -> Frequency {
         if ghz.is_sign_negative() || (u64::MAX as f32) < ghz {
             return Frequency::default();
         }
 
-        Frequency {
-            freq: u64::Frequency::new::<hertz>(
-                f32::Frequency::new::<gigahertz>(ghz).get::<hertz>() as u64,
-            ),
-        }
+        Frequency::from_hz_f32(f32::Frequency::new::<gigahertz>(ghz).get::<hertz>())
     }
 
-    /// Creates a frequency from gigahertz, returning zero for negative or overflowing values.
+    /// Creates a frequency from gigahertz, rounded to the nearest hertz.
+    ///
+    /// Returns zero for negative or overflowing values.
     pub fn from_ghz_f64(ghz: f64) -> Frequency {
         if ghz.is_sign_negative() || (u64::MAX as f64) < ghz {
             return Frequency::default();
         }
 
-        Frequency {
-            freq: u64::Frequency::new::<hertz>(
-                f64::Frequency::new::<gigahertz>(ghz).get::<hertz>() as u64,
-            ),
-        }
+        Frequency::from_hz_f64(f64::Frequency::new::<gigahertz>(ghz).get::<hertz>())
     }
 
     /// Returns the frequency in hertz.
@@ -183,6 +186,126 @@ impl Frequency {
     pub fn abs_diff(self, other: Frequency) -> Frequency {
         Frequency::from_hz(self.as_hz().abs_diff(other.as_hz()))
     }
+
+    /// Formats the frequency using the largest unit that keeps its value at or above 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rfe::Frequency;
+    ///
+    /// assert_eq!(Frequency::from_mhz(2_400).format_engineering(3), "2.400 GHz");
+    /// ```
+    pub fn format_engineering(&self, precision: usize) -> String {
+        self.format_in(FrequencyUnit::for_freq(*self), precision)
+    }
+
+    /// Formats the frequency in the given unit.
+    ///
+    /// Use [`FrequencyUnit::for_range`] to format every label on an axis in the same unit.
+    pub fn format_in(&self, unit: FrequencyUnit, precision: usize) -> String {
+        let value = match unit {
+            FrequencyUnit::Hz => self.as_hz_f64(),
+            FrequencyUnit::Khz => self.as_khz_f64(),
+            FrequencyUnit::Mhz => self.as_mhz_f64(),
+            FrequencyUnit::Ghz => self.as_ghz_f64(),
+        };
+        format!("{value:.precision$} {unit}")
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// Unit used to display frequencies.
+pub enum FrequencyUnit {
+    /// Hertz.
+    Hz,
+    /// Kilohertz.
+    Khz,
+    /// Megahertz.
+    Mhz,
+    /// Gigahertz.
+    Ghz,
+}
+
+impl FrequencyUnit {
+    /// Returns the largest unit that keeps the frequency's value at or above 1.
+    pub fn for_freq(freq: Frequency) -> FrequencyUnit {
+        match freq.as_hz() {
+            1_000_000_000.. => FrequencyUnit::Ghz,
+            1_000_000.. => FrequencyUnit::Mhz,
+            1_000.. => FrequencyUnit::Khz,
+            _ => FrequencyUnit::Hz,
+        }
+    }
+
+    /// Returns the unit used to label every frequency in the range.
+    ///
+    /// The unit is chosen from the end of the range so that all labels share it.
+    pub fn for_range(range: &RangeInclusive<Frequency>) -> FrequencyUnit {
+        FrequencyUnit::for_freq(*range.start().max(range.end()))
+    }
+}
+
+impl Display for FrequencyUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = match self {
+            Self::Hz => "Hz",
+            Self::Khz => "kHz",
+            Self::Mhz => "MHz",
+            Self::Ghz => "GHz",
+        };
+        write!(f, "{unit}")
+    }
+}
+
+/// Returns evenly spaced tick positions within the range for labeling a frequency axis.
+///
+/// Ticks are placed at multiples of the smallest step of the form 1, 2, or 5 × 10ⁿ Hz that
+/// produces at most `max_ticks` ticks.
+///
+/// # Examples
+///
+/// ```
+/// use rfe::{Frequency, ticks};
+///
+/// let ticks = ticks(Frequency::from_mhz(2_400)..=Frequency::from_mhz(2_500), 5);
+/// assert_eq!(
+///     ticks,
+///     [2_400, 2_450, 2_500].map(Frequency::from_mhz)
+/// );
+/// ```
+pub fn ticks(range: RangeInclusive<Frequency>, max_ticks: usize) -> Vec<Frequency> {
+    let (start, end) = (range.start().as_hz(), range.end().as_hz());
+    if max_ticks == 0 || start > end {
+        return Vec::new();
+    }
+    if start == end {
+        return vec![Frequency::from_hz(start)];
+    }
+
+    let max_ticks = u64::try_from(max_ticks).unwrap_or(u64::MAX);
+    let tick_count = |step: u64| {
+        (end / step)
+            .saturating_add(1)
+            .saturating_sub(start.div_ceil(step))
+    };
+
+    // Find the smallest 1-2-5 step that doesn't produce too many ticks
+    let Some(step) = (0..20)
+        .flat_map(|exponent| {
+            [1, 2, 5].map(|multiplier| 10u64.checked_pow(exponent)?.checked_mul(multiplier))
+        })
+        .map_while(|step| step)
+        .find(|&step| tick_count(step) <= max_ticks)
+    else {
+        return Vec::new();
+    };
+
+    let first = start.div_ceil(step) * step;
+    (first..=end)
+        .step_by(usize::try_from(step).unwrap_or(usize::MAX))
+        .map(Frequency::from_hz)
+        .collect()
 }
 
 impl Add for Frequency {
@@ -251,6 +374,110 @@ impl From<u64> for Frequency {
     }
 }
 
+/// Error returned when a string can't be parsed as a [`Frequency`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseFrequencyError {
+    /// The string doesn't contain a number.
+    #[error("Enter a frequency")]
+    Empty,
+
+    /// The number isn't a non-negative decimal number.
+    #[error("'{}' is not a valid number", .0)]
+    InvalidNumber(String),
+
+    /// The unit isn't Hz, kHz, MHz, or GHz.
+    #[error("'{}' is not a frequency unit", .0)]
+    UnknownUnit(String),
+
+    /// The frequency is too large to be stored in hertz.
+    #[error("The frequency is too large")]
+    TooLarge,
+}
+
+impl FromStr for Frequency {
+    type Err = ParseFrequencyError;
+
+    /// Parses a decimal number followed by an optional unit, like "2.44G", "915M", or
+    /// "433.92 MHz".
+    ///
+    /// Units are case-insensitive and the trailing "Hz" may be left off. Numbers without a unit
+    /// are in hertz. Digits finer than 1 Hz are rounded to the nearest hertz.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rfe::Frequency;
+    ///
+    /// assert_eq!("433.92 MHz".parse(), Ok(Frequency::from_khz(433_920)));
+    /// assert_eq!("2.44G".parse(), Ok(Frequency::from_mhz(2_440)));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let number_len = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(number_len);
+        if number.is_empty() {
+            return Err(if unit.is_empty() {
+                ParseFrequencyError::Empty
+            } else {
+                ParseFrequencyError::InvalidNumber(s.to_string())
+            });
+        }
+
+        let exponent = match unit.trim_start().to_ascii_lowercase().as_str() {
+            "" | "hz" => 0,
+            "k" | "khz" => 3,
+            "m" | "mhz" => 6,
+            "g" | "ghz" => 9,
+            _ => return Err(ParseFrequencyError::UnknownUnit(unit.trim().to_string())),
+        };
+
+        let invalid_number = || ParseFrequencyError::InvalidNumber(number.to_string());
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if fraction.contains('.') || (whole.is_empty() && fraction.is_empty()) {
+            return Err(invalid_number());
+        }
+
+        // Parse the digits as an integer number of hertz so no precision is lost to floats
+        let mut hz: u64 = 0;
+        let digits = whole.bytes().chain(fraction.bytes().take(exponent));
+        for digit in digits.chain(std::iter::repeat_n(
+            b'0',
+            exponent.saturating_sub(fraction.len()),
+        )) {
+            hz = hz
+                .checked_mul(10)
+                .and_then(|hz| hz.checked_add(u64::from(digit - b'0')))
+                .ok_or(ParseFrequencyError::TooLarge)?;
+        }
+        if fraction
+            .as_bytes()
+            .get(exponent)
+            .is_some_and(|&digit| digit >= b'5')
+        {
+            hz = hz.checked_add(1).ok_or(ParseFrequencyError::TooLarge)?;
+        }
+
+        Ok(Frequency::from_hz(hz))
+    }
+}
+
+/// Frequencies are serialized as a number of hertz.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Frequency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.as_hz())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Frequency {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <u64 as serde::Deserialize>::deserialize(deserializer).map(Frequency::from_hz)
+    }
+}
+
 impl Debug for Frequency {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Frequency")
@@ -296,6 +523,21 @@ mod tests {
         assert_eq!(frequency.as_hz(), 1_000_000_000);
     }
 
+    #[test]
+    fn round_float_frequencies_to_nearest_hz() {
+        assert_eq!(
+            Frequency::from_mhz_f64(433.92),
+            Frequency::from_khz(433_920)
+        );
+        assert_eq!(Frequency::from_ghz_f64(2.412), Frequency::from_mhz(2_412));
+        assert_eq!(Frequency::from_khz_f64(0.0006), Frequency::from_hz(1));
+        assert_eq!(Frequency::from_khz_f64(0.0004), Frequency::from_hz(0));
+        assert_eq!(
+            Frequency::from_mhz_f32(433.92),
+            Frequency::from_khz(433_920)
+        );
+    }
+
     #[test]
     fn frequency_to_khz() {
         let frequency = Frequency::from_hz(1_000_000_000);
@@ -491,6 +733,55 @@ mod tests {
         let _ = Frequency::from_hz(1) - Frequency::from_ghz(1);
     }
 
+    #[test]
+    fn parse_frequency() {
+        assert_eq!("2.44G".parse(), Ok(Frequency::from_mhz(2_440)));
+        assert_eq!("915M".parse(), Ok(Frequency::from_mhz(915)));
+        assert_eq!("433.92 MHz".parse(), Ok(Frequency::from_khz(433_920)));
+        assert_eq!(" 100 khz ".parse(), Ok(Frequency::from_khz(100)));
+        assert_eq!("50hz".parse(), Ok(Frequency::from_hz(50)));
+        assert_eq!("1234".parse(), Ok(Frequency::from_hz(1_234)));
+        assert_eq!(".5k".parse(), Ok(Frequency::from_hz(500)));
+        assert_eq!("7.".parse(), Ok(Frequency::from_hz(7)));
+        assert_eq!(
+            "2.4000000006 GHz".parse(),
+            Ok(Frequency::from_hz(2_400_000_001))
+        );
+        assert_eq!("1.4 Hz".parse(), Ok(Frequency::from_hz(1)));
+    }
+
+    #[test]
+    fn parse_formatted_frequency() {
+        let freq = Frequency::from_khz(2_412_500);
+        assert_eq!(freq.format_engineering(6).parse(), Ok(freq));
+        assert_eq!(freq.format_in(FrequencyUnit::Khz, 1).parse(), Ok(freq));
+    }
+
+    #[test]
+    fn parse_invalid_frequency() {
+        assert_eq!("".parse::<Frequency>(), Err(ParseFrequencyError::Empty));
+        assert_eq!(
+            "MHz".parse::<Frequency>(),
+            Err(ParseFrequencyError::InvalidNumber("MHz".to_string()))
+        );
+        assert_eq!(
+            "-5M".parse::<Frequency>(),
+            Err(ParseFrequencyError::InvalidNumber("-5M".to_string()))
+        );
+        assert_eq!(
+            "1.2.3".parse::<Frequency>(),
+            Err(ParseFrequencyError::InvalidNumber("1.2.3".to_string()))
+        );
+        assert_eq!(
+            "2.4 THz".parse::<Frequency>(),
+            Err(ParseFrequencyError::UnknownUnit("THz".to_string()))
+        );
+        assert_eq!(
+            "100000000000000 GHz".parse::<Frequency>(),
+            Err(ParseFrequencyError::TooLarge)
+        );
+    }
+
     #[test]
     fn multiply() {
         let freq = Frequency::from_hz(1) * 2;
@@ -526,4 +817,71 @@ mod tests {
     fn divide_by_zero() {
         let _ = Frequency::from_hz(1) / 0;
     }
+
+    #[test]
+    fn ticks_for_representative_ranges() {
+        let mhz = |freqs: &[u64]| {
+            freqs
+                .iter()
+                .map(|&mhz| Frequency::from_mhz(mhz))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ticks(Frequency::from_mhz(2_400)..=Frequency::from_mhz(2_500), 6),
+            mhz(&[2_400, 2_420, 2_440, 2_460, 2_480, 2_500])
+        );
+        assert_eq!(
+            ticks(Frequency::from_mhz(2_400)..=Frequency::from_mhz(2_500), 5),
+            mhz(&[2_400, 2_450, 2_500])
+        );
+        assert_eq!(
+            ticks(Frequency::from_mhz(88)..=Frequency::from_mhz(108), 5),
+            mhz(&[90, 95, 100, 105])
+        );
+        assert_eq!(
+            ticks(
+                Frequency::from_khz(433_050)..=Frequency::from_khz(434_790),
+                4
+            ),
+            [433_500, 434_000, 434_500].map(Frequency::from_khz)
+        );
+        assert_eq!(
+            ticks(Frequency::from_hz(0)..=Frequency::from_khz(960_000), 8),
+            mhz(&[0, 200, 400, 600, 800])
+        );
+    }
+
+    #[test]
+    fn ticks_for_degenerate_ranges() {
+        let freq = Frequency::from_mhz(100);
+        assert_eq!(ticks(freq..=freq, 5), [freq]);
+        assert!(ticks(freq..=Frequency::from_mhz(200), 0).is_empty());
+        assert!(ticks(Frequency::from_mhz(200)..=freq, 5).is_empty());
+    }
+
+    #[test]
+    fn format_engineering() {
+        assert_eq!(
+            Frequency::from_mhz(2_400).format_engineering(3),
+            "2.400 GHz"
+        );
+        assert_eq!(
+            Frequency::from_khz(433_920).format_engineering(2),
+            "433.92 MHz"
+        );
+        assert_eq!(Frequency::from_khz(50).format_engineering(0), "50 kHz");
+        assert_eq!(Frequency::from_hz(500).format_engineering(1), "500.0 Hz");
+    }
+
+    #[test]
+    fn format_axis_in_one_unit() {
+        let range = Frequency::from_mhz(900)..=Frequency::from_mhz(1_100);
+        let unit = FrequencyUnit::for_range(&range);
+        assert_eq!(unit, FrequencyUnit::Ghz);
+        let labels: Vec<_> = ticks(range, 3)
+            .iter()
+            .map(|tick| tick.format_in(unit, 1))
+            .collect();
+        assert_eq!(labels, ["0.9 GHz", "1.0 GHz", "1.1 GHz"]);
+    }
 }
@@ -0,0 +1,433 @@
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+/// The most likely cause of the problems seen on a serial link.
+///
+/// The diagnosis comes from heuristics that have only been tested against synthetic transcripts,
+/// not captures of failing hardware, so treat it as a hint.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum LinkDiagnosis {
+    /// No problems have been seen recently.
+    #[default]
+    Healthy,
+    /// The device keeps restarting, like when an underpowered USB hub browns out.
+    SuspectedPowerIssue,
+    /// Data is corrupted or lost without the device restarting, like with a damaged or
+    /// unshielded USB cable.
+    SuspectedCableIssue,
+    /// Problems have been seen recently, but they don't match a known pattern.
+    Unknown,
+}
+
+impl Display for LinkDiagnosis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let diagnosis = match self {
+            Self::Healthy => "Healthy",
+            Self::SuspectedPowerIssue => "Suspected power issue",
+            Self::SuspectedCableIssue => "Suspected cable issue",
+            Self::Unknown => "Unknown",
+        };
+        write!(f, "{diagnosis}")
+    }
+}
+
+/// What was seen on a serial link that can point to the cause of its problems.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LinkEventKind {
+    /// A line was mostly unprintable bytes, like a line received at the wrong baud rate or
+    /// corrupted by noise.
+    Garbage,
+    /// A line of text wasn't a known message.
+    ///
+    /// It's kept as evidence but never counted as corruption, since it's more likely a message
+    /// this crate doesn't parse than a corrupted one.
+    UnknownLine,
+    /// The device announced it started in firmware update (bootloader) mode.
+    BootloaderBanner,
+    /// The device sent its model and firmware version without being asked, as it does after
+    /// restarting.
+    Restarted,
+    /// The port stopped working, like when the device's USB connection drops.
+    PortLost,
+}
+
+/// Something seen on a serial link, kept as evidence for its [`LinkDiagnosis`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkEvent {
+    /// What was seen.
+    pub kind: LinkEventKind,
+    /// When it was seen.
+    pub at: Instant,
+    /// The first bytes of the line that was received or of the error that was returned.
+    pub raw: Vec<u8>,
+}
+
+/// A sequence of events that points to a diagnosis when it's seen within a time window.
+struct DiagnosisRule {
+    diagnosis: LinkDiagnosis,
+    window: Duration,
+    /// The kinds of events that must be seen, in order, and how many of each.
+    steps: &'static [(&'static [LinkEventKind], usize)],
+}
+
+const RESTARTS: &[LinkEventKind] = &[LinkEventKind::BootloaderBanner, LinkEventKind::Restarted];
+
+/// The rules used to diagnose a link, in order of precedence.
+const RULES: &[DiagnosisRule] = &[
+    // Corrupted data followed by a restart is the device losing power mid-transmission
+    DiagnosisRule {
+        diagnosis: LinkDiagnosis::SuspectedPowerIssue,
+        window: Duration::from_secs(30),
+        steps: &[(&[LinkEventKind::Garbage], 1), (RESTARTS, 1)],
+    },
+    // Firmware updates restart the device once, but brownouts restart it again and again
+    DiagnosisRule {
+        diagnosis: LinkDiagnosis::SuspectedPowerIssue,
+        window: Duration::from_secs(300),
+        steps: &[(&[LinkEventKind::Restarted], 2)],
+    },
+    DiagnosisRule {
+        diagnosis: LinkDiagnosis::SuspectedCableIssue,
+        window: Duration::from_secs(10),
+        steps: &[
+            (&[LinkEventKind::Garbage], 1),
+            (&[LinkEventKind::PortLost], 1),
+        ],
+    },
+    DiagnosisRule {
+        diagnosis: LinkDiagnosis::SuspectedCableIssue,
+        window: Duration::from_secs(10),
+        steps: &[(&[LinkEventKind::Garbage], 3)],
+    },
+];
+
+/// How long errors that don't match a rule keep a link from being diagnosed as healthy.
+const UNKNOWN_WINDOW: Duration = Duration::from_secs(60);
+
+impl DiagnosisRule {
+    /// Returns the events that match the rule, or `None` if the rule doesn't match.
+    fn matches(&self, events: &[LinkEvent], now: Instant) -> Option<Vec<LinkEvent>> {
+        let mut evidence = Vec::new();
+        let mut step = 0;
+        let mut matched = 0;
+        for event in events
+            .iter()
+            .filter(|event| now.saturating_duration_since(event.at) <= self.window)
+        {
+            let (kinds, count) = self.steps[step];
+            if !kinds.contains(&event.kind) {
+                continue;
+            }
+            evidence.push(event.clone());
+            matched += 1;
+            if matched == count {
+                step += 1;
+                matched = 0;
+                if step == self.steps.len() {
+                    return Some(evidence);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Diagnoses a link from the events seen on it, oldest first, and returns the events the
+/// diagnosis was made from.
+pub(crate) fn diagnose(events: &[LinkEvent], now: Instant) -> (LinkDiagnosis, Vec<LinkEvent>) {
+    if let Some((diagnosis, evidence)) = RULES
+        .iter()
+        .find_map(|rule| Some((rule.diagnosis, rule.matches(events, now)?)))
+    {
+        return (diagnosis, evidence);
+    }
+
+    let unexplained: Vec<_> = events
+        .iter()
+        .filter(|event| {
+            matches!(event.kind, LinkEventKind::Garbage | LinkEventKind::PortLost)
+                && now.saturating_duration_since(event.at) <= UNKNOWN_WINDOW
+        })
+        .cloned()
+        .collect();
+    if unexplained.is_empty() {
+        (LinkDiagnosis::Healthy, Vec::new())
+    } else {
+        (LinkDiagnosis::Unknown, unexplained)
+    }
+}
+
+/// The most recent events seen on a link.
+#[derive(Debug, Default)]
+pub(crate) struct LinkEvents {
+    events: Vec<LinkEvent>,
+    diagnosis: LinkDiagnosis,
+}
+
+impl LinkEvents {
+    /// The maximum number of events kept.
+    const CAPACITY: usize = 32;
+    /// The maximum number of bytes kept from each line or error.
+    const MAX_RAW_LEN: usize = 64;
+
+    /// Records an event and returns the link's new diagnosis if it changed.
+    pub(crate) fn push(
+        &mut self,
+        kind: LinkEventKind,
+        raw: &[u8],
+        at: Instant,
+    ) -> Option<(LinkDiagnosis, Vec<LinkEvent>)> {
+        if self.events.len() == Self::CAPACITY {
+            self.events.remove(0);
+        }
+        self.events.push(LinkEvent {
+            kind,
+            at,
+            raw: raw[..raw.len().min(Self::MAX_RAW_LEN)].to_vec(),
+        });
+        let (diagnosis, evidence) = self.diagnose(at);
+        if diagnosis == self.diagnosis {
+            return None;
+        }
+        self.diagnosis = diagnosis;
+        Some((diagnosis, evidence))
+    }
+
+    pub(crate) fn diagnose(&self, now: Instant) -> (LinkDiagnosis, Vec<LinkEvent>) {
+        diagnose(&self.events, now)
+    }
+}
+
+/// Returns the kind of event a line that isn't a known message is evidence of.
+pub(crate) fn classify_unknown_line(line: &[u8]) -> LinkEventKind {
+    let line = line.trim_ascii_end();
+    let unprintable = line
+        .iter()
+        .filter(|byte| !(byte.is_ascii_graphic() || **byte == b' '))
+        .count();
+    if unprintable * 2 > line.len() {
+        LinkEventKind::Garbage
+    } else {
+        LinkEventKind::UnknownLine
+    }
+}
+
+/// Returns whether a line is an RF Explorer's model and firmware version, which it sends when it
+/// starts.
+pub(crate) fn is_setup_info_line(line: &[u8]) -> bool {
+    line.len() >= 6 && line.starts_with(b"#C") && &line[3..6] == b"-M:"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(kinds: &[(LinkEventKind, u64)], start: Instant) -> Vec<LinkEvent> {
+        kinds
+            .iter()
+            .map(|&(kind, secs)| LinkEvent {
+                kind,
+                at: start + Duration::from_secs(secs),
+                raw: Vec::new(),
+            })
+            .collect()
+    }
+
+    fn diagnosis(kinds: &[(LinkEventKind, u64)], now_secs: u64) -> LinkDiagnosis {
+        let start = Instant::now();
+        diagnose(&events(kinds, start), start + Duration::from_secs(now_secs)).0
+    }
+
+    #[test]
+    fn diagnose_healthy_link() {
+        assert_eq!(diagnosis(&[], 0), LinkDiagnosis::Healthy);
+        // A firmware update restarts the device once without corrupting anything
+        assert_eq!(
+            diagnosis(
+                &[
+                    (LinkEventKind::BootloaderBanner, 0),
+                    (LinkEventKind::Restarted, 1)
+                ],
+                1
+            ),
+            LinkDiagnosis::Healthy
+        );
+        // Old errors are forgotten
+        assert_eq!(
+            diagnosis(&[(LinkEventKind::Garbage, 0)], 120),
+            LinkDiagnosis::Healthy
+        );
+    }
+
+    #[test]
+    fn ignore_readable_unknown_lines() {
+        // Messages this crate doesn't parse aren't corruption, even in bursts or before a restart
+        assert_eq!(
+            diagnosis(
+                &[
+                    (LinkEventKind::UnknownLine, 0),
+                    (LinkEventKind::UnknownLine, 1),
+                    (LinkEventKind::UnknownLine, 2)
+                ],
+                2
+            ),
+            LinkDiagnosis::Healthy
+        );
+        assert_eq!(
+            diagnosis(
+                &[
+                    (LinkEventKind::UnknownLine, 0),
+                    (LinkEventKind::Restarted, 1)
+                ],
+                1
+            ),
+            LinkDiagnosis::Healthy
+        );
+        assert_eq!(
+            diagnosis(
+                &[
+                    (LinkEventKind::UnknownLine, 0),
+                    (LinkEventKind::PortLost, 1)
+                ],
+                1
+            ),
+            LinkDiagnosis::Unknown
+        );
+    }
+
+    #[test]
+    fn diagnose_power_issues() {
+        assert_eq!(
+            diagnosis(
+                &[(LinkEventKind::Garbage, 0), (LinkEventKind::Restarted, 1)],
+                1
+            ),
+            LinkDiagnosis::SuspectedPowerIssue
+        );
+        assert_eq!(
+            diagnosis(
+                &[
+                    (LinkEventKind::Garbage, 0),
+                    (LinkEventKind::BootloaderBanner, 5)
+                ],
+                5
+            ),
+            LinkDiagnosis::SuspectedPowerIssue
+        );
+        assert_eq!(
+            diagnosis(
+                &[
+                    (LinkEventKind::Restarted, 0),
+                    (LinkEventKind::Restarted, 90)
+                ],
+                90
+            ),
+            LinkDiagnosis::SuspectedPowerIssue
+        );
+        // A restart before the errors doesn't explain them
+        assert_ne!(
+            diagnosis(
+                &[(LinkEventKind::Restarted, 0), (LinkEventKind::Garbage, 1)],
+                1
+            ),
+            LinkDiagnosis::SuspectedPowerIssue
+        );
+    }
+
+    #[test]
+    fn diagnose_cable_issues() {
+        assert_eq!(
+            diagnosis(
+                &[
+                    (LinkEventKind::Garbage, 0),
+                    (LinkEventKind::UnknownLine, 1),
+                    (LinkEventKind::Garbage, 2),
+                    (LinkEventKind::Garbage, 3)
+                ],
+                3
+            ),
+            LinkDiagnosis::SuspectedCableIssue
+        );
+        assert_eq!(
+            diagnosis(
+                &[(LinkEventKind::Garbage, 0), (LinkEventKind::PortLost, 1)],
+                1
+            ),
+            LinkDiagnosis::SuspectedCableIssue
+        );
+        // Errors that are too far apart aren't a burst
+        assert_eq!(
+            diagnosis(
+                &[
+                    (LinkEventKind::Garbage, 0),
+                    (LinkEventKind::Garbage, 20),
+                    (LinkEventKind::Garbage, 40)
+                ],
+                40
+            ),
+            LinkDiagnosis::Unknown
+        );
+    }
+
+    #[test]
+    fn attach_matching_events_as_evidence() {
+        let start = Instant::now();
+        let events = events(
+            &[
+                (LinkEventKind::Restarted, 0),
+                (LinkEventKind::Garbage, 100),
+                (LinkEventKind::BootloaderBanner, 101),
+            ],
+            start,
+        );
+        let (diagnosis, evidence) = diagnose(&events, start + Duration::from_secs(101));
+        assert_eq!(diagnosis, LinkDiagnosis::SuspectedPowerIssue);
+        assert_eq!(evidence, events[1..]);
+    }
+
+    #[test]
+    fn report_only_changed_diagnoses() {
+        let start = Instant::now();
+        let mut events = LinkEvents::default();
+        let (diagnosis, evidence) = events
+            .push(LinkEventKind::Garbage, b"\xFF\xFE", start)
+            .unwrap();
+        assert_eq!(diagnosis, LinkDiagnosis::Unknown);
+        assert_eq!(evidence[0].raw, b"\xFF\xFE");
+        assert!(
+            events
+                .push(LinkEventKind::UnknownLine, b"#X", start)
+                .is_none()
+        );
+        assert!(
+            events
+                .push(LinkEventKind::Garbage, b"\x00", start)
+                .is_none()
+        );
+        assert_eq!(
+            events
+                .push(LinkEventKind::Garbage, &[0; 100], start)
+                .unwrap()
+                .0,
+            LinkDiagnosis::SuspectedCableIssue
+        );
+        assert_eq!(events.diagnose(start).1[2].raw.len(), 64);
+    }
+
+    #[test]
+    fn classify_unknown_lines() {
+        assert_eq!(
+            classify_unknown_line(b"\x55\xAA\x10\x00\x7F\r\n"),
+            LinkEventKind::Garbage
+        );
+        assert_eq!(
+            classify_unknown_line(b"#Q0:unknown message\r\n"),
+            LinkEventKind::UnknownLine
+        );
+        assert!(is_setup_info_line(b"#C2-M:014,255,03.41\r\n"));
+        assert!(is_setup_info_line(b"#C3-M:060,255,01.15\r\n"));
+        assert!(!is_setup_info_line(b"#C2-F:2400000,0892857\r\n"));
+    }
+}
@@ -0,0 +1,99 @@
+/// Limits on the memory used by the internal buffers of a connection to an RF Explorer.
+///
+/// Every buffer that grows with what the RF Explorer sends or with how slowly the application
+/// keeps up is bounded, and so are the threads that run callbacks. When one reaches its limit,
+/// data is dropped and counted in [`MemoryStats`] instead of more memory being allocated. The
+/// other buffers have fixed sizes, like the 5 sweeps kept for suggesting amplitude ranges and the
+/// latest screen data, and are only counted by the memory usage estimate.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryBudget {
+    /// The length of the lines read from the RF Explorer, in bytes, at which they're dropped.
+    ///
+    /// The default leaves room for the longest sweep an RF Explorer can send. Values less than
+    /// [`MemoryBudget::MIN_MAX_LINE_LEN`] are treated as that minimum, since every message would
+    /// be dropped otherwise.
+    pub max_line_len: usize,
+    /// The most callbacks, like sweep and config callbacks, that can run on their own threads at
+    /// once.
+    ///
+    /// Sweeps and screen data received while that many callbacks are running aren't passed to a
+    /// callback. Configs and other state wait for a running callback to finish instead, and only
+    /// the latest value received while waiting is passed to each callback. Rebooting a frozen
+    /// RF Explorer and switching input stages automatically also run on these threads. Values
+    /// less than 1 are treated as 1, since callbacks would never run otherwise.
+    pub max_running_callbacks: usize,
+    /// The most events recorded with `annotate_event` that are kept while waiting for a sweep.
+    /// The oldest events are dropped first.
+    pub max_pending_events: usize,
+    /// The most sweeps a receiver created with `subscribe_buffered` can buffer, whatever depth it
+    /// was created with.
+    pub max_buffered_sweeps: usize,
+}
+
+impl MemoryBudget {
+    /// The length of the longest message an RF Explorer can send, a sweep with 65,535 points,
+    /// rounded up to a power of two.
+    pub const DEFAULT_MAX_LINE_LEN: usize = 131_072;
+
+    /// The smallest [`MemoryBudget::max_line_len`], which leaves room for configs and the other
+    /// text messages an RF Explorer sends, but not for sweeps with more than about 250 points.
+    pub const MIN_MAX_LINE_LEN: usize = 256;
+
+    /// Returns the budget with its limits raised to their minimums.
+    pub(crate) fn clamped(self) -> Self {
+        Self {
+            max_line_len: self.max_line_len.max(Self::MIN_MAX_LINE_LEN),
+            max_running_callbacks: self.max_running_callbacks.max(1),
+            ..self
+        }
+    }
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        Self {
+            max_line_len: Self::DEFAULT_MAX_LINE_LEN,
+            max_running_callbacks: 4,
+            max_pending_events: 256,
+            max_buffered_sweeps: 64,
+        }
+    }
+}
+
+/// Counts of the data dropped because a buffer reached its [`MemoryBudget`] limit.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct MemoryStats {
+    /// The number of lines that reached [`MemoryBudget::max_line_len`].
+    pub dropped_lines: u64,
+    /// The number of values that weren't passed to a callback because
+    /// [`MemoryBudget::max_running_callbacks`] callbacks were already running, including sweeps,
+    /// configs and other state replaced by a later value while waiting.
+    pub dropped_callbacks: u64,
+    /// The number of events dropped because [`MemoryBudget::max_pending_events`] events were
+    /// already waiting for a sweep.
+    pub dropped_events: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_limits_to_minimums() {
+        let budget = MemoryBudget {
+            max_line_len: 0,
+            max_running_callbacks: 0,
+            max_pending_events: 0,
+            max_buffered_sweeps: 0,
+        };
+        assert_eq!(
+            budget.clamped(),
+            MemoryBudget {
+                max_line_len: MemoryBudget::MIN_MAX_LINE_LEN,
+                max_running_callbacks: 1,
+                ..budget
+            }
+        );
+        assert_eq!(MemoryBudget::default().clamped(), MemoryBudget::default());
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{borrow::Cow, fmt::Debug, io, time::Duration};
 
 use nom::{Err, error::Error};
 use thiserror::Error;
@@ -6,6 +6,27 @@ use thiserror::Error;
 use super::ConnectionResult;
 
 /// Storage and synchronization contract for messages read by [`Device`](crate::Device).
+///
+/// Implement it to talk to a device that sends messages this crate doesn't know about, like an
+/// RF Explorer running custom firmware. The `custom_device` example wraps the public RF
+/// Explorer messages, like [`Config`](crate::spectrum_analyzer::Config), in an enum with a custom
+/// message and connects with [`Device::connect`](crate::Device::connect).
+///
+/// # Contract
+///
+/// - The device's reader thread splits what it reads into lines ending in `\r\n` and parses
+///   each one with [`MessageContainer::Message`]'s `TryFrom<&[u8]>` implementation.
+/// - Parsing must return [`MessageParseError::Incomplete`] while the bytes are the start of a
+///   message that's still arriving, so more bytes are read into the same buffer. Any other error
+///   discards the bytes, so lines that aren't messages should return
+///   [`MessageParseError::UnknownMessageType`].
+/// - [`MessageContainer::cache_message`] and [`MessageContainer::on_partial_message`] are called
+///   from the reader thread, so they should return quickly. Messages aren't read while they run.
+/// - [`MessageContainer::take_commands`] is called from the reader thread after every cached
+///   message, and the commands it returns are sent before more messages are read.
+/// - [`MessageContainer::wait_for_device_info`] is called from the thread that's connecting once
+///   the initialization command is sent. It decides whether the port is the device being looked
+///   for, so it must return an error if the device doesn't identify itself within a few seconds.
 pub trait MessageContainer: Default + Debug + Send + Sync {
     /// Parsed message type accepted by this container.
     type Message: for<'a> TryFrom<&'a [u8], Error = MessageParseError<'a>> + Debug;
@@ -14,7 +35,75 @@ pub trait MessageContainer: Default + Debug + Send + Sync {
     fn cache_message(&self, message: Self::Message);
 
     /// Waits until the initial device-identification messages have been received.
+    ///
+    /// Returning [`ConnectionError::DeviceInfoNotReceived`](crate::ConnectionError::DeviceInfoNotReceived)
+    /// after a timeout makes [`Device::connect`](crate::Device::connect) move on to the next port.
     fn wait_for_device_info(&self) -> ConnectionResult<()>;
+
+    /// Waits like [`MessageContainer::wait_for_device_info`] while calling `on_waiting` with how
+    /// long it has waited about every
+    /// [`ConnectProgress::WAITING_INTERVAL`](crate::ConnectProgress::WAITING_INTERVAL).
+    ///
+    /// The default implementation only calls `on_waiting` once, before it starts waiting.
+    fn wait_for_device_info_with_progress(
+        &self,
+        on_waiting: &dyn Fn(Duration),
+    ) -> ConnectionResult<()> {
+        on_waiting(Duration::ZERO);
+        self.wait_for_device_info()
+    }
+
+    /// Called with the bytes of a message that's still being received every time more of them
+    /// arrive, and with no bytes after a message is parsed or discarded.
+    ///
+    /// The default implementation ignores the bytes.
+    fn on_partial_message(&self, bytes: &[u8]) {
+        let _ = bytes;
+    }
+
+    /// Stores the error that stopped messages from being read and wakes any waiters so they can
+    /// return [`Error::Disconnected`](crate::Error::Disconnected) instead of waiting for their timeouts.
+    ///
+    /// The default implementation ignores the error.
+    fn disconnect(&self, error: io::Error) {
+        let _ = error;
+    }
+
+    /// Forgets the error passed to [`MessageContainer::disconnect`] after the device's port was
+    /// reopened, like by [`Device::reconnect`](crate::Device::reconnect), so waiters wait for
+    /// messages again.
+    ///
+    /// It's called before messages are read from the reopened port. The default implementation
+    /// does nothing.
+    fn reconnect(&self) {}
+
+    /// Returns the commands to send to the device because of the messages cached so far, like
+    /// switching input stages when sweeps overload the front end.
+    ///
+    /// The default implementation returns no commands.
+    fn take_commands(&self) -> Vec<Cow<'static, [u8]>> {
+        Vec::new()
+    }
+}
+
+/// A device message that can be written in the format the RF Explorer sends it in.
+///
+/// Parsing the written bytes returns an equal message, apart from the time it was received.
+pub trait WriteMessage {
+    /// Writes the message to `writer`, including the `\r\n` line ending.
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()>;
+
+    /// Returns the message's bytes, including the `\r\n` line ending.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the message can't be encoded, like a sweep with more than 65,535 points.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_message(&mut bytes)
+            .expect("The message should be encodable");
+        bytes
+    }
 }
 
 #[derive(Error, Debug, Eq, PartialEq)]
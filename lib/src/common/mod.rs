@@ -1,12 +1,44 @@
+mod amplitude;
+mod async_signal;
+mod connect_progress;
+mod connection_state;
+mod debouncer;
 mod device;
+mod diagnostics;
 mod error;
 mod frequency;
+mod link_diagnosis;
+mod memory_budget;
 mod message;
+mod port_lock;
+mod radio_module;
 mod serial_port;
+mod timestamped;
+#[cfg(test)]
+pub(crate) mod transcript_port;
 
+pub use amplitude::{Db, Dbm};
+pub(crate) use async_signal::AsyncSignal;
+pub use connect_progress::ConnectProgress;
+pub use connection_state::ConnectionState;
+pub(crate) use connection_state::{ConnectionStatus, is_bootloader_banner};
+pub(crate) use debouncer::Debouncer;
 pub use device::Device;
+pub use diagnostics::{COMMAND_LOG_TARGET, DebugSnapshot, LinkHealth, ReadErrorCounts, WriteStats};
+pub(crate) use diagnostics::{CommandLogCallback, LinkStats, log_command};
+pub(crate) use error::Disconnection;
 pub use error::{Error, Result};
-pub use frequency::Frequency;
-pub use message::{MessageContainer, MessageParseError};
-pub(crate) use serial_port::{BaudRate, SerialPort};
-pub use serial_port::{ConnectionError, ConnectionResult, is_driver_installed, port_names};
+pub use frequency::{Frequency, FrequencyUnit, ParseFrequencyError, ticks};
+pub use link_diagnosis::{LinkDiagnosis, LinkEvent, LinkEventKind};
+pub(crate) use link_diagnosis::{LinkEvents, classify_unknown_line, is_setup_info_line};
+pub use memory_budget::{MemoryBudget, MemoryStats};
+pub use message::{MessageContainer, MessageParseError, WriteMessage};
+pub(crate) use port_lock::PortLock;
+pub use port_lock::{is_port_locking_enabled, set_port_locking_enabled};
+pub use radio_module::RadioModule;
+pub(crate) use serial_port::SerialPort;
+pub use serial_port::{
+    BaudRate, ConnectionError, ConnectionResult, DEFAULT_MIN_COMMAND_INTERVAL, RFE_USB_PID,
+    RFE_USB_VID, is_driver_installed, looks_like_rf_explorer, port_names,
+};
+pub(crate) use timestamped::{Freshness, Timestamped};
@@ -0,0 +1,230 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use tracing::{debug, warn};
+
+use super::{ConnectionError, ConnectionResult};
+
+static PORT_LOCKING_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Sets whether serial ports are locked while they're open.
+///
+/// Ports are locked by default. While a port is open, an advisory lock on a file in the
+/// platform's temporary directory stops other processes using this crate from opening it, and
+/// they get [`ConnectionError::PortInUse`] instead of a serial port error. Programs that make
+/// sure only one process opens each port can turn locking off.
+///
+/// The setting applies to ports opened after it's changed.
+pub fn set_port_locking_enabled(enabled: bool) {
+    PORT_LOCKING_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether serial ports are locked while they're open.
+///
+/// See [`set_port_locking_enabled`].
+pub fn is_port_locking_enabled() -> bool {
+    PORT_LOCKING_ENABLED.load(Ordering::Relaxed)
+}
+
+/// An advisory lock on a serial port that's held until it's dropped.
+///
+/// The lock is taken on a file named after the port, and the file holds the ID of the process
+/// that has the port open. The operating system releases the lock when the process exits, so a
+/// lock file left behind by a process that crashed doesn't keep the port locked.
+#[derive(Debug)]
+pub(crate) struct PortLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl PortLock {
+    /// Locks the port if port locking is enabled.
+    pub(crate) fn acquire_if_enabled(port_name: &str) -> ConnectionResult<Option<Self>> {
+        if !is_port_locking_enabled() {
+            return Ok(None);
+        }
+        Self::acquire_in(&env::temp_dir(), port_name)
+    }
+
+    /// Locks the port with a lock file in `dir`.
+    ///
+    /// Returns `None` if the port can't be locked, like when the lock file can't be created, so
+    /// the port is used without a lock instead of not at all.
+    pub(crate) fn acquire_in(dir: &Path, port_name: &str) -> ConnectionResult<Option<Self>> {
+        let path = dir.join(lock_file_name(port_name));
+        let mut file = match open_lock_file(&path) {
+            Ok(file) => file,
+            Err(error) => {
+                warn!(%error, path = %path.display(), "Failed to open the serial port's lock file");
+                return Ok(None);
+            }
+        };
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(ConnectionError::PortInUse {
+                    port_name: port_name.to_string(),
+                    by_pid: read_pid(&mut file),
+                });
+            }
+            Err(TryLockError::Error(error)) => {
+                // Some file systems, like network file systems, can't lock files
+                warn!(%error, path = %path.display(), "Failed to lock the serial port");
+                return Ok(None);
+            }
+        }
+
+        // The process that wrote the previous ID doesn't hold the lock anymore, so the ID is stale
+        if let Some(stale_pid) = read_pid(&mut file) {
+            debug!(stale_pid, path = %path.display(), "Replacing a stale serial port lock");
+        }
+        if let Err(error) = write_pid(&mut file) {
+            // The lock is still held, so other processes only miss out on the ID
+            warn!(%error, path = %path.display(), "Failed to write the ID of the process holding the lock");
+        }
+
+        Ok(Some(PortLock { file, path }))
+    }
+}
+
+impl Drop for PortLock {
+    fn drop(&mut self) {
+        // The file is kept so that a process waiting to open it doesn't lock a file that's about to
+        // be deleted. Clearing the ID is enough to show that the port isn't in use.
+        if let Err(error) = self.file.set_len(0) {
+            debug!(%error, path = %self.path.display(), "Failed to clear the serial port lock");
+        }
+        let _ = self.file.unlock();
+    }
+}
+
+/// Returns the name of the lock file for a port.
+///
+/// Symbolic links, like the ones in `/dev/serial/by-id`, are resolved and macOS call-in ports are
+/// named after their call-out ports, so every name for the same port uses the same lock file.
+fn lock_file_name(port_name: &str) -> String {
+    let port_name = fs::canonicalize(port_name)
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| port_name.to_string());
+    let port_name = match port_name.strip_prefix("/dev/tty.") {
+        Some(name) => format!("/dev/cu.{name}"),
+        None => port_name,
+    };
+    let port_id: String = port_name
+        .trim_start_matches(r"\\.\")
+        .trim_start_matches("/dev/")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("rfe-{port_id}.lock")
+}
+
+fn open_lock_file(path: &Path) -> io::Result<File> {
+    let open = |write| {
+        OpenOptions::new()
+            .read(true)
+            .write(write)
+            .create(write)
+            .truncate(false)
+            .open(path)
+    };
+    match open(true) {
+        // The lock file was created by another user, so it can be locked but not written to
+        Err(error) if error.kind() == io::ErrorKind::PermissionDenied => open(false),
+        result => result,
+    }
+}
+
+fn read_pid(file: &mut File) -> Option<u32> {
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
+
+fn write_pid(file: &mut File) -> io::Result<()> {
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    write!(file, "{}", process::id())?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_dir(test_name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rfe-port-lock-{test_name}-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn acquire_and_release() {
+        let dir = lock_dir("acquire");
+        let lock = PortLock::acquire_in(&dir, "/dev/ttyUSB0").unwrap().unwrap();
+        let path = dir.join("rfe-ttyusb0.lock");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            process::id().to_string()
+        );
+
+        drop(lock);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        PortLock::acquire_in(&dir, "/dev/ttyUSB0").unwrap().unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn refuse_to_acquire_locked_port() {
+        let dir = lock_dir("locked");
+        let _lock = PortLock::acquire_in(&dir, "COM3").unwrap().unwrap();
+
+        let error = PortLock::acquire_in(&dir, "COM3").unwrap_err();
+        assert!(matches!(
+            error,
+            ConnectionError::PortInUse { by_pid: Some(pid), .. } if pid == process::id()
+        ));
+        // Other ports aren't affected
+        PortLock::acquire_in(&dir, "COM4").unwrap().unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn replace_stale_lock() {
+        let dir = lock_dir("stale");
+        // A process that exited without releasing its lock leaves its ID behind
+        let path = dir.join("rfe-ttyusb1.lock");
+        fs::write(&path, "4294967294").unwrap();
+
+        let _lock = PortLock::acquire_in(&dir, "/dev/ttyUSB1").unwrap().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            process::id().to_string()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn name_lock_files_after_ports() {
+        assert_eq!(lock_file_name("/dev/ttyUSB0"), "rfe-ttyusb0.lock");
+        assert_eq!(
+            lock_file_name("/dev/tty.SLAB_USBtoUART"),
+            lock_file_name("/dev/cu.SLAB_USBtoUART")
+        );
+        assert_eq!(lock_file_name(r"\\.\COM10"), "rfe-com10.lock");
+    }
+}
@@ -0,0 +1,19 @@
+use std::fmt::Display;
+
+/// One of the radio modules inside an RF Explorer.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RadioModule {
+    /// The main radio module.
+    Main,
+    /// The expansion radio module.
+    Expansion,
+}
+
+impl Display for RadioModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RadioModule::Main => write!(f, "Main"),
+            RadioModule::Expansion => write!(f, "Expansion"),
+        }
+    }
+}
@@ -1,53 +1,119 @@
 use std::{
     borrow::Cow,
-    fmt::Debug,
+    fmt::{self, Debug},
     io::{self, BufRead, BufReader, Read, Take},
     sync::{
         Mutex,
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicU32, AtomicU64, Ordering},
     },
-    time::Duration,
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 
 use serialport::{
     DataBits, FlowControl, Parity, SerialPortInfo, SerialPortType, StopBits, UsbPortInfo,
 };
 use thiserror::Error;
-use tracing::debug;
+use tracing::{debug, warn};
+
+use super::{CommandLogCallback, ConnectionStatus, PortLock, WriteStats};
 
 pub(crate) const SLOW_BAUD_RATE: u32 = 2_400;
 pub(crate) const FAST_BAUD_RATE: u32 = 500_000;
 
+/// The maximum line length until the device is connected and the limit is raised.
+const INITIAL_LINE_LIMIT: u64 = 128;
+
+/// The default minimum time between commands sent to a device.
+///
+/// RF Explorer firmware can ignore a command that arrives too soon after the previous one.
+pub const DEFAULT_MIN_COMMAND_INTERVAL: Duration = Duration::from_millis(50);
+
+type PortReader = BufReader<Take<Box<dyn serialport::SerialPort>>>;
+
 pub(crate) struct SerialPort {
-    buf_reader: Mutex<BufReader<Take<Box<dyn serialport::SerialPort>>>>,
+    /// The open port, or `None` while it's closed to be reopened.
+    buf_reader: Mutex<Option<PortReader>>,
     port_info: SerialPortInfo,
+    /// The baud rate the port was opened with or last set to, or 0 if it couldn't be read.
+    ///
+    /// It's kept outside of the reader's lock so it can be read while a read is blocked.
+    known_baud_rate: AtomicU32,
+    read_timeout: Duration,
     max_message_len: AtomicU64,
+    min_command_interval: Mutex<Duration>,
+    last_command_sent_at: Mutex<Option<Instant>>,
+    write_retry: Mutex<WriteRetry>,
+    successful_writes: AtomicU64,
+    failed_writes: AtomicU64,
+    write_retries: AtomicU64,
+    command_log_callback: Mutex<Option<CommandLogCallback>>,
+    connection_status: ConnectionStatus,
+    /// Released after the port is closed because fields are dropped in order.
+    _port_lock: Option<PortLock>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct WriteRetry {
+    pub(crate) attempts: u32,
+    pub(crate) delay: Duration,
+}
+
+impl Default for WriteRetry {
+    // Writes aren't retried unless a retry policy is set
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            delay: Duration::ZERO,
+        }
+    }
 }
 
 impl SerialPort {
     #[tracing::instrument(ret, err)]
     pub(crate) fn open(port_info: &SerialPortInfo, baud_rate: u32) -> ConnectionResult<Self> {
-        let serial_port = serialport::new(&port_info.port_name, baud_rate)
-            .data_bits(DataBits::Eight)
-            .flow_control(FlowControl::None)
-            .parity(Parity::None)
-            .stop_bits(StopBits::One)
-            .timeout(Duration::from_secs(1))
-            .open()?;
-
-        const INITIAL_LINE_LIMIT: u64 = 128;
-
-        let buf_reader = if cfg!(target_os = "windows") {
-            BufReader::with_capacity(1, serial_port.take(INITIAL_LINE_LIMIT))
-        } else {
-            BufReader::new(serial_port.take(INITIAL_LINE_LIMIT))
+        let port_lock = PortLock::acquire_if_enabled(&port_info.port_name)?;
+        Ok(SerialPort {
+            _port_lock: port_lock,
+            ..Self::from_port(
+                open_port(&port_info.port_name, baud_rate)?,
+                port_info.clone(),
+            )
+        })
+    }
+
+    /// Wraps an already opened transport, naming the port after it.
+    pub(crate) fn from_transport(transport: Box<dyn serialport::SerialPort>) -> Self {
+        let port_info = SerialPortInfo {
+            port_name: transport.name().unwrap_or_default(),
+            port_type: SerialPortType::Unknown,
         };
+        Self::from_port(transport, port_info)
+    }
 
-        Ok(SerialPort {
-            buf_reader: Mutex::new(buf_reader),
-            port_info: port_info.clone(),
+    pub(crate) fn from_port(
+        serial_port: Box<dyn serialport::SerialPort>,
+        port_info: SerialPortInfo,
+    ) -> Self {
+        let known_baud_rate = AtomicU32::new(serial_port.baud_rate().unwrap_or_default());
+        let read_timeout = serial_port.timeout();
+
+        SerialPort {
+            buf_reader: Mutex::new(Some(port_reader(serial_port))),
+            port_info,
+            known_baud_rate,
+            read_timeout,
             max_message_len: AtomicU64::new(INITIAL_LINE_LIMIT),
-        })
+            min_command_interval: Mutex::new(DEFAULT_MIN_COMMAND_INTERVAL),
+            last_command_sent_at: Mutex::new(None),
+            write_retry: Mutex::new(WriteRetry::default()),
+            successful_writes: AtomicU64::new(0),
+            failed_writes: AtomicU64::new(0),
+            write_retries: AtomicU64::new(0),
+            command_log_callback: Mutex::new(None),
+            connection_status: ConnectionStatus::default(),
+            _port_lock: None,
+        }
     }
 
     #[tracing::instrument(ret, err)]
@@ -60,31 +126,215 @@ impl SerialPort {
         Self::open(&port_info, baud_rate)
     }
 
+    /// Closes the port and opens the port with the same name again at the baud rate it last
+    /// used, like after its USB cable is unplugged and plugged back in.
+    ///
+    /// The port is closed first because some platforms give the device a new name while the old
+    /// port is still open. Reads and writes fail until it's reopened.
+    #[tracing::instrument(skip(self), err)]
+    pub(crate) fn reopen(&self) -> ConnectionResult<()> {
+        self.close();
+        let baud_rate = self.known_baud_rate().unwrap_or(FAST_BAUD_RATE);
+        self.replace_port(open_port(&self.port_info.port_name, baud_rate)?);
+        Ok(())
+    }
+
+    /// Replaces the port with an already opened transport, closing the current one.
+    pub(crate) fn replace_port(&self, serial_port: Box<dyn serialport::SerialPort>) {
+        self.known_baud_rate.store(
+            serial_port.baud_rate().unwrap_or_default(),
+            Ordering::Relaxed,
+        );
+        *self.buf_reader.lock().unwrap() = Some(port_reader(serial_port));
+    }
+
+    fn close(&self) {
+        self.buf_reader.lock().unwrap().take();
+    }
+
+    /// Calls `f` with the open port, or returns an error if it's closed.
+    fn with_port<T>(&self, f: impl FnOnce(&mut PortReader) -> io::Result<T>) -> io::Result<T> {
+        match self.buf_reader.lock().unwrap().as_mut() {
+            Some(buf_reader) => f(buf_reader),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "The serial port is closed",
+            )),
+        }
+    }
+
     #[tracing::instrument(skip(self), err)]
     pub(crate) fn read_line(&self, buf: &mut Vec<u8>) -> io::Result<usize> {
-        let mut buf_reader = self.buf_reader.lock().unwrap();
-        buf_reader
-            .get_mut()
-            .set_limit(self.max_message_len.load(Ordering::Relaxed));
-        buf_reader.read_until(b'\n', buf)
+        self.with_port(|buf_reader| {
+            buf_reader
+                .get_mut()
+                .set_limit(self.max_message_len.load(Ordering::Relaxed));
+            buf_reader.read_until(b'\n', buf)
+        })
     }
 
     #[tracing::instrument(skip(self), ret, err, fields(bytes_as_string = String::from_utf8_lossy(bytes.as_ref()).as_ref()))]
-    pub(crate) fn send_bytes(&self, bytes: impl AsRef<[u8]> + Debug) -> io::Result<()> {
-        self.buf_reader
-            .lock()
-            .unwrap()
-            .get_mut()
-            .get_mut()
-            .write_all(bytes.as_ref())
+    pub(crate) fn send_bytes(&self, bytes: impl AsRef<[u8]> + Debug) -> crate::Result<()> {
+        self.connection_status.check_can_send()?;
+        Ok(self.write_bytes(bytes)?)
+    }
+
+    /// Writes bytes to the port whatever state the device is in, retrying failed writes
+    /// according to the retry policy.
+    ///
+    /// A retry resumes after the bytes that were already written, so a write that times out
+    /// partway through doesn't send the start of the command twice.
+    ///
+    /// This is for the command that starts a connection, which is sent before the device has
+    /// had a chance to say what state it's in.
+    pub(crate) fn write_bytes(&self, bytes: impl AsRef<[u8]>) -> io::Result<()> {
+        let write_retry = *self.write_retry.lock().unwrap();
+        let mut attempt = 1;
+        let mut written = 0;
+        loop {
+            let result = self.with_port(|buf_reader| {
+                write_remaining(buf_reader.get_mut().get_mut(), bytes.as_ref(), &mut written)
+            });
+
+            match result {
+                Ok(()) => {
+                    self.successful_writes.fetch_add(1, Ordering::Relaxed);
+                    // The port's lock has been released, so a slow callback can't delay reads
+                    if let Some(callback) = self.command_log_callback.lock().unwrap().as_mut() {
+                        callback(bytes.as_ref(), SystemTime::now());
+                    }
+                    return Ok(());
+                }
+                Err(error) if attempt < write_retry.attempts && is_retriable(&error) => {
+                    warn!(%error, attempt, "Retrying failed write");
+                    self.write_retries.fetch_add(1, Ordering::Relaxed);
+                    attempt += 1;
+                    // Sleep without holding the lock so the device can still be read from
+                    thread::sleep(write_retry.delay);
+                }
+                Err(error) => {
+                    self.failed_writes.fetch_add(1, Ordering::Relaxed);
+                    return Err(error);
+                }
+            }
+        }
     }
 
     #[tracing::instrument(skip(self))]
     pub(crate) fn send_command(
         &self,
         command: impl Into<Cow<'static, [u8]>> + Debug,
-    ) -> io::Result<()> {
-        self.send_bytes(command.into())
+    ) -> crate::Result<()> {
+        // Hold the lock while sending so commands from other threads are paced as well
+        let mut last_command_sent_at = self.last_command_sent_at.lock().unwrap();
+
+        // Wait until the minimum interval since the previous command has elapsed
+        let min_command_interval = *self.min_command_interval.lock().unwrap();
+        if let Some(elapsed) = last_command_sent_at.map(|sent_at| sent_at.elapsed())
+            && elapsed < min_command_interval
+        {
+            thread::sleep(min_command_interval - elapsed);
+        }
+
+        let result = self.send_bytes(command.into());
+        *last_command_sent_at = Some(Instant::now());
+        result
+    }
+
+    /// Returns the time since a command was last sent, or `None` if no command has been sent.
+    pub(crate) fn since_last_command(&self, now: Instant) -> Option<Duration> {
+        self.last_command_sent_at
+            .lock()
+            .unwrap()
+            .map(|sent_at| now.saturating_duration_since(sent_at))
+    }
+
+    /// Writes each command once, waiting at most `write_timeout` for each write, and ignores the
+    /// ones that fail.
+    ///
+    /// This is for telling a device to stop sending data while its port is being closed, so it
+    /// can't hold up closing for long. Writes aren't retried and commands are paced by the smaller
+    /// of the minimum command interval and `write_timeout`. The port's timeout is left at
+    /// `write_timeout` afterward.
+    pub(crate) fn send_commands_before_close(
+        &self,
+        commands: impl IntoIterator<Item = Cow<'static, [u8]>>,
+        write_timeout: Duration,
+    ) {
+        if self.connection_status.check_can_send().is_err() {
+            return;
+        }
+        let mut last_command_sent_at = self.last_command_sent_at.lock().unwrap();
+        let min_command_interval = self.min_command_interval().min(write_timeout);
+        if let Err(error) = self.with_port(|buf_reader| {
+            Ok(buf_reader.get_mut().get_mut().set_timeout(write_timeout)?)
+        }) {
+            warn!(%error, "Failed to shorten the write timeout before closing");
+        }
+
+        for command in commands {
+            if let Some(elapsed) = last_command_sent_at.map(|sent_at| sent_at.elapsed())
+                && elapsed < min_command_interval
+            {
+                thread::sleep(min_command_interval - elapsed);
+            }
+
+            let result =
+                self.with_port(|buf_reader| buf_reader.get_mut().get_mut().write_all(&command));
+            *last_command_sent_at = Some(Instant::now());
+            match result {
+                Ok(()) => {
+                    self.successful_writes.fetch_add(1, Ordering::Relaxed);
+                    if let Some(callback) = self.command_log_callback.lock().unwrap().as_mut() {
+                        callback(&command, SystemTime::now());
+                    }
+                }
+                Err(error) => {
+                    self.failed_writes.fetch_add(1, Ordering::Relaxed);
+                    debug!(%error, "Failed to send a command before closing");
+                }
+            }
+        }
+    }
+
+    /// Returns the state of the connection, which is updated by the thread reading from the port.
+    pub(crate) fn connection_status(&self) -> &ConnectionStatus {
+        &self.connection_status
+    }
+
+    pub(crate) fn set_min_command_interval(&self, min_command_interval: Duration) {
+        *self.min_command_interval.lock().unwrap() = min_command_interval;
+    }
+
+    pub(crate) fn set_command_log_callback(&self, callback: Option<CommandLogCallback>) {
+        *self.command_log_callback.lock().unwrap() = callback;
+    }
+
+    pub(crate) fn write_retry(&self) -> WriteRetry {
+        *self.write_retry.lock().unwrap()
+    }
+
+    pub(crate) fn set_write_retry(&self, attempts: u32, delay: Duration) {
+        *self.write_retry.lock().unwrap() = WriteRetry {
+            attempts: attempts.max(1),
+            delay,
+        };
+    }
+
+    pub(crate) fn write_stats(&self) -> WriteStats {
+        WriteStats {
+            successful_writes: self.successful_writes.load(Ordering::Relaxed),
+            failed_writes: self.failed_writes.load(Ordering::Relaxed),
+            retries: self.write_retries.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn min_command_interval(&self) -> Duration {
+        *self.min_command_interval.lock().unwrap()
+    }
+
+    pub(crate) fn max_message_len(&self) -> u64 {
+        self.max_message_len.load(Ordering::Relaxed)
     }
 
     pub(crate) fn port_info(&self) -> &SerialPortInfo {
@@ -93,24 +343,25 @@ impl SerialPort {
 
     #[tracing::instrument(skip(self), err)]
     pub(crate) fn baud_rate(&self) -> io::Result<u32> {
-        self.buf_reader
-            .lock()
-            .unwrap()
-            .get_ref()
-            .get_ref()
-            .baud_rate()
-            .map_err(|err| err.into())
+        self.with_port(|buf_reader| Ok(buf_reader.get_ref().get_ref().baud_rate()?))
     }
 
     #[tracing::instrument(skip(self), err)]
     pub(crate) fn set_baud_rate(&self, baud_rate: u32) -> io::Result<()> {
-        self.buf_reader
-            .lock()
-            .unwrap()
-            .get_mut()
-            .get_mut()
-            .set_baud_rate(baud_rate)
-            .map_err(|err| err.into())
+        self.with_port(|buf_reader| {
+            Ok(buf_reader.get_mut().get_mut().set_baud_rate(baud_rate)?)
+        })?;
+        self.known_baud_rate.store(baud_rate, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Returns the baud rate the port was opened with or last set to without waiting for a read.
+    pub(crate) fn known_baud_rate(&self) -> Option<u32> {
+        Some(self.known_baud_rate.load(Ordering::Relaxed)).filter(|baud_rate| *baud_rate != 0)
+    }
+
+    pub(crate) fn read_timeout(&self) -> Duration {
+        self.read_timeout
     }
 
     pub(crate) fn set_max_message_len(&self, line_limit: u64) {
@@ -123,12 +374,58 @@ impl Debug for SerialPort {
         f.debug_struct("SerialPort")
             .field("port_info", &self.port_info)
             .field("max_message_len", &self.max_message_len)
+            .field("min_command_interval", &self.min_command_interval)
+            .field("write_retry", &self.write_retry)
             .finish()
     }
 }
 
+fn open_port(name: &str, baud_rate: u32) -> serialport::Result<Box<dyn serialport::SerialPort>> {
+    serialport::new(name, baud_rate)
+        .data_bits(DataBits::Eight)
+        .flow_control(FlowControl::None)
+        .parity(Parity::None)
+        .stop_bits(StopBits::One)
+        .timeout(Duration::from_secs(1))
+        .open()
+}
+
+fn port_reader(serial_port: Box<dyn serialport::SerialPort>) -> PortReader {
+    if cfg!(target_os = "windows") {
+        BufReader::with_capacity(1, serial_port.take(INITIAL_LINE_LIMIT))
+    } else {
+        BufReader::new(serial_port.take(INITIAL_LINE_LIMIT))
+    }
+}
+
+/// Writes the bytes after the first `written` ones, counting the bytes written as it goes so a
+/// failed write can be resumed.
+fn write_remaining(
+    writer: &mut impl io::Write,
+    bytes: &[u8],
+    written: &mut usize,
+) -> io::Result<()> {
+    while *written < bytes.len() {
+        match writer.write(&bytes[*written..]) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(len) => *written += len,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(())
+}
+
+fn is_retriable(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+    )
+}
+
 #[derive(Error, Debug)]
 /// Error returned while opening or initializing a device connection.
+#[non_exhaustive]
 pub enum ConnectionError {
     /// Initial device information was not received before the timeout elapsed.
     #[error("RF Explorer device info was not received")]
@@ -142,35 +439,91 @@ pub enum ConnectionError {
     #[error(transparent)]
     SerialPortFailedToOpen(#[from] serialport::Error),
 
+    /// The serial port is locked by another connection.
+    ///
+    /// See [`set_port_locking_enabled`](crate::set_port_locking_enabled).
+    #[error("The serial port '{port_name}' is in use by {}", in_use_by(*.by_pid))]
+    PortInUse {
+        /// The name of the serial port.
+        port_name: String,
+        /// The ID of the process that has the port open, if it could be read from the lock file.
+        by_pid: Option<u32>,
+    },
+
     /// No USB serial device with the requested name was found.
     #[error("A USB serial device with the name '{0}' could not be found")]
     UsbSerialDeviceNotFound(String),
+
+    /// The requested baud rate, in bits per second, isn't one an RF Explorer supports.
+    #[error("{0} bps isn't a baud rate an RF Explorer supports")]
+    UnsupportedBaudRate(u32),
+}
+
+fn in_use_by(pid: Option<u32>) -> String {
+    match pid {
+        Some(pid) if pid == std::process::id() => {
+            format!("another connection in this process ({pid})")
+        }
+        Some(pid) => format!("process {pid}"),
+        None => "another process".to_string(),
+    }
 }
 
 /// Result type returned while opening or initializing a device connection.
 pub type ConnectionResult<T> = Result<T, ConnectionError>;
 
-pub(crate) fn silabs_cp210x_ports() -> impl Iterator<Item = SerialPortInfo> {
+/// The USB vendor ID of the Silicon Labs CP210x USB to UART bridge used by RF Explorers.
+pub const RFE_USB_VID: u16 = 4_292;
+
+/// The USB product ID of the Silicon Labs CP210x USB to UART bridge used by RF Explorers.
+pub const RFE_USB_PID: u16 = 60_000;
+
+pub(crate) fn rf_explorer_ports() -> impl Iterator<Item = SerialPortInfo> {
     serialport::available_ports()
         .unwrap_or_default()
         .into_iter()
-        .filter(is_silabs_cp210x)
+        .filter(|port_info| {
+            if is_rf_explorer_usb_port(port_info) && is_macos_call_in_port(port_info) {
+                debug!(
+                    port_name = port_info.port_name,
+                    "Skipped a macOS call-in port; its /dev/cu.* port is probed instead"
+                );
+            }
+            looks_like_rf_explorer(port_info)
+        })
+}
+
+/// Returns whether a serial port could be an RF Explorer.
+///
+/// This is the check used to choose which ports to probe when connecting. A port looks like an
+/// RF Explorer if it belongs to a USB device with the VID and PID of the CP210x bridge used by
+/// RF Explorers, unless it's one of the `/dev/tty.*` call-in ports that macOS lists alongside
+/// each `/dev/cu.*` port for the same device. Probing both would find the same RF Explorer twice,
+/// so only the `/dev/cu.*` call-out port, which is the one meant for connecting to a device, is
+/// used. A `/dev/tty.*` port can still be opened by name with `connect_with_name_and_baud_rate`.
+///
+/// Other devices that use a CP210x bridge look the same, so the device on a matching port still
+/// has to respond like an RF Explorer for a connection to be made.
+pub fn looks_like_rf_explorer(port_info: &SerialPortInfo) -> bool {
+    is_rf_explorer_usb_port(port_info) && !is_macos_call_in_port(port_info)
 }
 
-const fn is_silabs_cp210x(port_info: &SerialPortInfo) -> bool {
-    const SILABS_VID: u16 = 4_292;
-    const CP210X_PID: u16 = 60_000;
+fn is_rf_explorer_usb_port(port_info: &SerialPortInfo) -> bool {
     matches!(
         port_info.port_type,
         SerialPortType::UsbPort(UsbPortInfo {
-            vid: SILABS_VID,
-            pid: CP210X_PID,
+            vid: RFE_USB_VID,
+            pid: RFE_USB_PID,
             ..
         })
     )
 }
 
-/// Returns the names of serial ports with the VID and PID of an RF Explorer.
+fn is_macos_call_in_port(port_info: &SerialPortInfo) -> bool {
+    port_info.port_name.starts_with("/dev/tty.")
+}
+
+/// Returns the names of serial ports that look like an RF Explorer.
 ///
 /// # Examples
 ///
@@ -180,7 +533,7 @@ const fn is_silabs_cp210x(port_info: &SerialPortInfo) -> bool {
 /// }
 /// ```
 pub fn port_names() -> Vec<String> {
-    silabs_cp210x_ports()
+    rf_explorer_ports()
         .map(|port_info| port_info.port_name)
         .collect()
 }
@@ -261,34 +614,82 @@ pub fn is_driver_installed() -> bool {
     exit_status.success()
 }
 
-fn bps_to_code(baud_rate: u32) -> super::Result<u8> {
-    match baud_rate {
-        1_200 => Ok(b'1'),
-        2_400 => Ok(b'2'),
-        4_800 => Ok(b'3'),
-        9_600 => Ok(b'4'),
-        19_200 => Ok(b'5'),
-        38_400 => Ok(b'6'),
-        57_600 => Ok(b'7'),
-        115_200 => Ok(b'8'),
-        500_000 => Ok(b'0'),
-        _ => Err(super::Error::InvalidInput("Invalid baud rate".to_string())),
-    }
-}
-
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-pub(crate) struct BaudRate {
-    bps: u32,
-    code: u8,
+/// A baud rate an RF Explorer can be switched to.
+///
+/// Every RF Explorer model communicates at 500,000 bps, the default baud rate, when it leaves the
+/// factory.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BaudRate {
+    /// 1,200 bps.
+    Bps1200,
+    /// 2,400 bps.
+    Bps2400,
+    /// 4,800 bps.
+    Bps4800,
+    /// 9,600 bps.
+    Bps9600,
+    /// 19,200 bps.
+    Bps19200,
+    /// 38,400 bps.
+    Bps38400,
+    /// 57,600 bps.
+    Bps57600,
+    /// 115,200 bps.
+    Bps115200,
+    /// 500,000 bps.
+    #[default]
+    Bps500000,
 }
 
 impl BaudRate {
-    pub(crate) fn bps(&self) -> u32 {
-        self.bps
+    /// Every baud rate an RF Explorer supports, from slowest to fastest.
+    pub const ALL: [BaudRate; 9] = [
+        BaudRate::Bps1200,
+        BaudRate::Bps2400,
+        BaudRate::Bps4800,
+        BaudRate::Bps9600,
+        BaudRate::Bps19200,
+        BaudRate::Bps38400,
+        BaudRate::Bps57600,
+        BaudRate::Bps115200,
+        BaudRate::Bps500000,
+    ];
+
+    /// Returns the baud rate in bits per second.
+    pub const fn bps(&self) -> u32 {
+        match self {
+            BaudRate::Bps1200 => 1_200,
+            BaudRate::Bps2400 => 2_400,
+            BaudRate::Bps4800 => 4_800,
+            BaudRate::Bps9600 => 9_600,
+            BaudRate::Bps19200 => 19_200,
+            BaudRate::Bps38400 => 38_400,
+            BaudRate::Bps57600 => 57_600,
+            BaudRate::Bps115200 => 115_200,
+            BaudRate::Bps500000 => 500_000,
+        }
     }
 
-    pub(crate) fn code(&self) -> u8 {
-        self.code
+    /// Returns the code a set baud rate command uses to select the baud rate.
+    pub(crate) const fn code(&self) -> u8 {
+        match self {
+            BaudRate::Bps1200 => b'1',
+            BaudRate::Bps2400 => b'2',
+            BaudRate::Bps4800 => b'3',
+            BaudRate::Bps9600 => b'4',
+            BaudRate::Bps19200 => b'5',
+            BaudRate::Bps38400 => b'6',
+            BaudRate::Bps57600 => b'7',
+            BaudRate::Bps115200 => b'8',
+            BaudRate::Bps500000 => b'0',
+        }
+    }
+
+    /// Returns the baud rate a set baud rate command's code selects.
+    pub(crate) fn from_code(code: u8) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|baud_rate| baud_rate.code() == code)
     }
 }
 
@@ -296,18 +697,276 @@ impl TryFrom<u32> for BaudRate {
     type Error = super::Error;
 
     fn try_from(bps: u32) -> Result<Self, Self::Error> {
-        Ok(BaudRate {
-            bps,
-            code: bps_to_code(bps)?,
-        })
+        Self::ALL
+            .into_iter()
+            .find(|baud_rate| baud_rate.bps() == bps)
+            .ok_or_else(|| {
+                super::Error::InvalidInput(format!(
+                    "{bps} bps is not a baud rate an RF Explorer supports"
+                ))
+            })
     }
 }
 
-impl Default for BaudRate {
-    fn default() -> Self {
-        BaudRate {
-            bps: 500_000,
-            code: b'0',
+impl From<BaudRate> for u32 {
+    fn from(baud_rate: BaudRate) -> Self {
+        baud_rate.bps()
+    }
+}
+
+impl fmt::Display for BaudRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bps", self.bps())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::common::transcript_port::TranscriptPort;
+
+    fn mock_serial_port() -> (SerialPort, TranscriptPort) {
+        let mock = TranscriptPort::from_transcript("");
+        let port_info = SerialPortInfo {
+            port_name: "mock".to_string(),
+            port_type: SerialPortType::Unknown,
+        };
+        (SerialPort::from_port(mock.transport(), port_info), mock)
+    }
+
+    fn usb_port_info(port_name: &str, vid: u16, pid: u16) -> SerialPortInfo {
+        SerialPortInfo {
+            port_name: port_name.to_string(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo {
+                vid,
+                pid,
+                serial_number: Some("0001".to_string()),
+                manufacturer: Some("Silicon Labs".to_string()),
+                product: Some("CP2102 USB to UART Bridge Controller".to_string()),
+            }),
+        }
+    }
+
+    #[test]
+    fn convert_supported_baud_rates() {
+        for baud_rate in BaudRate::ALL {
+            assert_eq!(BaudRate::try_from(baud_rate.bps()).unwrap(), baud_rate);
+            assert_eq!(BaudRate::from_code(baud_rate.code()), Some(baud_rate));
+        }
+        assert_eq!(BaudRate::default().bps(), FAST_BAUD_RATE);
+        assert_eq!(BaudRate::Bps115200.to_string(), "115200 bps");
+        assert!(matches!(
+            BaudRate::try_from(250_000),
+            Err(crate::Error::InvalidInput(_))
+        ));
+        assert_eq!(BaudRate::from_code(b'9'), None);
+    }
+
+    #[test]
+    fn recognize_rf_explorer_ports() {
+        assert!(looks_like_rf_explorer(&usb_port_info(
+            "/dev/ttyUSB0",
+            RFE_USB_VID,
+            RFE_USB_PID
+        )));
+        assert!(looks_like_rf_explorer(&usb_port_info(
+            "COM3",
+            RFE_USB_VID,
+            RFE_USB_PID
+        )));
+        assert!(looks_like_rf_explorer(&usb_port_info(
+            "/dev/cu.SLAB_USBtoUART",
+            RFE_USB_VID,
+            RFE_USB_PID
+        )));
+    }
+
+    #[test]
+    fn reject_other_ports() {
+        // An Arduino Uno
+        assert!(!looks_like_rf_explorer(&usb_port_info(
+            "/dev/ttyACM0",
+            0x2341,
+            0x0043
+        )));
+        // A different Silicon Labs bridge
+        assert!(!looks_like_rf_explorer(&usb_port_info(
+            "/dev/ttyUSB1",
+            RFE_USB_VID,
+            0xEA70
+        )));
+        // The macOS call-in duplicate of an RF Explorer's port
+        assert!(!looks_like_rf_explorer(&usb_port_info(
+            "/dev/tty.SLAB_USBtoUART",
+            RFE_USB_VID,
+            RFE_USB_PID
+        )));
+        for port_type in [
+            SerialPortType::PciPort,
+            SerialPortType::BluetoothPort,
+            SerialPortType::Unknown,
+        ] {
+            assert!(!looks_like_rf_explorer(&SerialPortInfo {
+                port_name: "/dev/ttyS0".to_string(),
+                port_type,
+            }));
+        }
+    }
+
+    #[test]
+    fn pace_commands() {
+        let (serial_port, mock) = mock_serial_port();
+        let min_command_interval = Duration::from_millis(30);
+        serial_port.set_min_command_interval(min_command_interval);
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            serial_port.send_command(&b"#\x04C0"[..]).unwrap();
         }
+
+        let writes = mock.timed_writes();
+        assert_eq!(writes.len(), 3);
+        // The first command shouldn't be delayed
+        assert!(writes[0].0 - start < min_command_interval);
+        for window in writes.windows(2) {
+            assert!(window[1].0 - window[0].0 >= min_command_interval);
+        }
+    }
+
+    #[test]
+    fn send_commands_before_close_without_retrying() {
+        let (serial_port, mock) = mock_serial_port();
+        serial_port.set_write_retry(3, Duration::from_millis(1));
+        serial_port.set_min_command_interval(Duration::from_secs(10));
+        mock.fail_writes([io::ErrorKind::TimedOut]);
+
+        let start = Instant::now();
+        serial_port.send_commands_before_close(
+            [&b"#\x04CH"[..], b"#\x04D0", b"#\x04C0"].map(Cow::from),
+            Duration::from_millis(20),
+        );
+
+        // The first command failed and the others were paced by the write timeout instead of the
+        // much longer minimum command interval
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let writes = mock.timed_writes();
+        assert_eq!(
+            writes
+                .iter()
+                .map(|(_, bytes)| &bytes[..])
+                .collect::<Vec<_>>(),
+            [&b"#\x04D0"[..], b"#\x04C0"]
+        );
+        assert!(writes[1].0 - writes[0].0 >= Duration::from_millis(20));
+        assert_eq!(
+            serial_port.write_stats(),
+            WriteStats {
+                successful_writes: 2,
+                failed_writes: 1,
+                retries: 0
+            }
+        );
+    }
+
+    #[test]
+    fn retry_retriable_write_failures() {
+        let (serial_port, mock) = mock_serial_port();
+        serial_port.set_write_retry(3, Duration::from_millis(1));
+        mock.fail_writes([io::ErrorKind::TimedOut, io::ErrorKind::WouldBlock]);
+
+        serial_port.send_bytes(b"#\x04C0").unwrap();
+        assert_eq!(mock.writes().len(), 1);
+        assert_eq!(
+            serial_port.write_stats(),
+            WriteStats {
+                successful_writes: 1,
+                failed_writes: 0,
+                retries: 2
+            }
+        );
+    }
+
+    #[test]
+    fn resume_partial_writes() {
+        let (serial_port, mock) = mock_serial_port();
+        serial_port.set_write_retry(3, Duration::from_millis(1));
+        // The first write times out after 2 bytes
+        mock.limit_writes([2]);
+        mock.fail_writes([io::ErrorKind::TimedOut]);
+
+        serial_port.send_bytes(b"#\x04C0").unwrap();
+        assert_eq!(mock.writes().concat(), b"#\x04C0");
+        assert_eq!(serial_port.write_stats().retries, 1);
+    }
+
+    #[test]
+    fn fail_after_last_write_attempt() {
+        let (serial_port, mock) = mock_serial_port();
+        serial_port.set_write_retry(2, Duration::from_millis(1));
+        mock.fail_writes([io::ErrorKind::WouldBlock; 3]);
+
+        assert!(serial_port.send_bytes(b"#\x04C0").is_err());
+        assert!(mock.writes().is_empty());
+        assert_eq!(
+            serial_port.write_stats(),
+            WriteStats {
+                successful_writes: 0,
+                failed_writes: 1,
+                retries: 1
+            }
+        );
+    }
+
+    #[test]
+    fn return_non_retriable_write_failures_immediately() {
+        let (serial_port, mock) = mock_serial_port();
+        serial_port.set_write_retry(3, Duration::from_millis(1));
+        mock.fail_writes([io::ErrorKind::BrokenPipe]);
+
+        let error = serial_port.write_bytes(b"#\x04C0").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(serial_port.write_stats().retries, 0);
+        assert_eq!(serial_port.write_stats().failed_writes, 1);
+
+        // Writes aren't retried by default
+        let (serial_port, mock) = mock_serial_port();
+        mock.fail_writes([io::ErrorKind::TimedOut]);
+        assert!(serial_port.send_bytes(b"#\x04C0").is_err());
+        assert_eq!(serial_port.write_stats().retries, 0);
+    }
+
+    #[test]
+    fn log_written_commands() {
+        let (serial_port, mock) = mock_serial_port();
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let logged_clone = logged.clone();
+        serial_port.set_command_log_callback(Some(Box::new(move |bytes, _| {
+            logged_clone.lock().unwrap().push(bytes.to_vec());
+        })));
+
+        serial_port.send_command(&b"#\x04C0"[..]).unwrap();
+        serial_port.send_bytes(b"#\x05CH").unwrap();
+        assert_eq!(*logged.lock().unwrap(), mock.writes());
+
+        // Failed writes aren't logged
+        mock.fail_writes([io::ErrorKind::BrokenPipe]);
+        assert!(serial_port.send_bytes(b"#\x04C0").is_err());
+        assert_eq!(logged.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn disable_command_pacing() {
+        let (serial_port, mock) = mock_serial_port();
+        serial_port.set_min_command_interval(Duration::ZERO);
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            serial_port.send_command(&b"#\x04C0"[..]).unwrap();
+        }
+
+        assert_eq!(mock.writes().len(), 3);
+        assert!(start.elapsed() < DEFAULT_MIN_COMMAND_INTERVAL);
     }
 }
@@ -0,0 +1,147 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A source of the current time, so tests can control how old cached values are.
+pub(crate) trait Clock: Debug + Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug)]
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A cached value and when it was received.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Timestamped<T> {
+    pub(crate) value: T,
+    pub(crate) received_at: Instant,
+}
+
+/// Timestamps values that RF Explorers report periodically and decides when they're too old to
+/// be returned.
+#[derive(Debug)]
+pub(crate) struct Freshness {
+    clock: Arc<dyn Clock>,
+    staleness_threshold: Mutex<Option<Duration>>,
+}
+
+impl Default for Freshness {
+    fn default() -> Self {
+        Freshness {
+            clock: Arc::new(SystemClock),
+            staleness_threshold: Mutex::new(None),
+        }
+    }
+}
+
+impl Freshness {
+    pub(crate) fn stamp<T>(&self, value: T) -> Timestamped<T> {
+        Timestamped {
+            value,
+            received_at: self.clock.now(),
+        }
+    }
+
+    /// Returns the cached value and how long ago it was received, no matter how old it is.
+    pub(crate) fn with_age<T: Copy>(
+        &self,
+        cached: Option<&Timestamped<T>>,
+    ) -> Option<(T, Duration)> {
+        cached.map(|cached| {
+            (
+                cached.value,
+                self.clock
+                    .now()
+                    .saturating_duration_since(cached.received_at),
+            )
+        })
+    }
+
+    /// Returns the cached value unless it's older than the staleness threshold.
+    pub(crate) fn fresh<T: Copy>(&self, cached: Option<&Timestamped<T>>) -> Option<T> {
+        let threshold = self.staleness_threshold();
+        self.with_age(cached)
+            .filter(|(_, age)| threshold.is_none_or(|threshold| *age <= threshold))
+            .map(|(value, _)| value)
+    }
+
+    pub(crate) fn staleness_threshold(&self) -> Option<Duration> {
+        *self.staleness_threshold.lock().unwrap()
+    }
+
+    pub(crate) fn set_staleness_threshold(&self, staleness_threshold: Option<Duration>) {
+        *self.staleness_threshold.lock().unwrap() = staleness_threshold;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct FakeClock(Mutex<Instant>);
+
+    impl FakeClock {
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn freshness() -> (Freshness, Arc<FakeClock>) {
+        let clock = Arc::new(FakeClock(Mutex::new(Instant::now())));
+        let freshness = Freshness {
+            clock: clock.clone(),
+            ..Freshness::default()
+        };
+        (freshness, clock)
+    }
+
+    #[test]
+    fn never_expire_without_a_threshold() {
+        let (freshness, clock) = freshness();
+        let cached = freshness.stamp(42);
+        clock.advance(Duration::from_secs(3_600));
+        assert_eq!(freshness.fresh(Some(&cached)), Some(42));
+        assert_eq!(
+            freshness.with_age(Some(&cached)),
+            Some((42, Duration::from_secs(3_600)))
+        );
+        assert_eq!(freshness.fresh::<i32>(None), None);
+    }
+
+    #[test]
+    fn expire_values_older_than_the_threshold() {
+        let (freshness, clock) = freshness();
+        freshness.set_staleness_threshold(Some(Duration::from_secs(30)));
+        let cached = freshness.stamp(42);
+
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(freshness.fresh(Some(&cached)), Some(42));
+
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(freshness.fresh(Some(&cached)), None);
+        // The value and its age are still available to callers that want them
+        assert_eq!(
+            freshness.with_age(Some(&cached)),
+            Some((42, Duration::from_millis(30_001)))
+        );
+
+        // A newer value is fresh again
+        let cached = freshness.stamp(43);
+        assert_eq!(freshness.fresh(Some(&cached)), Some(43));
+    }
+}
@@ -0,0 +1,386 @@
+//! A mock serial port that replays RF Explorer transcripts.
+//!
+//! It's the only mock port, used by the unit tests and, through `tests/common`, by the
+//! integration tests, so this file can't refer to anything in the crate.
+//!
+//! Transcripts are text files in `tests/transcripts` made up of the following lines:
+//!
+//! - `> <bytes>`: bytes written by the host
+//! - `< <bytes>`: bytes sent by the device, followed by `\r\n`
+//! - `~ <ms>`: delays the device's following lines by the given number of milliseconds
+//! - `# <comment>`: ignored, along with blank lines
+//!
+//! Non-printable bytes are written as `\xNN` and backslashes as `\\`. Device lines are sent once
+//! every host line before them has been written.
+//!
+//! A transcript that wasn't captured from a device says it's synthetic in its header comment.
+
+#![allow(dead_code)]
+
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Write(Vec<u8>),
+    Read(Vec<u8>),
+    Delay(Duration),
+}
+
+#[derive(Debug, Default)]
+struct State {
+    steps: VecDeque<Step>,
+    pending_reads: VecDeque<(Instant, Vec<u8>)>,
+    writes: Vec<(Instant, Vec<u8>)>,
+    write_failures: VecDeque<io::ErrorKind>,
+    write_limits: VecDeque<usize>,
+    is_unplugged: bool,
+    on_baud_rate: Option<Hook>,
+}
+
+/// A function called by the port, which can block to hold up the host at that point.
+#[derive(Clone)]
+struct Hook(Arc<dyn Fn() + Send + Sync>);
+
+impl std::fmt::Debug for Hook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+impl State {
+    /// Schedules every device line up to the next host line.
+    fn release_reads(&mut self) {
+        let mut release_at = self
+            .pending_reads
+            .back()
+            .map_or_else(Instant::now, |(instant, _)| *instant)
+            .max(Instant::now());
+        while let Some(step) = self.steps.front() {
+            match step {
+                Step::Write(_) => break,
+                Step::Read(bytes) => self.pending_reads.push_back((release_at, bytes.clone())),
+                Step::Delay(delay) => release_at += *delay,
+            }
+            self.steps.pop_front();
+        }
+    }
+}
+
+/// Serial port that plays back a transcript and records everything the host writes to it.
+#[derive(Debug, Clone)]
+pub struct TranscriptPort {
+    state: Arc<Mutex<State>>,
+    timeout: Duration,
+}
+
+impl TranscriptPort {
+    /// Loads a transcript from `tests/transcripts`.
+    pub fn load(name: &str) -> Self {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("transcripts")
+            .join(name);
+        let transcript = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("Failed to read {}: {err}", path.display()));
+        Self::from_transcript(&transcript)
+    }
+
+    /// Parses a transcript.
+    pub fn from_transcript(transcript: &str) -> Self {
+        let steps = transcript
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| match line.split_at(2) {
+                ("> ", bytes) => Step::Write(unescape(bytes)),
+                ("< ", bytes) => {
+                    let mut bytes = unescape(bytes);
+                    bytes.extend_from_slice(b"\r\n");
+                    Step::Read(bytes)
+                }
+                ("~ ", ms) => Step::Delay(Duration::from_millis(ms.trim().parse().unwrap())),
+                _ => panic!("Invalid transcript line: {line}"),
+            })
+            .collect();
+
+        let mut state = State {
+            steps,
+            ..State::default()
+        };
+        state.release_reads();
+
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            timeout: Duration::from_millis(10),
+        }
+    }
+
+    /// Returns the bytes of every write the host has made.
+    pub fn writes(&self) -> Vec<Vec<u8>> {
+        self.timed_writes()
+            .into_iter()
+            .map(|(_, bytes)| bytes)
+            .collect()
+    }
+
+    /// Returns every write the host has made along with the time it was made.
+    pub fn timed_writes(&self) -> Vec<(Instant, Vec<u8>)> {
+        self.state.lock().unwrap().writes.clone()
+    }
+
+    /// Makes the next writes fail with the given errors, in order, without writing anything.
+    pub fn fail_writes(&self, error_kinds: impl IntoIterator<Item = io::ErrorKind>) {
+        self.state
+            .lock()
+            .unwrap()
+            .write_failures
+            .extend(error_kinds);
+    }
+
+    /// Returns whether every host line in the transcript has been written.
+    pub fn is_finished(&self) -> bool {
+        !self
+            .state
+            .lock()
+            .unwrap()
+            .steps
+            .iter()
+            .any(|step| matches!(step, Step::Write(_)))
+    }
+
+    /// Makes the next writes accept at most the given numbers of bytes, in order, like a serial
+    /// port whose write timed out partway through.
+    ///
+    /// Limited writes are made before the failures given to [`TranscriptPort::fail_writes`].
+    pub fn limit_writes(&self, lens: impl IntoIterator<Item = usize>) {
+        self.state.lock().unwrap().write_limits.extend(lens);
+    }
+
+    /// Calls `hook` whenever the host reads the port's baud rate, like when the port is opened.
+    pub fn set_on_baud_rate(&self, hook: impl Fn() + Send + Sync + 'static) {
+        self.state.lock().unwrap().on_baud_rate = Some(Hook(Arc::new(hook)));
+    }
+
+    /// Returns whether every transport created from this port has been dropped.
+    pub fn is_released(&self) -> bool {
+        Arc::strong_count(&self.state) == 1
+    }
+
+    /// Makes every following read and write fail like they do after a USB cable is unplugged.
+    pub fn unplug(&self) {
+        self.state.lock().unwrap().is_unplugged = true;
+    }
+
+    /// Returns the port as a boxed transport that can be passed to `connect_with_transport`.
+    pub fn transport(&self) -> Box<dyn SerialPort> {
+        Box::new(self.clone())
+    }
+}
+
+fn unescape(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.bytes();
+    while let Some(byte) = chars.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        match chars.next() {
+            Some(b'\\') => bytes.push(b'\\'),
+            Some(b'x') => {
+                let hex = [chars.next().unwrap(), chars.next().unwrap()];
+                let hex = std::str::from_utf8(&hex).unwrap();
+                bytes.push(u8::from_str_radix(hex, 16).unwrap());
+            }
+            escape => panic!("Invalid escape sequence: {escape:?}"),
+        }
+    }
+    bytes
+}
+
+fn unplugged_error() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "The device was unplugged")
+}
+
+impl Read for TranscriptPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            if state.is_unplugged {
+                return Err(unplugged_error());
+            }
+            match state.pending_reads.front_mut() {
+                Some((release_at, bytes)) if *release_at <= Instant::now() => {
+                    let len = buf.len().min(bytes.len());
+                    buf[..len].copy_from_slice(&bytes[..len]);
+                    bytes.drain(..len);
+                    if bytes.is_empty() {
+                        state.pending_reads.pop_front();
+                    }
+                    return Ok(len);
+                }
+                Some((release_at, _)) => release_at
+                    .saturating_duration_since(Instant::now())
+                    .min(self.timeout),
+                None => self.timeout,
+            }
+        };
+
+        // Behave like a real serial port that didn't receive anything before its timeout
+        // The error is created without a message so that timing out doesn't allocate
+        thread::sleep(wait);
+        Err(io::ErrorKind::TimedOut.into())
+    }
+}
+
+impl Write for TranscriptPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        if state.is_unplugged {
+            return Err(unplugged_error());
+        }
+        // Limited writes come before failures so a write can time out after a partial write
+        let buf = match state.write_limits.pop_front() {
+            Some(limit) => &buf[..buf.len().min(limit)],
+            None => match state.write_failures.pop_front() {
+                Some(error_kind) => {
+                    return Err(io::Error::new(error_kind, "Simulated write failure"));
+                }
+                None => buf,
+            },
+        };
+        state.writes.push((Instant::now(), buf.to_vec()));
+        if matches!(state.steps.front(), Some(Step::Write(expected)) if expected == buf) {
+            state.steps.pop_front();
+            state.release_reads();
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for TranscriptPort {
+    fn name(&self) -> Option<String> {
+        Some("transcript".to_string())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        // Call the hook without holding the lock so it can block
+        let hook = self.state.lock().unwrap().on_baud_rate.clone();
+        if let Some(Hook(hook)) = hook {
+            hook();
+        }
+        Ok(500_000)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, _: u32) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        let state = self.state.lock().unwrap();
+        let len: usize = state
+            .pending_reads
+            .iter()
+            .filter(|(release_at, _)| *release_at <= Instant::now())
+            .map(|(_, bytes)| bytes.len())
+            .sum();
+        Ok(u32::try_from(len).unwrap_or(u32::MAX))
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, _: ClearBuffer) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(self.clone()))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
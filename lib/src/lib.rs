@@ -15,6 +15,21 @@
 //! # Ok::<(), rfe::Error>(())
 //! ```
 //!
+//! [`measure_blocking`] connects, measures a frequency range, and disconnects in one call:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use rfe::Frequency;
+//!
+//! let points = rfe::measure_blocking(
+//!     Frequency::from_mhz(2400),
+//!     Frequency::from_mhz(2500),
+//!     Duration::from_secs(2),
+//! )?;
+//! # Ok::<(), rfe::Error>(())
+//! ```
+//!
 //! # Extension API
 //!
 //! [`Device`] and [`MessageContainer`] provide the lower-level serial device
@@ -24,12 +39,21 @@
 mod common;
 mod rf_explorer;
 
+/// Measurements built on the spectrum analyzer, like quick scans and two-tone intermodulation tests.
+pub mod measurements;
+#[cfg(feature = "render")]
+pub mod render;
 /// RF Explorer signal generator types and commands.
 pub mod signal_generator;
+#[cfg(feature = "simulator")]
+pub mod simulator;
 /// RF Explorer spectrum analyzer types and commands.
 pub mod spectrum_analyzer;
+/// Background tasks for long-running operations.
+pub mod task;
 
 pub use common::*;
-pub use rf_explorer::ScreenData;
+pub use measurements::{measure, measure_blocking};
+pub use rf_explorer::{Command, DeviceId, ScreenData, ScreenDumpMode};
 pub use signal_generator::SignalGenerator;
 pub use spectrum_analyzer::SpectrumAnalyzer;
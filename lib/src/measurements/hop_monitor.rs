@@ -0,0 +1,470 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Error, Frequency, Result, SpectrumAnalyzer,
+    task::{self, TaskContext, TaskHandle},
+};
+
+/// How long to wait for each sweep before giving up on the spectrum analyzer.
+const SWEEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a hopping channel was seen during a [`hop_monitor`] measurement.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelActivity {
+    /// The channel's frequency.
+    pub freq: Frequency,
+    /// The number of sweeps the channel was seen in.
+    pub hits: usize,
+    /// The strongest amplitude measured on the channel, if it was seen.
+    pub peak_dbm: Option<f32>,
+}
+
+/// A signal above the threshold that isn't on any of the expected channels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnexpectedEmission {
+    /// The frequency the signal was strongest at.
+    pub freq: Frequency,
+    /// The number of sweeps the signal was seen in.
+    pub hits: usize,
+    /// The strongest amplitude measured.
+    pub peak_dbm: f32,
+}
+
+/// The result of a [`hop_monitor`] measurement.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HopReport {
+    /// The activity on each expected channel, from the lowest channel to the highest.
+    pub channels: Vec<ChannelActivity>,
+    /// The channels that weren't seen in any sweep.
+    pub missing_channels: Vec<Frequency>,
+    /// The signals seen outside of the expected channels, from the lowest frequency to the
+    /// highest.
+    pub unexpected: Vec<UnexpectedEmission>,
+    /// The number of sweeps that were watched.
+    pub sweep_count: usize,
+    /// The resolution bandwidth the sweeps were measured with.
+    pub rbw: Frequency,
+}
+
+impl HopReport {
+    /// Returns whether every channel was seen and nothing was seen outside of the channels.
+    pub fn is_pass(&self) -> bool {
+        self.missing_channels.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// Watches a frequency hopping transmitter on a background thread and reports which of its
+/// channels it was seen on.
+///
+/// The spectrum analyzer's original start and stop frequencies are restored when the task
+/// finishes, fails, or is canceled. See [`hop_monitor_blocking`] for the details and errors of the
+/// measurement.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{sync::Arc, time::Duration};
+///
+/// use rfe::{Frequency, SpectrumAnalyzer, measurements};
+///
+/// let rfe = Arc::new(SpectrumAnalyzer::connect().expect("RF Explorer should be connected"));
+/// let channels: Vec<Frequency> = (0..16)
+///     .map(|channel| Frequency::from_mhz(903) + Frequency::from_khz(1_600) * channel)
+///     .collect();
+/// let task = measurements::hop_monitor(rfe, &channels, Duration::from_secs(30), -70.);
+/// let report = task.wait(Duration::from_secs(60))?;
+/// # Ok::<(), rfe::Error>(())
+/// ```
+pub fn hop_monitor(
+    rfe: Arc<SpectrumAnalyzer>,
+    channels: &[Frequency],
+    dwell_window: Duration,
+    threshold_dbm: f32,
+) -> TaskHandle<HopReport> {
+    let channels = channels.to_vec();
+    let (start, stop) = (rfe.start_freq(), rfe.stop_freq());
+    let cleanup_rfe = rfe.clone();
+    TaskHandle::spawn(
+        move |context| {
+            check_channels(&channels)?;
+            monitor_channels(&rfe, &channels, dwell_window, threshold_dbm, context)
+        },
+        move || cleanup_rfe.set_start_stop(start, stop),
+    )
+}
+
+/// Watches a frequency hopping transmitter and reports which of its channels it was seen on.
+///
+/// The spectrum analyzer sweeps a span that covers every channel for `dwell_window`. Each sweep's
+/// peaks above `threshold_dbm` are counted as hits on the nearest channel, or as unexpected
+/// emissions if they're not on any channel. Peaks closer together than the resolution bandwidth
+/// (RBW) are counted as one signal, because the RBW filter spreads each signal over that width.
+/// The spectrum analyzer's original start and stop frequencies are restored afterwards.
+///
+/// Returns [`Error::InvalidInput`] if no channels are given and [`Error::TimedOut`] if the
+/// spectrum analyzer stops sending sweeps.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use rfe::{Frequency, SpectrumAnalyzer, measurements};
+///
+/// let rfe = SpectrumAnalyzer::connect().expect("RF Explorer should be connected");
+/// let channels: Vec<Frequency> = (0..16)
+///     .map(|channel| Frequency::from_mhz(903) + Frequency::from_khz(1_600) * channel)
+///     .collect();
+/// let report =
+///     measurements::hop_monitor_blocking(&rfe, &channels, Duration::from_secs(30), -70.)?;
+/// for channel in &report.missing_channels {
+///     println!("Never hopped to {} MHz", channel.as_mhz_f64());
+/// }
+/// # Ok::<(), rfe::Error>(())
+/// ```
+pub fn hop_monitor_blocking(
+    rfe: &SpectrumAnalyzer,
+    channels: &[Frequency],
+    dwell_window: Duration,
+    threshold_dbm: f32,
+) -> Result<HopReport> {
+    check_channels(channels)?;
+    let (start, stop) = (rfe.start_freq(), rfe.stop_freq());
+    task::run_blocking(
+        |context| monitor_channels(rfe, channels, dwell_window, threshold_dbm, context),
+        || rfe.set_start_stop(start, stop),
+    )
+}
+
+fn monitor_channels(
+    rfe: &SpectrumAnalyzer,
+    channels: &[Frequency],
+    dwell_window: Duration,
+    threshold_dbm: f32,
+    context: &TaskContext,
+) -> Result<HopReport> {
+    let (start, stop) = channel_span(channels, *rfe.capabilities().span_range.start());
+    rfe.set_start_stop(start, stop)?;
+    // The first sweep after retuning may have been measured while the RF Explorer was settling
+    rfe.wait_for_sweep_matching_current_config(SWEEP_TIMEOUT)?;
+
+    let step_size = rfe.step_size();
+    let rbw = rfe.rbw().unwrap_or(step_size);
+    let mut tally = HopTally::new(channels, rfe.start_freq(), step_size, rbw, threshold_dbm);
+    let started_at = Instant::now();
+    loop {
+        context.check_canceled()?;
+        let sweep = rfe.wait_for_sweep_matching_current_config(SWEEP_TIMEOUT)?;
+        tally.add_sweep(&sweep);
+        let elapsed = started_at.elapsed();
+        if elapsed >= dwell_window {
+            break;
+        }
+        context.report_progress(elapsed.as_secs_f32() / dwell_window.as_secs_f32());
+    }
+    context.report_progress(1.);
+    Ok(tally.into_report())
+}
+
+fn check_channels(channels: &[Frequency]) -> Result<()> {
+    if channels.is_empty() {
+        return Err(Error::InvalidInput(
+            "At least one channel must be monitored".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns a start and stop frequency that cover the channels with half a channel spacing to
+/// spare on each side, so signals just outside of the channel set are seen too.
+fn channel_span(channels: &[Frequency], min_span: Frequency) -> (Frequency, Frequency) {
+    let lowest = channels.iter().copied().min().unwrap_or_default();
+    let highest = channels.iter().copied().max().unwrap_or_default();
+    let mut sorted = channels.to_vec();
+    sorted.sort();
+    let margin = sorted
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .filter(|spacing| spacing.as_hz() > 0)
+        .min()
+        .unwrap_or_default()
+        / 2;
+
+    let span = (highest - lowest + margin * 2).max(min_span);
+    let center = lowest + (highest - lowest) / 2;
+    let start = Frequency::from_hz(center.as_hz().saturating_sub((span / 2).as_hz()));
+    (start, start + span)
+}
+
+/// Counts the channel hits and unexpected emissions in sweeps.
+#[derive(Debug)]
+struct HopTally {
+    channels: Vec<ChannelActivity>,
+    unexpected: Vec<UnexpectedEmission>,
+    sweep_count: usize,
+    start_freq: Frequency,
+    step_size: Frequency,
+    rbw: Frequency,
+    /// How far a peak can be from a channel and still be counted as a hit on it.
+    tolerance: Frequency,
+    threshold_dbm: f32,
+}
+
+impl HopTally {
+    fn new(
+        channels: &[Frequency],
+        start_freq: Frequency,
+        step_size: Frequency,
+        rbw: Frequency,
+        threshold_dbm: f32,
+    ) -> Self {
+        let mut channels = channels.to_vec();
+        channels.sort();
+        channels.dedup();
+        HopTally {
+            channels: channels
+                .into_iter()
+                .map(|freq| ChannelActivity {
+                    freq,
+                    hits: 0,
+                    peak_dbm: None,
+                })
+                .collect(),
+            unexpected: Vec::new(),
+            sweep_count: 0,
+            start_freq,
+            step_size,
+            rbw,
+            // A signal on a channel peaks within half the RBW of it, and the nearest sweep point
+            // can be another half a step away
+            tolerance: rbw.max(step_size) / 2 + step_size / 2,
+            threshold_dbm,
+        }
+    }
+
+    fn add_sweep(&mut self, sweep: &[f32]) {
+        self.sweep_count += 1;
+        let mut hit_channels = vec![false; self.channels.len()];
+        for (freq, amp_dbm) in self.peaks(sweep) {
+            match self.nearest_channel(freq) {
+                Some(index) => {
+                    let channel = &mut self.channels[index];
+                    // A channel is hit at most once per sweep
+                    if !hit_channels[index] {
+                        hit_channels[index] = true;
+                        channel.hits += 1;
+                    }
+                    channel.peak_dbm =
+                        Some(channel.peak_dbm.map_or(amp_dbm, |dbm| dbm.max(amp_dbm)));
+                }
+                None => self.add_unexpected(freq, amp_dbm),
+            }
+        }
+    }
+
+    /// Returns the frequency and amplitude of each signal above the threshold.
+    ///
+    /// A signal's energy is spread over the RBW, so a local maximum within the RBW of a stronger
+    /// one is part of the same signal and is left out.
+    fn peaks(&self, sweep: &[f32]) -> Vec<(Frequency, f32)> {
+        let mut maxima: Vec<(Frequency, f32)> = sweep
+            .iter()
+            .enumerate()
+            .filter(|&(i, &amp)| {
+                amp >= self.threshold_dbm
+                    && i.checked_sub(1).is_none_or(|left| amp >= sweep[left])
+                    && sweep.get(i + 1).is_none_or(|&right| amp > right)
+            })
+            .map(|(i, &amp)| (self.start_freq + self.step_size * i as u64, amp))
+            .collect();
+        maxima.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut peaks: Vec<(Frequency, f32)> = Vec::new();
+        for (freq, amp) in maxima {
+            if peaks
+                .iter()
+                .all(|(peak_freq, _)| peak_freq.abs_diff(freq) >= self.rbw)
+            {
+                peaks.push((freq, amp));
+            }
+        }
+        peaks.sort_by_key(|(freq, _)| *freq);
+        peaks
+    }
+
+    /// Returns the index of the channel closest to `freq` if it's within the tolerance.
+    fn nearest_channel(&self, freq: Frequency) -> Option<usize> {
+        self.channels
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, channel)| channel.freq.abs_diff(freq))
+            .filter(|(_, channel)| channel.freq.abs_diff(freq) <= self.tolerance)
+            .map(|(index, _)| index)
+    }
+
+    /// Counts a signal outside of the channels, merging it with one seen before at about the
+    /// same frequency.
+    fn add_unexpected(&mut self, freq: Frequency, amp_dbm: f32) {
+        let tolerance = self.tolerance;
+        match self
+            .unexpected
+            .iter_mut()
+            .find(|emission| emission.freq.abs_diff(freq) <= tolerance)
+        {
+            Some(emission) => {
+                emission.hits += 1;
+                if amp_dbm > emission.peak_dbm {
+                    emission.freq = freq;
+                    emission.peak_dbm = amp_dbm;
+                }
+            }
+            None => self.unexpected.push(UnexpectedEmission {
+                freq,
+                hits: 1,
+                peak_dbm: amp_dbm,
+            }),
+        }
+    }
+
+    fn into_report(mut self) -> HopReport {
+        self.unexpected.sort_by_key(|emission| emission.freq);
+        HopReport {
+            missing_channels: self
+                .channels
+                .iter()
+                .filter(|channel| channel.hits == 0)
+                .map(|channel| channel.freq)
+                .collect(),
+            channels: self.channels,
+            unexpected: self.unexpected,
+            sweep_count: self.sweep_count,
+            rbw: self.rbw,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOISE_FLOOR_DBM: f32 = -100.;
+
+    /// A tally for channels at 101, 103, and 105 MHz, swept from 100 MHz in 100 kHz steps.
+    fn new_tally(rbw: Frequency) -> HopTally {
+        let channels = [101, 103, 105].map(Frequency::from_mhz);
+        HopTally::new(
+            &channels,
+            Frequency::from_mhz(100),
+            Frequency::from_khz(100),
+            rbw,
+            -70.,
+        )
+    }
+
+    /// Returns a sweep of 61 points from 100 to 106 MHz with a signal at each point in `signals`.
+    fn sweep(signals: &[(usize, f32)]) -> Vec<f32> {
+        let mut sweep = vec![NOISE_FLOOR_DBM; 61];
+        for &(point, amp_dbm) in signals {
+            sweep[point] = amp_dbm;
+        }
+        sweep
+    }
+
+    #[test]
+    fn count_hits_on_channels() {
+        let mut tally = new_tally(Frequency::from_khz(100));
+        tally.add_sweep(&sweep(&[(10, -40.)]));
+        tally.add_sweep(&sweep(&[(30, -50.)]));
+        // One point off of the channel is still a hit
+        tally.add_sweep(&sweep(&[(11, -45.)]));
+        let report = tally.into_report();
+
+        let hits: Vec<usize> = report.channels.iter().map(|channel| channel.hits).collect();
+        assert_eq!(hits, [2, 1, 0]);
+        assert_eq!(report.channels[0].peak_dbm, Some(-40.));
+        assert_eq!(report.missing_channels, [Frequency::from_mhz(105)]);
+        assert!(report.unexpected.is_empty());
+        assert_eq!(report.sweep_count, 3);
+        assert!(!report.is_pass());
+    }
+
+    #[test]
+    fn report_unexpected_emissions() {
+        let mut tally = new_tally(Frequency::from_khz(100));
+        tally.add_sweep(&sweep(&[(10, -40.), (20, -60.), (30, -40.), (50, -40.)]));
+        tally.add_sweep(&sweep(&[(21, -55.)]));
+        // Signals below the threshold are ignored
+        tally.add_sweep(&sweep(&[(40, -80.)]));
+        let report = tally.into_report();
+
+        assert!(report.missing_channels.is_empty());
+        assert_eq!(
+            report.unexpected,
+            [UnexpectedEmission {
+                freq: Frequency::from_khz(102_100),
+                hits: 2,
+                peak_dbm: -55.,
+            }]
+        );
+    }
+
+    #[test]
+    fn merge_peaks_within_rbw() {
+        // With a 600 kHz RBW, a signal on 103 MHz spreads over the neighboring points
+        let mut tally = new_tally(Frequency::from_khz(600));
+        tally.add_sweep(&sweep(&[
+            (28, -62.),
+            (29, -50.),
+            (30, -40.),
+            (31, -52.),
+            (33, -65.),
+        ]));
+        let report = tally.into_report();
+        assert_eq!(report.channels[1].hits, 1);
+        assert!(report.unexpected.is_empty());
+
+        // With a 100 kHz RBW the same sweep has a separate signal 300 kHz above the channel
+        let mut tally = new_tally(Frequency::from_khz(100));
+        tally.add_sweep(&sweep(&[
+            (28, -62.),
+            (29, -50.),
+            (30, -40.),
+            (31, -52.),
+            (33, -65.),
+        ]));
+        let report = tally.into_report();
+        assert_eq!(report.channels[1].hits, 1);
+        assert_eq!(report.unexpected.len(), 1);
+        assert_eq!(report.unexpected[0].freq, Frequency::from_khz(103_300));
+    }
+
+    #[test]
+    fn count_several_channels_in_one_sweep() {
+        // A transmitter can hop several times while the RF Explorer is sweeping
+        let mut tally = new_tally(Frequency::from_khz(100));
+        tally.add_sweep(&sweep(&[(10, -40.), (30, -40.), (50, -40.)]));
+        let report = tally.into_report();
+        assert!(report.is_pass());
+        assert!(report.channels.iter().all(|channel| channel.hits == 1));
+    }
+
+    #[test]
+    fn cover_channels_with_margin() {
+        let channels = [105, 101, 103].map(Frequency::from_mhz);
+        assert_eq!(
+            channel_span(&channels, Frequency::from_khz(112)),
+            (Frequency::from_mhz(100), Frequency::from_mhz(106))
+        );
+        // A single channel is centered in the minimum span
+        assert_eq!(
+            channel_span(&[Frequency::from_mhz(915)], Frequency::from_mhz(2)),
+            (Frequency::from_mhz(914), Frequency::from_mhz(916))
+        );
+    }
+}
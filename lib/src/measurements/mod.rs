@@ -0,0 +1,14 @@
+mod hop_monitor;
+mod quick_scan;
+mod tracking;
+mod two_tone;
+
+pub use hop_monitor::{
+    ChannelActivity, HopReport, UnexpectedEmission, hop_monitor, hop_monitor_blocking,
+};
+pub use quick_scan::{measure, measure_blocking};
+pub use tracking::{TrackingResult, TrackingStepPlan};
+pub use two_tone::{
+    InterceptPoint, IntermodProduct, ProductMeasurement, ToneMeasurement, TwoToneReport,
+    TwoToneSetup, intercept_point_dbm, intermod_products, two_tone, two_tone_blocking,
+};
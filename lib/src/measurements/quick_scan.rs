@@ -0,0 +1,223 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    Error, Frequency, Result, SpectrumAnalyzer,
+    task::{self, TaskContext, TaskHandle},
+};
+
+/// How long to wait for each sweep before giving up on the spectrum analyzer.
+const SWEEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Measures the strongest amplitude at each frequency from `start` to `stop` over `duration` on a
+/// background thread.
+///
+/// The spectrum analyzer is disconnected when the task finishes or is canceled. See
+/// [`measure_blocking`] for the details and errors of the measurement.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use rfe::Frequency;
+///
+/// let task = rfe::measure(
+///     Frequency::from_mhz(88),
+///     Frequency::from_mhz(108),
+///     Duration::from_secs(5),
+/// );
+/// task.set_progress_callback(|progress| println!("{:.0}%", progress * 100.));
+/// let points = task.wait(Duration::from_secs(30))?;
+/// # Ok::<(), rfe::Error>(())
+/// ```
+pub fn measure(
+    start: impl Into<Frequency>,
+    stop: impl Into<Frequency>,
+    duration: Duration,
+) -> TaskHandle<Vec<(Frequency, f32)>> {
+    let (start, stop) = (start.into(), stop.into());
+    TaskHandle::spawn(
+        move |context| measure_range(start, stop, duration, context),
+        || Ok(()),
+    )
+}
+
+/// Measures the strongest amplitude at each frequency from `start` to `stop` over `duration`.
+///
+/// Connects to the first available spectrum analyzer, sweeps the range while holding the maximum
+/// amplitude of each point, then disconnects. Ranges wider than the spectrum analyzer's maximum
+/// span are split into equal segments that are each swept for an equal share of `duration` and
+/// stitched together. Each segment is swept at least once, even if `duration` is very short.
+///
+/// Returns [`Error::NoDeviceFound`] if no spectrum analyzer is connected, [`Error::InvalidInput`]
+/// if the range isn't within the spectrum analyzer's frequency range, and [`Error::TimedOut`] if
+/// it stops sending sweeps.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use rfe::Frequency;
+///
+/// let points = rfe::measure_blocking(
+///     Frequency::from_mhz(88),
+///     Frequency::from_mhz(108),
+///     Duration::from_secs(5),
+/// )?;
+/// for (freq, amp_dbm) in points {
+///     println!("{} MHz: {amp_dbm} dBm", freq.as_mhz_f64());
+/// }
+/// # Ok::<(), rfe::Error>(())
+/// ```
+pub fn measure_blocking(
+    start: impl Into<Frequency>,
+    stop: impl Into<Frequency>,
+    duration: Duration,
+) -> Result<Vec<(Frequency, f32)>> {
+    let (start, stop) = (start.into(), stop.into());
+    task::run_blocking(
+        |context| measure_range(start, stop, duration, context),
+        || Ok(()),
+    )
+}
+
+fn measure_range(
+    start: Frequency,
+    stop: Frequency,
+    duration: Duration,
+    context: &TaskContext,
+) -> Result<Vec<(Frequency, f32)>> {
+    if start >= stop {
+        return Err(Error::InvalidInput(
+            "The start frequency must be less than the stop frequency".to_string(),
+        ));
+    }
+
+    let rfe = SpectrumAnalyzer::connect().ok_or(Error::NoDeviceFound)?;
+    let capabilities = rfe.capabilities();
+    let freq_range = capabilities.freq_range;
+    if !freq_range.contains(&start) || !freq_range.contains(&stop) {
+        return Err(Error::InvalidInput(format!(
+            "{}-{} MHz is not within the {} spectrum analyzer's frequency range of {}-{} MHz",
+            start.as_mhz_f64(),
+            stop.as_mhz_f64(),
+            capabilities.active_radio_model,
+            freq_range.start().as_mhz_f64(),
+            freq_range.end().as_mhz_f64()
+        )));
+    }
+
+    let segments = segments(start, stop, *capabilities.span_range.end());
+    let segment_count = segments.len();
+    let segment_duration = duration / segment_count as u32;
+    let mut points: Vec<(Frequency, f32)> = Vec::new();
+    for (i, (segment_start, segment_stop)) in segments.into_iter().enumerate() {
+        let segment_points = measure_max_hold(
+            &rfe,
+            segment_start,
+            segment_stop,
+            segment_duration,
+            context,
+            |segment_progress| {
+                context.report_progress((i as f32 + segment_progress) / segment_count as f32);
+            },
+        )?;
+        // Neighboring segments share a frequency, so skip the repeated point
+        let skip = match (points.last(), segment_points.first()) {
+            (Some(last), Some(first)) if last.0 == first.0 => 1,
+            _ => 0,
+        };
+        points.extend(segment_points.into_iter().skip(skip));
+    }
+    Ok(points)
+}
+
+/// Splits the range into the fewest equal segments that are no wider than `max_span`.
+fn segments(start: Frequency, stop: Frequency, max_span: Frequency) -> Vec<(Frequency, Frequency)> {
+    let span = stop - start;
+    let len = span.as_hz().div_ceil(max_span.as_hz().max(1)).max(1);
+    (0..len)
+        .map(|i| {
+            (
+                start + span * i / len,
+                // Use the exact stop frequency for the last segment so rounding can't move it
+                if i + 1 == len {
+                    stop
+                } else {
+                    start + span * (i + 1) / len
+                },
+            )
+        })
+        .collect()
+}
+
+/// Sweeps from `start` to `stop` for `duration` and returns the maximum amplitude of each point.
+fn measure_max_hold(
+    rfe: &SpectrumAnalyzer,
+    start: Frequency,
+    stop: Frequency,
+    duration: Duration,
+    context: &TaskContext,
+    report_progress: impl Fn(f32),
+) -> Result<Vec<(Frequency, f32)>> {
+    rfe.set_start_stop(start, stop)?;
+    // The first sweep after retuning may have been measured while the RF Explorer was settling
+    rfe.wait_for_sweep_matching_current_config(SWEEP_TIMEOUT)?;
+
+    let started_at = Instant::now();
+    let mut max_hold = rfe.wait_for_sweep_matching_current_config(SWEEP_TIMEOUT)?;
+    while started_at.elapsed() < duration {
+        context.check_canceled()?;
+        let sweep = rfe.wait_for_sweep_matching_current_config(SWEEP_TIMEOUT)?;
+        hold_max(&mut max_hold, &sweep);
+        report_progress((started_at.elapsed().as_secs_f32() / duration.as_secs_f32()).min(1.));
+    }
+    report_progress(1.);
+
+    let (start, step_size) = (rfe.start_freq(), rfe.step_size());
+    Ok(max_hold
+        .into_iter()
+        .enumerate()
+        .map(|(i, amp_dbm)| (start + step_size * i as u64, amp_dbm))
+        .collect())
+}
+
+fn hold_max(max_hold: &mut [f32], sweep: &[f32]) {
+    for (max_amp_dbm, amp_dbm) in max_hold.iter_mut().zip(sweep) {
+        *max_amp_dbm = max_amp_dbm.max(*amp_dbm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_wide_ranges_into_segments() {
+        let mhz = Frequency::from_mhz;
+        assert_eq!(
+            segments(mhz(100), mhz(200), mhz(600)),
+            [(mhz(100), mhz(200))]
+        );
+        assert_eq!(
+            segments(mhz(0), mhz(1500), mhz(600)),
+            [
+                (mhz(0), mhz(500)),
+                (mhz(500), mhz(1000)),
+                (mhz(1000), mhz(1500))
+            ]
+        );
+        assert_eq!(
+            segments(mhz(15), mhz(2700), mhz(600)).last(),
+            Some(&(mhz(2163), mhz(2700)))
+        );
+    }
+
+    #[test]
+    fn hold_maximum_amplitudes() {
+        let mut max_hold = vec![-100., -50., -80.];
+        hold_max(&mut max_hold, &[-90., -60., -80.5]);
+        assert_eq!(max_hold, [-90., -50., -80.]);
+    }
+}
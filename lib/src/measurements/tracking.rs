@@ -0,0 +1,420 @@
+use std::{
+    io::{self, Write},
+    ops::RangeInclusive,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::{Error, Frequency, Result, signal_generator, spectrum_analyzer};
+
+/// The frequencies a tracking measurement steps through.
+///
+/// These are the start and step frequencies passed to
+/// [`SpectrumAnalyzer::request_tracking`](crate::SpectrumAnalyzer::request_tracking) and the
+/// number of times [`SpectrumAnalyzer::tracking_step`](crate::SpectrumAnalyzer::tracking_step) is
+/// called.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TrackingStepPlan {
+    /// The frequency of the first step.
+    pub start: Frequency,
+    /// The frequency between steps.
+    pub step: Frequency,
+    /// The number of steps, including the first one at `start`, so the last step is at
+    /// `start + step * (steps - 1)`.
+    pub steps: u16,
+}
+
+impl TrackingStepPlan {
+    /// Returns the frequency of each step.
+    pub fn freqs(&self) -> impl Iterator<Item = Frequency> + '_ {
+        (0..u64::from(self.steps)).map(|step| self.start + self.step * step)
+    }
+
+    /// Checks that the plan's start frequency and the frequency of its last step, the last one
+    /// [`TrackingStepPlan::freqs`] returns, are within the spectrum analyzer's and, if it's given, the signal generator's frequency
+    /// ranges.
+    ///
+    /// Returns [`Error::InvalidInput`] naming the device and the bound that was violated, so the
+    /// plan can be rejected before either device is configured instead of the spectrum analyzer
+    /// timing out.
+    pub fn validate(
+        &self,
+        analyzer: &spectrum_analyzer::Capabilities,
+        generator: Option<&signal_generator::Capabilities>,
+    ) -> Result<()> {
+        let stop = self
+            .step
+            .as_hz()
+            .checked_mul(u64::from(self.steps.saturating_sub(1)))
+            .and_then(|span_hz| self.start.as_hz().checked_add(span_hz))
+            .map(Frequency::from_hz)
+            .ok_or_else(|| {
+                Error::InvalidInput(
+                    "The tracking stop frequency exceeds the maximum representable frequency"
+                        .to_string(),
+                )
+            })?;
+
+        let analyzer_name = format!("{} spectrum analyzer", analyzer.active_radio_model);
+        check_freq_range(&analyzer_name, &analyzer.freq_range, self.start, stop)?;
+        if let Some(generator) = generator {
+            let generator_name = format!("{} signal generator", generator.active_radio_model);
+            check_freq_range(&generator_name, &generator.freq_range, self.start, stop)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_freq_range(
+    device: &str,
+    freq_range: &RangeInclusive<Frequency>,
+    start: Frequency,
+    stop: Frequency,
+) -> Result<()> {
+    for (name, freq) in [("start", start), ("stop", stop)] {
+        if freq < *freq_range.start() {
+            return Err(Error::InvalidInput(format!(
+                "The tracking {name} frequency {} MHz is below the {device}'s minimum frequency \
+                 of {} MHz",
+                freq.as_mhz_f64(),
+                freq_range.start().as_mhz_f64()
+            )));
+        } else if freq > *freq_range.end() {
+            return Err(Error::InvalidInput(format!(
+                "The tracking {name} frequency {} MHz is above the {device}'s maximum frequency \
+                 of {} MHz",
+                freq.as_mhz_f64(),
+                freq_range.end().as_mhz_f64()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The transmission magnitude, or S21, of a device under test measured in analyzer tracking mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackingResult {
+    /// The frequencies the measurement stepped through.
+    pub plan: TrackingStepPlan,
+    /// The gain of the device under test at each step in dB, relative to the through calibration.
+    pub s21_db: Vec<f32>,
+    /// A description of the spectrum analyzer and signal generator, like their models and serial
+    /// numbers.
+    pub device_info: Option<String>,
+    /// When the through calibration the measurement is relative to was made.
+    pub calibrated_at: Option<DateTime<Utc>>,
+}
+
+impl TrackingResult {
+    /// The magnitude written for the S-parameters tracking mode can't measure.
+    pub const UNMEASURED_DB: f32 = -200.;
+
+    /// Normalizes the amplitudes measured at each step against the amplitudes measured with the
+    /// device under test replaced by a through connection.
+    pub fn normalized(
+        plan: TrackingStepPlan,
+        measured_dbm: &[f32],
+        through_dbm: &[f32],
+    ) -> Result<Self> {
+        let steps = usize::from(plan.steps);
+        if measured_dbm.len() != steps || through_dbm.len() != steps {
+            return Err(Error::InvalidInput(format!(
+                "The plan has {steps} steps but {} measured and {} through amplitudes were given",
+                measured_dbm.len(),
+                through_dbm.len()
+            )));
+        }
+
+        Ok(TrackingResult {
+            plan,
+            s21_db: measured_dbm
+                .iter()
+                .zip(through_dbm)
+                .map(|(measured, through)| measured - through)
+                .collect(),
+            device_info: None,
+            calibrated_at: None,
+        })
+    }
+
+    /// Writes the result as a Touchstone version 1 `.s2p` file in Hz with magnitudes in dB.
+    ///
+    /// Tracking mode only measures the magnitude of S21, so the file is only an approximation of
+    /// the device under test and its comments say so:
+    ///
+    /// - Every angle is 0°.
+    /// - S12 is the same as S21, which is only true for reciprocal devices like passive filters.
+    /// - S11 and S22 are [`TrackingResult::UNMEASURED_DB`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// use rfe::{Frequency, measurements::{TrackingResult, TrackingStepPlan}};
+    ///
+    /// let plan = TrackingStepPlan {
+    ///     start: Frequency::from_mhz(900),
+    ///     step: Frequency::from_mhz(1),
+    ///     steps: 3,
+    /// };
+    /// let result = TrackingResult::normalized(plan, &[-31., -25., -32.], &[-20., -20., -20.])?;
+    /// result.write_s2p(File::create("filter.s2p")?)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn write_s2p<W: Write>(&self, mut writer: W) -> Result<()> {
+        if self.s21_db.len() != usize::from(self.plan.steps) {
+            return Err(Error::InvalidInput(format!(
+                "The plan has {} steps but the result has {} magnitudes",
+                self.plan.steps,
+                self.s21_db.len()
+            )));
+        }
+        if self.plan.steps > 1 && self.plan.step == Frequency::default() {
+            return Err(Error::InvalidInput(
+                "Touchstone frequencies must increase, so the step can't be 0 Hz".to_string(),
+            ));
+        }
+
+        self.write_s2p_lines(&mut writer)?;
+        Ok(writer.flush()?)
+    }
+
+    fn write_s2p_lines(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "! Measured in RF Explorer analyzer tracking mode and written by rfe {}",
+            env!("CARGO_PKG_VERSION")
+        )?;
+        if let Some(device_info) = &self.device_info {
+            for line in device_info.lines() {
+                writeln!(writer, "! Device: {line}")?;
+            }
+        }
+        match self.calibrated_at {
+            Some(calibrated_at) => writeln!(
+                writer,
+                "! Calibrated: {}",
+                calibrated_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+            )?,
+            None => writeln!(writer, "! Calibrated: unknown")?,
+        }
+        writeln!(
+            writer,
+            "! Tracking mode only measures the magnitude of S21, so every angle is 0 and isn't a \
+             measured phase."
+        )?;
+        writeln!(
+            writer,
+            "! S12 is a copy of S21, which assumes the device under test is reciprocal. S11 and \
+             S22 weren't measured and are {} dB.",
+            Self::UNMEASURED_DB
+        )?;
+        writeln!(writer, "# HZ S DB R 50")?;
+        writeln!(
+            writer,
+            "! freq S11_dB S11_ang S21_dB S21_ang S12_dB S12_ang S22_dB S22_ang"
+        )?;
+
+        let unmeasured = Self::UNMEASURED_DB;
+        for (freq, s21) in self.plan.freqs().zip(&self.s21_db) {
+            writeln!(
+                writer,
+                "{} {unmeasured:.3} 0 {s21:.3} 0 {s21:.3} 0 {unmeasured:.3} 0",
+                freq.as_hz()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// The parts of a Touchstone version 1 file needed to check what `write_s2p` writes.
+    #[derive(Debug, PartialEq)]
+    struct Touchstone {
+        comments: Vec<String>,
+        option_line: Vec<String>,
+        /// Each frequency in Hz and its 8 values.
+        points: Vec<(f64, Vec<f64>)>,
+    }
+
+    /// Parses a 2-port Touchstone version 1 file, following the rules of the specification that
+    /// apply to files with one point per line.
+    fn parse_touchstone_s2p(contents: &str) -> std::result::Result<Touchstone, String> {
+        let mut touchstone = Touchstone {
+            comments: Vec::new(),
+            option_line: Vec::new(),
+            points: Vec::new(),
+        };
+        for line in contents.lines() {
+            // Everything after a ! is a comment
+            let (data, comment) = line.split_once('!').unwrap_or((line, ""));
+            if line.contains('!') {
+                touchstone.comments.push(comment.trim().to_string());
+            }
+            let data = data.trim();
+            if data.is_empty() {
+                continue;
+            }
+            if let Some(options) = data.strip_prefix('#') {
+                if !touchstone.option_line.is_empty() || !touchstone.points.is_empty() {
+                    return Err("The option line must come once, before the data".to_string());
+                }
+                touchstone.option_line =
+                    options.split_whitespace().map(str::to_uppercase).collect();
+                continue;
+            }
+            if touchstone.option_line.is_empty() {
+                return Err("Data came before the option line".to_string());
+            }
+            let values = data
+                .split_whitespace()
+                .map(|value| value.parse::<f64>().map_err(|err| err.to_string()))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            if values.len() != 9 {
+                return Err(format!("A 2-port line has 9 values, not {}", values.len()));
+            }
+            if touchstone
+                .points
+                .last()
+                .is_some_and(|(freq, _)| *freq >= values[0])
+            {
+                return Err("Frequencies must increase".to_string());
+            }
+            touchstone.points.push((values[0], values[1..].to_vec()));
+        }
+        Ok(touchstone)
+    }
+
+    fn result() -> TrackingResult {
+        let plan = TrackingStepPlan {
+            start: Frequency::from_mhz(900),
+            step: Frequency::from_khz(500),
+            steps: 3,
+        };
+        TrackingResult {
+            device_info: Some("RF Explorer WSUB3G (B3AK7AL7CACAA74M)".to_string()),
+            calibrated_at: Some(Utc.with_ymd_and_hms(2026, 3, 14, 9, 30, 0).unwrap()),
+            ..TrackingResult::normalized(plan, &[-31.5, -21., -40.25], &[-20., -20., -20.]).unwrap()
+        }
+    }
+
+    #[test]
+    fn write_valid_s2p() {
+        let mut s2p = Vec::new();
+        result().write_s2p(&mut s2p).unwrap();
+        let touchstone = parse_touchstone_s2p(&String::from_utf8(s2p).unwrap()).unwrap();
+
+        assert_eq!(touchstone.option_line, ["HZ", "S", "DB", "R", "50"]);
+        let unmeasured = f64::from(TrackingResult::UNMEASURED_DB);
+        assert_eq!(
+            touchstone.points,
+            [
+                (
+                    900e6,
+                    vec![unmeasured, 0., -11.5, 0., -11.5, 0., unmeasured, 0.]
+                ),
+                (
+                    900.5e6,
+                    vec![unmeasured, 0., -1., 0., -1., 0., unmeasured, 0.]
+                ),
+                (
+                    901e6,
+                    vec![unmeasured, 0., -20.25, 0., -20.25, 0., unmeasured, 0.]
+                ),
+            ]
+        );
+        assert!(
+            touchstone
+                .comments
+                .contains(&"Device: RF Explorer WSUB3G (B3AK7AL7CACAA74M)".to_string())
+        );
+        assert!(
+            touchstone
+                .comments
+                .contains(&"Calibrated: 2026-03-14T09:30:00Z".to_string())
+        );
+        assert!(
+            touchstone
+                .comments
+                .iter()
+                .any(|comment| comment.contains("every angle is 0"))
+        );
+    }
+
+    fn plan(start_mhz: u64, step_mhz: u64, steps: u16) -> TrackingStepPlan {
+        TrackingStepPlan {
+            start: Frequency::from_mhz(start_mhz),
+            step: Frequency::from_mhz(step_mhz),
+            steps,
+        }
+    }
+
+    fn assert_invalid(result: Result<()>, expected: &str) {
+        match result {
+            Err(Error::InvalidInput(message)) => assert!(
+                message.contains(expected),
+                "{message:?} should contain {expected:?}"
+            ),
+            result => panic!("Expected Error::InvalidInput, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn accept_plans_at_the_edges_of_both_ranges() {
+        let analyzer = spectrum_analyzer::Capabilities::new(spectrum_analyzer::Model::Rfe6G, None);
+        let generator =
+            signal_generator::Capabilities::new(signal_generator::Model::Rfe6GenExpansion, None);
+        // The 6G analyzer covers 4850-6100 MHz and the generator covers 0.1-6000 MHz
+        let edges = plan(4_850, 10, 116);
+        assert_eq!(edges.freqs().last(), Some(Frequency::from_mhz(6_000)));
+        assert!(edges.validate(&analyzer, Some(&generator)).is_ok());
+        assert!(plan(4_850, 25, 51).validate(&analyzer, None).is_ok());
+        assert!(plan(4_850, 10, 0).validate(&analyzer, None).is_ok());
+    }
+
+    #[test]
+    fn reject_plans_outside_either_range() {
+        let analyzer = spectrum_analyzer::Capabilities::new(spectrum_analyzer::Model::Rfe6G, None);
+        let generator = signal_generator::Capabilities::new(signal_generator::Model::Rfe6Gen, None);
+
+        assert_invalid(
+            plan(4_849, 1, 10).validate(&analyzer, Some(&generator)),
+            "start frequency 4849 MHz is below the 6G spectrum analyzer's minimum",
+        );
+        // The stop is within the analyzer's range but not the generator's
+        assert_invalid(
+            plan(4_850, 10, 117).validate(&analyzer, Some(&generator)),
+            "stop frequency 6010 MHz is above the 6Gen signal generator's maximum",
+        );
+        assert_invalid(
+            plan(4_850, 25, 52).validate(&analyzer, None),
+            "stop frequency 6125 MHz is above the 6G spectrum analyzer's maximum",
+        );
+        assert_invalid(
+            TrackingStepPlan {
+                start: Frequency::from_mhz(5_000),
+                step: Frequency::from_hz(u64::MAX / 2),
+                steps: 3,
+            }
+            .validate(&analyzer, None),
+            "maximum representable frequency",
+        );
+    }
+
+    #[test]
+    fn refuse_to_write_mismatched_result() {
+        let mut result = result();
+        result.s21_db.pop();
+        assert!(matches!(
+            result.write_s2p(Vec::new()),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(TrackingResult::normalized(result.plan, &[-30.], &[-20.]).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    Error, Frequency, Result, SpectrumAnalyzer,
+    task::{self, TaskContext, TaskHandle},
+};
+
+/// An intermodulation product of two tones.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IntermodProduct {
+    /// The order of the product, like 3 for third-order products.
+    pub order: u8,
+    /// The multiples of the first and second tones' frequencies that add up to the product's
+    /// frequency, like `(2, -1)` for `2f1 - f2`.
+    pub coefficients: (i8, i8),
+    /// The frequency of the product.
+    pub freq: Frequency,
+}
+
+/// Returns the second-, third-, and fifth-order intermodulation products of two tones.
+///
+/// The second-order products are `f1 + f2` and `f2 - f1`. The third- and fifth-order products
+/// are the ones next to the tones: `2f1 - f2`, `2f2 - f1`, `3f1 - 2f2`, and `3f2 - 2f1`. Products
+/// that would be at or below 0 Hz are left out.
+pub fn intermod_products(f1: Frequency, f2: Frequency) -> Vec<IntermodProduct> {
+    const COEFFICIENTS: [(i8, i8); 6] = [(1, 1), (-1, 1), (2, -1), (-1, 2), (3, -2), (-2, 3)];
+
+    COEFFICIENTS
+        .into_iter()
+        .filter_map(|(c1, c2)| {
+            let hz =
+                i128::from(c1) * i128::from(f1.as_hz()) + i128::from(c2) * i128::from(f2.as_hz());
+            let freq = u64::try_from(hz).ok().filter(|hz| *hz > 0)?;
+            Some(IntermodProduct {
+                order: c1.unsigned_abs() + c2.unsigned_abs(),
+                coefficients: (c1, c2),
+                freq: Frequency::from_hz(freq),
+            })
+        })
+        .collect()
+}
+
+/// Returns the intercept point in dBm of a product of the given order.
+///
+/// `suppression_db` is how far the product is below the tones. Pass the tones' input level to get
+/// the input intercept point or their output level to get the output intercept point.
+pub fn intercept_point_dbm(order: u8, tone_level_dbm: f32, suppression_db: f32) -> f32 {
+    tone_level_dbm + suppression_db / f32::from(order.saturating_sub(1).max(1))
+}
+
+/// The settings of a two-tone measurement.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TwoToneSetup {
+    /// The frequencies of the two tones.
+    pub tone_freqs: (Frequency, Frequency),
+    /// The levels of the two tones going into the device under test, if known.
+    ///
+    /// Intercept points are only calculated when the input levels are known.
+    pub input_levels_dbm: Option<(f32, f32)>,
+    /// The span measured around each tone and product.
+    pub span: Frequency,
+    /// How long to wait for each retune and sweep.
+    pub timeout: Duration,
+}
+
+impl TwoToneSetup {
+    /// Creates a setup for the two tones that measures half the tones' spacing around each
+    /// frequency, so neighboring products are never in the same sweep.
+    pub fn new(f1: Frequency, f2: Frequency) -> Self {
+        TwoToneSetup {
+            tone_freqs: (f1, f2),
+            input_levels_dbm: None,
+            span: f1.abs_diff(f2) / 2,
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// The strongest signal measured around a frequency.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ToneMeasurement {
+    /// The frequency the spectrum analyzer was centered on.
+    pub freq: Frequency,
+    /// The frequency of the strongest point in the sweep.
+    pub peak_freq: Frequency,
+    /// The amplitude of the strongest point in the sweep.
+    pub peak_dbm: f32,
+}
+
+/// The input and output intercept points of an intermodulation product.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InterceptPoint {
+    /// The input intercept point in dBm.
+    pub input_dbm: f32,
+    /// The output intercept point in dBm.
+    pub output_dbm: f32,
+}
+
+/// The measured level of an intermodulation product.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ProductMeasurement {
+    /// The product that was measured.
+    pub product: IntermodProduct,
+    /// The strongest signal measured around the product's frequency.
+    pub measurement: ToneMeasurement,
+    /// How far the product is below the average level of the two tones, in dB.
+    pub suppression_db: f32,
+    /// The intercept points, if the input levels of the tones were supplied.
+    pub intercept: Option<InterceptPoint>,
+}
+
+/// The result of a two-tone measurement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwoToneReport {
+    /// The measured levels of the two tones.
+    pub tones: (ToneMeasurement, ToneMeasurement),
+    /// The measured levels of the products within the spectrum analyzer's frequency range.
+    pub products: Vec<ProductMeasurement>,
+}
+
+impl TwoToneReport {
+    /// Returns the lowest intercept point of the products of the given order, like 3 for IP3.
+    ///
+    /// Returns `None` if no products of that order were measured or if the input levels of the
+    /// tones weren't supplied.
+    pub fn intercept_point(&self, order: u8) -> Option<InterceptPoint> {
+        self.products
+            .iter()
+            .filter(|product| product.product.order == order)
+            .filter_map(|product| product.intercept)
+            .min_by(|a, b| a.input_dbm.total_cmp(&b.input_dbm))
+    }
+}
+
+/// Measures the levels of two tones and their intermodulation products on a background thread.
+///
+/// The spectrum analyzer's original start and stop frequencies are restored when the task
+/// finishes, fails, or is canceled. See [`two_tone_blocking`] for the details of the measurement.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{sync::Arc, time::Duration};
+///
+/// use rfe::{Frequency, SpectrumAnalyzer, measurements::{self, TwoToneSetup}};
+///
+/// let rfe = Arc::new(SpectrumAnalyzer::connect().expect("RF Explorer should be connected"));
+/// let setup = TwoToneSetup::new(Frequency::from_mhz(915), Frequency::from_mhz(916));
+/// let task = measurements::two_tone(rfe, &setup);
+/// let report = task.wait(Duration::from_secs(60))?;
+/// # Ok::<(), rfe::Error>(())
+/// ```
+pub fn two_tone(rfe: Arc<SpectrumAnalyzer>, setup: &TwoToneSetup) -> TaskHandle<TwoToneReport> {
+    let setup = *setup;
+    let (start, stop) = (rfe.start_freq(), rfe.stop_freq());
+    let cleanup_rfe = rfe.clone();
+    TaskHandle::spawn(
+        move |context| {
+            check_tone_freqs(&setup)?;
+            measure_two_tone(&rfe, &setup, context)
+        },
+        move || cleanup_rfe.set_start_stop(start, stop),
+    )
+}
+
+/// Measures the levels of two tones and their intermodulation products.
+///
+/// The spectrum analyzer is centered on each tone and each product from [`intermod_products`]
+/// that it can tune to, in turn, using the setup's span. The first sweep after each retune is
+/// skipped to let the measurement settle, and the strongest point of the next sweep is measured.
+/// The spectrum analyzer's original start and stop frequencies are restored afterwards.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rfe::{Frequency, SpectrumAnalyzer, measurements::{self, TwoToneSetup}};
+///
+/// let rfe = SpectrumAnalyzer::connect().expect("RF Explorer should be connected");
+/// let setup = TwoToneSetup {
+///     input_levels_dbm: Some((-20., -20.)),
+///     ..TwoToneSetup::new(Frequency::from_mhz(915), Frequency::from_mhz(916))
+/// };
+/// let report = measurements::two_tone_blocking(&rfe, &setup)?;
+/// if let Some(ip3) = report.intercept_point(3) {
+///     println!("IIP3: {} dBm, OIP3: {} dBm", ip3.input_dbm, ip3.output_dbm);
+/// }
+/// # Ok::<(), rfe::Error>(())
+/// ```
+pub fn two_tone_blocking(rfe: &SpectrumAnalyzer, setup: &TwoToneSetup) -> Result<TwoToneReport> {
+    check_tone_freqs(setup)?;
+    let (start, stop) = (rfe.start_freq(), rfe.stop_freq());
+    task::run_blocking(
+        |context| measure_two_tone(rfe, setup, context),
+        || rfe.set_start_stop(start, stop),
+    )
+}
+
+fn check_tone_freqs(setup: &TwoToneSetup) -> Result<()> {
+    let (f1, f2) = setup.tone_freqs;
+    if f1 == f2 {
+        return Err(Error::InvalidInput(
+            "The two tones must have different frequencies".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn measure_two_tone(
+    rfe: &SpectrumAnalyzer,
+    setup: &TwoToneSetup,
+    context: &TaskContext,
+) -> Result<TwoToneReport> {
+    let (f1, f2) = setup.tone_freqs;
+    let half_span = setup.span / 2;
+    let products: Vec<_> = intermod_products(f1, f2)
+        .into_iter()
+        .filter(|product| {
+            product.freq >= rfe.min_freq() + half_span && product.freq + half_span <= rfe.max_freq()
+        })
+        .collect();
+    // The spectrum analyzer is retuned once for each tone and product
+    let measurement_count = (products.len() + 2) as f32;
+    let mut measured = 0;
+    let mut measure_next = |freq| {
+        context.check_canceled()?;
+        let measurement = measure_peak(rfe, freq, setup)?;
+        measured += 1;
+        context.report_progress(measured as f32 / measurement_count);
+        Ok::<_, Error>(measurement)
+    };
+
+    let tones = (measure_next(f1)?, measure_next(f2)?);
+    let tone_level_dbm = (tones.0.peak_dbm + tones.1.peak_dbm) / 2.;
+    let input_level_dbm = setup
+        .input_levels_dbm
+        .map(|(level1, level2)| (level1 + level2) / 2.);
+
+    let products = products
+        .into_iter()
+        .map(|product| {
+            let measurement = measure_next(product.freq)?;
+            let suppression_db = tone_level_dbm - measurement.peak_dbm;
+            Ok(ProductMeasurement {
+                product,
+                measurement,
+                suppression_db,
+                intercept: input_level_dbm.map(|input_level_dbm| InterceptPoint {
+                    input_dbm: intercept_point_dbm(product.order, input_level_dbm, suppression_db),
+                    output_dbm: intercept_point_dbm(product.order, tone_level_dbm, suppression_db),
+                }),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(TwoToneReport { tones, products })
+}
+
+/// Centers the spectrum analyzer on `freq` and measures the strongest point of a settled sweep.
+fn measure_peak(
+    rfe: &SpectrumAnalyzer,
+    freq: Frequency,
+    setup: &TwoToneSetup,
+) -> Result<ToneMeasurement> {
+    rfe.set_center_span(freq, setup.span)?;
+    // The first sweep after retuning may have been measured while the RF Explorer was settling
+    rfe.wait_for_sweep_matching_current_config(setup.timeout)?;
+    let sweep = rfe.wait_for_sweep_matching_current_config(setup.timeout)?;
+
+    let (peak, peak_dbm) = sweep
+        .iter()
+        .copied()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .ok_or_else(|| {
+            Error::InvalidOperation("The spectrum analyzer sent an empty sweep".to_string())
+        })?;
+    Ok(ToneMeasurement {
+        freq,
+        peak_freq: rfe.start_freq() + rfe.step_size() * peak as u64,
+        peak_dbm,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compute_intermod_products() {
+        let products = intermod_products(Frequency::from_mhz(100), Frequency::from_mhz(101));
+        let freqs: Vec<(u8, u64)> = products
+            .iter()
+            .map(|product| (product.order, product.freq.as_mhz()))
+            .collect();
+        assert_eq!(
+            freqs,
+            [(2, 201), (2, 1), (3, 99), (3, 102), (5, 98), (5, 103)]
+        );
+        assert_eq!(products[2].coefficients, (2, -1));
+    }
+
+    #[test]
+    fn skip_products_below_zero_hz() {
+        // 2f1 - f2 and 3f1 - 2f2 are below 0 Hz when the tones are far apart
+        let products = intermod_products(Frequency::from_mhz(100), Frequency::from_mhz(300));
+        let freqs: Vec<u64> = products
+            .iter()
+            .map(|product| product.freq.as_mhz())
+            .collect();
+        assert_eq!(freqs, [400, 200, 500, 700]);
+    }
+
+    #[test]
+    fn compute_intercept_points() {
+        // Tones at -10 dBm with third-order products 60 dB below them intercept at +20 dBm
+        assert_eq!(intercept_point_dbm(3, -10., 60.), 20.);
+        // Second-order products rise 2 dB for each dB the tones rise, so they intercept at the
+        // suppression above the tones
+        assert_eq!(intercept_point_dbm(2, -10., 40.), 30.);
+        assert_eq!(intercept_point_dbm(5, 0., 80.), 20.);
+    }
+
+    #[test]
+    fn pick_lowest_intercept_point_of_order() {
+        let measurement = |freq| ToneMeasurement {
+            freq,
+            peak_freq: freq,
+            peak_dbm: -70.,
+        };
+        let products: Vec<ProductMeasurement> =
+            intermod_products(Frequency::from_mhz(100), Frequency::from_mhz(101))
+                .into_iter()
+                .map(|product| ProductMeasurement {
+                    product,
+                    measurement: measurement(product.freq),
+                    suppression_db: 0.,
+                    intercept: Some(InterceptPoint {
+                        input_dbm: product.freq.as_mhz() as f32,
+                        output_dbm: 0.,
+                    }),
+                })
+                .collect();
+        let report = TwoToneReport {
+            tones: (
+                measurement(Frequency::from_mhz(100)),
+                measurement(Frequency::from_mhz(101)),
+            ),
+            products,
+        };
+        assert_eq!(report.intercept_point(3).unwrap().input_dbm, 99.);
+        assert_eq!(report.intercept_point(5).unwrap().input_dbm, 98.);
+        assert_eq!(report.intercept_point(7), None);
+    }
+}
@@ -0,0 +1,571 @@
+//! Color maps for rendering amplitudes, like in a spectrogram.
+//!
+//! Each [`Colormap`] is defined by colors sampled evenly along it and interpolated in the
+//! [Oklab](https://bottosson.github.io/posts/oklab/) color space, so equal steps in value look
+//! like equal steps in color. Interpolating the colors' RGB values instead would make some steps
+//! look larger than others.
+//!
+//! # Examples
+//!
+//! ```
+//! use rfe::render::colormap::{self, Colormap};
+//!
+//! let value = colormap::normalize_dbm(-70., -105., -40., None);
+//! let [r, g, b] = Colormap::Viridis.map(value);
+//! ```
+
+/// A color map that maps values from 0 to 1 to colors.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Colormap {
+    /// Blue to yellow, designed to look the same to people with color vision deficiencies.
+    Cividis,
+    /// Cyan to magenta.
+    Cool,
+    /// Black to white through a helix of hues with increasing lightness.
+    CubeHelix,
+    /// Black to yellow through purple and red.
+    Inferno,
+    /// Black to white through purple and orange.
+    Magma,
+    /// Blue to yellow through purple and orange.
+    Plasma,
+    /// Blue to red through the rainbow, with smoother lightness than a classic rainbow.
+    #[default]
+    Turbo,
+    /// Purple to yellow through blue and green.
+    Viridis,
+    /// Purple to green through red and orange.
+    Warm,
+}
+
+impl Colormap {
+    /// Every color map.
+    pub const ALL: [Colormap; 9] = [
+        Colormap::Cividis,
+        Colormap::Cool,
+        Colormap::CubeHelix,
+        Colormap::Inferno,
+        Colormap::Magma,
+        Colormap::Plasma,
+        Colormap::Turbo,
+        Colormap::Viridis,
+        Colormap::Warm,
+    ];
+
+    /// Returns the RGB color of a value from 0 to 1.
+    ///
+    /// Values outside of 0 to 1 are clamped and `NaN` is mapped like 0.
+    pub fn map(self, value_normalized: f32) -> [u8; 3] {
+        let stops = self.stops();
+        let value = if value_normalized.is_nan() {
+            0.
+        } else {
+            f64::from(value_normalized.clamp(0., 1.))
+        };
+        let position = value * (STOP_COUNT - 1) as f64;
+        let i = (position.floor() as usize).min(STOP_COUNT - 2);
+        let t = position - i as f64;
+        if t == 0. {
+            return stops[i];
+        }
+
+        let (from, to) = (srgb_to_oklab(stops[i]), srgb_to_oklab(stops[i + 1]));
+        oklab_to_srgb([
+            from[0] + (to[0] - from[0]) * t,
+            from[1] + (to[1] - from[1]) * t,
+            from[2] + (to[2] - from[2]) * t,
+        ])
+    }
+
+    fn stops(self) -> &'static [[u8; 3]; STOP_COUNT] {
+        match self {
+            Colormap::Cividis => &CIVIDIS,
+            Colormap::Cool => &COOL,
+            Colormap::CubeHelix => &CUBEHELIX,
+            Colormap::Inferno => &INFERNO,
+            Colormap::Magma => &MAGMA,
+            Colormap::Plasma => &PLASMA,
+            Colormap::Turbo => &TURBO,
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Warm => &WARM,
+        }
+    }
+}
+
+/// Returns where an amplitude falls between `min_dbm` and `max_dbm`, from 0 to 1, to be mapped
+/// with [`Colormap::map`].
+///
+/// Amplitudes outside of the range are clamped to it. A `gamma` below 1 spreads the weaker
+/// amplitudes over more of the color map and a `gamma` above 1 spreads the stronger ones.
+/// Returns 0 if `max_dbm` isn't greater than `min_dbm`.
+pub fn normalize_dbm(amp_dbm: f32, min_dbm: f32, max_dbm: f32, gamma: Option<f32>) -> f32 {
+    if max_dbm <= min_dbm || amp_dbm.is_nan() {
+        return 0.;
+    }
+    let value = ((amp_dbm - min_dbm) / (max_dbm - min_dbm)).clamp(0., 1.);
+    match gamma {
+        Some(gamma) if gamma > 0. => value.powf(gamma),
+        _ => value,
+    }
+}
+
+fn srgb_to_oklab(rgb: [u8; 3]) -> [f64; 3] {
+    let [r, g, b] = rgb.map(|c| srgb_to_linear(f64::from(c) / 255.));
+    let l = (0.412_221_470_8 * r + 0.536_332_536_3 * g + 0.051_445_992_9 * b).cbrt();
+    let m = (0.211_903_498_2 * r + 0.680_699_545_1 * g + 0.107_396_956_6 * b).cbrt();
+    let s = (0.088_302_461_9 * r + 0.281_718_837_6 * g + 0.629_978_700_5 * b).cbrt();
+    [
+        0.210_454_255_3 * l + 0.793_617_785 * m - 0.004_072_046_8 * s,
+        1.977_998_495_1 * l - 2.428_592_205 * m + 0.450_593_709_9 * s,
+        0.025_904_037_1 * l + 0.782_771_766_2 * m - 0.808_675_766 * s,
+    ]
+}
+
+fn oklab_to_srgb(lab: [f64; 3]) -> [u8; 3] {
+    let [lightness, a, b] = lab;
+    let l = (lightness + 0.396_337_777_4 * a + 0.215_803_757_3 * b).powi(3);
+    let m = (lightness - 0.105_561_345_8 * a - 0.063_854_172_8 * b).powi(3);
+    let s = (lightness - 0.089_484_177_5 * a - 1.291_485_548 * b).powi(3);
+    [
+        4.076_741_662_1 * l - 3.307_711_591_3 * m + 0.230_969_929_2 * s,
+        -1.268_438_004_6 * l + 2.609_757_401_1 * m - 0.341_319_396_5 * s,
+        -0.004_196_086_3 * l - 0.703_418_614_7 * m + 1.707_614_701 * s,
+    ]
+    .map(|c| (linear_to_srgb(c) * 255.).round().clamp(0., 255.) as u8)
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// The number of colors sampled along each color map.
+const STOP_COUNT: usize = 33;
+
+const CIVIDIS: [[u8; 3]; STOP_COUNT] = [
+    [0, 32, 81],
+    [0, 37, 92],
+    [1, 43, 100],
+    [8, 48, 105],
+    [17, 54, 108],
+    [27, 60, 109],
+    [38, 65, 109],
+    [49, 71, 109],
+    [60, 77, 109],
+    [70, 82, 109],
+    [80, 88, 109],
+    [89, 94, 109],
+    [98, 100, 110],
+    [106, 105, 111],
+    [113, 111, 113],
+    [120, 117, 115],
+    [127, 123, 116],
+    [133, 129, 118],
+    [140, 135, 119],
+    [146, 141, 120],
+    [153, 148, 120],
+    [161, 154, 119],
+    [169, 161, 118],
+    [177, 167, 116],
+    [186, 174, 112],
+    [196, 181, 108],
+    [206, 188, 103],
+    [216, 195, 97],
+    [226, 203, 91],
+    [235, 210, 85],
+    [243, 218, 78],
+    [249, 225, 73],
+    [253, 233, 69],
+];
+
+const COOL: [[u8; 3]; STOP_COUNT] = [
+    [109, 63, 169],
+    [106, 69, 180],
+    [102, 76, 189],
+    [97, 82, 198],
+    [91, 90, 205],
+    [85, 98, 212],
+    [79, 106, 217],
+    [72, 115, 221],
+    [65, 124, 224],
+    [58, 134, 225],
+    [51, 143, 224],
+    [45, 153, 223],
+    [39, 163, 219],
+    [34, 172, 215],
+    [30, 181, 209],
+    [27, 190, 202],
+    [25, 199, 193],
+    [25, 207, 184],
+    [26, 214, 175],
+    [28, 221, 165],
+    [32, 227, 154],
+    [38, 232, 144],
+    [45, 237, 134],
+    [53, 240, 124],
+    [63, 243, 115],
+    [74, 245, 107],
+    [87, 246, 100],
+    [100, 246, 94],
+    [114, 246, 90],
+    [129, 245, 87],
+    [144, 243, 86],
+    [160, 241, 87],
+    [175, 239, 90],
+];
+
+const CUBEHELIX: [[u8; 3]; STOP_COUNT] = [
+    [0, 0, 0],
+    [12, 4, 13],
+    [20, 10, 29],
+    [25, 19, 44],
+    [26, 29, 59],
+    [25, 41, 70],
+    [22, 55, 76],
+    [21, 69, 78],
+    [21, 82, 75],
+    [25, 95, 70],
+    [34, 105, 62],
+    [48, 113, 54],
+    [66, 119, 48],
+    [88, 121, 46],
+    [112, 122, 49],
+    [136, 122, 58],
+    [160, 121, 73],
+    [180, 120, 92],
+    [195, 121, 116],
+    [206, 125, 141],
+    [211, 131, 167],
+    [212, 140, 190],
+    [209, 151, 210],
+    [204, 164, 226],
+    [198, 178, 236],
+    [194, 193, 242],
+    [193, 207, 243],
+    [195, 220, 242],
+    [202, 230, 239],
+    [212, 239, 238],
+    [225, 245, 240],
+    [240, 250, 245],
+    [255, 255, 255],
+];
+
+const INFERNO: [[u8; 3]; STOP_COUNT] = [
+    [0, 0, 4],
+    [3, 2, 17],
+    [10, 7, 35],
+    [20, 10, 54],
+    [32, 12, 73],
+    [46, 10, 90],
+    [60, 9, 100],
+    [73, 11, 106],
+    [86, 15, 109],
+    [99, 20, 110],
+    [112, 25, 110],
+    [124, 29, 108],
+    [137, 34, 105],
+    [150, 38, 102],
+    [162, 43, 97],
+    [175, 48, 91],
+    [187, 54, 84],
+    [198, 61, 76],
+    [208, 69, 68],
+    [218, 78, 59],
+    [227, 89, 50],
+    [234, 100, 40],
+    [240, 113, 30],
+    [245, 126, 19],
+    [249, 140, 9],
+    [251, 155, 6],
+    [251, 170, 15],
+    [250, 186, 31],
+    [248, 201, 50],
+    [245, 217, 73],
+    [241, 232, 101],
+    [242, 245, 134],
+    [252, 255, 164],
+];
+
+const MAGMA: [[u8; 3]; STOP_COUNT] = [
+    [0, 0, 4],
+    [3, 3, 17],
+    [9, 7, 33],
+    [18, 13, 51],
+    [28, 16, 70],
+    [40, 17, 89],
+    [53, 16, 106],
+    [67, 15, 117],
+    [80, 18, 123],
+    [92, 22, 126],
+    [105, 28, 128],
+    [117, 33, 128],
+    [130, 37, 129],
+    [143, 42, 129],
+    [155, 46, 127],
+    [169, 50, 124],
+    [181, 54, 121],
+    [194, 59, 117],
+    [207, 64, 111],
+    [219, 71, 105],
+    [229, 80, 99],
+    [238, 91, 94],
+    [244, 105, 92],
+    [248, 120, 92],
+    [251, 135, 96],
+    [253, 150, 104],
+    [253, 165, 113],
+    [254, 180, 123],
+    [254, 194, 135],
+    [254, 209, 147],
+    [253, 224, 161],
+    [252, 238, 176],
+    [252, 253, 191],
+];
+
+const PLASMA: [[u8; 3]; STOP_COUNT] = [
+    [13, 8, 135],
+    [33, 5, 143],
+    [48, 5, 150],
+    [63, 3, 156],
+    [75, 2, 161],
+    [88, 0, 164],
+    [101, 0, 167],
+    [113, 0, 167],
+    [125, 3, 168],
+    [137, 8, 165],
+    [148, 16, 161],
+    [159, 25, 156],
+    [169, 34, 149],
+    [178, 43, 142],
+    [187, 52, 135],
+    [195, 61, 127],
+    [203, 70, 120],
+    [210, 79, 113],
+    [217, 88, 106],
+    [223, 98, 99],
+    [229, 107, 92],
+    [234, 117, 85],
+    [239, 127, 78],
+    [244, 137, 71],
+    [247, 148, 64],
+    [250, 159, 57],
+    [252, 171, 51],
+    [253, 183, 44],
+    [253, 195, 39],
+    [251, 208, 36],
+    [248, 221, 36],
+    [244, 235, 38],
+    [240, 249, 33],
+];
+
+const TURBO: [[u8; 3]; STOP_COUNT] = [
+    [34, 23, 27],
+    [61, 41, 113],
+    [73, 62, 174],
+    [74, 83, 214],
+    [68, 105, 238],
+    [59, 127, 248],
+    [50, 148, 247],
+    [42, 169, 239],
+    [37, 188, 225],
+    [37, 205, 207],
+    [41, 220, 187],
+    [49, 232, 167],
+    [63, 242, 147],
+    [80, 249, 127],
+    [101, 252, 110],
+    [124, 253, 94],
+    [148, 250, 80],
+    [173, 244, 69],
+    [196, 235, 59],
+    [218, 223, 51],
+    [236, 208, 45],
+    [250, 191, 40],
+    [255, 172, 36],
+    [255, 151, 32],
+    [255, 130, 29],
+    [252, 107, 25],
+    [239, 86, 21],
+    [222, 65, 17],
+    [202, 46, 12],
+    [182, 30, 7],
+    [163, 18, 2],
+    [149, 12, 0],
+    [144, 12, 0],
+];
+
+const VIRIDIS: [[u8; 3]; STOP_COUNT] = [
+    [68, 1, 84],
+    [70, 12, 95],
+    [72, 24, 106],
+    [72, 34, 115],
+    [71, 44, 122],
+    [69, 54, 129],
+    [66, 63, 133],
+    [62, 72, 136],
+    [58, 81, 139],
+    [54, 90, 140],
+    [50, 98, 141],
+    [47, 106, 141],
+    [44, 113, 142],
+    [41, 121, 141],
+    [38, 129, 142],
+    [35, 136, 141],
+    [32, 144, 140],
+    [31, 151, 138],
+    [31, 159, 136],
+    [33, 166, 132],
+    [39, 173, 128],
+    [49, 181, 122],
+    [61, 187, 115],
+    [76, 194, 107],
+    [92, 200, 98],
+    [110, 206, 87],
+    [129, 211, 76],
+    [149, 215, 63],
+    [170, 219, 49],
+    [192, 223, 36],
+    [213, 226, 25],
+    [233, 228, 26],
+    [253, 231, 37],
+];
+
+const WARM: [[u8; 3]; STOP_COUNT] = [
+    [109, 63, 169],
+    [121, 62, 174],
+    [134, 62, 177],
+    [147, 61, 178],
+    [160, 60, 179],
+    [173, 60, 178],
+    [186, 60, 175],
+    [198, 60, 172],
+    [210, 61, 167],
+    [221, 63, 161],
+    [231, 65, 154],
+    [240, 68, 146],
+    [248, 71, 137],
+    [255, 76, 128],
+    [255, 81, 119],
+    [255, 87, 109],
+    [255, 93, 99],
+    [255, 101, 89],
+    [255, 109, 80],
+    [255, 117, 72],
+    [255, 126, 64],
+    [255, 136, 58],
+    [253, 146, 52],
+    [246, 156, 48],
+    [239, 166, 46],
+    [231, 177, 46],
+    [222, 187, 47],
+    [213, 197, 50],
+    [205, 206, 54],
+    [196, 216, 61],
+    [188, 224, 69],
+    [181, 232, 79],
+    [175, 239, 90],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The colors of 0, 0.3, 0.5, and 1 in each color map, so the color maps can't drift.
+    const GOLDEN: [(Colormap, [[u8; 3]; 4]); 9] = [
+        (
+            Colormap::Cividis,
+            [[0, 32, 81], [76, 86, 109], [127, 123, 116], [253, 233, 69]],
+        ),
+        (
+            Colormap::Cool,
+            [
+                [109, 63, 169],
+                [54, 139, 224],
+                [25, 199, 193],
+                [175, 239, 90],
+            ],
+        ),
+        (
+            Colormap::CubeHelix,
+            [[0, 0, 0], [30, 101, 65], [160, 121, 73], [255, 255, 255]],
+        ),
+        (
+            Colormap::Inferno,
+            [[0, 0, 4], [107, 23, 110], [187, 54, 84], [252, 255, 164]],
+        ),
+        (
+            Colormap::Magma,
+            [[0, 0, 4], [100, 26, 127], [181, 54, 121], [252, 253, 191]],
+        ),
+        (
+            Colormap::Plasma,
+            [[13, 8, 135], [144, 13, 163], [203, 70, 120], [240, 249, 33]],
+        ),
+        (
+            Colormap::Turbo,
+            [[34, 23, 27], [39, 214, 195], [148, 250, 80], [144, 12, 0]],
+        ),
+        (
+            Colormap::Viridis,
+            [[68, 1, 84], [52, 95, 141], [32, 144, 140], [253, 231, 37]],
+        ),
+        (
+            Colormap::Warm,
+            [
+                [109, 63, 169],
+                [227, 64, 157],
+                [255, 93, 99],
+                [175, 239, 90],
+            ],
+        ),
+    ];
+
+    #[test]
+    fn map_golden_values() {
+        for (colormap, colors) in GOLDEN {
+            let mapped = [0., 0.3, 0.5, 1.].map(|value| colormap.map(value));
+            assert_eq!(mapped, colors, "{colormap:?}");
+        }
+    }
+
+    #[test]
+    fn clamp_values_outside_of_range() {
+        let colormap = Colormap::Viridis;
+        assert_eq!(colormap.map(-1.), colormap.map(0.));
+        assert_eq!(colormap.map(2.), colormap.map(1.));
+        assert_eq!(colormap.map(f32::NAN), colormap.map(0.));
+    }
+
+    #[test]
+    fn interpolate_between_samples_in_oklab() {
+        // Halfway between two samples is halfway in lightness, not in RGB
+        let (from, to) = (VIRIDIS[0], VIRIDIS[1]);
+        let halfway = Colormap::Viridis.map(0.5 / (STOP_COUNT - 1) as f32);
+        let lightness = |rgb| srgb_to_oklab(rgb)[0];
+        let expected = (lightness(from) + lightness(to)) / 2.;
+        assert!((lightness(halfway) - expected).abs() < 0.005);
+    }
+
+    #[test]
+    fn normalize_amplitudes() {
+        assert_eq!(normalize_dbm(-100., -100., -50., None), 0.);
+        assert_eq!(normalize_dbm(-75., -100., -50., None), 0.5);
+        assert_eq!(normalize_dbm(-20., -100., -50., None), 1.);
+        assert_eq!(normalize_dbm(-120., -100., -50., None), 0.);
+        assert_eq!(normalize_dbm(-75., -100., -50., Some(2.)), 0.25);
+        assert_eq!(normalize_dbm(-75., -100., -50., Some(0.)), 0.5);
+        assert_eq!(normalize_dbm(-75., -50., -50., None), 0.);
+        assert_eq!(normalize_dbm(f32::NAN, -100., -50., None), 0.);
+    }
+}
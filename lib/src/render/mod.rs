@@ -0,0 +1,3 @@
+//! Helpers for drawing measurements, shared by frontends like the GUI.
+
+pub mod colormap;
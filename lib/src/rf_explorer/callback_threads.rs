@@ -0,0 +1,231 @@
+use std::{
+    fmt, ptr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use super::ConfigCallback;
+use crate::MemoryBudget;
+
+/// Runs user-provided callbacks on their own threads so that they can't block reading from the
+/// RF Explorer, while limiting how many can be running at once.
+///
+/// Sweeps and other streamed values that would start a callback while the limit is reached are
+/// dropped and counted instead, so a callback that's slower than the messages it's called for
+/// can't pile up threads. Configs and other state can't be dropped without leaving the
+/// application with a stale value, so they wait for a running callback to finish instead. Only
+/// the latest value waits for each callback, so they can't pile up either.
+#[derive(Debug)]
+pub(crate) struct CallbackThreads {
+    running: Arc<AtomicUsize>,
+    max_running: AtomicUsize,
+    dropped: Arc<AtomicU64>,
+    /// The callbacks waiting for a running one to finish, keyed by the callback they're for.
+    waiting: Arc<Mutex<Vec<WaitingCallback>>>,
+}
+
+struct WaitingCallback {
+    key: usize,
+    f: Box<dyn FnOnce() + Send>,
+}
+
+impl fmt::Debug for WaitingCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WaitingCallback")
+            .field("key", &self.key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CallbackThreads {
+    /// Calls the callback, if one is set, with `value` on a new thread.
+    ///
+    /// The value is never dropped, but if the callback is already waiting for a free thread,
+    /// `value` replaces the value it was waiting with.
+    ///
+    /// The callback's lock is released first, so the callback can replace or remove itself.
+    pub(crate) fn spawn_callback<T: Send + 'static>(
+        &self,
+        callback: &Mutex<ConfigCallback<T>>,
+        value: T,
+    ) {
+        let cb = callback.lock().unwrap().clone();
+        if let Some(cb) = cb {
+            self.spawn_latest(callback, move || cb(value));
+        }
+    }
+
+    /// Runs `f` on a new thread unless the most callbacks allowed are already running.
+    pub(crate) fn spawn(&self, f: impl FnOnce() + Send + 'static) {
+        if !self.try_start() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.start(Box::new(f));
+    }
+
+    /// Runs `f` on a new thread, or once a running callback finishes if the most callbacks
+    /// allowed are already running.
+    ///
+    /// `key` identifies what `f` is for, usually the callback it calls. If a callback with the
+    /// same key is already waiting, `f` replaces it and the replaced callback is counted as
+    /// dropped.
+    pub(crate) fn spawn_latest<K>(&self, key: &K, f: impl FnOnce() + Send + 'static) {
+        let key = ptr::from_ref(key).addr();
+        let mut waiting = self.waiting.lock().unwrap();
+        // Holding the lock while trying to start means a finishing callback can't miss `f`
+        if self.try_start() {
+            drop(waiting);
+            self.start(Box::new(f));
+            return;
+        }
+
+        let f = Box::new(f);
+        if let Some(replaced) = waiting.iter_mut().find(|waiting| waiting.key == key) {
+            replaced.f = f;
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        } else {
+            waiting.push(WaitingCallback { key, f });
+        }
+    }
+
+    fn try_start(&self) -> bool {
+        let max_running = self.max_running.load(Ordering::Relaxed);
+        self.running
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |running| {
+                (running < max_running).then_some(running + 1)
+            })
+            .is_ok()
+    }
+
+    /// Runs `f` on a new thread that has already been counted as running.
+    fn start(&self, f: Box<dyn FnOnce() + Send>) {
+        let running = RunningCallback {
+            running: self.running.clone(),
+            waiting: self.waiting.clone(),
+        };
+        run_on_new_thread(running, f);
+    }
+
+    pub(crate) fn set_max_running(&self, max_running: usize) {
+        self.max_running.store(max_running, Ordering::Relaxed);
+    }
+
+    /// Returns the number of callbacks that were dropped because too many were running.
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for CallbackThreads {
+    fn default() -> Self {
+        Self {
+            running: Arc::default(),
+            max_running: AtomicUsize::new(MemoryBudget::default().max_running_callbacks),
+            dropped: Arc::default(),
+            waiting: Arc::default(),
+        }
+    }
+}
+
+fn run_on_new_thread(running: RunningCallback, f: Box<dyn FnOnce() + Send>) {
+    thread::spawn(move || {
+        // Hand the thread's place over to a waiting callback once it returns, even if it panics
+        let _running = running;
+        f();
+    });
+}
+
+struct RunningCallback {
+    running: Arc<AtomicUsize>,
+    waiting: Arc<Mutex<Vec<WaitingCallback>>>,
+}
+
+impl Drop for RunningCallback {
+    fn drop(&mut self) {
+        let mut waiting = self.waiting.lock().unwrap();
+        if waiting.is_empty() {
+            self.running.fetch_sub(1, Ordering::AcqRel);
+            return;
+        }
+
+        // The waiting callback takes over this callback's place, so the count doesn't change
+        let next = waiting.remove(0);
+        drop(waiting);
+        let running = RunningCallback {
+            running: self.running.clone(),
+            waiting: self.waiting.clone(),
+        };
+        run_on_new_thread(running, next.f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn drop_callbacks_while_too_many_are_running() {
+        let threads = CallbackThreads::default();
+        threads.set_max_running(2);
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let release_rx = Arc::new(Mutex::new(release_rx));
+        let (done_tx, done_rx) = mpsc::channel();
+        for _ in 0..5 {
+            let (release_rx, done_tx) = (release_rx.clone(), done_tx.clone());
+            threads.spawn(move || {
+                release_rx.lock().unwrap().recv().unwrap();
+                done_tx.send(()).unwrap();
+            });
+        }
+        assert_eq!(threads.dropped(), 3);
+
+        for _ in 0..2 {
+            release_tx.send(()).unwrap();
+            done_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        }
+        // Wait for the finished callbacks to stop being counted
+        while threads.running.load(Ordering::Acquire) > 0 {
+            thread::yield_now();
+        }
+        threads.spawn(move || done_tx.send(()).unwrap());
+        done_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(threads.dropped(), 3);
+    }
+
+    #[test]
+    fn run_the_latest_waiting_callback_once_one_finishes() {
+        let threads = CallbackThreads::default();
+        threads.set_max_running(1);
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let (done_tx, done_rx) = mpsc::channel();
+        threads.spawn(move || release_rx.recv().unwrap());
+
+        // Both configs wait for the running callback, and the second replaces the first
+        let key = Mutex::new(None::<ConfigCallback<u32>>);
+        for config in [1, 2] {
+            let done_tx = done_tx.clone();
+            threads.spawn_latest(&key, move || done_tx.send(config).unwrap());
+        }
+        assert_eq!(
+            done_rx.recv_timeout(Duration::from_millis(100)),
+            Err(mpsc::RecvTimeoutError::Timeout)
+        );
+        assert_eq!(threads.dropped(), 1);
+
+        release_tx.send(()).unwrap();
+        assert_eq!(done_rx.recv_timeout(Duration::from_secs(1)), Ok(2));
+        assert_eq!(
+            done_rx.recv_timeout(Duration::from_millis(100)),
+            Err(mpsc::RecvTimeoutError::Timeout)
+        );
+        while threads.running.load(Ordering::Acquire) > 0 {
+            thread::yield_now();
+        }
+    }
+}
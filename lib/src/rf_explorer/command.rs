@@ -2,20 +2,106 @@ use std::borrow::Cow;
 
 use crate::common::BaudRate;
 
+/// A command understood by both RF Explorer spectrum analyzers and signal generators.
+///
+/// Commands are framed as `#`, the length of the whole command in bytes, and a body. New commands
+/// may be added in minor releases, so matches on this enum need a wildcard arm.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub(crate) enum Command {
+#[non_exhaustive]
+pub enum Command {
+    /// Requests the RF Explorer's setup info and config.
     RequestConfig,
+    /// Requests the RF Explorer's serial number.
     RequestSerialNumber,
+    /// Turns on the RF Explorer's LCD.
     EnableLcd,
+    /// Turns off the RF Explorer's LCD.
     DisableLcd,
+    /// Tells the RF Explorer to send its screen.
     EnableDumpScreen,
+    /// Tells the RF Explorer to stop sending its screen.
     DisableDumpScreen,
+    /// Tells the RF Explorer to stop sending sweeps or changing its output.
     Hold,
-    SetBaudRate { baud_rate: BaudRate },
+    /// Switches the RF Explorer's serial port to a different baud rate.
+    SetBaudRate {
+        /// The new baud rate.
+        baud_rate: BaudRate,
+    },
+    /// Reboots the RF Explorer.
     Reboot,
+    /// Turns off the RF Explorer.
     PowerOff,
 }
 
+impl Command {
+    /// Returns the bytes sent to the RF Explorer for the command.
+    pub fn to_bytes(self) -> Cow<'static, [u8]> {
+        self.into()
+    }
+
+    /// Returns a short description of what the command does.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Command::RequestConfig => "Request config",
+            Command::RequestSerialNumber => "Request serial number",
+            Command::EnableLcd => "Enable LCD",
+            Command::DisableLcd => "Disable LCD",
+            Command::EnableDumpScreen => "Enable screen dump",
+            Command::DisableDumpScreen => "Disable screen dump",
+            Command::Hold => "Hold",
+            Command::SetBaudRate { .. } => "Set baud rate",
+            Command::Reboot => "Reboot",
+            Command::PowerOff => "Power off",
+        }
+    }
+
+    /// Returns every command that doesn't take parameters.
+    pub fn parameterless() -> impl Iterator<Item = Command> {
+        [
+            Command::RequestConfig,
+            Command::RequestSerialNumber,
+            Command::EnableLcd,
+            Command::DisableLcd,
+            Command::EnableDumpScreen,
+            Command::DisableDumpScreen,
+            Command::Hold,
+            Command::Reboot,
+            Command::PowerOff,
+        ]
+        .into_iter()
+    }
+
+    /// Recognizes a complete command frame, like one captured by sniffing the serial port.
+    ///
+    /// Returns `None` if the bytes aren't exactly one command this enum can represent.
+    pub fn parse(bytes: &[u8]) -> Option<Command> {
+        if let Some(command) = Self::parameterless().find(|command| command.to_bytes() == bytes) {
+            return Some(command);
+        }
+        match frame_body(bytes)? {
+            [b'c', code] => Some(Command::SetBaudRate {
+                baud_rate: BaudRate::from_code(*code)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the body of a command frame if it starts with `#` and its length matches.
+pub(crate) fn frame_body(bytes: &[u8]) -> Option<&[u8]> {
+    match bytes {
+        [b'#', len, body @ ..] if usize::from(*len) == bytes.len() => Some(body),
+        _ => None,
+    }
+}
+
+/// Returns the comma-separated fields of a text command's body after its prefix.
+pub(crate) fn text_fields<'a>(body: &'a [u8], prefix: &str) -> Option<Vec<&'a str>> {
+    let fields = str::from_utf8(body.strip_prefix(prefix.as_bytes())?).ok()?;
+    Some(fields.split(',').collect())
+}
+
 impl From<Command> for Cow<'static, [u8]> {
     fn from(command: Command) -> Self {
         match command {
@@ -59,4 +145,36 @@ mod tests {
         assert_correct_size!(Command::Reboot);
         assert_correct_size!(Command::PowerOff);
     }
+
+    #[test]
+    fn match_documented_byte_sequences() {
+        assert_eq!(Command::RequestConfig.to_bytes(), &b"#\x04C0"[..]);
+        assert_eq!(Command::Hold.to_bytes(), &b"#\x04CH"[..]);
+        assert_eq!(Command::Reboot.to_bytes(), &b"#\x03r"[..]);
+        assert_eq!(
+            Command::SetBaudRate {
+                baud_rate: BaudRate::try_from(115_200).unwrap()
+            }
+            .to_bytes(),
+            &b"#\x04c8"[..]
+        );
+    }
+
+    #[test]
+    fn parse_sniffed_commands() {
+        for command in Command::parameterless() {
+            assert_eq!(Command::parse(&command.to_bytes()), Some(command));
+        }
+        let set_baud_rate = Command::parse(b"#\x04c0").unwrap();
+        assert_eq!(
+            set_baud_rate,
+            Command::SetBaudRate {
+                baud_rate: BaudRate::default()
+            }
+        );
+        assert_eq!(set_baud_rate.description(), "Set baud rate");
+        assert_eq!(Command::parse(b"#\x04c9"), None);
+        assert_eq!(Command::parse(b"#\x05C0"), None);
+        assert_eq!(Command::parse(b"C0"), None);
+    }
 }
@@ -0,0 +1,179 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Identifies an RF Explorer by its serial number and main radio model.
+///
+/// Port names can change every time RF Explorers are plugged in or rebooted, but a device ID stays
+/// the same, so it can be used to tell identical RF Explorers apart. Device IDs are ordered by
+/// serial number first. The firmware version is kept so it can be displayed, but it isn't compared,
+/// so an RF Explorer keeps its device ID when its firmware is updated.
+#[derive(Debug, Clone)]
+pub struct DeviceId {
+    serial_number: Arc<str>,
+    main_model: Arc<str>,
+    firmware_version: Arc<str>,
+}
+
+impl DeviceId {
+    /// Creates a device ID, like one that was saved from [`DeviceId::serial_number`],
+    /// [`DeviceId::main_model`], and [`DeviceId::firmware_version`].
+    pub fn new(
+        serial_number: impl Into<Arc<str>>,
+        main_model: impl Into<Arc<str>>,
+        firmware_version: impl Into<Arc<str>>,
+    ) -> Self {
+        DeviceId {
+            serial_number: serial_number.into(),
+            main_model: main_model.into(),
+            firmware_version: firmware_version.into(),
+        }
+    }
+
+    /// The RF Explorer's serial number.
+    pub fn serial_number(&self) -> &str {
+        &self.serial_number
+    }
+
+    /// The name of the RF Explorer's main radio model.
+    pub fn main_model(&self) -> &str {
+        &self.main_model
+    }
+
+    /// The RF Explorer's firmware version.
+    pub fn firmware_version(&self) -> &str {
+        &self.firmware_version
+    }
+}
+
+impl DeviceId {
+    fn key(&self) -> (&str, &str) {
+        (&self.serial_number, &self.main_model)
+    }
+}
+
+impl PartialEq for DeviceId {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for DeviceId {}
+
+impl Hash for DeviceId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for DeviceId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DeviceId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl Display for DeviceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({} v{})",
+            self.serial_number, self.main_model, self.firmware_version
+        )
+    }
+}
+
+/// Sorts devices by their device IDs, with devices that don't have one last.
+pub(crate) fn sort_by_device_id<T>(devices: &mut [T], device_id: impl Fn(&T) -> Option<DeviceId>) {
+    devices.sort_by_cached_key(|device| {
+        let device_id = device_id(device);
+        (device_id.is_none(), device_id)
+    });
+}
+
+/// Returns the device with the device ID and drops the others.
+pub(crate) fn find_by_device_id<T>(
+    devices: Vec<T>,
+    id: &DeviceId,
+    device_id: impl Fn(&T) -> Option<DeviceId>,
+) -> Option<T> {
+    devices
+        .into_iter()
+        .find(|device| device_id(device).as_ref() == Some(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{SpectrumAnalyzer, common::transcript_port::TranscriptPort};
+
+    fn device_id(serial_number: &str) -> DeviceId {
+        DeviceId::new(serial_number, "6G+", "01.35")
+    }
+
+    #[test]
+    fn sort_devices_without_ids_last() {
+        let mut devices = [Some("C"), None, Some("A"), Some("B")];
+        sort_by_device_id(&mut devices, |device| device.map(device_id));
+        assert_eq!(devices, [Some("A"), Some("B"), Some("C"), None]);
+    }
+
+    #[test]
+    fn find_device_by_id() {
+        let devices = vec![("COM3", Some("B")), ("COM4", None), ("COM5", Some("A"))];
+        let found = find_by_device_id(devices, &device_id("A"), |device| device.1.map(device_id));
+        assert_eq!(found, Some(("COM5", Some("A"))));
+    }
+
+    #[test]
+    fn ignore_firmware_version_when_comparing() {
+        let id = device_id("A");
+        let updated = DeviceId::new("A", "6G+", "01.36");
+        assert_eq!(id, updated);
+        assert_eq!(id.cmp(&updated), Ordering::Equal);
+        assert_eq!(HashSet::from([id, updated]).len(), 1);
+        assert_ne!(device_id("A"), DeviceId::new("A", "WSUB3G", "01.35"));
+    }
+
+    #[test]
+    fn keep_only_the_analyzer_with_the_device_id() {
+        let ports = ["B3AK7AL7CACAA74M", "A0BK7AL7CACAA12Q"].map(|serial_number| {
+            TranscriptPort::from_transcript(&format!(
+                "> #\\x04C0\n\
+                 < #C2-M:005,006,01.12B26\n\
+                 < #C2-F:0430000,0178571,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\n\
+                 > #\\x04Cn\n\
+                 < #Sn{serial_number}\n"
+            ))
+        });
+        let rfes = ports
+            .iter()
+            .map(|port| SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap())
+            .collect();
+        // The ID was saved before the firmware was updated
+        let id = DeviceId::new("A0BK7AL7CACAA12Q", "WSUB3G", "01.11B20");
+
+        let rfe = find_by_device_id(rfes, &id, SpectrumAnalyzer::device_id).unwrap();
+        assert_eq!(rfe.serial_number().as_deref(), Some("A0BK7AL7CACAA12Q"));
+        assert!(ports[0].is_released());
+        assert!(!ports[1].is_released());
+    }
+
+    #[test]
+    fn display_device_id() {
+        assert_eq!(
+            device_id("B3AK7AL7CACAA74M").to_string(),
+            "B3AK7AL7CACAA74M (6G+ v01.35)"
+        );
+    }
+}
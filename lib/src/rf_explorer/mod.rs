@@ -1,22 +1,85 @@
+mod callback_threads;
 mod command;
+mod device_id;
 pub(crate) mod parsers;
+mod reentrancy;
 mod screen_data;
+mod screen_data_cache;
+mod screen_dump;
 mod serial_number;
 mod setup_info;
 
-pub(crate) use command::Command;
+pub(crate) use callback_threads::CallbackThreads;
+pub use command::Command;
+pub(crate) use command::{frame_body, text_fields};
+pub use device_id::DeviceId;
+pub(crate) use device_id::{find_by_device_id, sort_by_device_id};
+pub(crate) use reentrancy::{MESSAGE_READER, ReaderCallback, check_not_held};
 pub use screen_data::ScreenData;
+pub(crate) use screen_data_cache::ScreenDataCache;
+pub use screen_dump::ScreenDumpMode;
+pub(crate) use screen_dump::ScreenDumpTimer;
 pub(crate) use serial_number::SerialNumber;
 pub(crate) use setup_info::SetupInfo;
 
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{ConnectProgress, ConnectionError, ConnectionResult};
 
 pub(crate) type ConfigCallback<T> = Option<Arc<Box<dyn Fn(T) + Send + Sync + 'static>>>;
+
 pub(crate) const NEXT_SCREEN_DATA_TIMEOUT: Duration = Duration::from_secs(2);
 pub(crate) const COMMAND_RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
 pub(crate) const RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT: Duration = Duration::from_secs(2);
 
+/// Waits until both the config and the setup info have been received, calling `on_waiting` with
+/// how long it has waited before it starts and then about every
+/// [`ConnectProgress::WAITING_INTERVAL`].
+///
+/// Each one has [`RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT`] to arrive.
+pub(crate) fn wait_for_device_info<C, S>(
+    config: &(Mutex<Option<C>>, Condvar),
+    setup_info: &(Mutex<Option<S>>, Condvar),
+    on_waiting: &dyn Fn(Duration),
+) -> ConnectionResult<()> {
+    let started_at = Instant::now();
+    on_waiting(Duration::ZERO);
+    if wait_until_some(config, started_at, on_waiting)
+        && wait_until_some(setup_info, started_at, on_waiting)
+    {
+        Ok(())
+    } else {
+        Err(ConnectionError::DeviceInfoNotReceived)
+    }
+}
+
+fn wait_until_some<T>(
+    (lock, cvar): &(Mutex<Option<T>>, Condvar),
+    started_at: Instant,
+    on_waiting: &dyn Fn(Duration),
+) -> bool {
+    let deadline = Instant::now() + RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT;
+    loop {
+        let now = Instant::now();
+        let timeout = deadline
+            .saturating_duration_since(now)
+            .min(ConnectProgress::WAITING_INTERVAL);
+        let (value, _) = cvar
+            .wait_timeout_while(lock.lock().unwrap(), timeout, |value| value.is_none())
+            .unwrap();
+        if value.is_some() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        // The lock is released first so the callback can't hold up reading messages
+        drop(value);
+        on_waiting(started_at.elapsed());
+    }
+}
+
 macro_rules! impl_rf_explorer {
     ($rf_explorer:ident, $message_container:ty) => {
         use crate::common::BaudRate;
@@ -31,15 +94,110 @@ macro_rules! impl_rf_explorer {
                 })
             }
 
+            /// Connects to the first available RF Explorer while reporting each step of connecting
+            /// to `observer`.
+            ///
+            /// Connecting can take several seconds, so the progress lets a user interface show
+            /// which ports are being probed instead of a spinner. `observer` is called from the
+            /// threads probing the ports, sometimes at the same time, so it must be cheap, like
+            /// storing the progress and requesting a repaint.
+            pub fn connect_with_progress(
+                observer: impl Fn(crate::ConnectProgress) + Send + Sync,
+            ) -> Option<Self> {
+                Some(Self {
+                    rfe: Device::connect_with_progress(
+                        Cow::from(rf_explorer::Command::RequestConfig),
+                        &observer,
+                    )?,
+                })
+            }
+
+            /// Connects to every available RF Explorer.
+            ///
+            /// The RF Explorers are sorted by [`device_id`](Self::device_id), so they're returned
+            /// in the same order no matter which ports they're connected to. RF Explorers that
+            /// don't report a serial number are last.
+            pub fn connect_all() -> Vec<Self> {
+                let mut devices: Vec<Self> =
+                    Device::connect_all(Cow::from(rf_explorer::Command::RequestConfig))
+                        .into_iter()
+                        .map(|rfe| Self { rfe })
+                        .collect();
+                rf_explorer::sort_by_device_id(&mut devices, Self::device_id);
+                devices
+            }
+
+            /// Connects to the RF Explorer with the given device ID.
+            ///
+            /// Every available RF Explorer is connected to and identified, and the ports of the
+            /// ones that don't match are closed so they can be connected to later.
+            pub fn connect_by_device_id(id: &crate::DeviceId) -> Option<Self> {
+                rf_explorer::find_by_device_id(Self::connect_all(), id, Self::device_id)
+            }
+
+            /// Returns the ID that identifies the RF Explorer no matter which port it's connected
+            /// to, or `None` if it doesn't report a serial number.
+            ///
+            /// The serial number is requested from the RF Explorer if it hasn't been received yet.
+            pub fn device_id(&self) -> Option<crate::DeviceId> {
+                Some(crate::DeviceId::new(
+                    self.serial_number()?,
+                    self.main_radio_model()
+                        .map(|model| model.to_string())
+                        .unwrap_or_default(),
+                    self.firmware_version(),
+                ))
+            }
+
             /// Connects to the first available RF Explorer with the given name while using the given baud rate.
+            ///
+            /// The baud rate can be a [`BaudRate`] or a number of bits per second, which returns
+            /// [`ConnectionError::UnsupportedBaudRate`](crate::ConnectionError::UnsupportedBaudRate)
+            /// if an RF Explorer doesn't support it.
             pub fn connect_with_name_and_baud_rate(
                 name: &str,
-                baud_rate: u32,
+                baud_rate: impl TryInto<BaudRate> + Into<u32> + Copy,
             ) -> ConnectionResult<Self> {
+                let baud_rate = baud_rate
+                    .try_into()
+                    .map_err(|_| crate::ConnectionError::UnsupportedBaudRate(baud_rate.into()))?;
                 Ok(Self {
                     rfe: Device::connect_with_name_and_baud_rate(
                         name,
-                        baud_rate,
+                        baud_rate.bps(),
+                        Cow::from(rf_explorer::Command::RequestConfig),
+                    )?,
+                })
+            }
+
+            /// Connects to the first available RF Explorer with the given name while using the
+            /// given baud rate, without blocking the async runtime.
+            ///
+            /// Opening the serial port and waiting for the RF Explorer's initial messages run on
+            /// Tokio's blocking thread pool, so this has to be awaited within a Tokio runtime.
+            #[cfg(feature = "async")]
+            pub async fn connect_with_name_and_baud_rate_async(
+                name: &str,
+                baud_rate: impl TryInto<BaudRate> + Into<u32> + Copy,
+            ) -> ConnectionResult<Self> {
+                let baud_rate = baud_rate
+                    .try_into()
+                    .map_err(|_| crate::ConnectionError::UnsupportedBaudRate(baud_rate.into()))?;
+                let name = name.to_string();
+                tokio::task::spawn_blocking(move || {
+                    Self::connect_with_name_and_baud_rate(&name, baud_rate)
+                })
+                .await
+                .unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic()))
+            }
+
+            /// Connects to an RF Explorer through an already opened transport.
+            pub fn connect_with_transport(
+                transport: Box<dyn serialport::SerialPort>,
+            ) -> ConnectionResult<Self> {
+                Ok(Self {
+                    rfe: Device::connect_with_transport(
+                        transport,
                         Cow::from(rf_explorer::Command::RequestConfig),
                     )?,
                 })
@@ -54,14 +212,27 @@ macro_rules! impl_rf_explorer {
                 self.rfe.port_name()
             }
 
+            /// Information about the serial port through which the RF Explorer is connected,
+            /// such as the USB adapter's serial number.
+            pub fn port_info(&self) -> &serialport::SerialPortInfo {
+                self.rfe.port_info()
+            }
+
             /// The baud rate of the serial connection to the RF Explorer.
             pub fn baud_rate(&self) -> io::Result<u32> {
                 self.rfe.baud_rate()
             }
 
             /// Sets the baud rate of the serial connection to the RF Explorer.
-            pub fn set_baud_rate(&self, baud_rate: u32) -> crate::Result<()> {
-                let baud_rate = BaudRate::try_from(baud_rate)?;
+            ///
+            /// The baud rate can be a [`BaudRate`] or a number of bits per second, which returns
+            /// [`Error::InvalidInput`](crate::Error::InvalidInput) if an RF Explorer doesn't
+            /// support it.
+            pub fn set_baud_rate(
+                &self,
+                baud_rate: impl TryInto<BaudRate, Error: Into<crate::Error>>,
+            ) -> crate::Result<()> {
+                let baud_rate = baud_rate.try_into().map_err(Into::into)?;
                 self.send_command(rf_explorer::Command::SetBaudRate { baud_rate })?;
                 self.rfe
                     .serial_port()
@@ -73,49 +244,322 @@ macro_rules! impl_rf_explorer {
             pub(crate) fn send_command(
                 &self,
                 command: impl Into<Cow<'static, [u8]>>,
-            ) -> io::Result<()> {
+            ) -> crate::Result<()> {
                 self.rfe.send_command(command)
             }
 
+            /// Sets the minimum time between commands sent to the RF Explorer.
+            ///
+            /// Commands are spaced by [`DEFAULT_MIN_COMMAND_INTERVAL`](crate::DEFAULT_MIN_COMMAND_INTERVAL)
+            /// by default so the firmware doesn't ignore them. Use `Duration::ZERO` to disable pacing.
+            pub fn set_min_command_interval(&self, min_command_interval: std::time::Duration) {
+                self.rfe.set_min_command_interval(min_command_interval)
+            }
+
+            /// Sets the number of attempts made for each write to the RF Explorer and the delay between them.
+            ///
+            /// Only writes that fail with an interrupted, timed out, or would block error are retried.
+            pub fn set_write_retry(&self, attempts: u32, delay: std::time::Duration) {
+                self.rfe.set_write_retry(attempts, delay)
+            }
+
+            /// Sets a callback that receives the exact bytes of every command sent to the RF Explorer.
+            ///
+            /// The callback is called after each write completes with the time it completed.
+            pub fn set_command_log_callback(
+                &self,
+                cb: impl FnMut(&[u8], std::time::SystemTime) + Send + 'static,
+            ) {
+                self.rfe.set_command_log_callback(cb)
+            }
+
+            /// Removes the callback that receives the commands sent to the RF Explorer.
+            pub fn remove_command_log_callback(&self) {
+                self.rfe.remove_command_log_callback()
+            }
+
+            /// Emits a hex-encoded `debug!` event with the
+            /// [`COMMAND_LOG_TARGET`](crate::COMMAND_LOG_TARGET) target for every command sent to
+            /// the RF Explorer.
+            pub fn enable_command_logging(&self) {
+                self.rfe.enable_command_logging()
+            }
+
+            /// Returns a snapshot of the connection to the RF Explorer for debugging.
+            pub fn debug_snapshot(&self) -> crate::DebugSnapshot {
+                self.rfe.debug_snapshot()
+            }
+
+            /// Returns the state of the connection to the RF Explorer, like whether it's in
+            /// firmware update (bootloader) mode.
+            ///
+            /// Commands sent while it's in bootloader mode return
+            /// [`Error::InvalidOperation`](crate::Error::InvalidOperation), since they could
+            /// corrupt the update.
+            pub fn connection_state(&self) -> crate::ConnectionState {
+                self.rfe.connection_state()
+            }
+
+            /// Sets the callback that is called when the state of the connection to the RF
+            /// Explorer changes, like when it enters or leaves bootloader mode or when the serial
+            /// link's diagnosis changes.
+            pub fn set_connection_state_callback(
+                &self,
+                cb: impl Fn(crate::ConnectionState) + Send + Sync + 'static,
+            ) {
+                self.rfe.set_connection_state_callback(cb)
+            }
+
+            /// Removes the callback that is called when the state of the connection to the RF
+            /// Explorer changes.
+            pub fn remove_connection_state_callback(&self) {
+                self.rfe.remove_connection_state_callback()
+            }
+
+            /// Returns whether messages can still be read from the RF Explorer.
+            ///
+            /// Once its USB cable is unplugged, it stays disconnected and waiting for its
+            /// messages returns [`Error::Disconnected`](crate::Error::Disconnected) until
+            /// [`reconnect`](Self::reconnect) succeeds.
+            pub fn is_connected(&self) -> bool {
+                self.rfe.is_connected()
+            }
+
+            /// Sets the callback that is called when messages can no longer be read from the
+            /// RF Explorer, like after its USB cable is unplugged.
+            ///
+            /// The callback is called on the thread that read from the RF Explorer right before
+            /// it stops, so it should return quickly, like by telling another thread to call
+            /// [`reconnect`](Self::reconnect). It isn't called again when a reconnect fails, since
+            /// [`reconnect`](Self::reconnect) returns the error instead.
+            pub fn set_disconnect_callback(&self, cb: impl FnMut() + Send + 'static) {
+                self.rfe.set_disconnect_callback(cb)
+            }
+
+            /// Removes the callback that is called when the RF Explorer is disconnected.
+            pub fn remove_disconnect_callback(&self) {
+                self.rfe.remove_disconnect_callback()
+            }
+
+            /// Reopens the serial port the RF Explorer was connected through, like after its USB
+            /// cable was unplugged and plugged back in, and requests its config again.
+            ///
+            /// Every callback and setting is kept, and the cached config and other values are
+            /// replaced as the RF Explorer sends them again. Returns
+            /// [`ConnectionError::DeviceInfoNotReceived`](crate::ConnectionError::DeviceInfoNotReceived)
+            /// if the RF Explorer doesn't respond within a few seconds, in which case it stays
+            /// disconnected and reconnecting can be tried again.
+            pub fn reconnect(&self) -> ConnectionResult<()> {
+                self.rfe
+                    .reconnect(Cow::from(rf_explorer::Command::RequestConfig))
+            }
+
+            /// Reconnects like [`reconnect`](Self::reconnect) through an already opened
+            /// transport instead of reopening the serial port.
+            pub fn reconnect_with_transport(
+                &self,
+                transport: Box<dyn serialport::SerialPort>,
+            ) -> ConnectionResult<()> {
+                self.rfe.reconnect_with_transport(
+                    transport,
+                    Cow::from(rf_explorer::Command::RequestConfig),
+                )
+            }
+
+            /// Returns the health of the serial link to the RF Explorer, like its throughput, the
+            /// time since the last message was received, and the most likely cause of its
+            /// problems.
+            pub fn link_health(&self) -> crate::LinkHealth {
+                self.rfe.link_health()
+            }
+
+            /// Limits the memory used by the internal buffers of the connection to the RF
+            /// Explorer.
+            ///
+            /// When a buffer reaches its limit, data is dropped and counted in
+            /// [`memory_stats`](Self::memory_stats) instead of more memory being allocated.
+            /// Limits below their documented minimums are raised to them.
+            pub fn set_memory_budget(&self, budget: crate::MemoryBudget) {
+                let budget = budget.clamped();
+                self.rfe.set_max_line_len(budget.max_line_len);
+                self.messages().set_memory_budget(budget);
+            }
+
+            /// Returns the limits on the memory used by the connection's internal buffers.
+            pub fn memory_budget(&self) -> crate::MemoryBudget {
+                self.messages().memory_budget()
+            }
+
+            /// Returns an estimate of the memory used by the connection's internal buffers, in
+            /// bytes.
+            ///
+            /// Values that have been passed to callbacks or received from a
+            /// [`SweepReceiver`](crate::spectrum_analyzer::SweepReceiver) aren't counted.
+            pub fn current_memory_usage_estimate(&self) -> usize {
+                self.rfe.line_buf_capacity() + self.messages().memory_usage_estimate()
+            }
+
+            /// Returns the counts of the data dropped because a buffer reached its
+            /// [`MemoryBudget`](crate::MemoryBudget) limit.
+            pub fn memory_stats(&self) -> crate::MemoryStats {
+                crate::MemoryStats {
+                    dropped_lines: self.rfe.dropped_lines(),
+                    ..self.messages().memory_stats()
+                }
+            }
+
             /// Sends bytes to the RF Explorer.
-            pub fn send_bytes(&self, bytes: impl AsRef<[u8]>) -> io::Result<()> {
+            pub fn send_bytes(&self, bytes: impl AsRef<[u8]>) -> crate::Result<()> {
                 self.rfe.send_bytes(bytes)
             }
 
             /// Turns the RF Explorer's LCD on.
-            pub fn lcd_on(&self) -> io::Result<()> {
+            pub fn lcd_on(&self) -> crate::Result<()> {
                 self.rfe.send_command(rf_explorer::Command::EnableLcd)
             }
 
             /// Turns the RF Explorer's LCD off.
-            pub fn lcd_off(&self) -> io::Result<()> {
+            pub fn lcd_off(&self) -> crate::Result<()> {
                 self.rfe.send_command(rf_explorer::Command::DisableLcd)
             }
 
             /// Tells the RF Explorer to start sending `ScreenData`.
-            pub fn enable_dump_screen(&self) -> io::Result<()> {
+            pub fn enable_dump_screen(&self) -> crate::Result<()> {
                 self.rfe
-                    .send_command(rf_explorer::Command::EnableDumpScreen)
+                    .set_screen_dump_mode(crate::ScreenDumpMode::Continuous)
             }
 
             /// Tells the RF Explorer to stop sending `ScreenData`.
-            pub fn disable_dump_screen(&self) -> io::Result<()> {
-                self.rfe
-                    .send_command(rf_explorer::Command::DisableDumpScreen)
+            pub fn disable_dump_screen(&self) -> crate::Result<()> {
+                self.rfe.set_screen_dump_mode(crate::ScreenDumpMode::Off)
+            }
+
+            /// Captures one frame of `ScreenData` every `interval`, or every frame if `interval`
+            /// is `None`.
+            ///
+            /// Sending every frame takes serial bandwidth away from sweeps. With an interval, the
+            /// RF Explorer is told to start sending its screen, then to stop as soon as a frame
+            /// arrives, so the sweep rate recovers between frames.
+            pub fn set_screen_dump_interval(
+                &self,
+                interval: Option<std::time::Duration>,
+            ) -> crate::Result<()> {
+                self.rfe.set_screen_dump_mode(interval.map_or(
+                    crate::ScreenDumpMode::Continuous,
+                    crate::ScreenDumpMode::Interval,
+                ))
+            }
+
+            /// Returns how the RF Explorer was last told to send `ScreenData`.
+            pub fn screen_dump_mode(&self) -> crate::ScreenDumpMode {
+                self.rfe.screen_dump_mode()
+            }
+
+            /// Returns the most recent `ScreenData` captured by the RF Explorer.
+            ///
+            /// The last screen data received is still returned after the RF Explorer is
+            /// disconnected.
+            pub fn screen_data(&self) -> Option<crate::ScreenData> {
+                self.messages().screen_data.latest()
+            }
+
+            /// Sets the callback that is called when the RF Explorer captures its `ScreenData`.
+            pub fn set_screen_data_callback(
+                &self,
+                cb: impl Fn(crate::ScreenData) + Send + Sync + 'static,
+            ) {
+                self.messages().screen_data.set_callback(cb)
+            }
+
+            /// Removes the callback that is called when the RF Explorer captures its `ScreenData`.
+            pub fn remove_screen_data_callback(&self) {
+                self.messages().screen_data.remove_callback()
+            }
+
+            /// Waits for the RF Explorer to capture its next `ScreenData`.
+            pub fn wait_for_next_screen_data(&self) -> crate::Result<crate::ScreenData> {
+                self.wait_for_next_screen_data_with_timeout(rf_explorer::NEXT_SCREEN_DATA_TIMEOUT)
+            }
+
+            /// Waits for the RF Explorer to capture its next `ScreenData` or for the timeout
+            /// duration to elapse.
+            ///
+            /// Returns [`Error::Disconnected`](crate::Error::Disconnected) as soon as the RF
+            /// Explorer is disconnected.
+            pub fn wait_for_next_screen_data_with_timeout(
+                &self,
+                timeout: std::time::Duration,
+            ) -> crate::Result<crate::ScreenData> {
+                let messages = self.messages();
+                messages
+                    .screen_data
+                    .wait_for_next(timeout, &messages.disconnection)
+            }
+
+            /// Returns a future that resolves to the first `ScreenData` the RF Explorer captures
+            /// after this is called.
+            ///
+            /// The future has to be awaited within a Tokio runtime with its time driver enabled.
+            /// It resolves to [`Error::TimedOut`](crate::Error::TimedOut) if no `ScreenData` is
+            /// captured before the timeout, or the same default timeout as
+            /// `wait_for_next_screen_data` if it's `None`, and to
+            /// [`Error::Disconnected`](crate::Error::Disconnected) as soon as the RF Explorer is
+            /// disconnected.
+            #[cfg(feature = "async")]
+            pub fn wait_for_next_screen_data_async(
+                &self,
+                timeout: Option<std::time::Duration>,
+            ) -> impl Future<Output = crate::Result<crate::ScreenData>> + Send + '_ {
+                let messages = self.messages();
+                messages.screen_data.wait_for_next_async(
+                    timeout.unwrap_or(rf_explorer::NEXT_SCREEN_DATA_TIMEOUT),
+                    &messages.disconnection,
+                )
+            }
+
+            /// Waits for the RF Explorer to capture `ScreenData` whose pixels differ from the most
+            /// recent `ScreenData`, or for the timeout duration to elapse.
+            ///
+            /// Frames that look the same as the most recent one are skipped, so a dashboard only
+            /// redraws the screen when it changes. Returns
+            /// [`Error::Disconnected`](crate::Error::Disconnected) as soon as the RF Explorer is
+            /// disconnected.
+            pub fn wait_for_changed_screen_data(
+                &self,
+                timeout: std::time::Duration,
+            ) -> crate::Result<crate::ScreenData> {
+                let messages = self.messages();
+                messages
+                    .screen_data
+                    .wait_for_changed(timeout, &messages.disconnection)
             }
 
             /// Tells the RF Explorer to stop collecting data.
-            pub fn hold(&self) -> io::Result<()> {
-                self.rfe.send_command(rf_explorer::Command::Hold)
+            pub fn hold(&self) -> crate::Result<()> {
+                self.rfe.send_command(rf_explorer::Command::Hold)?;
+                self.rfe.set_holding(true);
+                Ok(())
+            }
+
+            /// Tells the RF Explorer to resume collecting data after a call to `hold`.
+            pub fn resume(&self) -> crate::Result<()> {
+                self.rfe.send_command(rf_explorer::Command::RequestConfig)?;
+                self.rfe.set_holding(false);
+                Ok(())
+            }
+
+            /// Returns whether the RF Explorer was last told to hold.
+            pub fn is_holding(&self) -> bool {
+                self.rfe.is_holding()
             }
 
             /// Reboots the RF Explorer.
-            pub fn reboot(&self) -> io::Result<()> {
+            pub fn reboot(&self) -> crate::Result<()> {
                 self.rfe.send_command(rf_explorer::Command::Reboot)
             }
 
             /// Turns the RF Explorer's power off.
-            pub fn power_off(&self) -> io::Result<()> {
+            pub fn power_off(&self) -> crate::Result<()> {
                 self.rfe.send_command(rf_explorer::Command::PowerOff)
             }
         }
@@ -123,3 +567,32 @@ macro_rules! impl_rf_explorer {
 }
 
 pub(crate) use impl_rf_explorer;
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn report_progress_while_waiting_for_device_info() {
+        let config = Arc::new((Mutex::new(None), Condvar::new()));
+        let setup_info = (Mutex::new(Some(())), Condvar::new());
+        let sender_config = Arc::clone(&config);
+        thread::spawn(move || {
+            thread::sleep(ConnectProgress::WAITING_INTERVAL * 3);
+            *sender_config.0.lock().unwrap() = Some(());
+            sender_config.1.notify_all();
+        });
+
+        let waited = Mutex::new(Vec::new());
+        wait_for_device_info(&config, &setup_info, &|elapsed| {
+            waited.lock().unwrap().push(elapsed)
+        })
+        .unwrap();
+        let waited = waited.into_inner().unwrap();
+        assert_eq!(waited.first(), Some(&Duration::ZERO));
+        assert!(waited.len() >= 2);
+        assert!(waited.is_sorted());
+    }
+}
@@ -0,0 +1,175 @@
+use std::{cell::RefCell, sync::Mutex};
+
+/// The lock the thread that reads from an RF Explorer holds while it runs a callback, like a
+/// borrowed sweep callback. No messages can be received until the callback returns, so waiting
+/// for one from the callback would deadlock.
+pub(crate) const MESSAGE_READER: &str = "message reader";
+
+thread_local! {
+    /// The locks the current thread holds while it runs a callback. Only tracked in debug builds.
+    static HELD_LOCKS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks a lock as held by the current thread while it runs a callback, until it's dropped.
+#[must_use]
+pub(crate) struct HeldLock {
+    name: &'static str,
+}
+
+impl HeldLock {
+    pub(crate) fn new(name: &'static str) -> Self {
+        if cfg!(debug_assertions) {
+            HELD_LOCKS.with_borrow_mut(|held| held.push(name));
+        }
+        Self { name }
+    }
+}
+
+impl Drop for HeldLock {
+    fn drop(&mut self) {
+        if cfg!(debug_assertions) {
+            HELD_LOCKS.with_borrow_mut(|held| {
+                if let Some(index) = held.iter().rposition(|name| *name == self.name) {
+                    held.remove(index);
+                }
+            });
+        }
+    }
+}
+
+/// Panics in debug builds if the current thread is running a callback while it holds the lock,
+/// so a getter that needs it fails loudly instead of deadlocking.
+pub(crate) fn check_not_held(name: &'static str) {
+    if cfg!(debug_assertions) && HELD_LOCKS.with_borrow(|held| held.contains(&name)) {
+        panic!(
+            "getter called from callback while lock held: the {name} lock is held by this \
+             thread while it runs a callback, so the getter would deadlock"
+        );
+    }
+}
+
+/// A callback that's called on the thread that reads from the RF Explorer.
+///
+/// The callback is taken out of its slot while it runs so it can replace or remove itself
+/// without deadlocking.
+pub(crate) struct ReaderCallback<F: ?Sized> {
+    state: Mutex<ReaderCallbackState<F>>,
+}
+
+struct ReaderCallbackState<F: ?Sized> {
+    callback: Option<Box<F>>,
+    /// Incremented every time the callback is replaced or removed.
+    generation: u64,
+}
+
+impl<F: ?Sized> ReaderCallback<F> {
+    pub(crate) fn set(&self, cb: Box<F>) {
+        let mut state = self.state.lock().unwrap();
+        state.callback = Some(cb);
+        state.generation += 1;
+    }
+
+    pub(crate) fn remove(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.callback = None;
+        state.generation += 1;
+    }
+
+    pub(crate) fn is_set(&self) -> bool {
+        self.state.lock().unwrap().callback.is_some()
+    }
+
+    /// Calls the callback, if there is one, without holding the slot's lock.
+    ///
+    /// The callback is put back afterwards unless it replaced or removed itself.
+    pub(crate) fn call(&self, call: impl FnOnce(&mut F)) {
+        let (mut cb, generation) = {
+            let mut state = self.state.lock().unwrap();
+            match state.callback.take() {
+                Some(cb) => (cb, state.generation),
+                None => return,
+            }
+        };
+        {
+            let _reader = HeldLock::new(MESSAGE_READER);
+            call(&mut cb);
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.generation == generation {
+            state.callback = Some(cb);
+        }
+    }
+}
+
+impl<F: ?Sized> Default for ReaderCallback<F> {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(ReaderCallbackState {
+                callback: None,
+                generation: 0,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    type Callback = dyn FnMut(usize) + Send;
+
+    #[test]
+    fn remove_callback_from_inside_itself() {
+        let slot = Arc::new(ReaderCallback::<Callback>::default());
+        let calls = Arc::new(AtomicUsize::new(0));
+        let (callback_slot, callback_calls) = (slot.clone(), calls.clone());
+        slot.set(Box::new(move |_| {
+            callback_calls.fetch_add(1, Ordering::Relaxed);
+            callback_slot.remove();
+        }));
+
+        slot.call(|cb| cb(0));
+        slot.call(|cb| cb(1));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert!(!slot.is_set());
+    }
+
+    #[test]
+    fn keep_callback_set_from_inside_another() {
+        let slot = Arc::new(ReaderCallback::<Callback>::default());
+        let replaced = Arc::new(AtomicUsize::new(0));
+        let (callback_slot, replaced_calls) = (slot.clone(), replaced.clone());
+        slot.set(Box::new(move |_| {
+            let replaced_calls = replaced_calls.clone();
+            callback_slot.set(Box::new(move |value| {
+                replaced_calls.fetch_add(value, Ordering::Relaxed);
+            }));
+        }));
+
+        slot.call(|cb| cb(1));
+        slot.call(|cb| cb(2));
+        assert_eq!(replaced.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "getter called from callback while lock held")]
+    fn panic_when_waiting_for_the_reader_from_its_callback() {
+        let slot = ReaderCallback::<Callback>::default();
+        slot.set(Box::new(|_| check_not_held(MESSAGE_READER)));
+        slot.call(|cb| cb(0));
+    }
+
+    #[test]
+    fn release_held_locks_once_callbacks_return() {
+        let slot = ReaderCallback::<Callback>::default();
+        slot.set(Box::new(|_| {}));
+        slot.call(|cb| cb(0));
+        check_not_held(MESSAGE_READER);
+    }
+}
@@ -1,4 +1,4 @@
-use std::convert::TryInto;
+use std::{convert::TryInto, io};
 
 use chrono::{DateTime, Utc};
 use nom::{
@@ -8,13 +8,14 @@ use nom::{
 };
 
 use super::parsers::*;
-use crate::common::MessageParseError;
+use crate::common::{MessageParseError, WriteMessage};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 /// Monochrome LCD screen capture from an RF Explorer device.
 pub struct ScreenData {
     screen_data_matrix: Box<[[u8; ScreenData::COLUMNS]; ScreenData::ROWS]>,
     timestamp: DateTime<Utc>,
+    sequence: u64,
 }
 
 impl ScreenData {
@@ -22,9 +23,10 @@ impl ScreenData {
     pub const WIDTH_PX: u8 = 128;
     /// Screen height in pixels.
     pub const HEIGHT_PX: u8 = 64;
-    pub(crate) const PREFIX: &'static [u8] = b"$D";
-    const ROWS: usize = 8;
-    const COLUMNS: usize = 128;
+    /// The bytes every screen data message starts with.
+    pub const PREFIX: &'static [u8] = b"$D";
+    pub(crate) const ROWS: usize = 8;
+    pub(crate) const COLUMNS: usize = 128;
     const ROW_HEIGHT_PX: usize = 8;
 
     /// Returns whether a pixel is on or off at a given xy-coordinate.
@@ -53,10 +55,74 @@ impl ScreenData {
         Some((self.screen_data_matrix.get(row)?.get(column)? & (1 << (y % 8))) > 0)
     }
 
+    /// Returns every pixel's column, row, and whether it's on, row by row from the top-left of the
+    /// screen.
+    pub fn pixels(&self) -> impl Iterator<Item = (u8, u8, bool)> + '_ {
+        (0..Self::HEIGHT_PX)
+            .flat_map(|y| (0..Self::WIDTH_PX).map(move |x| (x, y)))
+            .map(|(x, y)| (x, y, self.get_pixel(x, y)))
+    }
+
+    /// Encodes the screen as a PNG image with each pixel scaled up `scale` times.
+    ///
+    /// Pixels that are on are black and pixels that are off are white, like on the RF Explorer's
+    /// LCD. Pixels are scaled with nearest-neighbor scaling so they stay sharp, and a `scale` of
+    /// 0 is treated as 1.
+    #[cfg(feature = "image")]
+    pub fn to_png(&self, scale: u8) -> Vec<u8> {
+        self.encode(scale, image::ImageFormat::Png)
+    }
+
+    /// Encodes the screen as a BMP image with each pixel scaled up `scale` times.
+    ///
+    /// See [`ScreenData::to_png`].
+    #[cfg(feature = "image")]
+    pub fn to_bmp(&self, scale: u8) -> Vec<u8> {
+        self.encode(scale, image::ImageFormat::Bmp)
+    }
+
+    #[cfg(feature = "image")]
+    fn encode(&self, scale: u8, format: image::ImageFormat) -> Vec<u8> {
+        let scale = u32::from(scale.max(1));
+        let image = image::GrayImage::from_fn(
+            u32::from(Self::WIDTH_PX) * scale,
+            u32::from(Self::HEIGHT_PX) * scale,
+            |x, y| {
+                // The coordinates are within the scaled screen, so they fit in a u8 once unscaled
+                let is_on = self.get_pixel((x / scale) as u8, (y / scale) as u8);
+                image::Luma([if is_on { 0 } else { 255 }])
+            },
+        );
+        let mut bytes = io::Cursor::new(Vec::new());
+        // Writing to memory can't fail, and the image is small enough for every format
+        image
+            .write_to(&mut bytes, format)
+            .expect("screen data should be encodable");
+        bytes.into_inner()
+    }
+
     /// The time at which this `ScreenData` was captured.
     pub fn timestamp(&self) -> DateTime<Utc> {
         self.timestamp
     }
+
+    /// The position of this `ScreenData` among the ones received from the RF Explorer since
+    /// connecting to it, starting at 1.
+    ///
+    /// Screen data that wasn't received from an RF Explorer, like screen data parsed from bytes,
+    /// has a sequence number of 0.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns whether every pixel is the same as in the other screen data.
+    pub fn has_same_pixels(&self, other: &ScreenData) -> bool {
+        self.screen_data_matrix == other.screen_data_matrix
+    }
+
+    pub(crate) fn with_sequence(self, sequence: u64) -> Self {
+        Self { sequence, ..self }
+    }
 }
 
 impl<'a> TryFrom<&'a [u8]> for ScreenData {
@@ -86,6 +152,86 @@ impl<'a> TryFrom<&'a [u8]> for ScreenData {
         Ok(ScreenData {
             screen_data_matrix,
             timestamp: Utc::now(),
+            sequence: 0,
         })
     }
 }
+
+impl WriteMessage for ScreenData {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(Self::PREFIX)?;
+        for row in self.screen_data_matrix.iter() {
+            writer.write_all(row)?;
+        }
+        writer.write_all(b"\r\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_screen_data() {
+        let mut bytes = ScreenData::PREFIX.to_vec();
+        bytes.extend((0..ScreenData::ROWS * ScreenData::COLUMNS).map(|i| (i % 251) as u8));
+        bytes.extend(b"\r\n");
+
+        let screen_data = ScreenData::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(screen_data.to_bytes(), bytes);
+
+        let written_bytes = screen_data.to_bytes();
+        let round_tripped = ScreenData::try_from(written_bytes.as_slice()).unwrap();
+        assert_eq!(
+            round_tripped.screen_data_matrix,
+            screen_data.screen_data_matrix
+        );
+    }
+
+    /// Returns screen data with the top-left pixel and the pixel at (5, 9) on.
+    fn screen_data_with_two_pixels() -> ScreenData {
+        let mut bytes = ScreenData::PREFIX.to_vec();
+        bytes.extend([0; ScreenData::ROWS * ScreenData::COLUMNS]);
+        bytes[2] = 0b1;
+        bytes[2 + ScreenData::COLUMNS + 5] = 0b10;
+        ScreenData::try_from(bytes.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn iterate_over_pixels_row_by_row() {
+        let screen_data = screen_data_with_two_pixels();
+        let pixels: Vec<_> = screen_data.pixels().collect();
+        assert_eq!(pixels.len(), 128 * 64);
+        assert_eq!(pixels[0], (0, 0, true));
+        assert_eq!(pixels[1], (1, 0, false));
+        assert_eq!(pixels[128], (0, 1, false));
+        assert_eq!(pixels.last(), Some(&(127, 63, false)));
+        let on: Vec<_> = pixels
+            .into_iter()
+            .filter(|(_, _, is_on)| *is_on)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert_eq!(on, [(0, 0), (5, 9)]);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn encode_scaled_images() {
+        let screen_data = screen_data_with_two_pixels();
+        for (bytes, format) in [
+            (screen_data.to_png(4), image::ImageFormat::Png),
+            (screen_data.to_bmp(4), image::ImageFormat::Bmp),
+        ] {
+            let image = image::load_from_memory_with_format(&bytes, format)
+                .unwrap()
+                .into_luma8();
+            assert_eq!(image.dimensions(), (512, 256));
+            assert_eq!(image.get_pixel(3, 3).0, [0]);
+            assert_eq!(image.get_pixel(4, 0).0, [255]);
+            assert_eq!(image.get_pixel(5 * 4 + 3, 9 * 4).0, [0]);
+            assert_eq!(image.get_pixel(511, 255).0, [255]);
+        }
+        // A scale of 0 is treated as 1
+        assert_eq!(screen_data.to_png(0), screen_data.to_png(1));
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    fmt,
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use super::{CallbackThreads, ConfigCallback, MESSAGE_READER, ScreenData, check_not_held};
+use crate::{
+    Error, Result,
+    common::{AsyncSignal, Disconnection},
+};
+
+/// The most recent `ScreenData` received from an RF Explorer and the callback that is called with
+/// each one.
+///
+/// Spectrum analyzers and signal generators both keep their screen data in one, so it's tagged,
+/// delivered, and waited for the same way by both.
+#[derive(Default)]
+pub(crate) struct ScreenDataCache {
+    latest: Mutex<Option<ScreenData>>,
+    condvar: Condvar,
+    signal: AsyncSignal,
+    callback: Mutex<ConfigCallback<ScreenData>>,
+}
+
+impl ScreenDataCache {
+    /// The number of bytes each `ScreenData` uses, including its pixels.
+    pub(crate) const SCREEN_DATA_LEN: usize =
+        size_of::<ScreenData>() + ScreenData::ROWS * ScreenData::COLUMNS;
+
+    pub(crate) fn latest(&self) -> Option<ScreenData> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set_callback(&self, cb: impl Fn(ScreenData) + Send + Sync + 'static) {
+        *self.callback.lock().unwrap() = Some(Arc::new(Box::new(cb)));
+    }
+
+    pub(crate) fn remove_callback(&self) {
+        *self.callback.lock().unwrap() = None;
+    }
+
+    /// Tags received screen data with the next sequence number, caches it, and calls the callback
+    /// with it on a new thread so the callback can't block reading from the RF Explorer.
+    ///
+    /// The callback isn't called if too many callbacks are already running.
+    ///
+    /// Returns the tagged screen data.
+    pub(crate) fn cache(
+        &self,
+        screen_data: ScreenData,
+        callback_threads: &CallbackThreads,
+    ) -> ScreenData {
+        let mut latest = self.latest.lock().unwrap();
+        let sequence = latest.as_ref().map_or(0, ScreenData::sequence) + 1;
+        let screen_data = screen_data.with_sequence(sequence);
+        *latest = Some(screen_data.clone());
+        drop(latest);
+        self.condvar.notify_all();
+        self.signal.notify();
+
+        let cb = self.callback.lock().unwrap().clone();
+        if let Some(cb) = cb {
+            let screen_data = screen_data.clone();
+            callback_threads.spawn(move || cb(screen_data));
+        }
+        screen_data
+    }
+
+    /// Waits for screen data received after the latest one.
+    pub(crate) fn wait_for_next(
+        &self,
+        timeout: Duration,
+        disconnection: &Disconnection,
+    ) -> Result<ScreenData> {
+        self.wait_for(timeout, disconnection, |previous, next| {
+            previous.is_none_or(|previous| next.sequence() > previous.sequence())
+        })
+    }
+
+    /// Waits for screen data whose pixels differ from the latest one's.
+    pub(crate) fn wait_for_changed(
+        &self,
+        timeout: Duration,
+        disconnection: &Disconnection,
+    ) -> Result<ScreenData> {
+        self.wait_for(timeout, disconnection, |previous, next| {
+            previous.is_none_or(|previous| !next.has_same_pixels(previous))
+        })
+    }
+
+    /// Returns a future that resolves to the first screen data received after this is called.
+    #[cfg(feature = "async")]
+    pub(crate) fn wait_for_next_async<'a>(
+        &'a self,
+        timeout: Duration,
+        disconnection: &'a Disconnection,
+    ) -> impl Future<Output = Result<ScreenData>> + Send + 'a {
+        let previous_sequence = self
+            .latest
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(ScreenData::sequence);
+        self.signal.wait_until(timeout, move || {
+            if let Err(error) = disconnection.check() {
+                return Some(Err(error));
+            }
+            self.latest
+                .lock()
+                .unwrap()
+                .as_ref()
+                .filter(|next| previous_sequence.is_none_or(|previous| next.sequence() > previous))
+                .map(|next| Ok(next.clone()))
+        })
+    }
+
+    fn wait_for(
+        &self,
+        timeout: Duration,
+        disconnection: &Disconnection,
+        is_wanted: impl Fn(Option<&ScreenData>, &ScreenData) -> bool,
+    ) -> Result<ScreenData> {
+        check_not_held(MESSAGE_READER);
+        let latest = self.latest.lock().unwrap();
+        let previous = latest.clone();
+        let (latest, _) = self
+            .condvar
+            .wait_timeout_while(latest, timeout, |latest| {
+                !latest
+                    .as_ref()
+                    .is_some_and(|next| is_wanted(previous.as_ref(), next))
+                    && !disconnection.is_disconnected()
+            })
+            .unwrap();
+        disconnection.check()?;
+
+        match &*latest {
+            Some(next) if is_wanted(previous.as_ref(), next) => Ok(next.clone()),
+            _ => Err(Error::TimedOut(timeout)),
+        }
+    }
+
+    /// Returns an estimate of the memory used by the cached screen data, in bytes.
+    pub(crate) fn memory_usage_estimate(&self) -> usize {
+        if self.latest.lock().unwrap().is_some() {
+            Self::SCREEN_DATA_LEN
+        } else {
+            0
+        }
+    }
+
+    /// Wakes every waiter so they can return once the RF Explorer is disconnected.
+    pub(crate) fn notify_disconnected(&self) {
+        // Lock the screen data before notifying so waiters can't miss the wake up between
+        // checking whether they've been disconnected and starting to wait
+        let _latest = self.latest.lock().unwrap();
+        self.condvar.notify_all();
+        self.signal.notify();
+    }
+}
+
+impl fmt::Debug for ScreenDataCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.latest.lock().unwrap().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, sync::mpsc, thread, time::Instant};
+
+    use super::*;
+
+    fn screen_data(first_byte: u8) -> ScreenData {
+        let mut bytes = ScreenData::PREFIX.to_vec();
+        bytes.push(first_byte);
+        bytes.extend([0; 8 * 128 - 1]);
+        ScreenData::try_from(bytes.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn tag_screen_data_with_sequence_numbers() {
+        let cache = ScreenDataCache::default();
+        assert_eq!(screen_data(0).sequence(), 0);
+        assert_eq!(
+            cache
+                .cache(screen_data(0), &CallbackThreads::default())
+                .sequence(),
+            1
+        );
+        assert_eq!(
+            cache
+                .cache(screen_data(0), &CallbackThreads::default())
+                .sequence(),
+            2
+        );
+        assert_eq!(cache.latest().unwrap().sequence(), 2);
+    }
+
+    #[test]
+    fn call_callback_with_tagged_screen_data() {
+        let cache = ScreenDataCache::default();
+        let (tx, rx) = mpsc::channel();
+        cache.set_callback(move |screen_data| tx.send(screen_data.sequence()).unwrap());
+        cache.cache(screen_data(0), &CallbackThreads::default());
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(1));
+
+        cache.remove_callback();
+        cache.cache(screen_data(0), &CallbackThreads::default());
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+
+    #[test]
+    fn wait_for_next_screen_data() {
+        let cache = Arc::new(ScreenDataCache::default());
+        let disconnection = Disconnection::default();
+        cache.cache(screen_data(0), &CallbackThreads::default());
+        assert!(matches!(
+            cache.wait_for_next(Duration::from_millis(10), &disconnection),
+            Err(Error::TimedOut(_))
+        ));
+
+        let sender = cache.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            sender.cache(screen_data(0), &CallbackThreads::default());
+        });
+        let next = cache
+            .wait_for_next(Duration::from_secs(1), &disconnection)
+            .unwrap();
+        assert_eq!(next.sequence(), 2);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn wait_for_screen_data_with_different_pixels() {
+        let cache = Arc::new(ScreenDataCache::default());
+        let disconnection = Disconnection::default();
+        cache.cache(screen_data(0), &CallbackThreads::default());
+
+        let sender = cache.clone();
+        let handle = thread::spawn(move || {
+            for first_byte in [0, 0, 1] {
+                thread::sleep(Duration::from_millis(10));
+                sender.cache(screen_data(first_byte), &CallbackThreads::default());
+            }
+        });
+        let changed = cache
+            .wait_for_changed(Duration::from_secs(1), &disconnection)
+            .unwrap();
+        assert_eq!(changed.sequence(), 4);
+        assert!(changed.get_pixel(0, 0));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn stop_waiting_when_disconnected() {
+        let cache = ScreenDataCache::default();
+        let disconnection = Disconnection::default();
+        disconnection.set(io::Error::from(io::ErrorKind::BrokenPipe));
+        cache.notify_disconnected();
+
+        let start = Instant::now();
+        assert!(matches!(
+            cache.wait_for_changed(Duration::from_secs(10), &disconnection),
+            Err(Error::Disconnected(_))
+        ));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::{Command, ScreenData};
+
+/// How an RF Explorer sends [`ScreenData`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum ScreenDumpMode {
+    /// The RF Explorer doesn't send its screen.
+    #[default]
+    Off,
+    /// The RF Explorer sends every frame of its screen, which slows down its sweeps.
+    Continuous,
+    /// One frame is captured at the interval and the RF Explorer is told to stop sending its
+    /// screen in between, so its sweep rate recovers between frames.
+    Interval(Duration),
+}
+
+/// Captures one frame of the screen at a time when the screen dump mode is
+/// [`ScreenDumpMode::Interval`].
+///
+/// The reader thread calls [`ScreenDumpTimer::tick`] and [`ScreenDumpTimer::on_message`] and
+/// sends the commands they return, so no extra thread is needed.
+#[derive(Debug, Default)]
+pub(crate) struct ScreenDumpTimer {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    mode: ScreenDumpMode,
+    phase: Phase,
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+enum Phase {
+    /// Screen dumping is off until the next capture is due.
+    #[default]
+    Idle,
+    /// Screen dumping is off and the next capture starts at the instant.
+    Waiting(Instant),
+    /// Screen dumping was turned on at the instant and the next frame is being waited for.
+    Capturing(Instant),
+}
+
+impl ScreenDumpTimer {
+    /// How long to wait after turning screen dumping on or off before toggling it again.
+    ///
+    /// Some RF Explorers ignore a toggle that arrives right after the previous one.
+    const SETTLING_DELAY: Duration = Duration::from_millis(50);
+
+    /// How long to wait for a frame before turning screen dumping off and trying again at the next
+    /// interval.
+    const CAPTURE_TIMEOUT: Duration = super::NEXT_SCREEN_DATA_TIMEOUT;
+
+    pub(crate) fn mode(&self) -> ScreenDumpMode {
+        self.state.lock().unwrap().mode
+    }
+
+    /// Sets the mode and returns the command that starts it.
+    pub(crate) fn set_mode(&self, mode: ScreenDumpMode, now: Instant) -> Command {
+        let mut state = self.state.lock().unwrap();
+        state.mode = mode;
+        match mode {
+            ScreenDumpMode::Off => {
+                state.phase = Phase::Idle;
+                Command::DisableDumpScreen
+            }
+            ScreenDumpMode::Continuous => {
+                state.phase = Phase::Idle;
+                Command::EnableDumpScreen
+            }
+            ScreenDumpMode::Interval(_) => {
+                state.phase = Phase::Capturing(now);
+                Command::EnableDumpScreen
+            }
+        }
+    }
+
+    /// Returns the command to send if a capture is due or has timed out.
+    pub(crate) fn tick(&self, now: Instant) -> Option<Command> {
+        let mut state = self.state.lock().unwrap();
+        let ScreenDumpMode::Interval(interval) = state.mode else {
+            return None;
+        };
+        match state.phase {
+            Phase::Waiting(next_capture) if now >= next_capture => {
+                state.phase = Phase::Capturing(now);
+                Some(Command::EnableDumpScreen)
+            }
+            Phase::Capturing(since) if now >= since + Self::CAPTURE_TIMEOUT => {
+                state.phase = Phase::Waiting(Self::next_capture(since, interval, now));
+                Some(Command::DisableDumpScreen)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the command to send after a message is received, which turns screen dumping off
+    /// once a frame has been captured.
+    pub(crate) fn on_message(&self, message: &[u8], now: Instant) -> Option<Command> {
+        let mut state = self.state.lock().unwrap();
+        match (state.mode, state.phase) {
+            (ScreenDumpMode::Interval(interval), Phase::Capturing(since))
+                if message.starts_with(ScreenData::PREFIX) =>
+            {
+                state.phase = Phase::Waiting(Self::next_capture(since, interval, now));
+                Some(Command::DisableDumpScreen)
+            }
+            _ => None,
+        }
+    }
+
+    fn next_capture(since: Instant, interval: Duration, now: Instant) -> Instant {
+        (since + interval).max(now + Self::SETTLING_DELAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_one_frame_per_interval() {
+        let timer = ScreenDumpTimer::default();
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        let interval = ScreenDumpMode::Interval(Duration::from_secs(1));
+
+        assert_eq!(timer.set_mode(interval, start), Command::EnableDumpScreen);
+        assert_eq!(timer.tick(ms(100)), None);
+        assert_eq!(timer.on_message(b"$S\x02\x50\x50", ms(120)), None);
+        assert_eq!(
+            timer.on_message(b"$D", ms(150)),
+            Some(Command::DisableDumpScreen)
+        );
+        assert_eq!(timer.on_message(b"$D", ms(160)), None);
+        assert_eq!(timer.tick(ms(999)), None);
+        assert_eq!(timer.tick(ms(1000)), Some(Command::EnableDumpScreen));
+
+        // Give up on a frame that never arrives and try again after the settling delay
+        assert_eq!(timer.tick(ms(3000)), Some(Command::DisableDumpScreen));
+        assert_eq!(timer.tick(ms(3049)), None);
+        assert_eq!(timer.tick(ms(3050)), Some(Command::EnableDumpScreen));
+    }
+
+    #[test]
+    fn only_toggle_in_interval_mode() {
+        let timer = ScreenDumpTimer::default();
+        let now = Instant::now();
+        assert_eq!(
+            timer.set_mode(ScreenDumpMode::Continuous, now),
+            Command::EnableDumpScreen
+        );
+        assert_eq!(timer.on_message(b"$D", now), None);
+        assert_eq!(timer.tick(now + Duration::from_secs(10)), None);
+        assert_eq!(timer.mode(), ScreenDumpMode::Continuous);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt::Display, str};
+use std::{fmt::Display, io, str, sync::Arc};
 
 use nom::{
     AsChar, Parser,
@@ -8,11 +8,11 @@ use nom::{
 };
 
 use super::parsers::*;
-use crate::common::MessageParseError;
+use crate::common::{MessageParseError, WriteMessage};
 
 #[derive(Debug, Clone, Eq, PartialEq, Default)]
 pub(crate) struct SerialNumber {
-    serial_number: String,
+    serial_number: Arc<str>,
 }
 
 impl SerialNumber {
@@ -21,6 +21,11 @@ impl SerialNumber {
     pub fn as_str(&self) -> &str {
         &self.serial_number
     }
+
+    /// Returns the serial number without copying it.
+    pub(crate) fn as_shared_str(&self) -> Arc<str> {
+        self.serial_number.clone()
+    }
 }
 
 impl<'a> TryFrom<&'a [u8]> for SerialNumber {
@@ -31,7 +36,7 @@ impl<'a> TryFrom<&'a [u8]> for SerialNumber {
             tag(SerialNumber::PREFIX),
             map(
                 map_res(take_while_m_n(16, 16, AsChar::is_alphanum), str::from_utf8),
-                str::to_string,
+                Arc::from,
             ),
         )
         .parse(bytes)?;
@@ -43,6 +48,13 @@ impl<'a> TryFrom<&'a [u8]> for SerialNumber {
     }
 }
 
+impl WriteMessage for SerialNumber {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(SerialNumber::PREFIX)?;
+        write!(writer, "{}\r\n", self.serial_number)
+    }
+}
+
 impl AsRef<str> for SerialNumber {
     fn as_ref(&self) -> &str {
         &self.serial_number
@@ -69,4 +81,15 @@ mod tests {
         assert!(SerialNumber::try_from(b"#Sn0SME38SI2X7NGR48".as_ref()).is_ok());
         assert!(SerialNumber::try_from(b"#SnB3AK7AL7CACAA74M\r\n".as_ref()).is_ok());
     }
+
+    #[test]
+    fn write_serial_number() {
+        let bytes = b"#SnB3AK7AL7CACAA74M\r\n";
+        let serial_number = SerialNumber::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(serial_number.to_bytes(), bytes);
+        assert_eq!(
+            SerialNumber::try_from(serial_number.to_bytes().as_slice()),
+            Ok(serial_number)
+        );
+    }
 }
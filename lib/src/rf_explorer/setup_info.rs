@@ -1,4 +1,4 @@
-use std::{fmt::Debug, str};
+use std::{fmt::Debug, io, str, sync::Arc};
 
 use nom::{
     Parser,
@@ -17,7 +17,8 @@ pub(crate) struct SetupInfo<
 > {
     pub main_radio_model: Option<M>,
     pub expansion_radio_model: Option<M>,
-    pub firmware_version: String,
+    /// Shared so that the firmware version can be handed out without copying it.
+    pub firmware_version: Arc<str>,
 }
 
 impl<M: Debug + Copy + TryFrom<u8> + Eq + PartialEq + Default> SetupInfo<M> {
@@ -58,7 +59,7 @@ impl<M: Debug + Copy + TryFrom<u8> + Eq + PartialEq + Default> SetupInfo<M> {
 
         // Parse the firmware version
         let (bytes, firmware_version) =
-            map(map_res(not_line_ending, str::from_utf8), str::to_string).parse(bytes)?;
+            map(map_res(not_line_ending, str::from_utf8), Arc::from).parse(bytes)?;
 
         // Consume \r or \r\n line ending and make sure there aren't any bytes left
         let _ = parse_opt_line_ending(bytes)?;
@@ -69,4 +70,25 @@ impl<M: Debug + Copy + TryFrom<u8> + Eq + PartialEq + Default> SetupInfo<M> {
             firmware_version,
         })
     }
+
+    /// Writes the setup info after the given prefix.
+    pub(crate) fn write_with_prefix(
+        &self,
+        prefix: &[u8],
+        writer: &mut impl io::Write,
+    ) -> io::Result<()>
+    where
+        M: Into<u8>,
+    {
+        // Radio modules that don't exist are sent as 255
+        let model_num = |model: Option<M>| model.map_or(u8::MAX, Into::into);
+        writer.write_all(prefix)?;
+        write!(
+            writer,
+            "{:03},{:03},{}\r\n",
+            model_num(self.main_radio_model),
+            model_num(self.expansion_radio_model),
+            self.firmware_version
+        )
+    }
 }
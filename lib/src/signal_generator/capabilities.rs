@@ -0,0 +1,57 @@
+use std::ops::RangeInclusive;
+
+use super::Model;
+use crate::Frequency;
+
+/// Features supported by a connected signal generator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    /// The model of the active radio module.
+    pub active_radio_model: Model,
+    /// Whether the RF Explorer has an expansion radio module.
+    pub has_expansion: bool,
+    /// Whether output power can be set in dBm using the expansion radio module.
+    pub supports_expansion_power_control: bool,
+    /// Whether the signal generator supports tracking mode.
+    pub supports_tracking: bool,
+    /// The active radio module's supported output frequency range.
+    pub freq_range: RangeInclusive<Frequency>,
+}
+
+impl Capabilities {
+    pub(crate) fn new(active_radio_model: Model, expansion_radio_model: Option<Model>) -> Self {
+        let has_expansion = expansion_radio_model.is_some();
+        Self {
+            active_radio_model,
+            has_expansion,
+            // The expansion module's commands take the output power in dBm
+            supports_expansion_power_control: has_expansion,
+            // Every radio module has a command to start tracking mode
+            supports_tracking: true,
+            freq_range: active_radio_model.min_freq()..=active_radio_model.max_freq(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn main_module_only_capabilities() {
+        let capabilities = Capabilities::new(Model::Rfe6Gen, None);
+        assert!(!capabilities.has_expansion);
+        assert!(!capabilities.supports_expansion_power_control);
+        assert!(capabilities.supports_tracking);
+        assert_eq!(*capabilities.freq_range.start(), Model::Rfe6Gen.min_freq());
+    }
+
+    #[test]
+    fn expansion_module_capabilities() {
+        let capabilities =
+            Capabilities::new(Model::Rfe6GenExpansion, Some(Model::Rfe6GenExpansion));
+        assert!(capabilities.has_expansion);
+        assert!(capabilities.supports_expansion_power_control);
+        assert_eq!(*capabilities.freq_range.start(), Frequency::from_khz(100));
+    }
+}
@@ -1,11 +1,22 @@
 use super::{Attenuation, PowerLevel};
 use crate::common::Frequency;
+use crate::rf_explorer::{frame_body, text_fields};
 use std::{borrow::Cow, time::Duration};
 
+/// A command understood by RF Explorer signal generators.
+///
+/// Commands understood by spectrum analyzers too are in [`rfe::Command`](crate::Command).
+/// Frequencies are sent in kHz, step delays in ms, and expansion module powers in dBm with one
+/// decimal place. New commands may be added in minor releases, so matches on this enum need a
+/// wildcard arm.
 #[derive(Debug, Copy, Clone, PartialEq)]
-pub(crate) enum Command {
+#[non_exhaustive]
+pub enum Command {
+    /// Turns on the RF output.
     RfPowerOn,
+    /// Turns off the RF output.
     RfPowerOff,
+    /// Starts an amplitude sweep on the main module.
     StartAmpSweep {
         cw: Frequency,
         start_attenuation: Attenuation,
@@ -14,6 +25,7 @@ pub(crate) enum Command {
         stop_power_level: PowerLevel,
         step_delay: Duration,
     },
+    /// Starts an amplitude sweep on the expansion module.
     StartAmpSweepExp {
         cw: Frequency,
         start_power_dbm: f64,
@@ -21,15 +33,15 @@ pub(crate) enum Command {
         stop_power_dbm: f64,
         step_delay: Duration,
     },
+    /// Starts a CW output on the main module.
     StartCw {
         cw: Frequency,
         attenuation: Attenuation,
         power_level: PowerLevel,
     },
-    StartCwExp {
-        cw: Frequency,
-        power_dbm: f64,
-    },
+    /// Starts a CW output on the expansion module.
+    StartCwExp { cw: Frequency, power_dbm: f64 },
+    /// Starts a frequency sweep on the main module.
     StartFreqSweep {
         start: Frequency,
         attenuation: Attenuation,
@@ -38,6 +50,7 @@ pub(crate) enum Command {
         step: Frequency,
         step_delay: Duration,
     },
+    /// Starts a frequency sweep on the expansion module.
     StartFreqSweepExp {
         start: Frequency,
         power_dbm: f64,
@@ -45,6 +58,7 @@ pub(crate) enum Command {
         step: Frequency,
         step_delay: Duration,
     },
+    /// Starts tracking a spectrum analyzer with the main module.
     StartTracking {
         start: Frequency,
         attenuation: Attenuation,
@@ -52,15 +66,161 @@ pub(crate) enum Command {
         sweep_steps: u16,
         step: Frequency,
     },
+    /// Starts tracking a spectrum analyzer with the expansion module.
     StartTrackingExp {
         start: Frequency,
         power_dbm: f64,
         sweep_steps: u16,
         step: Frequency,
     },
+    /// Moves tracking to a step.
     TrackingStep(u16),
 }
 
+impl Command {
+    /// Returns the bytes sent to the signal generator for the command.
+    pub fn to_bytes(self) -> Cow<'static, [u8]> {
+        self.into()
+    }
+
+    /// Returns a short description of what the command does.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Command::RfPowerOn => "RF power on",
+            Command::RfPowerOff => "RF power off",
+            Command::StartAmpSweep { .. } => "Start amplitude sweep",
+            Command::StartAmpSweepExp { .. } => "Start expansion amplitude sweep",
+            Command::StartCw { .. } => "Start CW",
+            Command::StartCwExp { .. } => "Start expansion CW",
+            Command::StartFreqSweep { .. } => "Start frequency sweep",
+            Command::StartFreqSweepExp { .. } => "Start expansion frequency sweep",
+            Command::StartTracking { .. } => "Start tracking",
+            Command::StartTrackingExp { .. } => "Start expansion tracking",
+            Command::TrackingStep(_) => "Tracking step",
+        }
+    }
+
+    /// Returns every command that doesn't take parameters.
+    pub fn parameterless() -> impl Iterator<Item = Command> {
+        [Command::RfPowerOn, Command::RfPowerOff].into_iter()
+    }
+
+    /// Recognizes a complete command frame, like one captured by sniffing the serial port.
+    ///
+    /// Returns `None` if the bytes aren't exactly one command this enum can represent.
+    pub fn parse(bytes: &[u8]) -> Option<Command> {
+        if let Some(command) = Self::parameterless().find(|command| command.to_bytes() == bytes) {
+            return Some(command);
+        }
+        let body = frame_body(bytes)?;
+        if let [b'k', high, low] = *body {
+            return Some(Command::TrackingStep(u16::from_be_bytes([high, low])));
+        }
+
+        let khz = |field: &str| field.parse().ok().map(Frequency::from_khz);
+        let ms = |field: &str| field.parse().ok().map(Duration::from_millis);
+        let attenuation = |field: &str| Attenuation::try_from(field.parse::<u8>().ok()?).ok();
+        let power_level = |field: &str| PowerLevel::try_from(field.parse::<u8>().ok()?).ok();
+        let prefix = body.get(..5)?;
+        let fields = text_fields(body, str::from_utf8(prefix).ok()?)?;
+        match (prefix, &fields[..]) {
+            (
+                b"C3-A:",
+                [
+                    cw,
+                    start_attenuation,
+                    start_power_level,
+                    stop_attenuation,
+                    stop_power_level,
+                    step_delay,
+                ],
+            ) => Some(Command::StartAmpSweep {
+                cw: khz(cw)?,
+                start_attenuation: attenuation(start_attenuation)?,
+                start_power_level: power_level(start_power_level)?,
+                stop_attenuation: attenuation(stop_attenuation)?,
+                stop_power_level: power_level(stop_power_level)?,
+                step_delay: ms(step_delay)?,
+            }),
+            (
+                b"C5-A:",
+                [
+                    cw,
+                    start_power_dbm,
+                    step_power_db,
+                    stop_power_dbm,
+                    step_delay,
+                ],
+            ) => Some(Command::StartAmpSweepExp {
+                cw: khz(cw)?,
+                start_power_dbm: start_power_dbm.parse().ok()?,
+                step_power_db: step_power_db.parse().ok()?,
+                stop_power_dbm: stop_power_dbm.parse().ok()?,
+                step_delay: ms(step_delay)?,
+            }),
+            (b"C3-F:", [cw, attenuation_field, power_level_field]) => Some(Command::StartCw {
+                cw: khz(cw)?,
+                attenuation: attenuation(attenuation_field)?,
+                power_level: power_level(power_level_field)?,
+            }),
+            (b"C5-F:", [cw, power_dbm]) => Some(Command::StartCwExp {
+                cw: khz(cw)?,
+                power_dbm: power_dbm.parse().ok()?,
+            }),
+            (
+                b"C3-F:",
+                [
+                    start,
+                    attenuation_field,
+                    power_level_field,
+                    sweep_steps,
+                    step,
+                    step_delay,
+                ],
+            ) => Some(Command::StartFreqSweep {
+                start: khz(start)?,
+                attenuation: attenuation(attenuation_field)?,
+                power_level: power_level(power_level_field)?,
+                sweep_steps: sweep_steps.parse().ok()?,
+                step: khz(step)?,
+                step_delay: ms(step_delay)?,
+            }),
+            (b"C5-F:", [start, power_dbm, sweep_steps, step, step_delay]) => {
+                Some(Command::StartFreqSweepExp {
+                    start: khz(start)?,
+                    power_dbm: power_dbm.parse().ok()?,
+                    sweep_steps: sweep_steps.parse().ok()?,
+                    step: khz(step)?,
+                    step_delay: ms(step_delay)?,
+                })
+            }
+            (
+                b"C3-T:",
+                [
+                    start,
+                    attenuation_field,
+                    power_level_field,
+                    sweep_steps,
+                    step,
+                ],
+            ) => Some(Command::StartTracking {
+                start: khz(start)?,
+                attenuation: attenuation(attenuation_field)?,
+                power_level: power_level(power_level_field)?,
+                sweep_steps: sweep_steps.parse().ok()?,
+                step: khz(step)?,
+            }),
+            (b"C5-T:", [start, power_dbm, sweep_steps, step]) => Some(Command::StartTrackingExp {
+                start: khz(start)?,
+                power_dbm: power_dbm.parse().ok()?,
+                sweep_steps: sweep_steps.parse().ok()?,
+                step: khz(step)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
 impl From<Command> for Cow<'static, [u8]> {
     fn from(command: Command) -> Cow<'static, [u8]> {
         match command {
@@ -298,4 +458,114 @@ mod tests {
         });
         assert_correct_size!(Command::TrackingStep(10));
     }
+
+    #[test]
+    fn match_documented_byte_sequences() {
+        assert_eq!(Command::RfPowerOn.to_bytes(), &b"#\x05CP1"[..]);
+        assert_eq!(
+            Command::StartCw {
+                cw: Frequency::from_mhz(1_000),
+                attenuation: Attenuation::Off,
+                power_level: PowerLevel::Highest
+            }
+            .to_bytes(),
+            &b"#\x12C3-F:1000000,1,3"[..]
+        );
+        assert_eq!(
+            Command::StartCwExp {
+                cw: Frequency::from_mhz(2_400),
+                power_dbm: -10.
+            }
+            .to_bytes(),
+            &b"#\x14C5-F:2400000,-10.0"[..]
+        );
+        assert_eq!(
+            Command::StartFreqSweep {
+                start: Frequency::from_mhz(1_000),
+                attenuation: Attenuation::On,
+                power_level: PowerLevel::Low,
+                sweep_steps: 10,
+                step: Frequency::from_mhz(1),
+                step_delay: Duration::from_millis(500)
+            }
+            .to_bytes(),
+            &b"#\x25C3-F:1000000,0,1,0010,0001000,00500"[..]
+        );
+        assert_eq!(
+            Command::StartAmpSweepExp {
+                cw: Frequency::from_mhz(100),
+                start_power_dbm: -40.,
+                step_power_db: 2.,
+                stop_power_dbm: 0.,
+                step_delay: Duration::from_secs(1)
+            }
+            .to_bytes(),
+            &b"#\x26C5-A:0100000,-40.0,+02.0,000.0,01000"[..]
+        );
+    }
+
+    #[test]
+    fn parse_sniffed_commands() {
+        let commands = [
+            Command::StartAmpSweep {
+                cw: Frequency::from_mhz(100),
+                start_attenuation: Attenuation::On,
+                start_power_level: PowerLevel::Low,
+                stop_attenuation: Attenuation::Off,
+                stop_power_level: PowerLevel::Highest,
+                step_delay: Duration::from_secs(1),
+            },
+            Command::StartAmpSweepExp {
+                cw: Frequency::from_mhz(100),
+                start_power_dbm: -40.,
+                step_power_db: 2.5,
+                stop_power_dbm: 0.,
+                step_delay: Duration::from_secs(1),
+            },
+            Command::StartCw {
+                cw: Frequency::from_mhz(1),
+                attenuation: Attenuation::Off,
+                power_level: PowerLevel::Low,
+            },
+            Command::StartCwExp {
+                cw: Frequency::from_ghz(1),
+                power_dbm: 10.,
+            },
+            Command::StartFreqSweep {
+                start: Frequency::from_ghz(1),
+                attenuation: Attenuation::Off,
+                power_level: PowerLevel::High,
+                sweep_steps: 10,
+                step: Frequency::from_mhz(1),
+                step_delay: Duration::from_secs(2),
+            },
+            Command::StartFreqSweepExp {
+                start: Frequency::from_ghz(1),
+                power_dbm: -10.,
+                sweep_steps: 10,
+                step: Frequency::from_mhz(1),
+                step_delay: Duration::from_secs(2),
+            },
+            Command::StartTracking {
+                start: Frequency::from_ghz(1),
+                attenuation: Attenuation::Off,
+                power_level: PowerLevel::High,
+                sweep_steps: 10,
+                step: Frequency::from_mhz(1),
+            },
+            Command::StartTrackingExp {
+                start: Frequency::from_ghz(1),
+                power_dbm: -10.,
+                sweep_steps: 10,
+                step: Frequency::from_mhz(1),
+            },
+            Command::TrackingStep(10),
+        ];
+        for command in commands.into_iter().chain(Command::parameterless()) {
+            assert_eq!(Command::parse(&command.to_bytes()), Some(command));
+        }
+        assert_eq!(Command::parse(b"#\x12C3-F:1000000,2,3"), None);
+        assert_eq!(Command::parse(b"#\x11C3-F:1000000,1"), None);
+        assert_eq!(Command::parse(b"#\x04C0"), None);
+    }
 }
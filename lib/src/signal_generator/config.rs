@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{io, time::Duration};
 
 use chrono::{DateTime, Utc};
 use nom::{
@@ -8,7 +8,7 @@ use nom::{
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::{
-    common::{Frequency, MessageParseError},
+    common::{Frequency, MessageParseError, WriteMessage},
     rf_explorer::parsers::*,
     signal_generator::parsers::*,
 };
@@ -179,6 +179,29 @@ impl<'a> TryFrom<&'a [u8]> for Config {
     }
 }
 
+impl WriteMessage for Config {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(Config::PREFIX)?;
+        write!(
+            writer,
+            "{:07},{:07},{:04},{:07},{},{},{:04},{},{},{},{},{},{:05}\r\n",
+            self.start.as_khz(),
+            self.cw.as_khz(),
+            self.total_steps,
+            self.step.as_khz(),
+            u8::from(self.attenuation),
+            u8::from(self.power_level),
+            self.sweep_power_steps,
+            u8::from(self.start_attenuation),
+            u8::from(self.start_power_level),
+            u8::from(self.stop_attenuation),
+            u8::from(self.stop_power_level),
+            u8::from(self.rf_power),
+            self.sweep_delay.as_millis(),
+        )
+    }
+}
+
 /// Expansion-module signal generator configuration.
 #[derive(Debug, Copy, Clone, Default, PartialEq)]
 pub struct ConfigExp {
@@ -285,6 +308,27 @@ impl<'a> TryFrom<&'a [u8]> for ConfigExp {
     }
 }
 
+impl WriteMessage for ConfigExp {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(Self::PREFIX)?;
+        write!(
+            writer,
+            "{:07},{:07},{:04},{:07},{:+05.1},{:+05.1},{:+05.1},{:+05.1},{},{:05}\r\n",
+            self.start.as_khz(),
+            self.cw.as_khz(),
+            self.total_steps,
+            self.step.as_khz(),
+            self.power_dbm,
+            self.step_power_dbm,
+            self.start_power_dbm,
+            self.stop_power_dbm,
+            // RF power is sent as 0 when it's on
+            if self.rf_power_on { 0 } else { 1 },
+            self.sweep_delay.as_millis(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -307,4 +351,39 @@ mod tests {
         assert_eq!(config.rf_power, RfPower::On);
         assert_eq!(config.sweep_delay.as_millis(), 100);
     }
+
+    #[test]
+    fn write_config() {
+        let bytes = b"#C3-*:0510000,0186525,0005,0001000,0,3,0000,0,0,1,3,0,00100\r\n";
+        let config = Config::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(config.to_bytes(), bytes);
+
+        let round_tripped = Config::try_from(config.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            Config {
+                timestamp: config.timestamp,
+                ..round_tripped
+            },
+            config
+        );
+    }
+
+    #[test]
+    fn write_config_exp() {
+        let bytes = b"#C5-*:0510000,0186525,0005,0001000,+05.0,+01.5,-30.0,-10.5,0,00100\r\n";
+        let config = ConfigExp::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(config.power_dbm, 5.);
+        assert_eq!(config.stop_power_dbm, -10.5);
+        assert!(config.rf_power_on);
+        assert_eq!(config.to_bytes(), bytes);
+
+        let round_tripped = ConfigExp::try_from(config.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            ConfigExp {
+                timestamp: config.timestamp,
+                ..round_tripped
+            },
+            config
+        );
+    }
 }
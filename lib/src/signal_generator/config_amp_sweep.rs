@@ -1,10 +1,10 @@
-use std::time::Duration;
+use std::{io, time::Duration};
 
 use chrono::{DateTime, Utc};
 use nom::{Parser, bytes::complete::tag};
 
 use crate::{
-    common::{Frequency, MessageParseError},
+    common::{Frequency, MessageParseError, WriteMessage},
     rf_explorer::parsers::*,
     signal_generator::{Attenuation, PowerLevel, RfPower, parsers::*},
 };
@@ -98,6 +98,24 @@ impl<'a> TryFrom<&'a [u8]> for ConfigAmpSweep {
     }
 }
 
+impl WriteMessage for ConfigAmpSweep {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(Self::PREFIX)?;
+        write!(
+            writer,
+            "{:07},{:04},{},{},{},{},{},{:05}\r\n",
+            self.cw.as_khz(),
+            self.sweep_power_steps,
+            u8::from(self.start_attenuation),
+            u8::from(self.start_power_level),
+            u8::from(self.stop_attenuation),
+            u8::from(self.stop_power_level),
+            u8::from(self.rf_power),
+            self.sweep_delay.as_millis(),
+        )
+    }
+}
+
 /// Expansion-module amplitude sweep configuration.
 #[derive(Debug, Copy, Clone, Default, PartialEq)]
 pub struct ConfigAmpSweepExp {
@@ -164,6 +182,21 @@ impl<'a> TryFrom<&'a [u8]> for ConfigAmpSweepExp {
     }
 }
 
+impl WriteMessage for ConfigAmpSweepExp {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(Self::PREFIX)?;
+        write!(
+            writer,
+            "{:07},{:+05.1},{:+05.1},{:+05.1},{:05}\r\n",
+            self.cw.as_khz(),
+            self.start_power_dbm,
+            self.step_power_dbm,
+            self.stop_power_dbm,
+            self.sweep_delay.as_millis(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,4 +214,39 @@ mod tests {
         assert_eq!(config_amp_sweep.rf_power, RfPower::On);
         assert_eq!(config_amp_sweep.sweep_delay.as_millis(), 100);
     }
+
+    #[test]
+    fn write_config() {
+        let bytes = b"#C3-A:0186525,0000,0,0,1,3,0,00100\r\n";
+        let config_amp_sweep = ConfigAmpSweep::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(config_amp_sweep.to_bytes(), bytes);
+
+        let round_tripped =
+            ConfigAmpSweep::try_from(config_amp_sweep.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            ConfigAmpSweep {
+                timestamp: config_amp_sweep.timestamp,
+                ..round_tripped
+            },
+            config_amp_sweep
+        );
+    }
+
+    #[test]
+    fn write_config_exp() {
+        let bytes = b"#C5-A:2450000,-40.0,+00.5,-10.0,00250\r\n";
+        let config_amp_sweep = ConfigAmpSweepExp::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(config_amp_sweep.step_power_dbm, 0.5);
+        assert_eq!(config_amp_sweep.to_bytes(), bytes);
+
+        let round_tripped =
+            ConfigAmpSweepExp::try_from(config_amp_sweep.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            ConfigAmpSweepExp {
+                timestamp: config_amp_sweep.timestamp,
+                ..round_tripped
+            },
+            config_amp_sweep
+        );
+    }
 }
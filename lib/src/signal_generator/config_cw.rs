@@ -1,14 +1,21 @@
+use std::io;
+
 use chrono::{DateTime, Utc};
 use nom::{Parser, bytes::complete::tag};
 
 use crate::{
-    common::{Frequency, MessageParseError},
+    Error,
+    common::{Frequency, MessageParseError, WriteMessage},
     rf_explorer::parsers::*,
-    signal_generator::{Attenuation, PowerLevel, RfPower, parsers::*},
+    signal_generator::{Attenuation, Model, PowerLevel, RfPower, parsers::*},
 };
 
 /// Main-module CW configuration.
+///
+/// New fields can be added as the signal generator reports more settings, so it can only be
+/// created by the crate.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[non_exhaustive]
 pub struct ConfigCw {
     /// CW frequency.
     pub cw: Frequency,
@@ -85,6 +92,58 @@ impl<'a> TryFrom<&'a [u8]> for ConfigCw {
     }
 }
 
+impl WriteMessage for ConfigCw {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(Self::PREFIX)?;
+        // The CW frequency is sent twice
+        write!(
+            writer,
+            "{:07},{:07},{:04},{:07},{},{},{}",
+            self.cw.as_khz(),
+            self.cw.as_khz(),
+            self.total_steps,
+            self.step_freq.as_khz(),
+            u8::from(self.attenuation),
+            u8::from(self.power_level),
+            u8::from(self.rf_power),
+        )?;
+        writer.write_all(b"\r\n")
+    }
+}
+
+/// Returns the CW frequency shifted by `delta_ppm` parts per million and rounded to the nearest
+/// frequency the model can be tuned to.
+///
+/// Returns [`Error::InvalidInput`] if the shift isn't a finite number or the shifted frequency is
+/// outside of the model's frequency range.
+pub(crate) fn shift_cw_by_ppm(
+    cw: Frequency,
+    delta_ppm: f64,
+    model: Model,
+) -> crate::Result<Frequency> {
+    if !delta_ppm.is_finite() {
+        return Err(Error::InvalidInput(format!(
+            "The frequency can't be shifted by {delta_ppm} ppm"
+        )));
+    }
+
+    let resolution_hz = model.tuning_resolution().as_hz_f64();
+    let shifted_hz = cw.as_hz_f64() * (1. + delta_ppm * 1e-6);
+    let steps = (shifted_hz / resolution_hz).round();
+    let (min_freq, max_freq) = (model.min_freq(), model.max_freq());
+    if steps * resolution_hz < min_freq.as_hz_f64() || steps * resolution_hz > max_freq.as_hz_f64()
+    {
+        return Err(Error::InvalidInput(format!(
+            "Shifting {} MHz by {delta_ppm} ppm is outside of the {model} signal generator's \
+             frequency range of {}-{} MHz",
+            cw.as_mhz_f64(),
+            min_freq.as_mhz_f64(),
+            max_freq.as_mhz_f64()
+        )));
+    }
+    Ok(model.tuning_resolution() * steps as u64)
+}
+
 /// Expansion-module CW configuration.
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub struct ConfigCwExp {
@@ -135,10 +194,70 @@ impl<'a> TryFrom<&'a [u8]> for ConfigCwExp {
     }
 }
 
+impl WriteMessage for ConfigCwExp {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(Self::PREFIX)?;
+        write!(
+            writer,
+            "{:07},{:+05.1},{}\r\n",
+            self.cw.as_khz(),
+            self.power_dbm,
+            u8::from(self.rf_power),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn shift_cw_by_ppm_and_round_to_tuning_resolution() {
+        let cw = Frequency::from_mhz(1_000);
+        // 1 ppm of 1 GHz is 1 kHz
+        assert_eq!(
+            shift_cw_by_ppm(cw, 1., Model::Rfe6Gen).unwrap(),
+            Frequency::from_khz(1_000_001)
+        );
+        assert_eq!(
+            shift_cw_by_ppm(cw, -2.6, Model::Rfe6Gen).unwrap(),
+            Frequency::from_hz(999_997_000)
+        );
+        // Shifts smaller than half of the tuning resolution round back to the same frequency
+        assert_eq!(shift_cw_by_ppm(cw, 0.4, Model::Rfe6Gen).unwrap(), cw);
+        assert_eq!(shift_cw_by_ppm(cw, -0.0001, Model::Rfe6Gen).unwrap(), cw);
+        assert_eq!(
+            shift_cw_by_ppm(cw, 0.6, Model::Rfe6Gen).unwrap(),
+            Frequency::from_khz(1_000_001)
+        );
+        assert!(shift_cw_by_ppm(cw, f64::NAN, Model::Rfe6Gen).is_err());
+    }
+
+    #[test]
+    fn refuse_to_shift_cw_outside_of_frequency_range() {
+        let max_freq = Model::Rfe6Gen.max_freq();
+        assert_eq!(
+            shift_cw_by_ppm(max_freq, 0.08, Model::Rfe6Gen).unwrap(),
+            max_freq
+        );
+        assert!(matches!(
+            shift_cw_by_ppm(max_freq, 0.1, Model::Rfe6Gen),
+            Err(Error::InvalidInput(_))
+        ));
+
+        // The main module's lowest frequency is 23.4 MHz, where 1 kHz is about 42.7 ppm
+        let min_freq = Model::Rfe6Gen.min_freq();
+        assert_eq!(
+            shift_cw_by_ppm(min_freq, -20., Model::Rfe6Gen).unwrap(),
+            min_freq
+        );
+        assert!(shift_cw_by_ppm(min_freq, -30., Model::Rfe6Gen).is_err());
+        assert_eq!(
+            shift_cw_by_ppm(min_freq, -30., Model::Rfe6GenExpansion).unwrap(),
+            Frequency::from_khz(23_399)
+        );
+    }
+
     #[test]
     fn parse_config_cw() {
         let bytes = b"#C3-G:0186525,0186525,0005,0001000,0,3,0\r\n";
@@ -150,4 +269,38 @@ mod tests {
         assert_eq!(config_cw.power_level, PowerLevel::Highest);
         assert_eq!(config_cw.rf_power, RfPower::On);
     }
+
+    #[test]
+    fn write_config_cw() {
+        let bytes = b"#C3-G:0186525,0186525,0005,0001000,0,3,0\r\n";
+        let config_cw = ConfigCw::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(config_cw.to_bytes(), bytes);
+
+        let round_tripped = ConfigCw::try_from(config_cw.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            ConfigCw {
+                timestamp: config_cw.timestamp,
+                ..round_tripped
+            },
+            config_cw
+        );
+    }
+
+    #[test]
+    fn write_config_cw_exp() {
+        let bytes = b"#C5-G:2450000,-15.5,1\r\n";
+        let config_cw = ConfigCwExp::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(config_cw.power_dbm, -15.5);
+        assert_eq!(config_cw.rf_power, RfPower::Off);
+        assert_eq!(config_cw.to_bytes(), bytes);
+
+        let round_tripped = ConfigCwExp::try_from(config_cw.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            ConfigCwExp {
+                timestamp: config_cw.timestamp,
+                ..round_tripped
+            },
+            config_cw
+        );
+    }
 }
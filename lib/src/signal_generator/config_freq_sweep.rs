@@ -1,10 +1,10 @@
-use std::time::Duration;
+use std::{io, time::Duration};
 
 use chrono::{DateTime, Utc};
 use nom::{Parser, bytes::complete::tag};
 
 use crate::{
-    common::{Frequency, MessageParseError},
+    common::{Frequency, MessageParseError, WriteMessage},
     rf_explorer::parsers::*,
     signal_generator::{Attenuation, PowerLevel, RfPower, parsers::*},
 };
@@ -89,6 +89,23 @@ impl<'a> TryFrom<&'a [u8]> for ConfigFreqSweep {
     }
 }
 
+impl WriteMessage for ConfigFreqSweep {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(Self::PREFIX)?;
+        write!(
+            writer,
+            "{:07},{:04},{:07},{},{},{},{:05}\r\n",
+            self.start.as_khz(),
+            self.total_steps,
+            self.step.as_khz(),
+            u8::from(self.attenuation),
+            u8::from(self.power_level),
+            u8::from(self.rf_power),
+            self.sweep_delay.as_millis(),
+        )
+    }
+}
+
 /// Expansion-module frequency sweep configuration.
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub struct ConfigFreqSweepExp {
@@ -163,6 +180,22 @@ impl<'a> TryFrom<&'a [u8]> for ConfigFreqSweepExp {
     }
 }
 
+impl WriteMessage for ConfigFreqSweepExp {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(Self::PREFIX)?;
+        write!(
+            writer,
+            "{:07},{:04},{:07},{:+05.1},{},{:05}\r\n",
+            self.start.as_khz(),
+            self.total_steps,
+            self.step.as_khz(),
+            self.power_dbm,
+            u8::from(self.rf_power),
+            self.sweep_delay.as_millis(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,4 +212,39 @@ mod tests {
         assert_eq!(config_freq_sweep.rf_power, RfPower::On);
         assert_eq!(config_freq_sweep.sweep_delay.as_millis(), 100);
     }
+
+    #[test]
+    fn write_config_freq_sweep() {
+        let bytes = b"#C3-F:0186525,0005,0001000,0,3,0,00100\r\n";
+        let config_freq_sweep = ConfigFreqSweep::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(config_freq_sweep.to_bytes(), bytes);
+
+        let round_tripped =
+            ConfigFreqSweep::try_from(config_freq_sweep.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            ConfigFreqSweep {
+                timestamp: config_freq_sweep.timestamp,
+                ..round_tripped
+            },
+            config_freq_sweep
+        );
+    }
+
+    #[test]
+    fn write_config_freq_sweep_exp() {
+        let bytes = b"#C5-F:2400000,0100,0001000,+10.0,0,00050\r\n";
+        let config_freq_sweep = ConfigFreqSweepExp::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(config_freq_sweep.power_dbm, 10.);
+        assert_eq!(config_freq_sweep.to_bytes(), bytes);
+
+        let round_tripped =
+            ConfigFreqSweepExp::try_from(config_freq_sweep.to_bytes().as_slice()).unwrap();
+        assert_eq!(
+            ConfigFreqSweepExp {
+                timestamp: config_freq_sweep.timestamp,
+                ..round_tripped
+            },
+            config_freq_sweep
+        );
+    }
 }
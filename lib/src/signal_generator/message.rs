@@ -1,8 +1,10 @@
+use std::io;
+
 use super::{
     Config, ConfigAmpSweep, ConfigAmpSweepExp, ConfigCw, ConfigCwExp, ConfigExp, ConfigFreqSweep,
     ConfigFreqSweepExp, Model, Temperature,
 };
-use crate::common::MessageParseError;
+use crate::common::{MessageParseError, WriteMessage};
 use crate::rf_explorer::{ScreenData, SerialNumber, SetupInfo};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -59,3 +61,22 @@ impl<'a> TryFrom<&'a [u8]> for Message {
         }
     }
 }
+
+impl WriteMessage for Message {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        match self {
+            Message::Config(config) => config.write_message(writer),
+            Message::ConfigAmpSweep(config) => config.write_message(writer),
+            Message::ConfigCw(config) => config.write_message(writer),
+            Message::ConfigFreqSweep(config) => config.write_message(writer),
+            Message::ConfigExp(config) => config.write_message(writer),
+            Message::ConfigAmpSweepExp(config) => config.write_message(writer),
+            Message::ConfigCwExp(config) => config.write_message(writer),
+            Message::ConfigFreqSweepExp(config) => config.write_message(writer),
+            Message::ScreenData(screen_data) => screen_data.write_message(writer),
+            Message::SerialNumber(serial_number) => serial_number.write_message(writer),
+            Message::SetupInfo(setup_info) => setup_info.write_message(writer),
+            Message::Temperature(temperature) => temperature.write_message(writer),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+mod capabilities;
 mod command;
 mod config;
 mod config_amp_sweep;
@@ -5,17 +6,21 @@ mod config_cw;
 mod config_freq_sweep;
 mod message;
 mod model;
+mod operating_mode;
 mod parsers;
 mod rf_explorer;
 mod setup_info;
 mod temperature;
 
-pub(crate) use command::Command;
+pub use capabilities::Capabilities;
+pub use command::Command;
 pub use config::{Attenuation, Config, ConfigExp, PowerLevel, RfPower};
 pub use config_amp_sweep::{ConfigAmpSweep, ConfigAmpSweepExp};
+pub(crate) use config_cw::shift_cw_by_ppm;
 pub use config_cw::{ConfigCw, ConfigCwExp};
 pub use config_freq_sweep::{ConfigFreqSweep, ConfigFreqSweepExp};
 pub(crate) use message::Message;
 pub use model::Model;
+pub use operating_mode::GeneratorMode;
 pub use rf_explorer::SignalGenerator;
 pub use temperature::Temperature;
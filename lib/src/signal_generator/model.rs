@@ -1,11 +1,11 @@
 use std::fmt::Display;
 
-use num_enum::TryFromPrimitive;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::Frequency;
 
 /// Signal generator model reported by the RF Explorer.
-#[derive(Debug, Copy, Clone, TryFromPrimitive, Eq, PartialEq, Default)]
+#[derive(Debug, Copy, Clone, TryFromPrimitive, IntoPrimitive, Eq, PartialEq, Default)]
 #[repr(u8)]
 pub enum Model {
     /// Main 6 GHz signal generator module.
@@ -33,6 +33,14 @@ impl Model {
         }
         .into()
     }
+
+    /// Returns the smallest step the model's output frequency can be tuned by.
+    ///
+    /// Frequencies are sent to the signal generator in kHz, so every model is tuned in 1 kHz
+    /// steps.
+    pub fn tuning_resolution(&self) -> Frequency {
+        Frequency::from_khz(1)
+    }
 }
 
 impl Display for Model {
@@ -0,0 +1,316 @@
+use chrono::{DateTime, Utc};
+
+use super::{
+    Config, ConfigAmpSweep, ConfigAmpSweepExp, ConfigCw, ConfigCwExp, ConfigExp, ConfigFreqSweep,
+    ConfigFreqSweepExp, RfPower,
+};
+
+/// What the signal generator is currently doing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GeneratorMode {
+    /// RF power is off.
+    Idle,
+    /// Generating a continuous wave.
+    Cw,
+    /// Sweeping the output power at a fixed frequency.
+    AmpSweep,
+    /// Sweeping the output frequency at a fixed power.
+    FreqSweep,
+    /// Stepping through frequencies for a spectrum analyzer in tracking mode.
+    ///
+    /// The signal generator's configs don't say whether it's tracking, so this mode is only
+    /// reported after tracking is started with
+    /// [`SignalGenerator::start_tracking`](crate::SignalGenerator::start_tracking) or
+    /// [`SignalGenerator::start_tracking_exp`](crate::SignalGenerator::start_tracking_exp).
+    Tracking,
+}
+
+/// A config message that reveals the signal generator's operating mode.
+pub(crate) trait ModeConfig {
+    /// The operating mode the signal generator is in when it sends this config with RF power on,
+    /// or `None` if the config doesn't show which mode turned RF power on.
+    const MODE: Option<GeneratorMode>;
+
+    /// Whether RF power was on when the config was sent.
+    fn rf_power_on(&self) -> bool;
+
+    /// When the config was received.
+    fn timestamp(&self) -> DateTime<Utc>;
+
+    /// The operating mode the signal generator was in when it sent the config, if it's known.
+    fn operating_mode(&self) -> Option<GeneratorMode> {
+        if self.rf_power_on() {
+            Self::MODE
+        } else {
+            Some(GeneratorMode::Idle)
+        }
+    }
+}
+
+// The general configs don't say which mode turned RF power on, so they only show when the signal
+// generator is idle
+impl ModeConfig for Config {
+    const MODE: Option<GeneratorMode> = None;
+
+    fn rf_power_on(&self) -> bool {
+        self.rf_power == RfPower::On
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+impl ModeConfig for ConfigExp {
+    const MODE: Option<GeneratorMode> = None;
+
+    fn rf_power_on(&self) -> bool {
+        self.rf_power_on
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+impl ModeConfig for ConfigCw {
+    const MODE: Option<GeneratorMode> = Some(GeneratorMode::Cw);
+
+    fn rf_power_on(&self) -> bool {
+        self.rf_power == RfPower::On
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+impl ModeConfig for ConfigCwExp {
+    const MODE: Option<GeneratorMode> = Some(GeneratorMode::Cw);
+
+    fn rf_power_on(&self) -> bool {
+        self.rf_power == RfPower::On
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+impl ModeConfig for ConfigAmpSweep {
+    const MODE: Option<GeneratorMode> = Some(GeneratorMode::AmpSweep);
+
+    fn rf_power_on(&self) -> bool {
+        self.rf_power == RfPower::On
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+impl ModeConfig for ConfigAmpSweepExp {
+    const MODE: Option<GeneratorMode> = Some(GeneratorMode::AmpSweep);
+
+    // The expansion module's amplitude sweep config doesn't include the RF power state
+    fn rf_power_on(&self) -> bool {
+        true
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+impl ModeConfig for ConfigFreqSweep {
+    const MODE: Option<GeneratorMode> = Some(GeneratorMode::FreqSweep);
+
+    fn rf_power_on(&self) -> bool {
+        self.rf_power == RfPower::On
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+impl ModeConfig for ConfigFreqSweepExp {
+    const MODE: Option<GeneratorMode> = Some(GeneratorMode::FreqSweep);
+
+    fn rf_power_on(&self) -> bool {
+        self.rf_power == RfPower::On
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+/// Keeps track of the operating mode shown by the most recent config.
+#[derive(Debug, Default)]
+pub(crate) struct ModeTracker {
+    latest: Option<(GeneratorMode, DateTime<Utc>)>,
+}
+
+impl ModeTracker {
+    /// The operating mode shown by the most recent config.
+    pub(crate) fn mode(&self) -> Option<GeneratorMode> {
+        self.latest.map(|(mode, _)| mode)
+    }
+
+    /// Updates the operating mode unless the config is older than the most recent one or doesn't
+    /// show the mode.
+    ///
+    /// Configs with the same timestamp as the most recent one win because they were received
+    /// later. Returns the new operating mode if it changed.
+    pub(crate) fn update(&mut self, config: &impl ModeConfig) -> Option<GeneratorMode> {
+        self.set(config.operating_mode()?, config.timestamp())
+    }
+
+    /// Sets the operating mode unless the most recent one is newer than `timestamp`, like when a
+    /// command that starts a mode was sent.
+    ///
+    /// Returns the new operating mode if it changed.
+    pub(crate) fn set(
+        &mut self,
+        mode: GeneratorMode,
+        timestamp: DateTime<Utc>,
+    ) -> Option<GeneratorMode> {
+        if self
+            .latest
+            .is_some_and(|(_, latest_timestamp)| timestamp < latest_timestamp)
+        {
+            return None;
+        }
+
+        let previous_mode = self.mode();
+        self.latest = Some((mode, timestamp));
+        (previous_mode != Some(mode)).then_some(mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    fn at(ms: i64) -> DateTime<Utc> {
+        DateTime::UNIX_EPOCH + TimeDelta::milliseconds(ms)
+    }
+
+    fn cw(ms: i64, rf_power: RfPower) -> ConfigCw {
+        ConfigCw {
+            rf_power,
+            timestamp: at(ms),
+            ..ConfigCw::default()
+        }
+    }
+
+    fn freq_sweep(ms: i64) -> ConfigFreqSweep {
+        ConfigFreqSweep {
+            rf_power: RfPower::On,
+            timestamp: at(ms),
+            ..ConfigFreqSweep::default()
+        }
+    }
+
+    fn config(ms: i64, rf_power: RfPower) -> Config {
+        Config {
+            rf_power,
+            timestamp: at(ms),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn most_recent_mode_wins() {
+        let mut tracker = ModeTracker::default();
+        assert_eq!(tracker.mode(), None);
+
+        assert_eq!(
+            tracker.update(&config(0, RfPower::Off)),
+            Some(GeneratorMode::Idle)
+        );
+        assert_eq!(
+            tracker.update(&cw(10, RfPower::On)),
+            Some(GeneratorMode::Cw)
+        );
+        assert_eq!(
+            tracker.update(&freq_sweep(20)),
+            Some(GeneratorMode::FreqSweep)
+        );
+        assert_eq!(
+            tracker.update(&ConfigAmpSweepExp {
+                timestamp: at(30),
+                ..ConfigAmpSweepExp::default()
+            }),
+            Some(GeneratorMode::AmpSweep)
+        );
+        assert_eq!(
+            tracker.set(GeneratorMode::Tracking, at(40)),
+            Some(GeneratorMode::Tracking)
+        );
+        assert_eq!(
+            tracker.update(&cw(50, RfPower::Off)),
+            Some(GeneratorMode::Idle)
+        );
+        assert_eq!(tracker.mode(), Some(GeneratorMode::Idle));
+    }
+
+    #[test]
+    fn ignore_older_configs() {
+        let mut tracker = ModeTracker::default();
+        tracker.update(&freq_sweep(20));
+
+        // A CW config that was received before the frequency sweep config doesn't change the mode
+        assert_eq!(tracker.update(&cw(10, RfPower::On)), None);
+        assert_eq!(tracker.mode(), Some(GeneratorMode::FreqSweep));
+
+        // Configs with the same timestamp are treated as newer
+        assert_eq!(
+            tracker.update(&cw(20, RfPower::On)),
+            Some(GeneratorMode::Cw)
+        );
+        assert_eq!(tracker.mode(), Some(GeneratorMode::Cw));
+    }
+
+    #[test]
+    fn keep_mode_when_general_config_has_rf_power_on() {
+        let mut tracker = ModeTracker::default();
+        tracker.set(GeneratorMode::Tracking, at(0));
+
+        // The general config doesn't say which mode turned RF power on
+        assert_eq!(tracker.update(&config(10, RfPower::On)), None);
+        assert_eq!(
+            tracker.update(&ConfigExp {
+                rf_power_on: true,
+                timestamp: at(20),
+                ..ConfigExp::default()
+            }),
+            None
+        );
+        assert_eq!(tracker.mode(), Some(GeneratorMode::Tracking));
+
+        assert_eq!(
+            tracker.update(&config(30, RfPower::Off)),
+            Some(GeneratorMode::Idle)
+        );
+    }
+
+    #[test]
+    fn only_report_mode_changes() {
+        let mut tracker = ModeTracker::default();
+        assert_eq!(tracker.update(&cw(0, RfPower::On)), Some(GeneratorMode::Cw));
+        assert_eq!(tracker.update(&cw(10, RfPower::On)), None);
+        assert_eq!(
+            tracker.update(&ConfigCwExp {
+                rf_power: RfPower::On,
+                timestamp: at(20),
+                ..ConfigCwExp::default()
+            }),
+            None
+        );
+        assert_eq!(tracker.mode(), Some(GeneratorMode::Cw));
+    }
+}
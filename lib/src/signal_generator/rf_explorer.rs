@@ -2,19 +2,23 @@ use std::{
     fmt::Debug,
     io,
     sync::{Arc, Condvar, Mutex},
-    thread,
     time::Duration,
 };
 
+use chrono::{DateTime, Utc};
+
 use super::{
-    Attenuation, Config, ConfigAmpSweep, ConfigAmpSweepExp, ConfigCw, ConfigCwExp, ConfigExp,
-    ConfigFreqSweep, ConfigFreqSweepExp, Model, PowerLevel, Temperature,
+    Attenuation, Capabilities, Config, ConfigAmpSweep, ConfigAmpSweepExp, ConfigCw, ConfigCwExp,
+    ConfigExp, ConfigFreqSweep, ConfigFreqSweepExp, GeneratorMode, Model, PowerLevel, Temperature,
+    operating_mode::{ModeConfig, ModeTracker},
+    shift_cw_by_ppm,
 };
+use crate::common::{Disconnection, Freshness, Timestamped};
 use crate::rf_explorer::{
-    ConfigCallback, NEXT_SCREEN_DATA_TIMEOUT, RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT, ScreenData,
-    SerialNumber, SetupInfo, impl_rf_explorer,
+    COMMAND_RESPONSE_TIMEOUT, CallbackThreads, ConfigCallback, ScreenDataCache, SerialNumber,
+    SetupInfo, impl_rf_explorer,
 };
-use crate::{ConnectionError, ConnectionResult, Device, Frequency, Result};
+use crate::{ConnectionResult, Device, Error, Frequency, MemoryBudget, MemoryStats, Result};
 
 #[derive(Debug)]
 /// RF Explorer signal generator device.
@@ -26,10 +30,10 @@ impl_rf_explorer!(SignalGenerator, MessageContainer);
 
 impl SignalGenerator {
     /// Returns the RF Explorer's serial number, if it exists.
-    pub fn serial_number(&self) -> Option<String> {
+    pub fn serial_number(&self) -> Option<Arc<str>> {
         // Return the serial number if we've already received it
         if let Some(ref serial_number) = *self.messages().serial_number.0.lock().unwrap() {
-            return Some(serial_number.to_string());
+            return Some(serial_number.as_shared_str());
         }
 
         // If we haven't already received the serial number, request it from the RF Explorer
@@ -49,11 +53,11 @@ impl SignalGenerator {
 
         (*self.messages().serial_number.0.lock().unwrap())
             .as_ref()
-            .map(|sn| sn.to_string())
+            .map(SerialNumber::as_shared_str)
     }
 
     /// Returns the firmware version reported by the RF Explorer.
-    pub fn firmware_version(&self) -> String {
+    pub fn firmware_version(&self) -> Arc<str> {
         self.messages()
             .setup_info
             .0
@@ -104,35 +108,49 @@ impl SignalGenerator {
         *self.messages().config_freq_sweep_exp.0.lock().unwrap()
     }
 
-    /// Returns the most recent `ScreenData` captured by the RF Explorer.
-    pub fn screen_data(&self) -> Option<ScreenData> {
-        self.messages().screen_data.0.lock().unwrap().clone()
+    /// Returns what the signal generator is currently doing, like generating a CW signal or
+    /// sweeping its output frequency.
+    ///
+    /// The mode is derived from the most recently received config, or from starting tracking
+    /// mode. Returns `None` until a config that shows the mode has been received.
+    pub fn operating_mode(&self) -> Option<GeneratorMode> {
+        self.messages().operating_mode.lock().unwrap().mode()
     }
 
-    /// Waits for the RF Explorer to capture its next `ScreenData`.
-    pub fn wait_for_next_screen_data(&self) -> Result<ScreenData> {
-        self.wait_for_next_screen_data_with_timeout(NEXT_SCREEN_DATA_TIMEOUT)
+    /// Returns the most recent temperature range reported by the signal generator.
+    ///
+    /// Returns `None` if the temperature is older than the
+    /// [staleness threshold](SignalGenerator::set_staleness_threshold).
+    pub fn temperature(&self) -> Option<Temperature> {
+        let messages = self.messages();
+        messages
+            .freshness
+            .fresh(messages.temperature.0.lock().unwrap().as_ref())
     }
 
-    /// Waits for the RF Explorer to capture its next `ScreenData` or for the timeout duration to elapse.
-    pub fn wait_for_next_screen_data_with_timeout(&self, timeout: Duration) -> Result<ScreenData> {
-        let previous_screen_data = self.screen_data();
-        let (screen_data, condvar) = &self.messages().screen_data;
-        let (screen_data, wait_result) = condvar
-            .wait_timeout_while(screen_data.lock().unwrap(), timeout, |screen_data| {
-                *screen_data == previous_screen_data || screen_data.is_none()
-            })
-            .unwrap();
+    /// Returns the most recent temperature range reported by the signal generator and how long
+    /// ago it was received, even if it's older than the staleness threshold.
+    pub fn temperature_with_age(&self) -> Option<(Temperature, Duration)> {
+        let messages = self.messages();
+        messages
+            .freshness
+            .with_age(messages.temperature.0.lock().unwrap().as_ref())
+    }
 
-        match &*screen_data {
-            Some(screen_data) if !wait_result.timed_out() => Ok(screen_data.clone()),
-            _ => Err(crate::Error::TimedOut(timeout)),
-        }
+    /// Sets how old the temperature the signal generator reports periodically can be before
+    /// [`SignalGenerator::temperature`] returns `None` instead.
+    ///
+    /// The signal generator stops reporting it when it's disconnected or busy, so the cached
+    /// temperature can be minutes old. Settings that are only reported when they change, like
+    /// the CW config, never expire. The temperature never expires if the threshold is `None`,
+    /// which is the default.
+    pub fn set_staleness_threshold(&self, threshold: Option<Duration>) {
+        self.messages().freshness.set_staleness_threshold(threshold)
     }
 
-    /// Returns the most recent temperature range reported by the signal generator.
-    pub fn temperature(&self) -> Option<Temperature> {
-        *self.messages().temperature.0.lock().unwrap()
+    /// Returns how old the temperature can be before it's considered stale.
+    pub fn staleness_threshold(&self) -> Option<Duration> {
+        self.messages().freshness.staleness_threshold()
     }
 
     /// Returns the main radio's model.
@@ -143,8 +161,7 @@ impl SignalGenerator {
             .lock()
             .unwrap()
             .as_ref()
-            .unwrap()
-            .main_radio_model
+            .and_then(|setup_info| setup_info.main_radio_model)
     }
 
     /// Returns the expansion radio's model (if one exists).
@@ -155,8 +172,12 @@ impl SignalGenerator {
             .lock()
             .unwrap()
             .as_ref()
-            .unwrap()
-            .expansion_radio_model
+            .and_then(|setup_info| setup_info.expansion_radio_model)
+    }
+
+    /// Returns whether the expansion radio module is active.
+    pub fn is_expansion_radio_module_active(&self) -> bool {
+        self.config_expansion().is_some()
     }
 
     /// The active radio's model.
@@ -165,7 +186,7 @@ impl SignalGenerator {
             return self.main_radio_model().unwrap_or_default();
         };
 
-        if self.config_expansion().is_some() {
+        if self.is_expansion_radio_module_active() {
             exp_model
         } else {
             self.main_radio_model().unwrap_or_default()
@@ -176,13 +197,18 @@ impl SignalGenerator {
     pub fn inactive_radio_model(&self) -> Option<Model> {
         let exp_model = self.expansion_radio_model()?;
 
-        if self.config_expansion().is_some() {
+        if self.is_expansion_radio_module_active() {
             self.main_radio_model()
         } else {
             Some(exp_model)
         }
     }
 
+    /// Returns the features supported by the signal generator.
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::new(self.active_radio_model(), self.expansion_radio_model())
+    }
+
     /// Starts the signal generator's amplitude sweep mode.
     pub fn start_amp_sweep(
         &self,
@@ -192,7 +218,7 @@ impl SignalGenerator {
         stop_attenuation: Attenuation,
         stop_power_level: PowerLevel,
         step_delay: Duration,
-    ) -> io::Result<()> {
+    ) -> Result<()> {
         self.send_command(super::Command::StartAmpSweep {
             cw: cw.into(),
             start_attenuation,
@@ -211,7 +237,7 @@ impl SignalGenerator {
         step_power_db: f64,
         stop_power_dbm: f64,
         step_delay: Duration,
-    ) -> io::Result<()> {
+    ) -> Result<()> {
         self.send_command(super::Command::StartAmpSweepExp {
             cw: cw.into(),
             start_power_dbm,
@@ -227,7 +253,7 @@ impl SignalGenerator {
         cw: impl Into<Frequency>,
         attenuation: Attenuation,
         power_level: PowerLevel,
-    ) -> io::Result<()> {
+    ) -> Result<()> {
         self.send_command(super::Command::StartCw {
             cw: cw.into(),
             attenuation,
@@ -236,13 +262,56 @@ impl SignalGenerator {
     }
 
     /// Starts the signal generator's CW mode using the expansion module.
-    pub fn start_cw_exp(&self, cw: impl Into<Frequency>, power_dbm: f64) -> io::Result<()> {
+    pub fn start_cw_exp(&self, cw: impl Into<Frequency>, power_dbm: f64) -> Result<()> {
         self.send_command(super::Command::StartCwExp {
             cw: cw.into(),
             power_dbm,
         })
     }
 
+    /// Shifts the CW frequency by `delta_ppm` parts per million, like when calibrating a receiver's
+    /// crystal, and returns the frequency the signal generator confirmed.
+    ///
+    /// The shifted frequency is rounded to the nearest multiple of the active radio module's
+    /// [`tuning_resolution`](Model::tuning_resolution). The CW command is sent again with the
+    /// current attenuation and power level, or output power for the expansion module.
+    ///
+    /// Returns [`Error::InvalidOperation`] if the signal generator isn't generating a CW signal,
+    /// [`Error::InvalidInput`] if the shifted frequency is outside of the radio module's frequency
+    /// range, or [`Error::TimedOut`] if the signal generator doesn't send its new CW config.
+    pub fn retune_cw_ppm(&self, delta_ppm: f64) -> Result<Frequency> {
+        let mode = self.messages().operating_mode.lock().unwrap().mode();
+        if mode != Some(GeneratorMode::Cw) {
+            return Err(Error::InvalidOperation(
+                "The signal generator isn't generating a CW signal".to_string(),
+            ));
+        }
+
+        let model = self.active_radio_model();
+        // The module that sent the most recent CW config is the one generating the signal
+        match (self.config_cw(), self.config_cw_expansion()) {
+            (config_cw, Some(config_cw_exp))
+                if config_cw
+                    .is_none_or(|config_cw| config_cw_exp.timestamp > config_cw.timestamp) =>
+            {
+                let cw = shift_cw_by_ppm(config_cw_exp.cw, delta_ppm, model)?;
+                self.start_cw_exp(cw, f64::from(config_cw_exp.power_dbm))?;
+                let (lock, condvar) = &self.messages().config_cw_exp;
+                wait_for_newer_config(lock, condvar, config_cw_exp.timestamp)
+                    .map(|config| config.cw)
+            }
+            (Some(config_cw), _) => {
+                let cw = shift_cw_by_ppm(config_cw.cw, delta_ppm, model)?;
+                self.start_cw(cw, config_cw.attenuation, config_cw.power_level)?;
+                let (lock, condvar) = &self.messages().config_cw;
+                wait_for_newer_config(lock, condvar, config_cw.timestamp).map(|config| config.cw)
+            }
+            (None, _) => Err(Error::InvalidOperation(
+                "The signal generator hasn't sent its CW config".to_string(),
+            )),
+        }
+    }
+
     /// Starts the signal generator's frequency sweep mode.
     pub fn start_freq_sweep(
         &self,
@@ -252,7 +321,7 @@ impl SignalGenerator {
         sweep_steps: u16,
         step_hz: u64,
         step_delay: Duration,
-    ) -> io::Result<()> {
+    ) -> Result<()> {
         self.send_command(super::Command::StartFreqSweep {
             start: start.into(),
             attenuation,
@@ -271,7 +340,7 @@ impl SignalGenerator {
         sweep_steps: u16,
         step: impl Into<Frequency>,
         step_delay: Duration,
-    ) -> io::Result<()> {
+    ) -> Result<()> {
         self.send_command(super::Command::StartFreqSweepExp {
             start: start.into(),
             power_dbm,
@@ -289,14 +358,16 @@ impl SignalGenerator {
         power_level: PowerLevel,
         sweep_steps: u16,
         step: impl Into<Frequency>,
-    ) -> io::Result<()> {
+    ) -> Result<()> {
         self.send_command(super::Command::StartTracking {
             start: start.into(),
             attenuation,
             power_level,
             sweep_steps,
             step: step.into(),
-        })
+        })?;
+        self.messages().set_operating_mode(GeneratorMode::Tracking);
+        Ok(())
     }
 
     /// Starts the signal generator's tracking mode using the expansion module.
@@ -306,17 +377,19 @@ impl SignalGenerator {
         power_dbm: f64,
         sweep_steps: u16,
         step: impl Into<Frequency>,
-    ) -> io::Result<()> {
+    ) -> Result<()> {
         self.send_command(super::Command::StartTrackingExp {
             start: start.into(),
             power_dbm,
             sweep_steps,
             step: step.into(),
-        })
+        })?;
+        self.messages().set_operating_mode(GeneratorMode::Tracking);
+        Ok(())
     }
 
     /// Jumps to a new frequency using the tracking step frequency.
-    pub fn tracking_step(&self, steps: u16) -> io::Result<()> {
+    pub fn tracking_step(&self, steps: u16) -> Result<()> {
         self.send_command(super::Command::TrackingStep(steps))
     }
 
@@ -428,17 +501,47 @@ impl SignalGenerator {
             .unwrap() = None;
     }
 
+    /// Sets the callback that is executed when the signal generator's
+    /// [operating mode](SignalGenerator::operating_mode) changes.
+    pub fn set_operating_mode_callback(&self, cb: impl Fn(GeneratorMode) + Send + Sync + 'static) {
+        *self.messages().operating_mode_callback.lock().unwrap() = Some(Arc::new(Box::new(cb)));
+    }
+
+    /// Removes the callback that is executed when the signal generator's operating mode changes.
+    pub fn remove_operating_mode_callback(&self) {
+        *self.messages().operating_mode_callback.lock().unwrap() = None;
+    }
+
     /// Turns on RF power with the current power and frequency configuration.
-    pub fn rf_power_on(&self) -> io::Result<()> {
+    pub fn rf_power_on(&self) -> Result<()> {
         self.send_command(super::Command::RfPowerOn)
     }
 
     /// Turns off RF power.
-    pub fn rf_power_off(&self) -> io::Result<()> {
+    pub fn rf_power_off(&self) -> Result<()> {
         self.send_command(super::Command::RfPowerOff)
     }
 }
 
+/// Waits for a config received after `previous`.
+fn wait_for_newer_config<T: ModeConfig + Copy>(
+    lock: &Mutex<Option<T>>,
+    condvar: &Condvar,
+    previous: DateTime<Utc>,
+) -> Result<T> {
+    let (config, wait_result) = condvar
+        .wait_timeout_while(lock.lock().unwrap(), COMMAND_RESPONSE_TIMEOUT, |config| {
+            config
+                .as_ref()
+                .is_none_or(|config| config.timestamp() <= previous)
+        })
+        .unwrap();
+    match *config {
+        Some(config) if !wait_result.timed_out() => Ok(config),
+        _ => Err(Error::TimedOut(COMMAND_RESPONSE_TIMEOUT)),
+    }
+}
+
 #[derive(Default)]
 struct MessageContainer {
     pub(crate) config: (Mutex<Option<Config>>, Condvar),
@@ -457,10 +560,61 @@ struct MessageContainer {
     pub(crate) config_freq_sweep_callback: Mutex<ConfigCallback<ConfigFreqSweep>>,
     pub(crate) config_freq_sweep_exp: (Mutex<Option<ConfigFreqSweepExp>>, Condvar),
     pub(crate) config_freq_sweep_exp_callback: Mutex<ConfigCallback<ConfigFreqSweepExp>>,
-    pub(crate) screen_data: (Mutex<Option<ScreenData>>, Condvar),
-    pub(crate) temperature: (Mutex<Option<Temperature>>, Condvar),
+    pub(crate) screen_data: ScreenDataCache,
+    /// Runs the callbacks that are called on their own threads.
+    pub(crate) callback_threads: CallbackThreads,
+    pub(crate) memory_budget: Mutex<MemoryBudget>,
+    pub(crate) temperature: (Mutex<Option<Timestamped<Temperature>>>, Condvar),
     pub(crate) setup_info: (Mutex<Option<SetupInfo<Model>>>, Condvar),
     pub(crate) serial_number: (Mutex<Option<SerialNumber>>, Condvar),
+    pub(crate) operating_mode: Mutex<ModeTracker>,
+    pub(crate) operating_mode_callback: Mutex<ConfigCallback<GeneratorMode>>,
+    pub(crate) disconnection: Disconnection,
+    /// Decides when the temperature is too old.
+    pub(crate) freshness: Freshness,
+}
+
+impl MessageContainer {
+    pub(crate) fn memory_budget(&self) -> MemoryBudget {
+        *self.memory_budget.lock().unwrap()
+    }
+
+    pub(crate) fn set_memory_budget(&self, budget: MemoryBudget) {
+        *self.memory_budget.lock().unwrap() = budget;
+        self.callback_threads
+            .set_max_running(budget.max_running_callbacks);
+    }
+
+    /// Returns an estimate of the memory used by the cached messages, in bytes.
+    ///
+    /// Signal generator configs have fixed sizes, so only the screen data is counted.
+    pub(crate) fn memory_usage_estimate(&self) -> usize {
+        self.screen_data.memory_usage_estimate()
+    }
+
+    pub(crate) fn memory_stats(&self) -> MemoryStats {
+        MemoryStats {
+            dropped_callbacks: self.callback_threads.dropped(),
+            ..MemoryStats::default()
+        }
+    }
+
+    fn update_operating_mode(&self, config: &impl ModeConfig) {
+        let Some(mode) = self.operating_mode.lock().unwrap().update(config) else {
+            return;
+        };
+        self.callback_threads
+            .spawn_callback(&self.operating_mode_callback, mode);
+    }
+
+    /// Sets the operating mode after a command that starts it was sent.
+    fn set_operating_mode(&self, mode: GeneratorMode) {
+        let Some(mode) = self.operating_mode.lock().unwrap().set(mode, Utc::now()) else {
+            return;
+        };
+        self.callback_threads
+            .spawn_callback(&self.operating_mode_callback, mode);
+    }
 }
 
 impl crate::common::MessageContainer for MessageContainer {
@@ -469,80 +623,63 @@ impl crate::common::MessageContainer for MessageContainer {
     fn cache_message(&self, message: Self::Message) {
         match message {
             Self::Message::Config(config) => {
+                self.update_operating_mode(&config);
                 *self.config.0.lock().unwrap() = Some(config);
                 self.config.1.notify_one();
-                if let Some(cb) = self.config_callback.lock().unwrap().clone() {
-                    thread::spawn(move || {
-                        cb(config);
-                    });
-                }
+                self.callback_threads
+                    .spawn_callback(&self.config_callback, config);
             }
             Self::Message::ConfigAmpSweep(config) => {
+                self.update_operating_mode(&config);
                 *self.config_amp_sweep.0.lock().unwrap() = Some(config);
                 self.config_amp_sweep.1.notify_one();
-                if let Some(cb) = self.config_amp_sweep_callback.lock().unwrap().clone() {
-                    thread::spawn(move || {
-                        cb(config);
-                    });
-                }
+                self.callback_threads
+                    .spawn_callback(&self.config_amp_sweep_callback, config);
             }
             Self::Message::ConfigCw(config) => {
+                self.update_operating_mode(&config);
                 *self.config_cw.0.lock().unwrap() = Some(config);
                 self.config_cw.1.notify_one();
-                if let Some(cb) = self.config_cw_callback.lock().unwrap().clone() {
-                    thread::spawn(move || {
-                        cb(config);
-                    });
-                }
+                self.callback_threads
+                    .spawn_callback(&self.config_cw_callback, config);
             }
             Self::Message::ConfigFreqSweep(config) => {
+                self.update_operating_mode(&config);
                 *self.config_freq_sweep.0.lock().unwrap() = Some(config);
                 self.config_freq_sweep.1.notify_one();
-                if let Some(cb) = self.config_freq_sweep_callback.lock().unwrap().clone() {
-                    thread::spawn(move || {
-                        cb(config);
-                    });
-                }
+                self.callback_threads
+                    .spawn_callback(&self.config_freq_sweep_callback, config);
             }
             Self::Message::ConfigExp(config) => {
+                self.update_operating_mode(&config);
                 *self.config_exp.0.lock().unwrap() = Some(config);
                 self.config_exp.1.notify_one();
-                if let Some(cb) = self.config_exp_callback.lock().unwrap().clone() {
-                    thread::spawn(move || {
-                        cb(config);
-                    });
-                }
+                self.callback_threads
+                    .spawn_callback(&self.config_exp_callback, config);
             }
             Self::Message::ConfigAmpSweepExp(config) => {
+                self.update_operating_mode(&config);
                 *self.config_amp_sweep_exp.0.lock().unwrap() = Some(config);
                 self.config_amp_sweep_exp.1.notify_one();
-                if let Some(cb) = self.config_amp_sweep_exp_callback.lock().unwrap().clone() {
-                    thread::spawn(move || {
-                        cb(config);
-                    });
-                }
+                self.callback_threads
+                    .spawn_callback(&self.config_amp_sweep_exp_callback, config);
             }
             Self::Message::ConfigCwExp(config) => {
+                self.update_operating_mode(&config);
                 *self.config_cw_exp.0.lock().unwrap() = Some(config);
                 self.config_cw_exp.1.notify_one();
-                if let Some(cb) = self.config_cw_exp_callback.lock().unwrap().clone() {
-                    thread::spawn(move || {
-                        cb(config);
-                    });
-                }
+                self.callback_threads
+                    .spawn_callback(&self.config_cw_exp_callback, config);
             }
             Self::Message::ConfigFreqSweepExp(config) => {
+                self.update_operating_mode(&config);
                 *self.config_freq_sweep_exp.0.lock().unwrap() = Some(config);
                 self.config_freq_sweep_exp.1.notify_one();
-                if let Some(cb) = self.config_freq_sweep_exp_callback.lock().unwrap().clone() {
-                    thread::spawn(move || {
-                        cb(config);
-                    });
-                }
+                self.callback_threads
+                    .spawn_callback(&self.config_freq_sweep_exp_callback, config);
             }
             Self::Message::ScreenData(screen_data) => {
-                *self.screen_data.0.lock().unwrap() = Some(screen_data);
-                self.screen_data.1.notify_one();
+                self.screen_data.cache(screen_data, &self.callback_threads);
             }
             Self::Message::SerialNumber(serial_number) => {
                 *self.serial_number.0.lock().unwrap() = Some(serial_number);
@@ -553,45 +690,30 @@ impl crate::common::MessageContainer for MessageContainer {
                 self.setup_info.1.notify_one();
             }
             Self::Message::Temperature(temperature) => {
-                *self.temperature.0.lock().unwrap() = Some(temperature);
+                *self.temperature.0.lock().unwrap() = Some(self.freshness.stamp(temperature));
                 self.temperature.1.notify_one();
             }
         }
     }
 
-    fn wait_for_device_info(&self) -> ConnectionResult<()> {
-        let (config_lock, config_cvar) = &self.config;
-        let (setup_info_lock, setup_info_cvar) = &self.setup_info;
+    fn disconnect(&self, error: io::Error) {
+        self.disconnection.set(error);
+        self.screen_data.notify_disconnected();
+    }
 
-        // Check to see if we've already received a Config and SetupInfo
-        if config_lock.lock().unwrap().is_some() && setup_info_lock.lock().unwrap().is_some() {
-            return Ok(());
-        }
+    fn reconnect(&self) {
+        self.disconnection.clear();
+    }
 
-        // Wait to see if we receive a Config and SetupInfo before timing out
-        if config_cvar
-            .wait_timeout_while(
-                config_lock.lock().unwrap(),
-                RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT,
-                |config| config.is_none(),
-            )
-            .unwrap()
-            .0
-            .is_some()
-            && setup_info_cvar
-                .wait_timeout_while(
-                    setup_info_lock.lock().unwrap(),
-                    RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT,
-                    |setup_info| setup_info.is_none(),
-                )
-                .unwrap()
-                .0
-                .is_some()
-        {
-            Ok(())
-        } else {
-            Err(ConnectionError::DeviceInfoNotReceived)
-        }
+    fn wait_for_device_info(&self) -> ConnectionResult<()> {
+        self.wait_for_device_info_with_progress(&|_| {})
+    }
+
+    fn wait_for_device_info_with_progress(
+        &self,
+        on_waiting: &dyn Fn(Duration),
+    ) -> ConnectionResult<()> {
+        rf_explorer::wait_for_device_info(&self.config, &self.setup_info, on_waiting)
     }
 }
 
@@ -615,10 +737,14 @@ impl Debug for MessageContainer {
                 "config_freq_sweep_exp",
                 &self.config_freq_sweep_exp.0.lock().unwrap(),
             )
-            .field("screen_data", &self.screen_data.0.lock().unwrap())
+            .field("screen_data", &self.screen_data)
             .field("temperature", &self.temperature.0.lock().unwrap())
             .field("setup_info", &self.setup_info.0.lock().unwrap())
             .field("serial_number", &self.serial_number.0.lock().unwrap())
+            .field(
+                "operating_mode",
+                &self.operating_mode.lock().unwrap().mode(),
+            )
             .finish()
     }
 }
@@ -1,5 +1,7 @@
+use std::io;
+
 use super::Model;
-use crate::common::MessageParseError;
+use crate::common::{MessageParseError, WriteMessage};
 use crate::rf_explorer::SetupInfo;
 
 impl SetupInfo<Model> {
@@ -14,8 +16,15 @@ impl<'a> TryFrom<&'a [u8]> for SetupInfo<Model> {
     }
 }
 
+impl WriteMessage for SetupInfo<Model> {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        self.write_with_prefix(Self::PREFIX, writer)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::WriteMessage;
     use crate::rf_explorer::SetupInfo;
     use crate::signal_generator::Model;
 
@@ -24,7 +33,7 @@ mod tests {
         let setup = SetupInfo::<Model>::try_from(b"#C3-M:060,255,01.15\r\n".as_ref()).unwrap();
         assert_eq!(setup.main_radio_model, Some(Model::Rfe6Gen));
         assert_eq!(setup.expansion_radio_model, None);
-        assert_eq!(setup.firmware_version, "01.15");
+        assert_eq!(&*setup.firmware_version, "01.15");
     }
 
     #[test]
@@ -32,6 +41,17 @@ mod tests {
         let setup = SetupInfo::<Model>::try_from(b"#C3-M:060,061,01.15\r\n".as_ref()).unwrap();
         assert_eq!(setup.main_radio_model, Some(Model::Rfe6Gen));
         assert_eq!(setup.expansion_radio_model, Some(Model::Rfe6GenExpansion));
-        assert_eq!(setup.firmware_version, "01.15");
+        assert_eq!(&*setup.firmware_version, "01.15");
+    }
+
+    #[test]
+    fn write_rfe_gen_combo_setup() {
+        let bytes = b"#C3-M:060,061,01.15\r\n";
+        let setup = SetupInfo::<Model>::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(setup.to_bytes(), bytes);
+        assert_eq!(
+            SetupInfo::<Model>::try_from(setup.to_bytes().as_slice()),
+            Ok(setup)
+        );
     }
 }
@@ -1,10 +1,10 @@
-use std::{convert::TryFrom, ops::RangeInclusive};
+use std::{convert::TryFrom, io, ops::RangeInclusive};
 
 use nom::Parser;
 use nom::{bytes::complete::tag, combinator::map_res, number::complete::u8 as nom_u8};
 use num_enum::TryFromPrimitive;
 
-use crate::common::MessageParseError;
+use crate::common::{MessageParseError, WriteMessage};
 use crate::rf_explorer::parsers::*;
 
 /// Temperature range reported by the signal generator.
@@ -60,3 +60,25 @@ impl<'a> TryFrom<&'a [u8]> for Temperature {
         Ok(temperature)
     }
 }
+
+impl WriteMessage for Temperature {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(Temperature::PREFIX)?;
+        writer.write_all(&[*self as u8])?;
+        writer.write_all(b"\r\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_temperature() {
+        assert_eq!(Temperature::TwentyToThirty.to_bytes(), b"#T:3\r\n");
+        assert_eq!(
+            Temperature::try_from(b"#T:3\r\n".as_ref()),
+            Ok(Temperature::TwentyToThirty)
+        );
+    }
+}
@@ -0,0 +1,430 @@
+use std::{str, time::Duration};
+
+use chrono::Utc;
+use tracing::debug;
+
+use super::Scenario;
+use crate::{
+    Frequency, WriteMessage,
+    rf_explorer::{SerialNumber, SetupInfo},
+    spectrum_analyzer::{
+        CalcMode, Capabilities, Config, DspMode, InputStage, Mode, Model, Sweep, SweepMeta,
+    },
+};
+
+/// An emulated RF Explorer spectrum analyzer.
+///
+/// The simulator answers the commands a host sends and produces synthetic sweeps, but it doesn't
+/// do any I/O itself. Use [`serve`](super::serve) to run it on a connection.
+#[derive(Debug)]
+pub struct Simulator {
+    scenario: Scenario,
+    config: Config,
+    is_sweeping: bool,
+    command_buf: Vec<u8>,
+    rng_state: u64,
+}
+
+impl Simulator {
+    /// Creates a simulator in the state described by the scenario.
+    pub fn new(scenario: Scenario) -> Self {
+        let mut simulator = Simulator {
+            config: Config::default(),
+            is_sweeping: false,
+            command_buf: Vec::new(),
+            // xorshift gets stuck at 0, so the seed is mixed with a nonzero constant
+            rng_state: scenario.sweeps.seed ^ 0x9E37_79B9_7F4A_7C15,
+            scenario,
+        };
+        simulator.config = simulator.build_config(
+            simulator.scenario.config.start,
+            simulator.scenario.config.stop,
+            simulator.scenario.config.sweep_len.max(2),
+            false,
+        );
+        simulator.config.min_amp_dbm = simulator.scenario.config.min_amp_dbm;
+        simulator.config.max_amp_dbm = simulator.scenario.config.max_amp_dbm;
+        simulator
+    }
+
+    /// Returns the scenario the simulator was created with.
+    pub fn scenario(&self) -> &Scenario {
+        &self.scenario
+    }
+
+    /// Returns the config the simulator reports to the host.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Returns whether sweeps should be sent to the host.
+    ///
+    /// Like a real RF Explorer, the simulator starts sweeping once the host requests its config
+    /// and stops when the host sends the hold command.
+    pub fn is_sweeping(&self) -> bool {
+        self.is_sweeping
+    }
+
+    /// Returns the time between sweeps.
+    pub fn sweep_interval(&self) -> Duration {
+        self.scenario.sweeps.interval()
+    }
+
+    /// Handles the bytes sent by the host and returns the bytes the device sends in reply.
+    ///
+    /// Commands can be split across calls, and bytes that aren't part of a command are skipped.
+    pub fn receive(&mut self, bytes: &[u8]) -> Vec<u8> {
+        self.command_buf.extend_from_slice(bytes);
+        let mut replies = Vec::new();
+        loop {
+            // Commands start with '#' followed by their length, including the '#' and length bytes
+            let Some(start) = self.command_buf.iter().position(|&byte| byte == b'#') else {
+                self.command_buf.clear();
+                break;
+            };
+            self.command_buf.drain(..start);
+            let Some(&len) = self.command_buf.get(1) else {
+                break;
+            };
+            let len = usize::from(len);
+            if len < 3 {
+                self.command_buf.drain(..1);
+                continue;
+            }
+            if self.command_buf.len() < len {
+                break;
+            }
+
+            let command: Vec<u8> = self.command_buf.drain(..len).collect();
+            self.handle_command(&command[2..], &mut replies);
+        }
+        replies
+    }
+
+    /// Measures the next synthetic sweep and returns it in the device's wire format.
+    pub fn next_sweep(&mut self) -> Vec<u8> {
+        let sweeps = &self.scenario.sweeps;
+        let (noise_floor_dbm, noise_dbm) = (sweeps.noise_floor_dbm, sweeps.noise_dbm);
+        let mut amplitudes_dbm = Vec::with_capacity(usize::from(self.config.sweep_len));
+        for i in 0..u64::from(self.config.sweep_len) {
+            let freq = self.config.start_freq + self.config.step_size * i;
+            let noise = (self.next_random() - 0.5) * noise_dbm;
+            let tone_dbm = self
+                .scenario
+                .sweeps
+                .tones
+                .iter()
+                .filter(|tone| {
+                    // Narrow tones still show up in the point closest to them
+                    let half_width = (tone.width / 2).max(self.config.step_size / 2);
+                    freq.abs_diff(tone.freq) <= half_width
+                })
+                .map(|tone| tone.amp_dbm)
+                .reduce(f32::max);
+            let amp_dbm =
+                tone_dbm.map_or(noise_floor_dbm, |tone_dbm| tone_dbm.max(noise_floor_dbm));
+            amplitudes_dbm.push((amp_dbm + noise).clamp(-127.5, 0.0));
+        }
+
+        Sweep {
+            amplitudes_dbm,
+            timestamp: Utc::now(),
+            meta: SweepMeta::default(),
+        }
+        .to_bytes()
+    }
+
+    fn handle_command(&mut self, command: &[u8], replies: &mut Vec<u8>) {
+        match command {
+            b"C0" => {
+                self.is_sweeping = true;
+                replies.extend(self.setup_info().to_bytes());
+                replies.extend(self.config.to_bytes());
+            }
+            b"Cn" => {
+                replies.extend(SerialNumber::PREFIX);
+                replies.extend(self.scenario.device.serial_number.as_bytes());
+                replies.extend(b"\r\n");
+            }
+            b"CH" => self.is_sweeping = false,
+            [b'C', b'2', b'-', b'F', b':', args @ ..] => {
+                if self.set_config(args) {
+                    replies.extend(self.config.to_bytes());
+                }
+            }
+            [b'C', b'J', sweep_points] => {
+                if self.set_sweep_len((u16::from(*sweep_points) + 1) * 16) {
+                    replies.extend(self.config.to_bytes());
+                }
+            }
+            [b'C', b'j', sweep_points @ ..] if sweep_points.len() == 2 => {
+                if self.set_sweep_len(u16::from_be_bytes([sweep_points[0], sweep_points[1]])) {
+                    replies.extend(self.config.to_bytes());
+                }
+            }
+            [b'C', b'M', module] => {
+                if self.switch_module(*module == 1) {
+                    replies.extend(self.setup_info().to_bytes());
+                    replies.extend(self.config.to_bytes());
+                }
+            }
+            [b'C', b'p', dsp_mode] => {
+                if let Ok(dsp_mode) = DspMode::try_from(*dsp_mode)
+                    && self.capabilities().supports_dsp_mode
+                {
+                    replies.extend(dsp_mode.to_bytes());
+                }
+            }
+            [b'a', input_stage] => {
+                if let Ok(input_stage) = InputStage::try_from(*input_stage)
+                    && self.capabilities().supports_input_stage
+                {
+                    replies.extend(input_stage.to_bytes());
+                }
+            }
+            _ => debug!(
+                command = String::from_utf8_lossy(command).as_ref(),
+                "Ignored unsupported command"
+            ),
+        }
+    }
+
+    /// Applies a set config command's start, stop, and amplitude range.
+    fn set_config(&mut self, args: &[u8]) -> bool {
+        let Some([start_khz, stop_khz, max_amp_dbm, min_amp_dbm]) = str::from_utf8(args)
+            .ok()
+            .and_then(|args| <[&str; 4]>::try_from(args.split(',').collect::<Vec<_>>()).ok())
+        else {
+            return false;
+        };
+        let (Ok(start_khz), Ok(stop_khz), Ok(max_amp_dbm), Ok(min_amp_dbm)) = (
+            start_khz.parse(),
+            stop_khz.parse(),
+            max_amp_dbm.parse(),
+            min_amp_dbm.parse(),
+        ) else {
+            return false;
+        };
+
+        let freq_range = self.capabilities().freq_range;
+        let start = Frequency::from_khz(start_khz).clamp(*freq_range.start(), *freq_range.end());
+        let stop = Frequency::from_khz(stop_khz).clamp(*freq_range.start(), *freq_range.end());
+        if start >= stop || min_amp_dbm >= max_amp_dbm {
+            return false;
+        }
+
+        self.config = self.build_config(
+            start,
+            stop,
+            self.config.sweep_len,
+            self.config.is_expansion_radio_module_active,
+        );
+        self.config.min_amp_dbm = min_amp_dbm;
+        self.config.max_amp_dbm = max_amp_dbm;
+        true
+    }
+
+    /// Changes the number of sweep points while keeping the start and stop frequencies.
+    fn set_sweep_len(&mut self, sweep_len: u16) -> bool {
+        let capabilities = self.capabilities();
+        if !capabilities.can_set_sweep_len {
+            return false;
+        }
+
+        // Like a real RF Explorer, the number of points is rounded down to a multiple of 16
+        let sweep_len =
+            (sweep_len / 16 * 16).clamp(capabilities.min_sweep_len, capabilities.max_sweep_len);
+        self.rebuild_config(sweep_len, self.config.is_expansion_radio_module_active);
+        true
+    }
+
+    /// Activates the main or expansion radio module.
+    fn switch_module(&mut self, to_expansion: bool) -> bool {
+        if to_expansion && self.scenario.device.expansion_radio_model.is_none() {
+            return false;
+        }
+
+        self.rebuild_config(self.config.sweep_len, to_expansion);
+        true
+    }
+
+    fn rebuild_config(&mut self, sweep_len: u16, is_expansion_radio_module_active: bool) {
+        let model = self.model(is_expansion_radio_module_active);
+        let (mut start, mut stop) = (self.config.start_freq, self.config.stop_freq);
+        if !model.freq_range().contains(&start) || !model.freq_range().contains(&stop) {
+            // Sweep the start of the new module's range if the old range is outside of it
+            start = model.min_freq();
+            stop = (start + model.max_span()).min(model.max_freq());
+        }
+
+        let (min_amp_dbm, max_amp_dbm) = (self.config.min_amp_dbm, self.config.max_amp_dbm);
+        self.config = self.build_config(start, stop, sweep_len, is_expansion_radio_module_active);
+        self.config.min_amp_dbm = min_amp_dbm;
+        self.config.max_amp_dbm = max_amp_dbm;
+    }
+
+    fn build_config(
+        &self,
+        start: Frequency,
+        stop: Frequency,
+        sweep_len: u16,
+        is_expansion_radio_module_active: bool,
+    ) -> Config {
+        let model = self.model(is_expansion_radio_module_active);
+        // Configs report the start frequency in kHz and the step size in Hz
+        let start_freq = Frequency::from_khz(start.as_khz());
+        let step_size = (stop.max(start_freq) - start_freq) / u64::from(sweep_len - 1);
+        let stop_freq = start_freq + step_size * u64::from(sweep_len - 1);
+        Config {
+            start_freq,
+            step_size,
+            stop_freq,
+            center_freq: start_freq + (stop_freq - start_freq) / 2,
+            span: stop_freq - start_freq,
+            max_amp_dbm: self.config.max_amp_dbm,
+            min_amp_dbm: self.config.min_amp_dbm,
+            sweep_len,
+            is_expansion_radio_module_active,
+            mode: Mode::SpectrumAnalyzer,
+            min_freq: model.min_freq(),
+            max_freq: model.max_freq(),
+            max_span: model.max_span(),
+            rbw: Some(
+                self.scenario
+                    .config
+                    .rbw
+                    .unwrap_or(step_size)
+                    .max(Frequency::from_khz(1)),
+            ),
+            amp_offset_db: Some(0),
+            calc_mode: Some(CalcMode::Normal),
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn model(&self, is_expansion_radio_module_active: bool) -> Model {
+        let device = &self.scenario.device;
+        match device.expansion_radio_model {
+            Some(model) if is_expansion_radio_module_active => model,
+            _ => device.main_radio_model,
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::new(
+            self.model(self.config.is_expansion_radio_module_active),
+            self.scenario.device.expansion_radio_model,
+        )
+    }
+
+    fn setup_info(&self) -> SetupInfo<Model> {
+        SetupInfo {
+            main_radio_model: Some(self.scenario.device.main_radio_model),
+            expansion_radio_model: self.scenario.device.expansion_radio_model,
+            firmware_version: self.scenario.device.firmware_version.as_str().into(),
+        }
+    }
+
+    /// Returns a pseudorandom number between 0 and 1.
+    fn next_random(&mut self) -> f32 {
+        // xorshift64*
+        self.rng_state ^= self.rng_state >> 12;
+        self.rng_state ^= self.rng_state << 25;
+        self.rng_state ^= self.rng_state >> 27;
+        let random = self.rng_state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (random >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+    use crate::{
+        rf_explorer,
+        simulator::Tone,
+        spectrum_analyzer::{Command, Message},
+    };
+
+    // The 50th point of the default 2.4-2.5 GHz sweep
+    fn tone_freq() -> Frequency {
+        Frequency::from_hz(2_400_000_000 + 900_900 * 50)
+    }
+
+    fn scenario() -> Scenario {
+        let mut scenario = Scenario::default();
+        scenario.sweeps.tones.push(Tone {
+            freq: tone_freq(),
+            amp_dbm: -40.0,
+            width: Frequency::default(),
+        });
+        scenario
+    }
+
+    fn parse_config(bytes: &[u8]) -> Config {
+        let line_end = bytes.windows(2).position(|w| w == b"\r\n").unwrap();
+        Config::try_from(&bytes[..line_end]).unwrap()
+    }
+
+    #[test]
+    fn reply_to_request_config() {
+        let mut simulator = Simulator::new(scenario());
+        assert!(!simulator.is_sweeping());
+
+        // The command is split to make sure partial commands are buffered
+        let command = Cow::from(rf_explorer::Command::RequestConfig);
+        assert!(simulator.receive(&command[..2]).is_empty());
+        let replies = simulator.receive(&command[2..]);
+        assert!(simulator.is_sweeping());
+
+        let setup_info_len = replies.windows(2).position(|w| w == b"\r\n").unwrap();
+        let setup_info = SetupInfo::<Model>::try_from(&replies[..setup_info_len]).unwrap();
+        assert_eq!(setup_info.main_radio_model, Some(Model::Rfe6GPlus));
+        let config = parse_config(&replies[setup_info_len + 2..]);
+        assert_eq!(config.start_freq, Frequency::from_mhz(2_400));
+        assert_eq!(config.sweep_len, 112);
+    }
+
+    #[test]
+    fn apply_set_config_and_sweep_len() {
+        let mut simulator = Simulator::new(scenario());
+        let config = parse_config(&simulator.receive(&Cow::from(Command::SetConfig {
+            start: Frequency::from_mhz(2_410),
+            stop: Frequency::from_mhz(2_480),
+            min_amp_dbm: -110,
+            max_amp_dbm: -20,
+        })));
+        assert!(config.contains_start_stop_amp_range(
+            Frequency::from_mhz(2_410),
+            Frequency::from_mhz(2_480),
+            -110,
+            -20
+        ));
+
+        let config = parse_config(&simulator.receive(&Cow::from(Command::SetSweepPointsExt(250))));
+        assert_eq!(config.sweep_len, 240);
+        assert_eq!(config.start_freq, Frequency::from_mhz(2_410));
+    }
+
+    #[test]
+    fn generate_sweeps_with_tones() {
+        let mut simulator = Simulator::new(scenario());
+        let bytes = simulator.next_sweep();
+        let Ok(Message::Sweep(sweep)) = Message::try_from(bytes.as_slice()) else {
+            panic!("Expected a sweep");
+        };
+
+        let tone_index = 50;
+        assert_eq!(sweep.amplitudes_dbm.len(), 112);
+        assert!((sweep.amplitudes_dbm[tone_index] + 40.0).abs() <= 2.5);
+        assert!(
+            sweep
+                .amplitudes_dbm
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != tone_index)
+                .all(|(_, amp_dbm)| (*amp_dbm + 105.0).abs() <= 2.5)
+        );
+    }
+}
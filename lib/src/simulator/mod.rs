@@ -0,0 +1,38 @@
+//! An emulated RF Explorer spectrum analyzer for development and tests without hardware.
+//!
+//! A [`Simulator`] answers the commands a host sends, like requests for the config or changes to
+//! the start and stop frequencies, and produces synthetic sweeps made of a noise floor and the
+//! tones described by its [`Scenario`]. [`serve`] runs a simulator on any connection, and
+//! [`TcpPort`] connects to a simulator that's served on a TCP socket.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::{net::TcpListener, thread};
+//!
+//! use rfe::{
+//!     SpectrumAnalyzer,
+//!     simulator::{self, Scenario, Simulator, TcpPort},
+//! };
+//!
+//! let listener = TcpListener::bind("127.0.0.1:0")?;
+//! let addr = listener.local_addr()?;
+//! thread::spawn(move || {
+//!     let (stream, _) = listener.accept()?;
+//!     simulator::serve(Simulator::new(Scenario::default()), stream.try_clone()?, stream)
+//! });
+//!
+//! let rfe = SpectrumAnalyzer::connect_with_transport(Box::new(TcpPort::connect(addr)?))?;
+//! let sweep = rfe.wait_for_next_sweep()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod device;
+mod scenario;
+mod serve;
+mod tcp_port;
+
+pub use device::Simulator;
+pub use scenario::{ConfigScenario, DeviceScenario, Scenario, ScenarioError, SweepScenario, Tone};
+pub use serve::serve;
+pub use tcp_port::TcpPort;
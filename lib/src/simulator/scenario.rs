@@ -0,0 +1,288 @@
+use std::{fs, io, path::Path, time::Duration};
+
+use serde::{Deserialize, Deserializer, de};
+use thiserror::Error;
+
+use crate::{Frequency, spectrum_analyzer::Model};
+
+/// Describes the RF Explorer that a [`Simulator`](super::Simulator) emulates.
+///
+/// Scenarios are usually loaded from TOML files. Every section and field is optional, and
+/// frequencies are written with a unit, like `"2.4 GHz"` or `"915M"`.
+///
+/// ```toml
+/// [device]
+/// main_radio_model = "6G+"
+/// firmware_version = "03.41"
+///
+/// [config]
+/// start = "2.4 GHz"
+/// stop = "2.5 GHz"
+/// sweep_len = 224
+///
+/// [sweeps]
+/// interval_ms = 100
+/// noise_floor_dbm = -105.0
+///
+/// [[sweeps.tones]]
+/// freq = "2.437 GHz"
+/// amp_dbm = -45.0
+/// width = "2 MHz"
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    /// The emulated device.
+    pub device: DeviceScenario,
+    /// The spectrum analyzer's config when the simulator starts.
+    pub config: ConfigScenario,
+    /// The synthetic sweeps measured by the spectrum analyzer.
+    pub sweeps: SweepScenario,
+}
+
+/// The models, firmware, and serial number of the emulated device.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeviceScenario {
+    /// The main radio module's model, written like it's shown on the device (e.g. `"6G+"`).
+    #[serde(deserialize_with = "deserialize_model")]
+    pub main_radio_model: Model,
+    /// The expansion radio module's model, if the device has one.
+    #[serde(deserialize_with = "deserialize_opt_model")]
+    pub expansion_radio_model: Option<Model>,
+    /// The firmware version reported by the device.
+    pub firmware_version: String,
+    /// The 16-character serial number reported by the device.
+    pub serial_number: String,
+}
+
+impl Default for DeviceScenario {
+    fn default() -> Self {
+        DeviceScenario {
+            main_radio_model: Model::Rfe6GPlus,
+            expansion_radio_model: None,
+            firmware_version: "03.41".to_string(),
+            serial_number: "SIMULATOR0000001".to_string(),
+        }
+    }
+}
+
+/// The spectrum analyzer's config when the simulator starts.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigScenario {
+    /// Sweep start frequency.
+    #[serde(deserialize_with = "deserialize_freq")]
+    pub start: Frequency,
+    /// Sweep stop frequency.
+    #[serde(deserialize_with = "deserialize_freq")]
+    pub stop: Frequency,
+    /// Bottom displayed amplitude in dBm.
+    pub min_amp_dbm: i16,
+    /// Top displayed amplitude in dBm.
+    pub max_amp_dbm: i16,
+    /// Number of points in each sweep.
+    pub sweep_len: u16,
+    /// Resolution bandwidth reported in the config. Defaults to the step size between points.
+    #[serde(deserialize_with = "deserialize_opt_freq")]
+    pub rbw: Option<Frequency>,
+}
+
+impl Default for ConfigScenario {
+    fn default() -> Self {
+        ConfigScenario {
+            start: Frequency::from_mhz(2_400),
+            stop: Frequency::from_mhz(2_500),
+            min_amp_dbm: -120,
+            max_amp_dbm: -10,
+            sweep_len: 112,
+            rbw: None,
+        }
+    }
+}
+
+/// How often sweeps are sent and what they contain.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SweepScenario {
+    /// Milliseconds between sweeps.
+    pub interval_ms: u64,
+    /// Average amplitude in dBm of points that don't contain a tone.
+    pub noise_floor_dbm: f32,
+    /// Peak-to-peak amplitude in dB of the random noise added to every point.
+    pub noise_dbm: f32,
+    /// Seed of the random noise, so runs of a scenario can be reproduced.
+    pub seed: u64,
+    /// Signals that stand out above the noise floor.
+    pub tones: Vec<Tone>,
+}
+
+impl SweepScenario {
+    /// The time between sweeps.
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+}
+
+impl Default for SweepScenario {
+    fn default() -> Self {
+        SweepScenario {
+            interval_ms: 100,
+            noise_floor_dbm: -105.0,
+            noise_dbm: 4.0,
+            seed: 1,
+            tones: Vec::new(),
+        }
+    }
+}
+
+/// A signal at a fixed frequency and amplitude.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tone {
+    /// Center frequency of the signal.
+    #[serde(deserialize_with = "deserialize_freq")]
+    pub freq: Frequency,
+    /// Amplitude of the signal in dBm.
+    pub amp_dbm: f32,
+    /// Bandwidth of the signal. Narrow signals still show up in the point closest to them.
+    #[serde(default, deserialize_with = "deserialize_freq")]
+    pub width: Frequency,
+}
+
+/// Error returned when a scenario can't be loaded.
+#[derive(Error, Debug)]
+pub enum ScenarioError {
+    /// The scenario file couldn't be read.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    /// The scenario isn't valid TOML or contains unknown fields.
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+
+    /// The scenario describes a device that can't be emulated.
+    #[error("Invalid scenario: {}", .0)]
+    Invalid(String),
+}
+
+impl Scenario {
+    /// Parses a scenario from TOML.
+    pub fn from_toml(toml: &str) -> Result<Self, ScenarioError> {
+        let scenario: Scenario = toml::from_str(toml)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Reads a scenario from a TOML file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    fn validate(&self) -> Result<(), ScenarioError> {
+        let invalid = |message: &str| Err(ScenarioError::Invalid(message.to_string()));
+        if self.device.main_radio_model == Model::Unknown {
+            return invalid("The main radio model must be a known model");
+        }
+        if self.device.serial_number.len() != 16
+            || !self
+                .device
+                .serial_number
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric())
+        {
+            return invalid("The serial number must be 16 letters or digits");
+        }
+        if self.config.start >= self.config.stop {
+            return invalid("The start frequency must be less than the stop frequency");
+        }
+        if self.config.min_amp_dbm >= self.config.max_amp_dbm {
+            return invalid("The minimum amplitude must be less than the maximum amplitude");
+        }
+        if self.config.sweep_len < 2 {
+            return invalid("Sweeps must contain at least 2 points");
+        }
+        if self.sweeps.interval_ms == 0 {
+            return invalid("The sweep interval must be at least 1 ms");
+        }
+        Ok(())
+    }
+}
+
+fn deserialize_freq<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Frequency, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(de::Error::custom)
+}
+
+fn deserialize_opt_freq<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Frequency>, D::Error> {
+    deserialize_freq(deserializer).map(Some)
+}
+
+fn deserialize_model<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Model, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    (0..=u8::from(Model::Unknown))
+        .filter_map(|code| Model::try_from(code).ok())
+        .find(|model| model.to_string().eq_ignore_ascii_case(&name))
+        .ok_or_else(|| de::Error::custom(format!("'{name}' is not an RF Explorer model")))
+}
+
+fn deserialize_opt_model<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Model>, D::Error> {
+    deserialize_model(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_scenario() {
+        let scenario = Scenario::from_toml(
+            r#"
+            [device]
+            main_radio_model = "6G"
+            expansion_radio_model = "wsub3g"
+
+            [config]
+            start = "5.2 GHz"
+            stop = "5300M"
+
+            [[sweeps.tones]]
+            freq = "5.25 GHz"
+            amp_dbm = -40.0
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(scenario.device.main_radio_model, Model::Rfe6G);
+        assert_eq!(
+            scenario.device.expansion_radio_model,
+            Some(Model::RfeWSub3G)
+        );
+        assert_eq!(scenario.config.start, Frequency::from_mhz(5_200));
+        assert_eq!(scenario.config.stop, Frequency::from_mhz(5_300));
+        assert_eq!(scenario.config.sweep_len, 112);
+        assert_eq!(scenario.sweeps.tones[0].freq, Frequency::from_mhz(5_250));
+        assert_eq!(scenario.sweeps.tones[0].width, Frequency::default());
+    }
+
+    #[test]
+    fn reject_invalid_scenarios() {
+        assert!(matches!(
+            Scenario::from_toml("[device]\nmain_radio_model = \"7G\""),
+            Err(ScenarioError::Toml(_))
+        ));
+        assert!(matches!(
+            Scenario::from_toml("[config]\nstart = \"2.5 GHz\"\nstop = \"2.4 GHz\""),
+            Err(ScenarioError::Invalid(_))
+        ));
+        assert!(matches!(
+            Scenario::from_toml("[sweeps]\nunknown = 1"),
+            Err(ScenarioError::Toml(_))
+        ));
+    }
+}
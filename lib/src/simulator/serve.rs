@@ -0,0 +1,109 @@
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use tracing::debug;
+
+use super::Simulator;
+
+/// Runs a simulator on a connection until the host closes it.
+///
+/// Commands read from `reader` are answered on `writer`, and sweeps are written to `writer` at
+/// the scenario's interval while the simulator is sweeping. For a TCP connection, `reader` and
+/// `writer` are usually clones of the same stream.
+///
+/// Returns `Ok(())` once `reader` reaches the end of the stream, or the first error that isn't a
+/// timeout.
+pub fn serve(
+    simulator: Simulator,
+    reader: impl Read + Send,
+    writer: impl Write + Send,
+) -> io::Result<()> {
+    let simulator = Mutex::new(simulator);
+    let writer = Mutex::new(writer);
+    let is_connected = AtomicBool::new(true);
+
+    thread::scope(|scope| {
+        let sweeps = scope.spawn(|| send_sweeps(&simulator, &writer, &is_connected));
+        let result = answer_commands(&simulator, reader, &writer, &is_connected);
+        is_connected.store(false, Ordering::Relaxed);
+        let sweeps_result = sweeps.join().unwrap();
+        result.and(sweeps_result)
+    })
+}
+
+fn answer_commands(
+    simulator: &Mutex<Simulator>,
+    mut reader: impl Read,
+    writer: &Mutex<impl Write>,
+    is_connected: &AtomicBool,
+) -> io::Result<()> {
+    let mut buf = [0; 256];
+    while is_connected.load(Ordering::Relaxed) {
+        let len = match reader.read(&mut buf) {
+            Ok(0) => {
+                debug!("The host closed the connection");
+                return Ok(());
+            }
+            Ok(len) => len,
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    ErrorKind::Interrupted | ErrorKind::TimedOut | ErrorKind::WouldBlock
+                ) =>
+            {
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+
+        let replies = simulator.lock().unwrap().receive(&buf[..len]);
+        if !replies.is_empty() {
+            let mut writer = writer.lock().unwrap();
+            writer.write_all(&replies)?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn send_sweeps(
+    simulator: &Mutex<Simulator>,
+    writer: &Mutex<impl Write>,
+    is_connected: &AtomicBool,
+) -> io::Result<()> {
+    // Sleep in short steps so the connection can be closed without waiting for a long interval
+    const MAX_SLEEP: Duration = Duration::from_millis(50);
+
+    let mut since_last_sweep = Duration::ZERO;
+    while is_connected.load(Ordering::Relaxed) {
+        let interval = simulator.lock().unwrap().sweep_interval();
+        if since_last_sweep < interval {
+            let sleep = (interval - since_last_sweep).min(MAX_SLEEP);
+            thread::sleep(sleep);
+            since_last_sweep += sleep;
+            continue;
+        }
+        since_last_sweep = Duration::ZERO;
+
+        let sweep = {
+            let mut simulator = simulator.lock().unwrap();
+            if !simulator.is_sweeping() {
+                continue;
+            }
+            simulator.next_sweep()
+        };
+        let mut writer = writer.lock().unwrap();
+        if let Err(error) = writer.write_all(&sweep).and_then(|()| writer.flush()) {
+            is_connected.store(false, Ordering::Relaxed);
+            return Err(error);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,541 @@
+use std::{sync::mpsc, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use super::SweepMeta;
+use crate::{Dbm, Frequency};
+
+/// The settings that determine what each amplitude in a sweep measures.
+///
+/// Sweeps with different keys can't be combined, so [`LongTermAccumulator`] starts a new series
+/// whenever the key changes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SeriesKey {
+    /// The start frequency of the sweeps in the series.
+    pub start_freq: Frequency,
+    /// The stop frequency of the sweeps in the series.
+    pub stop_freq: Frequency,
+    /// The number of points in each sweep in the series.
+    pub sweep_len: u16,
+    /// The [config generation](SweepMeta::config_generation) of the sweeps in the series, if
+    /// they were added with [`LongTermAccumulator::add_sweep_with_meta`].
+    ///
+    /// A config change that isn't visible in the sweeps' frequencies or length, like a new RBW,
+    /// still starts a new series.
+    pub config_generation: Option<u64>,
+}
+
+/// Per-bin statistics for the sweeps received during one aggregation window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateFrame {
+    /// The series the sweeps belong to.
+    ///
+    /// Series are numbered from 0 and the number increases every time the key changes.
+    pub series: u64,
+    /// The settings shared by every sweep in the series.
+    pub key: SeriesKey,
+    /// The start of the aggregation window.
+    pub window_start: DateTime<Utc>,
+    /// The end of the aggregation window.
+    ///
+    /// Frames closed early because the series changed still report the window's scheduled end.
+    pub window_end: DateTime<Utc>,
+    /// The number of sweeps included in the frame.
+    pub sweep_count: u32,
+    /// The minimum amplitude of each bin in dBm.
+    pub min_dbm: Vec<f32>,
+    /// The maximum amplitude of each bin in dBm.
+    pub max_dbm: Vec<f32>,
+    /// The mean power of each bin in dBm.
+    ///
+    /// Amplitudes are averaged as linear power, so the mean is dominated by the strongest
+    /// sweeps rather than being the mean of the dBm values.
+    pub mean_dbm: Vec<f32>,
+    /// The coarsest amplitude resolution in dB of the sweeps in the frame, if they were added with
+    /// [`LongTermAccumulator::add_sweep_with_meta`].
+    pub amplitude_resolution_db: Option<f32>,
+}
+
+impl AggregateFrame {
+    /// Returns whether every amplitude of the bin was within one quantization step of each other.
+    ///
+    /// The bin's variation is then below the quantization floor of the sweeps' wire format, so
+    /// it can't be told apart from rounding. Returns `None` if the frame's amplitude resolution
+    /// isn't known or the bin doesn't exist.
+    pub fn is_below_quantization_floor(&self, bin: usize) -> Option<bool> {
+        let resolution_db = self.amplitude_resolution_db?;
+        let spread_db = self.max_dbm.get(bin)? - self.min_dbm.get(bin)?;
+        Some(spread_db <= resolution_db)
+    }
+}
+
+enum FrameOutput {
+    Callback(Box<dyn FnMut(AggregateFrame) + Send>),
+    Queue(mpsc::Sender<AggregateFrame>),
+}
+
+/// The statistics of the window currently being accumulated.
+struct Window {
+    series: u64,
+    key: SeriesKey,
+    start: DateTime<Utc>,
+    sweep_count: u32,
+    min_dbm: Vec<f32>,
+    max_dbm: Vec<f32>,
+    sum_mw: Vec<f64>,
+    amplitude_resolution_db: Option<f32>,
+}
+
+/// Reduces a stream of sweeps to per-bin minimum, maximum, and mean power over fixed windows of
+/// time.
+///
+/// Windows are aligned to multiples of the window length since the Unix epoch, so a 60 s window
+/// produces one [`AggregateFrame`] per minute on the minute. A frame is emitted when the first
+/// sweep of a later window is added, when the sweep's [`SeriesKey`] changes, or when
+/// [`LongTermAccumulator::flush`] is called. Only the current window is kept in memory, so memory
+/// use depends on the number of bins and not on the number of sweeps.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{sync::Mutex, time::Duration};
+///
+/// use chrono::Utc;
+/// use rfe::SpectrumAnalyzer;
+/// use rfe::spectrum_analyzer::LongTermAccumulator;
+///
+/// let rfe = SpectrumAnalyzer::connect().expect("RF Explorer should be connected");
+/// let (accumulator, frames) = LongTermAccumulator::with_queue(Duration::from_secs(60));
+/// let accumulator = Mutex::new(accumulator);
+/// rfe.set_sweep_callback(move |amps, start_freq, stop_freq| {
+///     accumulator
+///         .lock()
+///         .unwrap()
+///         .add_sweep(Utc::now(), start_freq, stop_freq, amps);
+/// });
+///
+/// for frame in frames {
+///     println!("{}: {:?}", frame.window_start, frame.max_dbm);
+/// }
+/// ```
+pub struct LongTermAccumulator {
+    window_len: TimeDelta,
+    output: FrameOutput,
+    window: Option<Window>,
+    next_series: u64,
+}
+
+impl LongTermAccumulator {
+    /// Creates an accumulator that calls `cb` with every completed frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window_len` is shorter than 1 ms.
+    pub fn with_callback(
+        window_len: Duration,
+        cb: impl FnMut(AggregateFrame) + Send + 'static,
+    ) -> Self {
+        Self::new(window_len, FrameOutput::Callback(Box::new(cb)))
+    }
+
+    /// Creates an accumulator that sends every completed frame to the returned queue.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window_len` is shorter than 1 ms.
+    pub fn with_queue(window_len: Duration) -> (Self, mpsc::Receiver<AggregateFrame>) {
+        let (sender, receiver) = mpsc::channel();
+        (Self::new(window_len, FrameOutput::Queue(sender)), receiver)
+    }
+
+    fn new(window_len: Duration, output: FrameOutput) -> Self {
+        let window_len = TimeDelta::from_std(window_len).unwrap_or(TimeDelta::MAX);
+        assert!(
+            window_len >= TimeDelta::milliseconds(1),
+            "The aggregation window must be at least 1 ms long"
+        );
+        Self {
+            window_len,
+            output,
+            window: None,
+            next_series: 0,
+        }
+    }
+
+    /// The length of each aggregation window.
+    pub fn window_len(&self) -> Duration {
+        self.window_len.to_std().unwrap_or_default()
+    }
+
+    /// Adds a sweep received at `timestamp` to the current window.
+    ///
+    /// The current window is closed first if the sweep belongs to a later window or if its start
+    /// frequency, stop frequency, or length differ from the previous sweep's. Sweeps received
+    /// before the start of the current window are added to the current window.
+    pub fn add_sweep(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        start_freq: Frequency,
+        stop_freq: Frequency,
+        amplitudes_dbm: &[f32],
+    ) {
+        self.add_sweep_with_key(timestamp, start_freq, stop_freq, None, amplitudes_dbm);
+    }
+
+    fn add_sweep_with_key(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        start_freq: Frequency,
+        stop_freq: Frequency,
+        config_generation: Option<u64>,
+        amplitudes_dbm: &[f32],
+    ) {
+        let key = SeriesKey {
+            start_freq,
+            stop_freq,
+            sweep_len: u16::try_from(amplitudes_dbm.len()).unwrap_or(u16::MAX),
+            config_generation,
+        };
+        let window_start = self.window_start(timestamp);
+
+        let series = match &self.window {
+            Some(window) if window.key == key => {
+                if window_start > window.start {
+                    self.close_window(window_start);
+                }
+                None
+            }
+            Some(_) => {
+                self.flush();
+                Some(self.start_series())
+            }
+            None => Some(self.start_series()),
+        };
+        if let Some(series) = series {
+            self.window = Some(Window {
+                series,
+                key,
+                start: window_start,
+                sweep_count: 0,
+                min_dbm: vec![f32::INFINITY; amplitudes_dbm.len()],
+                max_dbm: vec![f32::NEG_INFINITY; amplitudes_dbm.len()],
+                sum_mw: vec![0.; amplitudes_dbm.len()],
+                amplitude_resolution_db: None,
+            });
+        }
+
+        let Some(window) = self.window.as_mut() else {
+            return;
+        };
+        window.sweep_count += 1;
+        for (i, &amp_dbm) in amplitudes_dbm.iter().enumerate() {
+            window.min_dbm[i] = window.min_dbm[i].min(amp_dbm);
+            window.max_dbm[i] = window.max_dbm[i].max(amp_dbm);
+            window.sum_mw[i] += dbm_to_mw(amp_dbm);
+        }
+    }
+
+    /// Adds a sweep with amplitudes in [`Dbm`] received at `timestamp` to the current window.
+    ///
+    /// See [`LongTermAccumulator::add_sweep`].
+    pub fn add_sweep_dbm(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        start_freq: Frequency,
+        stop_freq: Frequency,
+        amplitudes: &[Dbm],
+    ) {
+        self.add_sweep(
+            timestamp,
+            start_freq,
+            stop_freq,
+            Dbm::as_f32_slice(amplitudes),
+        );
+    }
+
+    /// Adds a sweep received at `timestamp` using the frequencies, config generation and wire
+    /// format in its [`SweepMeta`].
+    ///
+    /// The current window is also closed and a new series started if the sweep's
+    /// [config generation](SweepMeta::config_generation) differs from the previous sweep's, so
+    /// sweeps received with different configs are never combined. The frame the sweep ends up in
+    /// reports the coarsest [amplitude resolution](super::SweepEncoding::amplitude_resolution_db)
+    /// of its sweeps. See [`LongTermAccumulator::add_sweep`].
+    pub fn add_sweep_with_meta(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        amplitudes_dbm: &[f32],
+        meta: &SweepMeta,
+    ) {
+        self.add_sweep_with_key(
+            timestamp,
+            meta.start_freq,
+            meta.stop_freq,
+            Some(meta.config_generation),
+            amplitudes_dbm,
+        );
+        if let Some(window) = self.window.as_mut() {
+            let resolution_db = meta.encoding.amplitude_resolution_db();
+            window.amplitude_resolution_db = Some(
+                window
+                    .amplitude_resolution_db
+                    .map_or(resolution_db, |previous| previous.max(resolution_db)),
+            );
+        }
+    }
+
+    /// Emits the current window's frame, if any sweeps have been added to it, and ends the
+    /// current series.
+    ///
+    /// Sweeps added with [`LongTermAccumulator::add_sweep_with_meta`] start a new series by
+    /// themselves when the spectrum analyzer's config changes. For sweeps added without their
+    /// meta, call this when the config changes in a way that isn't visible in its sweeps, like a
+    /// new RBW. The accumulator is also flushed when it's dropped.
+    pub fn flush(&mut self) {
+        if let Some(window) = self.window.take() {
+            self.emit(&window);
+        }
+    }
+
+    /// Emits the current window's frame and resets the window to start at `window_start`.
+    ///
+    /// The window's buffers are reused so that closing a window doesn't allocate.
+    fn close_window(&mut self, window_start: DateTime<Utc>) {
+        let Some(mut window) = self.window.take() else {
+            return;
+        };
+        self.emit(&window);
+        window.start = window_start;
+        window.sweep_count = 0;
+        window.min_dbm.fill(f32::INFINITY);
+        window.max_dbm.fill(f32::NEG_INFINITY);
+        window.sum_mw.fill(0.);
+        window.amplitude_resolution_db = None;
+        self.window = Some(window);
+    }
+
+    fn emit(&mut self, window: &Window) {
+        if window.sweep_count == 0 {
+            return;
+        }
+
+        let frame = AggregateFrame {
+            series: window.series,
+            key: window.key,
+            window_start: window.start,
+            window_end: window.start + self.window_len,
+            sweep_count: window.sweep_count,
+            min_dbm: window.min_dbm.clone(),
+            max_dbm: window.max_dbm.clone(),
+            mean_dbm: window
+                .sum_mw
+                .iter()
+                .map(|&sum_mw| mw_to_dbm(sum_mw / f64::from(window.sweep_count)))
+                .collect(),
+            amplitude_resolution_db: window.amplitude_resolution_db,
+        };
+        match &mut self.output {
+            FrameOutput::Callback(cb) => cb(frame),
+            // Nobody is listening anymore if the receiver has been dropped
+            FrameOutput::Queue(sender) => {
+                let _ = sender.send(frame);
+            }
+        }
+    }
+
+    fn start_series(&mut self) -> u64 {
+        let series = self.next_series;
+        self.next_series += 1;
+        series
+    }
+
+    /// Returns the start of the window that `timestamp` falls in.
+    fn window_start(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let window_len_ms = self.window_len.num_milliseconds();
+        let since_epoch_ms = (timestamp - DateTime::UNIX_EPOCH).num_milliseconds();
+        DateTime::UNIX_EPOCH
+            + TimeDelta::milliseconds(since_epoch_ms - since_epoch_ms.rem_euclid(window_len_ms))
+    }
+}
+
+impl Drop for LongTermAccumulator {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+fn dbm_to_mw(dbm: f32) -> f64 {
+    10f64.powf(f64::from(dbm) / 10.)
+}
+
+fn mw_to_dbm(mw: f64) -> f32 {
+    (10. * mw.log10()) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::spectrum_analyzer::SweepEncoding;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::UNIX_EPOCH + TimeDelta::seconds(secs)
+    }
+
+    fn add_sweep(accumulator: &mut LongTermAccumulator, secs: i64, amplitudes_dbm: &[f32]) {
+        accumulator.add_sweep(
+            at(secs),
+            Frequency::from_mhz(100),
+            Frequency::from_mhz(200),
+            amplitudes_dbm,
+        );
+    }
+
+    #[test]
+    fn aggregate_sweeps_in_window() {
+        let (mut accumulator, frames) = LongTermAccumulator::with_queue(Duration::from_secs(60));
+        add_sweep(&mut accumulator, 120, &[-100., -50.]);
+        add_sweep(&mut accumulator, 150, &[-80., -50.]);
+        assert!(frames.try_recv().is_err());
+
+        // The first sweep of the next window closes the previous one
+        add_sweep(&mut accumulator, 185, &[-90., -90.]);
+        let frame = frames.try_recv().unwrap();
+        assert_eq!(frame.series, 0);
+        assert_eq!(frame.window_start, at(120));
+        assert_eq!(frame.window_end, at(180));
+        assert_eq!(frame.sweep_count, 2);
+        assert_eq!(frame.min_dbm, vec![-100., -50.]);
+        assert_eq!(frame.max_dbm, vec![-80., -50.]);
+        // -100 dBm adds little power to -80 dBm, so the mean is close to half of -80 dBm's power
+        assert!((frame.mean_dbm[0] - -82.97).abs() < 0.01);
+        assert!((frame.mean_dbm[1] - -50.).abs() < 0.001);
+
+        accumulator.flush();
+        let frame = frames.try_recv().unwrap();
+        assert_eq!(frame.window_start, at(180));
+        assert_eq!(frame.sweep_count, 1);
+        assert!(frames.try_recv().is_err());
+    }
+
+    #[test]
+    fn start_new_series_when_key_changes() {
+        let (mut accumulator, frames) = LongTermAccumulator::with_queue(Duration::from_secs(60));
+        add_sweep(&mut accumulator, 0, &[-100.; 4]);
+        add_sweep(&mut accumulator, 10, &[-100.; 4]);
+        // Changing the sweep length closes the window before it would have ended
+        add_sweep(&mut accumulator, 20, &[-90.; 8]);
+        accumulator.add_sweep(
+            at(30),
+            Frequency::from_mhz(150),
+            Frequency::from_mhz(250),
+            &[-80.; 8],
+        );
+        drop(accumulator);
+
+        let frames: Vec<_> = frames.iter().collect();
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| (frame.series, frame.sweep_count, frame.key.sweep_len))
+                .collect::<Vec<_>>(),
+            vec![(0, 2, 4), (1, 1, 8), (2, 1, 8)]
+        );
+        assert_eq!(frames[2].key.start_freq, Frequency::from_mhz(150));
+        assert!(frames.iter().all(|frame| frame.window_start == at(0)));
+    }
+
+    #[test]
+    fn start_new_series_when_config_generation_changes() {
+        let (mut accumulator, frames) = LongTermAccumulator::with_queue(Duration::from_secs(60));
+        let meta = SweepMeta {
+            start_freq: Frequency::from_mhz(100),
+            stop_freq: Frequency::from_mhz(200),
+            ..SweepMeta::default()
+        };
+        accumulator.add_sweep_with_meta(at(0), &[-100.; 4], &meta);
+        accumulator.add_sweep_with_meta(at(10), &[-100.; 4], &meta);
+        // Only the config generation changes, like after a new RBW was set
+        let new_meta = SweepMeta {
+            config_generation: 1,
+            ..meta
+        };
+        accumulator.add_sweep_with_meta(at(20), &[-90.; 4], &new_meta);
+        drop(accumulator);
+
+        let frames: Vec<_> = frames.iter().collect();
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| (frame.series, frame.sweep_count, frame.key.config_generation))
+                .collect::<Vec<_>>(),
+            vec![(0, 2, Some(0)), (1, 1, Some(1))]
+        );
+        assert_eq!(frames[1].max_dbm, vec![-90.; 4]);
+    }
+
+    #[test]
+    fn report_bins_below_quantization_floor() {
+        let (mut accumulator, frames) = LongTermAccumulator::with_queue(Duration::from_secs(60));
+        let meta = SweepMeta {
+            start_freq: Frequency::from_mhz(100),
+            stop_freq: Frequency::from_mhz(200),
+            encoding: SweepEncoding::Large,
+            ..SweepMeta::default()
+        };
+        accumulator.add_sweep_with_meta(at(0), &[-100., -80.], &meta);
+        accumulator.add_sweep_with_meta(at(10), &[-100.5, -70.], &meta);
+        add_sweep(&mut accumulator, 70, &[-100., -80.]);
+
+        let frame = frames.try_recv().unwrap();
+        assert_eq!(frame.key.start_freq, Frequency::from_mhz(100));
+        assert_eq!(frame.amplitude_resolution_db, Some(0.5));
+        assert_eq!(frame.is_below_quantization_floor(0), Some(true));
+        assert_eq!(frame.is_below_quantization_floor(1), Some(false));
+        assert_eq!(frame.is_below_quantization_floor(2), None);
+
+        // Sweeps added without their meta don't have a known resolution
+        accumulator.flush();
+        let frame = frames.try_recv().unwrap();
+        assert_eq!(frame.amplitude_resolution_db, None);
+        assert_eq!(frame.is_below_quantization_floor(0), None);
+    }
+
+    #[test]
+    fn memory_doesnt_grow_with_sweeps() {
+        const SWEEP_LEN: usize = 112;
+        const SWEEP_COUNT: i64 = 10_000;
+
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let frames_clone = frames.clone();
+        let mut accumulator =
+            LongTermAccumulator::with_callback(Duration::from_secs(60), move |frame| {
+                frames_clone
+                    .lock()
+                    .unwrap()
+                    .push((frame.sweep_count, frame.min_dbm.len()));
+            });
+        let mut amplitudes_dbm = [0.; SWEEP_LEN];
+        for i in 0..SWEEP_COUNT {
+            for (j, amp) in amplitudes_dbm.iter_mut().enumerate() {
+                *amp = -120. + ((i as usize + j) % 100) as f32;
+            }
+            add_sweep(&mut accumulator, i, &amplitudes_dbm);
+
+            let window = accumulator.window.as_ref().unwrap();
+            assert_eq!(window.min_dbm.capacity(), SWEEP_LEN);
+            assert_eq!(window.max_dbm.capacity(), SWEEP_LEN);
+            assert_eq!(window.sum_mw.capacity(), SWEEP_LEN);
+        }
+
+        let window = accumulator.window.as_ref().unwrap();
+        assert_eq!(window.series, 0);
+        assert_eq!(window.start, at(SWEEP_COUNT / 60 * 60));
+        assert_eq!(window.sweep_count, (SWEEP_COUNT % 60) as u32);
+        assert_eq!(
+            *frames.lock().unwrap(),
+            vec![(60, SWEEP_LEN); (SWEEP_COUNT / 60) as usize]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use crate::Frequency;
+
+/// How far a spectrum analyzer's sweep points are from where they were requested.
+///
+/// RF Explorer firmware rounds the start frequency and step size it's sent, so the points of its
+/// sweeps don't land exactly where the requested start and stop frequencies would put them. The
+/// error at each point is the start offset plus the step error times the point's index, so it
+/// drifts across the span.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AlignmentReport {
+    /// The requested start frequency.
+    pub requested_start: Frequency,
+    /// The requested stop frequency.
+    pub requested_stop: Frequency,
+    /// The start frequency the firmware used.
+    pub actual_start: Frequency,
+    /// The step size the firmware used.
+    pub actual_step: Frequency,
+    /// The number of points in each sweep.
+    pub sweep_len: u16,
+    /// How far the first point is from the requested start frequency in Hz.
+    pub start_offset_hz: i64,
+    /// How far each step is from the requested step size in Hz.
+    ///
+    /// The requested step size is the requested span divided evenly between the sweep's points.
+    pub step_error_hz: f64,
+}
+
+impl AlignmentReport {
+    pub(crate) fn new(
+        requested_start: Frequency,
+        requested_stop: Frequency,
+        actual_start: Frequency,
+        actual_step: Frequency,
+        sweep_len: u16,
+    ) -> Self {
+        let requested_step_hz = requested_stop
+            .as_hz()
+            .saturating_sub(requested_start.as_hz()) as f64
+            / f64::from(sweep_len.saturating_sub(1).max(1));
+        AlignmentReport {
+            requested_start,
+            requested_stop,
+            actual_start,
+            actual_step,
+            sweep_len,
+            start_offset_hz: actual_start.as_hz() as i64 - requested_start.as_hz() as i64,
+            step_error_hz: actual_step.as_hz() as f64 - requested_step_hz,
+        }
+    }
+
+    /// Returns how far the point at `index` is from where it was requested in Hz.
+    pub fn point_error_hz(&self, index: u16) -> f64 {
+        self.start_offset_hz as f64 + self.step_error_hz * f64::from(index)
+    }
+
+    /// Returns the largest error of any point in Hz.
+    ///
+    /// The error drifts linearly across the span, so it's largest at the first or last point.
+    pub fn max_error_hz(&self) -> f64 {
+        let last_index = self.sweep_len.saturating_sub(1);
+        self.point_error_hz(0)
+            .abs()
+            .max(self.point_error_hz(last_index).abs())
+    }
+
+    /// Returns whether any point is more than half a step from where it was requested, so it's
+    /// closer to a neighboring point's requested frequency than its own.
+    pub fn exceeds_half_step(&self) -> bool {
+        self.max_error_hz() > self.actual_step.as_hz() as f64 / 2.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_start_offset_and_step_drift() {
+        // 100-200 MHz over 101 points should step by exactly 1 MHz
+        let report = AlignmentReport::new(
+            Frequency::from_mhz(100),
+            Frequency::from_mhz(200),
+            Frequency::from_khz(100_002),
+            Frequency::from_hz(999_990),
+            101,
+        );
+        assert_eq!(report.start_offset_hz, 2_000);
+        assert_eq!(report.step_error_hz, -10.);
+        assert_eq!(report.point_error_hz(100), 1_000.);
+        assert_eq!(report.max_error_hz(), 2_000.);
+        assert!(!report.exceeds_half_step());
+    }
+
+    #[test]
+    fn flag_errors_over_half_a_step() {
+        let report = AlignmentReport::new(
+            Frequency::from_mhz(100),
+            Frequency::from_mhz(200),
+            Frequency::from_mhz(100),
+            Frequency::from_hz(1_006_000),
+            101,
+        );
+        assert_eq!(report.max_error_hz(), 600_000.);
+        assert!(report.exceeds_half_step());
+    }
+}
@@ -0,0 +1,338 @@
+use std::{
+    io::{self, Write},
+    sync::Arc,
+};
+
+use arrow_array::{
+    FixedSizeListArray, Float32Array, RecordBatch, TimestampMicrosecondArray, UInt64Array,
+};
+use arrow_buffer::ScalarBuffer;
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+
+use super::{Config, Record, RecordSink};
+
+/// Returns the schema of the record batches created from sweeps with `sweep_len` points.
+///
+/// | Column           | Type                              |
+/// |------------------|-----------------------------------|
+/// | `timestamp`      | `Timestamp(Microsecond, "UTC")`   |
+/// | `start_hz`       | `UInt64`                          |
+/// | `stop_hz`        | `UInt64`                          |
+/// | `amplitudes_dbm` | `FixedSizeList<Float32>[sweep_len]` |
+pub fn sweep_schema(sweep_len: u16) -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("start_hz", DataType::UInt64, false),
+        Field::new("stop_hz", DataType::UInt64, false),
+        Field::new(
+            "amplitudes_dbm",
+            DataType::FixedSizeList(amplitude_field(), i32::from(sweep_len)),
+            false,
+        ),
+    ]))
+}
+
+fn amplitude_field() -> Arc<Field> {
+    Arc::new(Field::new("item", DataType::Float32, false))
+}
+
+/// Converts the sweeps measured with `config` into a record batch with the [`sweep_schema`].
+///
+/// Records that aren't sweeps are skipped. Returns `ArrowError::InvalidArgumentError` if a sweep
+/// doesn't contain `config.sweep_len` points.
+pub fn sweeps_to_record_batch<'a>(
+    config: &Config,
+    records: impl IntoIterator<Item = &'a Record>,
+) -> Result<RecordBatch, ArrowError> {
+    let mut batch = SweepBatch::new(config.sweep_len);
+    for record in records {
+        batch.push(record)?;
+    }
+    batch.finish()
+}
+
+/// Sweeps collected column by column so they can be moved into Arrow arrays without copying.
+#[derive(Debug)]
+struct SweepBatch {
+    sweep_len: u16,
+    timestamps: Vec<i64>,
+    start_hz: Vec<u64>,
+    stop_hz: Vec<u64>,
+    amplitudes_dbm: Vec<f32>,
+}
+
+impl SweepBatch {
+    fn new(sweep_len: u16) -> Self {
+        Self {
+            sweep_len,
+            timestamps: Vec::new(),
+            start_hz: Vec::new(),
+            stop_hz: Vec::new(),
+            amplitudes_dbm: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    fn push(&mut self, record: &Record) -> Result<(), ArrowError> {
+        let Record::Sweep {
+            timestamp,
+            start_freq,
+            stop_freq,
+            amplitudes_dbm,
+        } = record
+        else {
+            return Ok(());
+        };
+
+        if amplitudes_dbm.len() != usize::from(self.sweep_len) {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Expected a sweep with {} points but it has {}",
+                self.sweep_len,
+                amplitudes_dbm.len()
+            )));
+        }
+
+        self.timestamps.push(timestamp.timestamp_micros());
+        self.start_hz.push(start_freq.as_hz());
+        self.stop_hz.push(stop_freq.as_hz());
+        self.amplitudes_dbm.extend_from_slice(amplitudes_dbm);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<RecordBatch, ArrowError> {
+        // Each column's Vec becomes the array's buffer, so the values aren't copied again
+        let amplitudes_dbm = FixedSizeListArray::try_new(
+            amplitude_field(),
+            i32::from(self.sweep_len),
+            Arc::new(Float32Array::new(
+                ScalarBuffer::from(self.amplitudes_dbm),
+                None,
+            )),
+            None,
+        )?;
+        RecordBatch::try_new(
+            sweep_schema(self.sweep_len),
+            vec![
+                Arc::new(
+                    TimestampMicrosecondArray::new(ScalarBuffer::from(self.timestamps), None)
+                        .with_timezone("UTC"),
+                ),
+                Arc::new(UInt64Array::new(ScalarBuffer::from(self.start_hz), None)),
+                Arc::new(UInt64Array::new(ScalarBuffer::from(self.stop_hz), None)),
+                Arc::new(amplitudes_dbm),
+            ],
+        )
+    }
+}
+
+/// A [`RecordSink`] that writes sweeps to an Arrow IPC stream.
+///
+/// Sweeps are written in batches with the [`sweep_schema`], so the stream can be read by
+/// `pyarrow.ipc.open_stream` or any other Arrow implementation. Configs and screen data aren't
+/// written.
+///
+/// The stream's schema is fixed by the first sweep's length. Returns an `InvalidData` error if a
+/// later sweep has a different length, so start a new sink after changing the sweep length.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rfe::{SpectrumAnalyzer, spectrum_analyzer::{RecordingSession, arrow::ArrowIpcSink}};
+///
+/// let rfe = SpectrumAnalyzer::connect().expect("RF Explorer should be connected");
+/// let session = RecordingSession::start(&rfe, ArrowIpcSink::new(std::io::stdout()))?;
+/// std::thread::sleep(std::time::Duration::from_secs(10));
+/// session.stop()?;
+/// # Ok::<(), rfe::Error>(())
+/// ```
+pub struct ArrowIpcSink<W: Write> {
+    state: SinkState<W>,
+    last_config_sweep_len: Option<u16>,
+}
+
+enum SinkState<W: Write> {
+    // The schema isn't known until the first sweep is received
+    NotStarted(W),
+    Started {
+        stream: StreamWriter<W>,
+        batch: SweepBatch,
+    },
+    Finished(StreamWriter<W>),
+    // Only seen if starting or finishing the stream failed
+    Failed,
+}
+
+impl<W: Write> ArrowIpcSink<W> {
+    // The number of sweeps buffered before a batch is written to the stream
+    const SWEEPS_PER_BATCH: usize = 16;
+
+    /// Creates a sink that writes an Arrow IPC stream to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            state: SinkState::NotStarted(writer),
+            last_config_sweep_len: None,
+        }
+    }
+
+    /// Finishes the stream and returns the writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.finish_stream()?;
+        match self.state {
+            SinkState::Finished(stream) => stream.into_inner().map_err(io::Error::other),
+            _ => Err(io::Error::other(
+                "The Arrow IPC stream couldn't be finished",
+            )),
+        }
+    }
+
+    fn start_stream(&mut self, sweep_len: u16) -> io::Result<()> {
+        if !matches!(self.state, SinkState::NotStarted(_)) {
+            return Ok(());
+        }
+        let SinkState::NotStarted(writer) = std::mem::replace(&mut self.state, SinkState::Failed)
+        else {
+            unreachable!();
+        };
+        let stream =
+            StreamWriter::try_new(writer, &sweep_schema(sweep_len)).map_err(io::Error::other)?;
+        self.state = SinkState::Started {
+            stream,
+            batch: SweepBatch::new(sweep_len),
+        };
+        Ok(())
+    }
+
+    fn write_batch(&mut self) -> io::Result<()> {
+        let SinkState::Started { stream, batch } = &mut self.state else {
+            return Ok(());
+        };
+        if batch.len() == 0 {
+            return Ok(());
+        }
+
+        let full_batch = std::mem::replace(batch, SweepBatch::new(batch.sweep_len));
+        stream
+            .write(&full_batch.finish().map_err(io::Error::other)?)
+            .map_err(io::Error::other)?;
+        // Flush every batch so readers on the other end of a pipe see sweeps as they arrive
+        stream.flush().map_err(io::Error::other)
+    }
+
+    fn finish_stream(&mut self) -> io::Result<()> {
+        // A stream without sweeps still needs a schema so readers can open it
+        if matches!(self.state, SinkState::NotStarted(_)) {
+            self.start_stream(self.last_config_sweep_len.unwrap_or_default())?;
+        }
+        self.write_batch()?;
+        match std::mem::replace(&mut self.state, SinkState::Failed) {
+            SinkState::Started { mut stream, .. } => {
+                stream.finish().map_err(io::Error::other)?;
+                stream.flush().map_err(io::Error::other)?;
+                self.state = SinkState::Finished(stream);
+            }
+            state => self.state = state,
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Send + 'static> RecordSink for ArrowIpcSink<W> {
+    fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        let sweep_len = match record {
+            Record::Config { config, .. } => {
+                self.last_config_sweep_len = Some(config.sweep_len);
+                return Ok(());
+            }
+            Record::Sweep { amplitudes_dbm, .. } => amplitudes_dbm.len(),
+            Record::ScreenData(_) | Record::Event(_) | Record::LinkHealth { .. } => return Ok(()),
+        };
+
+        let sweep_len = u16::try_from(sweep_len)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "The sweep is too long"))?;
+        self.start_stream(sweep_len)?;
+        let SinkState::Started { batch, .. } = &mut self.state else {
+            return Err(io::Error::other("The Arrow IPC stream is already finished"));
+        };
+        batch
+            .push(record)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        if batch.len() >= Self::SWEEPS_PER_BATCH {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.finish_stream()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{
+        Array,
+        cast::AsArray,
+        types::{Float32Type, TimestampMicrosecondType, UInt64Type},
+    };
+    use arrow_ipc::reader::StreamReader;
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::Frequency;
+
+    fn sweep(timestamp_secs: i64, amplitudes_dbm: Vec<f32>) -> Record {
+        Record::Sweep {
+            timestamp: Utc.timestamp_opt(timestamp_secs, 0).unwrap(),
+            start_freq: Frequency::from_mhz(2_400),
+            stop_freq: Frequency::from_mhz(2_500),
+            amplitudes_dbm,
+        }
+    }
+
+    #[test]
+    fn read_back_ipc_stream() {
+        let mut sink = ArrowIpcSink::new(Vec::new());
+        let sweeps: Vec<Record> = (0..20)
+            .map(|i| sweep(1_700_000_000 + i, vec![-100.0 + i as f32; 112]))
+            .collect();
+        for sweep in &sweeps {
+            sink.write_record(sweep).unwrap();
+        }
+        let bytes = sink.into_inner().unwrap();
+
+        let reader = StreamReader::try_new(bytes.as_slice(), None).unwrap();
+        assert_eq!(reader.schema(), sweep_schema(112));
+        let batches: Vec<RecordBatch> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 20);
+
+        let last_batch = &batches[1];
+        let timestamps = last_batch
+            .column(0)
+            .as_primitive::<TimestampMicrosecondType>();
+        assert_eq!(timestamps.value(3), 1_700_000_019_000_000);
+        let start_hz = last_batch.column(1).as_primitive::<UInt64Type>();
+        assert_eq!(start_hz.value(3), 2_400_000_000);
+        let amplitudes = last_batch.column(3).as_fixed_size_list();
+        assert_eq!(amplitudes.value_length(), 112);
+        let last_sweep = amplitudes.value(3);
+        assert_eq!(last_sweep.len(), 112);
+        assert_eq!(last_sweep.as_primitive::<Float32Type>().value(0), -81.0);
+    }
+
+    #[test]
+    fn reject_sweeps_with_different_lengths() {
+        let mut sink = ArrowIpcSink::new(Vec::new());
+        sink.write_record(&sweep(0, vec![-100.0; 112])).unwrap();
+        let error = sink.write_record(&sweep(1, vec![-100.0; 224])).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,229 @@
+use super::InputStage;
+
+/// How many sweeps after a switch are flagged as settling, since they may have been measured
+/// before the RF Explorer switched input stages.
+const SETTLING_SWEEPS: usize = 2;
+
+/// The settings of the controller that switches to an attenuating input stage when strong signals
+/// would overload the spectrum analyzer's front end, and back again once they're gone.
+///
+/// Peaks are compared as the RF Explorer reports them. The correction needed to get absolute
+/// amplitudes is unknown while an input stage other than the direct one is active (see
+/// [`SweepMeta::correction_db`](super::SweepMeta::correction_db)), so the thresholds apply to the
+/// reported amplitudes of whichever input stage is active.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AutoInputStage {
+    /// The peak amplitude above which the front end is overloaded.
+    pub overload_dbm: f32,
+    /// The peak amplitude below which the attenuation is no longer needed.
+    ///
+    /// It must be lower than [`AutoInputStage::overload_dbm`] so that a signal near the threshold
+    /// doesn't switch the input stage back and forth.
+    pub recovery_dbm: f32,
+    /// How many consecutive sweeps must cross a threshold before the input stage is switched.
+    /// Values less than 1 are treated as 1.
+    pub consecutive_sweeps: usize,
+    /// The input stage that's switched to when the front end is overloaded.
+    pub attenuated_stage: InputStage,
+    /// The input stage that's switched back to once the peaks are below
+    /// [`AutoInputStage::recovery_dbm`].
+    pub normal_stage: InputStage,
+}
+
+impl Default for AutoInputStage {
+    fn default() -> Self {
+        AutoInputStage {
+            overload_dbm: -25.,
+            recovery_dbm: -40.,
+            consecutive_sweeps: 3,
+            attenuated_stage: InputStage::Attenuator30dB,
+            normal_stage: InputStage::Direct,
+        }
+    }
+}
+
+/// A switch of input stages made by the automatic input stage controller.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InputStageTransition {
+    /// The input stage that was active, if the RF Explorer reported it.
+    pub from: Option<InputStage>,
+    /// The input stage that was switched to.
+    pub to: InputStage,
+    /// The corrected peak amplitude of the sweep that caused the switch.
+    pub peak_dbm: f32,
+}
+
+/// What the controller decided after a sweep.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum InputStageDecision {
+    Keep,
+    /// The sweep was received right after a switch, so it isn't used to make decisions.
+    Settling,
+    Switch(InputStageTransition),
+}
+
+/// Counts consecutive sweeps above and below the thresholds and decides when to switch input
+/// stages.
+#[derive(Debug, Default)]
+pub(crate) struct AutoInputStageController {
+    settings: Option<AutoInputStage>,
+    sweeps_over: usize,
+    sweeps_under: usize,
+    settling_sweeps: usize,
+}
+
+impl AutoInputStageController {
+    pub(crate) fn settings(&self) -> Option<AutoInputStage> {
+        self.settings
+    }
+
+    pub(crate) fn set_settings(&mut self, settings: Option<AutoInputStage>) {
+        *self = AutoInputStageController {
+            settings,
+            ..AutoInputStageController::default()
+        };
+    }
+
+    /// Decides whether to switch input stages after a sweep whose corrected peak is `peak_dbm`
+    /// was measured with `input_stage`.
+    pub(crate) fn on_sweep(
+        &mut self,
+        peak_dbm: Option<f32>,
+        input_stage: Option<InputStage>,
+    ) -> InputStageDecision {
+        let Some(settings) = self.settings else {
+            return InputStageDecision::Keep;
+        };
+        if self.settling_sweeps > 0 {
+            self.settling_sweeps -= 1;
+            return InputStageDecision::Settling;
+        }
+        let Some(peak_dbm) = peak_dbm else {
+            return InputStageDecision::Keep;
+        };
+
+        let (count, other_count, crossed, to) = if input_stage == Some(settings.attenuated_stage) {
+            (
+                &mut self.sweeps_under,
+                &mut self.sweeps_over,
+                peak_dbm < settings.recovery_dbm,
+                settings.normal_stage,
+            )
+        } else {
+            (
+                &mut self.sweeps_over,
+                &mut self.sweeps_under,
+                peak_dbm > settings.overload_dbm,
+                settings.attenuated_stage,
+            )
+        };
+        *other_count = 0;
+        *count = if crossed { *count + 1 } else { 0 };
+        if *count < settings.consecutive_sweeps.max(1) {
+            return InputStageDecision::Keep;
+        }
+
+        self.sweeps_over = 0;
+        self.sweeps_under = 0;
+        self.settling_sweeps = SETTLING_SWEEPS;
+        InputStageDecision::Switch(InputStageTransition {
+            from: input_stage,
+            to,
+            peak_dbm,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller() -> AutoInputStageController {
+        let mut controller = AutoInputStageController::default();
+        controller.set_settings(Some(AutoInputStage::default()));
+        controller
+    }
+
+    fn is_switch_to(decision: InputStageDecision, input_stage: InputStage) -> bool {
+        matches!(decision, InputStageDecision::Switch(transition) if transition.to == input_stage)
+    }
+
+    #[test]
+    fn attenuate_after_consecutive_overloaded_sweeps() {
+        let mut controller = controller();
+        let direct = Some(InputStage::Direct);
+        assert_eq!(
+            controller.on_sweep(Some(-20.), direct),
+            InputStageDecision::Keep
+        );
+        assert_eq!(
+            controller.on_sweep(Some(-20.), direct),
+            InputStageDecision::Keep
+        );
+        // A sweep below the threshold restarts the count
+        assert_eq!(
+            controller.on_sweep(Some(-30.), direct),
+            InputStageDecision::Keep
+        );
+        assert_eq!(
+            controller.on_sweep(Some(-20.), direct),
+            InputStageDecision::Keep
+        );
+        assert_eq!(
+            controller.on_sweep(Some(-20.), direct),
+            InputStageDecision::Keep
+        );
+        assert!(is_switch_to(
+            controller.on_sweep(Some(-20.), direct),
+            InputStage::Attenuator30dB
+        ));
+    }
+
+    #[test]
+    fn switch_back_below_recovery_threshold() {
+        let mut controller = controller();
+        let attenuated = Some(InputStage::Attenuator30dB);
+        // Peaks between the thresholds keep the attenuation
+        for _ in 0..10 {
+            assert_eq!(
+                controller.on_sweep(Some(-30.), attenuated),
+                InputStageDecision::Keep
+            );
+        }
+        controller.on_sweep(Some(-50.), attenuated);
+        controller.on_sweep(Some(-50.), attenuated);
+        assert!(is_switch_to(
+            controller.on_sweep(Some(-50.), attenuated),
+            InputStage::Direct
+        ));
+    }
+
+    #[test]
+    fn skip_settling_sweeps_after_switch() {
+        let mut controller = controller();
+        for _ in 0..3 {
+            controller.on_sweep(Some(0.), Some(InputStage::Direct));
+        }
+        for _ in 0..SETTLING_SWEEPS {
+            assert_eq!(
+                controller.on_sweep(Some(0.), Some(InputStage::Direct)),
+                InputStageDecision::Settling
+            );
+        }
+        assert_eq!(
+            controller.on_sweep(Some(0.), Some(InputStage::Attenuator30dB)),
+            InputStageDecision::Keep
+        );
+    }
+
+    #[test]
+    fn keep_input_stage_when_off() {
+        let mut controller = AutoInputStageController::default();
+        for _ in 0..10 {
+            assert_eq!(
+                controller.on_sweep(Some(0.), Some(InputStage::Direct)),
+                InputStageDecision::Keep
+            );
+        }
+    }
+}
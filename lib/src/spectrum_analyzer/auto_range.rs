@@ -0,0 +1,160 @@
+use std::{collections::VecDeque, ops::RangeInclusive};
+
+/// The fraction of amplitudes that are below the estimated noise floor.
+const NOISE_FLOOR_PERCENTILE: f32 = 0.1;
+/// How far below the noise floor the suggested minimum amplitude is.
+const NOISE_FLOOR_MARGIN_DB: f32 = 10.;
+/// How far above the strongest amplitude the suggested maximum amplitude is.
+const HEADROOM_DB: f32 = 10.;
+/// Suggested amplitudes are rounded to multiples of this step.
+const ROUNDING_DB: i16 = 5;
+
+/// The most recent sweeps received since the config last changed.
+///
+/// The oldest sweep's buffer is reused for the newest one once the history is full, so no
+/// allocations are made for each sweep.
+#[derive(Debug, Default)]
+pub(crate) struct SweepHistory {
+    sweeps: VecDeque<Vec<f32>>,
+}
+
+impl SweepHistory {
+    pub(crate) const CAPACITY: usize = 5;
+
+    pub(crate) fn push(&mut self, amplitudes_dbm: &[f32]) {
+        let mut buf = if self.sweeps.len() == Self::CAPACITY {
+            self.sweeps.pop_front().unwrap_or_default()
+        } else {
+            Vec::with_capacity(amplitudes_dbm.len())
+        };
+        buf.clear();
+        buf.extend_from_slice(amplitudes_dbm);
+        self.sweeps.push_back(buf);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.sweeps.clear();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.sweeps.len()
+    }
+
+    pub(crate) fn sweeps(&mut self) -> &[Vec<f32>] {
+        self.sweeps.make_contiguous()
+    }
+
+    /// Returns an estimate of the memory used by the sweeps, in bytes.
+    pub(crate) fn memory_usage_estimate(&self) -> usize {
+        self.sweeps
+            .iter()
+            .map(|sweep| sweep.capacity() * size_of::<f32>())
+            .sum()
+    }
+}
+
+/// Suggests minimum and maximum amplitudes that keep the noise floor and the strongest signals of
+/// the sweeps visible.
+///
+/// The minimum is below the noise floor, which is estimated as a low percentile of the
+/// amplitudes, and the maximum is above the strongest amplitude. Both are rounded outward to a
+/// multiple of 5 dB and kept within `amp_range`. Returns `None` if there are no amplitudes.
+pub(crate) fn suggest_amp_range(
+    sweeps: &[Vec<f32>],
+    amp_range: RangeInclusive<i16>,
+) -> Option<(i16, i16)> {
+    let mut amplitudes_dbm: Vec<f32> = sweeps
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|amp_dbm| amp_dbm.is_finite())
+        .collect();
+    if amplitudes_dbm.is_empty() {
+        return None;
+    }
+    amplitudes_dbm.sort_by(f32::total_cmp);
+
+    let noise_floor_dbm =
+        amplitudes_dbm[((amplitudes_dbm.len() - 1) as f32 * NOISE_FLOOR_PERCENTILE) as usize];
+    let peak_dbm = amplitudes_dbm[amplitudes_dbm.len() - 1];
+    let step_db = f32::from(ROUNDING_DB);
+    let min_amp_dbm =
+        (((noise_floor_dbm - NOISE_FLOOR_MARGIN_DB) / step_db).floor() * step_db) as i16;
+    let max_amp_dbm = (((peak_dbm + HEADROOM_DB) / step_db).ceil() * step_db) as i16;
+    let min_amp_dbm = min_amp_dbm.clamp(*amp_range.start(), *amp_range.end());
+    let max_amp_dbm = max_amp_dbm.clamp(*amp_range.start(), *amp_range.end());
+
+    // Keep at least one step between the amplitudes when they were both clamped to the same edge
+    if min_amp_dbm < max_amp_dbm {
+        Some((min_amp_dbm, max_amp_dbm))
+    } else if max_amp_dbm == *amp_range.end() {
+        Some((max_amp_dbm - ROUNDING_DB, max_amp_dbm))
+    } else {
+        Some((min_amp_dbm, min_amp_dbm + ROUNDING_DB))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMP_RANGE_DBM: RangeInclusive<i16> = -120..=35;
+
+    #[test]
+    fn suggest_range_around_noise_floor_and_peak() {
+        // A -100 dBm noise floor with a -42 dBm signal in one point of each sweep
+        let sweeps: Vec<Vec<f32>> = (0..5)
+            .map(|i| {
+                let mut sweep = vec![-100. + i as f32 * 0.5; 112];
+                sweep[50] = -42.;
+                sweep
+            })
+            .collect();
+        assert_eq!(suggest_amp_range(&sweeps, AMP_RANGE_DBM), Some((-110, -30)));
+    }
+
+    #[test]
+    fn ignore_outliers_below_the_noise_floor() {
+        let mut sweep = vec![-90.; 100];
+        sweep[0] = -127.;
+        assert_eq!(
+            suggest_amp_range(&[sweep], AMP_RANGE_DBM),
+            Some((-100, -80))
+        );
+    }
+
+    #[test]
+    fn keep_suggestions_within_amp_range() {
+        assert_eq!(
+            suggest_amp_range(&[vec![-125., 30.]], AMP_RANGE_DBM),
+            Some((-120, 35))
+        );
+        assert_eq!(
+            suggest_amp_range(&[vec![40.; 10]], AMP_RANGE_DBM),
+            Some((30, 35))
+        );
+        // An amplitude offset shifts the range
+        assert_eq!(
+            suggest_amp_range(&[vec![-122.; 10]], -140..=15),
+            Some((-135, -110))
+        );
+    }
+
+    #[test]
+    fn need_amplitudes_to_suggest() {
+        assert_eq!(suggest_amp_range(&[], AMP_RANGE_DBM), None);
+        assert_eq!(suggest_amp_range(&[vec![f32::NAN]], AMP_RANGE_DBM), None);
+    }
+
+    #[test]
+    fn reuse_oldest_buffer_when_full() {
+        let mut history = SweepHistory::default();
+        for i in 0..SweepHistory::CAPACITY + 2 {
+            history.push(&[i as f32]);
+        }
+        assert_eq!(history.len(), SweepHistory::CAPACITY);
+        assert_eq!(history.sweeps()[0], [2.]);
+        history.clear();
+        assert_eq!(history.len(), 0);
+    }
+}
@@ -0,0 +1,108 @@
+use tracing::warn;
+
+use super::{Config, Message, Model};
+use crate::{
+    WriteMessage,
+    rf_explorer::{SerialNumber, SetupInfo},
+};
+
+/// What an RF Explorer reported about itself, saved so that it can be reconnected to without
+/// waiting for it to identify itself again.
+///
+/// Get one from [`SpectrumAnalyzer::cached_identity`](super::SpectrumAnalyzer::cached_identity)
+/// and pass it to
+/// [`SpectrumAnalyzer::connect_with_cached_identity`](super::SpectrumAnalyzer::connect_with_cached_identity).
+/// With the `serde` feature, it can be serialized so it outlives the process.
+///
+/// The messages are kept in the format the RF Explorer sends them in, so an identity saved by one
+/// version of this crate can be read by another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CachedIdentity {
+    setup_info: String,
+    serial_number: Option<String>,
+    config: String,
+}
+
+impl CachedIdentity {
+    pub(crate) fn new(
+        setup_info: &SetupInfo<Model>,
+        serial_number: Option<&SerialNumber>,
+        config: &Config,
+    ) -> Self {
+        CachedIdentity {
+            setup_info: message_line(setup_info),
+            serial_number: serial_number.map(message_line),
+            config: message_line(config),
+        }
+    }
+
+    /// Returns whether the identity includes the RF Explorer's serial number.
+    pub fn has_serial_number(&self) -> bool {
+        self.serial_number.is_some()
+    }
+
+    /// Parses the saved messages in the order the RF Explorer would send them.
+    ///
+    /// Messages that can't be parsed, like ones edited by hand, are skipped, so the connection
+    /// waits for the RF Explorer to send them instead.
+    pub(crate) fn messages(&self) -> Vec<Message> {
+        [
+            Some(&self.setup_info),
+            self.serial_number.as_ref(),
+            Some(&self.config),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|line| {
+            let bytes = format!("{line}\r\n");
+            let message = Message::try_from(bytes.as_bytes()).ok();
+            if message.is_none() {
+                warn!(line, "Skipped a cached message that couldn't be parsed");
+            }
+            message
+        })
+        .collect()
+    }
+}
+
+/// Returns the message's line without its line ending.
+fn message_line(message: &impl WriteMessage) -> String {
+    String::from_utf8_lossy(&message.to_bytes())
+        .trim_end_matches(['\r', '\n'])
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETUP_INFO: &[u8] = b"#C2-M:005,006,01.12B26\r\n";
+    const SERIAL_NUMBER: &[u8] = b"#Sn0SME38SI2X7NGR48\r\n";
+    const CONFIG: &[u8] =
+        b"#C2-F:0430000,0178571,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\r\n";
+
+    fn identity() -> CachedIdentity {
+        CachedIdentity::new(
+            &SetupInfo::try_from(SETUP_INFO).unwrap(),
+            Some(&SerialNumber::try_from(SERIAL_NUMBER).unwrap()),
+            &Config::try_from(CONFIG).unwrap(),
+        )
+    }
+
+    #[test]
+    fn parse_saved_messages_in_order() {
+        let messages = identity().messages();
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(messages[0], Message::SetupInfo(_)));
+        assert!(matches!(messages[1], Message::SerialNumber(_)));
+        assert!(matches!(&messages[2], Message::Config(config) if config.sweep_len == 112));
+    }
+
+    #[test]
+    fn skip_unparsable_messages() {
+        let mut identity = identity();
+        identity.config = "#C2-F:garbage".to_string();
+        assert_eq!(identity.messages().len(), 2);
+    }
+}
@@ -0,0 +1,129 @@
+use std::ops::RangeInclusive;
+
+use super::{Model, WifiBand};
+use crate::Frequency;
+
+/// Features supported by a connected spectrum analyzer's active radio module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The model of the active radio module.
+    pub active_radio_model: Model,
+    /// Whether the RF Explorer has an expansion radio module.
+    pub has_expansion: bool,
+    /// Whether the number of points in each sweep can be changed.
+    pub can_set_sweep_len: bool,
+    /// The minimum number of points in a sweep.
+    pub min_sweep_len: u16,
+    /// The maximum number of points in a sweep.
+    pub max_sweep_len: u16,
+    /// The Wi-Fi bands the active radio module can analyze.
+    pub supported_wifi_bands: Vec<WifiBand>,
+    /// Whether the input stage (attenuator or LNA) can be changed.
+    pub supports_input_stage: bool,
+    /// Whether the DSP mode can be changed.
+    pub supports_dsp_mode: bool,
+    /// The active radio module's supported input frequency range.
+    pub freq_range: RangeInclusive<Frequency>,
+    /// The active radio module's supported sweep span range.
+    pub span_range: RangeInclusive<Frequency>,
+}
+
+impl Capabilities {
+    pub(crate) const MIN_SWEEP_LEN: u16 = 112;
+
+    /// The most points any RF Explorer can put in a sweep.
+    ///
+    /// Configs with longer sweeps are treated as corrupt, even before the model is known.
+    // The largest sweep contains 65,535 points, which gets rounded down to a multiple of 16
+    pub const MAX_SWEEP_LEN: u16 = 65_520;
+
+    pub(crate) fn new(active_radio_model: Model, expansion_radio_model: Option<Model>) -> Self {
+        let is_plus_model = active_radio_model.is_plus_model();
+        let supported_wifi_bands = [WifiBand::TwoPointFourGhz, WifiBand::FiveGhz]
+            .into_iter()
+            .filter(|band| {
+                let band_freq_range = band.freq_range();
+                active_radio_model.has_wifi_analyzer()
+                    && active_radio_model
+                        .freq_range()
+                        .contains(band_freq_range.start())
+                    && active_radio_model
+                        .freq_range()
+                        .contains(band_freq_range.end())
+            })
+            .collect();
+
+        Self {
+            active_radio_model,
+            has_expansion: expansion_radio_model.is_some(),
+            can_set_sweep_len: is_plus_model,
+            min_sweep_len: Self::MIN_SWEEP_LEN,
+            max_sweep_len: Self::max_sweep_len(active_radio_model),
+            supported_wifi_bands,
+            supports_input_stage: is_plus_model,
+            supports_dsp_mode: is_plus_model,
+            freq_range: active_radio_model.freq_range(),
+            span_range: active_radio_model.min_span()..=active_radio_model.max_span(),
+        }
+    }
+
+    /// Returns the most points the model can put in a sweep.
+    pub(crate) fn max_sweep_len(model: Model) -> u16 {
+        if model.is_plus_model() {
+            Self::MAX_SWEEP_LEN
+        } else {
+            Self::MIN_SWEEP_LEN
+        }
+    }
+
+    /// Returns whether the active radio module can analyze the Wi-Fi band.
+    pub fn supports_wifi_band(&self, wifi_band: WifiBand) -> bool {
+        self.supported_wifi_bands.contains(&wifi_band)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wsub3g_capabilities() {
+        let capabilities = Capabilities::new(Model::RfeWSub3G, Some(Model::Rfe6G));
+        assert!(capabilities.has_expansion);
+        assert!(!capabilities.can_set_sweep_len);
+        assert_eq!(capabilities.max_sweep_len, 112);
+        assert_eq!(
+            capabilities.supported_wifi_bands,
+            [WifiBand::TwoPointFourGhz]
+        );
+        assert!(!capabilities.supports_input_stage);
+    }
+
+    #[test]
+    fn six_g_plus_capabilities() {
+        let capabilities = Capabilities::new(Model::Rfe6GPlus, None);
+        assert!(!capabilities.has_expansion);
+        assert!(capabilities.can_set_sweep_len);
+        assert_eq!(capabilities.max_sweep_len, 65_520);
+        assert_eq!(
+            capabilities.supported_wifi_bands,
+            [WifiBand::TwoPointFourGhz, WifiBand::FiveGhz]
+        );
+        assert!(capabilities.supports_input_stage);
+        assert!(capabilities.supports_dsp_mode);
+    }
+
+    #[test]
+    fn six_g_capabilities() {
+        let capabilities = Capabilities::new(Model::Rfe6G, Some(Model::RfeWSub3G));
+        assert!(capabilities.supports_wifi_band(WifiBand::FiveGhz));
+        assert!(!capabilities.supports_wifi_band(WifiBand::TwoPointFourGhz));
+    }
+
+    #[test]
+    fn wsub1g_plus_capabilities() {
+        let capabilities = Capabilities::new(Model::RfeWSub1GPlus, None);
+        assert!(capabilities.can_set_sweep_len);
+        assert!(capabilities.supported_wifi_bands.is_empty());
+    }
+}
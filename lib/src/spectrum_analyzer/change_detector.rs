@@ -0,0 +1,455 @@
+use std::{collections::VecDeque, ops::RangeInclusive};
+
+use crate::{Error, Frequency, Result};
+
+/// Returns the normalized cross-correlation at zero lag of two sweeps' amplitudes in dB, from -1
+/// to 1.
+///
+/// Sweeps with the same shape correlate at 1 even if one is offset by a constant number of dB,
+/// so the correlation picks up signals appearing, disappearing, or moving rather than the noise
+/// floor rising. Points that are `NaN` in either sweep are skipped. If either sweep is flat, the
+/// correlation is 1 if they're identical and 0 otherwise.
+///
+/// Returns [`Error::InvalidInput`] if the sweeps have different lengths or no points to compare.
+pub fn sweep_correlation(a: &[f32], b: &[f32]) -> Result<f32> {
+    let pairs = comparable_pairs(a, b)?;
+    let len = pairs.len() as f64;
+    let mean_a = pairs.iter().map(|(a, _)| a).sum::<f64>() / len;
+    let mean_b = pairs.iter().map(|(_, b)| b).sum::<f64>() / len;
+
+    let (mut covariance, mut variance_a, mut variance_b) = (0., 0., 0.);
+    for (a, b) in &pairs {
+        covariance += (a - mean_a) * (b - mean_b);
+        variance_a += (a - mean_a).powi(2);
+        variance_b += (b - mean_b).powi(2);
+    }
+    let denominator = (variance_a * variance_b).sqrt();
+    if denominator == 0. {
+        let is_identical = pairs.iter().all(|(a, b)| a == b);
+        return Ok(if is_identical { 1. } else { 0. });
+    }
+    Ok((covariance / denominator).clamp(-1., 1.) as f32)
+}
+
+/// Returns the mean absolute difference in dB between two sweeps' amplitudes.
+///
+/// Points that are `NaN` in either sweep are skipped.
+///
+/// Returns [`Error::InvalidInput`] if the sweeps have different lengths or no points to compare.
+pub fn spectral_distance(a: &[f32], b: &[f32]) -> Result<f32> {
+    let pairs = comparable_pairs(a, b)?;
+    let total_db = pairs.iter().map(|(a, b)| (a - b).abs()).sum::<f64>();
+    Ok((total_db / pairs.len() as f64) as f32)
+}
+
+/// Returns the pairs of points that aren't `NaN` in either sweep.
+fn comparable_pairs(a: &[f32], b: &[f32]) -> Result<Vec<(f64, f64)>> {
+    if a.len() != b.len() {
+        return Err(Error::InvalidInput(format!(
+            "Sweeps with {} and {} points can't be compared",
+            a.len(),
+            b.len()
+        )));
+    }
+    let pairs: Vec<_> = a
+        .iter()
+        .zip(b)
+        .filter(|(a, b)| !a.is_nan() && !b.is_nan())
+        .map(|(&a, &b)| (f64::from(a), f64::from(b)))
+        .collect();
+    if pairs.is_empty() {
+        return Err(Error::InvalidInput(
+            "The sweeps have no points to compare".to_string(),
+        ));
+    }
+    Ok(pairs)
+}
+
+/// How a [`ChangeDetector`] compares each sweep with its baseline.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ChangeMetric {
+    /// [`sweep_correlation`], which counts as changed when it falls below the threshold.
+    Correlation,
+    /// [`spectral_distance`] in dB, which counts as changed when it rises above the threshold.
+    SpectralDistance,
+}
+
+/// The settings of a [`ChangeDetector`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ChangeDetectorConfig {
+    /// How each sweep is compared with the baseline.
+    pub metric: ChangeMetric,
+    /// The value of the metric at which a sweep counts as changed.
+    pub threshold: f32,
+    /// How many changed sweeps in a row are reported as a change. Values less than 1 are treated
+    /// as 1.
+    pub consecutive_sweeps: usize,
+    /// How many of the recent unchanged sweeps are averaged into the baseline. Values less than 1
+    /// are treated as 1.
+    pub baseline_sweeps: usize,
+}
+
+impl Default for ChangeDetectorConfig {
+    fn default() -> Self {
+        ChangeDetectorConfig {
+            metric: ChangeMetric::SpectralDistance,
+            threshold: 3.,
+            consecutive_sweeps: 3,
+            baseline_sweeps: 20,
+        }
+    }
+}
+
+/// A change reported by a [`ChangeDetector`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeReport {
+    /// The metric's value for the sweep that completed the run of changed sweeps.
+    pub metric_value: f32,
+    /// The indexes of the points around the most changed point that changed by at least half as
+    /// much as it did.
+    pub region: RangeInclusive<usize>,
+    /// How much the most changed point differed from the baseline in dB.
+    pub max_change_db: f32,
+}
+
+impl ChangeReport {
+    /// Returns the frequencies of the region's points in sweeps that start at `start_freq` and
+    /// whose points are `step_size` apart.
+    pub fn freq_range(
+        &self,
+        start_freq: Frequency,
+        step_size: Frequency,
+    ) -> RangeInclusive<Frequency> {
+        let freq = |i: usize| start_freq + step_size * i as u64;
+        freq(*self.region.start())..=freq(*self.region.end())
+    }
+}
+
+/// Detects slow drift and intermittent interferers by comparing each sweep with a rolling
+/// baseline of the recent sweeps.
+///
+/// A change is reported once [`consecutive_sweeps`](ChangeDetectorConfig::consecutive_sweeps)
+/// sweeps in a row cross the threshold, so a single noisy sweep isn't reported. Changed sweeps
+/// aren't added to the baseline, so an interferer can't hide by becoming part of it. Once a
+/// change is reported the baseline starts over from the changed sweeps, so a lasting change is
+/// only reported once. If it ends after the baseline has filled up again, its end is reported as
+/// another change.
+///
+/// A drift is detected when the sweeps move away from the average of the last
+/// [`baseline_sweeps`](ChangeDetectorConfig::baseline_sweeps) faster than the threshold allows,
+/// so a longer baseline detects slower drifts. Nothing is reported until the baseline has filled
+/// up, and it starts over whenever the sweep length changes. Call [`ChangeDetector::reset`] after
+/// other config changes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rfe::SpectrumAnalyzer;
+/// use rfe::spectrum_analyzer::{ChangeDetector, ChangeDetectorConfig};
+///
+/// let rfe = SpectrumAnalyzer::connect().expect("RF Explorer should be connected");
+/// let mut detector = ChangeDetector::new(ChangeDetectorConfig::default());
+/// loop {
+///     let sweep = rfe.wait_for_next_sweep()?;
+///     if let Some(change) = detector.add_sweep(&sweep)? {
+///         let freqs = change.freq_range(rfe.start_freq(), rfe.step_size());
+///         println!("{:?} changed by {} dB", freqs, change.max_change_db);
+///     }
+/// }
+/// # Ok::<(), rfe::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChangeDetector {
+    config: ChangeDetectorConfig,
+    baseline: VecDeque<Vec<f32>>,
+    /// The sum of each point over the sweeps in the baseline.
+    sums: Vec<f64>,
+    /// The number of changed sweeps since the last unchanged one.
+    consecutive_changed: usize,
+}
+
+impl ChangeDetector {
+    /// Creates a detector with an empty baseline.
+    pub fn new(config: ChangeDetectorConfig) -> Self {
+        ChangeDetector {
+            config,
+            baseline: VecDeque::new(),
+            sums: Vec::new(),
+            consecutive_changed: 0,
+        }
+    }
+
+    /// Returns the detector's settings.
+    pub fn config(&self) -> ChangeDetectorConfig {
+        self.config
+    }
+
+    /// Forgets the baseline, like after the config changes.
+    pub fn reset(&mut self) {
+        self.baseline.clear();
+        self.sums.clear();
+        self.consecutive_changed = 0;
+    }
+
+    /// Compares the sweep with the baseline and returns a report if it completes a run of
+    /// changed sweeps.
+    ///
+    /// Returns [`Error::InvalidInput`] if the sweep has no points that aren't `NaN`.
+    pub fn add_sweep(&mut self, amplitudes_dbm: &[f32]) -> Result<Option<ChangeReport>> {
+        let baseline_sweeps = self.config.baseline_sweeps.max(1);
+        if self.sums.len() != amplitudes_dbm.len() {
+            self.reset();
+        }
+        if self.baseline.len() < baseline_sweeps {
+            self.push(amplitudes_dbm, baseline_sweeps);
+            return Ok(None);
+        }
+
+        let baseline = self.baseline_dbm();
+        let metric_value = match self.config.metric {
+            ChangeMetric::Correlation => sweep_correlation(amplitudes_dbm, &baseline)?,
+            ChangeMetric::SpectralDistance => spectral_distance(amplitudes_dbm, &baseline)?,
+        };
+        let is_changed = match self.config.metric {
+            ChangeMetric::Correlation => metric_value < self.config.threshold,
+            ChangeMetric::SpectralDistance => metric_value > self.config.threshold,
+        };
+        if !is_changed {
+            self.consecutive_changed = 0;
+            self.push(amplitudes_dbm, baseline_sweeps);
+            return Ok(None);
+        }
+
+        self.consecutive_changed += 1;
+        if self.consecutive_changed < self.config.consecutive_sweeps.max(1) {
+            return Ok(None);
+        }
+
+        let (region, max_change_db) = most_changed_region(amplitudes_dbm, &baseline);
+        // The sweeps have changed for real, so start over from the new normal
+        self.reset();
+        self.push(amplitudes_dbm, baseline_sweeps);
+        Ok(Some(ChangeReport {
+            metric_value,
+            region,
+            max_change_db,
+        }))
+    }
+
+    fn baseline_dbm(&self) -> Vec<f32> {
+        let len = self.baseline.len() as f64;
+        self.sums.iter().map(|sum| (sum / len) as f32).collect()
+    }
+
+    fn push(&mut self, amplitudes_dbm: &[f32], baseline_sweeps: usize) {
+        self.sums.resize(amplitudes_dbm.len(), 0.);
+        while self.baseline.len() >= baseline_sweeps {
+            let Some(oldest) = self.baseline.pop_front() else {
+                break;
+            };
+            for (sum, amp_dbm) in self.sums.iter_mut().zip(oldest) {
+                *sum -= f64::from(amp_dbm);
+            }
+        }
+        for (sum, amp_dbm) in self.sums.iter_mut().zip(amplitudes_dbm) {
+            *sum += f64::from(*amp_dbm);
+        }
+        self.baseline.push_back(amplitudes_dbm.to_vec());
+    }
+}
+
+/// Returns the points around the most changed point that changed by at least half as much, and
+/// how much the most changed point changed.
+fn most_changed_region(amplitudes_dbm: &[f32], baseline: &[f32]) -> (RangeInclusive<usize>, f32) {
+    let changes_db: Vec<f32> = amplitudes_dbm
+        .iter()
+        .zip(baseline)
+        .map(|(amp_dbm, baseline_dbm)| (amp_dbm - baseline_dbm).abs())
+        .map(|change_db| if change_db.is_nan() { 0. } else { change_db })
+        .collect();
+    let Some((peak, &max_change_db)) = changes_db
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    else {
+        return (0..=0, 0.);
+    };
+
+    let is_in_region = |change_db: &f32| *change_db >= max_change_db / 2.;
+    let start = changes_db[..peak]
+        .iter()
+        .rposition(|change_db| !is_in_region(change_db))
+        .map_or(0, |i| i + 1);
+    let end = changes_db[peak..]
+        .iter()
+        .position(|change_db| !is_in_region(change_db))
+        .map_or(changes_db.len() - 1, |i| peak + i - 1);
+    (start..=end, max_change_db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWEEP_LEN: usize = 112;
+    const SWEEPS: u32 = 500;
+
+    /// Returns a sweep with noise around -100 dBm and a tone at -40 dBm, like a band with one
+    /// strong transmitter.
+    fn sweep(seed: u32) -> Vec<f32> {
+        let mut state = seed.wrapping_mul(2_654_435_761).wrapping_add(1);
+        (0..SWEEP_LEN)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let noise_db = (state % 60) as f32 / 10. - 3.;
+                let tone_db = if (50..54).contains(&i) { 60. } else { 0. };
+                -100. + noise_db + tone_db
+            })
+            .collect()
+    }
+
+    /// Adds an interferer at -60 dBm to points 20 to 24.
+    fn with_interferer(mut sweep: Vec<f32>) -> Vec<f32> {
+        for amp_dbm in &mut sweep[20..25] {
+            *amp_dbm = -60.;
+        }
+        sweep
+    }
+
+    fn reports(
+        config: ChangeDetectorConfig,
+        sweeps: impl IntoIterator<Item = Vec<f32>>,
+    ) -> Vec<(usize, ChangeReport)> {
+        let mut detector = ChangeDetector::new(config);
+        sweeps
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, sweep)| Some((i, detector.add_sweep(&sweep).unwrap()?)))
+            .collect()
+    }
+
+    fn correlation_config() -> ChangeDetectorConfig {
+        ChangeDetectorConfig {
+            metric: ChangeMetric::Correlation,
+            threshold: 0.9,
+            ..ChangeDetectorConfig::default()
+        }
+    }
+
+    #[test]
+    fn compare_sweeps() {
+        let a = sweep(1);
+        let offset: Vec<f32> = a.iter().map(|amp_dbm| amp_dbm + 5.).collect();
+        assert!((sweep_correlation(&a, &offset).unwrap() - 1.).abs() < 1e-6);
+        assert!((spectral_distance(&a, &offset).unwrap() - 5.).abs() < 1e-4);
+
+        let inverted: Vec<f32> = a.iter().map(|amp_dbm| -amp_dbm).collect();
+        assert!((sweep_correlation(&a, &inverted).unwrap() + 1.).abs() < 1e-6);
+        assert!(sweep_correlation(&a, &with_interferer(a.clone())).unwrap() < 0.9);
+
+        assert_eq!(sweep_correlation(&[-90.; 4], &[-90.; 4]).unwrap(), 1.);
+        assert_eq!(sweep_correlation(&[-90.; 4], &[-80.; 4]).unwrap(), 0.);
+        assert_eq!(
+            spectral_distance(&[-90., f32::NAN, -70.], &[-80., -10., f32::NAN]).unwrap(),
+            10.
+        );
+    }
+
+    #[test]
+    fn refuse_to_compare_mismatched_sweeps() {
+        assert!(matches!(
+            sweep_correlation(&sweep(1), &sweep(2)[..100]),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            spectral_distance(&[f32::NAN], &[-90.]),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(spectral_distance(&[], &[]).is_err());
+    }
+
+    #[test]
+    fn ignore_stable_sweeps() {
+        for config in [ChangeDetectorConfig::default(), correlation_config()] {
+            assert_eq!(reports(config, (0..SWEEPS).map(sweep)), []);
+        }
+    }
+
+    #[test]
+    fn detect_drift() {
+        // The whole sweep drifts up by 0.5 dB each sweep after the baseline fills up
+        let drifting = (0..SWEEPS).map(|i| {
+            let drift_db = i.saturating_sub(50) as f32 * 0.5;
+            sweep(i)
+                .into_iter()
+                .map(|amp_dbm| amp_dbm + drift_db)
+                .collect()
+        });
+        let reports = reports(ChangeDetectorConfig::default(), drifting);
+        assert!(!reports.is_empty());
+        // The baseline lags 5 dB behind once it's full of drifting sweeps
+        let (first, report) = &reports[0];
+        assert!((50..80).contains(first), "Reported at sweep {first}");
+        assert!(report.metric_value > 3.);
+    }
+
+    #[test]
+    fn ignore_bursts_shorter_than_consecutive_sweeps() {
+        // An interferer shows up for two sweeps at a time
+        let bursty = (0..SWEEPS).map(|i| {
+            if i > 30 && i % 10 < 2 {
+                with_interferer(sweep(i))
+            } else {
+                sweep(i)
+            }
+        });
+        assert_eq!(reports(correlation_config(), bursty), []);
+    }
+
+    #[test]
+    fn report_where_intermittent_interferer_appears() {
+        // An interferer shows up for 5 sweeps at a time
+        let bursty = (0..SWEEPS).map(|i| {
+            if i > 30 && i % 50 < 5 {
+                with_interferer(sweep(i))
+            } else {
+                sweep(i)
+            }
+        });
+        let reports = reports(correlation_config(), bursty);
+
+        // Each burst is reported on its third sweep. The baseline is rebuilt before the burst
+        // ends, so the end isn't reported
+        let starts: Vec<usize> = reports.iter().map(|(i, _)| *i).collect();
+        assert_eq!(starts, [52, 102, 152, 202, 252, 302, 352, 402, 452]);
+        for (_, report) in &reports {
+            assert_eq!(report.region, 20..=24);
+            assert!(report.max_change_db > 30.);
+            assert!(report.metric_value < 0.9);
+        }
+
+        assert_eq!(
+            reports[0]
+                .1
+                .freq_range(Frequency::from_mhz(2_400), Frequency::from_mhz(1)),
+            Frequency::from_mhz(2_420)..=Frequency::from_mhz(2_424)
+        );
+    }
+
+    #[test]
+    fn start_over_when_sweep_len_changes() {
+        let mut detector = ChangeDetector::new(ChangeDetectorConfig {
+            baseline_sweeps: 2,
+            consecutive_sweeps: 1,
+            ..ChangeDetectorConfig::default()
+        });
+        detector.add_sweep(&sweep(1)).unwrap();
+        detector.add_sweep(&sweep(2)).unwrap();
+        // A shorter sweep would be compared with the baseline if it didn't start over
+        assert_eq!(detector.add_sweep(&[-50.; 10]).unwrap(), None);
+        assert_eq!(detector.add_sweep(&[-50.; 10]).unwrap(), None);
+        assert!(detector.add_sweep(&[-10.; 10]).unwrap().is_some());
+    }
+}
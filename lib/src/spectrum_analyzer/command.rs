@@ -2,32 +2,144 @@ use std::borrow::Cow;
 
 use super::{CalcMode, DspMode, InputStage, WifiBand};
 use crate::common::Frequency;
+use crate::rf_explorer::{frame_body, text_fields};
 
+/// A command understood by RF Explorer spectrum analyzers.
+///
+/// Commands understood by signal generators too are in [`rfe::Command`](crate::Command). New
+/// commands may be added in minor releases, so matches on this enum need a wildcard arm.
 #[derive(Debug, Copy, Clone, PartialEq)]
-pub(crate) enum Command {
+#[non_exhaustive]
+pub enum Command {
+    /// Sets the start and stop frequencies and the amplitude range of the display.
     SetConfig {
+        /// The start frequency, sent in kHz.
         start: Frequency,
+        /// The stop frequency, sent in kHz.
         stop: Frequency,
+        /// The bottom of the display's amplitude range.
         min_amp_dbm: i16,
+        /// The top of the display's amplitude range.
         max_amp_dbm: i16,
     },
+    /// Switches to the main radio module.
     SwitchModuleMain,
+    /// Switches to the expansion radio module.
     SwitchModuleExp,
+    /// Starts tracking a signal generator.
     StartTracking {
+        /// The first frequency, sent in kHz.
         start: Frequency,
+        /// The step between frequencies, sent in kHz.
         step: Frequency,
     },
+    /// Starts the Wi-Fi analyzer on a band.
     StartWifiAnalyzer(WifiBand),
+    /// Stops the Wi-Fi analyzer.
     StopWifiAnalyzer,
+    /// Sets the calculator mode.
     SetCalcMode(CalcMode),
+    /// Moves tracking to a step.
     TrackingStep(u16),
+    /// Sets the DSP mode.
     SetDsp(DspMode),
+    /// Sets the amplitude offset in dB.
     SetOffsetDB(i8),
+    /// Selects the RF input stage.
     SetInputStage(InputStage),
+    /// Sets the number of points in each sweep, which must be a multiple of 16 up to 4096.
     SetSweepPointsExt(u16),
+    /// Sets the number of points in each sweep on models that support more than 4096.
     SetSweepPointsLarge(u16),
 }
 
+impl Command {
+    /// Returns the bytes sent to the spectrum analyzer for the command.
+    pub fn to_bytes(self) -> Cow<'static, [u8]> {
+        self.into()
+    }
+
+    /// Returns a short description of what the command does.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Command::SetConfig { .. } => "Set config",
+            Command::SwitchModuleMain => "Switch to main module",
+            Command::SwitchModuleExp => "Switch to expansion module",
+            Command::StartTracking { .. } => "Start tracking",
+            Command::StartWifiAnalyzer(_) => "Start Wi-Fi analyzer",
+            Command::StopWifiAnalyzer => "Stop Wi-Fi analyzer",
+            Command::SetCalcMode(_) => "Set calculator mode",
+            Command::TrackingStep(_) => "Tracking step",
+            Command::SetDsp(_) => "Set DSP mode",
+            Command::SetOffsetDB(_) => "Set amplitude offset",
+            Command::SetInputStage(_) => "Set input stage",
+            Command::SetSweepPointsExt(_) => "Set sweep points",
+            Command::SetSweepPointsLarge(_) => "Set large sweep points",
+        }
+    }
+
+    /// Returns every command that doesn't take parameters.
+    pub fn parameterless() -> impl Iterator<Item = Command> {
+        [
+            Command::SwitchModuleMain,
+            Command::SwitchModuleExp,
+            Command::StopWifiAnalyzer,
+        ]
+        .into_iter()
+    }
+
+    /// Recognizes a complete command frame, like one captured by sniffing the serial port.
+    ///
+    /// Returns `None` if the bytes aren't exactly one command this enum can represent.
+    pub fn parse(bytes: &[u8]) -> Option<Command> {
+        if let Some(command) = Self::parameterless().find(|command| command.to_bytes() == bytes) {
+            return Some(command);
+        }
+        let body = frame_body(bytes)?;
+        if let Some(fields) = text_fields(body, "C2-F:") {
+            let [start_khz, stop_khz, max_amp_dbm, min_amp_dbm] = fields[..] else {
+                return None;
+            };
+            return Some(Command::SetConfig {
+                start: Frequency::from_khz(start_khz.parse().ok()?),
+                stop: Frequency::from_khz(stop_khz.parse().ok()?),
+                min_amp_dbm: min_amp_dbm.parse().ok()?,
+                max_amp_dbm: max_amp_dbm.parse().ok()?,
+            });
+        }
+        if let Some(fields) = text_fields(body, "C3-K:") {
+            let [start_khz, step_khz] = fields[..] else {
+                return None;
+            };
+            return Some(Command::StartTracking {
+                start: Frequency::from_khz(start_khz.parse().ok()?),
+                step: Frequency::from_khz(step_khz.parse().ok()?),
+            });
+        }
+        match *body {
+            [b'C', b'W', wifi_band] => Some(Command::StartWifiAnalyzer(
+                WifiBand::try_from(wifi_band).ok()?,
+            )),
+            [b'C', b'+', calc_mode] => {
+                Some(Command::SetCalcMode(CalcMode::try_from(calc_mode).ok()?))
+            }
+            [b'k', high, low] => Some(Command::TrackingStep(u16::from_be_bytes([high, low]))),
+            [b'C', b'p', dsp_mode] => Some(Command::SetDsp(DspMode::try_from(dsp_mode).ok()?)),
+            [b'C', b'O', offset_db] => Some(Command::SetOffsetDB(offset_db as i8)),
+            [b'a', input_stage] => Some(Command::SetInputStage(
+                InputStage::try_from(input_stage).ok()?,
+            )),
+            [b'C', b'J', sweep_points] => Some(Command::SetSweepPointsExt(
+                (u16::from(sweep_points) + 1) * 16,
+            )),
+            [b'C', b'j', high, low] => Some(Command::SetSweepPointsLarge(u16::from_be_bytes([
+                high, low,
+            ]))),
+            _ => None,
+        }
+    }
+}
+
 impl From<Command> for Cow<'static, [u8]> {
     fn from(command: Command) -> Cow<'static, [u8]> {
         match command {
@@ -134,4 +246,83 @@ mod tests {
         assert_correct_size!(Command::SetSweepPointsExt(1024));
         assert_correct_size!(Command::SetSweepPointsLarge(8192));
     }
+
+    #[test]
+    fn match_documented_byte_sequences() {
+        assert_eq!(
+            Command::SetConfig {
+                start: Frequency::from_mhz(90),
+                stop: Frequency::from_mhz(110),
+                min_amp_dbm: -120,
+                max_amp_dbm: -40
+            }
+            .to_bytes(),
+            &b"#\x20C2-F:0090000,0110000,-040,-120"[..]
+        );
+        assert_eq!(
+            Command::StartTracking {
+                start: Frequency::from_mhz(100),
+                step: Frequency::from_mhz(1)
+            }
+            .to_bytes(),
+            &b"#\x16C3-K:0100000,0001000"[..]
+        );
+        assert_eq!(Command::SwitchModuleExp.to_bytes(), &b"#\x05CM\x01"[..]);
+        assert_eq!(
+            Command::StartWifiAnalyzer(WifiBand::FiveGhz).to_bytes(),
+            &b"#\x05CW\x02"[..]
+        );
+        assert_eq!(
+            Command::TrackingStep(258).to_bytes(),
+            &b"#\x05k\x01\x02"[..]
+        );
+        assert_eq!(Command::SetOffsetDB(-10).to_bytes(), &b"#\x05CO\xf6"[..]);
+        assert_eq!(
+            Command::SetInputStage(InputStage::Lna25dB).to_bytes(),
+            &b"#\x04a2"[..]
+        );
+        assert_eq!(
+            Command::SetSweepPointsExt(1024).to_bytes(),
+            &b"#\x05CJ\x3f"[..]
+        );
+        assert_eq!(
+            Command::SetSweepPointsLarge(8192).to_bytes(),
+            &b"#\x06Cj\x20\x00"[..]
+        );
+    }
+
+    #[test]
+    fn parse_sniffed_commands() {
+        let commands = [
+            Command::SetConfig {
+                start: Frequency::from_mhz(90),
+                stop: Frequency::from_mhz(110),
+                min_amp_dbm: -120,
+                max_amp_dbm: -40,
+            },
+            Command::StartTracking {
+                start: Frequency::from_mhz(100),
+                step: Frequency::from_mhz(1),
+            },
+            Command::StartWifiAnalyzer(WifiBand::TwoPointFourGhz),
+            Command::SetCalcMode(CalcMode::MaxHold),
+            Command::TrackingStep(4),
+            Command::SetDsp(DspMode::Fast),
+            Command::SetOffsetDB(-10),
+            Command::SetInputStage(InputStage::Attenuator30dB),
+            Command::SetSweepPointsExt(1024),
+            Command::SetSweepPointsLarge(8192),
+        ];
+        for command in commands.into_iter().chain(Command::parameterless()) {
+            assert_eq!(Command::parse(&command.to_bytes()), Some(command));
+        }
+        assert_eq!(
+            Command::parse(b"#\x05CW\x00"),
+            Some(Command::StopWifiAnalyzer)
+        );
+        assert_eq!(Command::parse(b"#\x04a9"), None);
+        assert_eq!(Command::parse(b"#\x0eC3-K:0100000"), None);
+        // Common commands are parsed by `rfe::Command`
+        assert_eq!(Command::parse(b"#\x04C0"), None);
+    }
 }
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, io};
 
 use chrono::{DateTime, Utc};
 use nom::{
@@ -8,11 +8,12 @@ use nom::{
     combinator::{map, map_res, opt},
 };
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use thiserror::Error;
 
 use crate::{
-    common::{Frequency, MessageParseError},
+    common::{Frequency, MessageParseError, WriteMessage},
     rf_explorer::parsers::*,
-    spectrum_analyzer::parsers::*,
+    spectrum_analyzer::{AlignmentReport, parsers::*},
 };
 
 #[derive(Debug, Copy, Clone, TryFromPrimitive, Eq, PartialEq, Default)]
@@ -97,7 +98,9 @@ impl Display for CalcMode {
 }
 
 /// Spectrum analyzer configuration reported by an RF Explorer.
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+///
+/// Two configs are equal when their settings are equal, regardless of when they were received.
+#[derive(Debug, Default, Clone, Eq)]
 pub struct Config {
     /// Sweep start frequency.
     pub start_freq: Frequency,
@@ -135,8 +138,75 @@ pub struct Config {
 }
 
 impl Config {
-    pub(crate) const PREFIX: &'static [u8] = b"#C2-F:";
+    /// The bytes every config message starts with.
+    pub const PREFIX: &'static [u8] = b"#C2-F:";
 
+    /// Compares the config's sweep points with where the requested start and stop frequencies
+    /// would put them.
+    pub fn requested_vs_actual(
+        &self,
+        requested_start: Frequency,
+        requested_stop: Frequency,
+    ) -> AlignmentReport {
+        AlignmentReport::new(
+            requested_start,
+            requested_stop,
+            self.start_freq,
+            self.step_size,
+            self.sweep_len,
+        )
+    }
+
+    /// Returns a hash of the settings that determine which frequency each sweep point measures.
+    ///
+    /// Only the start frequency, stop frequency, and sweep length are included, so configs that
+    /// differ in settings like the amplitude range have the same fingerprint. The hash doesn't
+    /// depend on the platform or the version of this crate, so fingerprints can be saved.
+    pub fn fingerprint(&self) -> u64 {
+        // 64-bit FNV-1a
+        [
+            self.start_freq.as_hz().to_le_bytes().as_slice(),
+            self.stop_freq.as_hz().to_le_bytes().as_slice(),
+            self.sweep_len.to_le_bytes().as_slice(),
+        ]
+        .concat()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    /// Checks that the config's settings are possible, so a config that was corrupted on its way
+    /// from the RF Explorer can be dropped instead of replacing the last good config.
+    pub(crate) fn validate(&self, max_sweep_len: u16) -> Result<(), InvalidConfig> {
+        if self.sweep_len > max_sweep_len {
+            return Err(InvalidConfig::SweepLen {
+                sweep_len: self.sweep_len,
+                max_sweep_len,
+            });
+        }
+        if self.start_freq >= self.stop_freq {
+            return Err(InvalidConfig::StartStop {
+                start: self.start_freq,
+                stop: self.stop_freq,
+            });
+        }
+        if self.span > self.max_span {
+            return Err(InvalidConfig::Span {
+                span: self.span,
+                max_span: self.max_span,
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether the config has the start and stop frequencies and the amplitude range that were
+    /// requested.
+    ///
+    /// The RF Explorer is sent whole kilohertz, so the frequencies must be within half a step of
+    /// the requested ones rounded down to kilohertz. The step size is reported rounded down to
+    /// whole hertz, so the stop frequency calculated from it can also be up to a hertz per step
+    /// lower.
     #[tracing::instrument(skip(self), ret, fields(self.start_freq = ?self.start_freq, self.stop_freq = ?self.stop_freq, self.min_amp_dbm = ?self.min_amp_dbm, self.max_amp_dbm = ?self.max_amp_dbm))]
     pub(crate) fn contains_start_stop_amp_range(
         &self,
@@ -145,13 +215,61 @@ impl Config {
         min_amp_dbm: i16,
         max_amp_dbm: i16,
     ) -> bool {
-        self.start_freq.abs_diff(start) <= self.step_size
-            && self.stop_freq.abs_diff(stop) <= self.step_size * 2
+        let half_step = self.step_size / 2;
+        let step_rounding = Frequency::from_hz(u64::from(self.sweep_len.saturating_sub(1)));
+        self.start_freq
+            .abs_diff(Frequency::from_khz(start.as_khz()))
+            <= half_step
+            && self.stop_freq.abs_diff(Frequency::from_khz(stop.as_khz()))
+                <= half_step + step_rounding
             && self.min_amp_dbm == min_amp_dbm
             && self.max_amp_dbm == max_amp_dbm
     }
 }
 
+/// The reason a config's settings are impossible.
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum InvalidConfig {
+    #[error("Sweep length of {sweep_len} is longer than the maximum of {max_sweep_len}")]
+    SweepLen { sweep_len: u16, max_sweep_len: u16 },
+    #[error(
+        "Start frequency of {} MHz is not below the stop frequency of {} MHz",
+        .start.as_mhz_f64(),
+        .stop.as_mhz_f64()
+    )]
+    StartStop { start: Frequency, stop: Frequency },
+    #[error(
+        "Span of {} MHz is wider than the maximum span of {} MHz",
+        .span.as_mhz_f64(),
+        .max_span.as_mhz_f64()
+    )]
+    Span {
+        span: Frequency,
+        max_span: Frequency,
+    },
+}
+
+impl PartialEq for Config {
+    fn eq(&self, other: &Self) -> bool {
+        self.start_freq == other.start_freq
+            && self.step_size == other.step_size
+            && self.stop_freq == other.stop_freq
+            && self.center_freq == other.center_freq
+            && self.span == other.span
+            && self.max_amp_dbm == other.max_amp_dbm
+            && self.min_amp_dbm == other.min_amp_dbm
+            && self.sweep_len == other.sweep_len
+            && self.is_expansion_radio_module_active == other.is_expansion_radio_module_active
+            && self.mode == other.mode
+            && self.min_freq == other.min_freq
+            && self.max_freq == other.max_freq
+            && self.max_span == other.max_span
+            && self.rbw == other.rbw
+            && self.amp_offset_db == other.amp_offset_db
+            && self.calc_mode == other.calc_mode
+    }
+}
+
 impl<'a> TryFrom<&'a [u8]> for Config {
     type Error = MessageParseError<'a>;
 
@@ -182,7 +300,7 @@ impl<'a> TryFrom<&'a [u8]> for Config {
         // Parse the number of points in a sweep
         // 0-9999 uses 4 bytes and 10000+ uses 5 bytes
         // Try to parse using 5 bytes first and if that doesn't work fall back to 4 bytes
-        let (bytes, sweep_len) = alt((num_parser(5u8), num_parser(4u8))).parse(bytes)?;
+        let (bytes, sweep_len): (_, u16) = alt((num_parser(5u8), num_parser(4u8))).parse(bytes)?;
 
         let (bytes, _) = parse_comma(bytes)?;
 
@@ -235,7 +353,8 @@ impl<'a> TryFrom<&'a [u8]> for Config {
         // Consume \n or \r\n line endings and make sure there aren't any bytes left afterwards
         let _ = parse_opt_line_ending(bytes)?;
 
-        let stop_freq = start_freq + (step_size * u64::from(sweep_len - 1));
+        // A corrupt sweep length of 0 is caught when the config is validated
+        let stop_freq = start_freq + (step_size * u64::from(sweep_len.saturating_sub(1)));
 
         Ok(Config {
             start_freq,
@@ -259,9 +378,42 @@ impl<'a> TryFrom<&'a [u8]> for Config {
     }
 }
 
+impl WriteMessage for Config {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(Config::PREFIX)?;
+        write!(
+            writer,
+            "{:07},{:07},{:04},{:04},{:04},{},{:03},{:07},{:07},{:07}",
+            self.start_freq.as_khz(),
+            self.step_size.as_hz(),
+            self.max_amp_dbm,
+            self.min_amp_dbm,
+            self.sweep_len,
+            u8::from(self.is_expansion_radio_module_active),
+            self.mode as u8,
+            self.min_freq.as_khz(),
+            self.max_freq.as_khz(),
+            self.max_span.as_khz(),
+        )?;
+
+        // Older RF Explorers don't send the RBW, amplitude offset, or calculator mode
+        if let Some(rbw) = self.rbw {
+            write!(writer, ",{:05}", rbw.as_khz())?;
+        }
+        if let Some(amp_offset_db) = self.amp_offset_db {
+            write!(writer, ",{amp_offset_db:04}")?;
+        }
+        if let Some(calc_mode) = self.calc_mode {
+            write!(writer, ",{:03}", u8::from(calc_mode))?;
+        }
+        writer.write_all(b"\r\n")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::spectrum_analyzer::Capabilities;
 
     #[test]
     fn parse_6g_combo_config() {
@@ -286,6 +438,56 @@ mod tests {
         assert_eq!(config.calc_mode, Some(CalcMode::Normal));
     }
 
+    #[test]
+    fn contain_start_stop_entered_as_float_mhz() {
+        // 433.92 MHz to 434.04 MHz with 4096 points, whose 29 Hz steps are finer than a kilohertz
+        let config = Config::try_from(
+            b"#C2-F:0433920,0000029,-030,-118,4096,0,000,0015000,2700000,2685000,00003,0000,000"
+                .as_ref(),
+        )
+        .unwrap();
+        let start = Frequency::from_mhz_f64(433.92);
+        let stop = Frequency::from_mhz_f64(434.04);
+        assert_eq!(start, Frequency::from_khz(433_920));
+        assert!(config.contains_start_stop_amp_range(start, stop, -118, -30));
+        assert!(!config.contains_start_stop_amp_range(
+            Frequency::from_khz(433_921),
+            stop,
+            -118,
+            -30
+        ));
+        assert!(!config.contains_start_stop_amp_range(
+            start,
+            Frequency::from_khz(434_045),
+            -118,
+            -30
+        ));
+    }
+
+    #[test]
+    fn fingerprint_ignores_amp_range() {
+        let bytes =
+            b"#C2-F:5249000,0196428,-030,-118,0112,0,000,4850000,6100000,0600000,00200,0000,000";
+        let config = Config::try_from(bytes.as_ref()).unwrap();
+        let amp_range_changed = Config {
+            max_amp_dbm: -10,
+            min_amp_dbm: -100,
+            ..config.clone()
+        };
+        assert_eq!(config.fingerprint(), amp_range_changed.fingerprint());
+
+        let start_changed = Config {
+            start_freq: Frequency::from_mhz(5_250),
+            ..config.clone()
+        };
+        let sweep_len_changed = Config {
+            sweep_len: 224,
+            ..config.clone()
+        };
+        assert_ne!(config.fingerprint(), start_changed.fingerprint());
+        assert_ne!(config.fingerprint(), sweep_len_changed.fingerprint());
+    }
+
     #[test]
     fn parse_wsub1g_plus_config() {
         let bytes =
@@ -315,6 +517,38 @@ mod tests {
         assert_eq!(config.calc_mode, None);
     }
 
+    #[test]
+    fn write_config() {
+        let bytes =
+            b"#C2-F:5249000,0196428,-030,-118,0112,0,000,4850000,6100000,0600000,00200,0000,000\r\n";
+        let config = Config::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(config.to_bytes(), bytes);
+        assert_eq!(
+            Config::try_from(config.to_bytes().as_slice()).unwrap(),
+            config
+        );
+
+        let bytes = b"#C2-F:0096000,0090072,-010,-120,10240,1,000,0000050,0960000,0959950\r\n";
+        let config = Config::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(config.to_bytes(), bytes);
+        assert_eq!(
+            Config::try_from(config.to_bytes().as_slice()).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn configs_with_same_settings_are_equal() {
+        let bytes =
+            b"#C2-F:0096000,0090072,-010,-120,0112,0,000,0000050,0960000,0959950,00110,0000,000";
+        let config = Config::try_from(bytes.as_ref()).unwrap();
+        let later_config = Config {
+            timestamp: config.timestamp + chrono::Duration::seconds(1),
+            ..config.clone()
+        };
+        assert_eq!(config, later_config);
+    }
+
     #[test]
     fn fail_to_parse_config_with_incorrect_prefix() {
         let bytes =
@@ -322,6 +556,42 @@ mod tests {
         assert!(Config::try_from(bytes.as_ref()).is_err());
     }
 
+    #[test]
+    fn reject_configs_with_impossible_settings() {
+        let parse = |bytes: &[u8]| Config::try_from(bytes).unwrap();
+        let config = parse(b"#C2-F:2400000,0892857,-010,-120,0112,0,000,0240000,6100000,5860000");
+        assert_eq!(config.validate(112), Ok(()));
+
+        // A sweep length that's longer than the model supports
+        let config = parse(b"#C2-F:2400000,0000100,-010,-120,50000,0,000,0240000,6100000,5860000");
+        assert_eq!(
+            config.validate(112),
+            Err(InvalidConfig::SweepLen {
+                sweep_len: 50_000,
+                max_sweep_len: 112
+            })
+        );
+        assert_eq!(config.validate(Capabilities::MAX_SWEEP_LEN), Ok(()));
+
+        // Sweep lengths of 0 and 1 put the stop frequency at the start frequency
+        for sweep_len in [b"0000", b"0001"] {
+            let mut bytes =
+                b"#C2-F:2400000,0892857,-010,-120,0112,0,000,0240000,6100000,5860000".to_vec();
+            bytes[32..36].copy_from_slice(sweep_len);
+            assert!(matches!(
+                parse(&bytes).validate(112),
+                Err(InvalidConfig::StartStop { .. })
+            ));
+        }
+
+        // A span that's wider than the maximum span
+        let config = parse(b"#C2-F:0240000,9999999,-010,-120,0112,0,000,0240000,6100000,0100000");
+        assert!(matches!(
+            config.validate(112),
+            Err(InvalidConfig::Span { .. })
+        ));
+    }
+
     #[test]
     fn fail_to_parse_config_with_invalid_start_freq() {
         let bytes =
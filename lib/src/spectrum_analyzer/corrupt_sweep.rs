@@ -0,0 +1,289 @@
+use std::collections::VecDeque;
+
+/// The settings of the heuristic that flags sweeps that were probably corrupted on their way from
+/// the RF Explorer.
+///
+/// The RF Explorer's protocol has no checksums, so a sweep whose bytes were garbled by a long USB
+/// extension or a noisy cable can still parse. Instead, each point is compared with its average
+/// over the recent sweeps, and a sweep is flagged if too many of its points jumped away from
+/// their averages at once. Real signals usually only change a few points between sweeps, while
+/// corruption shifts or replaces large runs of them.
+///
+/// # False positives
+///
+/// A real change that affects most of the sweep, like a broadband signal switching on or the
+/// input stage changing, looks the same as corruption. Raise [`jump_db`](Self::jump_db) or
+/// [`jump_fraction`](Self::jump_fraction) if real sweeps are being flagged. If the change
+/// persists, the heuristic accepts it as the new normal after
+/// [`history_len`](Self::history_len) flagged sweeps in a row, so at most that many real sweeps
+/// are dropped. The history starts over whenever the config changes, and nothing is flagged until
+/// it has filled up again.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HeuristicConfig {
+    /// How far in dB a point must be from its average over the recent sweeps to count as a jump.
+    pub jump_db: f32,
+    /// The fraction of points, from 0 to 1, that must jump for a sweep to be flagged.
+    pub jump_fraction: f32,
+    /// How many of the recent unflagged sweeps each point's average is computed over. Values less
+    /// than 1 are treated as 1.
+    pub history_len: usize,
+    /// Whether flagged sweeps are dropped. Otherwise they're kept and marked with
+    /// [`SweepMeta::suspected_corrupt`](crate::spectrum_analyzer::SweepMeta::suspected_corrupt).
+    pub drop: bool,
+}
+
+impl Default for HeuristicConfig {
+    fn default() -> Self {
+        HeuristicConfig {
+            jump_db: 20.,
+            jump_fraction: 0.25,
+            history_len: 8,
+            drop: false,
+        }
+    }
+}
+
+/// Counts of the sweeps checked by the corruption heuristic.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct CorruptionStats {
+    /// The number of sweeps that were compared with the recent history.
+    pub checked: u64,
+    /// The number of sweeps that were flagged as probably corrupt.
+    pub flagged: u64,
+    /// The number of flagged sweeps that were dropped.
+    pub dropped: u64,
+}
+
+/// What to do with a sweep that was checked for corruption.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum CorruptionVerdict {
+    Clean,
+    /// The sweep is probably corrupt but should be kept.
+    Flagged,
+    /// The sweep is probably corrupt and should be dropped.
+    Dropped,
+}
+
+/// Keeps the recent history of each point and flags sweeps that jump away from it.
+#[derive(Debug, Default)]
+pub(crate) struct CorruptionDetector {
+    heuristic: Option<HeuristicConfig>,
+    history: VecDeque<Vec<f32>>,
+    /// The sum of each point over the sweeps in the history.
+    sums: Vec<f64>,
+    /// The number of flagged sweeps since the last unflagged one.
+    consecutive_flagged: usize,
+    stats: CorruptionStats,
+}
+
+impl CorruptionDetector {
+    pub(crate) fn heuristic(&self) -> Option<HeuristicConfig> {
+        self.heuristic
+    }
+
+    pub(crate) fn set_heuristic(&mut self, heuristic: Option<HeuristicConfig>) {
+        self.heuristic = heuristic;
+        self.reset();
+    }
+
+    pub(crate) fn stats(&self) -> CorruptionStats {
+        self.stats
+    }
+
+    /// Forgets the recent sweeps, like after the config changes.
+    pub(crate) fn reset(&mut self) {
+        self.history.clear();
+        self.sums.clear();
+        self.consecutive_flagged = 0;
+    }
+
+    pub(crate) fn on_sweep(&mut self, amplitudes_dbm: &[f32]) -> CorruptionVerdict {
+        let Some(heuristic) = self.heuristic else {
+            return CorruptionVerdict::Clean;
+        };
+        let history_len = heuristic.history_len.max(1);
+        if self.sums.len() != amplitudes_dbm.len() {
+            self.reset();
+        }
+        if self.history.len() < history_len {
+            self.push(amplitudes_dbm, history_len);
+            return CorruptionVerdict::Clean;
+        }
+
+        self.stats.checked += 1;
+        if !self.is_corrupt(amplitudes_dbm, &heuristic) {
+            self.consecutive_flagged = 0;
+            self.push(amplitudes_dbm, history_len);
+            return CorruptionVerdict::Clean;
+        }
+
+        self.stats.flagged += 1;
+        self.consecutive_flagged += 1;
+        if self.consecutive_flagged >= history_len {
+            // The sweeps have changed for real, so start over from the new normal
+            self.reset();
+            self.push(amplitudes_dbm, history_len);
+        }
+        if heuristic.drop {
+            self.stats.dropped += 1;
+            CorruptionVerdict::Dropped
+        } else {
+            CorruptionVerdict::Flagged
+        }
+    }
+
+    fn is_corrupt(&self, amplitudes_dbm: &[f32], heuristic: &HeuristicConfig) -> bool {
+        if amplitudes_dbm.is_empty() {
+            return false;
+        }
+        let history_len = self.history.len() as f64;
+        let jumps = amplitudes_dbm
+            .iter()
+            .zip(&self.sums)
+            .filter(|&(&amp_dbm, &sum)| {
+                (f64::from(amp_dbm) - sum / history_len).abs() > f64::from(heuristic.jump_db)
+            })
+            .count();
+        jumps as f32 > heuristic.jump_fraction * amplitudes_dbm.len() as f32
+    }
+
+    fn push(&mut self, amplitudes_dbm: &[f32], history_len: usize) {
+        self.sums.resize(amplitudes_dbm.len(), 0.);
+        while self.history.len() >= history_len {
+            let Some(oldest) = self.history.pop_front() else {
+                break;
+            };
+            for (sum, amp_dbm) in self.sums.iter_mut().zip(oldest) {
+                *sum -= f64::from(amp_dbm);
+            }
+        }
+        for (sum, amp_dbm) in self.sums.iter_mut().zip(amplitudes_dbm) {
+            *sum += f64::from(*amp_dbm);
+        }
+        self.history.push_back(amplitudes_dbm.to_vec());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWEEP_LEN: usize = 112;
+
+    /// Returns a sweep with noise around -100 dBm and a tone at -40 dBm, like a 2.4 GHz band with
+    /// one strong access point.
+    fn sweep(seed: u32) -> Vec<f32> {
+        let mut state = seed.wrapping_mul(2_654_435_761).wrapping_add(1);
+        (0..SWEEP_LEN)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let noise_db = (state % 60) as f32 / 10. - 3.;
+                let tone_db = if (50..54).contains(&i) { 60. } else { 0. };
+                -100. + noise_db + tone_db
+            })
+            .collect()
+    }
+
+    /// Shifts the sweep's amplitudes by a few points, like when bytes are lost mid-sweep, and
+    /// garbles a run of them.
+    fn corrupt(mut sweep: Vec<f32>) -> Vec<f32> {
+        sweep.rotate_left(20);
+        for amp_dbm in &mut sweep[60..100] {
+            *amp_dbm = -13.5;
+        }
+        sweep
+    }
+
+    fn detector(drop: bool) -> CorruptionDetector {
+        let mut detector = CorruptionDetector::default();
+        detector.set_heuristic(Some(HeuristicConfig {
+            history_len: 4,
+            drop,
+            ..HeuristicConfig::default()
+        }));
+        detector
+    }
+
+    #[test]
+    fn flag_corrupt_sweeps() {
+        let mut detector = detector(false);
+        for seed in 0..20 {
+            assert_eq!(detector.on_sweep(&sweep(seed)), CorruptionVerdict::Clean);
+        }
+        assert_eq!(
+            detector.on_sweep(&corrupt(sweep(20))),
+            CorruptionVerdict::Flagged
+        );
+        assert_eq!(detector.on_sweep(&sweep(21)), CorruptionVerdict::Clean);
+        assert_eq!(
+            detector.stats(),
+            CorruptionStats {
+                checked: 18,
+                flagged: 1,
+                dropped: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn drop_corrupt_sweeps() {
+        let mut detector = detector(true);
+        for seed in 0..4 {
+            detector.on_sweep(&sweep(seed));
+        }
+        assert_eq!(
+            detector.on_sweep(&corrupt(sweep(4))),
+            CorruptionVerdict::Dropped
+        );
+        assert_eq!(detector.stats().dropped, 1);
+    }
+
+    #[test]
+    fn ignore_signals_in_a_few_points() {
+        let mut detector = detector(false);
+        for seed in 0..4 {
+            detector.on_sweep(&sweep(seed));
+        }
+        // A new tone that covers a tenth of the sweep is a real signal
+        let mut with_tone = sweep(4);
+        for amp_dbm in &mut with_tone[10..21] {
+            *amp_dbm = -30.;
+        }
+        assert_eq!(detector.on_sweep(&with_tone), CorruptionVerdict::Clean);
+    }
+
+    #[test]
+    fn accept_lasting_changes() {
+        let mut detector = detector(false);
+        for seed in 0..4 {
+            detector.on_sweep(&sweep(seed));
+        }
+        // The noise floor rising by 30 dB for good is flagged until it fills the history
+        let raised = |seed| {
+            sweep(seed)
+                .iter()
+                .map(|amp_dbm| amp_dbm + 30.)
+                .collect::<Vec<_>>()
+        };
+        for seed in 4..8 {
+            assert_eq!(detector.on_sweep(&raised(seed)), CorruptionVerdict::Flagged);
+        }
+        for seed in 8..16 {
+            assert_eq!(detector.on_sweep(&raised(seed)), CorruptionVerdict::Clean);
+        }
+    }
+
+    #[test]
+    fn stay_off_without_heuristic() {
+        let mut detector = CorruptionDetector::default();
+        for seed in 0..10 {
+            assert_eq!(
+                detector.on_sweep(&corrupt(sweep(seed))),
+                CorruptionVerdict::Clean
+            );
+        }
+        assert_eq!(detector.stats(), CorruptionStats::default());
+    }
+}
@@ -1,10 +1,10 @@
-use std::{convert::TryFrom, fmt::Display};
+use std::{convert::TryFrom, fmt::Display, io};
 
 use nom::Parser;
 use nom::{bytes::complete::tag, combinator::map_res};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
-use crate::common::MessageParseError;
+use crate::common::{MessageParseError, WriteMessage};
 use crate::rf_explorer::parsers::*;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, TryFromPrimitive, IntoPrimitive, Default)]
@@ -23,7 +23,8 @@ pub enum DspMode {
 }
 
 impl DspMode {
-    pub(crate) const PREFIX: &'static [u8] = b"DSP:";
+    /// The bytes every DSP mode message starts with.
+    pub const PREFIX: &'static [u8] = b"DSP:";
 }
 
 impl<'a> TryFrom<&'a [u8]> for DspMode {
@@ -43,6 +44,13 @@ impl<'a> TryFrom<&'a [u8]> for DspMode {
     }
 }
 
+impl WriteMessage for DspMode {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(DspMode::PREFIX)?;
+        write!(writer, "{}\r\n", u8::from(*self))
+    }
+}
+
 impl Display for DspMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let dsp_mode = match self {
@@ -65,4 +73,20 @@ mod tests {
         let dsp_mode = DspMode::try_from(bytes.as_ref()).unwrap();
         assert_eq!(dsp_mode, DspMode::Auto);
     }
+
+    #[test]
+    fn write_dsp_mode() {
+        assert_eq!(DspMode::NoImg.to_bytes(), b"DSP:3\r\n");
+        for dsp_mode in [
+            DspMode::Auto,
+            DspMode::Filter,
+            DspMode::Fast,
+            DspMode::NoImg,
+        ] {
+            assert_eq!(
+                DspMode::try_from(dsp_mode.to_bytes().as_slice()),
+                Ok(dsp_mode)
+            );
+        }
+    }
 }
@@ -0,0 +1,179 @@
+use std::{collections::VecDeque, mem, sync::Mutex};
+
+use chrono::{DateTime, Utc};
+
+/// An external event, like a test rig's trigger, recorded with
+/// [`SpectrumAnalyzer::annotate_event`](crate::SpectrumAnalyzer::annotate_event).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// When the event happened.
+    pub timestamp: DateTime<Utc>,
+    /// The label the event was recorded with.
+    pub label: String,
+}
+
+/// The events recorded since the last sweep was received.
+///
+/// Only the newest events are kept, [`EventLog::DEFAULT_CAPACITY`] unless the memory budget says
+/// otherwise, so events recorded while no sweeps are being received can't use more and more
+/// memory.
+#[derive(Debug)]
+pub(crate) struct EventLog {
+    state: Mutex<EventLogState>,
+}
+
+#[derive(Debug)]
+struct EventLogState {
+    pending: VecDeque<Event>,
+    capacity: usize,
+    dropped_events: u64,
+}
+
+impl EventLog {
+    /// The maximum number of events waiting for a sweep by default.
+    pub(crate) const DEFAULT_CAPACITY: usize = 256;
+
+    /// Records an event, dropping the oldest pending event if the log is full.
+    pub(crate) fn push(&self, event: Event) {
+        let mut state = self.state.lock().unwrap();
+        if state.capacity == 0 {
+            state.dropped_events += 1;
+            return;
+        }
+        if state.pending.len() >= state.capacity {
+            state.pending.pop_front();
+            state.dropped_events += 1;
+        }
+        // Events with injected timestamps can arrive out of order, so keep them sorted
+        let index = state
+            .pending
+            .iter()
+            .rposition(|pending| pending.timestamp <= event.timestamp)
+            .map_or(0, |i| i + 1);
+        state.pending.insert(index, event);
+    }
+
+    /// Moves the events that happened at or before `received_at` into `events`, replacing its
+    /// contents.
+    ///
+    /// `events` is reused so that no allocations are needed when there are no events.
+    pub(crate) fn take_until(&self, received_at: DateTime<Utc>, events: &mut Vec<Event>) {
+        events.clear();
+        let mut state = self.state.lock().unwrap();
+        let len = state
+            .pending
+            .iter()
+            .position(|pending| pending.timestamp > received_at)
+            .unwrap_or(state.pending.len());
+        events.extend(state.pending.drain(..len));
+    }
+
+    /// Returns the number of events that were dropped because the log was full.
+    pub(crate) fn dropped_events(&self) -> u64 {
+        self.state.lock().unwrap().dropped_events
+    }
+
+    /// Sets the maximum number of events waiting for a sweep, dropping the oldest ones that no
+    /// longer fit.
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.capacity = capacity;
+        let excess = state.pending.len().saturating_sub(capacity);
+        state.pending.drain(..excess);
+        state.dropped_events += excess as u64;
+        state.pending.shrink_to(capacity);
+    }
+
+    /// Returns an estimate of the memory used by the pending events, in bytes.
+    pub(crate) fn memory_usage_estimate(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.pending.capacity() * mem::size_of::<Event>()
+            + state
+                .pending
+                .iter()
+                .map(|event| event.label.capacity())
+                .sum::<usize>()
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(EventLogState {
+                pending: VecDeque::new(),
+                capacity: Self::DEFAULT_CAPACITY,
+                dropped_events: 0,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    fn event(secs: i64, label: &str) -> Event {
+        Event {
+            timestamp: DateTime::UNIX_EPOCH + TimeDelta::seconds(secs),
+            label: label.to_string(),
+        }
+    }
+
+    fn labels(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|event| event.label.as_str()).collect()
+    }
+
+    #[test]
+    fn take_events_before_each_sweep() {
+        let log = EventLog::default();
+        log.push(event(1, "tx on"));
+        log.push(event(3, "tx off"));
+        // An injected timestamp from before the latest event
+        log.push(event(2, "gpio"));
+
+        let mut events = Vec::new();
+        log.take_until(event(2, "").timestamp, &mut events);
+        assert_eq!(labels(&events), ["tx on", "gpio"]);
+        log.take_until(event(2, "").timestamp, &mut events);
+        assert!(events.is_empty());
+        log.take_until(event(5, "").timestamp, &mut events);
+        assert_eq!(labels(&events), ["tx off"]);
+    }
+
+    #[test]
+    fn drop_oldest_events_when_full() {
+        let log = EventLog::default();
+        for i in 0..EventLog::DEFAULT_CAPACITY + 2 {
+            log.push(event(i as i64, &i.to_string()));
+        }
+        assert_eq!(log.dropped_events(), 2);
+
+        let mut events = Vec::new();
+        log.take_until(Utc::now(), &mut events);
+        assert_eq!(events.len(), EventLog::DEFAULT_CAPACITY);
+        assert_eq!(events[0].label, "2");
+    }
+
+    #[test]
+    fn drop_oldest_events_when_capacity_shrinks() {
+        let log = EventLog::default();
+        for i in 0..10 {
+            log.push(event(i, &i.to_string()));
+        }
+        log.set_capacity(4);
+        assert_eq!(log.dropped_events(), 6);
+        log.push(event(10, "10"));
+        assert_eq!(log.dropped_events(), 7);
+
+        let mut events = Vec::new();
+        log.take_until(Utc::now(), &mut events);
+        assert_eq!(labels(&events), ["7", "8", "9", "10"]);
+
+        log.set_capacity(0);
+        log.push(event(11, "11"));
+        assert_eq!(log.dropped_events(), 8);
+        assert_eq!(log.memory_usage_estimate(), 0);
+    }
+}
@@ -0,0 +1,282 @@
+use std::time::Instant;
+
+use super::{Config, DspMode};
+use crate::Frequency;
+
+/// A setting that the spectrum analyzer is expected to confirm after a command was sent.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Expectation {
+    /// The config contains the start and stop frequencies and the amplitude range.
+    StartStopAmpRange {
+        start: Frequency,
+        stop: Frequency,
+        min_amp_dbm: i16,
+        max_amp_dbm: i16,
+    },
+    /// The config contains the number of sweep points.
+    SweepLen(u16),
+    /// The DSP mode message contains the DSP mode.
+    DspMode(DspMode),
+}
+
+impl Expectation {
+    /// Whether the most recent config and DSP mode confirm the expected setting.
+    fn is_met(&self, config: Option<&Config>, dsp_mode: Option<DspMode>) -> bool {
+        match *self {
+            Expectation::StartStopAmpRange {
+                start,
+                stop,
+                min_amp_dbm,
+                max_amp_dbm,
+            } => config.is_some_and(|config| {
+                config.contains_start_stop_amp_range(start, stop, min_amp_dbm, max_amp_dbm)
+            }),
+            Expectation::SweepLen(sweep_len) => {
+                config.is_some_and(|config| config.sweep_len == sweep_len)
+            }
+            Expectation::DspMode(expected) => dsp_mode == Some(expected),
+        }
+    }
+}
+
+/// Identifies an expectation that was registered with [`Expectations::register`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct ExpectationId(u64);
+
+/// Whether a registered expectation has been confirmed yet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ExpectationStatus {
+    /// The expected setting was confirmed or the expectation was cleared.
+    Met,
+    /// The expected setting hasn't been confirmed and the expectation expires at the deadline.
+    Pending(Instant),
+    /// The expected setting wasn't confirmed before the deadline.
+    Expired,
+}
+
+#[derive(Debug)]
+struct Pending {
+    id: ExpectationId,
+    expectation: Expectation,
+    deadline: Instant,
+}
+
+/// Keeps track of the settings that commands are waiting for the spectrum analyzer to confirm.
+///
+/// Each expectation is removed once a received config or DSP mode confirms it. Expectations that
+/// aren't confirmed before their deadline, like ones for commands the RF Explorer rejected, no
+/// longer keep the spectrum analyzer from being synchronized.
+#[derive(Debug, Default)]
+pub(crate) struct Expectations {
+    pending: Vec<Pending>,
+    next_id: u64,
+}
+
+impl Expectations {
+    /// Registers an expectation unless the current config and DSP mode already confirm it.
+    pub(crate) fn register(
+        &mut self,
+        expectation: Expectation,
+        config: Option<&Config>,
+        dsp_mode: Option<DspMode>,
+        deadline: Instant,
+    ) -> Option<ExpectationId> {
+        if expectation.is_met(config, dsp_mode) {
+            return None;
+        }
+
+        let id = ExpectationId(self.next_id);
+        self.next_id += 1;
+        self.pending.push(Pending {
+            id,
+            expectation,
+            deadline,
+        });
+        Some(id)
+    }
+
+    /// Removes the expectations that are confirmed by the most recent config and DSP mode.
+    ///
+    /// Returns whether any expectations were removed.
+    pub(crate) fn confirm(&mut self, config: Option<&Config>, dsp_mode: Option<DspMode>) -> bool {
+        let pending_len = self.pending.len();
+        self.pending
+            .retain(|pending| !pending.expectation.is_met(config, dsp_mode));
+        self.pending.len() != pending_len
+    }
+
+    /// Checks whether an expectation has been confirmed and removes it if it has expired.
+    pub(crate) fn status(&mut self, id: ExpectationId, now: Instant) -> ExpectationStatus {
+        let Some(index) = self.pending.iter().position(|pending| pending.id == id) else {
+            return ExpectationStatus::Met;
+        };
+
+        let deadline = self.pending[index].deadline;
+        if now < deadline {
+            ExpectationStatus::Pending(deadline)
+        } else {
+            self.pending.remove(index);
+            ExpectationStatus::Expired
+        }
+    }
+
+    /// Whether every expectation that hasn't expired has been confirmed.
+    pub(crate) fn is_synchronized(&self, now: Instant) -> bool {
+        self.next_deadline(now).is_none()
+    }
+
+    /// The earliest deadline of the expectations that haven't expired.
+    pub(crate) fn next_deadline(&self, now: Instant) -> Option<Instant> {
+        self.pending
+            .iter()
+            .map(|pending| pending.deadline)
+            .filter(|deadline| now < *deadline)
+            .min()
+    }
+
+    /// Removes every expectation.
+    pub(crate) fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn config(sweep_len: u16) -> Config {
+        Config {
+            start_freq: Frequency::from_mhz(2_400),
+            stop_freq: Frequency::from_mhz(2_500),
+            step_size: Frequency::from_khz(900),
+            min_amp_dbm: -120,
+            max_amp_dbm: -10,
+            sweep_len,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn confirm_expectations_with_received_messages() {
+        let now = Instant::now();
+        let deadline = now + Duration::from_secs(2);
+        let mut expectations = Expectations::default();
+
+        // Expectations that are already met aren't registered
+        assert_eq!(
+            expectations.register(
+                Expectation::SweepLen(112),
+                Some(&config(112)),
+                None,
+                deadline
+            ),
+            None
+        );
+        assert!(expectations.is_synchronized(now));
+
+        let sweep_len = expectations
+            .register(
+                Expectation::SweepLen(224),
+                Some(&config(112)),
+                None,
+                deadline,
+            )
+            .unwrap();
+        let dsp_mode = expectations
+            .register(
+                Expectation::DspMode(DspMode::Fast),
+                Some(&config(112)),
+                Some(DspMode::Auto),
+                deadline,
+            )
+            .unwrap();
+        assert!(!expectations.is_synchronized(now));
+        assert_eq!(
+            expectations.status(sweep_len, now),
+            ExpectationStatus::Pending(deadline)
+        );
+
+        assert!(expectations.confirm(Some(&config(224)), Some(DspMode::Auto)));
+        assert_eq!(expectations.status(sweep_len, now), ExpectationStatus::Met);
+        assert!(!expectations.is_synchronized(now));
+
+        assert!(!expectations.confirm(Some(&config(224)), Some(DspMode::Auto)));
+        assert!(expectations.confirm(Some(&config(224)), Some(DspMode::Fast)));
+        assert_eq!(expectations.status(dsp_mode, now), ExpectationStatus::Met);
+        assert!(expectations.is_synchronized(now));
+    }
+
+    #[test]
+    fn confirm_start_stop_within_half_a_step() {
+        let mut expectations = Expectations::default();
+        let id = expectations
+            .register(
+                Expectation::StartStopAmpRange {
+                    start: Frequency::from_khz(2_400_400),
+                    stop: Frequency::from_mhz(2_500),
+                    min_amp_dbm: -120,
+                    max_amp_dbm: -10,
+                },
+                None,
+                None,
+                Instant::now() + Duration::from_secs(2),
+            )
+            .unwrap();
+
+        assert!(expectations.confirm(Some(&config(112)), None));
+        assert_eq!(
+            expectations.status(id, Instant::now()),
+            ExpectationStatus::Met
+        );
+    }
+
+    #[test]
+    fn expire_unconfirmed_expectations() {
+        let now = Instant::now();
+        let mut expectations = Expectations::default();
+        let first = expectations
+            .register(
+                Expectation::SweepLen(224),
+                None,
+                None,
+                now + Duration::from_secs(1),
+            )
+            .unwrap();
+        let second = expectations
+            .register(
+                Expectation::SweepLen(448),
+                None,
+                None,
+                now + Duration::from_secs(2),
+            )
+            .unwrap();
+        assert_eq!(
+            expectations.next_deadline(now),
+            Some(now + Duration::from_secs(1))
+        );
+
+        // Expired expectations don't keep the spectrum analyzer from being synchronized, but
+        // they're only reported as expired to the setter that's waiting for them
+        let later = now + Duration::from_secs(1);
+        assert_eq!(
+            expectations.next_deadline(later),
+            Some(now + Duration::from_secs(2))
+        );
+        assert!(!expectations.is_synchronized(later));
+        assert!(expectations.is_synchronized(now + Duration::from_secs(2)));
+        assert_eq!(
+            expectations.status(first, later),
+            ExpectationStatus::Expired
+        );
+        assert_eq!(
+            expectations.status(second, later),
+            ExpectationStatus::Pending(now + Duration::from_secs(2))
+        );
+
+        expectations.clear();
+        assert_eq!(expectations.status(second, later), ExpectationStatus::Met);
+        assert!(expectations.is_synchronized(now));
+    }
+}
@@ -0,0 +1,145 @@
+use std::{sync::Weak, thread, time::Duration};
+
+use tracing::warn;
+
+use crate::common::SerialPort;
+use crate::rf_explorer::Command;
+
+/// Detects a spectrum analyzer whose front end has frozen and keeps sending identical sweeps.
+///
+/// Real sweeps have noise at every point, so consecutive sweeps are practically never exactly the
+/// same.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FrozenSweepWatchdog {
+    /// How many consecutive identical sweeps mean the front end has frozen. Values less than 2
+    /// are treated as 2.
+    pub identical_sweeps: usize,
+    /// Whether to reboot the RF Explorer and request its config again, which restarts its sweeps,
+    /// once it has frozen.
+    pub reboot: bool,
+    /// How long to wait for the RF Explorer to reboot before requesting its config.
+    pub reboot_delay: Duration,
+}
+
+impl FrozenSweepWatchdog {
+    /// How long an RF Explorer usually takes to reboot.
+    pub const DEFAULT_REBOOT_DELAY: Duration = Duration::from_secs(5);
+}
+
+/// Counts how many consecutive sweeps have been identical.
+///
+/// Sweeps are compared by a checksum of their amplitudes, so the previous sweep doesn't need to be
+/// kept.
+#[derive(Debug, Default)]
+pub(crate) struct FrozenSweepDetector {
+    watchdog: Option<FrozenSweepWatchdog>,
+    previous_checksum: Option<u64>,
+    identical_sweeps: usize,
+}
+
+impl FrozenSweepDetector {
+    pub(crate) fn watchdog(&self) -> Option<FrozenSweepWatchdog> {
+        self.watchdog
+    }
+
+    pub(crate) fn set_watchdog(&mut self, watchdog: Option<FrozenSweepWatchdog>) {
+        *self = FrozenSweepDetector {
+            watchdog,
+            ..FrozenSweepDetector::default()
+        };
+    }
+
+    /// Returns the watchdog if the sweep is the one that makes the sweeps count as frozen.
+    ///
+    /// The watchdog is only returned once for each run of identical sweeps.
+    pub(crate) fn on_sweep(&mut self, amplitudes_dbm: &[f32]) -> Option<FrozenSweepWatchdog> {
+        let watchdog = self.watchdog?;
+        let checksum = checksum_amplitudes(amplitudes_dbm);
+        if self.previous_checksum == Some(checksum) {
+            self.identical_sweeps += 1;
+        } else {
+            self.previous_checksum = Some(checksum);
+            self.identical_sweeps = 1;
+        }
+        (self.identical_sweeps == watchdog.identical_sweeps.max(2)).then_some(watchdog)
+    }
+}
+
+/// Reboots the RF Explorer and requests its config once it has restarted, blocking while it
+/// reboots.
+///
+/// The serial port stays open while the RF Explorer reboots, so requesting its config is all
+/// that's needed to reconnect and restart its sweeps.
+pub(crate) fn reboot_and_request_config(serial_port: &Weak<SerialPort>, reboot_delay: Duration) {
+    let Some(port) = serial_port.upgrade() else {
+        return;
+    };
+    if let Err(error) = port.send_command(Command::Reboot) {
+        warn!(%error, "Failed to reboot the frozen RF Explorer");
+        return;
+    }
+    drop(port);
+
+    thread::sleep(reboot_delay);
+    if let Some(port) = serial_port.upgrade()
+        && let Err(error) = port.send_command(Command::RequestConfig)
+    {
+        warn!(%error, "Failed to request the config of the rebooted RF Explorer");
+    }
+}
+
+/// Returns an FNV-1a checksum of the amplitudes.
+///
+/// It only has to tell consecutive sweeps apart, not resist collisions, so it's much cheaper than
+/// a general-purpose hash on the reader thread.
+fn checksum_amplitudes(amplitudes_dbm: &[f32]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    amplitudes_dbm.iter().fold(
+        OFFSET_BASIS ^ amplitudes_dbm.len() as u64,
+        |checksum, amp_dbm| (checksum ^ u64::from(amp_dbm.to_bits())).wrapping_mul(PRIME),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WATCHDOG: FrozenSweepWatchdog = FrozenSweepWatchdog {
+        identical_sweeps: 3,
+        reboot: false,
+        reboot_delay: FrozenSweepWatchdog::DEFAULT_REBOOT_DELAY,
+    };
+
+    #[test]
+    fn detect_identical_sweeps_once() {
+        let mut detector = FrozenSweepDetector::default();
+        detector.set_watchdog(Some(WATCHDOG));
+        let sweep = vec![-100.5; 65_520];
+        assert_eq!(detector.on_sweep(&sweep), None);
+        assert_eq!(detector.on_sweep(&sweep), None);
+        assert_eq!(detector.on_sweep(&sweep), Some(WATCHDOG));
+        assert_eq!(detector.on_sweep(&sweep), None);
+    }
+
+    #[test]
+    fn ignore_nearly_identical_sweeps() {
+        let mut detector = FrozenSweepDetector::default();
+        detector.set_watchdog(Some(WATCHDOG));
+        let mut sweep = vec![-100.5; 65_520];
+        for i in 0..10 {
+            // Only the last point changes
+            sweep[65_519] = -100. - i as f32 * 0.5;
+            assert_eq!(detector.on_sweep(&sweep), None);
+        }
+    }
+
+    #[test]
+    fn stay_off_without_watchdog() {
+        let mut detector = FrozenSweepDetector::default();
+        for _ in 0..10 {
+            assert_eq!(detector.on_sweep(&[-100.]), None);
+        }
+    }
+}
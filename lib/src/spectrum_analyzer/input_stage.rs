@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, io};
 
 use nom::{
     Parser,
@@ -7,7 +7,7 @@ use nom::{
 };
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
-use crate::common::MessageParseError;
+use crate::common::{MessageParseError, WriteMessage};
 use crate::rf_explorer::parsers::*;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
@@ -27,7 +27,8 @@ pub enum InputStage {
 }
 
 impl InputStage {
-    pub(crate) const PREFIX: &'static [u8] = b"#a";
+    /// The bytes every input stage message starts with.
+    pub const PREFIX: &'static [u8] = b"#a";
 }
 
 impl<'a> TryFrom<&'a [u8]> for InputStage {
@@ -48,6 +49,14 @@ impl<'a> TryFrom<&'a [u8]> for InputStage {
     }
 }
 
+impl WriteMessage for InputStage {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(InputStage::PREFIX)?;
+        writer.write_all(&[u8::from(*self)])?;
+        writer.write_all(b"\r\n")
+    }
+}
+
 impl Display for InputStage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let input_stage = match self {
@@ -60,3 +69,25 @@ impl Display for InputStage {
         write!(f, "{input_stage}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_input_stage() {
+        assert_eq!(InputStage::Lna25dB.to_bytes(), b"#a2\r\n");
+        for input_stage in [
+            InputStage::Direct,
+            InputStage::Attenuator30dB,
+            InputStage::Lna25dB,
+            InputStage::Attenuator60dB,
+            InputStage::Lna12dB,
+        ] {
+            assert_eq!(
+                InputStage::try_from(input_stage.to_bytes().as_slice()),
+                Ok(input_stage)
+            );
+        }
+    }
+}
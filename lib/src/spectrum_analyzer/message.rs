@@ -1,5 +1,7 @@
+use std::io;
+
 use super::{Config, DspMode, InputStage, Model, Sweep, TrackingStatus};
-use crate::common::MessageParseError;
+use crate::common::{MessageParseError, WriteMessage};
 use crate::rf_explorer::{ScreenData, SerialNumber, SetupInfo};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -43,3 +45,18 @@ impl<'a> TryFrom<&'a [u8]> for Message {
         }
     }
 }
+
+impl WriteMessage for Message {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        match self {
+            Message::Config(config) => config.write_message(writer),
+            Message::DspMode(dsp_mode) => dsp_mode.write_message(writer),
+            Message::InputStage(input_stage) => input_stage.write_message(writer),
+            Message::ScreenData(screen_data) => screen_data.write_message(writer),
+            Message::SerialNumber(serial_number) => serial_number.write_message(writer),
+            Message::SetupInfo(setup_info) => setup_info.write_message(writer),
+            Message::Sweep(sweep) => sweep.write_message(writer),
+            Message::TrackingStatus(tracking_status) => tracking_status.write_message(writer),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+/// What a spectrum analyzer does with a sweep whose length doesn't match the config's sweep
+/// length.
+///
+/// Firmware can send a few sweeps measured with the previous sweep length while it retunes, so
+/// their points don't line up with the current config's frequencies. Sweeps received before the
+/// first config are always kept because there's nothing to compare them to.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum MismatchedSweepPolicy {
+    /// Drop the sweep.
+    #[default]
+    Drop,
+    /// Keep only the first `sweep_len` points of longer sweeps and drop shorter sweeps.
+    Truncate,
+    /// Truncate longer sweeps and pad shorter sweeps with their lowest amplitude, which is
+    /// usually the noise floor.
+    PadWithNoiseFloor,
+    /// Keep the sweep as it was received.
+    Deliver,
+}
+
+/// Counts of the sweeps whose length didn't match the config's sweep length.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct MismatchedSweepStats {
+    /// The number of sweeps that were dropped.
+    pub dropped: u64,
+    /// The number of longer sweeps that were truncated to the config's sweep length.
+    pub truncated: u64,
+    /// The number of shorter sweeps that were padded to the config's sweep length.
+    pub padded: u64,
+    /// The number of sweeps that were kept without changing their length.
+    pub delivered: u64,
+}
+
+impl MismatchedSweepPolicy {
+    /// Fits the amplitudes to `sweep_len` points according to the policy and counts what was done
+    /// in `stats`.
+    ///
+    /// Returns whether the sweep should be kept.
+    pub(crate) fn apply(
+        self,
+        amplitudes_dbm: &mut Vec<f32>,
+        sweep_len: usize,
+        stats: &mut MismatchedSweepStats,
+    ) -> bool {
+        let is_short = amplitudes_dbm.len() < sweep_len;
+        let noise_floor_dbm = amplitudes_dbm.iter().copied().reduce(f32::min);
+        match (self, noise_floor_dbm) {
+            (MismatchedSweepPolicy::Truncate | MismatchedSweepPolicy::PadWithNoiseFloor, _)
+                if !is_short =>
+            {
+                amplitudes_dbm.truncate(sweep_len);
+                stats.truncated += 1;
+                true
+            }
+            (MismatchedSweepPolicy::PadWithNoiseFloor, Some(noise_floor_dbm)) => {
+                amplitudes_dbm.resize(sweep_len, noise_floor_dbm);
+                stats.padded += 1;
+                true
+            }
+            (MismatchedSweepPolicy::Deliver, _) => {
+                stats.delivered += 1;
+                true
+            }
+            _ => {
+                stats.dropped += 1;
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_each_policy() {
+        let mut stats = MismatchedSweepStats::default();
+
+        let mut short = vec![-50., -60., -55.];
+        assert!(!MismatchedSweepPolicy::Drop.apply(&mut short, 5, &mut stats));
+        assert!(!MismatchedSweepPolicy::Truncate.apply(&mut short, 5, &mut stats));
+        assert!(MismatchedSweepPolicy::Deliver.apply(&mut short, 5, &mut stats));
+        assert_eq!(short, [-50., -60., -55.]);
+        assert!(MismatchedSweepPolicy::PadWithNoiseFloor.apply(&mut short, 5, &mut stats));
+        assert_eq!(short, [-50., -60., -55., -60., -60.]);
+
+        let mut long = vec![-50., -60., -55., -70.];
+        assert!(MismatchedSweepPolicy::Truncate.apply(&mut long, 3, &mut stats));
+        assert_eq!(long, [-50., -60., -55.]);
+        assert!(MismatchedSweepPolicy::PadWithNoiseFloor.apply(&mut long, 2, &mut stats));
+        assert_eq!(long, [-50., -60.]);
+
+        // An empty sweep has no noise floor to pad it with
+        assert!(!MismatchedSweepPolicy::PadWithNoiseFloor.apply(&mut Vec::new(), 2, &mut stats));
+
+        assert_eq!(
+            stats,
+            MismatchedSweepStats {
+                dropped: 3,
+                truncated: 2,
+                padded: 1,
+                delivered: 1,
+            }
+        );
+    }
+}
@@ -1,23 +1,81 @@
+mod accumulator;
+mod alignment;
+/// Apache Arrow conversions for recorded sweeps.
+#[cfg(feature = "arrow")]
+pub mod arrow;
+mod auto_input_stage;
+mod auto_range;
+mod cached_identity;
+mod capabilities;
+mod change_detector;
 mod command;
 mod config;
+mod corrupt_sweep;
 mod dsp_mode;
+mod event_log;
+mod expectations;
+mod frozen_sweep;
 mod input_stage;
 mod message;
+mod mismatched_sweep;
 mod model;
 mod parsers;
+mod pending_change;
+mod recording;
+mod resample;
+mod restored_config;
+mod retained_sweeps;
 mod rf_explorer;
 mod setup_info;
+mod smoothing;
 mod sweep;
+mod sweep_csv;
+mod sweep_meta;
+mod sweep_rate;
+mod sweep_receiver;
+mod trace_accumulator;
 mod tracking_status;
 mod wifi_band;
+mod zoom;
 
-pub(crate) use command::Command;
+pub use accumulator::{AggregateFrame, LongTermAccumulator, SeriesKey};
+pub use alignment::AlignmentReport;
+pub use auto_input_stage::{AutoInputStage, InputStageTransition};
+pub use cached_identity::CachedIdentity;
+pub use capabilities::Capabilities;
+pub use change_detector::{
+    ChangeDetector, ChangeDetectorConfig, ChangeMetric, ChangeReport, spectral_distance,
+    sweep_correlation,
+};
+pub use command::Command;
 pub use config::{CalcMode, Config, Mode};
+pub(crate) use corrupt_sweep::{CorruptionDetector, CorruptionVerdict};
+pub use corrupt_sweep::{CorruptionStats, HeuristicConfig};
 pub use dsp_mode::DspMode;
+pub use event_log::Event;
+pub(crate) use event_log::EventLog;
+pub(crate) use expectations::{Expectation, ExpectationId, ExpectationStatus, Expectations};
+pub use frozen_sweep::FrozenSweepWatchdog;
 pub use input_stage::InputStage;
 pub(crate) use message::Message;
+pub use mismatched_sweep::{MismatchedSweepPolicy, MismatchedSweepStats};
 pub use model::Model;
+pub(crate) use pending_change::DebouncedConfig;
+pub use pending_change::PendingChange;
+pub(crate) use recording::RecordQueue;
+pub use recording::{
+    CsvDirSink, MemorySink, Record, RecordSink, RecordingSession, RecordingSummary, read_csv_dir,
+};
+pub use resample::ResampleMethod;
+pub use restored_config::{ClampedSetting, RestoredConfig};
+pub(crate) use retained_sweeps::RetainedSweeps;
 pub use rf_explorer::SpectrumAnalyzer;
-pub(crate) use sweep::Sweep;
+pub use smoothing::SmoothingMethod;
+pub(crate) use sweep::PartialSweepDecoder;
+pub use sweep::Sweep;
+pub use sweep_meta::{SweepEncoding, SweepMeta};
+pub(crate) use sweep_receiver::SweepSubscribers;
+pub use sweep_receiver::{BufferedSweep, SweepReceiver};
+pub use trace_accumulator::{SavedTrace, TraceAccumulator};
 pub use tracking_status::TrackingStatus;
-pub use wifi_band::WifiBand;
+pub use wifi_band::{WifiBand, WifiChannel};
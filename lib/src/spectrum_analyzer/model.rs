@@ -1,10 +1,10 @@
-use std::fmt::Display;
+use std::{fmt::Display, ops::RangeInclusive};
 
-use num_enum::TryFromPrimitive;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::Frequency;
 
-#[derive(Debug, Copy, Clone, TryFromPrimitive, Eq, PartialEq, Default)]
+#[derive(Debug, Copy, Clone, TryFromPrimitive, IntoPrimitive, Eq, PartialEq, Default)]
 #[repr(u8)]
 /// RF Explorer spectrum analyzer model.
 pub enum Model {
@@ -115,6 +115,11 @@ impl Model {
         .into()
     }
 
+    /// Returns the model's supported input frequency range.
+    pub fn freq_range(&self) -> RangeInclusive<Frequency> {
+        self.min_freq()..=self.max_freq()
+    }
+
     /// Returns the model's minimum supported sweep span.
     pub fn min_span(&self) -> Frequency {
         match self {
@@ -0,0 +1,195 @@
+use std::{
+    io,
+    sync::{Arc, Condvar, Mutex, Weak},
+};
+
+use super::{Expectation, ExpectationId, rf_explorer::MessageContainer};
+use crate::{Error, Frequency, Result};
+
+/// A config change requested with a debounced setter, like
+/// [`SpectrumAnalyzer::set_center_span_debounced`](crate::SpectrumAnalyzer::set_center_span_debounced).
+///
+/// Changes requested before the previous one's debounce duration elapsed are coalesced into a
+/// single command, so the handles of every coalesced change complete together once the RF
+/// Explorer confirms the latest values.
+#[derive(Debug)]
+pub struct PendingChange {
+    batch: Arc<Batch>,
+    messages: Weak<MessageContainer>,
+}
+
+impl PendingChange {
+    /// Waits until the command has been sent and the RF Explorer has confirmed it.
+    ///
+    /// Returns [`Error::InvalidOperation`] if the spectrum analyzer was dropped before the command
+    /// was sent, or [`Error::TimedOut`] if the RF Explorer didn't confirm the change in time.
+    pub fn wait(&self) -> Result<()> {
+        let outcome = {
+            let outcome = self.batch.outcome.lock().unwrap();
+            let outcome = self
+                .batch
+                .condvar
+                .wait_while(outcome, |outcome| outcome.is_none())
+                .unwrap();
+            outcome.clone().expect("The change should have an outcome")
+        };
+
+        let sent = match outcome {
+            Outcome::Sent(sent) => sent,
+            // Every waiter gets its own copy of the error, which can't be cloned
+            Outcome::Failed(error) => {
+                return Err(match &*error {
+                    Error::Io(error) => io::Error::new(error.kind(), error.to_string()).into(),
+                    Error::InvalidOperation(message) => Error::InvalidOperation(message.clone()),
+                    error => Error::InvalidOperation(error.to_string()),
+                });
+            }
+            Outcome::Canceled => return Err(canceled()),
+        };
+        let Some((id, expectation)) = sent else {
+            return Ok(());
+        };
+        let messages = self.messages.upgrade().ok_or_else(canceled)?;
+        messages.wait_for_expectation(id, expectation)
+    }
+
+    /// Returns `true` once the command has been sent, failed to send, or been canceled.
+    pub fn is_done(&self) -> bool {
+        self.batch.outcome.lock().unwrap().is_some()
+    }
+}
+
+fn canceled() -> Error {
+    Error::InvalidOperation(
+        "The spectrum analyzer was dropped before the change was sent".to_string(),
+    )
+}
+
+#[derive(Debug, Default)]
+struct Batch {
+    outcome: Mutex<Option<Outcome>>,
+    condvar: Condvar,
+}
+
+#[derive(Debug, Clone)]
+enum Outcome {
+    /// The command was sent and the expectation is `None` if the config already contained it.
+    Sent(Option<(ExpectationId, Expectation)>),
+    Failed(Arc<Error>),
+    Canceled,
+}
+
+impl Batch {
+    fn finish(&self, outcome: Outcome) {
+        self.outcome.lock().unwrap().get_or_insert(outcome);
+        self.condvar.notify_all();
+    }
+}
+
+/// The latest values requested with the debounced setters that haven't been sent yet.
+///
+/// Dropping it before it's sent, like when the spectrum analyzer is dropped, cancels its changes.
+#[derive(Debug)]
+pub(crate) struct DebouncedConfig {
+    pub(crate) start: Frequency,
+    pub(crate) stop: Frequency,
+    pub(crate) min_amp_dbm: i16,
+    pub(crate) max_amp_dbm: i16,
+    /// Whether the start and stop frequencies were requested, rather than kept from the config.
+    pub(crate) start_stop_requested: bool,
+    batch: Arc<Batch>,
+}
+
+impl DebouncedConfig {
+    pub(crate) fn new(
+        start: Frequency,
+        stop: Frequency,
+        min_amp_dbm: i16,
+        max_amp_dbm: i16,
+    ) -> Self {
+        Self {
+            start,
+            stop,
+            min_amp_dbm,
+            max_amp_dbm,
+            start_stop_requested: false,
+            batch: Arc::default(),
+        }
+    }
+
+    /// Returns a handle that completes when these values are confirmed.
+    pub(crate) fn pending_change(&self, messages: Weak<MessageContainer>) -> PendingChange {
+        PendingChange {
+            batch: self.batch.clone(),
+            messages,
+        }
+    }
+
+    pub(crate) fn expectation(&self) -> Expectation {
+        Expectation::StartStopAmpRange {
+            start: self.start,
+            stop: self.stop,
+            min_amp_dbm: self.min_amp_dbm,
+            max_amp_dbm: self.max_amp_dbm,
+        }
+    }
+
+    pub(crate) fn sent(self, expectation: Option<ExpectationId>) {
+        let expectation = expectation.map(|id| (id, self.expectation()));
+        self.batch.finish(Outcome::Sent(expectation));
+    }
+
+    pub(crate) fn failed(self, error: Error) {
+        self.batch.finish(Outcome::Failed(Arc::new(error)));
+    }
+}
+
+impl Drop for DebouncedConfig {
+    fn drop(&mut self) {
+        self.batch.finish(Outcome::Canceled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    fn debounced_config() -> DebouncedConfig {
+        DebouncedConfig::new(Frequency::from_mhz(100), Frequency::from_mhz(200), -100, 0)
+    }
+
+    #[test]
+    fn complete_every_coalesced_change() {
+        let debounced = debounced_config();
+        let first = debounced.pending_change(Weak::new());
+        let second = debounced.pending_change(Weak::new());
+        assert!(!first.is_done());
+
+        let waiter = thread::spawn(move || first.wait());
+        thread::sleep(Duration::from_millis(10));
+        debounced.sent(None);
+        assert!(waiter.join().unwrap().is_ok());
+        assert!(second.is_done());
+        assert!(second.wait().is_ok());
+    }
+
+    #[test]
+    fn cancel_changes_that_are_never_sent() {
+        let debounced = debounced_config();
+        let change = debounced.pending_change(Weak::new());
+        drop(debounced);
+        assert!(matches!(change.wait(), Err(Error::InvalidOperation(_))));
+    }
+
+    #[test]
+    fn report_failures_to_send() {
+        let debounced = debounced_config();
+        let change = debounced.pending_change(Weak::new());
+        debounced.failed(io::Error::from(io::ErrorKind::TimedOut).into());
+        assert!(
+            matches!(change.wait(), Err(Error::Io(error)) if error.kind() == io::ErrorKind::TimedOut)
+        );
+    }
+}
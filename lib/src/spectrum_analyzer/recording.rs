@@ -0,0 +1,755 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+
+use super::{Config, Event, SpectrumAnalyzer};
+use crate::{Frequency, LinkEvent, LinkHealth, Result, ScreenData, rf_explorer::ScreenDataCache};
+
+/// A single timestamped entry captured by a [`RecordingSession`].
+///
+/// New kinds of records can be added as more of the spectrum analyzer's messages are recorded.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Record {
+    /// The spectrum analyzer's configuration changed.
+    ///
+    /// Every sweep recorded after a `Config` record was measured using that configuration.
+    Config {
+        /// The time at which the configuration was received.
+        timestamp: DateTime<Utc>,
+        /// The new configuration.
+        config: Config,
+    },
+    /// The spectrum analyzer measured a sweep.
+    Sweep {
+        /// The time at which the sweep was received.
+        timestamp: DateTime<Utc>,
+        /// The start frequency of the sweep.
+        start_freq: Frequency,
+        /// The stop frequency of the sweep.
+        stop_freq: Frequency,
+        /// The amplitudes of the sweep in dBm.
+        amplitudes_dbm: Vec<f32>,
+    },
+    /// The spectrum analyzer captured its screen.
+    ScreenData(ScreenData),
+    /// An external event was recorded with
+    /// [`SpectrumAnalyzer::annotate_event`](crate::SpectrumAnalyzer::annotate_event).
+    Event(Event),
+    /// A snapshot of the serial link's health, recorded every
+    /// [`RecordingSession::LINK_HEALTH_INTERVAL`].
+    LinkHealth {
+        /// The time at which the snapshot was taken.
+        timestamp: DateTime<Utc>,
+        /// The link's health, as returned by
+        /// [`SpectrumAnalyzer::link_health`](crate::SpectrumAnalyzer::link_health).
+        link_health: LinkHealth,
+    },
+}
+
+impl Record {
+    /// The time at which the record was received.
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            Record::Config { timestamp, .. }
+            | Record::Sweep { timestamp, .. }
+            | Record::LinkHealth { timestamp, .. } => *timestamp,
+            Record::ScreenData(screen_data) => screen_data.timestamp(),
+            Record::Event(event) => event.timestamp,
+        }
+    }
+}
+
+/// Destination for the records captured by a [`RecordingSession`].
+pub trait RecordSink: Send + 'static {
+    /// Writes a single record to the sink.
+    fn write_record(&mut self, record: &Record) -> io::Result<()>;
+
+    /// Called once after the final record has been written.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A [`RecordSink`] that keeps every record in memory.
+///
+/// Clones share the same records, so keep a clone to read the records after the sink has been
+/// moved into a [`RecordingSession`].
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    records: Arc<Mutex<Vec<Record>>>,
+}
+
+impl MemorySink {
+    /// Creates an empty `MemorySink`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of the records written to the sink.
+    pub fn records(&self) -> Vec<Record> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+impl RecordSink for MemorySink {
+    fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        self.records.lock().unwrap().push(record.clone());
+        Ok(())
+    }
+}
+
+/// A [`RecordSink`] that writes records to CSV files in a directory.
+///
+/// Configs, sweeps, screen data, events, and link health snapshots are written to `configs.csv`,
+/// `sweeps.csv`, `screen_data.csv`, `events.csv`, and `link_health.csv` respectively. Each row
+/// starts with an RFC 3339 timestamp. Line breaks in event labels are written as spaces.
+pub struct CsvDirSink {
+    configs: BufWriter<File>,
+    sweeps: BufWriter<File>,
+    screen_data: BufWriter<File>,
+    events: BufWriter<File>,
+    link_health: BufWriter<File>,
+}
+
+impl CsvDirSink {
+    /// Creates the directory (if needed) and the CSV files inside of it.
+    pub fn create(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let mut configs = BufWriter::new(File::create(dir.join("configs.csv"))?);
+        writeln!(
+            configs,
+            "timestamp,start_hz,stop_hz,step_hz,sweep_len,min_amp_dbm,max_amp_dbm,rbw_hz,amp_offset_db,expansion_radio_active"
+        )?;
+
+        let mut sweeps = BufWriter::new(File::create(dir.join("sweeps.csv"))?);
+        writeln!(sweeps, "timestamp,start_hz,stop_hz,amplitudes_dbm")?;
+
+        let mut screen_data = BufWriter::new(File::create(dir.join("screen_data.csv"))?);
+        writeln!(screen_data, "timestamp,pixels")?;
+
+        let mut events = BufWriter::new(File::create(dir.join("events.csv"))?);
+        writeln!(events, "timestamp,label")?;
+
+        let mut link_health = BufWriter::new(File::create(dir.join("link_health.csv"))?);
+        writeln!(
+            link_health,
+            "timestamp,bytes_per_sec,timeouts,parse_errors,io_errors,since_last_message_ms,diagnosis"
+        )?;
+
+        Ok(Self {
+            configs,
+            sweeps,
+            screen_data,
+            events,
+            link_health,
+        })
+    }
+}
+
+impl RecordSink for CsvDirSink {
+    fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        let timestamp = record.timestamp().to_rfc3339();
+        match record {
+            Record::Config { config, .. } => writeln!(
+                self.configs,
+                "{timestamp},{},{},{},{},{},{},{},{},{}",
+                config.start_freq.as_hz(),
+                config.stop_freq.as_hz(),
+                config.step_size.as_hz(),
+                config.sweep_len,
+                config.min_amp_dbm,
+                config.max_amp_dbm,
+                config
+                    .rbw
+                    .map(|rbw| rbw.as_hz().to_string())
+                    .unwrap_or_default(),
+                config
+                    .amp_offset_db
+                    .map(|offset| offset.to_string())
+                    .unwrap_or_default(),
+                config.is_expansion_radio_module_active,
+            ),
+            Record::Sweep {
+                start_freq,
+                stop_freq,
+                amplitudes_dbm,
+                ..
+            } => {
+                write!(
+                    self.sweeps,
+                    "{timestamp},{},{}",
+                    start_freq.as_hz(),
+                    stop_freq.as_hz()
+                )?;
+                for amp in amplitudes_dbm {
+                    write!(self.sweeps, ",{amp}")?;
+                }
+                writeln!(self.sweeps)
+            }
+            Record::ScreenData(screen_data) => {
+                // Store the screen as one row of '0' and '1' characters per pixel row
+                write!(self.screen_data, "{timestamp},")?;
+                for y in 0..ScreenData::HEIGHT_PX {
+                    if y > 0 {
+                        write!(self.screen_data, "|")?;
+                    }
+                    for x in 0..ScreenData::WIDTH_PX {
+                        let pixel = if screen_data.get_pixel(x, y) {
+                            '1'
+                        } else {
+                            '0'
+                        };
+                        write!(self.screen_data, "{pixel}")?;
+                    }
+                }
+                writeln!(self.screen_data)
+            }
+            // The label is the last field, so commas in it don't need to be escaped
+            Record::Event(event) => writeln!(
+                self.events,
+                "{timestamp},{}",
+                event.label.replace(['\r', '\n'], " ")
+            ),
+            Record::LinkHealth { link_health, .. } => writeln!(
+                self.link_health,
+                "{timestamp},{},{},{},{},{},{}",
+                link_health.bytes_per_sec,
+                link_health.read_errors.timeouts,
+                link_health.read_errors.parse_errors,
+                link_health.read_errors.io_errors,
+                link_health
+                    .since_last_message
+                    .map(|since| since.as_millis().to_string())
+                    .unwrap_or_default(),
+                link_health.diagnosis,
+            ),
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.configs.flush()?;
+        self.sweeps.flush()?;
+        self.screen_data.flush()?;
+        self.events.flush()?;
+        self.link_health.flush()
+    }
+}
+
+/// Reads the configs, sweeps, and events that a [`CsvDirSink`] wrote to a directory, oldest
+/// first.
+///
+/// Only the settings written to `configs.csv` are restored, so the other fields of each config,
+/// like its minimum and maximum frequencies, are left at their defaults. Screen data and link
+/// health snapshots aren't read,
+/// and recordings made before events were recorded don't have an `events.csv` to read. Returns
+/// `io::ErrorKind::InvalidData` if a row can't be parsed.
+pub fn read_csv_dir(dir: impl AsRef<Path>) -> io::Result<Vec<Record>> {
+    let dir = dir.as_ref();
+    let mut records = Vec::new();
+    for_each_csv_row(&dir.join("configs.csv"), |fields| {
+        let [
+            timestamp,
+            start_hz,
+            stop_hz,
+            step_hz,
+            sweep_len,
+            min_amp_dbm,
+            max_amp_dbm,
+            rbw_hz,
+            amp_offset_db,
+            expansion_radio_active,
+        ] = fields
+        else {
+            return None;
+        };
+        let start_freq = Frequency::from_hz(start_hz.parse().ok()?);
+        let stop_freq = Frequency::from_hz(stop_hz.parse().ok()?);
+        let timestamp = parse_timestamp(timestamp)?;
+        records.push(Record::Config {
+            timestamp,
+            config: Config {
+                start_freq,
+                stop_freq,
+                step_size: Frequency::from_hz(step_hz.parse().ok()?),
+                center_freq: (start_freq + stop_freq) / 2,
+                span: stop_freq - start_freq,
+                max_amp_dbm: max_amp_dbm.parse().ok()?,
+                min_amp_dbm: min_amp_dbm.parse().ok()?,
+                sweep_len: sweep_len.parse().ok()?,
+                is_expansion_radio_module_active: expansion_radio_active.parse().ok()?,
+                rbw: parse_optional(rbw_hz)?.map(Frequency::from_hz),
+                amp_offset_db: parse_optional(amp_offset_db)?,
+                timestamp,
+                ..Config::default()
+            },
+        });
+        Some(())
+    })?;
+    for_each_csv_row(&dir.join("sweeps.csv"), |fields| {
+        let [timestamp, start_hz, stop_hz, amplitudes_dbm @ ..] = fields else {
+            return None;
+        };
+        records.push(Record::Sweep {
+            timestamp: parse_timestamp(timestamp)?,
+            start_freq: Frequency::from_hz(start_hz.parse().ok()?),
+            stop_freq: Frequency::from_hz(stop_hz.parse().ok()?),
+            amplitudes_dbm: amplitudes_dbm
+                .iter()
+                .map(|amp_dbm| amp_dbm.parse().ok())
+                .collect::<Option<_>>()?,
+        });
+        Some(())
+    })?;
+    let events_path = dir.join("events.csv");
+    if events_path.exists() {
+        for_each_csv_row(&events_path, |fields| {
+            let [timestamp, label @ ..] = fields else {
+                return None;
+            };
+            records.push(Record::Event(Event {
+                timestamp: parse_timestamp(timestamp)?,
+                label: label.join(","),
+            }));
+            Some(())
+        })?;
+    }
+
+    // The sort is stable, so a config stays ahead of the sweeps received at the same time
+    records.sort_by_key(Record::timestamp);
+    Ok(records)
+}
+
+/// Calls `parse_row` with the fields of each row after the header, which returns `None` if the
+/// row is invalid.
+fn for_each_csv_row(
+    path: &Path,
+    mut parse_row: impl FnMut(&[&str]) -> Option<()>,
+) -> io::Result<()> {
+    for (i, line) in BufReader::new(File::open(path)?)
+        .lines()
+        .enumerate()
+        .skip(1)
+    {
+        let line = line?;
+        let fields: Vec<&str> = line.split(',').collect();
+        if parse_row(&fields).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid row on line {} of {}", i + 1, path.display()),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn parse_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+/// Parses a field that's empty when the value wasn't reported.
+fn parse_optional<T: std::str::FromStr>(field: &str) -> Option<Option<T>> {
+    if field.is_empty() {
+        Some(None)
+    } else {
+        field.parse().ok().map(Some)
+    }
+}
+
+/// Summary of a [`RecordingSession`] returned by [`RecordingSession::stop`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordingSummary {
+    /// The time at which the recording started.
+    pub started_at: DateTime<Utc>,
+    /// The time at which the recording stopped.
+    pub stopped_at: DateTime<Utc>,
+    /// The number of configs written to the sink.
+    pub configs_recorded: usize,
+    /// The number of sweeps written to the sink.
+    pub sweeps_recorded: usize,
+    /// The number of screen captures written to the sink.
+    pub screen_data_recorded: usize,
+    /// The number of events written to the sink.
+    pub events_recorded: usize,
+    /// The number of link health snapshots written to the sink.
+    pub link_health_recorded: usize,
+    /// The number of sweeps dropped because the sink could not keep up.
+    pub sweeps_dropped: usize,
+    /// The number of screen captures dropped because the sink could not keep up.
+    pub screen_data_dropped: usize,
+}
+
+/// Records every sweep, config change, and screen capture from a [`SpectrumAnalyzer`], along with
+/// a snapshot of the serial link's health every
+/// [`LINK_HEALTH_INTERVAL`](Self::LINK_HEALTH_INTERVAL).
+///
+/// Records are queued by the spectrum analyzer's reader and written to the sink on a separate
+/// thread, which also takes the link health snapshots. If the sink falls behind and the queue fills up, queued screen data is dropped first
+/// to make room and sweeps are only dropped when there is no screen data left to drop. Config
+/// changes and events are never dropped.
+///
+/// Screen data is only recorded while dump screen is enabled with
+/// [`SpectrumAnalyzer::enable_dump_screen`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use rfe::SpectrumAnalyzer;
+/// use rfe::spectrum_analyzer::{CsvDirSink, RecordingSession};
+///
+/// let rfe = SpectrumAnalyzer::connect().expect("RF Explorer should be connected");
+/// let session = RecordingSession::start(&rfe, CsvDirSink::create("recording")?)?;
+/// std::thread::sleep(std::time::Duration::from_secs(10));
+/// let summary = session.stop()?;
+/// println!("Recorded {} sweeps", summary.sweeps_recorded);
+/// # Ok::<(), rfe::Error>(())
+/// ```
+pub struct RecordingSession {
+    queue: Arc<RecordQueue>,
+    writer_thread_handle: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl RecordingSession {
+    // The maximum number of records waiting to be written before records start being dropped
+    const QUEUE_CAPACITY: usize = 256;
+
+    /// How often a snapshot of the serial link's health is recorded.
+    pub const LINK_HEALTH_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Starts recording from the spectrum analyzer into the sink.
+    ///
+    /// Returns `Error::InvalidOperation` if the spectrum analyzer is already being recorded.
+    pub fn start(rfe: &SpectrumAnalyzer, mut sink: impl RecordSink) -> Result<Self> {
+        let queue = Arc::new(RecordQueue::new(Self::QUEUE_CAPACITY));
+        rfe.set_record_queue(queue.clone())?;
+
+        // Begin the recording with the current config so every sweep has a known config
+        if let Some(config) = rfe.config().clone() {
+            queue.push(Record::Config {
+                timestamp: config.timestamp,
+                config,
+            });
+        }
+
+        let writer_queue = queue.clone();
+        let link_health = rfe.link_health_sampler();
+        let writer_thread_handle = thread::spawn(move || {
+            let mut result = Ok(());
+            let mut next_link_health_at = Instant::now() + Self::LINK_HEALTH_INTERVAL;
+            loop {
+                let record = match writer_queue.pop_until(next_link_health_at) {
+                    Pop::Record(record) => record,
+                    Pop::TimedOut => {
+                        next_link_health_at += Self::LINK_HEALTH_INTERVAL;
+                        // The sampler returns None once the spectrum analyzer has been dropped
+                        let Some(link_health) = link_health() else {
+                            continue;
+                        };
+                        Record::LinkHealth {
+                            timestamp: Utc::now(),
+                            link_health,
+                        }
+                    }
+                    Pop::Closed => break,
+                };
+                if result.is_err() {
+                    continue;
+                }
+                result = sink.write_record(&record);
+                if result.is_ok() {
+                    writer_queue.count_recorded(&record);
+                }
+            }
+            result.and_then(|_| sink.finish())
+        });
+
+        Ok(Self {
+            queue,
+            writer_thread_handle: Some(writer_thread_handle),
+        })
+    }
+
+    /// Stops recording, waits for queued records to be written, and returns a summary.
+    ///
+    /// Returns the first error encountered while writing to the sink, if any.
+    pub fn stop(mut self) -> Result<RecordingSummary> {
+        self.stop_internal()?;
+        Ok(self.queue.summary())
+    }
+
+    fn stop_internal(&mut self) -> io::Result<()> {
+        self.queue.close();
+        match self.writer_thread_handle.take() {
+            Some(handle) => handle
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("The recording thread panicked"))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for RecordingSession {
+    fn drop(&mut self) {
+        let _ = self.stop_internal();
+    }
+}
+
+/// Bounded queue shared between a spectrum analyzer's reader and a recording session's writer.
+#[derive(Debug)]
+pub(crate) struct RecordQueue {
+    state: Mutex<RecordQueueState>,
+    condvar: Condvar,
+    capacity: usize,
+}
+
+/// What [`RecordQueue::pop_until`] found.
+enum Pop {
+    Record(Record),
+    TimedOut,
+    Closed,
+}
+
+#[derive(Debug, Default)]
+struct RecordQueueState {
+    records: VecDeque<Record>,
+    is_closed: bool,
+    summary: RecordingSummary,
+}
+
+impl RecordQueue {
+    fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(RecordQueueState {
+                summary: RecordingSummary {
+                    started_at: Utc::now(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            condvar: Condvar::new(),
+            capacity,
+        }
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().is_closed
+    }
+
+    /// Returns an estimate of the memory used by the queued records, in bytes.
+    pub(crate) fn memory_usage_estimate(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .records
+            .iter()
+            .map(|record| match record {
+                Record::Sweep { amplitudes_dbm, .. } => {
+                    size_of::<Record>() + amplitudes_dbm.capacity() * size_of::<f32>()
+                }
+                Record::ScreenData(_) => size_of::<Record>() + ScreenDataCache::SCREEN_DATA_LEN,
+                Record::LinkHealth { link_health, .. } => {
+                    size_of::<Record>() + link_health.evidence.capacity() * size_of::<LinkEvent>()
+                }
+                Record::Config { .. } | Record::Event(_) => size_of::<Record>(),
+            })
+            .sum()
+    }
+
+    pub(crate) fn push(&self, record: Record) {
+        let mut state = self.state.lock().unwrap();
+        if state.is_closed {
+            return;
+        }
+
+        if state.records.len() >= self.capacity {
+            // Make room by dropping the oldest queued screen data before dropping any sweeps
+            if let Some(index) = state
+                .records
+                .iter()
+                .position(|record| matches!(record, Record::ScreenData(_)))
+            {
+                state.records.remove(index);
+                state.summary.screen_data_dropped += 1;
+            } else {
+                match record {
+                    Record::ScreenData(_) => {
+                        state.summary.screen_data_dropped += 1;
+                        return;
+                    }
+                    Record::Sweep { .. } => {
+                        state.summary.sweeps_dropped += 1;
+                        return;
+                    }
+                    // Link health snapshots are taken by the writer, so they aren't queued
+                    Record::LinkHealth { .. } => return,
+                    // Config records mark the boundaries between sweeps measured with different
+                    // settings and events can't be measured again, so they are always queued
+                    Record::Config { .. } | Record::Event(_) => (),
+                }
+            }
+        }
+
+        state.records.push_back(record);
+        self.condvar.notify_one();
+    }
+
+    /// Waits for the next record until the deadline, and returns every queued record before
+    /// reporting that the queue is closed.
+    fn pop_until(&self, deadline: Instant) -> Pop {
+        let state = self.state.lock().unwrap();
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let (mut state, _) = self
+            .condvar
+            .wait_timeout_while(state, timeout, |state| {
+                state.records.is_empty() && !state.is_closed
+            })
+            .unwrap();
+        match state.records.pop_front() {
+            Some(record) => Pop::Record(record),
+            None if state.is_closed => Pop::Closed,
+            None => Pop::TimedOut,
+        }
+    }
+
+    fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !state.is_closed {
+            state.is_closed = true;
+            state.summary.stopped_at = Utc::now();
+        }
+        self.condvar.notify_all();
+    }
+
+    fn count_recorded(&self, record: &Record) {
+        let summary = &mut self.state.lock().unwrap().summary;
+        match record {
+            Record::Config { .. } => summary.configs_recorded += 1,
+            Record::Sweep { .. } => summary.sweeps_recorded += 1,
+            Record::ScreenData(_) => summary.screen_data_recorded += 1,
+            Record::Event(_) => summary.events_recorded += 1,
+            Record::LinkHealth { .. } => summary.link_health_recorded += 1,
+        }
+    }
+
+    fn summary(&self) -> RecordingSummary {
+        self.state.lock().unwrap().summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sweep_record() -> Record {
+        Record::Sweep {
+            timestamp: Utc::now(),
+            start_freq: Frequency::from_mhz(100),
+            stop_freq: Frequency::from_mhz(200),
+            amplitudes_dbm: vec![-100.; 112],
+        }
+    }
+
+    fn screen_data_record() -> Record {
+        let mut bytes = ScreenData::PREFIX.to_vec();
+        bytes.extend([0; 1024]);
+        Record::ScreenData(ScreenData::try_from(bytes.as_slice()).unwrap())
+    }
+
+    #[test]
+    fn drop_screen_data_before_sweeps() {
+        let queue = RecordQueue::new(2);
+        queue.push(screen_data_record());
+        queue.push(sweep_record());
+        queue.push(sweep_record());
+        queue.push(sweep_record());
+        queue.push(screen_data_record());
+
+        let summary = queue.summary();
+        assert_eq!(summary.screen_data_dropped, 2);
+        assert_eq!(summary.sweeps_dropped, 1);
+        assert_eq!(queue.state.lock().unwrap().records.len(), 2);
+    }
+
+    #[test]
+    fn never_drop_configs() {
+        let queue = RecordQueue::new(1);
+        queue.push(sweep_record());
+        queue.push(Record::Config {
+            timestamp: Utc::now(),
+            config: Config::default(),
+        });
+
+        let state = queue.state.lock().unwrap();
+        assert_eq!(state.records.len(), 2);
+        assert!(matches!(state.records[1], Record::Config { .. }));
+    }
+
+    #[test]
+    fn read_records_written_to_csv_dir() {
+        let dir = std::env::temp_dir().join(format!("rfe-csv-dir-{}", std::process::id()));
+        let start = Utc::now();
+        let config = Config {
+            start_freq: Frequency::from_mhz(100),
+            stop_freq: Frequency::from_mhz(200),
+            step_size: Frequency::from_khz(900_900),
+            center_freq: Frequency::from_mhz(150),
+            span: Frequency::from_mhz(100),
+            min_amp_dbm: -120,
+            max_amp_dbm: -10,
+            sweep_len: 112,
+            rbw: Some(Frequency::from_khz(600)),
+            timestamp: start,
+            ..Config::default()
+        };
+        let records = [
+            Record::Config {
+                timestamp: start,
+                config: config.clone(),
+            },
+            Record::Sweep {
+                timestamp: start + chrono::Duration::milliseconds(250),
+                start_freq: config.start_freq,
+                stop_freq: config.stop_freq,
+                amplitudes_dbm: vec![-100.5; 112],
+            },
+            screen_data_record(),
+            Record::Event(Event {
+                timestamp: start + chrono::Duration::milliseconds(100),
+                label: "tx on, channel 3".to_string(),
+            }),
+        ];
+        let mut sink = CsvDirSink::create(&dir).unwrap();
+        for record in &records {
+            sink.write_record(record).unwrap();
+        }
+        sink.finish().unwrap();
+
+        let read_records = read_csv_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        // RFC 3339 timestamps keep every digit of the time, so the records round-trip exactly
+        assert_eq!(
+            read_records,
+            [records[0].clone(), records[3].clone(), records[1].clone()]
+        );
+    }
+
+    #[test]
+    fn ignore_records_after_close() {
+        let queue = RecordQueue::new(4);
+        queue.close();
+        queue.push(sweep_record());
+        assert!(matches!(queue.pop_until(Instant::now()), Pop::Closed));
+    }
+}
@@ -0,0 +1,279 @@
+use std::fmt::Display;
+
+use super::Config;
+use crate::Frequency;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+/// Method used to resample the amplitudes of a sweep onto a different frequency grid.
+pub enum ResampleMethod {
+    /// Use the amplitude of the sweep point closest to each grid point.
+    ///
+    /// Each sweep point covers half a step on either side of it, including beyond the first and
+    /// last points.
+    #[default]
+    NearestBin,
+    /// Linearly interpolate between the two sweep points on either side of each grid point.
+    ///
+    /// Only grid points between the first and last sweep points are covered.
+    LinearInterpolation,
+}
+
+impl ResampleMethod {
+    /// Resamples the amplitudes of a sweep measured with `config` onto a grid of `grid_len`
+    /// points starting at `grid_start` and spaced `grid_step` apart.
+    ///
+    /// Grid points that the sweep doesn't cover are `NaN`, so they can be told apart from
+    /// measured amplitudes and skipped when sweeps from different configs are combined.
+    pub fn apply(
+        &self,
+        amplitudes_dbm: &[f32],
+        config: &Config,
+        grid_start: Frequency,
+        grid_step: Frequency,
+        grid_len: usize,
+    ) -> Vec<f32> {
+        self.resample(
+            amplitudes_dbm,
+            config.start_freq.as_hz_f64(),
+            config.step_size.as_hz_f64(),
+            grid_start,
+            grid_step,
+            grid_len,
+        )
+    }
+
+    /// Resamples amplitudes measured from `start_hz` in steps of `step_hz` onto the grid.
+    pub(crate) fn resample(
+        &self,
+        amplitudes_dbm: &[f32],
+        start_hz: f64,
+        step_hz: f64,
+        grid_start: Frequency,
+        grid_step: Frequency,
+        grid_len: usize,
+    ) -> Vec<f32> {
+        (0..grid_len)
+            .map(|i| {
+                let freq_hz = grid_start.as_hz_f64() + grid_step.as_hz_f64() * i as f64;
+                self.amplitude_at(amplitudes_dbm, start_hz, step_hz, freq_hz)
+            })
+            .collect()
+    }
+
+    fn amplitude_at(
+        &self,
+        amplitudes_dbm: &[f32],
+        start_hz: f64,
+        step_hz: f64,
+        freq_hz: f64,
+    ) -> f32 {
+        let Some(last_index) = amplitudes_dbm.len().checked_sub(1) else {
+            return f32::NAN;
+        };
+        // A sweep without a step only covers its start frequency
+        if step_hz <= 0. {
+            return if freq_hz == start_hz {
+                amplitudes_dbm[0]
+            } else {
+                f32::NAN
+            };
+        }
+
+        // The grid point's position in the sweep, measured in sweep points
+        let position = (freq_hz - start_hz) / step_hz;
+        match self {
+            ResampleMethod::NearestBin => {
+                if position < -0.5 || position > last_index as f64 + 0.5 {
+                    return f32::NAN;
+                }
+                amplitudes_dbm[(position.round().max(0.) as usize).min(last_index)]
+            }
+            ResampleMethod::LinearInterpolation => {
+                if position < 0. || position > last_index as f64 {
+                    return f32::NAN;
+                }
+                let below = position.floor() as usize;
+                let above = (below + 1).min(last_index);
+                let fraction = (position - below as f64) as f32;
+                amplitudes_dbm[below] + (amplitudes_dbm[above] - amplitudes_dbm[below]) * fraction
+            }
+        }
+    }
+}
+
+impl Display for ResampleMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResampleMethod::NearestBin => write!(f, "Nearest Bin"),
+            ResampleMethod::LinearInterpolation => write!(f, "Linear Interpolation"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 112-point config from 100 MHz to 211 MHz with a 1 MHz step.
+    fn config() -> Config {
+        Config {
+            start_freq: Frequency::from_mhz(100),
+            step_size: Frequency::from_mhz(1),
+            stop_freq: Frequency::from_mhz(211),
+            sweep_len: 112,
+            ..Config::default()
+        }
+    }
+
+    /// A -100 dBm noise floor with a -40 dBm tone at `tone_index`.
+    fn sweep_with_tone(tone_index: usize) -> Vec<f32> {
+        let mut sweep = vec![-100.; 112];
+        sweep[tone_index] = -40.;
+        sweep
+    }
+
+    fn peak_index(amplitudes_dbm: &[f32]) -> Option<usize> {
+        amplitudes_dbm
+            .iter()
+            .enumerate()
+            .filter(|(_, amp_dbm)| !amp_dbm.is_nan())
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+    }
+
+    #[test]
+    fn keep_sweep_on_its_own_grid() {
+        let sweep = sweep_with_tone(37);
+        for method in [
+            ResampleMethod::NearestBin,
+            ResampleMethod::LinearInterpolation,
+        ] {
+            let resampled = method.apply(
+                &sweep,
+                &config(),
+                Frequency::from_mhz(100),
+                Frequency::from_mhz(1),
+                112,
+            );
+            assert_eq!(resampled, sweep);
+        }
+    }
+
+    #[test]
+    fn land_tones_on_nearest_grid_bins() {
+        // A grid with a 2.5 MHz step that's offset by 0.3 MHz from the sweep
+        let grid_start = Frequency::from_khz(100_300);
+        let grid_step = Frequency::from_khz(2_500);
+        for tone_index in [0, 1, 13, 50, 77, 110] {
+            let tone_freq_hz = 100e6 + tone_index as f64 * 1e6;
+            let resampled = ResampleMethod::NearestBin.apply(
+                &sweep_with_tone(tone_index),
+                &config(),
+                grid_start,
+                grid_step,
+                44,
+            );
+            // The tone shows up at every grid point within half a sweep step of it and nowhere else
+            for (i, amp_dbm) in resampled.iter().enumerate() {
+                let freq_hz = grid_start.as_hz_f64() + grid_step.as_hz_f64() * i as f64;
+                let is_near_tone = (freq_hz - tone_freq_hz).abs() <= 0.5e6;
+                assert_eq!(
+                    *amp_dbm == -40.,
+                    is_near_tone,
+                    "tone {tone_index}, grid point {i}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shift_tones_by_at_most_half_a_bin() {
+        // A grid with a finer step than the sweep puts the tone across several grid points, which
+        // must all be within half a sweep step of the tone
+        let grid_start = Frequency::from_khz(99_900);
+        let grid_step = Frequency::from_khz(250);
+        let resampled = ResampleMethod::NearestBin.apply(
+            &sweep_with_tone(40),
+            &config(),
+            grid_start,
+            grid_step,
+            450,
+        );
+        let tone_freq_hz = 140e6;
+        assert_eq!(
+            resampled.iter().filter(|amp_dbm| **amp_dbm == -40.).count(),
+            4
+        );
+        for (i, amp_dbm) in resampled.iter().enumerate() {
+            let freq_hz = grid_start.as_hz_f64() + grid_step.as_hz_f64() * i as f64;
+            if *amp_dbm == -40. {
+                assert!((freq_hz - tone_freq_hz).abs() <= 0.5e6);
+            }
+        }
+
+        // Interpolation peaks at the grid point closest to the tone
+        let resampled = ResampleMethod::LinearInterpolation.apply(
+            &sweep_with_tone(40),
+            &config(),
+            grid_start,
+            grid_step,
+            450,
+        );
+        let peak_freq_hz =
+            grid_start.as_hz_f64() + grid_step.as_hz_f64() * peak_index(&resampled).unwrap() as f64;
+        assert!((peak_freq_hz - tone_freq_hz).abs() <= grid_step.as_hz_f64() / 2.);
+    }
+
+    #[test]
+    fn interpolate_between_points() {
+        let mut sweep = vec![-100.; 112];
+        sweep[11] = -80.;
+        let resampled = ResampleMethod::LinearInterpolation.apply(
+            &sweep,
+            &config(),
+            Frequency::from_khz(110_250),
+            Frequency::from_khz(250),
+            5,
+        );
+        assert_eq!(resampled, [-95., -90., -85., -80., -85.]);
+    }
+
+    #[test]
+    fn fill_uncovered_grid_points_with_nan() {
+        let sweep = vec![-100.; 112];
+        let grid_start = Frequency::from_khz(99_000);
+        let grid_step = Frequency::from_khz(400);
+        // The grid runs from 99 MHz to 213 MHz
+        let grid_len = 286;
+
+        let resampled =
+            ResampleMethod::NearestBin.apply(&sweep, &config(), grid_start, grid_step, grid_len);
+        // Points within half a step of the first and last sweep points are covered
+        let covered: Vec<bool> = resampled.iter().map(|amp_dbm| !amp_dbm.is_nan()).collect();
+        assert_eq!(covered.iter().position(|covered| *covered), Some(2));
+        assert_eq!(covered.iter().rposition(|covered| *covered), Some(281));
+
+        let resampled = ResampleMethod::LinearInterpolation.apply(
+            &sweep,
+            &config(),
+            grid_start,
+            grid_step,
+            grid_len,
+        );
+        let covered: Vec<bool> = resampled.iter().map(|amp_dbm| !amp_dbm.is_nan()).collect();
+        assert_eq!(covered.iter().position(|covered| *covered), Some(3));
+        assert_eq!(covered.iter().rposition(|covered| *covered), Some(280));
+    }
+
+    #[test]
+    fn resample_empty_sweep() {
+        let resampled = ResampleMethod::NearestBin.apply(
+            &[],
+            &config(),
+            Frequency::from_mhz(100),
+            Frequency::from_mhz(1),
+            3,
+        );
+        assert!(resampled.iter().all(|amp_dbm| amp_dbm.is_nan()));
+    }
+}
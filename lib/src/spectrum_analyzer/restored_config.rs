@@ -0,0 +1,107 @@
+use std::ops::RangeInclusive;
+
+use crate::Frequency;
+
+/// The sweep configuration that was re-applied after switching radio modules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoredConfig {
+    /// The start frequency applied to the new radio module.
+    pub start_freq: Frequency,
+    /// The stop frequency applied to the new radio module.
+    pub stop_freq: Frequency,
+    /// The minimum amplitude applied to the new radio module.
+    pub min_amp_dbm: i16,
+    /// The maximum amplitude applied to the new radio module.
+    pub max_amp_dbm: i16,
+    /// The number of points in each sweep measured by the new radio module.
+    pub sweep_len: u16,
+    /// The settings that had to be changed to fit within the new radio module's limits.
+    pub clamped: Vec<ClampedSetting>,
+}
+
+/// A setting that couldn't be restored as-is after switching radio modules.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClampedSetting {
+    /// The start frequency was moved into the new radio module's frequency range.
+    StartFreq {
+        requested: Frequency,
+        applied: Frequency,
+    },
+    /// The stop frequency was moved into the new radio module's frequency range.
+    StopFreq {
+        requested: Frequency,
+        applied: Frequency,
+    },
+    /// The number of sweep points isn't supported by the new radio module.
+    SweepLen { requested: u16, applied: u16 },
+}
+
+/// Fits a start and stop frequency within a radio module's frequency and span ranges.
+///
+/// Frequencies outside of the frequency range are clamped to it. If the start and stop
+/// frequencies don't overlap the frequency range at all, the original span is kept and placed at
+/// the nearest edge of the range instead.
+pub(crate) fn clamp_start_stop(
+    start: Frequency,
+    stop: Frequency,
+    freq_range: &RangeInclusive<Frequency>,
+    span_range: &RangeInclusive<Frequency>,
+) -> (Frequency, Frequency) {
+    let (min_freq, max_freq) = (*freq_range.start(), *freq_range.end());
+    let clamped_start = start.clamp(min_freq, max_freq);
+    let clamped_stop = stop.clamp(min_freq, max_freq);
+    let span = if clamped_start < clamped_stop {
+        clamped_stop - clamped_start
+    } else {
+        stop - start
+    };
+
+    let max_span = (*span_range.end()).min(max_freq - min_freq);
+    let span = span.clamp((*span_range.start()).min(max_span), max_span);
+    let start = clamped_start.min(max_freq - span);
+    (start, start + span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mhz_range(start: u64, end: u64) -> RangeInclusive<Frequency> {
+        Frequency::from_mhz(start)..=Frequency::from_mhz(end)
+    }
+
+    fn clamp_mhz(start: u64, stop: u64) -> (u64, u64) {
+        // The frequency and span ranges of the RF Explorer 6G
+        let (start, stop) = clamp_start_stop(
+            Frequency::from_mhz(start),
+            Frequency::from_mhz(stop),
+            &mhz_range(4_850, 6_100),
+            &mhz_range(2, 600),
+        );
+        (start.as_mhz(), stop.as_mhz())
+    }
+
+    #[test]
+    fn keep_start_stop_within_range() {
+        assert_eq!(clamp_mhz(5_000, 5_100), (5_000, 5_100));
+    }
+
+    #[test]
+    fn clamp_partially_overlapping_start_stop() {
+        assert_eq!(clamp_mhz(4_800, 4_900), (4_850, 4_900));
+        assert_eq!(clamp_mhz(6_050, 6_200), (6_050, 6_100));
+    }
+
+    #[test]
+    fn move_non_overlapping_start_stop_to_nearest_edge() {
+        assert_eq!(clamp_mhz(430, 450), (4_850, 4_870));
+        assert_eq!(clamp_mhz(7_000, 7_010), (6_090, 6_100));
+    }
+
+    #[test]
+    fn clamp_span() {
+        assert_eq!(clamp_mhz(4_900, 6_000), (4_900, 5_500));
+        assert_eq!(clamp_mhz(5_000, 5_001), (5_000, 5_002));
+        assert_eq!(clamp_mhz(100, 2_000), (4_850, 5_450));
+    }
+}
@@ -0,0 +1,106 @@
+use std::{collections::VecDeque, mem};
+
+use super::Sweep;
+
+/// The most recent sweeps that haven't been drained with
+/// [`SpectrumAnalyzer::drain_sweep_history`](crate::SpectrumAnalyzer::drain_sweep_history).
+///
+/// With a capacity of 1, the latest sweep is the cached sweep, so nothing is copied and only
+/// whether it's been drained is tracked. Larger capacities keep a copy of each sweep and drop the
+/// oldest one once they're full.
+#[derive(Debug)]
+pub(crate) struct RetainedSweeps {
+    sweeps: VecDeque<Sweep>,
+    capacity: usize,
+    is_latest_drained: bool,
+}
+
+impl RetainedSweeps {
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns whether each sweep has to be copied with [`RetainedSweeps::push`] instead of
+    /// being marked with [`RetainedSweeps::push_latest`].
+    pub(crate) fn is_copying(&self) -> bool {
+        self.capacity > 1
+    }
+
+    /// Keeps a copy of a sweep, dropping the oldest one if the history is full.
+    pub(crate) fn push(&mut self, sweep: Sweep) {
+        if self.sweeps.len() == self.capacity {
+            self.sweeps.pop_front();
+        }
+        self.sweeps.push_back(sweep);
+    }
+
+    /// Marks the newly cached sweep as not drained yet.
+    pub(crate) fn push_latest(&mut self) {
+        self.is_latest_drained = false;
+    }
+
+    /// Returns the number of sweeps that haven't been drained, given the cached sweep.
+    pub(crate) fn len(&self, latest: Option<&Sweep>) -> usize {
+        if self.is_copying() {
+            self.sweeps.len()
+        } else {
+            usize::from(latest.is_some() && !self.is_latest_drained)
+        }
+    }
+
+    /// Removes and returns the sweeps that haven't been drained, oldest first.
+    pub(crate) fn drain(&mut self, latest: Option<&Sweep>) -> Vec<Sweep> {
+        if self.is_copying() {
+            return self.sweeps.drain(..).collect();
+        }
+        let is_latest_drained = mem::replace(&mut self.is_latest_drained, true);
+        latest
+            .filter(|_| !is_latest_drained)
+            .cloned()
+            .into_iter()
+            .collect()
+    }
+
+    /// Changes the number of sweeps that are kept, keeping the newest ones that still fit.
+    pub(crate) fn set_capacity(&mut self, capacity: usize, latest: Option<&Sweep>) {
+        let capacity = capacity.max(1);
+        match (self.is_copying(), capacity > 1) {
+            (true, true) => {
+                let excess = self.sweeps.len().saturating_sub(capacity);
+                self.sweeps.drain(..excess);
+            }
+            (true, false) => {
+                self.is_latest_drained = self.sweeps.is_empty();
+                self.sweeps = VecDeque::new();
+            }
+            (false, true) => {
+                if let Some(latest) = latest.filter(|_| !self.is_latest_drained) {
+                    self.sweeps.push_back(latest.clone());
+                }
+            }
+            (false, false) => {}
+        }
+        self.capacity = capacity;
+        self.sweeps.shrink_to(capacity);
+    }
+
+    /// Returns an estimate of the memory used by the copied sweeps, in bytes.
+    pub(crate) fn memory_usage_estimate(&self) -> usize {
+        self.sweeps.capacity() * size_of::<Sweep>()
+            + self
+                .sweeps
+                .iter()
+                .map(|sweep| sweep.amplitudes_dbm.capacity() * size_of::<f32>())
+                .sum::<usize>()
+    }
+}
+
+impl Default for RetainedSweeps {
+    fn default() -> Self {
+        Self {
+            sweeps: VecDeque::new(),
+            capacity: 1,
+            is_latest_drained: true,
+        }
+    }
+}
@@ -1,22 +1,45 @@
 use std::{
     fmt::Debug,
-    io,
+    fs::File,
+    io::{self, BufWriter},
+    mem,
     ops::RangeInclusive,
-    sync::{Arc, Condvar, Mutex, MutexGuard, WaitTimeoutResult},
-    thread,
-    time::Duration,
+    path::Path,
+    sync::{
+        Arc, Condvar, Mutex, MutexGuard, WaitTimeoutResult, Weak,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
+use chrono::{DateTime, TimeDelta, Utc};
 use tracing::{error, info, trace, warn};
 
 use super::{
-    CalcMode, Command, Config, DspMode, InputStage, Mode, Model, Sweep, TrackingStatus, WifiBand,
+    AlignmentReport, AutoInputStage, BufferedSweep, CachedIdentity, CalcMode, Capabilities,
+    ClampedSetting, Command, Config, CorruptionDetector, CorruptionStats, CorruptionVerdict,
+    DebouncedConfig, DspMode, Event, EventLog, Expectation, ExpectationId, ExpectationStatus,
+    Expectations, FrozenSweepWatchdog, HeuristicConfig, InputStage, InputStageTransition,
+    MismatchedSweepPolicy, MismatchedSweepStats, Mode, Model, PartialSweepDecoder, PendingChange,
+    Record, RecordQueue, ResampleMethod, RestoredConfig, RetainedSweeps, SmoothingMethod, Sweep,
+    SweepMeta, SweepReceiver, SweepSubscribers, TrackingStatus, WifiBand,
+    auto_input_stage::{AutoInputStageController, InputStageDecision},
+    auto_range::{self, SweepHistory},
+    frozen_sweep::{self, FrozenSweepDetector},
+    restored_config::clamp_start_stop,
+    sweep_rate::SweepRate,
+    zoom,
 };
+use crate::common::{AsyncSignal, Disconnection, SerialPort};
 use crate::rf_explorer::{
-    COMMAND_RESPONSE_TIMEOUT, ConfigCallback, NEXT_SCREEN_DATA_TIMEOUT,
-    RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT, ScreenData, SerialNumber, SetupInfo, impl_rf_explorer,
+    COMMAND_RESPONSE_TIMEOUT, CallbackThreads, ConfigCallback, MESSAGE_READER,
+    RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT, ReaderCallback, ScreenDataCache, SerialNumber, SetupInfo,
+    check_not_held, impl_rf_explorer,
+};
+use crate::{
+    ConnectionError, ConnectionResult, Dbm, Device, Error, Frequency, LinkHealth, MemoryBudget,
+    MemoryStats, RadioModule, Result, SignalGenerator, measurements::TrackingStepPlan,
 };
-use crate::{ConnectionError, ConnectionResult, Device, Error, Frequency, Result};
 
 #[derive(Debug)]
 /// RF Explorer spectrum analyzer device.
@@ -28,14 +51,87 @@ impl_rf_explorer!(SpectrumAnalyzer, MessageContainer);
 
 impl SpectrumAnalyzer {
     const MIN_MAX_AMP_RANGE_DBM: RangeInclusive<i16> = -120..=35;
-    const MIN_SWEEP_LEN: u16 = 112;
     const NEXT_SWEEP_TIMEOUT: Duration = Duration::from_secs(2);
+    const LATENCY_SAMPLES: usize = 5;
+
+    /// The number of sweeps that must be received after the config changes before
+    /// [`SpectrumAnalyzer::auto_range`] adjusts the amplitudes.
+    pub const AUTO_RANGE_MIN_SWEEPS: usize = SweepHistory::CAPACITY;
+
+    /// Connects to the RF Explorer on the named port using what it reported the last time it was
+    /// connected to, instead of waiting for it to identify itself.
+    ///
+    /// This returns as soon as any message confirms the RF Explorer is alive, rather than after
+    /// its config and setup info arrive. The cached values are replaced as the RF Explorer sends
+    /// the real ones. If the real setup info or serial number differs from the cached one, like
+    /// when another RF Explorer was plugged into the port, the config callback is called with
+    /// the current config so the app can resynchronize. A config that differs from the cached
+    /// one is reported by the config callback like any other.
+    pub fn connect_with_cached_identity(
+        name: &str,
+        baud_rate: u32,
+        identity: &CachedIdentity,
+    ) -> ConnectionResult<Self> {
+        Self::connect_with_identity(SerialPort::open_with_name(name, baud_rate)?, identity)
+    }
+
+    /// Connects to an RF Explorer through an already opened transport using what it reported the
+    /// last time it was connected to.
+    ///
+    /// See [`SpectrumAnalyzer::connect_with_cached_identity`].
+    pub fn connect_with_transport_and_cached_identity(
+        transport: Box<dyn serialport::SerialPort>,
+        identity: &CachedIdentity,
+    ) -> ConnectionResult<Self> {
+        Self::connect_with_identity(SerialPort::from_transport(transport), identity)
+    }
+
+    fn connect_with_identity(
+        serial_port: SerialPort,
+        identity: &CachedIdentity,
+    ) -> ConnectionResult<Self> {
+        let messages = MessageContainer::default();
+        messages.seed(identity);
+        let rfe = Self {
+            rfe: Device::connect_with_messages(
+                serial_port,
+                Cow::from(rf_explorer::Command::RequestConfig),
+                messages,
+                MessageContainer::wait_for_any_message,
+            )?,
+        };
+        // The RF Explorer only sends its serial number when it's asked for it. Like the command
+        // that starts the connection, it's written whatever state the device is in.
+        if identity.has_serial_number() {
+            rfe.rfe
+                .serial_port()
+                .write_bytes(Cow::from(rf_explorer::Command::RequestSerialNumber))?;
+        }
+        Ok(rfe)
+    }
+
+    /// Returns what the RF Explorer has reported about itself, which can be passed to
+    /// [`SpectrumAnalyzer::connect_with_cached_identity`] to reconnect to it quickly.
+    ///
+    /// The serial number is only included if it has already been received. Returns `None` if
+    /// the config or setup info hasn't been received.
+    pub fn cached_identity(&self) -> Option<CachedIdentity> {
+        let messages = self.messages();
+        let setup_info = messages.setup_info.0.lock().unwrap().clone()?;
+        let config = messages.config.0.lock().unwrap().clone()?;
+        let serial_number = messages.serial_number.0.lock().unwrap().clone();
+        Some(CachedIdentity::new(
+            &setup_info,
+            serial_number.as_ref(),
+            &config,
+        ))
+    }
 
     /// The serial number of the RF Explorer, if it exists.
-    pub fn serial_number(&self) -> Option<String> {
+    pub fn serial_number(&self) -> Option<Arc<str>> {
         // Return the serial number if we've already received it
         if let Some(ref serial_number) = *self.messages().serial_number.0.lock().unwrap() {
-            return Some(serial_number.to_string());
+            return Some(serial_number.as_shared_str());
         }
 
         // If we haven't already received the serial number, request it from the RF Explorer
@@ -55,11 +151,11 @@ impl SpectrumAnalyzer {
 
         (*self.messages().serial_number.0.lock().unwrap())
             .as_ref()
-            .map(|sn| sn.to_string())
+            .map(SerialNumber::as_shared_str)
     }
 
     /// The firmware version of the RF Explorer.
-    pub fn firmware_version(&self) -> String {
+    pub fn firmware_version(&self) -> Arc<str> {
         self.messages()
             .setup_info
             .0
@@ -70,10 +166,15 @@ impl SpectrumAnalyzer {
             .unwrap_or_default()
     }
 
-    fn config(&'_ self) -> MutexGuard<'_, Option<Config>> {
+    pub(crate) fn config(&'_ self) -> MutexGuard<'_, Option<Config>> {
         self.messages().config.0.lock().unwrap()
     }
 
+    /// Returns a copy of the most recent `Config`, or an error if one hasn't been received.
+    fn current_config(&self) -> Result<Config> {
+        config_or_err(self.config().as_ref()).cloned()
+    }
+
     /// The start frequency of the RF Explorer's sweeps.
     pub fn start_freq(&self) -> Frequency {
         self.config()
@@ -202,6 +303,8 @@ impl SpectrumAnalyzer {
     }
 
     /// The amplitudes of the most recent sweep measured by the RF Explorer.
+    ///
+    /// The last sweep received is still returned after the RF Explorer is disconnected.
     pub fn sweep(&self) -> Option<Vec<f32>> {
         self.rfe
             .messages()
@@ -213,6 +316,34 @@ impl SpectrumAnalyzer {
             .map(|sweep| sweep.amplitudes_dbm.clone())
     }
 
+    /// Writes the most recent sweep to a CSV file.
+    ///
+    /// See [`Sweep::to_csv_writer`] for the file's layout.
+    pub fn export_sweep_csv(&self, path: impl AsRef<Path>) -> Result<()> {
+        // Copy the sweep so the lock isn't held while the file is written
+        let sweep = self
+            .messages()
+            .sweep
+            .0
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| {
+                Error::InvalidOperation(
+                    "No sweeps have been measured by the RF Explorer".to_string(),
+                )
+            })?;
+        sweep.to_csv_writer(BufWriter::new(File::create(path)?))?;
+        Ok(())
+    }
+
+    /// The amplitudes of the most recent sweep measured by the RF Explorer as [`Dbm`]s.
+    ///
+    /// Use [`Dbm::from_f32_slice`] to view the amplitudes passed to sweep callbacks as `Dbm`s.
+    pub fn sweep_dbm(&self) -> Option<Vec<Dbm>> {
+        self.sweep().map(Dbm::from_f32_vec)
+    }
+
     /// Fills the buffer with the amplitudes of the most recent sweep and returns the length of the sweep.
     pub fn fill_buf_with_sweep(&self, buf: &mut [f32]) -> Result<usize> {
         let sweep = self.messages().sweep.0.lock().unwrap();
@@ -221,16 +352,26 @@ impl SpectrumAnalyzer {
                 "No sweeps have been measured by the RF Explorer".to_string(),
             ));
         };
+        fill_buf(sweep, buf)
+    }
 
-        let sweep_len = sweep.amplitudes_dbm.len();
-        if buf.len() >= sweep_len {
-            buf[0..sweep_len].copy_from_slice(sweep.amplitudes_dbm.as_slice());
-            Ok(sweep_len)
-        } else {
-            Err(Error::InvalidInput(
-                "The buffer is too small to fit the sweep".to_string(),
-            ))
-        }
+    /// Fills the buffer with the amplitudes of the most recent sweep and returns the length of the
+    /// sweep along with its start and stop frequencies.
+    ///
+    /// The frequencies are the ones that were in effect when the sweep was received, so they
+    /// always match the amplitudes even if the config changes in the meantime.
+    pub fn fill_buf_with_sweep_and_range(
+        &self,
+        buf: &mut [f32],
+    ) -> Result<(usize, Frequency, Frequency)> {
+        let sweep = self.messages().sweep.0.lock().unwrap();
+        let Some(sweep) = sweep.as_ref() else {
+            return Err(Error::InvalidOperation(
+                "No sweeps have been measured by the RF Explorer".to_string(),
+            ));
+        };
+        let sweep_len = fill_buf(sweep, buf)?;
+        Ok((sweep_len, sweep.meta.start_freq, sweep.meta.stop_freq))
     }
 
     /// Waits for the RF Explorer to measure the next sweep.
@@ -244,91 +385,522 @@ impl SpectrumAnalyzer {
     }
 
     /// Waits for the RF Explorer to measure the next sweep or for the timeout duration to elapse.
+    ///
+    /// Returns [`Error::Disconnected`] as soon as the RF Explorer is disconnected.
     pub fn wait_for_next_sweep_with_timeout(&self, timeout: Duration) -> Result<Vec<f32>> {
-        let previous_sweep_timestamp = self
-            .rfe
-            .messages()
+        let sweep = self.wait_for_new_sweep(timeout, |_| true)?;
+        Ok(sweep.amplitudes_dbm.clone())
+    }
+
+    /// Waits for the RF Explorer to measure the next sweep, or for the timeout duration to elapse,
+    /// and fills the buffer with its amplitudes.
+    ///
+    /// The buffer is filled with the sweep the wait ended on, even if another sweep or a config
+    /// change arrives right after it.
+    ///
+    /// Returns [`Error::Disconnected`] as soon as the RF Explorer is disconnected.
+    pub fn wait_for_next_sweep_with_timeout_and_fill_buf(
+        &self,
+        timeout: Duration,
+        buf: &mut [f32],
+    ) -> Result<usize> {
+        let sweep = self.wait_for_new_sweep(timeout, |_| true)?;
+        fill_buf(&sweep, buf)
+    }
+
+    /// Returns a future that resolves to the amplitudes of the first sweep the RF Explorer measures
+    /// after this is called.
+    ///
+    /// Unlike [`SpectrumAnalyzer::wait_for_next_sweep`], waiting doesn't block the thread, so one
+    /// task can wait for several RF Explorers. The future has to be awaited within a Tokio runtime
+    /// with its time driver enabled. It resolves to [`Error::TimedOut`] if no sweep is measured
+    /// before the timeout, 2 seconds if it's `None`, and to [`Error::Disconnected`] as soon as the
+    /// RF Explorer is disconnected.
+    #[cfg(feature = "async")]
+    pub fn wait_for_next_sweep_async(
+        &self,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<Vec<f32>>> + Send + '_ {
+        let messages = self.messages();
+        let previous_sweep_timestamp = messages
             .sweep
             .0
             .lock()
             .unwrap()
             .as_ref()
             .map(|sweep| sweep.timestamp);
+        let timeout = timeout.unwrap_or(Self::NEXT_SWEEP_TIMEOUT);
+        messages.sweep_signal.wait_until(timeout, move || {
+            if let Err(error) = messages.disconnection.check() {
+                return Some(Err(error));
+            }
+            messages
+                .sweep
+                .0
+                .lock()
+                .unwrap()
+                .as_ref()
+                .filter(|sweep| Some(sweep.timestamp) != previous_sweep_timestamp)
+                .map(|sweep| Ok(sweep.amplitudes_dbm.clone()))
+        })
+    }
+
+    /// Waits for the next sweep that was measured with the RF Explorer's current config or for the
+    /// timeout duration to elapse.
+    ///
+    /// Sweeps that are still on their way when the config changes, like the first sweeps after
+    /// [`SpectrumAnalyzer::set_start_stop`], are skipped so that they aren't mistaken for sweeps
+    /// of the new frequency range. A sweep is skipped if it was received before the current config
+    /// or if its length doesn't match the config's sweep length.
+    ///
+    /// Returns [`Error::Disconnected`] as soon as the RF Explorer is disconnected.
+    pub fn wait_for_sweep_matching_current_config(&self, timeout: Duration) -> Result<Vec<f32>> {
+        let messages = self.messages();
+        let sweep = self.wait_for_new_sweep(timeout, |sweep| {
+            messages.is_sweep_from_current_config(sweep)
+        })?;
+        Ok(sweep.amplitudes_dbm.clone())
+    }
+
+    /// Waits for a sweep newer than the most recent one that `is_wanted` accepts and returns the
+    /// locked sweep, so callers read the same sweep the wait ended on.
+    ///
+    /// The most recent sweep's timestamp is read under the same lock the wait starts with, so a
+    /// sweep received in between can't be mistaken for the previous one.
+    fn wait_for_new_sweep(
+        &self,
+        timeout: Duration,
+        is_wanted: impl Fn(&Sweep) -> bool,
+    ) -> Result<SweepGuard<'_>> {
+        check_not_held(MESSAGE_READER);
+        let (sweep, cond_var) = &self.messages().sweep;
+        let disconnection = &self.messages().disconnection;
+        let sweep = sweep.lock().unwrap();
+        let previous_sweep_timestamp = sweep.as_ref().map(|sweep| sweep.timestamp);
+
+        let (sweep, wait_result) = cond_var
+            .wait_timeout_while(sweep, timeout, |sweep| {
+                !sweep.as_ref().is_some_and(|sweep| {
+                    Some(sweep.timestamp) != previous_sweep_timestamp && is_wanted(sweep)
+                }) && !disconnection.is_disconnected()
+            })
+            .unwrap();
+        disconnection.check()?;
+
+        if wait_result.timed_out() || sweep.is_none() {
+            return Err(Error::TimedOut(timeout));
+        }
+        Ok(SweepGuard(sweep))
+    }
+
+    /// Compares the current config's sweep points with the start and stop frequencies last
+    /// requested with a setter like [`SpectrumAnalyzer::set_start_stop`].
+    ///
+    /// Returns `None` if no frequencies have been requested or no config has been received.
+    pub fn alignment_report(&self) -> Option<AlignmentReport> {
+        let (requested_start, requested_stop) =
+            (*self.messages().requested_start_stop.lock().unwrap())?;
+        self.config()
+            .as_ref()
+            .map(|config| config.requested_vs_actual(requested_start, requested_stop))
+    }
+
+    /// Returns the generation of the RF Explorer's config.
+    ///
+    /// The generation starts at 0 before a config is received and is incremented every time a
+    /// config that differs from the previous one is received. Compare it to
+    /// [`SweepMeta::config_generation`] to find out if a sweep was received with the current
+    /// config.
+    pub fn config_generation(&self) -> u64 {
+        self.messages().config_generation.load(Ordering::Relaxed)
+    }
+
+    /// Triggers exactly one sweep and returns it, leaving the RF Explorer on hold.
+    ///
+    /// The returned sweep is guaranteed to have been measured after the sweep was triggered. If
+    /// the sweep can't be captured, the RF Explorer is returned to the hold or run state it was in
+    /// before the call.
+    pub fn single_sweep(&self, timeout: Duration) -> Result<Sweep> {
+        let was_holding = self.is_holding();
+        let result = self.trigger_sweep(timeout);
+        if result.is_err() && !was_holding {
+            let _ = self.resume();
+        } else {
+            let _ = self.hold();
+        }
+        result
+    }
+
+    /// Triggers `n` sweeps one at a time and returns their amplitudes, leaving the RF Explorer on
+    /// hold.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let rfe = rfe::SpectrumAnalyzer::connect().expect("RF Explorer should be connected");
+    /// let sweeps = rfe.trigger_and_collect(10, Duration::from_secs(2))?;
+    /// let average_dbm: Vec<f32> = (0..sweeps[0].len())
+    ///     .map(|i| sweeps.iter().map(|sweep| sweep[i]).sum::<f32>() / sweeps.len() as f32)
+    ///     .collect();
+    /// # Ok::<(), rfe::Error>(())
+    /// ```
+    pub fn trigger_and_collect(&self, n: usize, timeout: Duration) -> Result<Vec<Vec<f32>>> {
+        let was_holding = self.is_holding();
+        let result = (0..n)
+            .map(|_| {
+                self.trigger_sweep(timeout)
+                    .map(|sweep| sweep.amplitudes_dbm.clone())
+            })
+            .collect::<Result<Vec<_>>>();
+        if result.is_err() && !was_holding {
+            let _ = self.resume();
+        } else {
+            let _ = self.hold();
+        }
+        result
+    }
+
+    fn trigger_sweep(&self, timeout: Duration) -> Result<Sweep> {
+        // Put the RF Explorer on hold and then resume it, which requests its config and makes it
+        // start measuring a new sweep
+        self.hold()?;
+        self.resume()?;
 
+        // A sweep that was being measured when the RF Explorer was put on hold can still be
+        // received after it was resumed, so skip the first sweep received after resuming
+        let resumed_sequence = self.messages().sweep_sequence.load(Ordering::Relaxed);
+        check_not_held(MESSAGE_READER);
         let (sweep, cond_var) = &self.messages().sweep;
-        // Wait until the timestamp of the previous sweep and the next sweep are different
+        let disconnection = &self.messages().disconnection;
         let (sweep, wait_result) = cond_var
-            .wait_timeout_while(sweep.lock().unwrap(), timeout, |sweep| {
-                sweep.as_ref().map(|sweep| sweep.timestamp) == previous_sweep_timestamp
-                    || sweep.is_none()
+            .wait_timeout_while(sweep.lock().unwrap(), timeout, |_| {
+                self.messages().sweep_sequence.load(Ordering::Relaxed) < resumed_sequence + 2
+                    && !disconnection.is_disconnected()
             })
             .unwrap();
+        disconnection.check()?;
 
         match &*sweep {
-            Some(sweep) if !wait_result.timed_out() => Ok(sweep.amplitudes_dbm.clone()),
+            Some(sweep) if !wait_result.timed_out() => Ok(sweep.clone()),
             _ => Err(Error::TimedOut(timeout)),
         }
     }
 
-    /// Waits for the RF Explorer to measure the next sweep, or for the timeout duration to elapse,
-    /// and fills the buffer with its amplitudes.
-    pub fn wait_for_next_sweep_with_timeout_and_fill_buf(
+    /// Returns the amplitudes of the most recent sweep smoothed using the given window size and method.
+    pub fn smoothed_sweep(&self, window: usize, method: SmoothingMethod) -> Result<Vec<f32>> {
+        let sweep = self.messages().sweep.0.lock().unwrap();
+        let Some(sweep) = sweep.as_ref() else {
+            return Err(Error::InvalidOperation(
+                "No sweeps have been measured by the RF Explorer".to_string(),
+            ));
+        };
+        sweep.smoothed(window, method)
+    }
+
+    /// Returns the amplitudes of the most recent sweep resampled onto a grid of `grid_len` points
+    /// starting at `grid_start` and spaced `grid_step` apart.
+    ///
+    /// The sweep is resampled using the start and stop frequencies it was received with, so a
+    /// config change that hasn't reached the sweeps yet doesn't shift it. Grid points that the
+    /// sweep doesn't cover are `NaN`. See [`Sweep::resample_to_grid`] to resample other sweeps.
+    pub fn resampled_sweep(
         &self,
-        timeout: Duration,
-        buf: &mut [f32],
-    ) -> Result<usize> {
-        let previous_sweep_timestamp = self
-            .rfe
-            .messages()
+        grid_start: Frequency,
+        grid_step: Frequency,
+        grid_len: usize,
+        method: ResampleMethod,
+    ) -> Option<Vec<f32>> {
+        self.messages()
             .sweep
             .0
             .lock()
             .unwrap()
             .as_ref()
-            .map(|sweep| sweep.timestamp);
+            .map(|sweep| sweep.resample_to_grid(grid_start, grid_step, grid_len, method))
+    }
 
-        let (sweep, cond_var) = &self.messages().sweep;
-        // Wait until the timestamp of the previous sweep and the next sweep are different
-        let (sweep, wait_result) = cond_var
-            .wait_timeout_while(sweep.lock().unwrap(), timeout, |sweep| {
-                sweep.as_ref().map(|sweep| sweep.timestamp) == previous_sweep_timestamp
-                    || sweep.is_none()
-            })
-            .unwrap();
-        drop(sweep);
+    /// Returns the input stage, amplitude offset, and firmware version that were in effect when
+    /// the most recent sweep was received.
+    pub fn sweep_meta(&self) -> Option<SweepMeta> {
+        self.messages()
+            .sweep
+            .0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|sweep| sweep.meta.clone())
+    }
 
-        if !wait_result.timed_out() {
-            self.fill_buf_with_sweep(buf)
-        } else {
-            Err(Error::TimedOut(timeout))
+    /// Returns the absolute amplitudes of the most recent sweep.
+    ///
+    /// `None` is returned if no sweep has been received or the correction it needs is unknown.
+    /// See [`SweepMeta::correction_db`].
+    pub fn absolute_sweep(&self) -> Option<Vec<f32>> {
+        self.messages()
+            .sweep
+            .0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|sweep| sweep.absolute_amplitudes(&sweep.meta))
+    }
+
+    /// Records an external event, like a test rig toggling a GPIO when the device under test
+    /// transmits, so it can be correlated with the sweeps around it.
+    ///
+    /// The event is timestamped now and shows up in the
+    /// [`events_since_previous_sweep`](SweepMeta::events_since_previous_sweep) of the next sweep,
+    /// and it's written to the [`RecordingSession`](super::RecordingSession) if one is running.
+    /// Events can be recorded from any thread. See [`SpectrumAnalyzer::dropped_events`] for what
+    /// happens when sweeps stop arriving.
+    pub fn annotate_event(&self, label: &str) {
+        self.annotate_event_at(label, Utc::now());
+    }
+
+    /// Records an external event that happened at `timestamp`, like one timestamped by the test
+    /// rig that detected it.
+    ///
+    /// The event belongs to the first sweep received at or after `timestamp`. See
+    /// [`SpectrumAnalyzer::annotate_event`].
+    pub fn annotate_event_at(&self, label: &str, timestamp: DateTime<Utc>) {
+        let event = Event {
+            timestamp,
+            label: label.to_string(),
+        };
+        self.messages().record(|| Record::Event(event.clone()));
+        self.messages().events.push(event);
+    }
+
+    /// Returns the number of events that were dropped because too many were recorded before the
+    /// next sweep was received.
+    ///
+    /// Only the newest [`MemoryBudget::max_pending_events`] events are kept while waiting for a
+    /// sweep. Dropped events are still written to a running
+    /// [`RecordingSession`](super::RecordingSession).
+    pub fn dropped_events(&self) -> u64 {
+        self.messages().events.dropped_events()
+    }
+
+    /// Estimates the latency of the serial connection by timing how long the RF Explorer takes to
+    /// respond to several serial number requests.
+    ///
+    /// Half of each round trip is smoothed into the current estimate, which is then subtracted
+    /// from the time each sweep is received to get [`SweepMeta::captured_at_adjusted`]. Returns
+    /// the updated estimate.
+    #[tracing::instrument(skip(self))]
+    pub fn estimate_latency(&self) -> Result<Duration> {
+        let mut estimate = *self.messages().latency_estimate.lock().unwrap();
+        for _ in 0..Self::LATENCY_SAMPLES {
+            // Clear the cached serial number so we can tell when the response has been received
+            *self.messages().serial_number.0.lock().unwrap() = None;
+            self.send_command(crate::rf_explorer::Command::RequestSerialNumber)?;
+            let sent_at = Instant::now();
+
+            let (lock, condvar) = &self.messages().serial_number;
+            let wait_result = condvar
+                .wait_timeout_while(
+                    lock.lock().unwrap(),
+                    COMMAND_RESPONSE_TIMEOUT,
+                    |serial_number| serial_number.is_none(),
+                )
+                .unwrap()
+                .1;
+            if wait_result.timed_out() {
+                return Err(Error::TimedOut(COMMAND_RESPONSE_TIMEOUT));
+            }
+
+            // Only the trip from the RF Explorer to the host delays a sweep's timestamp
+            let sample = sent_at.elapsed() / 2;
+            estimate = Some(estimate.map_or(sample, |estimate| (estimate * 3 + sample) / 4));
         }
+
+        let estimate = estimate.unwrap_or_default();
+        *self.messages().latency_estimate.lock().unwrap() = Some(estimate);
+        Ok(estimate)
+    }
+
+    /// Returns the current estimate of the serial connection's latency, if it has been estimated.
+    pub fn latency_estimate(&self) -> Option<Duration> {
+        *self.messages().latency_estimate.lock().unwrap()
     }
 
-    /// Returns the most recent `ScreenData` captured by the RF Explorer.
-    pub fn screen_data(&self) -> Option<ScreenData> {
-        self.messages().screen_data.0.lock().unwrap().clone()
+    /// Sets whether the latency estimate is subtracted from the time sweeps are received.
+    ///
+    /// Adjustment is enabled by default but has no effect until
+    /// [`estimate_latency`](Self::estimate_latency) has been called.
+    pub fn set_latency_adjustment(&self, enabled: bool) {
+        self.messages()
+            .latency_adjustment_disabled
+            .store(!enabled, Ordering::Relaxed);
     }
 
-    /// Waits for the RF Explorer to capture its next `ScreenData`.
-    pub fn wait_for_next_screen_data(&self) -> Result<ScreenData> {
-        self.wait_for_next_screen_data_with_timeout(NEXT_SCREEN_DATA_TIMEOUT)
+    /// Sets what happens to sweeps whose length doesn't match the config's sweep length.
+    ///
+    /// Mismatched sweeps are dropped by default. Kept sweeps are flagged with
+    /// [`SweepMeta::mismatched_len`].
+    pub fn set_mismatched_sweep_policy(&self, policy: MismatchedSweepPolicy) {
+        *self.messages().mismatched_sweep_policy.lock().unwrap() = policy;
     }
 
-    /// Waits for the RF Explorer to capture its next `ScreenData` or for the timeout duration to elapse.
-    pub fn wait_for_next_screen_data_with_timeout(&self, timeout: Duration) -> Result<ScreenData> {
-        let previous_screen_data = self.screen_data();
+    /// Returns what happens to sweeps whose length doesn't match the config's sweep length.
+    pub fn mismatched_sweep_policy(&self) -> MismatchedSweepPolicy {
+        *self.messages().mismatched_sweep_policy.lock().unwrap()
+    }
 
-        let (screen_data, condvar) = &self.messages().screen_data;
-        let (screen_data, wait_result) = condvar
-            .wait_timeout_while(screen_data.lock().unwrap(), timeout, |screen_data| {
-                *screen_data == previous_screen_data || screen_data.is_none()
-            })
-            .unwrap();
+    /// Returns the number of configs that were dropped because their settings were impossible,
+    /// like a sweep length longer than the radio module supports or a start frequency above the
+    /// stop frequency.
+    ///
+    /// Dropped configs never replace the last good config or reach the config callback.
+    pub fn invalid_config_count(&self) -> u64 {
+        self.messages().invalid_configs.load(Ordering::Relaxed)
+    }
 
-        match &*screen_data {
-            Some(screen_data) if !wait_result.timed_out() => Ok(screen_data.clone()),
-            _ => Err(Error::TimedOut(timeout)),
+    /// Returns counts of the sweeps whose length didn't match the config's sweep length and what
+    /// was done with them.
+    pub fn mismatched_sweep_stats(&self) -> MismatchedSweepStats {
+        *self.messages().mismatched_sweep_stats.lock().unwrap()
+    }
+
+    /// Sets the heuristic that flags sweeps that were probably corrupted on their way from the RF
+    /// Explorer, or turns it off with `None`.
+    ///
+    /// The heuristic is off by default. See [`HeuristicConfig`] for how sweeps are judged and how
+    /// to tune it if real sweeps are flagged. Flagged sweeps that are kept are marked with
+    /// [`SweepMeta::suspected_corrupt`].
+    pub fn set_corruption_heuristic(&self, heuristic: Option<HeuristicConfig>) {
+        self.messages()
+            .corrupt_sweeps
+            .lock()
+            .unwrap()
+            .set_heuristic(heuristic);
+    }
+
+    /// Returns the heuristic that flags corrupt sweeps, or `None` if it's off.
+    pub fn corruption_heuristic(&self) -> Option<HeuristicConfig> {
+        self.messages().corrupt_sweeps.lock().unwrap().heuristic()
+    }
+
+    /// Returns counts of the sweeps checked by the corruption heuristic and what was done with
+    /// the ones it flagged.
+    pub fn corruption_stats(&self) -> CorruptionStats {
+        self.messages().corrupt_sweeps.lock().unwrap().stats()
+    }
+
+    /// Sets the watchdog that detects when the spectrum analyzer's front end has frozen and it
+    /// keeps sending identical sweeps, or turns it off with `None`.
+    ///
+    /// The watchdog is off by default. When it detects frozen sweeps, the callback set with
+    /// [`SpectrumAnalyzer::set_frozen_sweep_callback`] is called and, if
+    /// [`FrozenSweepWatchdog::reboot`] is set, the RF Explorer is rebooted and its config is
+    /// requested again once it has restarted.
+    pub fn set_frozen_sweep_watchdog(&self, watchdog: Option<FrozenSweepWatchdog>) {
+        *self.messages().serial_port.lock().unwrap() = self.rfe.weak_serial_port();
+        self.messages()
+            .frozen_sweeps
+            .lock()
+            .unwrap()
+            .set_watchdog(watchdog);
+    }
+
+    /// Returns the watchdog that detects frozen sweeps, or `None` if it's off.
+    pub fn frozen_sweep_watchdog(&self) -> Option<FrozenSweepWatchdog> {
+        self.messages().frozen_sweeps.lock().unwrap().watchdog()
+    }
+
+    /// Sets the callback that is called when the watchdog detects frozen sweeps.
+    pub fn set_frozen_sweep_callback(&self, cb: impl Fn() + Send + Sync + 'static) {
+        *self.messages().frozen_sweep_callback.lock().unwrap() = Some(Arc::new(Box::new(cb)));
+    }
+
+    /// Removes the callback that is called when the watchdog detects frozen sweeps.
+    pub fn remove_frozen_sweep_callback(&self) {
+        *self.messages().frozen_sweep_callback.lock().unwrap() = None;
+    }
+
+    /// Sets the controller that switches to an attenuating input stage when strong signals would
+    /// overload the front end and back once they're gone, or turns it off with `None`.
+    ///
+    /// The controller is off by default. The input stage is switched with
+    /// [`SpectrumAnalyzer::set_input_stage`] and
+    /// [`SpectrumAnalyzer::input_stage`] reports the new input stage right away, so the
+    /// [`SweepMeta`] of later sweeps is corrected for it. The sweeps received while the RF
+    /// Explorer switches are flagged with [`SweepMeta::input_stage_settling`].
+    pub fn set_auto_input_stage(&self, settings: Option<AutoInputStage>) -> Result<()> {
+        if let Some(settings) = settings {
+            if !self.capabilities().supports_input_stage {
+                return Err(Error::InvalidOperation(
+                    "The active radio module doesn't have an input stage".to_string(),
+                ));
+            }
+            if settings.recovery_dbm >= settings.overload_dbm {
+                return Err(Error::InvalidInput(format!(
+                    "The recovery threshold of {} dBm must be lower than the overload threshold of {} dBm",
+                    settings.recovery_dbm, settings.overload_dbm
+                )));
+            }
         }
+        self.messages()
+            .auto_input_stage
+            .lock()
+            .unwrap()
+            .set_settings(settings);
+        Ok(())
+    }
+
+    /// Returns the settings of the automatic input stage controller, or `None` if it's off.
+    pub fn auto_input_stage(&self) -> Option<AutoInputStage> {
+        self.messages().auto_input_stage.lock().unwrap().settings()
+    }
+
+    /// Sets the callback that is called when the automatic input stage controller switches input
+    /// stages.
+    pub fn set_input_stage_transition_callback(
+        &self,
+        cb: impl Fn(InputStageTransition) + Send + Sync + 'static,
+    ) {
+        *self
+            .messages()
+            .input_stage_transition_callback
+            .lock()
+            .unwrap() = Some(Arc::new(Box::new(cb)));
+    }
+
+    /// Removes the callback that is called when the automatic input stage controller switches
+    /// input stages.
+    pub fn remove_input_stage_transition_callback(&self) {
+        *self
+            .messages()
+            .input_stage_transition_callback
+            .lock()
+            .unwrap() = None;
+    }
+
+    /// Sets whether the RF Explorer is told to hold and stop sending its screen when the
+    /// `SpectrumAnalyzer` is dropped.
+    ///
+    /// Otherwise the RF Explorer keeps streaming sweeps and screen data after the program exits,
+    /// which slows down the next connection to it. This is off by default. The commands are sent
+    /// with a short write timeout and failures are ignored, so dropping never waits long for
+    /// them.
+    pub fn set_quiesce_on_drop(&self, quiesce_on_drop: bool) {
+        self.rfe.set_quiesce_on_drop(quiesce_on_drop);
+    }
+
+    /// Returns whether the RF Explorer is told to hold and stop sending its screen when the
+    /// `SpectrumAnalyzer` is dropped.
+    pub fn quiesce_on_drop(&self) -> bool {
+        self.rfe.quiesce_on_drop()
+    }
+
+    /// Returns how many sweeps per second the RF Explorer is sending, or `None` if fewer than two
+    /// sweeps have been received since the config last changed.
+    ///
+    /// The rate is estimated from the smoothed time between received sweeps.
+    pub fn sweep_rate_hz(&self) -> Option<f64> {
+        self.messages().sweep_rate.lock().unwrap().hz()
     }
 
     /// Returns the RF Explorer's DSP mode.
@@ -354,8 +926,7 @@ impl SpectrumAnalyzer {
             .lock()
             .unwrap()
             .as_ref()
-            .unwrap()
-            .main_radio_model
+            .and_then(|setup_info| setup_info.main_radio_model)
     }
 
     /// Returns the expansion radio's model (if one exists).
@@ -367,8 +938,7 @@ impl SpectrumAnalyzer {
             .lock()
             .unwrap()
             .as_ref()
-            .unwrap()
-            .expansion_radio_model
+            .and_then(|setup_info| setup_info.expansion_radio_model)
     }
 
     /// Returns the active radio module.
@@ -394,21 +964,52 @@ impl SpectrumAnalyzer {
         }
     }
 
+    /// Returns the features supported by the RF Explorer's active radio module.
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::new(self.active_radio_model(), self.expansion_radio_model())
+    }
+
     /// Starts the spectrum analyzer's Wi-Fi analyzer.
+    ///
+    /// Returns `Error::InvalidOperation` if the active radio module can't measure the Wi-Fi band.
     #[tracing::instrument]
-    pub fn start_wifi_analyzer(&self, wifi_band: WifiBand) -> io::Result<()> {
-        self.send_command(Command::StartWifiAnalyzer(wifi_band))
+    pub fn start_wifi_analyzer(&self, wifi_band: WifiBand) -> Result<()> {
+        let capabilities = self.capabilities();
+        if !capabilities.supports_wifi_band(wifi_band) {
+            let band_freq_range = wifi_band.freq_range();
+            return Err(Error::InvalidOperation(format!(
+                "The {} radio module cannot analyze the {}-{} MHz Wi-Fi band",
+                capabilities.active_radio_model,
+                band_freq_range.start().as_mhz_f64(),
+                band_freq_range.end().as_mhz_f64()
+            )));
+        }
+
+        self.send_command(Command::StartWifiAnalyzer(wifi_band))?;
+        Ok(())
     }
 
     /// Stops the spectrum analyzer's Wi-Fi analyzer.
     #[tracing::instrument(skip(self))]
-    pub fn stop_wifi_analyzer(&self) -> io::Result<()> {
+    pub fn stop_wifi_analyzer(&self) -> Result<()> {
         self.send_command(Command::StopWifiAnalyzer)
     }
 
     /// Requests the spectrum analyzer enter tracking mode.
+    ///
+    /// Returns [`Error::InvalidInput`] without sending anything if the start frequency is outside
+    /// of the active radio module's frequency range. Use
+    /// [`SpectrumAnalyzer::request_tracking_plan`] to also check the frequencies the steps reach
+    /// and the signal generator's frequency range.
     #[tracing::instrument(skip(self))]
     pub fn request_tracking(&self, start_hz: u64, step_hz: u64) -> Result<TrackingStatus> {
+        TrackingStepPlan {
+            start: Frequency::from_hz(start_hz),
+            step: Frequency::from_hz(step_hz),
+            steps: 0,
+        }
+        .validate(&self.capabilities(), None)?;
+
         // Set the tracking status to None so we can tell whether or not we've received a new
         // tracking status message by checking for Some
         *self.messages().tracking_status.0.lock().unwrap() = None;
@@ -436,18 +1037,35 @@ impl SpectrumAnalyzer {
         }
     }
 
+    /// Requests the spectrum analyzer enter tracking mode for the plan, after checking that every
+    /// frequency the plan reaches is within this spectrum analyzer's and the signal generator's
+    /// frequency ranges.
+    ///
+    /// Returns [`Error::InvalidInput`] naming the device and bound that was violated without
+    /// sending anything, so call this before configuring the signal generator.
+    #[tracing::instrument(skip(self, generator))]
+    pub fn request_tracking_plan(
+        &self,
+        plan: &TrackingStepPlan,
+        generator: Option<&SignalGenerator>,
+    ) -> Result<TrackingStatus> {
+        let generator_capabilities = generator.map(SignalGenerator::capabilities);
+        plan.validate(&self.capabilities(), generator_capabilities.as_ref())?;
+        self.request_tracking(plan.start.as_hz(), plan.step.as_hz())
+    }
+
     /// Steps over the tracking step frequency and makes a measurement.
     #[tracing::instrument(skip(self))]
-    pub fn tracking_step(&self, step: u16) -> io::Result<()> {
+    pub fn tracking_step(&self, step: u16) -> Result<()> {
         self.send_command(Command::TrackingStep(step))
     }
 
     /// Activates the RF Explorer's main radio.
+    ///
+    /// Returns [`Error::AlreadyActive`] if the main radio is already active.
     pub fn activate_main_radio(&self) -> Result<()> {
         if !self.is_expansion_radio_module_active() {
-            return Err(Error::InvalidOperation(
-                "Main radio module is already active.".to_string(),
-            ));
+            return Err(Error::AlreadyActive(RadioModule::Main));
         }
 
         self.send_command(Command::SwitchModuleMain)?;
@@ -458,7 +1076,7 @@ impl SpectrumAnalyzer {
                 .as_ref()
                 .filter(|config| !config.is_expansion_radio_module_active)
                 .is_none()
-        });
+        })?;
 
         if !self.is_expansion_radio_module_active() {
             Ok(())
@@ -468,17 +1086,16 @@ impl SpectrumAnalyzer {
     }
 
     /// Activates the RF Explorer's expansion radio (if one exists).
+    ///
+    /// Returns [`Error::NoExpansionModule`] if the RF Explorer doesn't have an expansion radio and
+    /// [`Error::AlreadyActive`] if the expansion radio is already active.
     pub fn activate_expansion_radio(&self) -> Result<()> {
         if self.expansion_radio_model().is_none() {
-            return Err(Error::InvalidOperation(
-                "This RF Explorer does not contain an expansion radio module.".to_string(),
-            ));
+            return Err(Error::NoExpansionModule);
         }
 
         if self.is_expansion_radio_module_active() {
-            return Err(Error::InvalidOperation(
-                "Expansion radio module is already active.".to_string(),
-            ));
+            return Err(Error::AlreadyActive(RadioModule::Expansion));
         }
 
         self.send_command(Command::SwitchModuleExp)?;
@@ -489,7 +1106,7 @@ impl SpectrumAnalyzer {
                 .as_ref()
                 .filter(|config| config.is_expansion_radio_module_active)
                 .is_none()
-        });
+        })?;
 
         if self.is_expansion_radio_module_active() {
             Ok(())
@@ -498,18 +1115,95 @@ impl SpectrumAnalyzer {
         }
     }
 
+    /// Activates a radio module and restores the sweep configuration that the previous module was
+    /// using.
+    ///
+    /// RF Explorers reset the frequency and amplitude settings to the new module's defaults when
+    /// switching modules. This re-applies the previous start and stop frequencies, amplitudes,
+    /// and number of sweep points once the new module's config has been received. Settings that
+    /// aren't supported by the new module are clamped to its limits and reported in the returned
+    /// [`RestoredConfig`].
+    #[tracing::instrument(skip(self))]
+    pub fn activate_radio_module_preserving_config(
+        &self,
+        radio_module: RadioModule,
+    ) -> Result<RestoredConfig> {
+        let previous_config = self.current_config()?;
+
+        // Both of these wait until the new module's config has been received
+        match radio_module {
+            RadioModule::Main => self.activate_main_radio()?,
+            RadioModule::Expansion => self.activate_expansion_radio()?,
+        }
+
+        let capabilities = self.capabilities();
+        let mut clamped = Vec::new();
+
+        let (start_freq, stop_freq) = clamp_start_stop(
+            previous_config.start_freq,
+            previous_config.stop_freq,
+            &capabilities.freq_range,
+            &capabilities.span_range,
+        );
+        if start_freq != previous_config.start_freq {
+            clamped.push(ClampedSetting::StartFreq {
+                requested: previous_config.start_freq,
+                applied: start_freq,
+            });
+        }
+        if stop_freq != previous_config.stop_freq {
+            clamped.push(ClampedSetting::StopFreq {
+                requested: previous_config.stop_freq,
+                applied: stop_freq,
+            });
+        }
+
+        let sweep_len = if capabilities.can_set_sweep_len {
+            previous_config
+                .sweep_len
+                .clamp(capabilities.min_sweep_len, capabilities.max_sweep_len)
+        } else {
+            self.sweep_len()
+        };
+        if sweep_len != previous_config.sweep_len {
+            clamped.push(ClampedSetting::SweepLen {
+                requested: previous_config.sweep_len,
+                applied: sweep_len,
+            });
+        }
+
+        // Change the number of sweep points first because doing so can reset the frequencies
+        if capabilities.can_set_sweep_len && sweep_len != self.sweep_len() {
+            self.set_sweep_len(sweep_len)?;
+        }
+        self.set_config(
+            start_freq,
+            stop_freq,
+            previous_config.min_amp_dbm,
+            previous_config.max_amp_dbm,
+        )?;
+
+        Ok(RestoredConfig {
+            start_freq,
+            stop_freq,
+            min_amp_dbm: previous_config.min_amp_dbm,
+            max_amp_dbm: previous_config.max_amp_dbm,
+            sweep_len,
+            clamped,
+        })
+    }
+
     /// Sets the start and stop frequency of sweeps measured by the spectrum analyzer.
     pub fn set_start_stop(
         &self,
         start: impl Into<Frequency>,
         stop: impl Into<Frequency>,
     ) -> Result<()> {
-        self.set_config(
-            start.into(),
-            stop.into(),
-            self.min_amp_dbm(),
-            self.max_amp_dbm(),
-        )
+        let (start, stop) = (start.into(), stop.into());
+        let config = self.current_config()?;
+        self.validate_start_stop(start, stop)?;
+        *self.messages().requested_start_stop.lock().unwrap() = Some((start, stop));
+        self.set_config(start, stop, config.min_amp_dbm, config.max_amp_dbm)
     }
 
     /// Sets the start frequency, stop frequency, and number of points of sweeps measured by the spectrum analyzer.
@@ -544,18 +1238,125 @@ impl SpectrumAnalyzer {
         self.set_start_stop_sweep_len(start, stop, sweep_len)
     }
 
+    /// Centers the sweeps on the strongest point of the most recent sweep with the given span and
+    /// returns the frequency it centered on.
+    ///
+    /// The span is clamped to the active radio module's span range. Near the edges of its
+    /// frequency range, the span is moved inside the range, so the peak isn't centered.
+    pub fn zoom_to_peak(&self, span: impl Into<Frequency>) -> Result<Frequency> {
+        self.check_can_zoom()?;
+        let peak_freq = self
+            .messages()
+            .sweep
+            .0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .ok_or_else(|| {
+                Error::InvalidOperation(
+                    "No sweeps have been measured by the RF Explorer".to_string(),
+                )
+            })?
+            .peak_freq()
+            .ok_or_else(|| Error::InvalidOperation("The sweep has no peak".to_string()))?;
+        self.zoom(peak_freq, span.into())?;
+        Ok(peak_freq)
+    }
+
+    /// Widens the span by `factor` around the current center frequency.
+    ///
+    /// The span is clamped to the active radio module's span range and moved inside its frequency
+    /// range if it would cross an edge. The factor must be at least 1.
+    pub fn zoom_out(&self, factor: f64) -> Result<()> {
+        self.check_can_zoom()?;
+        if !(factor >= 1. && factor.is_finite()) {
+            return Err(Error::InvalidInput(format!(
+                "The zoom factor {factor} must be a finite number of at least 1"
+            )));
+        }
+        let config = self.current_config()?;
+        let span = Frequency::from_hz((config.span.as_hz() as f64 * factor) as u64);
+        self.zoom(config.center_freq, span)
+    }
+
+    fn zoom(&self, center: Frequency, span: Frequency) -> Result<()> {
+        let capabilities = self.capabilities();
+        let (start, stop) = zoom::centered_start_stop(
+            center,
+            span,
+            &capabilities.freq_range,
+            &capabilities.span_range,
+        );
+        self.set_start_stop(start, stop)
+    }
+
+    /// Returns an error if the RF Explorer is in a mode where its span can't be changed.
+    fn check_can_zoom(&self) -> Result<()> {
+        match self.mode() {
+            mode @ (Mode::WifiAnalyzer | Mode::AnalyzerTracking) => Err(Error::InvalidOperation(
+                format!("Can't zoom while the RF Explorer is in {mode} mode"),
+            )),
+            _ => Ok(()),
+        }
+    }
+
     /// Sets the minimum and maximum amplitudes displayed on the RF Explorer's screen.
+    ///
+    /// Both amplitudes must be within -120 to 35 dBm shifted by the RF Explorer's
+    /// [amplitude offset](Self::amp_offset_db). For example, an offset of +30 dB for an external
+    /// LNA allows amplitudes from -90 to 65 dBm.
     #[tracing::instrument(skip(self))]
     pub fn set_min_max_amps(&self, min_amp_dbm: i16, max_amp_dbm: i16) -> Result<()> {
+        let config = self.current_config()?;
         self.set_config(
-            self.start_freq(),
-            self.stop_freq(),
+            config.start_freq,
+            config.stop_freq,
             min_amp_dbm,
             max_amp_dbm,
         )
     }
 
-    /// Sets the spectrum analyzer's configuration.
+    /// Suggests minimum and maximum amplitudes that keep the recent sweeps' noise floor and
+    /// strongest signals visible.
+    ///
+    /// The minimum is 10 dB below the noise floor and the maximum is 10 dB above the strongest
+    /// amplitude, both rounded outward to a multiple of 5 dB. Only sweeps received since the config
+    /// last changed are used. Returns `None` if none have been received.
+    pub fn suggest_amp_range(&self) -> Option<(i16, i16)> {
+        let amp_range = min_max_amp_range_dbm(self.amp_offset_db());
+        auto_range::suggest_amp_range(
+            self.messages().auto_range_sweeps.lock().unwrap().sweeps(),
+            amp_range,
+        )
+    }
+
+    /// Sets the minimum and maximum amplitudes to the ones suggested by
+    /// [`SpectrumAnalyzer::suggest_amp_range`] and returns them.
+    ///
+    /// Returns [`Error::InvalidOperation`] if fewer than
+    /// [`AUTO_RANGE_MIN_SWEEPS`](Self::AUTO_RANGE_MIN_SWEEPS) sweeps have been received since the
+    /// config last changed, including the change made by the previous call.
+    #[tracing::instrument(skip(self), ret, err)]
+    pub fn auto_range(&self) -> Result<(i16, i16)> {
+        let sweeps_len = self.messages().auto_range_sweeps.lock().unwrap().len();
+        if sweeps_len < Self::AUTO_RANGE_MIN_SWEEPS {
+            return Err(Error::InvalidOperation(format!(
+                "Only {sweeps_len} sweeps have been received since the config changed, but {} are \
+                 needed to suggest amplitudes",
+                Self::AUTO_RANGE_MIN_SWEEPS
+            )));
+        }
+
+        let Some((min_amp_dbm, max_amp_dbm)) = self.suggest_amp_range() else {
+            return Err(Error::InvalidOperation(
+                "The recent sweeps don't have any amplitudes to suggest a range from".to_string(),
+            ));
+        };
+        self.set_min_max_amps(min_amp_dbm, max_amp_dbm)?;
+        Ok((min_amp_dbm, max_amp_dbm))
+    }
+
+    /// Sets the spectrum analyzer's configuration.
     #[tracing::instrument(skip(self), ret, err)]
     fn set_config(
         &self,
@@ -574,35 +1375,104 @@ impl SpectrumAnalyzer {
             max_amp_dbm,
         })?;
 
-        // Check if the current config already contains the requested values
-        if self
-            .config()
-            .as_ref()
-            .unwrap_or(&Config::default())
-            .contains_start_stop_amp_range(start, stop, min_amp_dbm, max_amp_dbm)
-        {
-            return Ok(());
-        }
+        self.wait_for_expectation(Expectation::StartStopAmpRange {
+            start,
+            stop,
+            min_amp_dbm,
+            max_amp_dbm,
+        })
+    }
 
-        // Wait until the current config contains the requested values
-        trace!("Waiting to receive updated 'Config'");
-        let (config, wait_result) = self.wait_for_config_while(|config| {
-            let Some(config) = config else {
-                return true;
-            };
+    /// Sets the start and stop frequencies once no other debounced change has been requested for
+    /// the debounce duration, without waiting for the RF Explorer.
+    ///
+    /// This is meant for controls like sliders that request many changes in quick succession. The
+    /// frequencies are validated right away, but only the latest values requested with any of the
+    /// debounced setters are sent, in a single command. Completion is reported by the config
+    /// callback and the returned [`PendingChange`].
+    pub fn set_start_stop_debounced(
+        &self,
+        start: impl Into<Frequency>,
+        stop: impl Into<Frequency>,
+        debounce: Duration,
+    ) -> Result<PendingChange> {
+        let (start, stop) = (start.into(), stop.into());
+        self.validate_start_stop(start, stop)?;
+        self.debounce_config(debounce, |debounced| {
+            debounced.start = start;
+            debounced.stop = stop;
+            debounced.start_stop_requested = true;
+        })
+    }
+
+    /// Sets the center frequency and span once no other debounced change has been requested for
+    /// the debounce duration, without waiting for the RF Explorer.
+    ///
+    /// See [`SpectrumAnalyzer::set_start_stop_debounced`].
+    pub fn set_center_span_debounced(
+        &self,
+        center: impl Into<Frequency>,
+        span: impl Into<Frequency>,
+        debounce: Duration,
+    ) -> Result<PendingChange> {
+        let (start, stop) = self.start_stop_from_center_span(center.into(), span.into())?;
+        self.set_start_stop_debounced(start, stop, debounce)
+    }
+
+    /// Sets the minimum and maximum amplitudes once no other debounced change has been requested
+    /// for the debounce duration, without waiting for the RF Explorer.
+    ///
+    /// See [`SpectrumAnalyzer::set_start_stop_debounced`].
+    pub fn set_min_max_amps_debounced(
+        &self,
+        min_amp_dbm: i16,
+        max_amp_dbm: i16,
+        debounce: Duration,
+    ) -> Result<PendingChange> {
+        self.validate_min_max_amps(min_amp_dbm, max_amp_dbm)?;
+        self.debounce_config(debounce, |debounced| {
+            debounced.min_amp_dbm = min_amp_dbm;
+            debounced.max_amp_dbm = max_amp_dbm;
+        })
+    }
 
-            !config.contains_start_stop_amp_range(start, stop, min_amp_dbm, max_amp_dbm)
+    fn debounce_config(
+        &self,
+        debounce: Duration,
+        update: impl FnOnce(&mut DebouncedConfig),
+    ) -> Result<PendingChange> {
+        let config = self.current_config()?;
+        *self.messages().serial_port.lock().unwrap() = self.rfe.weak_serial_port();
+
+        let mut debounced = self.messages().debounced_config.lock().unwrap();
+        let debounced = debounced.get_or_insert_with(|| {
+            DebouncedConfig::new(
+                config.start_freq,
+                config.stop_freq,
+                config.min_amp_dbm,
+                config.max_amp_dbm,
+            )
         });
-        drop(config);
+        update(debounced);
+        let change = debounced.pending_change(self.rfe.weak_messages());
 
-        if !wait_result.timed_out() {
-            Ok(())
-        } else {
-            Err(Error::TimedOut(COMMAND_RESPONSE_TIMEOUT))
-        }
+        let messages = self.rfe.weak_messages();
+        self.rfe.debounce("config", debounce, move || {
+            if let Some(messages) = messages.upgrade() {
+                messages.send_debounced_config();
+            }
+        });
+        Ok(change)
     }
 
     /// Sets the callback that is called when the spectrum analyzer receives a sweep.
+    ///
+    /// The callback runs on a new thread without any of the spectrum analyzer's locks held, so it
+    /// can call any method, including ones that wait for the RF Explorer.
+    ///
+    /// If [`MemoryBudget::max_running_callbacks`] callbacks are already running, the sweep waits
+    /// for one of them to finish. Only the newest sweep waits, so when the callback falls behind,
+    /// older waiting sweeps are dropped and counted in [`MemoryStats::dropped_callbacks`].
     pub fn set_sweep_callback(
         &self,
         cb: impl Fn(&[f32], Frequency, Frequency) + Send + Sync + 'static,
@@ -615,7 +1485,90 @@ impl SpectrumAnalyzer {
         *self.messages().sweep_callback.lock().unwrap() = None;
     }
 
+    /// Sets a callback that borrows the amplitudes of each sweep the spectrum analyzer receives.
+    ///
+    /// Unlike [`SpectrumAnalyzer::set_sweep_callback`], the callback is called on the thread that
+    /// reads from the RF Explorer and no allocations are made for each sweep once the first few
+    /// sweeps have been received. The callback should return quickly because the next message
+    /// isn't read until it returns.
+    ///
+    /// The callback is called after the sweep is cached, so it can call getters of cached values,
+    /// like [`SpectrumAnalyzer::sweep`] and [`SpectrumAnalyzer::start_freq`], which return the
+    /// same sweep and its metadata. It can also replace or remove itself. It must not wait for the
+    /// RF Explorer, like with [`SpectrumAnalyzer::wait_for_next_sweep`] or a setter that waits for
+    /// the new config to be confirmed, because nothing is received until it returns. Debug builds
+    /// panic when it does instead of deadlocking.
+    pub fn set_sweep_callback_borrowed(&self, cb: impl FnMut(&[f32]) + Send + 'static) {
+        self.messages().borrowed_sweep_callback.set(Box::new(cb));
+    }
+
+    /// Removes the callback set with [`SpectrumAnalyzer::set_sweep_callback_borrowed`].
+    pub fn remove_sweep_callback_borrowed(&self) {
+        self.messages().borrowed_sweep_callback.remove();
+    }
+
+    /// Keeps up to `capacity` of the most recent sweeps until they're drained with
+    /// [`SpectrumAnalyzer::drain_sweep_history`], so sweeps aren't missed while they're polled
+    /// slowly.
+    ///
+    /// Once the history is full, the oldest sweep is dropped to make room for the newest one. The
+    /// default capacity of 1 only keeps the cached sweep, so it doesn't use any more memory.
+    /// Capacities of 0 are treated as 1, and capacities above the memory budget's
+    /// [`max_buffered_sweeps`](MemoryBudget::max_buffered_sweeps) are reduced to it. When the
+    /// capacity shrinks, the newest sweeps that still fit are kept.
+    pub fn set_sweep_history_capacity(&self, capacity: usize) {
+        self.messages().set_sweep_history_capacity(capacity);
+    }
+
+    /// Removes and returns the sweeps kept since the history was last drained, oldest first.
+    ///
+    /// See [`SpectrumAnalyzer::set_sweep_history_capacity`].
+    pub fn drain_sweep_history(&self) -> Vec<Sweep> {
+        self.messages().drain_sweep_history()
+    }
+
+    /// Returns the number of sweeps [`SpectrumAnalyzer::drain_sweep_history`] would return.
+    pub fn sweep_history_len(&self) -> usize {
+        self.messages().sweep_history_len()
+    }
+
+    /// Returns a receiver that buffers up to `depth` of the sweeps the spectrum analyzer receives
+    /// until they're received from it.
+    ///
+    /// Unlike the sweep callbacks, sweeps aren't missed while the receiver's owner is briefly busy,
+    /// like when a logger waits for its writes to reach the disk. When the buffer is full, the
+    /// oldest sweep is dropped. Any number of receivers can be subscribed at once, and dropping a
+    /// receiver unsubscribes it. A `depth` of 0 is treated as 1, and depths above the memory
+    /// budget's [`max_buffered_sweeps`](MemoryBudget::max_buffered_sweeps) are reduced to it.
+    pub fn subscribe_buffered(&self, depth: usize) -> SweepReceiver {
+        self.messages().subscribe_buffered(depth)
+    }
+
+    /// Sets a callback that's called with each part of a sweep as it arrives, so long sweeps can
+    /// be shown before they've been completely received.
+    ///
+    /// The callback is called with the newly received amplitudes and the index of the first one
+    /// in the sweep. Every part of a sweep is delivered in order, ending with the part that
+    /// completes it, before the sweep is delivered to the other sweep callbacks. A sweep that
+    /// turns out to be truncated or corrupted may never be completed, and a sweep that's
+    /// dropped because its length doesn't match the config's may already have been delivered in
+    /// part, so parts with an index of 0 start a new sweep.
+    ///
+    /// Like [`SpectrumAnalyzer::set_sweep_callback_borrowed`], the callback is called on the
+    /// thread that reads from the RF Explorer, so it should return quickly and mustn't wait for
+    /// the RF Explorer.
+    pub fn set_partial_sweep_callback(&self, cb: impl FnMut(&[f32], usize) + Send + 'static) {
+        self.messages().partial_sweep_callback.set(Box::new(cb));
+    }
+
+    /// Removes the callback set with [`SpectrumAnalyzer::set_partial_sweep_callback`].
+    pub fn remove_partial_sweep_callback(&self) {
+        self.messages().partial_sweep_callback.remove();
+    }
+
     /// Sets the callback that is called when the spectrum analyzer receives a `Config`.
+    ///
+    /// Like the sweep callback, it runs on a new thread without any locks held.
     pub fn set_config_callback(&self, cb: impl Fn(Config) + Send + Sync + 'static) {
         *self.messages().config_callback.lock().unwrap() = Some(Arc::new(Box::new(cb)));
     }
@@ -625,11 +1578,32 @@ impl SpectrumAnalyzer {
         *self.messages().config_callback.lock().unwrap() = None;
     }
 
+    /// Starts forwarding sweeps, configs, and screen data to a recording session's queue.
+    pub(crate) fn link_health_sampler(
+        &self,
+    ) -> impl Fn() -> Option<LinkHealth> + Send + 'static + use<> {
+        self.rfe.link_health_sampler()
+    }
+
+    pub(crate) fn set_record_queue(&self, queue: Arc<RecordQueue>) -> Result<()> {
+        let mut record_queue = self.messages().record_queue.lock().unwrap();
+        if record_queue
+            .as_ref()
+            .is_some_and(|record_queue| !record_queue.is_closed())
+        {
+            return Err(Error::InvalidOperation(
+                "The spectrum analyzer is already being recorded".to_string(),
+            ));
+        }
+        *record_queue = Some(queue);
+        Ok(())
+    }
+
     /// Sets the number of points in each sweep measured by the spectrum analyzer.
     #[tracing::instrument(skip(self))]
     pub fn set_sweep_len(&self, sweep_len: u16) -> Result<()> {
         // Only 'Plus' models can set the number of points in a sweep
-        if !self.active_radio_model().is_plus_model() {
+        if !self.capabilities().can_set_sweep_len {
             return Err(Error::InvalidOperation(
                 "Only RF Explorer 'Plus' models support setting the number of sweep points"
                     .to_string(),
@@ -643,50 +1617,30 @@ impl SpectrumAnalyzer {
         }
 
         // The requested number of sweep points gets rounded down to a number that's a multiple of 16
-        let expected_sweep_len = if sweep_len < 112 {
-            Self::MIN_SWEEP_LEN
+        let expected_sweep_len = if sweep_len < Capabilities::MIN_SWEEP_LEN {
+            Capabilities::MIN_SWEEP_LEN
         } else {
             (sweep_len / 16) * 16
         };
 
-        // Check if the current config already contains the requested sweep points
-        if self.sweep_len() == expected_sweep_len {
-            return Ok(());
-        }
-
-        // Wait until the current config contains the requested sweep points
-        info!("Waiting to receive updated config");
-        let (config, wait_result) = self.wait_for_config_while(|config| {
-            config
-                .as_ref()
-                .filter(|config| config.sweep_len == expected_sweep_len)
-                .is_none()
-        });
-        drop(config);
-
-        if !wait_result.timed_out() {
-            Ok(())
-        } else {
-            warn!("Failed to receive updated config");
-            Err(Error::TimedOut(COMMAND_RESPONSE_TIMEOUT))
-        }
+        self.wait_for_expectation(Expectation::SweepLen(expected_sweep_len))
     }
 
     /// Sets the spectrum analyzer's calculator mode.
     #[tracing::instrument(skip(self))]
-    pub fn set_calc_mode(&self, calc_mode: CalcMode) -> io::Result<()> {
+    pub fn set_calc_mode(&self, calc_mode: CalcMode) -> Result<()> {
         self.send_command(Command::SetCalcMode(calc_mode))
     }
 
     /// Sets the spectrum analyzer's input stage.
     #[tracing::instrument(skip(self))]
-    pub fn set_input_stage(&self, input_stage: InputStage) -> io::Result<()> {
+    pub fn set_input_stage(&self, input_stage: InputStage) -> Result<()> {
         self.send_command(Command::SetInputStage(input_stage))
     }
 
     /// Adds or subtracts an offset to the amplitudes in each sweep.
     #[tracing::instrument(skip(self))]
-    pub fn set_offset_db(&self, offset_db: i8) -> io::Result<()> {
+    pub fn set_offset_db(&self, offset_db: i8) -> Result<()> {
         self.send_command(Command::SetOffsetDB(offset_db))
     }
 
@@ -701,32 +1655,80 @@ impl SpectrumAnalyzer {
         // Send the command to set the DSP mode
         self.send_command(Command::SetDsp(dsp_mode))?;
 
-        // Wait to see if we receive a DSP mode message in response
-        let (lock, condvar) = &self.messages().dsp_mode;
-        let (dsp_mode, wait_result) = condvar
-            .wait_timeout_while(
-                lock.lock().unwrap(),
-                COMMAND_RESPONSE_TIMEOUT,
-                |new_dsp_mode| *new_dsp_mode != Some(dsp_mode),
-            )
-            .unwrap();
-        drop(dsp_mode);
+        self.wait_for_expectation(Expectation::DspMode(dsp_mode))
+    }
 
-        if !wait_result.timed_out() {
-            Ok(())
-        } else {
-            Err(Error::TimedOut(COMMAND_RESPONSE_TIMEOUT))
+    /// Whether the spectrum analyzer has confirmed the settings from every command that's waiting
+    /// for a response.
+    ///
+    /// Settings that weren't confirmed within the time allowed for a response, like ones from
+    /// commands the RF Explorer rejected, are no longer waited for. Returns `false` if the RF
+    /// Explorer has been disconnected.
+    pub fn is_synchronized(&self) -> bool {
+        !self.messages().disconnection.is_disconnected()
+            && self
+                .messages()
+                .expectations
+                .0
+                .lock()
+                .unwrap()
+                .is_synchronized(Instant::now())
+    }
+
+    /// Waits until the spectrum analyzer has confirmed the settings from every command that's
+    /// waiting for a response.
+    ///
+    /// Returns [`Error::TimedOut`] if the settings weren't confirmed within `timeout` and
+    /// [`Error::Disconnected`] if the RF Explorer was disconnected while waiting.
+    #[tracing::instrument(skip(self))]
+    pub fn wait_until_synchronized(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let (lock, condvar) = &self.messages().expectations;
+        let mut expectations = lock.lock().unwrap();
+        loop {
+            self.messages().disconnection.check()?;
+            let now = Instant::now();
+            let Some(next_deadline) = expectations.next_deadline(now) else {
+                return Ok(());
+            };
+            if now >= deadline {
+                return Err(Error::TimedOut(timeout));
+            }
+            // Wake up when the next expectation expires in case it's never confirmed
+            expectations = condvar
+                .wait_timeout(expectations, next_deadline.min(deadline) - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// Waits until the spectrum analyzer confirms the expected setting.
+    fn wait_for_expectation(&self, expectation: Expectation) -> Result<()> {
+        let messages = self.messages();
+        match messages.register_expectation(expectation) {
+            Some(id) => messages.wait_for_expectation(id, expectation),
+            None => Ok(()),
         }
     }
 
+    /// Waits while `condition` holds for the current config, or until the command response
+    /// timeout elapses.
+    ///
+    /// Returns [`Error::Disconnected`] as soon as the RF Explorer is disconnected.
     fn wait_for_config_while(
         &'_ self,
-        condition: impl FnMut(&mut Option<Config>) -> bool,
-    ) -> (MutexGuard<'_, Option<Config>>, WaitTimeoutResult) {
+        mut condition: impl FnMut(&mut Option<Config>) -> bool,
+    ) -> Result<(MutexGuard<'_, Option<Config>>, WaitTimeoutResult)> {
+        check_not_held(MESSAGE_READER);
         let (lock, condvar) = &self.messages().config;
-        condvar
-            .wait_timeout_while(lock.lock().unwrap(), COMMAND_RESPONSE_TIMEOUT, condition)
-            .unwrap()
+        let disconnection = &self.messages().disconnection;
+        let (config, wait_result) = condvar
+            .wait_timeout_while(lock.lock().unwrap(), COMMAND_RESPONSE_TIMEOUT, |config| {
+                condition(config) && !disconnection.is_disconnected()
+            })
+            .unwrap();
+        disconnection.check()?;
+        Ok((config, wait_result))
     }
 
     fn start_stop_from_center_span(
@@ -762,9 +1764,9 @@ impl SpectrumAnalyzer {
             ));
         }
 
-        let active_model = self.active_radio_model();
+        let capabilities = self.capabilities();
 
-        let min_max_freq = active_model.min_freq()..=active_model.max_freq();
+        let min_max_freq = capabilities.freq_range;
         if !min_max_freq.contains(&start) {
             return Err(Error::InvalidInput(format!(
                 "The start frequency {} MHz is not within the RF Explorer's frequency range of {}-{} MHz",
@@ -781,7 +1783,7 @@ impl SpectrumAnalyzer {
             )));
         }
 
-        let min_max_span = active_model.min_span()..=active_model.max_span();
+        let min_max_span = capabilities.span_range;
         if !min_max_span.contains(&(stop - start)) {
             return Err(Error::InvalidInput(format!(
                 "The span {} MHz is not within the RF Explorer's span range of {}-{} MHz",
@@ -804,94 +1806,657 @@ impl SpectrumAnalyzer {
             ));
         }
 
-        // The top and bottom amplitude must be within the RF Explorer's min and max amplitude range
-        if !Self::MIN_MAX_AMP_RANGE_DBM.contains(&min_amp_dbm) {
-            return Err(Error::InvalidInput(format!(
-                "The amplitude {} dBm is not within the RF Explorer's amplitude range of {}-{} dBm",
-                min_amp_dbm,
-                Self::MIN_MAX_AMP_RANGE_DBM.start(),
-                Self::MIN_MAX_AMP_RANGE_DBM.end()
-            )));
-        } else if !Self::MIN_MAX_AMP_RANGE_DBM.contains(&max_amp_dbm) {
-            return Err(Error::InvalidInput(format!(
-                "The amplitude {} dBm is not within the RF Explorer's amplitude range of {}-{} dBm",
-                max_amp_dbm,
-                Self::MIN_MAX_AMP_RANGE_DBM.start(),
-                Self::MIN_MAX_AMP_RANGE_DBM.end()
-            )));
+        // The top and bottom amplitude must be within the RF Explorer's min and max amplitude range,
+        // which is shifted by the amplitude offset of an external amplifier or attenuator
+        let amp_offset_db = self.amp_offset_db();
+        let amp_range = min_max_amp_range_dbm(amp_offset_db);
+        for amp_dbm in [min_amp_dbm, max_amp_dbm] {
+            if !amp_range.contains(&amp_dbm) {
+                return Err(Error::InvalidInput(format!(
+                    "The amplitude {} dBm is not within the RF Explorer's amplitude range of {}-{} dBm \
+                     with an amplitude offset of {} dB",
+                    amp_dbm,
+                    amp_range.start(),
+                    amp_range.end(),
+                    amp_offset_db.unwrap_or_default()
+                )));
+            }
         }
 
         Ok(())
     }
 }
 
+/// Returns the range of amplitudes that can be displayed with the given amplitude offset.
+///
+/// The amplitude offset is added to every amplitude the RF Explorer measures, so the displayed
+/// range moves with it.
+fn min_max_amp_range_dbm(amp_offset_db: Option<i8>) -> RangeInclusive<i16> {
+    let amp_offset_db = i16::from(amp_offset_db.unwrap_or_default());
+    SpectrumAnalyzer::MIN_MAX_AMP_RANGE_DBM.start() + amp_offset_db
+        ..=SpectrumAnalyzer::MIN_MAX_AMP_RANGE_DBM.end() + amp_offset_db
+}
+
+/// The locked most recent sweep, which is known to exist.
+struct SweepGuard<'a>(MutexGuard<'a, Option<Sweep>>);
+
+impl std::ops::Deref for SweepGuard<'_> {
+    type Target = Sweep;
+
+    fn deref(&self) -> &Sweep {
+        self.0.as_ref().expect("a sweep guard always holds a sweep")
+    }
+}
+
+/// Fills the start of the buffer with the sweep's amplitudes and returns the length of the sweep.
+fn fill_buf(sweep: &Sweep, buf: &mut [f32]) -> Result<usize> {
+    let sweep_len = sweep.amplitudes_dbm.len();
+    if buf.len() >= sweep_len {
+        buf[0..sweep_len].copy_from_slice(sweep.amplitudes_dbm.as_slice());
+        Ok(sweep_len)
+    } else {
+        Err(Error::InvalidInput(
+            "The buffer is too small to fit the sweep".to_string(),
+        ))
+    }
+}
+
+fn config_or_err(config: Option<&Config>) -> Result<&Config> {
+    config.ok_or_else(|| {
+        Error::InvalidOperation("No config has been received from the RF Explorer yet".to_string())
+    })
+}
+
 #[derive(Default)]
-struct MessageContainer {
+pub(crate) struct MessageContainer {
     pub(crate) config: (Mutex<Option<Config>>, Condvar),
+    /// Incremented every time a config that differs from the cached config is received.
+    pub(crate) config_generation: AtomicU64,
+    /// The number of configs that were dropped because their settings were impossible.
+    pub(crate) invalid_configs: AtomicU64,
     pub(crate) config_callback: Mutex<ConfigCallback<Config>>,
+    /// The start and stop frequencies that were last sent to the RF Explorer.
+    pub(crate) requested_start_stop: Mutex<Option<(Frequency, Frequency)>>,
+    /// The values requested with the debounced setters that are waiting to be sent.
+    pub(crate) debounced_config: Mutex<Option<DebouncedConfig>>,
     pub(crate) sweep: (Mutex<Option<Sweep>>, Condvar),
+    /// Incremented every time a sweep is cached.
+    pub(crate) sweep_sequence: AtomicU64,
+    /// Wakes the async waiters for the next sweep.
+    pub(crate) sweep_signal: AsyncSignal,
+    /// The most recent sweeps received since the config last changed, used to suggest amplitudes.
+    pub(crate) auto_range_sweeps: Mutex<SweepHistory>,
+    pub(crate) sweep_rate: Mutex<SweepRate>,
     pub(crate) sweep_callback: Mutex<Option<SweepCallback>>,
-    pub(crate) screen_data: (Mutex<Option<ScreenData>>, Condvar),
+    pub(crate) borrowed_sweep_callback: ReaderCallback<BorrowedSweepCallback>,
+    /// The amplitudes passed to the borrowed sweep callback, reused for every sweep.
+    pub(crate) borrowed_sweep_buffer: Mutex<Vec<f32>>,
+    pub(crate) partial_sweep_callback: ReaderCallback<PartialSweepCallback>,
+    /// The buffers of the receivers created with `SpectrumAnalyzer::subscribe_buffered`.
+    pub(crate) sweep_subscribers: SweepSubscribers,
+    /// The sweeps kept for `SpectrumAnalyzer::drain_sweep_history`.
+    pub(crate) retained_sweeps: Mutex<RetainedSweeps>,
+    pub(crate) partial_sweep_decoder: Mutex<PartialSweepDecoder>,
+    pub(crate) mismatched_sweep_policy: Mutex<MismatchedSweepPolicy>,
+    pub(crate) mismatched_sweep_stats: Mutex<MismatchedSweepStats>,
+    pub(crate) corrupt_sweeps: Mutex<CorruptionDetector>,
+    pub(crate) frozen_sweeps: Mutex<FrozenSweepDetector>,
+    pub(crate) frozen_sweep_callback: Mutex<Option<FrozenSweepCallback>>,
+    pub(crate) auto_input_stage: Mutex<AutoInputStageController>,
+    pub(crate) input_stage_transition_callback: Mutex<Option<InputStageTransitionCallback>>,
+    /// The commands waiting for the thread reading messages to send them.
+    pub(crate) commands: Mutex<Vec<Cow<'static, [u8]>>>,
+    /// The serial port used to send commands from the thread reading messages, like rebooting
+    /// the RF Explorer when its sweeps are frozen.
+    pub(crate) serial_port: Mutex<Weak<SerialPort>>,
+    pub(crate) screen_data: ScreenDataCache,
+    /// Runs the callbacks that are called on their own threads.
+    pub(crate) callback_threads: CallbackThreads,
+    pub(crate) memory_budget: Mutex<MemoryBudget>,
     pub(crate) dsp_mode: (Mutex<Option<DspMode>>, Condvar),
     pub(crate) tracking_status: (Mutex<Option<TrackingStatus>>, Condvar),
     pub(crate) input_stage: (Mutex<Option<InputStage>>, Condvar),
     pub(crate) setup_info: (Mutex<Option<SetupInfo>>, Condvar),
     pub(crate) serial_number: (Mutex<Option<SerialNumber>>, Condvar),
+    pub(crate) record_queue: Mutex<Option<Arc<RecordQueue>>>,
+    /// The events recorded with `SpectrumAnalyzer::annotate_event` since the last sweep.
+    pub(crate) events: EventLog,
+    pub(crate) latency_estimate: Mutex<Option<Duration>>,
+    pub(crate) latency_adjustment_disabled: AtomicBool,
+    pub(crate) disconnection: Disconnection,
+    pub(crate) expectations: (Mutex<Expectations>, Condvar),
+    /// Whether a message has been read from the RF Explorer, as opposed to seeded from a cache.
+    pub(crate) message_received: (Mutex<bool>, Condvar),
 }
 
 type SweepCallback = Arc<Box<dyn Fn(&[f32], Frequency, Frequency) + Send + Sync + 'static>>;
+type BorrowedSweepCallback = dyn FnMut(&[f32]) + Send + 'static;
+type PartialSweepCallback = dyn FnMut(&[f32], usize) + Send + 'static;
+type FrozenSweepCallback = Arc<Box<dyn Fn() + Send + Sync + 'static>>;
+type InputStageTransitionCallback = Arc<Box<dyn Fn(InputStageTransition) + Send + Sync + 'static>>;
+
+impl MessageContainer {
+    /// Fills in the messages saved in a cached identity, before any are read from the RF
+    /// Explorer.
+    fn seed(&self, identity: &CachedIdentity) {
+        use crate::common::MessageContainer as _;
+
+        for message in identity.messages() {
+            self.cache_message(message);
+        }
+        *self.message_received.0.lock().unwrap() = false;
+    }
+
+    /// Waits until any message is read from the RF Explorer, which shows it's alive.
+    fn wait_for_any_message(&self) -> ConnectionResult<()> {
+        let (lock, cvar) = &self.message_received;
+        if *cvar
+            .wait_timeout_while(
+                lock.lock().unwrap(),
+                RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT,
+                |message_received| !*message_received,
+            )
+            .unwrap()
+            .0
+        {
+            Ok(())
+        } else {
+            Err(ConnectionError::DeviceInfoNotReceived)
+        }
+    }
+
+    /// Registers an expectation for a command that was just sent and returns its ID, or `None`
+    /// if the current config and DSP mode already confirm it.
+    fn register_expectation(&self, expectation: Expectation) -> Option<ExpectationId> {
+        self.expectations.0.lock().unwrap().register(
+            expectation,
+            self.config.0.lock().unwrap().as_ref(),
+            *self.dsp_mode.0.lock().unwrap(),
+            Instant::now() + COMMAND_RESPONSE_TIMEOUT,
+        )
+    }
+
+    /// Waits until a registered expectation is confirmed or expires.
+    pub(crate) fn wait_for_expectation(
+        &self,
+        id: ExpectationId,
+        expectation: Expectation,
+    ) -> Result<()> {
+        check_not_held(MESSAGE_READER);
+        let (lock, condvar) = &self.expectations;
+        let mut expectations = lock.lock().unwrap();
+        trace!("Waiting for the RF Explorer to confirm {expectation:?}");
+        loop {
+            self.disconnection.check()?;
+            let now = Instant::now();
+            match expectations.status(id, now) {
+                ExpectationStatus::Met => return Ok(()),
+                ExpectationStatus::Expired => {
+                    warn!("The RF Explorer didn't confirm {expectation:?}");
+                    return Err(Error::TimedOut(COMMAND_RESPONSE_TIMEOUT));
+                }
+                ExpectationStatus::Pending(deadline) => {
+                    expectations = condvar
+                        .wait_timeout(expectations, deadline - now)
+                        .unwrap()
+                        .0;
+                }
+            }
+        }
+    }
+
+    /// Sends the values requested with the debounced setters, which is called on the device's
+    /// timer thread once the debounce duration has elapsed without newer values.
+    fn send_debounced_config(&self) {
+        let Some(debounced) = self.debounced_config.lock().unwrap().take() else {
+            return;
+        };
+        let Some(serial_port) = self.serial_port.lock().unwrap().upgrade() else {
+            return;
+        };
+        let command = Command::SetConfig {
+            start: debounced.start,
+            stop: debounced.stop,
+            min_amp_dbm: debounced.min_amp_dbm,
+            max_amp_dbm: debounced.max_amp_dbm,
+        };
+        if let Err(error) = serial_port.send_command(command) {
+            warn!(%error, "Failed to send the debounced config");
+            debounced.failed(error);
+            return;
+        }
+        if debounced.start_stop_requested {
+            *self.requested_start_stop.lock().unwrap() = Some((debounced.start, debounced.stop));
+        }
+        let id = self.register_expectation(debounced.expectation());
+        debounced.sent(id);
+    }
+
+    fn call_config_callback(&self) {
+        // Copy the callback and the config so neither lock is held while the callback runs
+        let cb = self.config_callback.lock().unwrap().clone();
+        let config = self.config.0.lock().unwrap().clone();
+        if let (Some(cb), Some(config)) = (cb, config) {
+            self.callback_threads
+                .spawn_latest(&self.config_callback, move || cb(config));
+        }
+    }
+
+    pub(crate) fn memory_budget(&self) -> MemoryBudget {
+        *self.memory_budget.lock().unwrap()
+    }
+
+    pub(crate) fn set_memory_budget(&self, budget: MemoryBudget) {
+        *self.memory_budget.lock().unwrap() = budget;
+        self.callback_threads
+            .set_max_running(budget.max_running_callbacks);
+        self.events.set_capacity(budget.max_pending_events);
+        let sweep = self.sweep.0.lock().unwrap();
+        let mut retained_sweeps = self.retained_sweeps.lock().unwrap();
+        if retained_sweeps.capacity() > budget.max_buffered_sweeps {
+            retained_sweeps.set_capacity(budget.max_buffered_sweeps, sweep.as_ref());
+        }
+    }
+
+    fn subscribe_buffered(&self, depth: usize) -> SweepReceiver {
+        let max_depth = self.memory_budget().max_buffered_sweeps;
+        self.sweep_subscribers.subscribe(depth.min(max_depth))
+    }
+
+    fn set_sweep_history_capacity(&self, capacity: usize) {
+        let max_capacity = self.memory_budget().max_buffered_sweeps;
+        let sweep = self.sweep.0.lock().unwrap();
+        self.retained_sweeps
+            .lock()
+            .unwrap()
+            .set_capacity(capacity.min(max_capacity), sweep.as_ref());
+    }
+
+    fn drain_sweep_history(&self) -> Vec<Sweep> {
+        let sweep = self.sweep.0.lock().unwrap();
+        self.retained_sweeps.lock().unwrap().drain(sweep.as_ref())
+    }
+
+    fn sweep_history_len(&self) -> usize {
+        let sweep = self.sweep.0.lock().unwrap();
+        self.retained_sweeps.lock().unwrap().len(sweep.as_ref())
+    }
+
+    /// Returns an estimate of the memory used by the cached messages and the buffers that are
+    /// filled from them, in bytes.
+    pub(crate) fn memory_usage_estimate(&self) -> usize {
+        let sweep = self.sweep.0.lock().unwrap().as_ref().map_or(0, |sweep| {
+            sweep.amplitudes_dbm.capacity() * size_of::<f32>()
+        });
+        let record_queue = self
+            .record_queue
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |record_queue| record_queue.memory_usage_estimate());
+        sweep
+            + self
+                .auto_range_sweeps
+                .lock()
+                .unwrap()
+                .memory_usage_estimate()
+            + self.sweep_subscribers.memory_usage_estimate()
+            + self.retained_sweeps.lock().unwrap().memory_usage_estimate()
+            + self.events.memory_usage_estimate()
+            + self.screen_data.memory_usage_estimate()
+            + record_queue
+    }
+
+    pub(crate) fn memory_stats(&self) -> MemoryStats {
+        MemoryStats {
+            dropped_callbacks: self.callback_threads.dropped(),
+            dropped_events: self.events.dropped_events(),
+            ..MemoryStats::default()
+        }
+    }
+
+    fn record(&self, record: impl FnOnce() -> Record) {
+        if let Some(record_queue) = self.record_queue.lock().unwrap().as_ref()
+            && !record_queue.is_closed()
+        {
+            record_queue.push(record());
+        }
+    }
+
+    /// Updates the metadata with the settings that are in effect for sweeps received now.
+    ///
+    /// The metadata is updated in place so that no allocations are needed when it's unchanged.
+    fn update_sweep_meta(&self, meta: &mut SweepMeta, received_at: DateTime<Utc>) {
+        meta.captured_at_adjusted = received_at - self.latency_adjustment();
+        meta.config_generation = self.config_generation.load(Ordering::Relaxed);
+        self.events
+            .take_until(received_at, &mut meta.events_since_previous_sweep);
+        meta.input_stage = *self.input_stage.0.lock().unwrap();
+        let config = self.config.0.lock().unwrap();
+        meta.amp_offset_db = config.as_ref().and_then(|config| config.amp_offset_db);
+        (meta.start_freq, meta.stop_freq) = config
+            .as_ref()
+            .map(|config| (config.start_freq, config.stop_freq))
+            .unwrap_or_default();
+        drop(config);
+        if let Some(setup_info) = self.setup_info.0.lock().unwrap().as_ref()
+            && *meta.firmware_version != *setup_info.firmware_version
+        {
+            meta.firmware_version.clear();
+            meta.firmware_version.push_str(&setup_info.firmware_version);
+        }
+    }
+
+    /// Returns whether the sweep was received with the current config and matches its length.
+    fn is_sweep_from_current_config(&self, sweep: &Sweep) -> bool {
+        sweep.meta.config_generation == self.config_generation.load(Ordering::Relaxed)
+            && self
+                .config
+                .0
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|config| usize::from(config.sweep_len) == sweep.amplitudes_dbm.len())
+    }
+
+    /// Applies the mismatched sweep policy if the sweep's length doesn't match the config's.
+    ///
+    /// Returns `None` if the sweep should be dropped. Otherwise, returns the length the sweep was
+    /// received with if it didn't match.
+    fn fit_sweep_to_config(&self, amplitudes_dbm: &mut Vec<f32>) -> Option<Option<usize>> {
+        let received_len = amplitudes_dbm.len();
+        let sweep_len = self
+            .config
+            .0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|config| usize::from(config.sweep_len));
+        match sweep_len {
+            Some(sweep_len) if sweep_len != received_len => {
+                let policy = *self.mismatched_sweep_policy.lock().unwrap();
+                let mut stats = self.mismatched_sweep_stats.lock().unwrap();
+                policy
+                    .apply(amplitudes_dbm, sweep_len, &mut stats)
+                    .then_some(Some(received_len))
+            }
+            _ => Some(None),
+        }
+    }
+
+    /// Removes the expectations confirmed by the most recent config and DSP mode and wakes up
+    /// anything waiting for them.
+    fn confirm_expectations(&self) {
+        // The expectations are locked before the config and DSP mode so that a setter can't
+        // register an expectation between them being updated and being checked
+        let mut expectations = self.expectations.0.lock().unwrap();
+        if expectations.confirm(
+            self.config.0.lock().unwrap().as_ref(),
+            *self.dsp_mode.0.lock().unwrap(),
+        ) {
+            self.expectations.1.notify_all();
+        }
+    }
+
+    /// Returns the most points the radio module that sent the config can put in a sweep, or the
+    /// most that any RF Explorer can if its model isn't known yet.
+    fn max_sweep_len(&self, config: &Config) -> u16 {
+        self.setup_info
+            .0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|setup_info| {
+                if config.is_expansion_radio_module_active {
+                    setup_info.expansion_radio_model
+                } else {
+                    setup_info.main_radio_model
+                }
+            })
+            .map_or(Capabilities::MAX_SWEEP_LEN, Capabilities::max_sweep_len)
+    }
+
+    /// Notifies the callback and reboots the RF Explorer if the sweep shows it has frozen.
+    fn watch_for_frozen_sweeps(&self, amplitudes_dbm: &[f32]) {
+        let Some(watchdog) = self.frozen_sweeps.lock().unwrap().on_sweep(amplitudes_dbm) else {
+            return;
+        };
+        warn!(
+            identical_sweeps = watchdog.identical_sweeps,
+            "The RF Explorer's sweeps have stopped changing"
+        );
+        let cb = self.frozen_sweep_callback.lock().unwrap().clone();
+        if let Some(cb) = cb {
+            self.callback_threads
+                .spawn_latest(&self.frozen_sweep_callback, move || cb());
+        }
+        if watchdog.reboot {
+            let serial_port = self.serial_port.lock().unwrap().clone();
+            self.callback_threads
+                .spawn_latest(&self.frozen_sweeps, move || {
+                    frozen_sweep::reboot_and_request_config(&serial_port, watchdog.reboot_delay);
+                });
+        }
+    }
+
+    /// Delivers the part of a completed sweep that the partial sweep callback hasn't received.
+    fn finish_partial_sweep(&self, amplitudes_dbm: &[f32]) {
+        let mut decoder = self.partial_sweep_decoder.lock().unwrap();
+        let offset = decoder.decoded();
+        if self.partial_sweep_callback.is_set()
+            && let Some(rest) = amplitudes_dbm.get(offset..)
+            && !rest.is_empty()
+        {
+            self.partial_sweep_callback.call(|cb| cb(rest, offset));
+        }
+        decoder.reset();
+    }
+
+    /// Lets the automatic input stage controller switch input stages and returns whether the
+    /// sweep was received while the RF Explorer was switching.
+    fn control_input_stage(&self, amplitudes_dbm: &[f32], meta: &SweepMeta) -> bool {
+        let peak_dbm = amplitudes_dbm
+            .iter()
+            .copied()
+            .filter(|amp_dbm| amp_dbm.is_finite())
+            .reduce(f32::max);
+        let transition = match self
+            .auto_input_stage
+            .lock()
+            .unwrap()
+            .on_sweep(peak_dbm, meta.input_stage)
+        {
+            InputStageDecision::Keep => return false,
+            InputStageDecision::Settling => return true,
+            InputStageDecision::Switch(transition) => transition,
+        };
+
+        info!(
+            from = ?transition.from,
+            to = ?transition.to,
+            peak_dbm = transition.peak_dbm,
+            "Switching input stages automatically"
+        );
+        // Not every firmware version reports its input stage after it changes, so the new one is
+        // assumed so that later sweeps are corrected for it
+        *self.input_stage.0.lock().unwrap() = Some(transition.to);
+        self.input_stage.1.notify_one();
+        self.commands
+            .lock()
+            .unwrap()
+            .push(Command::SetInputStage(transition.to).into());
+        // Run the user-provided callback on a callback thread so that it can't block reading from
+        // the RF Explorer
+        if let Some(cb) = self.input_stage_transition_callback.lock().unwrap().clone() {
+            let key = &self.input_stage_transition_callback;
+            self.callback_threads
+                .spawn_latest(key, move || cb(transition));
+        }
+        false
+    }
+
+    /// Returns how much earlier sweeps are estimated to have been captured than received.
+    fn latency_adjustment(&self) -> TimeDelta {
+        if self.latency_adjustment_disabled.load(Ordering::Relaxed) {
+            return TimeDelta::zero();
+        }
+        self.latency_estimate
+            .lock()
+            .unwrap()
+            .and_then(|estimate| TimeDelta::from_std(estimate).ok())
+            .unwrap_or_default()
+    }
+
+    fn sweep_start_stop(&self) -> (Frequency, Frequency) {
+        let config = self.config.0.lock().unwrap();
+        (
+            config
+                .as_ref()
+                .map(|config| config.start_freq)
+                .unwrap_or_default(),
+            config
+                .as_ref()
+                .map(|config| config.stop_freq)
+                .unwrap_or_default(),
+        )
+    }
+}
 
 impl crate::common::MessageContainer for MessageContainer {
     type Message = super::Message;
 
     fn cache_message(&self, message: Self::Message) {
+        {
+            let mut message_received = self.message_received.0.lock().unwrap();
+            if !*message_received {
+                *message_received = true;
+                self.message_received.1.notify_all();
+            }
+        }
         match message {
             Self::Message::Config(config) => {
-                *self.config.0.lock().unwrap() = Some(config);
+                if let Err(error) = config.validate(self.max_sweep_len(&config)) {
+                    warn!(%error, "Dropped a corrupt config");
+                    self.invalid_configs.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                self.record(|| Record::Config {
+                    timestamp: config.timestamp,
+                    config: config.clone(),
+                });
+                {
+                    let mut cached_config = self.config.0.lock().unwrap();
+                    if cached_config.as_ref() != Some(&config) {
+                        self.config_generation.fetch_add(1, Ordering::Relaxed);
+                        self.auto_range_sweeps.lock().unwrap().clear();
+                        self.corrupt_sweeps.lock().unwrap().reset();
+                        self.sweep_rate.lock().unwrap().reset();
+                    }
+                    *cached_config = Some(config);
+                }
                 self.config.1.notify_one();
-                if let Some(cb) = self.config_callback.lock().unwrap().clone()
-                    && let Some(config) = self.config.0.lock().unwrap().clone()
+                self.confirm_expectations();
+                self.call_config_callback();
+            }
+            Self::Message::Sweep(mut sweep) => {
+                self.finish_partial_sweep(&sweep.amplitudes_dbm);
+                let Some(mismatched_len) = self.fit_sweep_to_config(&mut sweep.amplitudes_dbm)
+                else {
+                    return;
+                };
+                let suspected_corrupt = match self
+                    .corrupt_sweeps
+                    .lock()
+                    .unwrap()
+                    .on_sweep(&sweep.amplitudes_dbm)
                 {
-                    // Run the user-provided callback on a new thread so that it can't
-                    // block reading from the RF Explorer
-                    thread::spawn(move || {
-                        cb(config);
+                    CorruptionVerdict::Clean => false,
+                    CorruptionVerdict::Flagged => true,
+                    CorruptionVerdict::Dropped => {
+                        warn!("Dropped a sweep that was probably corrupted");
+                        return;
+                    }
+                };
+                self.auto_range_sweeps
+                    .lock()
+                    .unwrap()
+                    .push(&sweep.amplitudes_dbm);
+                self.sweep_rate.lock().unwrap().on_sweep(sweep.timestamp);
+                self.watch_for_frozen_sweeps(&sweep.amplitudes_dbm);
+                let (start_freq, stop_freq) = self.sweep_start_stop();
+                let mut cached_sweep = self.sweep.0.lock().unwrap();
+                let encoding = sweep.meta.encoding;
+                if let Some(previous_sweep) = cached_sweep.as_mut() {
+                    sweep.meta = mem::take(&mut previous_sweep.meta);
+                }
+                self.update_sweep_meta(&mut sweep.meta, sweep.timestamp);
+                sweep.meta.encoding = encoding;
+                sweep.meta.mismatched_len = mismatched_len;
+                sweep.meta.suspected_corrupt = suspected_corrupt;
+                sweep.meta.input_stage_settling =
+                    self.control_input_stage(&sweep.amplitudes_dbm, &sweep.meta);
+                self.record(|| Record::Sweep {
+                    timestamp: sweep.timestamp,
+                    start_freq,
+                    stop_freq,
+                    amplitudes_dbm: sweep.amplitudes_dbm.clone(),
+                });
+                let mut retained_sweeps = self.retained_sweeps.lock().unwrap();
+                if retained_sweeps.is_copying() {
+                    retained_sweeps.push(sweep.clone());
+                } else {
+                    retained_sweeps.push_latest();
+                }
+                if !self.sweep_subscribers.is_empty() {
+                    self.sweep_subscribers.push(BufferedSweep {
+                        amplitudes_dbm: sweep.amplitudes_dbm.clone(),
+                        start_freq,
+                        stop_freq,
+                        timestamp: sweep.timestamp,
+                        meta: sweep.meta.clone(),
                     });
                 }
-            }
-            Self::Message::Sweep(sweep) => {
-                *self.sweep.0.lock().unwrap() = Some(sweep);
+                drop(retained_sweeps);
+                // Copy the amplitudes for the callback so the sweep's lock isn't held while it runs
+                let sweep_callback = self.sweep_callback.lock().unwrap().clone();
+                let callback_amplitudes_dbm = sweep_callback
+                    .as_ref()
+                    .map(|_| sweep.amplitudes_dbm.clone());
+                // Copy the amplitudes for the borrowed callback into a reused buffer so it can be
+                // called after the sweep is cached and its lock is released
+                let borrowed_callback_is_set = self.borrowed_sweep_callback.is_set();
+                if borrowed_callback_is_set {
+                    let mut borrowed_amplitudes_dbm = self.borrowed_sweep_buffer.lock().unwrap();
+                    borrowed_amplitudes_dbm.clear();
+                    borrowed_amplitudes_dbm.extend_from_slice(&sweep.amplitudes_dbm);
+                }
+                // Replacing the previous sweep returns its amplitude buffer to the pool
+                *cached_sweep = Some(sweep);
+                self.sweep_sequence.fetch_add(1, Ordering::Relaxed);
+                drop(cached_sweep);
                 self.sweep.1.notify_one();
-                if let Some(cb) = self.sweep_callback.lock().unwrap().clone() {
-                    let (start_freq, stop_freq) = {
-                        let config = self.config.0.lock().unwrap();
-                        (
-                            config
-                                .as_ref()
-                                .map(|config| config.start_freq)
-                                .unwrap_or_default(),
-                            config
-                                .as_ref()
-                                .map(|config| config.stop_freq)
-                                .unwrap_or_default(),
-                        )
-                    };
-                    if let Some(sweep) = self.sweep.0.lock().unwrap().clone() {
-                        // Run the user-provided callback on a new thread so that it can't
-                        // block reading from the RF Explorer
-                        thread::spawn(move || {
-                            cb(sweep.amplitudes_dbm.as_slice(), start_freq, stop_freq);
+                self.sweep_signal.notify();
+                if borrowed_callback_is_set {
+                    let borrowed_amplitudes_dbm = self.borrowed_sweep_buffer.lock().unwrap();
+                    self.borrowed_sweep_callback
+                        .call(|cb| cb(&borrowed_amplitudes_dbm));
+                }
+                if let (Some(cb), Some(amplitudes_dbm)) = (sweep_callback, callback_amplitudes_dbm)
+                {
+                    self.callback_threads
+                        .spawn_latest(&self.sweep_callback, move || {
+                            cb(amplitudes_dbm.as_slice(), start_freq, stop_freq);
                         });
-                    }
                 }
             }
             Self::Message::ScreenData(screen_data) => {
-                *self.screen_data.0.lock().unwrap() = Some(screen_data);
-                self.screen_data.1.notify_one();
+                let screen_data = self.screen_data.cache(screen_data, &self.callback_threads);
+                self.record(|| Record::ScreenData(screen_data));
             }
             Self::Message::DspMode(dsp_mode) => {
                 *self.dsp_mode.0.lock().unwrap() = Some(dsp_mode);
                 self.dsp_mode.1.notify_one();
+                self.confirm_expectations();
             }
             Self::Message::InputStage(input_stage) => {
                 *self.input_stage.0.lock().unwrap() = Some(input_stage);
@@ -902,50 +2467,89 @@ impl crate::common::MessageContainer for MessageContainer {
                 self.tracking_status.1.notify_one();
             }
             Self::Message::SerialNumber(serial_number) => {
-                *self.serial_number.0.lock().unwrap() = Some(serial_number);
+                let previous = self
+                    .serial_number
+                    .0
+                    .lock()
+                    .unwrap()
+                    .replace(serial_number.clone());
                 self.serial_number.1.notify_one();
+                // Only a cached serial number can be replaced by a different one
+                if previous.is_some_and(|previous| previous != serial_number) {
+                    warn!("The RF Explorer's serial number differs from the cached one");
+                    self.call_config_callback();
+                }
             }
             Self::Message::SetupInfo(setup_info) => {
-                *self.setup_info.0.lock().unwrap() = Some(setup_info);
+                let previous = self
+                    .setup_info
+                    .0
+                    .lock()
+                    .unwrap()
+                    .replace(setup_info.clone());
                 self.setup_info.1.notify_one();
+                if previous.is_some_and(|previous| previous != setup_info) {
+                    warn!("The RF Explorer's setup info differs from the cached one");
+                    self.call_config_callback();
+                }
             }
         }
     }
 
+    fn disconnect(&self, error: io::Error) {
+        self.disconnection.set(error);
+        // Lock each value before notifying so waiters can't miss the wake up between checking
+        // whether they've been disconnected and starting to wait
+        {
+            let _config = self.config.0.lock().unwrap();
+            self.config.1.notify_all();
+        }
+        let _sweep = self.sweep.0.lock().unwrap();
+        self.sweep.1.notify_all();
+        self.sweep_signal.notify();
+        self.screen_data.notify_disconnected();
+        if let Some(error) = self.disconnection.error() {
+            self.sweep_subscribers.disconnect(error);
+        }
+        // Nothing can be confirmed anymore, so stop waiting for the expected settings
+        self.expectations.0.lock().unwrap().clear();
+        self.expectations.1.notify_all();
+    }
+
+    fn reconnect(&self) {
+        self.disconnection.clear();
+        self.sweep_subscribers.reconnect();
+    }
+
+    fn take_commands(&self) -> Vec<Cow<'static, [u8]>> {
+        mem::take(&mut *self.commands.lock().unwrap())
+    }
+
     fn wait_for_device_info(&self) -> ConnectionResult<()> {
-        let (config_lock, config_cvar) = &self.config;
-        let (setup_info_lock, setup_info_cvar) = &self.setup_info;
+        self.wait_for_device_info_with_progress(&|_| {})
+    }
 
-        // Check to see if we've already received a Config and SetupInfo
-        if config_lock.lock().unwrap().is_some() && setup_info_lock.lock().unwrap().is_some() {
-            return Ok(());
+    fn on_partial_message(&self, bytes: &[u8]) {
+        let mut decoder = self.partial_sweep_decoder.lock().unwrap();
+        if bytes.is_empty() {
+            decoder.reset();
+            return;
         }
-
-        // Wait to see if we receive a Config and SetupInfo before timing out
-        if config_cvar
-            .wait_timeout_while(
-                config_lock.lock().unwrap(),
-                RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT,
-                |config| config.is_none(),
-            )
-            .unwrap()
-            .0
-            .is_some()
-            && setup_info_cvar
-                .wait_timeout_while(
-                    setup_info_lock.lock().unwrap(),
-                    RECEIVE_INITIAL_DEVICE_INFO_TIMEOUT,
-                    |setup_info| setup_info.is_none(),
-                )
-                .unwrap()
-                .0
-                .is_some()
+        // Sweeps are only decoded while they arrive if they're wanted
+        if self.partial_sweep_callback.is_set()
+            && let Some((offset, amplitudes_dbm)) = decoder.decode(bytes)
         {
-            Ok(())
-        } else {
-            Err(ConnectionError::DeviceInfoNotReceived)
+            self.partial_sweep_callback
+                .call(|cb| cb(amplitudes_dbm, offset));
         }
     }
+
+    fn wait_for_device_info_with_progress(
+        &self,
+        on_waiting: &dyn Fn(Duration),
+    ) -> ConnectionResult<()> {
+        rf_explorer::wait_for_device_info(&self.config, &self.setup_info, on_waiting)
+    }
 }
 
 impl Debug for MessageContainer {
@@ -953,12 +2557,230 @@ impl Debug for MessageContainer {
         f.debug_struct("MessageContainer")
             .field("config", &self.config.0.lock().unwrap())
             .field("sweep", &self.sweep.0.lock().unwrap())
-            .field("screen_data", &self.screen_data.0.lock().unwrap())
+            .field("screen_data", &self.screen_data)
             .field("dsp_mode", &self.dsp_mode.0.lock().unwrap())
             .field("tracking_status", &self.tracking_status.0.lock().unwrap())
             .field("input_stage", &self.input_stage.0.lock().unwrap())
             .field("setup_info", &self.setup_info.0.lock().unwrap())
             .field("serial_number", &self.serial_number.0.lock().unwrap())
+            .field("latency_estimate", &self.latency_estimate.lock().unwrap())
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, thread};
+
+    use super::*;
+
+    #[test]
+    fn fail_without_config() {
+        assert!(matches!(
+            config_or_err(None),
+            Err(Error::InvalidOperation(_))
+        ));
+    }
+
+    /// Returns a container that has received a 112-point config starting at 430 MHz.
+    fn container_with_config() -> MessageContainer {
+        use crate::common::MessageContainer as _;
+
+        let messages = MessageContainer::default();
+        messages.cache_message(
+            super::super::Message::try_from(
+                b"#C2-F:0430000,0178571,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\r\n"
+                    .as_ref(),
+            )
+            .unwrap(),
+        );
+        messages
+    }
+
+    /// Caches a synthetic sweep whose amplitudes are all `-amp / 2` dBm.
+    fn cache_sweep(messages: &MessageContainer, amp: u8) {
+        use crate::common::MessageContainer as _;
+
+        let mut bytes = b"$S\x70".to_vec();
+        bytes.extend([amp; 112]);
+        bytes.extend(b"\r\n");
+        messages.cache_message(super::super::Message::try_from(bytes.as_ref()).unwrap());
+    }
+
+    fn first_amps(sweeps: &[Sweep]) -> Vec<f32> {
+        sweeps.iter().map(|sweep| sweep.amplitudes_dbm[0]).collect()
+    }
+
+    #[test]
+    fn keep_only_latest_sweep_by_default() {
+        let messages = container_with_config();
+        assert_eq!(messages.sweep_history_len(), 0);
+        assert!(messages.drain_sweep_history().is_empty());
+
+        for amp in [100, 102, 104] {
+            cache_sweep(&messages, amp);
+        }
+        assert_eq!(messages.sweep_history_len(), 1);
+        let sweeps = messages.drain_sweep_history();
+        assert_eq!(first_amps(&sweeps), [-52.]);
+        assert_eq!(sweeps[0].meta().start_freq, Frequency::from_mhz(430));
+        assert_eq!(messages.sweep_history_len(), 0);
+        assert!(messages.drain_sweep_history().is_empty());
+        assert_eq!(
+            messages
+                .retained_sweeps
+                .lock()
+                .unwrap()
+                .memory_usage_estimate(),
+            0
+        );
+    }
+
+    #[test]
+    fn drop_oldest_sweeps_from_full_history() {
+        let messages = container_with_config();
+        cache_sweep(&messages, 100);
+        // The cached sweep hasn't been drained, so it's the oldest sweep in the history
+        messages.set_sweep_history_capacity(3);
+        for amp in [102, 104, 106, 108] {
+            cache_sweep(&messages, amp);
+        }
+        assert_eq!(messages.sweep_history_len(), 3);
+        assert_eq!(
+            first_amps(&messages.drain_sweep_history()),
+            [-52., -53., -54.]
+        );
+        assert_eq!(messages.sweep_history_len(), 0);
+
+        for amp in [110, 112, 114] {
+            cache_sweep(&messages, amp);
+        }
+        messages.set_sweep_history_capacity(2);
+        assert_eq!(first_amps(&messages.drain_sweep_history()), [-56., -57.]);
+
+        // Going back to the default keeps only the undrained latest sweep
+        cache_sweep(&messages, 116);
+        messages.set_sweep_history_capacity(1);
+        assert_eq!(first_amps(&messages.drain_sweep_history()), [-58.]);
+        assert_eq!(
+            messages
+                .retained_sweeps
+                .lock()
+                .unwrap()
+                .memory_usage_estimate(),
+            0
+        );
+    }
+
+    #[test]
+    fn limit_sweep_history_to_memory_budget() {
+        let messages = container_with_config();
+        messages.set_sweep_history_capacity(100);
+        for amp in 0..80 {
+            cache_sweep(&messages, 100 + amp);
+        }
+        assert_eq!(
+            messages.sweep_history_len(),
+            MemoryBudget::default().max_buffered_sweeps
+        );
+
+        messages.set_memory_budget(MemoryBudget {
+            max_buffered_sweeps: 4,
+            ..MemoryBudget::default()
+        });
+        assert_eq!(
+            first_amps(&messages.drain_sweep_history()),
+            [-88., -88.5, -89., -89.5]
+        );
+    }
+
+    #[test]
+    fn stay_within_memory_budget_when_flooded_with_sweeps() {
+        let messages = container_with_config();
+        messages.set_memory_budget(MemoryBudget {
+            max_buffered_sweeps: 16,
+            max_pending_events: 10,
+            ..MemoryBudget::default()
+        });
+        // A callback that's much slower than the sweeps arrive
+        *messages.sweep_callback.lock().unwrap() = Some(Arc::new(Box::new(|_, _, _| {
+            thread::sleep(Duration::from_millis(50))
+        })));
+        let receiver = messages.subscribe_buffered(10_000);
+
+        let estimate_after_first_sweep = {
+            let mut estimate = 0;
+            for i in 0..5000 {
+                cache_sweep(&messages, 100 + (i % 50) as u8);
+                if i == 0 {
+                    estimate = messages.memory_usage_estimate();
+                }
+            }
+            estimate
+        };
+        for _ in 0..20 {
+            messages.events.push(Event {
+                timestamp: Utc::now(),
+                label: "trigger".to_string(),
+            });
+        }
+
+        let stats = messages.memory_stats();
+        assert!(stats.dropped_callbacks > 0);
+        assert_eq!(stats.dropped_events, 10);
+        assert_eq!(receiver.len(), 16);
+        assert_eq!(receiver.dropped_sweeps(), 5000 - 16);
+        // Only the fixed-size buffers grew after the first sweep
+        assert!(messages.memory_usage_estimate() < estimate_after_first_sweep + 64 * 1024);
+    }
+
+    #[test]
+    fn keep_only_newest_sweep_waiting_for_slow_callback() {
+        let messages = container_with_config();
+        messages.set_memory_budget(MemoryBudget {
+            max_running_callbacks: 1,
+            ..MemoryBudget::default()
+        });
+        let (release_tx, release_rx) = mpsc::channel();
+        let (amp_tx, amp_rx) = mpsc::channel();
+        let (release_rx, amp_tx) = (Mutex::new(release_rx), Mutex::new(amp_tx));
+        *messages.sweep_callback.lock().unwrap() =
+            Some(Arc::new(Box::new(move |amps: &[f32], _, _| {
+                release_rx.lock().unwrap().recv().unwrap();
+                amp_tx.lock().unwrap().send(amps[0]).unwrap();
+            })));
+
+        for amp in 200..204 {
+            cache_sweep(&messages, amp);
+        }
+        release_tx.send(()).unwrap();
+        release_tx.send(()).unwrap();
+
+        // The first sweep's callback was already running and the newest sweep replaced the ones
+        // waiting behind it
+        let timeout = Duration::from_secs(1);
+        assert_eq!(amp_rx.recv_timeout(timeout), Ok(-100.));
+        assert_eq!(amp_rx.recv_timeout(timeout), Ok(-101.5));
+        assert!(amp_rx.recv_timeout(Duration::from_millis(200)).is_err());
+        assert_eq!(messages.memory_stats().dropped_callbacks, 2);
+    }
+
+    #[test]
+    fn shift_amp_range_by_offset() {
+        assert_eq!(min_max_amp_range_dbm(None), -120..=35);
+        assert_eq!(min_max_amp_range_dbm(Some(0)), -120..=35);
+        // An external LNA raises the displayed amplitudes
+        assert_eq!(min_max_amp_range_dbm(Some(30)), -90..=65);
+        assert!(min_max_amp_range_dbm(Some(30)).contains(&50));
+        // An external attenuator lowers them
+        assert_eq!(min_max_amp_range_dbm(Some(-20)), -140..=15);
+        assert!(min_max_amp_range_dbm(Some(-20)).contains(&-130));
+        assert!(!min_max_amp_range_dbm(Some(-20)).contains(&30));
+    }
+
+    #[test]
+    fn succeed_with_config() {
+        let config = Config::default();
+        assert_eq!(config_or_err(Some(&config)).unwrap(), &config);
+    }
+}
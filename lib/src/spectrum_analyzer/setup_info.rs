@@ -1,5 +1,7 @@
+use std::io;
+
 use super::Model;
-use crate::common::MessageParseError;
+use crate::common::{MessageParseError, WriteMessage};
 use crate::rf_explorer::SetupInfo;
 
 impl SetupInfo<Model> {
@@ -14,17 +16,23 @@ impl<'a> TryFrom<&'a [u8]> for SetupInfo<Model> {
     }
 }
 
+impl WriteMessage for SetupInfo<Model> {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        self.write_with_prefix(Self::PREFIX, writer)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Model;
-    use crate::rf_explorer::SetupInfo;
+    use crate::{WriteMessage, rf_explorer::SetupInfo};
 
     #[test]
     fn accept_wsub1g_setup() {
         let setup = SetupInfo::<Model>::try_from(b"#C2-M:003,255,XX.XXXX".as_ref()).unwrap();
         assert_eq!(setup.main_radio_model, Some(Model::RfeWSub1G));
         assert_eq!(setup.expansion_radio_model, None);
-        assert_eq!(setup.firmware_version, "XX.XXXX");
+        assert_eq!(&*setup.firmware_version, "XX.XXXX");
     }
 
     #[test]
@@ -32,7 +40,7 @@ mod tests {
         let setup = SetupInfo::<Model>::try_from(b"#C2-M:004,255,XX.XXXX".as_ref()).unwrap();
         assert_eq!(setup.main_radio_model, Some(Model::Rfe24G));
         assert_eq!(setup.expansion_radio_model, None);
-        assert_eq!(setup.firmware_version, "XX.XXXX");
+        assert_eq!(&*setup.firmware_version, "XX.XXXX");
     }
 
     #[test]
@@ -40,7 +48,7 @@ mod tests {
         let setup = SetupInfo::<Model>::try_from(b"#C2-M:003,004,XX.XXXX".as_ref()).unwrap();
         assert_eq!(setup.main_radio_model, Some(Model::RfeWSub1G));
         assert_eq!(setup.expansion_radio_model, Some(Model::Rfe24G));
-        assert_eq!(setup.firmware_version, "XX.XXXX");
+        assert_eq!(&*setup.firmware_version, "XX.XXXX");
     }
 
     #[test]
@@ -48,7 +56,7 @@ mod tests {
         let setup = SetupInfo::<Model>::try_from(b"#C2-M:003,005,XX.XXXX".as_ref()).unwrap();
         assert_eq!(setup.main_radio_model, Some(Model::RfeWSub1G));
         assert_eq!(setup.expansion_radio_model, Some(Model::RfeWSub3G));
-        assert_eq!(setup.firmware_version, "XX.XXXX");
+        assert_eq!(&*setup.firmware_version, "XX.XXXX");
     }
 
     #[test]
@@ -56,7 +64,7 @@ mod tests {
         let setup = SetupInfo::<Model>::try_from(b"#C2-M:006,005,XX.XXXX".as_ref()).unwrap();
         assert_eq!(setup.main_radio_model, Some(Model::Rfe6G));
         assert_eq!(setup.expansion_radio_model, Some(Model::RfeWSub3G));
-        assert_eq!(setup.firmware_version, "XX.XXXX");
+        assert_eq!(&*setup.firmware_version, "XX.XXXX");
     }
 
     #[test]
@@ -64,7 +72,7 @@ mod tests {
         let setup = SetupInfo::<Model>::try_from(b"#C2-M:010,255,XX.XXXX".as_ref()).unwrap();
         assert_eq!(setup.main_radio_model, Some(Model::RfeWSub1GPlus));
         assert_eq!(setup.expansion_radio_model, None);
-        assert_eq!(setup.firmware_version, "XX.XXXX");
+        assert_eq!(&*setup.firmware_version, "XX.XXXX");
     }
 
     #[test]
@@ -72,7 +80,7 @@ mod tests {
         let setup = SetupInfo::<Model>::try_from(b"#C2-M:010,012,XX.XXXX".as_ref()).unwrap();
         assert_eq!(setup.main_radio_model, Some(Model::RfeWSub1GPlus));
         assert_eq!(setup.expansion_radio_model, Some(Model::Rfe24GPlus));
-        assert_eq!(setup.firmware_version, "XX.XXXX");
+        assert_eq!(&*setup.firmware_version, "XX.XXXX");
     }
 
     #[test]
@@ -80,7 +88,7 @@ mod tests {
         let setup = SetupInfo::<Model>::try_from(b"#C2-M:010,013,XX.XXXX".as_ref()).unwrap();
         assert_eq!(setup.main_radio_model, Some(Model::RfeWSub1GPlus));
         assert_eq!(setup.expansion_radio_model, Some(Model::Rfe4GPlus));
-        assert_eq!(setup.firmware_version, "XX.XXXX");
+        assert_eq!(&*setup.firmware_version, "XX.XXXX");
     }
 
     #[test]
@@ -88,7 +96,7 @@ mod tests {
         let setup = SetupInfo::<Model>::try_from(b"#C2-M:010,014,XX.XXXX".as_ref()).unwrap();
         assert_eq!(setup.main_radio_model, Some(Model::RfeWSub1GPlus));
         assert_eq!(setup.expansion_radio_model, Some(Model::Rfe6GPlus));
-        assert_eq!(setup.firmware_version, "XX.XXXX");
+        assert_eq!(&*setup.firmware_version, "XX.XXXX");
     }
 
     #[test]
@@ -111,4 +119,22 @@ mod tests {
     fn reject_setup_with_incorrect_prefix() {
         assert!(SetupInfo::<Model>::try_from(b"$C2-M:006,005,01.12B26".as_ref()).is_err());
     }
+
+    #[test]
+    fn write_setup_info() {
+        let bytes = b"#C2-M:006,005,01.12B26\r\n";
+        let setup = SetupInfo::<Model>::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(setup.to_bytes(), bytes);
+
+        let setup = SetupInfo {
+            main_radio_model: Some(Model::RfeWSub1GPlus),
+            expansion_radio_model: None,
+            firmware_version: "03.42".into(),
+        };
+        assert_eq!(setup.to_bytes(), b"#C2-M:010,255,03.42\r\n");
+        assert_eq!(
+            SetupInfo::<Model>::try_from(setup.to_bytes().as_slice()),
+            Ok(setup)
+        );
+    }
 }
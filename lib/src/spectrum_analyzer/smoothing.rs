@@ -0,0 +1,124 @@
+use std::fmt::Display;
+
+use crate::{Error, Result};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+/// Method used to smooth the amplitudes of a sweep.
+pub enum SmoothingMethod {
+    /// Replace each point with the mean of the points in the window around it.
+    #[default]
+    MovingAverage,
+    /// Replace each point with the median of the points in the window around it.
+    Median,
+}
+
+impl SmoothingMethod {
+    /// Smooths the amplitudes using a window of `window` points centered on each point.
+    ///
+    /// The window shrinks at the edges of the sweep so that only measured points are used.
+    /// Returns an error if `window` is 0 or larger than the number of amplitudes.
+    pub fn apply(&self, amplitudes_dbm: &[f32], window: usize) -> Result<Vec<f32>> {
+        if window == 0 || window > amplitudes_dbm.len() {
+            return Err(Error::InvalidInput(format!(
+                "The smoothing window must contain between 1 and {} points",
+                amplitudes_dbm.len()
+            )));
+        }
+
+        // Windows with an even number of points extend one point further to the right
+        let (left, right) = ((window - 1) / 2, window / 2);
+        let mut window_amps = Vec::with_capacity(window);
+        Ok((0..amplitudes_dbm.len())
+            .map(|i| {
+                let start = i.saturating_sub(left);
+                let end = (i + right + 1).min(amplitudes_dbm.len());
+                window_amps.clear();
+                window_amps.extend_from_slice(&amplitudes_dbm[start..end]);
+                match self {
+                    SmoothingMethod::MovingAverage => mean(&window_amps),
+                    SmoothingMethod::Median => median(&mut window_amps),
+                }
+            })
+            .collect())
+    }
+}
+
+fn mean(amps: &[f32]) -> f32 {
+    amps.iter().sum::<f32>() / amps.len() as f32
+}
+
+fn median(amps: &mut [f32]) -> f32 {
+    amps.sort_unstable_by(f32::total_cmp);
+    let mid = amps.len() / 2;
+    if amps.len().is_multiple_of(2) {
+        (amps[mid - 1] + amps[mid]) / 2.
+    } else {
+        amps[mid]
+    }
+}
+
+impl Display for SmoothingMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let smoothing_method = match self {
+            Self::MovingAverage => "Moving Average",
+            Self::Median => "Median",
+        };
+        write!(f, "{smoothing_method}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn impulse(len: usize, index: usize) -> Vec<f32> {
+        let mut amps = vec![-100.; len];
+        amps[index] = -40.;
+        amps
+    }
+
+    #[test]
+    fn moving_average_spreads_impulse() {
+        let smoothed = SmoothingMethod::MovingAverage
+            .apply(&impulse(11, 5), 5)
+            .unwrap();
+        // The impulse contributes 60 dB / 5 points to every window that contains it
+        for (i, amp) in smoothed.iter().enumerate() {
+            let expected = if (3..=7).contains(&i) { -88. } else { -100. };
+            assert!((amp - expected).abs() < 1e-4, "{i}: {amp}");
+        }
+    }
+
+    #[test]
+    fn median_removes_impulse() {
+        let smoothed = SmoothingMethod::Median.apply(&impulse(11, 5), 3).unwrap();
+        assert_eq!(smoothed, vec![-100.; 11]);
+    }
+
+    #[test]
+    fn shrink_window_at_edges() {
+        // Only 3 of the 5 points are available for the first point
+        let smoothed = SmoothingMethod::MovingAverage
+            .apply(&impulse(11, 0), 5)
+            .unwrap();
+        assert!((smoothed[0] - -80.).abs() < 1e-4);
+        assert!((smoothed[1] - -85.).abs() < 1e-4);
+        assert!((smoothed[2] - -88.).abs() < 1e-4);
+
+        let smoothed = SmoothingMethod::Median
+            .apply(&[-50., -90., -100.], 3)
+            .unwrap();
+        assert_eq!(smoothed, [-70., -90., -95.]);
+    }
+
+    #[test]
+    fn reject_invalid_windows() {
+        let amps = impulse(11, 5);
+        assert!(SmoothingMethod::MovingAverage.apply(&amps, 0).is_err());
+        assert!(SmoothingMethod::Median.apply(&amps, 12).is_err());
+        assert_eq!(
+            SmoothingMethod::Median.apply(&amps, 1).unwrap(),
+            amps.as_slice()
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{cell::RefCell, fmt::Debug, io, mem};
 
 use chrono::{DateTime, Utc};
 use nom::{
@@ -10,21 +10,156 @@ use nom::{
     number::complete::{be_u16, u8 as nom_u8},
 };
 
-use super::{Config, Model};
-use crate::common::MessageParseError;
+use super::{Config, Model, ResampleMethod, SmoothingMethod, SweepEncoding, SweepMeta};
+use crate::Frequency;
+use crate::common::{MessageParseError, WriteMessage};
 use crate::rf_explorer::{SetupInfo, parsers::*};
 
+/// A sweep received from an RF Explorer spectrum analyzer.
 #[derive(Debug, Clone, PartialEq, Default)]
-pub(crate) struct Sweep {
+pub struct Sweep {
     pub(crate) amplitudes_dbm: Vec<f32>,
     pub(crate) timestamp: DateTime<Utc>,
+    pub(crate) meta: SweepMeta,
+}
+
+thread_local! {
+    /// Amplitude buffers of dropped sweeps that are reused by sweeps parsed on the same thread.
+    ///
+    /// Reusing buffers means parsing a sweep doesn't allocate once the pool has warmed up. Each
+    /// connection parses its sweeps on its reader thread, which is also where the sweeps they
+    /// replace are dropped, so a pool per thread keeps connections from contending for a lock.
+    /// Threads that have never parsed a sweep don't keep buffers.
+    static AMPLITUDE_BUFFER_POOL: RefCell<Option<Vec<Vec<f32>>>> = const { RefCell::new(None) };
 }
 
 impl Sweep {
+    /// The maximum number of amplitude buffers kept for reuse.
+    const AMPLITUDE_BUFFER_POOL_LEN: usize = 4;
+
     pub(crate) const STANDARD_PREFIX: &'static [u8] = b"$S";
     pub(crate) const EXT_PREFIX: &'static [u8] = b"$s";
     pub(crate) const LARGE_PREFIX: &'static [u8] = b"$z";
     const EEOT_BYTES: [u8; 5] = [255, 254, 255, 254, 0];
+
+    /// Returns the sweep's amplitudes in dBm.
+    pub fn amplitudes_dbm(&self) -> &[f32] {
+        &self.amplitudes_dbm
+    }
+
+    /// Returns the time when the sweep was received.
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    /// Returns the metadata the sweep was received with.
+    pub fn meta(&self) -> &SweepMeta {
+        &self.meta
+    }
+
+    /// Returns the sweep's amplitudes smoothed using the given window size and method.
+    pub(crate) fn smoothed(
+        &self,
+        window: usize,
+        method: SmoothingMethod,
+    ) -> crate::Result<Vec<f32>> {
+        method.apply(&self.amplitudes_dbm, window)
+    }
+
+    /// Returns the sweep's amplitudes resampled onto a grid of `grid_len` points starting at
+    /// `grid_start` and spaced `grid_step` apart, using the start and stop frequencies the sweep
+    /// was received with.
+    ///
+    /// Grid points that the sweep doesn't cover are `NaN`.
+    pub fn resample_to_grid(
+        &self,
+        grid_start: Frequency,
+        grid_step: Frequency,
+        grid_len: usize,
+        method: ResampleMethod,
+    ) -> Vec<f32> {
+        let start_hz = self.meta.start_freq.as_hz_f64();
+        let step_hz = match self.amplitudes_dbm.len() {
+            0 | 1 => 0.,
+            len => (self.meta.stop_freq.as_hz_f64() - start_hz) / (len - 1) as f64,
+        };
+        method.resample(
+            &self.amplitudes_dbm,
+            start_hz,
+            step_hz,
+            grid_start,
+            grid_step,
+            grid_len,
+        )
+    }
+
+    /// Returns the sweep's absolute amplitudes, or `None` if the correction they need is unknown.
+    pub(crate) fn absolute_amplitudes(&self, meta: &SweepMeta) -> Option<Vec<f32>> {
+        let correction_db = meta.correction_db()?;
+        Some(
+            self.amplitudes_dbm
+                .iter()
+                .map(|amp_dbm| amp_dbm + correction_db)
+                .collect(),
+        )
+    }
+
+    /// Returns the normalized cross-correlation of this sweep's amplitudes with another sweep's.
+    ///
+    /// See [`sweep_correlation`](super::sweep_correlation) for how it's calculated and when it
+    /// returns an error.
+    pub fn correlation(&self, other: &Sweep) -> crate::Result<f32> {
+        super::sweep_correlation(&self.amplitudes_dbm, &other.amplitudes_dbm)
+    }
+
+    /// Returns the mean absolute difference in dB between this sweep's amplitudes and another
+    /// sweep's.
+    ///
+    /// See [`spectral_distance`](super::spectral_distance) for how it's calculated and when it
+    /// returns an error.
+    pub fn spectral_distance(&self, other: &Sweep) -> crate::Result<f32> {
+        super::spectral_distance(&self.amplitudes_dbm, &other.amplitudes_dbm)
+    }
+
+    /// Returns the frequency of the sweep's strongest point, using the start and stop frequencies
+    /// the sweep was received with.
+    pub(crate) fn peak_freq(&self) -> Option<Frequency> {
+        let (peak_index, _) = self
+            .amplitudes_dbm
+            .iter()
+            .enumerate()
+            .filter(|(_, amp_dbm)| !amp_dbm.is_nan())
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        let last_index = self.amplitudes_dbm.len().saturating_sub(1).max(1) as u64;
+        let span = self.meta.stop_freq - self.meta.start_freq;
+        Some(self.meta.start_freq + span * peak_index as u64 / last_index)
+    }
+}
+
+impl Drop for Sweep {
+    fn drop(&mut self) {
+        let mut amplitudes_dbm = mem::take(&mut self.amplitudes_dbm);
+        if amplitudes_dbm.capacity() == 0 {
+            return;
+        }
+        // The pool is already gone if the thread is exiting
+        let _ = AMPLITUDE_BUFFER_POOL.try_with(|pool| {
+            if let Some(pool) = pool.borrow_mut().as_mut()
+                && pool.len() < Self::AMPLITUDE_BUFFER_POOL_LEN
+            {
+                amplitudes_dbm.clear();
+                pool.push(amplitudes_dbm);
+            }
+        });
+    }
+}
+
+fn take_amplitude_buffer() -> Vec<f32> {
+    AMPLITUDE_BUFFER_POOL
+        .try_with(|pool| pool.borrow_mut().get_or_insert_default().pop())
+        .ok()
+        .flatten()
+        .unwrap_or_default()
 }
 
 impl<'a> TryFrom<&'a [u8]> for Sweep {
@@ -41,34 +176,37 @@ impl<'a> TryFrom<&'a [u8]> for Sweep {
 
         // Determine whether or not the Sweep is 'truncated' by looking for the EEOT byte
         // sequence as well as Config and SetupInfo messages
-        if let Some(index) = bytes.windows(5).enumerate().find_map(|(i, window)| {
-            if Self::EEOT_BYTES.starts_with(window) {
-                Some(i + Self::EEOT_BYTES.len())
-            } else if Config::PREFIX.starts_with(window)
-                || SetupInfo::<Model>::PREFIX.starts_with(window)
-            {
-                Some(i)
+        if let Some((index, is_eeot)) = Self::find_truncation(bytes) {
+            let index = if is_eeot {
+                index + Self::EEOT_BYTES.len()
             } else {
-                None
-            }
-        }) {
+                index
+            };
             return Err(MessageParseError::Truncated {
                 remainder: bytes.get(index..),
             });
         }
 
         // Get the slice containing the amplitudes in the sweep data
-        let (bytes, amps) = match prefix {
-            Self::STANDARD_PREFIX => length_data(nom_u8).parse(bytes)?,
+        let (bytes, amps, encoding) = match prefix {
             Self::EXT_PREFIX => {
-                length_data(map(nom_u8, |len| (usize::from(len) + 1) * 16)).parse(bytes)?
+                let (bytes, amps) =
+                    length_data(map(nom_u8, |len| (usize::from(len) + 1) * 16)).parse(bytes)?;
+                (bytes, amps, SweepEncoding::Extended)
+            }
+            Self::LARGE_PREFIX => {
+                let (bytes, amps) = length_data(be_u16).parse(bytes)?;
+                (bytes, amps, SweepEncoding::Large)
+            }
+            _ => {
+                let (bytes, amps) = length_data(nom_u8).parse(bytes)?;
+                (bytes, amps, SweepEncoding::Standard)
             }
-            Self::LARGE_PREFIX => length_data(be_u16).parse(bytes)?,
-            _ => length_data(nom_u8).parse(bytes)?,
         };
 
         // Convert the amplitude bytes into dBm by dividing them by -2
-        let amplitudes_dbm = amps.iter().map(|&byte| f32::from(byte) / -2.).collect();
+        let mut amplitudes_dbm = take_amplitude_buffer();
+        amplitudes_dbm.extend(amps.iter().map(|&byte| f32::from(byte) / -2.));
 
         // Consume any \r or \r\n line endings and make sure there aren't any bytes left
         let _ = parse_opt_line_ending(bytes)?;
@@ -76,13 +214,163 @@ impl<'a> TryFrom<&'a [u8]> for Sweep {
         Ok(Sweep {
             amplitudes_dbm,
             timestamp: Utc::now(),
+            meta: SweepMeta {
+                encoding,
+                ..SweepMeta::default()
+            },
         })
     }
 }
 
+impl Sweep {
+    /// Returns the index of the first EEOT byte sequence, Config, or SetupInfo message in the
+    /// bytes after a sweep's prefix, and whether it's the EEOT byte sequence.
+    fn find_truncation(bytes: &[u8]) -> Option<(usize, bool)> {
+        bytes.windows(5).enumerate().find_map(|(i, window)| {
+            if Self::EEOT_BYTES.starts_with(window) {
+                Some((i, true))
+            } else if Config::PREFIX.starts_with(window)
+                || SetupInfo::<Model>::PREFIX.starts_with(window)
+            {
+                Some((i, false))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the length of a sweep message's prefix and length field, and the number of
+    /// amplitudes the field says follow it.
+    fn parse_header(bytes: &[u8]) -> Option<(usize, usize)> {
+        match bytes {
+            [b'$', b'S', len, ..] => Some((3, usize::from(*len))),
+            [b'$', b's', len, ..] => Some((3, (usize::from(*len) + 1) * 16)),
+            [b'$', b'z', len_hi, len_lo, ..] => {
+                Some((4, usize::from(u16::from_be_bytes([*len_hi, *len_lo]))))
+            }
+            _ => None,
+        }
+    }
+
+    /// Writes the sweep using the encoding that comes after the given prefix.
+    ///
+    /// Amplitudes are rounded to the nearest 0.5 dB between 0 and -127.5 dBm.
+    fn write_with_prefix(&self, prefix: &[u8], writer: &mut impl io::Write) -> io::Result<()> {
+        let len = self.amplitudes_dbm.len();
+        let invalid_len = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("A sweep with {len} points can't be encoded after {prefix:?}"),
+            )
+        };
+
+        writer.write_all(prefix)?;
+        match prefix {
+            Self::STANDARD_PREFIX => {
+                writer.write_all(&[u8::try_from(len).map_err(|_| invalid_len())?])?
+            }
+            Self::EXT_PREFIX => {
+                if len == 0 || !len.is_multiple_of(16) {
+                    return Err(invalid_len());
+                }
+                writer.write_all(&[u8::try_from(len / 16 - 1).map_err(|_| invalid_len())?])?;
+            }
+            Self::LARGE_PREFIX => {
+                writer.write_all(&u16::try_from(len).map_err(|_| invalid_len())?.to_be_bytes())?
+            }
+            _ => return Err(invalid_len()),
+        }
+
+        // Amplitudes are sent as bytes that are -2 times the amplitude in dBm
+        for amp_dbm in &self.amplitudes_dbm {
+            writer.write_all(&[(amp_dbm * -2.).round().clamp(0., 255.) as u8])?;
+        }
+        writer.write_all(b"\r\n")
+    }
+}
+
+/// Sweeps are written with the shortest encoding that fits the number of points.
+///
+/// The parser treats sweeps that contain the end-of-transmission bytes or the start of a config
+/// or setup message as truncated, so sweeps whose amplitudes happen to encode those bytes can't be
+/// parsed after they're written.
+impl WriteMessage for Sweep {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        let len = self.amplitudes_dbm.len();
+        let prefix = if len <= usize::from(u8::MAX) {
+            Self::STANDARD_PREFIX
+        } else if len.is_multiple_of(16) && len <= 4096 {
+            Self::EXT_PREFIX
+        } else {
+            Self::LARGE_PREFIX
+        };
+        self.write_with_prefix(prefix, writer)
+    }
+}
+
+/// Decodes the amplitudes of a sweep that's still being received, so they can be used before
+/// the rest of the sweep arrives.
+#[derive(Debug, Default)]
+pub(crate) struct PartialSweepDecoder {
+    /// The number of amplitudes decoded from the sweep being received.
+    decoded: usize,
+    amplitudes_dbm: Vec<f32>,
+}
+
+impl PartialSweepDecoder {
+    /// Decodes the amplitudes that haven't been decoded yet from `bytes`, the start of a sweep
+    /// message that's still being received.
+    ///
+    /// Returns the index of the first new amplitude and the new amplitudes, or `None` if there
+    /// aren't any or `bytes` isn't the start of a sweep. Amplitudes from a byte sequence that
+    /// makes the sweep truncated onwards aren't decoded, and neither are the last few bytes of
+    /// an unfinished sweep because they could be the start of one.
+    pub(crate) fn decode(&mut self, bytes: &[u8]) -> Option<(usize, &[f32])> {
+        let (header_len, sweep_len) = Sweep::parse_header(bytes)?;
+        let mut available = if bytes.len() - header_len >= sweep_len {
+            sweep_len
+        } else {
+            (bytes.len() - header_len).saturating_sub(Sweep::EEOT_BYTES.len() - 1)
+        };
+        // The prefix is left out like when the whole sweep is parsed
+        if let Some((index, _)) = Sweep::find_truncation(&bytes[2..]) {
+            available = available.min((index + 2).saturating_sub(header_len));
+        }
+        let amps = &bytes[header_len..header_len + available];
+        if available < self.decoded {
+            // The buffer was cleared and a new sweep started arriving
+            self.decoded = 0;
+        }
+        if available == self.decoded {
+            return None;
+        }
+
+        let offset = self.decoded;
+        self.amplitudes_dbm.clear();
+        self.amplitudes_dbm.extend(
+            amps[offset..available]
+                .iter()
+                .map(|&byte| f32::from(byte) / -2.),
+        );
+        self.decoded = available;
+        Some((offset, &self.amplitudes_dbm))
+    }
+
+    /// The number of amplitudes decoded from the sweep being received.
+    pub(crate) fn decoded(&self) -> usize {
+        self.decoded
+    }
+
+    /// Starts decoding a new sweep.
+    pub(crate) fn reset(&mut self) {
+        self.decoded = 0;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{Frequency, spectrum_analyzer::InputStage};
 
     #[test]
     fn parse_sweep() {
@@ -112,6 +400,7 @@ mod tests {
                 -41.5, -3.5, -94.5, -113.5, -10.0, -46.0, -3.0, -114.5, -60.0, -62.5, -119.5
             ]
         );
+        assert_eq!(sweep.meta.encoding, SweepEncoding::Standard);
     }
 
     #[test]
@@ -142,6 +431,7 @@ mod tests {
                 -41.5, -3.5, -94.5, -113.5, -10.0, -46.0, -3.0, -114.5, -60.0, -62.5, -119.5
             ]
         );
+        assert_eq!(sweep.meta.encoding, SweepEncoding::Extended);
     }
 
     #[test]
@@ -172,6 +462,161 @@ mod tests {
                 -41.5, -3.5, -94.5, -113.5, -10.0, -46.0, -3.0, -114.5, -60.0, -62.5, -119.5
             ]
         );
+        assert_eq!(sweep.meta.encoding, SweepEncoding::Large);
+    }
+
+    #[test]
+    fn write_sweep_with_each_encoding() {
+        let sweep = Sweep {
+            amplitudes_dbm: (0..112u8).map(|i| f32::from(i) / -2.).collect(),
+            timestamp: Utc::now(),
+            meta: SweepMeta::default(),
+        };
+        for prefix in [
+            Sweep::STANDARD_PREFIX,
+            Sweep::EXT_PREFIX,
+            Sweep::LARGE_PREFIX,
+        ] {
+            let mut bytes = Vec::new();
+            sweep.write_with_prefix(prefix, &mut bytes).unwrap();
+            assert!(bytes.starts_with(prefix));
+            assert_eq!(
+                Sweep::try_from(bytes.as_slice()).unwrap().amplitudes_dbm,
+                sweep.amplitudes_dbm
+            );
+        }
+
+        // The extended encoding only supports multiples of 16 points
+        let sweep = Sweep {
+            amplitudes_dbm: vec![-50.; 100],
+            timestamp: Utc::now(),
+            meta: SweepMeta::default(),
+        };
+        assert!(
+            sweep
+                .write_with_prefix(Sweep::EXT_PREFIX, &mut Vec::new())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn write_sweep_with_shortest_encoding() {
+        for (len, prefix) in [
+            (112, Sweep::STANDARD_PREFIX),
+            (1024, Sweep::EXT_PREFIX),
+            (4100, Sweep::LARGE_PREFIX),
+            (10_000, Sweep::LARGE_PREFIX),
+        ] {
+            let sweep = Sweep {
+                amplitudes_dbm: vec![-60.5; len],
+                timestamp: Utc::now(),
+                meta: SweepMeta::default(),
+            };
+            let bytes = sweep.to_bytes();
+            assert!(bytes.starts_with(prefix));
+            assert_eq!(
+                Sweep::try_from(bytes.as_slice()).unwrap().amplitudes_dbm,
+                sweep.amplitudes_dbm
+            );
+        }
+
+        let bytes = [b'$', b'S', 3, 200, 100, 0, b'\r', b'\n'];
+        assert_eq!(Sweep::try_from(bytes.as_slice()).unwrap().to_bytes(), bytes);
+    }
+
+    #[test]
+    fn absolute_amplitudes_need_known_correction() {
+        let sweep = Sweep {
+            amplitudes_dbm: vec![-100., -50.],
+            timestamp: Utc::now(),
+            meta: SweepMeta::default(),
+        };
+        let meta = SweepMeta {
+            input_stage: Some(InputStage::Direct),
+            amp_offset_db: Some(0),
+            ..SweepMeta::default()
+        };
+        assert_eq!(
+            sweep.absolute_amplitudes(&meta).as_deref(),
+            Some(sweep.amplitudes_dbm.as_slice())
+        );
+
+        let meta = SweepMeta {
+            input_stage: Some(InputStage::Attenuator30dB),
+            ..meta
+        };
+        assert_eq!(sweep.absolute_amplitudes(&meta), None);
+    }
+
+    #[test]
+    fn find_peak_freq() {
+        let sweep = Sweep {
+            amplitudes_dbm: vec![-90., f32::NAN, -40., -80., -95.],
+            timestamp: Utc::now(),
+            meta: SweepMeta {
+                start_freq: Frequency::from_mhz(2_400),
+                stop_freq: Frequency::from_mhz(2_480),
+                ..SweepMeta::default()
+            },
+        };
+        assert_eq!(sweep.peak_freq(), Some(Frequency::from_mhz(2_440)));
+        assert_eq!(Sweep::default().peak_freq(), None);
+    }
+
+    #[test]
+    fn compare_sweeps() {
+        let sweep = |amplitudes_dbm: Vec<f32>| Sweep {
+            amplitudes_dbm,
+            timestamp: Utc::now(),
+            meta: SweepMeta::default(),
+        };
+        let a = sweep(vec![-100., -40., -100.]);
+        let b = sweep(vec![-90., -30., -90.]);
+        assert_eq!(a.correlation(&b).unwrap(), 1.);
+        assert_eq!(a.spectral_distance(&b).unwrap(), 10.);
+        assert!(a.correlation(&sweep(vec![-100.])).is_err());
+    }
+
+    #[test]
+    fn resample_to_grid_with_sweep_freqs() {
+        let sweep = Sweep {
+            amplitudes_dbm: vec![-90., -40., -80., -95., -100.],
+            timestamp: Utc::now(),
+            meta: SweepMeta {
+                start_freq: Frequency::from_mhz(2_400),
+                stop_freq: Frequency::from_mhz(2_480),
+                ..SweepMeta::default()
+            },
+        };
+        let resampled = sweep.resample_to_grid(
+            Frequency::from_mhz(2_380),
+            Frequency::from_mhz(20),
+            3,
+            ResampleMethod::NearestBin,
+        );
+        assert!(resampled[0].is_nan());
+        assert_eq!(resampled[1..], [-90., -40.]);
+    }
+
+    #[test]
+    fn reuse_amplitude_buffers() {
+        let long_sweep = Sweep::try_from([b'$', b'S', 4, 20, 20, 20, 20].as_slice()).unwrap();
+        drop(long_sweep);
+
+        // A reused buffer must only contain the new sweep's amplitudes
+        let sweep = Sweep::try_from([b'$', b'S', 3, 200, 100, 0].as_slice()).unwrap();
+        assert_eq!(sweep.amplitudes_dbm, [-100., -50., 0.]);
+    }
+
+    #[test]
+    fn keep_amplitude_buffers_only_on_parsing_threads() {
+        let sweep = Sweep::try_from([b'$', b'S', 3, 200, 100, 0].as_slice()).unwrap();
+        std::thread::spawn(move || {
+            drop(sweep);
+            AMPLITUDE_BUFFER_POOL.with(|pool| assert!(pool.borrow().is_none()));
+        })
+        .join()
+        .unwrap();
     }
 
     #[test]
@@ -242,4 +687,58 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn decode_chunked_sweep_like_whole_sweep() {
+        let sweep = Sweep {
+            amplitudes_dbm: (0..4001).map(|i| f32::from(i as u16 % 241) / -2.).collect(),
+            timestamp: Utc::now(),
+            meta: SweepMeta::default(),
+        };
+        let bytes = sweep.to_bytes();
+        let whole = Sweep::try_from(&bytes[..]).unwrap();
+
+        let mut decoder = PartialSweepDecoder::default();
+        let mut decoded = Vec::new();
+        let mut received = 0;
+        for chunk_len in [1, 2, 7, 100, 333, 1].into_iter().cycle() {
+            received = (received + chunk_len).min(bytes.len());
+            let partial = &bytes[..received];
+            match Sweep::try_from(partial) {
+                Err(MessageParseError::Incomplete) => {
+                    if let Some((offset, amps)) = decoder.decode(partial) {
+                        assert_eq!(offset, decoded.len());
+                        decoded.extend_from_slice(amps);
+                    }
+                }
+                Ok(parsed) => {
+                    // The rest is taken from the whole sweep, like when it finishes arriving
+                    decoded.extend_from_slice(&parsed.amplitudes_dbm[decoder.decoded()..]);
+                    assert_eq!(parsed.amplitudes_dbm, whole.amplitudes_dbm);
+                    break;
+                }
+                // Only part of the line ending has arrived
+                Err(_) => assert!(received < bytes.len()),
+            }
+        }
+        assert_eq!(decoded, whole.amplitudes_dbm);
+    }
+
+    #[test]
+    fn stop_decoding_partial_sweep_at_truncation() {
+        let mut decoder = PartialSweepDecoder::default();
+        let mut bytes = vec![b'$', b'S', 112];
+        bytes.extend([200; 20]);
+        bytes.extend(Sweep::EEOT_BYTES);
+        bytes.extend([200; 20]);
+        let (offset, amps) = decoder.decode(&bytes).unwrap();
+        assert_eq!((offset, amps.len()), (0, 20));
+        assert!(decoder.decode(&bytes).is_none());
+
+        // A new sweep starts arriving after the truncated one is discarded
+        assert_eq!(
+            decoder.decode(b"$S\x70\x10\x10\x10\x10\x10\x10"),
+            Some((0, &[-8.; 2][..]))
+        );
+    }
 }
@@ -0,0 +1,223 @@
+use std::io::{self, BufRead, Write};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use super::{Sweep, SweepMeta};
+use crate::Frequency;
+
+const TITLE: &str = "RF Explorer CSV data file";
+const START_FREQ: &str = "Start Frequency (MHz)";
+const STOP_FREQ: &str = "Stop Frequency (MHz)";
+const STEP_FREQ: &str = "Step Frequency (MHz)";
+const SWEEP_POINTS: &str = "Sweep Points";
+const TIMESTAMP: &str = "Timestamp";
+const COLUMNS: &str = "Frequency (MHz),Amplitude (dBm)";
+
+impl Sweep {
+    /// Writes the sweep as CSV that [`Sweep::from_csv_reader`] can read back.
+    ///
+    /// A header with the sweep's start, stop, and step frequencies, its number of points, and
+    /// when it was received is followed by one row per point with its frequency in MHz and its
+    /// amplitude in dBm. This layout is this crate's own, so other software like the RF Explorer
+    /// for Windows client may not be able to import it. Numbers are always written with `.` as the decimal separator, whatever
+    /// the system's locale, and frequencies are written to the hertz.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// use rfe::SpectrumAnalyzer;
+    ///
+    /// let rfe = SpectrumAnalyzer::connect().expect("RF Explorer should be connected");
+    /// let sweep = rfe.single_sweep(std::time::Duration::from_secs(2))?;
+    /// sweep.to_csv_writer(File::create("sweep.csv")?)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn to_csv_writer(&self, mut w: impl Write) -> io::Result<()> {
+        writeln!(w, "{TITLE}")?;
+        writeln!(w, "{START_FREQ},{}", format_mhz(self.meta.start_freq))?;
+        writeln!(w, "{STOP_FREQ},{}", format_mhz(self.meta.stop_freq))?;
+        writeln!(w, "{STEP_FREQ},{:.6}", self.step_size_hz() / 1e6)?;
+        writeln!(w, "{SWEEP_POINTS},{}", self.amplitudes_dbm.len())?;
+        writeln!(
+            w,
+            "{TIMESTAMP},{}",
+            self.timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+        )?;
+        writeln!(w, "{COLUMNS}")?;
+        for (i, amp_dbm) in self.amplitudes_dbm.iter().enumerate() {
+            let freq_hz = self.meta.start_freq.as_hz_f64() + self.step_size_hz() * i as f64;
+            writeln!(w, "{:.6},{amp_dbm}", freq_hz / 1e6)?;
+        }
+        w.flush()
+    }
+
+    /// Reads a sweep written by [`Sweep::to_csv_writer`].
+    ///
+    /// Only the frequencies, amplitudes, and timestamp are stored in the CSV, so the rest of the
+    /// sweep's [`SweepMeta`] is left at its defaults. Returns `io::ErrorKind::InvalidData` if the
+    /// header is incomplete, a row can't be parsed, or the number of rows doesn't match the
+    /// header's number of points.
+    pub fn from_csv_reader(r: impl BufRead) -> io::Result<Self> {
+        let mut start_freq = None;
+        let mut stop_freq = None;
+        let mut sweep_len = None;
+        let mut timestamp = None;
+        let mut amplitudes_dbm = Vec::new();
+        let mut is_in_rows = false;
+
+        for (i, line) in r.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            let invalid_line = || invalid_data(format!("Invalid CSV on line {}: {line}", i + 1));
+            if line.is_empty() || (i == 0 && line == TITLE) {
+                continue;
+            }
+            if is_in_rows {
+                let (_, amp_dbm) = line.split_once(',').ok_or_else(invalid_line)?;
+                amplitudes_dbm.push(amp_dbm.trim().parse().map_err(|_| invalid_line())?);
+                continue;
+            }
+            if line == COLUMNS {
+                is_in_rows = true;
+                continue;
+            }
+
+            let (key, value) = line.split_once(',').ok_or_else(invalid_line)?;
+            let value = value.trim();
+            match key {
+                START_FREQ => start_freq = Some(parse_mhz(value).ok_or_else(invalid_line)?),
+                STOP_FREQ => stop_freq = Some(parse_mhz(value).ok_or_else(invalid_line)?),
+                SWEEP_POINTS => sweep_len = Some(value.parse().map_err(|_| invalid_line())?),
+                TIMESTAMP => {
+                    timestamp = Some(
+                        DateTime::parse_from_rfc3339(value)
+                            .map_err(|_| invalid_line())?
+                            .with_timezone(&Utc),
+                    )
+                }
+                // The step size follows from the other fields, and other header fields are
+                // skipped so files with more of them can still be read
+                _ => {}
+            }
+        }
+
+        let (Some(start_freq), Some(stop_freq), Some(sweep_len), Some(timestamp)) =
+            (start_freq, stop_freq, sweep_len, timestamp)
+        else {
+            return Err(invalid_data("The CSV header is incomplete".to_string()));
+        };
+        if amplitudes_dbm.len() != sweep_len {
+            return Err(invalid_data(format!(
+                "The CSV has {} points instead of {sweep_len}",
+                amplitudes_dbm.len()
+            )));
+        }
+        Ok(Self {
+            amplitudes_dbm,
+            timestamp,
+            meta: SweepMeta {
+                start_freq,
+                stop_freq,
+                captured_at_adjusted: timestamp,
+                ..SweepMeta::default()
+            },
+        })
+    }
+
+    fn step_size_hz(&self) -> f64 {
+        if self.amplitudes_dbm.len() < 2 {
+            return 0.;
+        }
+        (self.meta.stop_freq.as_hz_f64() - self.meta.start_freq.as_hz_f64())
+            / (self.amplitudes_dbm.len() - 1) as f64
+    }
+}
+
+fn format_mhz(freq: Frequency) -> String {
+    format!(
+        "{}.{:06}",
+        freq.as_hz() / 1_000_000,
+        freq.as_hz() % 1_000_000
+    )
+}
+
+fn parse_mhz(mhz: &str) -> Option<Frequency> {
+    let mhz: f64 = mhz.parse().ok()?;
+    (mhz.is_finite() && mhz >= 0.).then(|| Frequency::from_mhz_f64(mhz))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sweep() -> Sweep {
+        let start_freq = Frequency::from_hz(430_000_000);
+        let stop_freq = Frequency::from_hz(430_357_142);
+        let timestamp = DateTime::parse_from_rfc3339("2024-05-01T12:30:45.123Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        Sweep {
+            amplitudes_dbm: vec![-100., -87.5, -42.25],
+            timestamp,
+            meta: SweepMeta {
+                start_freq,
+                stop_freq,
+                captured_at_adjusted: timestamp,
+                ..SweepMeta::default()
+            },
+        }
+    }
+
+    #[test]
+    fn write_header_and_one_row_per_point() {
+        let mut csv = Vec::new();
+        sweep().to_csv_writer(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "RF Explorer CSV data file\n\
+             Start Frequency (MHz),430.000000\n\
+             Stop Frequency (MHz),430.357142\n\
+             Step Frequency (MHz),0.178571\n\
+             Sweep Points,3\n\
+             Timestamp,2024-05-01T12:30:45.123Z\n\
+             Frequency (MHz),Amplitude (dBm)\n\
+             430.000000,-100\n\
+             430.178571,-87.5\n\
+             430.357142,-42.25\n"
+        );
+    }
+
+    #[test]
+    fn round_trip_through_csv() {
+        let mut csv = Vec::new();
+        sweep().to_csv_writer(&mut csv).unwrap();
+        assert_eq!(Sweep::from_csv_reader(csv.as_slice()).unwrap(), sweep());
+
+        // Files saved on Windows end their lines with \r\n
+        let csv = String::from_utf8(csv).unwrap().replace('\n', "\r\n");
+        assert_eq!(Sweep::from_csv_reader(csv.as_bytes()).unwrap(), sweep());
+    }
+
+    #[test]
+    fn reject_invalid_csv() {
+        let mut csv = Vec::new();
+        sweep().to_csv_writer(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        for invalid in [
+            // A decimal comma splits the amplitude into another field
+            csv.replace("-87.5", "-87,5"),
+            csv.replace("Sweep Points,3", "Sweep Points,4"),
+            csv.replace("Timestamp,2024-05-01T12:30:45.123Z\n", ""),
+            csv.replace("430.357142,-42.25", "430.357142"),
+        ] {
+            let error = Sweep::from_csv_reader(invalid.as_bytes()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{invalid}");
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use chrono::{DateTime, Utc};
+
+use super::{Event, InputStage};
+use crate::Frequency;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// The settings that were in effect when a sweep was received.
+///
+/// Whether the RF Explorer's firmware includes the input stage's gain or attenuation and the
+/// amplitude offset in the amplitudes it reports isn't documented, so the correction needed to get
+/// absolute amplitudes is only known when neither is in effect. See [`SweepMeta::correction_db`].
+pub struct SweepMeta {
+    /// The input stage that was active, if reported by the device.
+    pub input_stage: Option<InputStage>,
+    /// The amplitude offset in dB, if reported by the device.
+    pub amp_offset_db: Option<i8>,
+    /// The firmware version of the device that measured the sweep.
+    pub firmware_version: String,
+    /// When the sweep was received, minus the estimated latency of the serial connection.
+    ///
+    /// This is the same as the time the sweep was received until the latency has been estimated
+    /// with [`SpectrumAnalyzer::estimate_latency`](crate::SpectrumAnalyzer::estimate_latency) or
+    /// if latency adjustment has been disabled.
+    pub captured_at_adjusted: DateTime<Utc>,
+    /// The [generation](crate::SpectrumAnalyzer::config_generation) of the config that was in
+    /// effect when the sweep was received.
+    ///
+    /// Sweeps from an older generation were received before the config last changed, so they
+    /// don't belong to the current frequency range.
+    pub config_generation: u64,
+    /// The start frequency of the config that was in effect when the sweep was received.
+    pub start_freq: Frequency,
+    /// The stop frequency of the config that was in effect when the sweep was received.
+    pub stop_freq: Frequency,
+    /// The number of points the sweep had when it was received, if that didn't match the
+    /// config's sweep length.
+    ///
+    /// The sweep was kept because of the
+    /// [`MismatchedSweepPolicy`](crate::spectrum_analyzer::MismatchedSweepPolicy), which may have
+    /// truncated or padded it to the config's sweep length.
+    pub mismatched_len: Option<usize>,
+    /// Whether the sweep was flagged as probably corrupt by the heuristic set with
+    /// [`SpectrumAnalyzer::set_corruption_heuristic`](crate::SpectrumAnalyzer::set_corruption_heuristic).
+    pub suspected_corrupt: bool,
+    /// Whether the sweep was received right after the automatic input stage controller switched
+    /// input stages.
+    ///
+    /// The sweep may have been measured with either input stage, so
+    /// [`SweepMeta::correction_db`] may not match it. See
+    /// [`SpectrumAnalyzer::set_auto_input_stage`](crate::SpectrumAnalyzer::set_auto_input_stage).
+    pub input_stage_settling: bool,
+    /// The wire format the sweep was received in.
+    pub encoding: SweepEncoding,
+    /// The events recorded with
+    /// [`SpectrumAnalyzer::annotate_event`](crate::SpectrumAnalyzer::annotate_event) after the
+    /// previous sweep was received and before this one was, oldest first.
+    pub events_since_previous_sweep: Vec<Event>,
+}
+
+/// The wire format of a sweep message.
+///
+/// Every format sends one byte per point, so amplitudes are quantized to the same
+/// [resolution](SweepEncoding::amplitude_resolution_db) whichever format the RF Explorer uses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum SweepEncoding {
+    /// The classic `$S` format with up to 255 points.
+    #[default]
+    Standard,
+    /// The extended `$s` format with up to 4096 points in multiples of 16.
+    Extended,
+    /// The large `$z` format with up to 65535 points.
+    Large,
+}
+
+impl SweepEncoding {
+    /// Returns the step in dB between the amplitudes that can be represented in this format.
+    ///
+    /// Differences between amplitudes smaller than this step are lost when the sweep is sent.
+    pub fn amplitude_resolution_db(self) -> f32 {
+        match self {
+            SweepEncoding::Standard | SweepEncoding::Extended | SweepEncoding::Large => 0.5,
+        }
+    }
+
+    /// Returns the standard deviation in dB of the error caused by rounding amplitudes to the
+    /// format's resolution.
+    ///
+    /// A standard deviation below this floor can't be told apart from quantization noise.
+    pub fn quantization_std_dev_db(self) -> f32 {
+        self.amplitude_resolution_db() / 12f32.sqrt()
+    }
+}
+
+impl SweepMeta {
+    /// Returns the correction in dB that must be added to the reported amplitudes to get absolute
+    /// amplitudes, or `None` if it's unknown.
+    ///
+    /// The correction is 0 dB when the direct input stage and no amplitude offset were in effect,
+    /// or neither was reported. It's unknown when any other input stage or an amplitude offset was
+    /// in effect, because whether the firmware already applied them isn't documented.
+    pub fn correction_db(&self) -> Option<f32> {
+        let direct = self
+            .input_stage
+            .is_none_or(|input_stage| input_stage == InputStage::Direct);
+        let no_offset = self
+            .amp_offset_db
+            .is_none_or(|amp_offset_db| amp_offset_db == 0);
+        (direct && no_offset).then_some(0.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(input_stage: InputStage, amp_offset_db: i8) -> SweepMeta {
+        SweepMeta {
+            input_stage: Some(input_stage),
+            amp_offset_db: Some(amp_offset_db),
+            firmware_version: "01.12B26".to_string(),
+            ..SweepMeta::default()
+        }
+    }
+
+    #[test]
+    fn no_correction_without_input_stage_or_offset() {
+        assert_eq!(meta(InputStage::Direct, 0).correction_db(), Some(0.));
+        assert_eq!(SweepMeta::default().correction_db(), Some(0.));
+    }
+
+    #[test]
+    fn unknown_correction_with_input_stage_or_offset() {
+        assert_eq!(meta(InputStage::Direct, -5).correction_db(), None);
+        assert_eq!(meta(InputStage::Attenuator30dB, 0).correction_db(), None);
+        assert_eq!(meta(InputStage::Lna25dB, 10).correction_db(), None);
+        let meta = SweepMeta {
+            input_stage: Some(InputStage::Lna12dB),
+            ..SweepMeta::default()
+        };
+        assert_eq!(meta.correction_db(), None);
+    }
+
+    #[test]
+    fn amplitude_resolution_of_each_encoding() {
+        for encoding in [
+            SweepEncoding::Standard,
+            SweepEncoding::Extended,
+            SweepEncoding::Large,
+        ] {
+            assert_eq!(encoding.amplitude_resolution_db(), 0.5);
+            assert!((encoding.quantization_std_dev_db() - 0.1443).abs() < 1e-4);
+        }
+        assert_eq!(SweepMeta::default().encoding, SweepEncoding::Standard);
+    }
+}
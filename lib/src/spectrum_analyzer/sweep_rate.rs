@@ -0,0 +1,70 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+/// Estimates how many sweeps a spectrum analyzer sends per second from the time between them.
+///
+/// Each interval is smoothed into the estimate the same way latency samples are, so a single
+/// late sweep doesn't make the rate jump.
+#[derive(Debug, Default)]
+pub(crate) struct SweepRate {
+    last_sweep_at: Option<DateTime<Utc>>,
+    interval: Option<TimeDelta>,
+}
+
+impl SweepRate {
+    pub(crate) fn on_sweep(&mut self, received_at: DateTime<Utc>) {
+        if let Some(last_sweep_at) = self.last_sweep_at {
+            let sample = received_at - last_sweep_at;
+            // Sweeps received out of order or at the same time say nothing about the rate
+            if sample > TimeDelta::zero() {
+                self.interval = Some(
+                    self.interval
+                        .map_or(sample, |interval| (interval * 3 + sample) / 4),
+                );
+            }
+        }
+        self.last_sweep_at = Some(received_at);
+    }
+
+    /// Forgets the previous sweeps, because the rate changes with the config.
+    pub(crate) fn reset(&mut self) {
+        *self = SweepRate::default();
+    }
+
+    pub(crate) fn hz(&self) -> Option<f64> {
+        let interval_us = self.interval?.num_microseconds()?;
+        (interval_us > 0).then(|| 1_000_000. / interval_us as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smooth_intervals_between_sweeps() {
+        let start = Utc::now();
+        let mut rate = SweepRate::default();
+        rate.on_sweep(start);
+        assert_eq!(rate.hz(), None);
+
+        rate.on_sweep(start + TimeDelta::milliseconds(100));
+        assert_eq!(rate.hz(), Some(10.));
+
+        // A 500 ms gap only moves the 100 ms interval a quarter of the way toward it
+        rate.on_sweep(start + TimeDelta::milliseconds(600));
+        assert_eq!(rate.hz(), Some(5.));
+
+        rate.reset();
+        assert_eq!(rate.hz(), None);
+    }
+
+    #[test]
+    fn ignore_sweeps_received_out_of_order() {
+        let start = Utc::now();
+        let mut rate = SweepRate::default();
+        rate.on_sweep(start);
+        rate.on_sweep(start);
+        rate.on_sweep(start - TimeDelta::milliseconds(50));
+        assert_eq!(rate.hz(), None);
+    }
+}
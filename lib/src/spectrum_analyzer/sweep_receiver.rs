@@ -0,0 +1,290 @@
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{Arc, Condvar, Mutex, Weak},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use super::SweepMeta;
+use crate::{Error, Frequency, Result};
+
+/// A sweep delivered to a [`SweepReceiver`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferedSweep {
+    /// The sweep's amplitudes in dBm.
+    pub amplitudes_dbm: Vec<f32>,
+    /// The start frequency of the config the sweep was received with.
+    pub start_freq: Frequency,
+    /// The stop frequency of the config the sweep was received with.
+    pub stop_freq: Frequency,
+    /// When the sweep was received.
+    pub timestamp: DateTime<Utc>,
+    /// The settings that were in effect when the sweep was received.
+    pub meta: SweepMeta,
+}
+
+/// Receives every sweep a spectrum analyzer receives, buffering them while they aren't being
+/// received fast enough.
+///
+/// Created with [`SpectrumAnalyzer::subscribe_buffered`](crate::SpectrumAnalyzer::subscribe_buffered).
+/// When the buffer is full, the oldest sweep is dropped to make room for the newest one and
+/// counted in [`SweepReceiver::dropped_sweeps`]. Dropping the receiver stops sweeps from being
+/// buffered for it.
+#[derive(Debug)]
+pub struct SweepReceiver {
+    queue: Arc<SweepQueue>,
+}
+
+impl SweepReceiver {
+    /// Waits for the next sweep, or for the timeout duration to elapse.
+    ///
+    /// Returns [`Error::Disconnected`] once the RF Explorer is disconnected and every buffered
+    /// sweep has been received.
+    pub fn recv(&self, timeout: Duration) -> Result<BufferedSweep> {
+        let state = self.queue.state.lock().unwrap();
+        let (mut state, _) = self
+            .queue
+            .condvar
+            .wait_timeout_while(state, timeout, |state| {
+                state.sweeps.is_empty() && state.disconnection.is_none()
+            })
+            .unwrap();
+        if let Some(sweep) = state.sweeps.pop_front() {
+            return Ok(sweep);
+        }
+        match state.disconnection.as_ref() {
+            Some(error) => Err(Error::Disconnected(error.clone())),
+            None => Err(Error::TimedOut(timeout)),
+        }
+    }
+
+    /// Returns the next buffered sweep without waiting, or `None` if none are buffered.
+    pub fn try_recv(&self) -> Option<BufferedSweep> {
+        self.queue.state.lock().unwrap().sweeps.pop_front()
+    }
+
+    /// Returns the number of sweeps that are buffered.
+    pub fn len(&self) -> usize {
+        self.queue.state.lock().unwrap().sweeps.len()
+    }
+
+    /// Returns `true` if no sweeps are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of sweeps that were dropped because the buffer was full.
+    pub fn dropped_sweeps(&self) -> u64 {
+        self.queue.state.lock().unwrap().dropped_sweeps
+    }
+}
+
+#[derive(Debug)]
+struct SweepQueue {
+    state: Mutex<SweepQueueState>,
+    condvar: Condvar,
+    capacity: usize,
+}
+
+#[derive(Debug, Default)]
+struct SweepQueueState {
+    sweeps: VecDeque<BufferedSweep>,
+    dropped_sweeps: u64,
+    disconnection: Option<Arc<io::Error>>,
+}
+
+impl SweepQueue {
+    fn push(&self, sweep: BufferedSweep) {
+        let mut state = self.state.lock().unwrap();
+        if state.sweeps.len() >= self.capacity {
+            state.sweeps.pop_front();
+            state.dropped_sweeps += 1;
+        }
+        state.sweeps.push_back(sweep);
+        self.condvar.notify_all();
+    }
+
+    fn disconnect(&self, error: Arc<io::Error>) {
+        self.state
+            .lock()
+            .unwrap()
+            .disconnection
+            .get_or_insert(error);
+        self.condvar.notify_all();
+    }
+}
+
+/// The buffers of every [`SweepReceiver`] subscribed to a spectrum analyzer.
+#[derive(Debug, Default)]
+pub(crate) struct SweepSubscribers {
+    queues: Mutex<Vec<Weak<SweepQueue>>>,
+    disconnection: Mutex<Option<Arc<io::Error>>>,
+}
+
+impl SweepSubscribers {
+    /// Creates a receiver that buffers up to `depth` sweeps.
+    pub(crate) fn subscribe(&self, depth: usize) -> SweepReceiver {
+        let queue = Arc::new(SweepQueue {
+            state: Mutex::default(),
+            condvar: Condvar::new(),
+            capacity: depth.max(1),
+        });
+        // Hold the lock while checking for a disconnection so it can't be missed
+        let mut queues = self.queues.lock().unwrap();
+        if let Some(error) = self.disconnection.lock().unwrap().clone() {
+            queue.disconnect(error);
+        }
+        queues.push(Arc::downgrade(&queue));
+        SweepReceiver { queue }
+    }
+
+    /// Returns an estimate of the memory used by every receiver's buffered sweeps, in bytes.
+    pub(crate) fn memory_usage_estimate(&self) -> usize {
+        self.queues
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .flat_map(|queue| {
+                let state = queue.state.lock().unwrap();
+                state
+                    .sweeps
+                    .iter()
+                    .map(|sweep| {
+                        size_of::<BufferedSweep>()
+                            + sweep.amplitudes_dbm.capacity() * size_of::<f32>()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .sum()
+    }
+
+    /// Returns `true` if no receivers are subscribed.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.queues.lock().unwrap().is_empty()
+    }
+
+    /// Buffers a sweep for every receiver, forgetting receivers that have been dropped.
+    pub(crate) fn push(&self, sweep: BufferedSweep) {
+        let mut queues = self.queues.lock().unwrap();
+        queues.retain(|queue| queue.strong_count() > 0);
+        let Some((last, rest)) = queues.split_last() else {
+            return;
+        };
+        for queue in rest.iter().filter_map(Weak::upgrade) {
+            queue.push(sweep.clone());
+        }
+        if let Some(queue) = last.upgrade() {
+            queue.push(sweep);
+        }
+    }
+
+    /// Wakes every receiver so they stop waiting for sweeps that will never arrive.
+    pub(crate) fn disconnect(&self, error: Arc<io::Error>) {
+        let queues = self.queues.lock().unwrap();
+        self.disconnection
+            .lock()
+            .unwrap()
+            .get_or_insert(error.clone());
+        for queue in queues.iter().filter_map(Weak::upgrade) {
+            queue.disconnect(error.clone());
+        }
+    }
+
+    /// Lets every receiver wait for sweeps again after the RF Explorer was reconnected.
+    pub(crate) fn reconnect(&self) {
+        let queues = self.queues.lock().unwrap();
+        self.disconnection.lock().unwrap().take();
+        for queue in queues.iter().filter_map(Weak::upgrade) {
+            queue.state.lock().unwrap().disconnection.take();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sweep(amplitude_dbm: f32) -> BufferedSweep {
+        BufferedSweep {
+            amplitudes_dbm: vec![amplitude_dbm],
+            start_freq: Frequency::from_mhz(100),
+            stop_freq: Frequency::from_mhz(200),
+            timestamp: Utc::now(),
+            meta: SweepMeta::default(),
+        }
+    }
+
+    #[test]
+    fn drop_oldest_sweeps_when_full() {
+        let subscribers = SweepSubscribers::default();
+        let receiver = subscribers.subscribe(2);
+        for amplitude_dbm in [-100., -90., -80.] {
+            subscribers.push(sweep(amplitude_dbm));
+        }
+
+        assert_eq!(receiver.dropped_sweeps(), 1);
+        assert_eq!(receiver.len(), 2);
+        assert_eq!(receiver.try_recv().unwrap().amplitudes_dbm, [-90.]);
+        assert_eq!(
+            receiver
+                .recv(Duration::from_millis(10))
+                .unwrap()
+                .amplitudes_dbm,
+            [-80.]
+        );
+        assert!(receiver.try_recv().is_none());
+        assert!(matches!(
+            receiver.recv(Duration::from_millis(10)),
+            Err(Error::TimedOut(_))
+        ));
+    }
+
+    #[test]
+    fn deliver_to_every_subscriber() {
+        let subscribers = SweepSubscribers::default();
+        let first = subscribers.subscribe(4);
+        let second = subscribers.subscribe(1);
+        subscribers.push(sweep(-100.));
+        subscribers.push(sweep(-90.));
+
+        assert_eq!(first.len(), 2);
+        assert_eq!(first.dropped_sweeps(), 0);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second.dropped_sweeps(), 1);
+    }
+
+    #[test]
+    fn unsubscribe_dropped_receivers() {
+        let subscribers = SweepSubscribers::default();
+        let kept = subscribers.subscribe(4);
+        drop(subscribers.subscribe(4));
+        subscribers.push(sweep(-100.));
+
+        assert_eq!(subscribers.queues.lock().unwrap().len(), 1);
+        assert_eq!(kept.len(), 1);
+        drop(kept);
+        subscribers.push(sweep(-90.));
+        assert!(subscribers.is_empty());
+    }
+
+    #[test]
+    fn receive_buffered_sweeps_before_disconnection() {
+        let subscribers = SweepSubscribers::default();
+        let receiver = subscribers.subscribe(4);
+        subscribers.push(sweep(-100.));
+        subscribers.disconnect(Arc::new(io::Error::from(io::ErrorKind::BrokenPipe)));
+
+        assert!(receiver.recv(Duration::from_millis(10)).is_ok());
+        assert!(matches!(
+            receiver.recv(Duration::from_millis(10)),
+            Err(Error::Disconnected(_))
+        ));
+        assert!(matches!(
+            subscribers.subscribe(4).recv(Duration::from_millis(10)),
+            Err(Error::Disconnected(_))
+        ));
+    }
+}
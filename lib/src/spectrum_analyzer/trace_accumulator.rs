@@ -0,0 +1,204 @@
+use super::Config;
+use crate::{Error, Result};
+
+/// Keeps the maximum amplitude of each sweep point across every sweep measured with one config.
+///
+/// The trace belongs to the config it was started with, identified by [`Config::fingerprint`].
+/// Save it with [`TraceAccumulator::save`] before disconnecting and resume it with
+/// [`TraceAccumulator::resume_from`] once the RF Explorer reconnects, so the max-hold survives
+/// reconnects that don't change the sweep settings.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::{Arc, Mutex};
+///
+/// use rfe::SpectrumAnalyzer;
+/// use rfe::spectrum_analyzer::TraceAccumulator;
+///
+/// let rfe = SpectrumAnalyzer::connect().expect("RF Explorer should be connected");
+/// let accumulator = Arc::new(Mutex::new(None::<TraceAccumulator>));
+///
+/// // Keep the trace while the config's fingerprint stays the same and start over when it changes
+/// let config_accumulator = Arc::clone(&accumulator);
+/// rfe.set_config_callback(move |config| {
+///     let mut accumulator = config_accumulator.lock().unwrap();
+///     let resumed = accumulator
+///         .take()
+///         .and_then(|trace| TraceAccumulator::resume_from(trace.save(), &config).ok());
+///     *accumulator = Some(resumed.unwrap_or_else(|| TraceAccumulator::new(&config)));
+/// });
+///
+/// let sweep_accumulator = Arc::clone(&accumulator);
+/// rfe.set_sweep_callback(move |amps, _, _| {
+///     if let Some(trace) = sweep_accumulator.lock().unwrap().as_mut() {
+///         let _ = trace.add_sweep(amps);
+///     }
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceAccumulator {
+    fingerprint: u64,
+    sweep_len: u16,
+    max_hold_dbm: Vec<f32>,
+    sweep_count: u64,
+}
+
+/// The state of a [`TraceAccumulator`], saved so that the trace can be resumed later.
+///
+/// With the `serde` feature, it can be serialized so it outlives the process.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SavedTrace {
+    fingerprint: u64,
+    max_hold_dbm: Vec<f32>,
+    sweep_count: u64,
+}
+
+impl TraceAccumulator {
+    /// Creates an empty trace for sweeps measured with `config`.
+    pub fn new(config: &Config) -> Self {
+        TraceAccumulator {
+            fingerprint: config.fingerprint(),
+            sweep_len: config.sweep_len,
+            max_hold_dbm: Vec::new(),
+            sweep_count: 0,
+        }
+    }
+
+    /// Resumes a saved trace if `config` has the same fingerprint as the config it was measured
+    /// with.
+    ///
+    /// If the start frequency, stop frequency, or sweep length changed, the saved amplitudes
+    /// would be compared against different frequencies, so an error is returned and the trace
+    /// should be started again with [`TraceAccumulator::new`].
+    pub fn resume_from(saved: SavedTrace, config: &Config) -> Result<Self> {
+        if saved.fingerprint != config.fingerprint() {
+            return Err(Error::InvalidInput(format!(
+                "The saved trace was measured with a different start frequency, stop frequency, or sweep length (fingerprint {:016x}, current config {:016x})",
+                saved.fingerprint,
+                config.fingerprint()
+            )));
+        }
+        if !saved.max_hold_dbm.is_empty()
+            && saved.max_hold_dbm.len() != usize::from(config.sweep_len)
+        {
+            return Err(Error::InvalidInput(format!(
+                "The saved trace has {} points but the config's sweep length is {}",
+                saved.max_hold_dbm.len(),
+                config.sweep_len
+            )));
+        }
+
+        Ok(TraceAccumulator {
+            fingerprint: saved.fingerprint,
+            sweep_len: config.sweep_len,
+            max_hold_dbm: saved.max_hold_dbm,
+            sweep_count: saved.sweep_count,
+        })
+    }
+
+    /// Adds a sweep's amplitudes to the trace.
+    ///
+    /// Returns an error if the sweep doesn't have the config's sweep length.
+    pub fn add_sweep(&mut self, amplitudes_dbm: &[f32]) -> Result<()> {
+        if amplitudes_dbm.len() != usize::from(self.sweep_len) {
+            return Err(Error::InvalidInput(format!(
+                "The sweep has {} points but the trace's sweep length is {}",
+                amplitudes_dbm.len(),
+                self.sweep_len
+            )));
+        }
+
+        if self.max_hold_dbm.is_empty() {
+            self.max_hold_dbm.extend_from_slice(amplitudes_dbm);
+        } else {
+            for (max, &amp) in self.max_hold_dbm.iter_mut().zip(amplitudes_dbm) {
+                *max = max.max(amp);
+            }
+        }
+        self.sweep_count += 1;
+        Ok(())
+    }
+
+    /// The maximum amplitude of each sweep point in dBm, or an empty slice if no sweeps have been
+    /// added.
+    pub fn max_hold_dbm(&self) -> &[f32] {
+        &self.max_hold_dbm
+    }
+
+    /// The number of sweeps added to the trace, including the ones added before it was saved.
+    pub fn sweep_count(&self) -> u64 {
+        self.sweep_count
+    }
+
+    /// The fingerprint of the config the trace belongs to.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Removes every sweep from the trace.
+    pub fn clear(&mut self) {
+        self.max_hold_dbm.clear();
+        self.sweep_count = 0;
+    }
+
+    /// Saves the trace so it can be resumed with [`TraceAccumulator::resume_from`].
+    pub fn save(&self) -> SavedTrace {
+        SavedTrace {
+            fingerprint: self.fingerprint,
+            max_hold_dbm: self.max_hold_dbm.clone(),
+            sweep_count: self.sweep_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(bytes: &[u8]) -> Config {
+        Config::try_from(bytes).unwrap()
+    }
+
+    fn wsub3g_config() -> Config {
+        config(b"#C2-F:0430000,0178571,-030,-118,0004,0,000,0015000,2700000,2685000,00200,0000,000")
+    }
+
+    #[test]
+    fn keep_max_of_each_point() {
+        let mut accumulator = TraceAccumulator::new(&wsub3g_config());
+        accumulator.add_sweep(&[-90., -80., -70., -60.]).unwrap();
+        accumulator.add_sweep(&[-50., -85., -75., -40.]).unwrap();
+        assert_eq!(accumulator.max_hold_dbm(), [-50., -80., -70., -40.]);
+        assert_eq!(accumulator.sweep_count(), 2);
+        assert!(accumulator.add_sweep(&[-90.; 3]).is_err());
+    }
+
+    #[test]
+    fn resume_with_different_amp_range() {
+        let mut accumulator = TraceAccumulator::new(&wsub3g_config());
+        accumulator.add_sweep(&[-90., -80., -70., -60.]).unwrap();
+
+        let reconnected_config = config(
+            b"#C2-F:0430000,0178571,-010,-100,0004,0,000,0015000,2700000,2685000,00200,0000,000",
+        );
+        let resumed =
+            TraceAccumulator::resume_from(accumulator.save(), &reconnected_config).unwrap();
+        assert_eq!(resumed, accumulator);
+    }
+
+    #[test]
+    fn refuse_to_resume_with_different_freqs() {
+        let mut accumulator = TraceAccumulator::new(&wsub3g_config());
+        accumulator.add_sweep(&[-90., -80., -70., -60.]).unwrap();
+
+        let reconnected_config = config(
+            b"#C2-F:0500000,0178571,-030,-118,0004,0,000,0015000,2700000,2685000,00200,0000,000",
+        );
+        assert!(matches!(
+            TraceAccumulator::resume_from(accumulator.save(), &reconnected_config),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+}
@@ -1,10 +1,10 @@
-use std::convert::TryFrom;
+use std::{convert::TryFrom, io};
 
 use nom::Parser;
 use nom::{bytes::complete::tag, combinator::map_res, number::complete::u8 as nom_u8};
 use num_enum::TryFromPrimitive;
 
-use crate::common::MessageParseError;
+use crate::common::{MessageParseError, WriteMessage};
 use crate::rf_explorer::parsers::*;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, TryFromPrimitive, Default)]
@@ -19,7 +19,8 @@ pub enum TrackingStatus {
 }
 
 impl TrackingStatus {
-    pub(crate) const PREFIX: &'static [u8] = b"#K";
+    /// The bytes every tracking status message starts with.
+    pub const PREFIX: &'static [u8] = b"#K";
 }
 
 impl<'a> TryFrom<&'a [u8]> for TrackingStatus {
@@ -39,6 +40,14 @@ impl<'a> TryFrom<&'a [u8]> for TrackingStatus {
     }
 }
 
+impl WriteMessage for TrackingStatus {
+    fn write_message(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(TrackingStatus::PREFIX)?;
+        writer.write_all(&[*self as u8])?;
+        writer.write_all(b"\r\n")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,4 +58,18 @@ mod tests {
         let tracking_status = TrackingStatus::try_from(bytes.as_ref()).unwrap();
         assert_eq!(tracking_status, TrackingStatus::Disabled);
     }
+
+    #[test]
+    fn write_tracking_status() {
+        assert_eq!(
+            TrackingStatus::Enabled.to_bytes(),
+            [b'#', b'K', 1, b'\r', b'\n']
+        );
+        for tracking_status in [TrackingStatus::Disabled, TrackingStatus::Enabled] {
+            assert_eq!(
+                TrackingStatus::try_from(tracking_status.to_bytes().as_slice()),
+                Ok(tracking_status)
+            );
+        }
+    }
 }
@@ -1,6 +1,10 @@
-use num_enum::IntoPrimitive;
+use std::ops::RangeInclusive;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, IntoPrimitive)]
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::Frequency;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
 #[repr(u8)]
 /// Wi-Fi band used by Wi-Fi analyzer mode.
 pub enum WifiBand {
@@ -9,3 +13,106 @@ pub enum WifiBand {
     /// 5 GHz Wi-Fi band.
     FiveGhz,
 }
+
+impl WifiBand {
+    const TWO_POINT_FOUR_GHZ_CHANNELS: [u8; 14] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14];
+    // 20 MHz channels in the U-NII-1, U-NII-2, U-NII-2 Extended, and U-NII-3 sub-bands
+    const FIVE_GHZ_CHANNELS: [u8; 25] = [
+        36, 40, 44, 48, 52, 56, 60, 64, 100, 104, 108, 112, 116, 120, 124, 128, 132, 136, 140, 144,
+        149, 153, 157, 161, 165,
+    ];
+
+    /// Returns the range of frequencies the band's channels occupy.
+    pub fn freq_range(&self) -> RangeInclusive<Frequency> {
+        match self {
+            WifiBand::TwoPointFourGhz => Frequency::from_mhz(2_400)..=Frequency::from_mhz(2_500),
+            WifiBand::FiveGhz => Frequency::from_mhz(5_150)..=Frequency::from_mhz(5_850),
+        }
+    }
+
+    /// Returns the band's 20 MHz channels and their center frequencies.
+    pub fn channels(&self) -> Vec<WifiChannel> {
+        let channel_numbers: &[u8] = match self {
+            WifiBand::TwoPointFourGhz => &Self::TWO_POINT_FOUR_GHZ_CHANNELS,
+            WifiBand::FiveGhz => &Self::FIVE_GHZ_CHANNELS,
+        };
+
+        channel_numbers
+            .iter()
+            .map(|&number| WifiChannel {
+                number,
+                center_freq: self.channel_center_freq(number),
+            })
+            .collect()
+    }
+
+    fn channel_center_freq(&self, number: u8) -> Frequency {
+        match self {
+            // Channel 14 doesn't follow the 5 MHz spacing of the other 2.4 GHz channels
+            WifiBand::TwoPointFourGhz if number == 14 => Frequency::from_mhz(2_484),
+            WifiBand::TwoPointFourGhz => Frequency::from_mhz(2_407 + 5 * u64::from(number)),
+            WifiBand::FiveGhz => Frequency::from_mhz(5_000 + 5 * u64::from(number)),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+/// A Wi-Fi channel within a [`WifiBand`].
+pub struct WifiChannel {
+    /// The channel number.
+    pub number: u8,
+    /// The channel's center frequency.
+    pub center_freq: Frequency,
+}
+
+impl WifiChannel {
+    /// The bandwidth of a Wi-Fi channel.
+    pub fn bandwidth(&self) -> Frequency {
+        Frequency::from_mhz(20)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_point_four_ghz_channels() {
+        let channels = WifiBand::TwoPointFourGhz.channels();
+        assert_eq!(channels.len(), 14);
+        assert_eq!(channels[0].center_freq, Frequency::from_mhz(2_412));
+        assert_eq!(channels[5].center_freq, Frequency::from_mhz(2_437));
+        assert_eq!(channels[10].center_freq, Frequency::from_mhz(2_462));
+        assert_eq!(channels[12].center_freq, Frequency::from_mhz(2_472));
+        assert_eq!(channels[13].center_freq, Frequency::from_mhz(2_484));
+    }
+
+    #[test]
+    fn five_ghz_channels() {
+        let channels = WifiBand::FiveGhz.channels();
+        assert_eq!(channels.first().unwrap().number, 36);
+        assert_eq!(
+            channels.first().unwrap().center_freq,
+            Frequency::from_mhz(5_180)
+        );
+        assert_eq!(channels[8].number, 100);
+        assert_eq!(channels[8].center_freq, Frequency::from_mhz(5_500));
+        assert_eq!(channels.last().unwrap().number, 165);
+        assert_eq!(
+            channels.last().unwrap().center_freq,
+            Frequency::from_mhz(5_825)
+        );
+    }
+
+    #[test]
+    fn channels_are_within_band() {
+        for band in [WifiBand::TwoPointFourGhz, WifiBand::FiveGhz] {
+            let freq_range = band.freq_range();
+            for channel in band.channels() {
+                let half_bandwidth = channel.bandwidth() / 2;
+                assert!(freq_range.contains(&(channel.center_freq - half_bandwidth)));
+                assert!(freq_range.contains(&(channel.center_freq + half_bandwidth)));
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::ops::RangeInclusive;
+
+use crate::Frequency;
+
+/// Returns the start and stop frequencies of a span centered as close to `center` as a radio
+/// module's frequency and span ranges allow.
+///
+/// The span is clamped to the span range and to the width of the frequency range. If the span
+/// would cross an edge of the frequency range, it's moved inside of it instead of being narrowed,
+/// so it's no longer centered.
+pub(crate) fn centered_start_stop(
+    center: Frequency,
+    span: Frequency,
+    freq_range: &RangeInclusive<Frequency>,
+    span_range: &RangeInclusive<Frequency>,
+) -> (Frequency, Frequency) {
+    let (min_freq, max_freq) = (*freq_range.start(), *freq_range.end());
+    let max_span = (*span_range.end()).min(max_freq - min_freq);
+    let span = span.clamp((*span_range.start()).min(max_span), max_span);
+    let start = (center.max(span / 2) - span / 2).clamp(min_freq, max_freq - span);
+    (start, start + span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mhz_range(start: u64, end: u64) -> RangeInclusive<Frequency> {
+        Frequency::from_mhz(start)..=Frequency::from_mhz(end)
+    }
+
+    fn center_mhz(center: u64, span: u64) -> (u64, u64) {
+        // The frequency and span ranges of the RF Explorer 6G
+        let (start, stop) = centered_start_stop(
+            Frequency::from_mhz(center),
+            Frequency::from_mhz(span),
+            &mhz_range(4_850, 6_100),
+            &mhz_range(2, 600),
+        );
+        (start.as_mhz(), stop.as_mhz())
+    }
+
+    #[test]
+    fn center_span_within_range() {
+        assert_eq!(center_mhz(5_500, 20), (5_490, 5_510));
+    }
+
+    #[test]
+    fn move_span_inside_range_at_edges() {
+        assert_eq!(center_mhz(4_855, 20), (4_850, 4_870));
+        assert_eq!(center_mhz(6_095, 20), (6_080, 6_100));
+        assert_eq!(center_mhz(10, 20), (4_850, 4_870));
+    }
+
+    #[test]
+    fn clamp_span() {
+        assert_eq!(center_mhz(5_500, 1), (5_499, 5_501));
+        assert_eq!(center_mhz(5_500, 2_000), (5_200, 5_800));
+    }
+}
@@ -0,0 +1,340 @@
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{Error, Result};
+
+type ProgressCallback = Option<Arc<dyn Fn(f32) + Send + Sync + 'static>>;
+
+/// A long-running operation, like [`measurements::hop_monitor`](crate::measurements::hop_monitor),
+/// that runs on a background thread.
+///
+/// The task's cleanup, like restoring the spectrum analyzer's frequency range, runs on the task's
+/// thread once the task finishes, whether it succeeded, failed, was canceled, or panicked.
+/// Dropping the handle cancels the task.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{sync::Arc, time::Duration};
+///
+/// use rfe::{Frequency, SpectrumAnalyzer, measurements};
+///
+/// let rfe = Arc::new(SpectrumAnalyzer::connect().expect("RF Explorer should be connected"));
+/// let channels = [Frequency::from_mhz(903), Frequency::from_mhz(905)];
+/// let task = measurements::hop_monitor(rfe, &channels, Duration::from_secs(30), -70.);
+/// task.set_progress_callback(|progress| println!("{:.0}%", progress * 100.));
+/// let report = task.wait(Duration::from_secs(60))?;
+/// # Ok::<(), rfe::Error>(())
+/// ```
+pub struct TaskHandle<T> {
+    control: Arc<Control>,
+    completion: Arc<Completion<T>>,
+}
+
+/// Lets a running task check whether it's been canceled and report its progress.
+pub struct TaskContext {
+    control: Arc<Control>,
+}
+
+#[derive(Default)]
+struct Control {
+    is_canceled: AtomicBool,
+    progress_callback: Mutex<ProgressCallback>,
+}
+
+struct Completion<T> {
+    outcome: Mutex<Outcome<T>>,
+    condvar: Condvar,
+}
+
+enum Outcome<T> {
+    Running,
+    Finished(Result<T>),
+    Taken,
+}
+
+impl<T: Send + 'static> TaskHandle<T> {
+    /// Runs `work` on a new thread, followed by `cleanup`.
+    ///
+    /// `cleanup` runs even if `work` fails, is canceled, or panics. If `work` succeeds but
+    /// `cleanup` fails, the task fails with the cleanup's error.
+    pub fn spawn(
+        work: impl FnOnce(&TaskContext) -> Result<T> + Send + 'static,
+        cleanup: impl FnOnce() -> Result<()> + Send + 'static,
+    ) -> Self {
+        let control = Arc::new(Control::default());
+        let completion = Arc::new(Completion {
+            outcome: Mutex::new(Outcome::Running),
+            condvar: Condvar::new(),
+        });
+
+        let context = TaskContext {
+            control: control.clone(),
+        };
+        let task_completion = completion.clone();
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| work(&context)))
+                .unwrap_or_else(|_| Err(panicked("task")));
+            let cleanup_result = panic::catch_unwind(AssertUnwindSafe(cleanup))
+                .unwrap_or_else(|_| Err(panicked("task's cleanup")));
+            let result = with_cleanup_result(result, cleanup_result);
+
+            *task_completion.outcome.lock().unwrap() = Outcome::Finished(result);
+            task_completion.condvar.notify_all();
+        });
+
+        TaskHandle {
+            control,
+            completion,
+        }
+    }
+}
+
+impl<T> TaskHandle<T> {
+    /// Asks the task to stop at its next opportunity.
+    ///
+    /// The task's cleanup still runs, so [`TaskHandle::wait`] for the task to make sure it's done
+    /// before using the device again. Waiting for a canceled task returns
+    /// [`Error::InvalidOperation`] unless it finished before it noticed it was canceled.
+    pub fn cancel(&self) {
+        self.control.is_canceled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` once the task and its cleanup have finished.
+    pub fn is_finished(&self) -> bool {
+        !matches!(*self.completion.outcome.lock().unwrap(), Outcome::Running)
+    }
+
+    /// Waits for the task and its cleanup to finish and returns its result.
+    ///
+    /// Returns [`Error::TimedOut`] if the task is still running after the timeout, in which case
+    /// it keeps running and can be waited for again. The result can only be returned once, so
+    /// waiting again afterwards returns [`Error::InvalidOperation`].
+    pub fn wait(&self, timeout: Duration) -> Result<T> {
+        let outcome = self.completion.outcome.lock().unwrap();
+        let (mut outcome, _) = self
+            .completion
+            .condvar
+            .wait_timeout_while(outcome, timeout, |outcome| {
+                matches!(outcome, Outcome::Running)
+            })
+            .unwrap();
+        match std::mem::replace(&mut *outcome, Outcome::Taken) {
+            Outcome::Finished(result) => result,
+            Outcome::Running => {
+                *outcome = Outcome::Running;
+                Err(Error::TimedOut(timeout))
+            }
+            Outcome::Taken => Err(Error::InvalidOperation(
+                "The task's result was already returned".to_string(),
+            )),
+        }
+    }
+
+    /// Sets the callback that's called on the task's thread with the task's progress, from 0 to 1.
+    pub fn set_progress_callback(&self, cb: impl Fn(f32) + Send + Sync + 'static) {
+        *self.control.progress_callback.lock().unwrap() = Some(Arc::new(cb));
+    }
+
+    /// Removes the callback set with [`TaskHandle::set_progress_callback`].
+    pub fn remove_progress_callback(&self) {
+        *self.control.progress_callback.lock().unwrap() = None;
+    }
+}
+
+impl<T> Drop for TaskHandle<T> {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+impl<T> fmt::Debug for TaskHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskHandle")
+            .field("is_canceled", &self.control.is_canceled())
+            .field("is_finished", &self.is_finished())
+            .finish()
+    }
+}
+
+impl TaskContext {
+    /// Returns `true` once the task has been canceled.
+    pub fn is_canceled(&self) -> bool {
+        self.control.is_canceled()
+    }
+
+    /// Returns [`Error::InvalidOperation`] once the task has been canceled, so the task can stop
+    /// with `?`.
+    pub fn check_canceled(&self) -> Result<()> {
+        if self.is_canceled() {
+            Err(Error::InvalidOperation("The task was canceled".to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Calls the task's progress callback, if it has one, with a progress from 0 to 1.
+    pub fn report_progress(&self, progress: f32) {
+        let cb = self.control.progress_callback.lock().unwrap().clone();
+        if let Some(cb) = cb {
+            cb(progress.clamp(0., 1.));
+        }
+    }
+}
+
+impl fmt::Debug for TaskContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskContext")
+            .field("is_canceled", &self.is_canceled())
+            .finish()
+    }
+}
+
+impl Control {
+    fn is_canceled(&self) -> bool {
+        self.is_canceled.load(Ordering::Relaxed)
+    }
+}
+
+/// Runs `work` followed by `cleanup` on the caller's thread, like a task that can't be canceled
+/// and has no progress callback.
+///
+/// This is what the `_blocking` versions of long-running operations use so they behave the same
+/// as their tasks.
+pub(crate) fn run_blocking<T>(
+    work: impl FnOnce(&TaskContext) -> Result<T>,
+    cleanup: impl FnOnce() -> Result<()>,
+) -> Result<T> {
+    let context = TaskContext {
+        control: Arc::default(),
+    };
+    let result = work(&context);
+    with_cleanup_result(result, cleanup())
+}
+
+/// Returns the task's result, or the cleanup's error if the task succeeded but its cleanup didn't.
+fn with_cleanup_result<T>(result: Result<T>, cleanup_result: Result<()>) -> Result<T> {
+    result.and_then(|value| cleanup_result.map(|()| value))
+}
+
+fn panicked(what: &str) -> Error {
+    Error::InvalidOperation(format!("The {what} panicked"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Spawns a task that runs until it's canceled and reports when its cleanup runs.
+    fn spawn_until_canceled() -> (TaskHandle<()>, mpsc::Receiver<()>) {
+        let (tx, rx) = mpsc::channel();
+        let task = TaskHandle::spawn(
+            |context| loop {
+                context.check_canceled()?;
+                thread::sleep(Duration::from_millis(1));
+            },
+            move || {
+                tx.send(()).unwrap();
+                Ok(())
+            },
+        );
+        (task, rx)
+    }
+
+    #[test]
+    fn return_result_once() {
+        let task = TaskHandle::spawn(|_| Ok(5), || Ok(()));
+        assert_eq!(task.wait(TIMEOUT).unwrap(), 5);
+        assert!(task.is_finished());
+        assert!(matches!(
+            task.wait(TIMEOUT),
+            Err(Error::InvalidOperation(_))
+        ));
+    }
+
+    #[test]
+    fn clean_up_when_canceled() {
+        let (task, cleaned_up) = spawn_until_canceled();
+        assert!(matches!(
+            task.wait(Duration::from_millis(10)),
+            Err(Error::TimedOut(_))
+        ));
+        assert!(!task.is_finished());
+
+        task.cancel();
+        assert!(matches!(
+            task.wait(TIMEOUT),
+            Err(Error::InvalidOperation(_))
+        ));
+        assert_eq!(cleaned_up.try_recv(), Ok(()));
+    }
+
+    #[test]
+    fn cancel_when_dropped() {
+        let (task, cleaned_up) = spawn_until_canceled();
+        drop(task);
+        assert_eq!(cleaned_up.recv_timeout(TIMEOUT), Ok(()));
+    }
+
+    #[test]
+    fn clean_up_when_panicked() {
+        let (tx, cleaned_up) = mpsc::channel();
+        let task = TaskHandle::<()>::spawn(
+            |_| panic!("The task failed"),
+            move || {
+                tx.send(()).unwrap();
+                Ok(())
+            },
+        );
+        assert!(matches!(
+            task.wait(TIMEOUT),
+            Err(Error::InvalidOperation(_))
+        ));
+        assert_eq!(cleaned_up.try_recv(), Ok(()));
+    }
+
+    #[test]
+    fn fail_when_cleanup_fails() {
+        let task = TaskHandle::spawn(
+            |_| Ok(5),
+            || Err(Error::InvalidInput("Can't clean up".to_string())),
+        );
+        assert!(matches!(task.wait(TIMEOUT), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn report_progress() {
+        let (start_tx, start_rx) = mpsc::channel();
+        let task = TaskHandle::spawn(
+            move |context| {
+                start_rx.recv().unwrap();
+                for step in 1..=4 {
+                    context.report_progress(step as f32 / 4.);
+                }
+                Ok(())
+            },
+            || Ok(()),
+        );
+        let (progress_tx, progress_rx) = mpsc::channel();
+        task.set_progress_callback(move |progress| progress_tx.send(progress).unwrap());
+        start_tx.send(()).unwrap();
+
+        task.wait(TIMEOUT).unwrap();
+        assert_eq!(
+            progress_rx.try_iter().collect::<Vec<_>>(),
+            [0.25, 0.5, 0.75, 1.]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use std::time::Duration;
+
+use common::TranscriptPort;
+use rfe::{ConnectionError, Error, SignalGenerator, SpectrumAnalyzer};
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+fn assert_send<T: Send>(value: T) -> T {
+    value
+}
+
+#[test]
+fn wait_for_next_sweep_without_blocking() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 200\n\
+         < $S\\x02\\x50\\x50\n\
+         ~ 100\n\
+         < $S\\x02\\x64\\x64\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    let (first, second) = block_on(async {
+        let first = assert_send(rfe.wait_for_next_sweep_async(None)).await;
+        let second = rfe
+            .wait_for_next_sweep_async(Some(Duration::from_secs(2)))
+            .await;
+        (first, second)
+    });
+    assert_eq!(first.unwrap(), [-40., -40.]);
+    assert_eq!(second.unwrap(), [-50., -50.]);
+
+    let timeout = Duration::from_millis(50);
+    assert!(matches!(
+        block_on(rfe.wait_for_next_sweep_async(Some(timeout))),
+        Err(Error::TimedOut(t)) if t == timeout
+    ));
+}
+
+#[test]
+fn return_disconnected_error_when_unplugged_while_waiting_async() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    let result = block_on(async {
+        let wait = rfe.wait_for_next_sweep_async(Some(Duration::from_secs(5)));
+        port.unplug();
+        wait.await
+    });
+    assert!(matches!(result, Err(Error::Disconnected(_))));
+}
+
+#[test]
+fn wait_for_next_screen_data_without_blocking() {
+    let frame = |first_byte: &str| format!("< $D\\x{first_byte}{}\n", "\\x00".repeat(1023));
+    let port = TranscriptPort::from_transcript(&format!(
+        "> #\\x04C0\n\
+         < #C3-M:060,255,01.15\n\
+         < #C3-*:0510000,0186525,0005,0001000,0,3,0000,0,0,1,3,0,00100\n\
+         > #\\x04D1\n\
+         ~ 50\n\
+         {}",
+        frame("01"),
+    ));
+    let rfe = SignalGenerator::connect_with_transport(port.transport()).unwrap();
+
+    let screen_data = block_on(async {
+        let wait = assert_send(rfe.wait_for_next_screen_data_async(None));
+        rfe.enable_dump_screen().unwrap();
+        wait.await
+    })
+    .unwrap();
+    assert_eq!(screen_data.sequence(), 1);
+    assert!(screen_data.get_pixel(0, 0));
+    assert!(port.is_finished());
+}
+
+#[test]
+fn fail_to_connect_async_to_missing_device() {
+    let result = block_on(SpectrumAnalyzer::connect_with_name_and_baud_rate_async(
+        "No Such RF Explorer",
+        500_000,
+    ));
+    assert!(matches!(
+        result,
+        Err(ConnectionError::UsbSerialDeviceNotFound(_))
+    ));
+}
@@ -0,0 +1,6 @@
+//! The mock serial port shared with the unit tests.
+
+#[path = "../../src/common/transcript_port.rs"]
+mod transcript_port;
+
+pub use transcript_port::TranscriptPort;
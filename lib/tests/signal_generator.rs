@@ -0,0 +1,236 @@
+mod common;
+
+use std::{
+    sync::{Arc, mpsc},
+    thread,
+    time::Duration,
+};
+
+use common::TranscriptPort;
+use rfe::{
+    DeviceId, Error, Frequency, SignalGenerator,
+    signal_generator::{Attenuation, GeneratorMode, Model, PowerLevel, RfPower},
+    task::TaskHandle,
+};
+
+#[test]
+fn start_cw_and_turn_off_rf_power() {
+    let port = TranscriptPort::load("signal_generator_cw.txt");
+    let rfe = SignalGenerator::connect_with_transport(port.transport()).unwrap();
+
+    assert_eq!(rfe.main_radio_model(), Some(Model::Rfe6Gen));
+    assert_eq!(rfe.expansion_radio_model(), None);
+    assert_eq!(&*rfe.firmware_version(), "01.15");
+
+    rfe.start_cw(
+        Frequency::from_mhz(2_440),
+        Attenuation::Off,
+        PowerLevel::High,
+    )
+    .unwrap();
+    let config_cw = wait_for_config_cw(&rfe, |config_cw| config_cw.rf_power == RfPower::On);
+    assert_eq!(config_cw.cw, Frequency::from_mhz(2_440));
+    assert_eq!(config_cw.attenuation, Attenuation::Off);
+    assert_eq!(config_cw.power_level, PowerLevel::High);
+    assert_eq!(rfe.operating_mode(), Some(GeneratorMode::Cw));
+
+    rfe.rf_power_off().unwrap();
+    wait_for_config_cw(&rfe, |config_cw| config_cw.rf_power == RfPower::Off);
+    assert_eq!(rfe.operating_mode(), Some(GeneratorMode::Idle));
+
+    assert_eq!(
+        port.writes(),
+        [&b"#\x04C0"[..], b"#\x12C3-F:2440000,1,2", b"#\x05CP0"]
+    );
+    assert!(port.is_finished());
+}
+
+#[test]
+fn report_tracking_mode_once_started() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C3-M:060,255,01.15\n\
+         < #C3-*:0510000,0186525,0005,0001000,0,3,0000,0,0,1,3,0,00100\n\
+         > #\\x1fC3-T:2440000,1,2,0010,0001000\n",
+    );
+    let rfe = SignalGenerator::connect_with_transport(port.transport()).unwrap();
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_operating_mode_callback(move |mode| sender.send(mode).unwrap());
+
+    // The general config doesn't show which mode turned RF power on, so tracking is only known
+    // once it's started
+    rfe.start_tracking(
+        Frequency::from_mhz(2_440),
+        Attenuation::Off,
+        PowerLevel::High,
+        10,
+        Frequency::from_mhz(1),
+    )
+    .unwrap();
+    assert_eq!(rfe.operating_mode(), Some(GeneratorMode::Tracking));
+    assert_eq!(
+        receiver.recv_timeout(Duration::from_secs(2)),
+        Ok(GeneratorMode::Tracking)
+    );
+    assert!(port.is_finished());
+}
+
+#[test]
+fn retune_cw_by_ppm() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C3-M:060,255,01.15\n\
+         < #C3-*:0510000,0186525,0005,0001000,0,3,0000,0,0,1,3,0,00100\n\
+         > #\\x12C3-F:2440000,1,2\n\
+         ~ 40\n\
+         < #C3-G:2440000,2440000,0005,0001000,1,2,0\n\
+         > #\\x12C3-F:2440002,1,2\n\
+         ~ 40\n\
+         < #C3-G:2440002,2440002,0005,0001000,1,2,0\n",
+    );
+    let rfe = SignalGenerator::connect_with_transport(port.transport()).unwrap();
+    assert!(matches!(
+        rfe.retune_cw_ppm(1.),
+        Err(Error::InvalidOperation(_))
+    ));
+
+    rfe.start_cw(
+        Frequency::from_mhz(2_440),
+        Attenuation::Off,
+        PowerLevel::High,
+    )
+    .unwrap();
+    wait_for_config_cw(&rfe, |config_cw| config_cw.rf_power == RfPower::On);
+    assert_eq!(
+        rfe.retune_cw_ppm(1.).unwrap(),
+        Frequency::from_khz(2_440_002)
+    );
+    assert!(port.is_finished());
+}
+
+#[test]
+fn identify_signal_generator_by_serial_number() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C3-M:060,255,01.15\n\
+         < #C3-*:0510000,0186525,0005,0001000,0,3,0000,0,0,1,3,0,00100\n\
+         > #\\x04Cn\n\
+         < #SnC7AK7AL7CACAA21X\n",
+    );
+    let rfe = SignalGenerator::connect_with_transport(port.transport()).unwrap();
+
+    let device_id = rfe.device_id().unwrap();
+    assert_eq!(
+        device_id,
+        DeviceId::new("C7AK7AL7CACAA21X", "6Gen", "01.15")
+    );
+    assert_eq!(device_id.to_string(), "C7AK7AL7CACAA21X (6Gen v01.15)");
+    assert!(port.is_finished());
+}
+
+#[test]
+fn tag_and_deliver_screen_data() {
+    // Two identical frames followed by one with the top-left pixel on
+    let frame = |first_byte: &str| format!("< $D\\x{first_byte}{}\n", "\\x00".repeat(1023));
+    let port = TranscriptPort::from_transcript(&format!(
+        "> #\\x04C0\n\
+         < #C3-M:060,255,01.15\n\
+         < #C3-*:0510000,0186525,0005,0001000,0,3,0000,0,0,1,3,0,00100\n\
+         > #\\x04D1\n\
+         ~ 50\n\
+         {}~ 30\n\
+         {}~ 30\n\
+         {}",
+        frame("00"),
+        frame("00"),
+        frame("01"),
+    ));
+    let rfe = SignalGenerator::connect_with_transport(port.transport()).unwrap();
+    let (tx, rx) = mpsc::channel();
+    rfe.set_screen_data_callback(move |screen_data| tx.send(screen_data.sequence()).unwrap());
+    rfe.enable_dump_screen().unwrap();
+
+    let next = rfe.wait_for_next_screen_data().unwrap();
+    assert!((1..=2).contains(&next.sequence()));
+    let changed = rfe
+        .wait_for_changed_screen_data(Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(changed.sequence(), 3);
+    assert!(changed.get_pixel(0, 0));
+    assert!(!next.has_same_pixels(&changed));
+    assert_eq!(rfe.screen_data(), Some(changed));
+
+    let mut sequences: Vec<_> = rx.iter().take(3).collect();
+    sequences.sort();
+    assert_eq!(sequences, [1, 2, 3]);
+    assert!(port.is_finished());
+}
+
+/// Starts a CW task that turns off the RF power when it's done and panics if `panics` is set
+/// instead of running until it's canceled.
+fn spawn_cw_task(rfe: &Arc<SignalGenerator>, panics: bool) -> TaskHandle<()> {
+    let (task_rfe, cleanup_rfe) = (rfe.clone(), rfe.clone());
+    TaskHandle::spawn(
+        move |context| {
+            task_rfe.start_cw(
+                Frequency::from_mhz(2_440),
+                Attenuation::Off,
+                PowerLevel::High,
+            )?;
+            wait_for_config_cw(&task_rfe, |config_cw| config_cw.rf_power == RfPower::On);
+            assert!(!panics, "The task failed while the RF power was on");
+            loop {
+                context.check_canceled()?;
+                thread::sleep(Duration::from_millis(5));
+            }
+        },
+        move || cleanup_rfe.rf_power_off(),
+    )
+}
+
+#[test]
+fn turn_off_rf_power_when_task_is_canceled() {
+    let port = TranscriptPort::load("signal_generator_cw.txt");
+    let rfe = Arc::new(SignalGenerator::connect_with_transport(port.transport()).unwrap());
+
+    let task = spawn_cw_task(&rfe, false);
+    assert!(matches!(
+        task.wait(Duration::from_millis(200)),
+        Err(Error::TimedOut(_))
+    ));
+    task.cancel();
+    assert!(matches!(
+        task.wait(Duration::from_secs(1)),
+        Err(Error::InvalidOperation(_))
+    ));
+    assert!(task.is_finished());
+    wait_for_config_cw(&rfe, |config_cw| config_cw.rf_power == RfPower::Off);
+    assert!(port.is_finished());
+}
+
+#[test]
+fn turn_off_rf_power_when_task_panics() {
+    let port = TranscriptPort::load("signal_generator_cw.txt");
+    let rfe = Arc::new(SignalGenerator::connect_with_transport(port.transport()).unwrap());
+
+    let task = spawn_cw_task(&rfe, true);
+    assert!(matches!(
+        task.wait(Duration::from_secs(2)),
+        Err(Error::InvalidOperation(_))
+    ));
+    wait_for_config_cw(&rfe, |config_cw| config_cw.rf_power == RfPower::Off);
+    assert!(port.is_finished());
+}
+
+fn wait_for_config_cw(
+    rfe: &SignalGenerator,
+    condition: impl Fn(&rfe::signal_generator::ConfigCw) -> bool,
+) -> rfe::signal_generator::ConfigCw {
+    for _ in 0..100 {
+        if let Some(config_cw) = rfe.config_cw().filter(&condition) {
+            return config_cw;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    panic!("The expected CW config was not received");
+}
@@ -0,0 +1,169 @@
+use std::{net::TcpListener, sync::Arc, thread, time::Duration};
+
+use rfe::{
+    Frequency, SpectrumAnalyzer,
+    measurements::{self, TwoToneSetup},
+    simulator::{self, Scenario, Simulator, TcpPort, Tone},
+    spectrum_analyzer::{DspMode, Model},
+};
+
+const SCENARIO: &str = r#"
+[device]
+main_radio_model = "6G+"
+firmware_version = "03.41"
+serial_number = "SIMTEST000000001"
+
+[config]
+start = "2.4 GHz"
+stop = "2.5 GHz"
+
+[sweeps]
+interval_ms = 20
+
+[[sweeps.tones]]
+freq = "2.44 GHz"
+amp_dbm = -40.0
+width = "2 MHz"
+"#;
+
+fn connect_to_simulator(scenario: Scenario) -> SpectrumAnalyzer {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        simulator::serve(
+            Simulator::new(scenario),
+            stream.try_clone().unwrap(),
+            stream,
+        )
+    });
+
+    SpectrumAnalyzer::connect_with_transport(Box::new(TcpPort::connect(addr).unwrap())).unwrap()
+}
+
+fn peak_freq(rfe: &SpectrumAnalyzer, sweep: &[f32]) -> Frequency {
+    let peak = sweep
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(i, _)| i)
+        .unwrap();
+    rfe.start_freq() + rfe.step_size() * peak as u64
+}
+
+#[test]
+fn connect_to_simulator_and_receive_sweeps() {
+    let rfe = connect_to_simulator(Scenario::from_toml(SCENARIO).unwrap());
+
+    assert_eq!(rfe.main_radio_model(), Some(Model::Rfe6GPlus));
+    assert_eq!(rfe.expansion_radio_model(), None);
+    assert_eq!(&*rfe.firmware_version(), "03.41");
+    assert_eq!(rfe.serial_number().as_deref(), Some("SIMTEST000000001"));
+    assert_eq!(rfe.start_freq(), Frequency::from_mhz(2_400));
+    assert_eq!(rfe.sweep_len(), 112);
+
+    let sweep = rfe.wait_for_next_sweep().unwrap();
+    assert_eq!(sweep.len(), 112);
+    assert!(peak_freq(&rfe, &sweep).abs_diff(Frequency::from_mhz(2_440)) <= Frequency::from_mhz(1));
+}
+
+#[test]
+fn change_simulator_config() {
+    let rfe = connect_to_simulator(Scenario::from_toml(SCENARIO).unwrap());
+
+    rfe.set_start_stop(Frequency::from_mhz(2_430), Frequency::from_mhz(2_450))
+        .unwrap();
+    rfe.set_sweep_len(224).unwrap();
+    rfe.set_dsp_mode(DspMode::Fast).unwrap();
+    assert!(rfe.is_synchronized());
+    assert_eq!(rfe.start_freq(), Frequency::from_mhz(2_430));
+    assert!(rfe.stop_freq().abs_diff(Frequency::from_mhz(2_450)) <= rfe.step_size());
+    assert_eq!(rfe.sweep_len(), 224);
+    assert_eq!(rfe.dsp_mode(), Some(DspMode::Fast));
+
+    // Sweeps measured before the sweep length changed may still be on their way
+    let sweep = (0..5)
+        .map(|_| rfe.wait_for_next_sweep().unwrap())
+        .find(|sweep| sweep.len() == 224)
+        .unwrap();
+    assert!(peak_freq(&rfe, &sweep).abs_diff(Frequency::from_mhz(2_440)) <= Frequency::from_mhz(1));
+}
+
+#[test]
+fn auto_range_amplitudes_to_recent_sweeps() {
+    let rfe = connect_to_simulator(Scenario::from_toml(SCENARIO).unwrap());
+    for _ in 0..SpectrumAnalyzer::AUTO_RANGE_MIN_SWEEPS {
+        rfe.wait_for_next_sweep().unwrap();
+    }
+
+    // The -105 dBm noise floor and the -40 dBm tone with ±2 dB of noise
+    let (min_amp_dbm, max_amp_dbm) = rfe.auto_range().unwrap();
+    assert_eq!(min_amp_dbm, -120);
+    assert!((-30..=-25).contains(&max_amp_dbm));
+    assert_eq!(rfe.min_amp_dbm(), min_amp_dbm);
+    assert_eq!(rfe.max_amp_dbm(), max_amp_dbm);
+
+    // Changing the amplitudes changed the config, so new sweeps are needed
+    assert!(rfe.auto_range().is_err());
+}
+
+#[test]
+fn measure_two_tone_intermod_products() {
+    let mut scenario = Scenario::from_toml(SCENARIO).unwrap();
+    scenario.sweeps.noise_floor_dbm = -120.;
+    scenario.sweeps.noise_dbm = 0.;
+    scenario.sweeps.tones.clear();
+    for (freq_mhz, amp_dbm) in [(2_440, -30.), (2_450, -30.), (2_430, -90.), (2_460, -90.)] {
+        scenario.sweeps.tones.push(Tone {
+            freq: Frequency::from_mhz(freq_mhz),
+            amp_dbm,
+            width: Frequency::default(),
+        });
+    }
+    let rfe = connect_to_simulator(scenario);
+
+    let setup = TwoToneSetup {
+        input_levels_dbm: Some((-40., -40.)),
+        ..TwoToneSetup::new(Frequency::from_mhz(2_440), Frequency::from_mhz(2_450))
+    };
+    let report = measurements::two_tone_blocking(&rfe, &setup).unwrap();
+
+    assert!((report.tones.0.peak_dbm + 30.).abs() < 1.);
+    assert!((report.tones.1.peak_dbm + 30.).abs() < 1.);
+    // IIP3 = -40 dBm input + 60 dB of suppression / 2
+    let ip3 = report.intercept_point(3).unwrap();
+    assert!((ip3.input_dbm + 10.).abs() < 1., "{ip3:?}");
+    assert!((ip3.output_dbm - 0.).abs() < 1., "{ip3:?}");
+    // The difference product at 1 MHz is below the spectrum analyzer's range
+    assert!(
+        report
+            .products
+            .iter()
+            .all(|product| product.product.freq > Frequency::from_mhz(1))
+    );
+
+    // The original frequency range is restored afterwards
+    assert_eq!(rfe.start_freq(), Frequency::from_mhz(2_400));
+}
+
+#[test]
+fn restore_frequency_range_when_hop_monitor_is_canceled() {
+    let rfe = Arc::new(connect_to_simulator(Scenario::from_toml(SCENARIO).unwrap()));
+    let channels = [Frequency::from_mhz(2_440), Frequency::from_mhz(2_442)];
+    let task = measurements::hop_monitor(rfe.clone(), &channels, Duration::from_secs(60), -70.);
+    let (tx, rx) = std::sync::mpsc::channel();
+    task.set_progress_callback(move |progress| {
+        let _ = tx.send(progress);
+    });
+
+    // Cancel once the task has started watching the channels
+    let progress = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!((0. ..1.).contains(&progress));
+    assert_ne!(rfe.start_freq(), Frequency::from_mhz(2_400));
+    task.cancel();
+    assert!(matches!(
+        task.wait(Duration::from_secs(5)),
+        Err(rfe::Error::InvalidOperation(_))
+    ));
+    assert_eq!(rfe.start_freq(), Frequency::from_mhz(2_400));
+}
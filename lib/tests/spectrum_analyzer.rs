@@ -0,0 +1,1546 @@
+mod common;
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Condvar, Mutex, mpsc},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{TimeDelta, Utc};
+use common::TranscriptPort;
+use rfe::{
+    ConnectionError, ConnectionState, DeviceId, Error, Frequency, LinkDiagnosis, LinkEventKind,
+    MemoryBudget, RadioModule, SpectrumAnalyzer,
+    measurements::TrackingStepPlan,
+    spectrum_analyzer::{
+        AutoInputStage, CachedIdentity, ClampedSetting, CorruptionStats, DspMode,
+        FrozenSweepWatchdog, HeuristicConfig, InputStage, MemorySink, MismatchedSweepPolicy,
+        MismatchedSweepStats, Model, Record, RecordingSession, Sweep,
+    },
+};
+
+const REQUEST_CONFIG: &[u8] = b"#\x04C0";
+
+#[test]
+fn connect_to_6g_combo_and_receive_sweeps() {
+    let port = TranscriptPort::load("6g_combo_sweeps.txt");
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    assert_eq!(rfe.port_name(), "transcript");
+    assert_eq!(rfe.main_radio_model(), Some(Model::RfeWSub3G));
+    assert_eq!(rfe.expansion_radio_model(), Some(Model::Rfe6G));
+    assert_eq!(rfe.active_radio_model(), Model::RfeWSub3G);
+    assert_eq!(&*rfe.firmware_version(), "01.12B26");
+    assert_eq!(rfe.start_freq(), Frequency::from_khz(430_000));
+    assert_eq!(rfe.step_size(), Frequency::from_hz(178_571));
+    assert_eq!(rfe.min_amp_dbm(), -118);
+    assert_eq!(rfe.max_amp_dbm(), -30);
+    assert_eq!(rfe.sweep_len(), 112);
+    assert_eq!(rfe.rbw(), Some(Frequency::from_khz(200)));
+
+    // Collect sweeps until the recording runs out
+    let mut sweeps = vec![rfe.wait_for_next_sweep().unwrap()];
+    while let Ok(sweep) = rfe.wait_for_next_sweep_with_timeout(Duration::from_millis(500)) {
+        sweeps.push(sweep);
+    }
+    assert!(sweeps.len() <= 12);
+    for sweep in &sweeps {
+        assert_eq!(sweep.len(), 112);
+        let peak = sweep
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i);
+        assert_eq!(peak, Some(56));
+    }
+
+    // The last sweep in the transcript peaks at -46 dBm
+    assert_eq!(rfe.sweep().unwrap()[56], -46.);
+
+    let link_health = rfe.link_health();
+    assert!(link_health.bytes_per_sec > 0.);
+    assert!(link_health.since_last_message.is_some());
+    assert_eq!(link_health.read_errors.parse_errors, 0);
+    assert_eq!(link_health.read_errors.io_errors, 0);
+
+    assert_eq!(port.writes(), [REQUEST_CONFIG]);
+    assert!(port.is_finished());
+}
+
+#[test]
+fn change_6g_plus_config() {
+    let port = TranscriptPort::load("6g_plus_config_change.txt");
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    assert_eq!(rfe.main_radio_model(), Some(Model::Rfe6GPlus));
+    assert_eq!(rfe.expansion_radio_model(), None);
+    assert!(rfe.capabilities().can_set_sweep_len);
+    assert_eq!(rfe.start_freq(), Frequency::from_mhz(2_400));
+    assert_eq!(rfe.alignment_report(), None);
+
+    rfe.set_start_stop(Frequency::from_mhz(2_410), Frequency::from_mhz(2_480))
+        .unwrap();
+    assert!(rfe.is_synchronized());
+    // The firmware rounds the step size down to a whole number of hertz
+    let alignment = rfe.alignment_report().unwrap();
+    assert_eq!(alignment.start_offset_hz, 0);
+    assert!((-1.0..0.).contains(&alignment.step_error_hz));
+    assert!(!alignment.exceeds_half_step());
+    rfe.wait_until_synchronized(Duration::ZERO).unwrap();
+    assert_eq!(rfe.start_freq(), Frequency::from_mhz(2_410));
+    assert_eq!(rfe.step_size(), Frequency::from_hz(630_630));
+    assert_eq!(rfe.rbw(), Some(Frequency::from_khz(300)));
+
+    let sweep = rfe.wait_for_next_sweep().unwrap();
+    assert_eq!(sweep.len(), 112);
+
+    assert_eq!(
+        port.writes(),
+        [REQUEST_CONFIG, b"#\x20C2-F:2410000,2480000,-010,-120"]
+    );
+    assert!(port.is_finished());
+}
+
+#[test]
+fn confirm_dsp_mode_from_reply() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:014,255,03.41\n\
+         < #C2-F:2400000,0892857,-010,-120,0112,0,000,0240000,6100000,5860000,00600,0000,000\n\
+         > #\\x05Cp\\x02\n\
+         ~ 20\n\
+         < DSP:2\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    let start = Instant::now();
+    rfe.set_dsp_mode(DspMode::Fast).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(rfe.is_synchronized());
+    assert_eq!(rfe.dsp_mode(), Some(DspMode::Fast));
+    assert_eq!(port.writes(), [REQUEST_CONFIG, b"#\x05Cp\x02"]);
+    assert!(port.is_finished());
+}
+
+#[test]
+fn confirm_start_stop_with_rounded_step_size() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:014,255,03.41\n\
+         < #C2-F:2400000,0000488,-010,-120,4096,0,000,0240000,6100000,5860000,00003,0000,000\n\
+         > #\\x20C2-F:2410000,2412000,-010,-120\n\
+         ~ 20\n\
+         < #C2-F:2410000,0000488,-010,-120,4096,0,000,0240000,6100000,5860000,00003,0000,000\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    // The 488.4 Hz steps are reported as 488 Hz, so the stop frequency calculated from them is
+    // more than half a step below the requested one
+    rfe.set_start_stop(Frequency::from_mhz(2_410), Frequency::from_mhz(2_412))
+        .unwrap();
+    assert!(rfe.is_synchronized());
+    assert_eq!(rfe.stop_freq(), Frequency::from_hz(2_411_998_360));
+    assert!(port.is_finished());
+}
+
+#[test]
+fn only_report_alignment_for_requested_frequencies() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:014,255,03.41\n\
+         < #C2-F:2400000,0892857,-010,-120,0112,0,000,0240000,6100000,5860000,00600,0000,000\n\
+         > #\\x20C2-F:2400000,2499107,-020,-100\n\
+         ~ 20\n\
+         < #C2-F:2400000,0892857,-020,-100,0112,0,000,0240000,6100000,5860000,00600,0000,000\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    // Changing only the amplitudes keeps the current frequencies without requesting them
+    rfe.set_min_max_amps(-100, -20).unwrap();
+    assert_eq!(rfe.alignment_report(), None);
+    assert!(port.is_finished());
+}
+
+#[test]
+fn coalesce_debounced_config_changes() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:014,255,03.41\n\
+         < #C2-F:2400000,0892857,-010,-120,0112,0,000,0240000,6100000,5860000,00600,0000,000\n\
+         > #\\x20C2-F:2410000,2480000,-020,-100\n\
+         ~ 20\n\
+         < #C2-F:2410000,0630630,-020,-100,0112,0,000,0240000,6100000,5860000,00300,0000,000\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let (config_tx, config_rx) = mpsc::channel();
+    rfe.set_config_callback(move |config| config_tx.send(config).unwrap());
+
+    // A slider dragged across several center frequencies, then the amplitude range changed
+    let debounce = Duration::from_millis(100);
+    let mut changes = Vec::new();
+    for center_mhz in [2_425, 2_435, 2_445] {
+        changes.push(
+            rfe.set_center_span_debounced(
+                Frequency::from_mhz(center_mhz),
+                Frequency::from_mhz(70),
+                debounce,
+            )
+            .unwrap(),
+        );
+    }
+    changes.push(rfe.set_min_max_amps_debounced(-100, -20, debounce).unwrap());
+    assert!(matches!(
+        rfe.set_center_span_debounced(Frequency::from_mhz(2_445), Frequency::from_hz(0), debounce),
+        Err(Error::InvalidInput(_))
+    ));
+    assert_eq!(port.writes(), [REQUEST_CONFIG]);
+
+    for change in &changes {
+        change.wait().unwrap();
+    }
+    let config = config_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(config.start_freq, Frequency::from_mhz(2_410));
+    assert_eq!(rfe.min_amp_dbm(), -100);
+    assert_eq!(rfe.max_amp_dbm(), -20);
+    assert_eq!(
+        port.writes(),
+        [REQUEST_CONFIG, b"#\x20C2-F:2410000,2480000,-020,-100"]
+    );
+    assert!(port.is_finished());
+}
+
+#[test]
+fn cancel_debounced_config_changes_on_drop() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:014,255,03.41\n\
+         < #C2-F:2400000,0892857,-010,-120,0112,0,000,0240000,6100000,5860000,00600,0000,000\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let change = rfe
+        .set_start_stop_debounced(
+            Frequency::from_mhz(2_410),
+            Frequency::from_mhz(2_480),
+            Duration::from_secs(60),
+        )
+        .unwrap();
+
+    let start = Instant::now();
+    drop(rfe);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(change.is_done());
+    assert!(matches!(change.wait(), Err(Error::InvalidOperation(_))));
+    assert_eq!(port.writes(), [REQUEST_CONFIG]);
+}
+
+#[test]
+fn identify_analyzers_by_serial_number() {
+    let connect = |serial_number: &str| {
+        let port = TranscriptPort::from_transcript(&format!(
+            "> #\\x04C0\n\
+             < #C2-M:005,006,01.12B26\n\
+             < #C2-F:0430000,0178571,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\n\
+             > #\\x04Cn\n\
+             < #Sn{serial_number}\n"
+        ));
+        SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap()
+    };
+    let first = connect("B3AK7AL7CACAA74M");
+    let second = connect("A0BK7AL7CACAA12Q");
+
+    let first_id = first.device_id().unwrap();
+    assert_eq!(
+        first_id,
+        DeviceId::new(
+            "B3AK7AL7CACAA74M",
+            first.main_radio_model().unwrap().to_string(),
+            "01.12B26"
+        )
+    );
+    // The serial number is only requested once
+    assert_eq!(first.device_id(), Some(first_id.clone()));
+
+    let second_id = second.device_id().unwrap();
+    assert!(second_id < first_id);
+    assert_eq!(HashSet::from([first_id, second_id]).len(), 2);
+}
+
+#[test]
+fn switch_modules_and_restore_config() {
+    let port = TranscriptPort::load("6g_combo_module_switch.txt");
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    assert_eq!(rfe.active_radio_model(), Model::RfeWSub3G);
+
+    let restored = rfe
+        .activate_radio_module_preserving_config(RadioModule::Expansion)
+        .unwrap();
+    assert_eq!(rfe.active_radio_model(), Model::Rfe6G);
+    assert_eq!(rfe.start_freq(), Frequency::from_mhz(4_850));
+    assert_eq!(rfe.stop_freq(), restored.stop_freq);
+    assert_eq!(rfe.min_amp_dbm(), -118);
+    assert_eq!(rfe.max_amp_dbm(), -30);
+    assert_eq!(
+        restored.clamped,
+        [
+            ClampedSetting::StartFreq {
+                requested: Frequency::from_mhz(430),
+                applied: Frequency::from_mhz(4_850),
+            },
+            ClampedSetting::StopFreq {
+                requested: Frequency::from_hz(449_999_980),
+                applied: Frequency::from_hz(4_869_999_980),
+            },
+        ]
+    );
+
+    assert_eq!(
+        port.writes(),
+        [
+            REQUEST_CONFIG,
+            b"#\x05CM\x01",
+            b"#\x20C2-F:4850000,4869999,-030,-118"
+        ]
+    );
+    assert!(port.is_finished());
+}
+
+#[test]
+fn fail_to_connect_without_response() {
+    let port = TranscriptPort::from_transcript("> #\\x04C0\n");
+    assert!(SpectrumAnalyzer::connect_with_transport(port.transport()).is_err());
+    assert_eq!(port.writes(), [REQUEST_CONFIG]);
+}
+
+#[test]
+fn estimate_latency_and_adjust_sweep_timestamps() {
+    let mut transcript = String::from(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n",
+    );
+    // The RF Explorer takes 100 ms to respond to each serial number request
+    for _ in 0..5 {
+        transcript.push_str("> #\\x04Cn\n~ 100\n< #SnB3AK7AL7CACAA74M\n");
+    }
+    transcript.push_str("~ 50\n< $S\\x02\\x50\\x50\n~ 300\n< $S\\x02\\x50\\x50\n");
+    let port = TranscriptPort::from_transcript(&transcript);
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    assert_eq!(rfe.latency_estimate(), None);
+
+    let estimate = rfe.estimate_latency().unwrap();
+    assert!(
+        (Duration::from_millis(50)..Duration::from_millis(100)).contains(&estimate),
+        "{estimate:?}"
+    );
+    assert_eq!(rfe.latency_estimate(), Some(estimate));
+    assert_eq!(rfe.serial_number().as_deref(), Some("B3AK7AL7CACAA74M"));
+
+    // Sweeps are adjusted by the estimate
+    let estimate = TimeDelta::from_std(estimate).unwrap();
+    rfe.wait_for_next_sweep().unwrap();
+    let adjustment = Utc::now() - rfe.sweep_meta().unwrap().captured_at_adjusted;
+    assert!(
+        adjustment >= estimate && adjustment < estimate + TimeDelta::milliseconds(100),
+        "{adjustment:?}"
+    );
+
+    // Sweeps aren't adjusted once adjustment is disabled
+    rfe.set_latency_adjustment(false);
+    rfe.wait_for_next_sweep().unwrap();
+    let adjustment = Utc::now() - rfe.sweep_meta().unwrap().captured_at_adjusted;
+    assert!(adjustment < estimate, "{adjustment:?}");
+}
+
+#[test]
+fn wait_for_identical_sweeps_and_screen_data() {
+    let mut transcript = String::from(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n",
+    );
+    // A quiet band measures the same sweep and shows the same screen over and over
+    for _ in 0..2 {
+        transcript.push_str("~ 200\n< $S\\x02\\x50\\x50\n");
+    }
+    for _ in 0..2 {
+        transcript.push_str("~ 200\n< $D");
+        transcript.push_str(&"\\x00".repeat(1024));
+        transcript.push('\n');
+    }
+    let port = TranscriptPort::from_transcript(&transcript);
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    let first_sweep = rfe
+        .wait_for_next_sweep_with_timeout(Duration::from_secs(2))
+        .unwrap();
+    let start = Instant::now();
+    let second_sweep = rfe
+        .wait_for_next_sweep_with_timeout(Duration::from_secs(2))
+        .unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(first_sweep, second_sweep);
+
+    let first_screen_data = rfe
+        .wait_for_next_screen_data_with_timeout(Duration::from_secs(2))
+        .unwrap();
+    let start = Instant::now();
+    let second_screen_data = rfe
+        .wait_for_next_screen_data_with_timeout(Duration::from_secs(2))
+        .unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(second_screen_data.timestamp() > first_screen_data.timestamp());
+    assert!(port.is_finished());
+}
+
+#[test]
+fn skip_stale_sweeps_after_config_change() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 200\n\
+         < $S\\x02\\x50\\x50\n\
+         ~ 200\n\
+         < #C2-F:0500000,0178571,-030,-118,0004,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 50\n\
+         < $S\\x02\\x60\\x60\n\
+         ~ 50\n\
+         < #C2-F:0500000,0178571,-030,-118,0004,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 50\n\
+         < $S\\x04\\x70\\x70\\x70\\x70\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    assert_eq!(rfe.config_generation(), 1);
+
+    rfe.wait_for_next_sweep().unwrap();
+    assert_eq!(rfe.sweep_meta().unwrap().config_generation, 1);
+
+    // The sweep that arrives right after the retune still has the old sweep length, and the
+    // repeated config doesn't start a new generation
+    let sweep = rfe
+        .wait_for_sweep_matching_current_config(Duration::from_secs(2))
+        .unwrap();
+    assert_eq!(sweep, [-56.; 4]);
+    assert_eq!(rfe.start_freq(), Frequency::from_mhz(500));
+    assert_eq!(rfe.config_generation(), 2);
+    assert_eq!(rfe.sweep_meta().unwrap().config_generation, 2);
+    assert!(port.is_finished());
+}
+
+#[test]
+fn drop_corrupt_configs() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 200\n\
+         < #C2-F:0430000,0000100,-030,-118,50000,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         < #C2-F:0430000,0178571,-030,-118,0000,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         < #C2-F:0430000,0178571,-030,-118,0112,0,000,0015000,2700000,0010000,00200,0000,000\n\
+         < #C2-F:0500000,0178571,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_config_callback(move |config| sender.send(config).unwrap());
+
+    // Only the last good config reaches the callback and replaces the cached config
+    let config = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(config.start_freq, Frequency::from_mhz(500));
+    assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+    assert_eq!(rfe.start_freq(), Frequency::from_mhz(500));
+    assert_eq!(rfe.sweep_len(), 112);
+    assert_eq!(rfe.config_generation(), 2);
+    assert_eq!(rfe.invalid_config_count(), 3);
+    assert!(port.is_finished());
+}
+
+#[test]
+fn fill_buf_with_range_the_sweep_was_measured_over() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 200\n\
+         < $S\\x02\\x50\\x50\n\
+         ~ 200\n\
+         < #C2-F:0500000,0178571,-030,-118,0004,0,000,0015000,2700000,2685000,00200,0000,000\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    rfe.wait_for_next_sweep().unwrap();
+    let (start, stop) = (rfe.start_freq(), rfe.stop_freq());
+
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while rfe.start_freq() != Frequency::from_mhz(500) && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(20));
+    }
+    assert!(port.is_finished());
+
+    // The cached sweep is still from the old config, so its range doesn't match the new config's
+    let mut buf = [0.; 4];
+    assert_eq!(
+        rfe.fill_buf_with_sweep_and_range(&mut buf).unwrap(),
+        (2, start, stop)
+    );
+    assert_eq!(start, Frequency::from_mhz(430));
+    assert_ne!(stop, rfe.stop_freq());
+    assert_eq!(buf[..2], [-40.; 2]);
+}
+
+#[test]
+fn fill_buf_with_the_sweep_the_wait_ended_on() {
+    // The config change and the longer sweep arrive right after the first sweep
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 200\n\
+         < $S\\x02\\x50\\x50\n\
+         < #C2-F:0500000,0178571,-030,-118,0004,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         < $S\\x04\\x70\\x70\\x70\\x70\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    let mut sweep_lens = Vec::new();
+    loop {
+        let mut buf = [f32::NAN; 6];
+        let Ok(sweep_len) =
+            rfe.wait_for_next_sweep_with_timeout_and_fill_buf(Duration::from_millis(500), &mut buf)
+        else {
+            break;
+        };
+        // The returned length and the amplitudes are always from the same sweep
+        let expected_dbm = match sweep_len {
+            2 => -40.,
+            4 => -56.,
+            _ => panic!("Unexpected sweep length {sweep_len}"),
+        };
+        assert!(
+            buf[..sweep_len].iter().all(|amp| *amp == expected_dbm),
+            "{buf:?}"
+        );
+        assert!(buf[sweep_len..].iter().all(|amp| amp.is_nan()), "{buf:?}");
+        sweep_lens.push(sweep_len);
+    }
+    // The first sweep can be replaced before the first wait returns, but never seen twice
+    assert!(sweep_lens == [2, 4] || sweep_lens == [4], "{sweep_lens:?}");
+    assert!(port.is_finished());
+}
+
+#[test]
+fn skip_sweep_in_flight_when_triggering_single_sweep() {
+    // The sweep that was being measured when the hold command arrived is sent after the resume
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         > #\\x04CH\n\
+         > #\\x04C0\n\
+         ~ 50\n\
+         < $S\\x02\\x50\\x50\n\
+         < #C2-F:0430000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 50\n\
+         < $S\\x02\\x60\\x60\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    let sweep = rfe.single_sweep(Duration::from_secs(2)).unwrap();
+    assert_eq!(sweep.amplitudes_dbm(), [-48.; 2]);
+    assert!(rfe.is_holding());
+    assert_eq!(
+        port.writes(),
+        [REQUEST_CONFIG, b"#\x04CH", REQUEST_CONFIG, b"#\x04CH"]
+    );
+    assert!(port.is_finished());
+}
+
+/// Receives sweeps of every length around a change from a 4-point to a 6-point config and
+/// returns the amplitudes of the sweeps that were kept, in the order they were received.
+fn receive_mismatched_sweeps(policy: MismatchedSweepPolicy) -> (SpectrumAnalyzer, Vec<Vec<f32>>) {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0004,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 200\n\
+         < $S\\x04\\x50\\x50\\x50\\x50\n\
+         ~ 50\n\
+         < #C2-F:0500000,0178571,-030,-118,0006,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 50\n\
+         < $S\\x04\\x60\\x60\\x62\\x60\n\
+         ~ 50\n\
+         < $S\\x06\\x70\\x70\\x70\\x70\\x70\\x70\n\
+         ~ 50\n\
+         < $S\\x08\\x80\\x80\\x80\\x80\\x80\\x80\\x82\\x82\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    rfe.set_mismatched_sweep_policy(policy);
+    let sweeps = Arc::new(Mutex::new(Vec::new()));
+    let cb_sweeps = sweeps.clone();
+    rfe.set_sweep_callback_borrowed(move |sweep| cb_sweeps.lock().unwrap().push(sweep.to_vec()));
+
+    // Every sweep is either kept or dropped, and the last kept sweep is cached after the
+    // callback sees it
+    let is_done = || {
+        let sweeps = sweeps.lock().unwrap();
+        sweeps.len() as u64 + rfe.mismatched_sweep_stats().dropped == 4
+            && rfe.sweep().as_ref() == sweeps.last()
+    };
+    let start = Instant::now();
+    while !is_done() {
+        assert!(start.elapsed() < Duration::from_secs(2));
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(port.is_finished());
+    let sweeps = sweeps.lock().unwrap().clone();
+    (rfe, sweeps)
+}
+
+#[test]
+fn drop_mismatched_sweeps_by_default() {
+    let (rfe, sweeps) = receive_mismatched_sweeps(MismatchedSweepPolicy::default());
+    assert_eq!(sweeps, [vec![-40.; 4], vec![-56.; 6]]);
+    assert_eq!(rfe.sweep_meta().unwrap().mismatched_len, None);
+    assert_eq!(
+        rfe.mismatched_sweep_stats(),
+        MismatchedSweepStats {
+            dropped: 2,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn truncate_long_mismatched_sweeps() {
+    let (rfe, sweeps) = receive_mismatched_sweeps(MismatchedSweepPolicy::Truncate);
+    assert_eq!(sweeps, [vec![-40.; 4], vec![-56.; 6], vec![-64.; 6]]);
+    assert_eq!(rfe.sweep_meta().unwrap().mismatched_len, Some(8));
+    assert_eq!(
+        rfe.mismatched_sweep_stats(),
+        MismatchedSweepStats {
+            dropped: 1,
+            truncated: 1,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn pad_short_mismatched_sweeps_with_noise_floor() {
+    let (rfe, sweeps) = receive_mismatched_sweeps(MismatchedSweepPolicy::PadWithNoiseFloor);
+    assert_eq!(
+        sweeps,
+        [
+            vec![-40.; 4],
+            vec![-48., -48., -49., -48., -49., -49.],
+            vec![-56.; 6],
+            vec![-64.; 6],
+        ]
+    );
+    assert_eq!(rfe.sweep().unwrap().len(), 6);
+    assert_eq!(rfe.sweep_meta().unwrap().mismatched_len, Some(8));
+    assert_eq!(
+        rfe.mismatched_sweep_stats(),
+        MismatchedSweepStats {
+            truncated: 1,
+            padded: 1,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn deliver_mismatched_sweeps() {
+    let (rfe, sweeps) = receive_mismatched_sweeps(MismatchedSweepPolicy::Deliver);
+    assert_eq!(
+        sweeps,
+        [
+            vec![-40.; 4],
+            vec![-48., -48., -49., -48.],
+            vec![-56.; 6],
+            [vec![-64.; 6], vec![-65.; 2]].concat(),
+        ]
+    );
+    assert_eq!(rfe.sweep().unwrap().len(), 8);
+    assert_eq!(rfe.sweep_meta().unwrap().mismatched_len, Some(8));
+    assert_eq!(
+        rfe.mismatched_sweep_stats(),
+        MismatchedSweepStats {
+            delivered: 2,
+            ..Default::default()
+        }
+    );
+}
+
+/// Returns a transcript of a 112-point config followed by the sweeps, after giving the test time
+/// to set up the spectrum analyzer.
+fn sweeps_transcript(sweeps: &[[u8; 112]]) -> String {
+    let mut transcript = String::from(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 200\n",
+    );
+    for sweep in sweeps {
+        transcript.push_str("< $S\\x70");
+        for amp in sweep {
+            transcript.push_str(&format!("\\x{amp:02X}"));
+        }
+        transcript.push_str("\n~ 20\n");
+    }
+    transcript
+}
+
+/// Connects to a spectrum analyzer that sends the sweeps after giving the test time to set up
+/// the frozen sweep watchdog.
+fn connect_and_send_sweeps(
+    sweeps: &[[u8; 112]],
+    reboot: bool,
+) -> (SpectrumAnalyzer, TranscriptPort) {
+    let mut transcript = sweeps_transcript(sweeps);
+    if reboot {
+        // The config is requested again once the RF Explorer has had time to reboot
+        transcript.push_str("> #\\x03r\n> #\\x04C0\n");
+    }
+    let port = TranscriptPort::from_transcript(&transcript);
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    rfe.set_frozen_sweep_watchdog(Some(FrozenSweepWatchdog {
+        identical_sweeps: 3,
+        reboot,
+        reboot_delay: Duration::from_millis(100),
+    }));
+    (rfe, port)
+}
+
+#[test]
+fn reboot_when_sweeps_are_frozen() {
+    let (rfe, port) = connect_and_send_sweeps(&[[200; 112]; 4], true);
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_frozen_sweep_callback(move || sender.send(()).unwrap());
+
+    receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+    let start = Instant::now();
+    while !port.is_finished() && start.elapsed() < Duration::from_secs(2) {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(port.is_finished());
+    // The callback is only called once for each run of identical sweeps
+    assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+}
+
+#[test]
+fn ignore_nearly_identical_sweeps() {
+    let sweeps: Vec<[u8; 112]> = (0..5)
+        .map(|i| {
+            let mut sweep = [200; 112];
+            sweep[111] = 200 + i;
+            sweep
+        })
+        .collect();
+    let (rfe, port) = connect_and_send_sweeps(&sweeps, false);
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_frozen_sweep_callback(move || sender.send(()).unwrap());
+
+    assert!(receiver.recv_timeout(Duration::from_millis(500)).is_err());
+    assert_eq!(rfe.sweep().unwrap()[111], -102.);
+    assert_eq!(port.writes(), [REQUEST_CONFIG]);
+}
+
+#[test]
+fn deliver_sweeps_in_parts_as_they_arrive() {
+    // Amplitudes of -5 dBm are \n bytes, so the sweep arrives in several reads
+    let mut sweep = [200; 112];
+    sweep[30] = 10;
+    sweep[80] = 10;
+    let port = TranscriptPort::from_transcript(&sweeps_transcript(&[sweep]));
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let parts = Arc::new(Mutex::new(Vec::new()));
+    let cb_parts = parts.clone();
+    rfe.set_partial_sweep_callback(move |amps, offset| {
+        cb_parts.lock().unwrap().push((offset, amps.to_vec()))
+    });
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_sweep_callback_borrowed(move |sweep| sender.send(sweep.to_vec()).unwrap());
+
+    let whole = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+    let parts = parts.lock().unwrap().clone();
+    assert!(parts.len() > 1);
+    let mut received = Vec::new();
+    for (offset, amps) in parts {
+        assert_eq!(offset, received.len());
+        received.extend(amps);
+    }
+    assert_eq!(received, whole);
+    assert_eq!(whole[80], -5.);
+}
+
+#[test]
+fn call_getters_and_remove_callbacks_from_inside_them() {
+    let sweeps: Vec<[u8; 112]> = (0..3).map(|i| [200 + i; 112]).collect();
+    let port = TranscriptPort::from_transcript(&sweeps_transcript(&sweeps));
+    let rfe = Arc::new(SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap());
+
+    // Removing a borrowed callback from inside itself used to deadlock the thread reading from
+    // the RF Explorer
+    let (sender, receiver) = mpsc::channel();
+    let weak_rfe = Arc::downgrade(&rfe);
+    rfe.set_sweep_callback_borrowed(move |sweep| {
+        let rfe = weak_rfe.upgrade().unwrap();
+        sender
+            .send((
+                sweep[0],
+                rfe.start_freq(),
+                rfe.sweep().map(|sweep| sweep[0]),
+            ))
+            .unwrap();
+        rfe.remove_sweep_callback_borrowed();
+    });
+
+    assert_eq!(
+        receiver.recv_timeout(Duration::from_secs(2)).unwrap(),
+        (-100., Frequency::from_mhz(430), Some(-100.))
+    );
+    // The callback removed itself after the first sweep, but the thread kept reading sweeps
+    let start = Instant::now();
+    while rfe.sweep().unwrap()[0] != -101. {
+        assert!(start.elapsed() < Duration::from_secs(2));
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(receiver.try_recv().is_err());
+    assert!(port.is_finished());
+}
+
+#[test]
+fn get_the_borrowed_sweep_from_inside_its_callback() {
+    let sweeps: Vec<[u8; 112]> = (0..3).map(|i| [200 + i; 112]).collect();
+    let port = TranscriptPort::from_transcript(&sweeps_transcript(&sweeps));
+    let rfe = Arc::new(SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap());
+
+    let (sender, receiver) = mpsc::channel();
+    let weak_rfe = Arc::downgrade(&rfe);
+    rfe.set_sweep_callback_borrowed(move |sweep| {
+        let rfe = weak_rfe.upgrade().unwrap();
+        sender.send((sweep.to_vec(), rfe.sweep())).unwrap();
+    });
+
+    for amp_dbm in [-100., -100.5, -101.] {
+        let (borrowed, cached) = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(borrowed, [amp_dbm; 112]);
+        assert_eq!(cached, Some(borrowed));
+    }
+    assert!(port.is_finished());
+}
+
+#[test]
+fn buffer_sweeps_for_every_subscriber() {
+    let sweeps: Vec<[u8; 112]> = (0..4).map(|i| [200 + i; 112]).collect();
+    let port = TranscriptPort::from_transcript(&sweeps_transcript(&sweeps));
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let deep = rfe.subscribe_buffered(8);
+    let shallow = rfe.subscribe_buffered(2);
+    drop(rfe.subscribe_buffered(8));
+
+    // Stall until every sweep has been buffered
+    wait_for_first_amp(&rfe, -101.5);
+    let received: Vec<f32> = (0..4)
+        .map(|_| deep.recv(Duration::from_secs(2)).unwrap().amplitudes_dbm[0])
+        .collect();
+    assert_eq!(received, [-100., -100.5, -101., -101.5]);
+    assert_eq!(deep.dropped_sweeps(), 0);
+    assert_eq!(shallow.len(), 2);
+    assert_eq!(shallow.dropped_sweeps(), 2);
+    let sweep = shallow.try_recv().unwrap();
+    assert_eq!(sweep.amplitudes_dbm[0], -101.);
+    assert_eq!(sweep.start_freq, Frequency::from_khz(430_000));
+}
+
+/// Waits until the latest sweep's first amplitude is `amp`.
+fn wait_for_first_amp(rfe: &SpectrumAnalyzer, amp: f32) {
+    let start = Instant::now();
+    while rfe.sweep().map(|sweep| sweep[0]) != Some(amp) {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn drop_lines_longer_than_the_memory_budget() {
+    let garbage = "A".repeat(10_000_000);
+    let transcript = sweeps_transcript(&[[200; 112]]).replacen(
+        "~ 200\n",
+        &format!("~ 200\n< {garbage}\n~ 20\n"),
+        1,
+    );
+    let port = TranscriptPort::from_transcript(&transcript);
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    // The connection keeps working after the garbage line is dropped
+    wait_for_first_amp(&rfe, -100.);
+    assert_eq!(rfe.memory_stats().dropped_lines, 1);
+    assert!(rfe.current_memory_usage_estimate() < 2 * MemoryBudget::DEFAULT_MAX_LINE_LEN);
+}
+
+#[test]
+fn export_latest_sweep_to_csv() {
+    let sweeps: Vec<[u8; 112]> = (0..2).map(|i| [200 + i; 112]).collect();
+    let port = TranscriptPort::from_transcript(&sweeps_transcript(&sweeps));
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let path = std::env::temp_dir().join(format!("rfe-sweep-{}.csv", std::process::id()));
+    assert!(matches!(
+        rfe.export_sweep_csv(&path),
+        Err(Error::InvalidOperation(_))
+    ));
+
+    wait_for_first_amp(&rfe, -100.5);
+    rfe.export_sweep_csv(&path).unwrap();
+    let file = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
+    let sweep = Sweep::from_csv_reader(file).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(sweep.amplitudes_dbm(), rfe.sweep().unwrap());
+    assert_eq!(sweep.meta().start_freq, Frequency::from_mhz(430));
+    assert_eq!(sweep.meta().stop_freq, rfe.stop_freq());
+}
+
+#[test]
+fn attach_events_to_next_sweep() {
+    let sweeps: Vec<[u8; 112]> = (0..3).map(|i| [200 + i; 112]).collect();
+    let port = TranscriptPort::from_transcript(&sweeps_transcript(&sweeps));
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let sink = MemorySink::new();
+    let session = RecordingSession::start(&rfe, sink.clone()).unwrap();
+    let receiver = rfe.subscribe_buffered(8);
+
+    rfe.annotate_event("tx on");
+    // An event timestamped by the test rig after every sweep has been received
+    rfe.annotate_event_at("tx off", Utc::now() + TimeDelta::hours(1));
+    let events: Vec<Vec<String>> = (0..3)
+        .map(|_| {
+            let sweep = receiver.recv(Duration::from_secs(2)).unwrap();
+            let events = sweep.meta.events_since_previous_sweep;
+            events.into_iter().map(|event| event.label).collect()
+        })
+        .collect();
+    assert_eq!(events, [vec!["tx on".to_string()], vec![], vec![]]);
+    assert_eq!(rfe.dropped_events(), 0);
+
+    let summary = session.stop().unwrap();
+    assert_eq!(summary.events_recorded, 2);
+    let labels: Vec<String> = sink
+        .records()
+        .into_iter()
+        .filter_map(|record| match record {
+            Record::Event(event) => Some(event.label),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(labels, ["tx on", "tx off"]);
+}
+
+#[test]
+fn record_link_health_snapshots() {
+    let port = TranscriptPort::from_transcript(&sweeps_transcript(&[[200; 112]]));
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let sink = MemorySink::new();
+    let session = RecordingSession::start(&rfe, sink.clone()).unwrap();
+
+    let start = Instant::now();
+    let link_health = loop {
+        if let Some(link_health) = sink.records().into_iter().find_map(|record| match record {
+            Record::LinkHealth { link_health, .. } => Some(link_health),
+            _ => None,
+        }) {
+            break link_health;
+        }
+        assert!(start.elapsed() < RecordingSession::LINK_HEALTH_INTERVAL * 3);
+        thread::sleep(Duration::from_millis(50));
+    };
+    assert_eq!(link_health.read_errors.parse_errors, 0);
+    assert!(link_health.since_last_message.is_some());
+    assert!(session.stop().unwrap().link_health_recorded >= 1);
+}
+
+#[test]
+fn reject_tracking_outside_frequency_range() {
+    let port = TranscriptPort::from_transcript(&sweeps_transcript(&[]));
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    // The WSUB3G covers 15-2700 MHz
+    assert!(matches!(
+        rfe.request_tracking(Frequency::from_mhz(2_701).as_hz(), 1_000_000),
+        Err(Error::InvalidInput(_))
+    ));
+    let plan = TrackingStepPlan {
+        start: Frequency::from_mhz(2_600),
+        step: Frequency::from_mhz(1),
+        steps: 102,
+    };
+    assert!(matches!(
+        rfe.request_tracking_plan(&plan, None),
+        Err(Error::InvalidInput(message)) if message.contains("stop frequency 2701 MHz")
+    ));
+
+    // Nothing was sent to the spectrum analyzer
+    assert_eq!(port.writes(), [REQUEST_CONFIG]);
+}
+
+#[test]
+fn attenuate_overloaded_input() {
+    // A -10 dBm signal on a 6G Plus, which has an input stage
+    let mut transcript =
+        sweeps_transcript(&[[20; 112]; 3]).replace("#C2-M:005,006,01.12B26", "#C2-M:014,255,03.41");
+    transcript.push_str("> #\\x04a1\n");
+    let port = TranscriptPort::from_transcript(&transcript);
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_input_stage_transition_callback(move |transition| sender.send(transition).unwrap());
+    rfe.set_auto_input_stage(Some(AutoInputStage::default()))
+        .unwrap();
+
+    let transition = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(transition.to, InputStage::Attenuator30dB);
+    assert_eq!(transition.peak_dbm, -10.);
+    assert_eq!(rfe.input_stage(), Some(InputStage::Attenuator30dB));
+    let start = Instant::now();
+    while !port.is_finished() && start.elapsed() < Duration::from_secs(2) {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(port.is_finished());
+}
+
+#[test]
+fn refuse_auto_input_stage_without_input_stage() {
+    let (rfe, _port) = connect_and_send_sweeps(&[], false);
+    assert!(matches!(
+        rfe.set_auto_input_stage(Some(AutoInputStage::default())),
+        Err(Error::InvalidOperation(_))
+    ));
+    assert_eq!(rfe.auto_input_stage(), None);
+}
+
+#[test]
+fn drop_corrupt_sweeps() {
+    // Noise around -100 dBm with a -40 dBm tone, varying a little from sweep to sweep
+    let mut sweeps: Vec<[u8; 112]> = (0..8u8)
+        .map(|i| {
+            std::array::from_fn(|point| match point {
+                50..54 => 80,
+                _ => 196 + (point as u8 + i) % 9,
+            })
+        })
+        .collect();
+    // Lost bytes shift the rest of the sweep and the tone lands on garbage
+    let mut corrupt = sweeps[5];
+    corrupt.rotate_left(20);
+    corrupt[60..100].fill(27);
+    sweeps.insert(6, corrupt);
+
+    let port = TranscriptPort::from_transcript(&sweeps_transcript(&sweeps));
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    rfe.set_corruption_heuristic(Some(HeuristicConfig {
+        history_len: 4,
+        drop: true,
+        ..HeuristicConfig::default()
+    }));
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_sweep_callback_borrowed(move |sweep| sender.send(sweep.to_vec()).unwrap());
+
+    // Only the corrupt sweep is dropped
+    for _ in 0..8 {
+        let sweep = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert!(sweep[60..100].iter().all(|amp| *amp < -90.), "{sweep:?}");
+    }
+    assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+    assert_eq!(
+        rfe.corruption_stats(),
+        CorruptionStats {
+            checked: 5,
+            flagged: 1,
+            dropped: 1,
+        }
+    );
+    assert!(!rfe.sweep_meta().unwrap().suspected_corrupt);
+}
+
+/// Returns what a WSUB3G with a 6G expansion module reports when it's first connected to.
+fn cached_wsub3g_identity() -> CachedIdentity {
+    let port = TranscriptPort::from_transcript(&sweeps_transcript(&[]));
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    rfe.cached_identity().unwrap()
+}
+
+#[test]
+fn connect_with_cached_identity() {
+    let identity = cached_wsub3g_identity();
+    assert!(!identity.has_serial_number());
+
+    // The RF Explorer is streaming sweeps and only sends its new config later
+    let sweep = "\\x80".repeat(112);
+    let port = TranscriptPort::from_transcript(&format!(
+        "> #\\x04C0\n\
+         < $S\\x70{sweep}\n\
+         ~ 300\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:2400000,0178571,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\n"
+    ));
+    let started_at = Instant::now();
+    let rfe =
+        SpectrumAnalyzer::connect_with_transport_and_cached_identity(port.transport(), &identity)
+            .unwrap();
+    assert!(started_at.elapsed() < Duration::from_millis(300));
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_config_callback(move |config| sender.send(config).unwrap());
+
+    // The cached config is used until the real one arrives
+    assert_eq!(rfe.main_radio_model(), Some(Model::RfeWSub3G));
+    assert_eq!(rfe.start_freq(), Frequency::from_khz(430_000));
+    assert_eq!(rfe.sweep_len(), 112);
+    let config = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(config.start_freq, Frequency::from_mhz(2_400));
+    assert_eq!(rfe.start_freq(), Frequency::from_mhz(2_400));
+    assert_eq!(port.writes(), [REQUEST_CONFIG]);
+}
+
+#[test]
+fn resynchronize_when_cached_setup_info_differs() {
+    let identity = cached_wsub3g_identity();
+
+    // Another RF Explorer was plugged into the port
+    let sweep = "\\x80".repeat(112);
+    let port = TranscriptPort::from_transcript(&format!(
+        "> #\\x04C0\n\
+         < $S\\x70{sweep}\n\
+         ~ 300\n\
+         < #C2-M:014,255,03.41\n"
+    ));
+    let rfe =
+        SpectrumAnalyzer::connect_with_transport_and_cached_identity(port.transport(), &identity)
+            .unwrap();
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_config_callback(move |config| sender.send(config).unwrap());
+
+    let config = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(config.start_freq, Frequency::from_khz(430_000));
+    assert_eq!(rfe.main_radio_model(), Some(Model::Rfe6GPlus));
+    assert_eq!(rfe.expansion_radio_model(), None);
+}
+
+#[test]
+fn fail_to_connect_with_cached_identity_to_silent_port() {
+    let identity = cached_wsub3g_identity();
+    let port = TranscriptPort::from_transcript("> #\\x04C0\n");
+    assert!(matches!(
+        SpectrumAnalyzer::connect_with_transport_and_cached_identity(port.transport(), &identity),
+        Err(ConnectionError::DeviceInfoNotReceived)
+    ));
+}
+
+#[test]
+fn refuse_to_connect_with_unsupported_baud_rate() {
+    let error =
+        SpectrumAnalyzer::connect_with_name_and_baud_rate("RF Explorer", 250_000).unwrap_err();
+    assert!(
+        matches!(error, ConnectionError::UnsupportedBaudRate(250_000)),
+        "{error:?}"
+    );
+    assert_eq!(
+        error.to_string(),
+        "250000 bps isn't a baud rate an RF Explorer supports"
+    );
+}
+
+#[test]
+fn return_disconnected_error_when_unplugged_while_waiting() {
+    let port = TranscriptPort::load("6g_combo_sweeps.txt");
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    rfe.wait_for_next_sweep().unwrap();
+
+    let unplug_port = port.clone();
+    let unplug_thread = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        unplug_port.unplug();
+    });
+
+    // Waiting returns as soon as the reader thread stops instead of waiting out the timeout
+    let start = Instant::now();
+    let mut result = rfe.wait_for_next_sweep_with_timeout(Duration::from_secs(10));
+    while result.is_ok() {
+        result = rfe.wait_for_next_sweep_with_timeout(Duration::from_secs(10));
+    }
+    unplug_thread.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    let Err(Error::Disconnected(source)) = result else {
+        panic!("Expected a disconnected error but got {result:?}");
+    };
+    assert_eq!(source.kind(), std::io::ErrorKind::BrokenPipe);
+
+    // Later waits fail immediately and the last sweep is still available
+    assert!(matches!(
+        rfe.wait_for_next_screen_data_with_timeout(Duration::from_secs(10)),
+        Err(Error::Disconnected(_))
+    ));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(rfe.sweep().map(|sweep| sweep.len()), Some(112));
+}
+
+#[test]
+fn reconnect_after_unplugged_mid_sweep() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 50\n\
+         < $S\\x02\\x50\\x50\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let (disconnect_tx, disconnect_rx) = mpsc::channel();
+    rfe.set_disconnect_callback(move || disconnect_tx.send(()).unwrap());
+    let (config_tx, config_rx) = mpsc::channel();
+    rfe.set_config_callback(move |config| config_tx.send(config).unwrap());
+    assert_eq!(rfe.wait_for_next_sweep().unwrap(), [-40., -40.]);
+    assert!(rfe.is_connected());
+
+    port.unplug();
+    disconnect_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert!(!rfe.is_connected());
+    assert_eq!(rfe.connection_state(), ConnectionState::Disconnected);
+    assert!(matches!(
+        rfe.wait_for_next_sweep(),
+        Err(Error::Disconnected(_))
+    ));
+
+    // The RF Explorer comes back with a new config, which is reported by the same callback
+    let replugged_port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0440000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 50\n\
+         < $S\\x02\\x64\\x64\n",
+    );
+    rfe.reconnect_with_transport(replugged_port.transport())
+        .unwrap();
+    assert!(rfe.is_connected());
+    assert_eq!(rfe.wait_for_next_sweep().unwrap(), [-50., -50.]);
+    assert_eq!(
+        config_rx
+            .recv_timeout(Duration::from_secs(2))
+            .unwrap()
+            .start_freq,
+        Frequency::from_khz(440_000)
+    );
+    assert!(replugged_port.is_finished());
+
+    // The disconnect callback is kept too
+    replugged_port.unplug();
+    disconnect_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert!(!rfe.is_connected());
+}
+
+#[test]
+fn stop_waiting_for_module_switch_on_disconnect() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0180180,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         > #\\x05CM\\x01\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    // The RF Explorer is unplugged before it replies to the module switch
+    let unplug_port = port.clone();
+    let unplug = thread::spawn(move || {
+        while !unplug_port.is_finished() {
+            thread::sleep(Duration::from_millis(5));
+        }
+        unplug_port.unplug();
+    });
+    let start = Instant::now();
+    assert!(matches!(
+        rfe.activate_expansion_radio(),
+        Err(Error::Disconnected(_))
+    ));
+    assert!(start.elapsed() < Duration::from_secs(1));
+    unplug.join().unwrap();
+}
+
+#[test]
+fn reconnect_from_two_threads_at_once() {
+    let transcript = "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0002,0,000,0015000,2700000,2685000,00200,0000,000\n";
+    let port = TranscriptPort::from_transcript(transcript);
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    port.unplug();
+    let start = Instant::now();
+    while rfe.is_connected() {
+        assert!(start.elapsed() < Duration::from_secs(2));
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let replugged_ports = [
+        TranscriptPort::from_transcript(transcript),
+        TranscriptPort::from_transcript(transcript),
+    ];
+    // Each reconnect waits a while for the other one to open its port, which they'd both do
+    // before either started reading if reconnects could overlap
+    let opened = Arc::new((Mutex::new(0), Condvar::new()));
+    for replugged_port in &replugged_ports {
+        let opened = opened.clone();
+        replugged_port.set_on_baud_rate(move || {
+            let (count, condvar) = &*opened;
+            *count.lock().unwrap() += 1;
+            condvar.notify_all();
+            let _ = condvar
+                .wait_timeout_while(count.lock().unwrap(), Duration::from_millis(200), |count| {
+                    *count < 2
+                })
+                .unwrap();
+        });
+    }
+    thread::scope(|scope| {
+        for replugged_port in &replugged_ports {
+            let rfe = &rfe;
+            scope.spawn(move || {
+                rfe.reconnect_with_transport(replugged_port.transport())
+                    .unwrap();
+            });
+        }
+    });
+    assert!(rfe.is_connected());
+
+    // Only one reader was left running, so dropping the analyzer releases every port
+    drop(rfe);
+    assert!(port.is_released());
+    for replugged_port in &replugged_ports {
+        assert!(replugged_port.is_finished());
+        assert!(replugged_port.is_released());
+    }
+}
+
+#[test]
+fn stay_disconnected_when_reconnecting_to_silent_device() {
+    let port = TranscriptPort::load("6g_combo_sweeps.txt");
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    port.unplug();
+    let start = Instant::now();
+    while rfe.is_connected() {
+        assert!(start.elapsed() < Duration::from_secs(2));
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let silent_port = TranscriptPort::from_transcript("> #\\x04C0\n");
+    assert!(matches!(
+        rfe.reconnect_with_transport(silent_port.transport()),
+        Err(ConnectionError::DeviceInfoNotReceived)
+    ));
+    assert!(!rfe.is_connected());
+    assert!(matches!(
+        rfe.wait_for_next_sweep(),
+        Err(Error::Disconnected(_))
+    ));
+}
+
+#[test]
+fn quiesce_when_dropped() {
+    let port = TranscriptPort::load("6g_combo_sweeps.txt");
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    assert!(!rfe.quiesce_on_drop());
+    drop(rfe);
+    assert_eq!(port.writes(), [REQUEST_CONFIG]);
+
+    let port = TranscriptPort::load("6g_combo_sweeps.txt");
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    rfe.set_quiesce_on_drop(true);
+    drop(rfe);
+    assert_eq!(port.writes(), [REQUEST_CONFIG, b"#\x04CH", b"#\x04D0"]);
+}
+
+#[test]
+fn drop_unplugged_analyzer_quickly_when_quiescing() {
+    let port = TranscriptPort::load("6g_combo_sweeps.txt");
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    rfe.set_quiesce_on_drop(true);
+    rfe.wait_for_next_sweep().unwrap();
+    port.unplug();
+
+    // The failed writes are ignored instead of holding up the drop
+    let start = Instant::now();
+    drop(rfe);
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(port.writes(), [REQUEST_CONFIG]);
+}
+
+#[test]
+fn refuse_commands_in_bootloader_mode() {
+    let port = TranscriptPort::load("6g_plus_bootloader.txt");
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let states = Arc::new(Mutex::new(Vec::new()));
+    let callback_states = states.clone();
+    rfe.set_connection_state_callback(move |state| callback_states.lock().unwrap().push(state));
+    assert_eq!(rfe.connection_state(), ConnectionState::Connected);
+
+    let wait_for_state = |state| {
+        let start = Instant::now();
+        while rfe.connection_state() != state {
+            assert!(start.elapsed() < Duration::from_secs(2), "Never {state:?}");
+            thread::sleep(Duration::from_millis(10));
+        }
+    };
+
+    // Nothing is written to the device while it's in bootloader mode
+    wait_for_state(ConnectionState::Bootloader);
+    let error = rfe.hold().unwrap_err();
+    assert!(
+        matches!(&error, Error::InvalidOperation(message) if message == "device is in bootloader mode"),
+        "{error:?}"
+    );
+    assert!(matches!(
+        rfe.set_start_stop(Frequency::from_mhz(2_410), Frequency::from_mhz(2_480)),
+        Err(Error::InvalidOperation(_))
+    ));
+    assert_eq!(port.writes(), [REQUEST_CONFIG]);
+
+    // The firmware sending its config again restores normal operation
+    wait_for_state(ConnectionState::Connected);
+    rfe.hold().unwrap();
+    assert_eq!(port.writes(), [REQUEST_CONFIG, b"#\x04CH"]);
+    assert_eq!(
+        *states.lock().unwrap(),
+        [ConnectionState::Bootloader, ConnectionState::Connected]
+    );
+}
+
+/// Replays a synthetic failure transcript and returns the diagnoses reported by the connection
+/// state callback until the link is diagnosed with `diagnosis`.
+fn diagnose_transcript(
+    name: &str,
+    diagnosis: LinkDiagnosis,
+) -> (SpectrumAnalyzer, Vec<LinkDiagnosis>) {
+    let port = TranscriptPort::load(name);
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_connection_state_callback(move |state| {
+        if let ConnectionState::Degraded(diagnosis) = state {
+            let _ = sender.send(diagnosis);
+        }
+    });
+
+    let mut diagnoses = Vec::new();
+    while diagnoses.last() != Some(&diagnosis) {
+        let Ok(diagnosis) = receiver.recv_timeout(Duration::from_secs(2)) else {
+            panic!("Never diagnosed {diagnosis:?}, only {diagnoses:?}");
+        };
+        diagnoses.push(diagnosis);
+    }
+    (rfe, diagnoses)
+}
+
+#[test]
+fn diagnose_brownout_as_power_issue() {
+    let (rfe, diagnoses) =
+        diagnose_transcript("6g_plus_brownout.txt", LinkDiagnosis::SuspectedPowerIssue);
+    assert_eq!(
+        diagnoses,
+        [LinkDiagnosis::Unknown, LinkDiagnosis::SuspectedPowerIssue]
+    );
+    assert_eq!(
+        rfe.connection_state(),
+        ConnectionState::Degraded(LinkDiagnosis::SuspectedPowerIssue)
+    );
+
+    let link_health = rfe.link_health();
+    assert_eq!(link_health.diagnosis, LinkDiagnosis::SuspectedPowerIssue);
+    assert_eq!(
+        link_health
+            .evidence
+            .iter()
+            .map(|event| event.kind)
+            .collect::<Vec<_>>(),
+        [LinkEventKind::Garbage, LinkEventKind::Restarted]
+    );
+    assert_eq!(link_health.evidence[1].raw, b"#C2-M:014,255,03.41\r\n");
+}
+
+#[test]
+fn diagnose_noisy_cable_as_cable_issue() {
+    let (rfe, _) = diagnose_transcript(
+        "6g_plus_noisy_cable.txt",
+        LinkDiagnosis::SuspectedCableIssue,
+    );
+
+    let link_health = rfe.link_health();
+    assert_eq!(link_health.diagnosis, LinkDiagnosis::SuspectedCableIssue);
+    assert_eq!(link_health.evidence.len(), 3);
+    assert_eq!(link_health.read_errors.parse_errors, 3);
+}
+
+#[test]
+fn diagnose_firmware_update_as_healthy() {
+    let port = TranscriptPort::load("6g_plus_bootloader.txt");
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    let (sender, receiver) = mpsc::channel();
+    rfe.set_connection_state_callback(move |state| sender.send(state).unwrap());
+    assert_eq!(
+        receiver.recv_timeout(Duration::from_secs(2)),
+        Ok(ConnectionState::Bootloader)
+    );
+    assert_eq!(
+        receiver.recv_timeout(Duration::from_secs(2)),
+        Ok(ConnectionState::Connected)
+    );
+
+    // The bootloader's traffic and the restart after the update aren't problems with the link
+    let link_health = rfe.link_health();
+    assert_eq!(link_health.diagnosis, LinkDiagnosis::Healthy);
+    assert!(link_health.evidence.is_empty());
+}
+
+#[test]
+fn zoom_to_peak() {
+    let mut sweep = [200; 112];
+    sweep[56] = 40;
+    let mut transcript = sweeps_transcript(&[sweep]);
+    transcript.push_str(
+        "> #\\x20C2-F:0434999,0444999,-030,-118\n\
+         < #C2-F:0434999,0090090,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\n",
+    );
+    let port = TranscriptPort::from_transcript(&transcript);
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    rfe.wait_for_next_sweep().unwrap();
+
+    let peak_freq = rfe.zoom_to_peak(Frequency::from_mhz(10)).unwrap();
+    assert_eq!(peak_freq, Frequency::from_hz(439_999_976));
+    assert_eq!(rfe.start_freq(), Frequency::from_khz(434_999));
+    assert!(port.is_finished());
+}
+
+#[test]
+fn zoom_out() {
+    let port = TranscriptPort::from_transcript(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         > #\\x20C2-F:0400267,0479553,-030,-118\n\
+         < #C2-F:0400267,0714283,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\n",
+    );
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    assert!(matches!(rfe.zoom_out(0.5), Err(Error::InvalidInput(_))));
+    rfe.zoom_out(4.).unwrap();
+    assert_eq!(rfe.span(), Frequency::from_hz(79_285_413));
+    assert!(port.is_finished());
+}
+
+#[test]
+fn refuse_to_zoom_in_wifi_analyzer_mode() {
+    let mut transcript = sweeps_transcript(&[[200; 112]]);
+    transcript = transcript.replace(",0,000,0015000", ",0,002,0015000");
+    let port = TranscriptPort::from_transcript(&transcript);
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+    rfe.wait_for_next_sweep().unwrap();
+
+    assert!(matches!(
+        rfe.zoom_to_peak(Frequency::from_mhz(10)),
+        Err(Error::InvalidOperation(_))
+    ));
+    assert!(matches!(rfe.zoom_out(2.), Err(Error::InvalidOperation(_))));
+    assert_eq!(port.writes(), [REQUEST_CONFIG]);
+}
@@ -0,0 +1,109 @@
+//! Counts the allocations made while receiving sweeps.
+//!
+//! Before amplitude buffers were pooled, every sweep made 2 allocations: 1 for its amplitudes
+//! and 1 for its metadata. With a borrowed sweep callback, sweeps are received without any
+//! allocations once the first few have been received.
+
+mod common;
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::TranscriptPort;
+use rfe::SpectrumAnalyzer;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const SWEEP_COUNT: usize = 20;
+const WARM_UP_SWEEPS: usize = 5;
+
+fn sweeps_transcript() -> String {
+    let mut transcript = String::from(
+        "> #\\x04C0\n\
+         < #C2-M:005,006,01.12B26\n\
+         < #C2-F:0430000,0178571,-030,-118,0112,0,000,0015000,2700000,2685000,00200,0000,000\n\
+         ~ 200\n",
+    );
+    for i in 0..SWEEP_COUNT {
+        transcript.push_str("< $S\\x70");
+        for j in 0..112 {
+            transcript.push_str(&format!("\\x{:02X}", 120 + (i + j) % 100));
+        }
+        transcript.push_str("\n~ 30\n");
+    }
+    transcript
+}
+
+#[test]
+fn receive_sweeps_without_allocating() {
+    // Without a subscriber, tracing falls back to formatting its spans for the `log` crate when
+    // its "log" feature is enabled by another crate in the workspace
+    tracing::dispatcher::set_global_default(tracing::Dispatch::new(
+        tracing::subscriber::NoSubscriber::default(),
+    ))
+    .unwrap();
+
+    let port = TranscriptPort::from_transcript(&sweeps_transcript());
+    let rfe = SpectrumAnalyzer::connect_with_transport(port.transport()).unwrap();
+
+    // Record the allocation count when each sweep is received
+    let allocation_counts = Arc::new(Mutex::new(Vec::with_capacity(SWEEP_COUNT)));
+    let allocation_counts_clone = allocation_counts.clone();
+    rfe.set_sweep_callback_borrowed(move |amps| {
+        assert_eq!(amps.len(), 112);
+        let mut allocation_counts = allocation_counts_clone.lock().unwrap();
+        if allocation_counts.len() < allocation_counts.capacity() {
+            allocation_counts.push(ALLOCATIONS.load(Ordering::Relaxed));
+        }
+    });
+
+    let start = Instant::now();
+    while allocation_counts.lock().unwrap().len() < SWEEP_COUNT
+        && start.elapsed() < Duration::from_secs(10)
+    {
+        thread::sleep(Duration::from_millis(50));
+    }
+    rfe.remove_sweep_callback_borrowed();
+
+    let allocation_counts = allocation_counts.lock().unwrap().clone();
+    assert_eq!(allocation_counts.len(), SWEEP_COUNT);
+    let allocations_per_sweep: Vec<_> = allocation_counts
+        .windows(2)
+        .map(|counts| counts[1] - counts[0])
+        .skip(WARM_UP_SWEEPS)
+        .collect();
+    assert!(
+        allocations_per_sweep
+            .iter()
+            .all(|&allocations| allocations == 0),
+        "{allocations_per_sweep:?}"
+    );
+}
@@ -0,0 +1,125 @@
+use std::{env, io, net::TcpListener, process::ExitCode, thread};
+
+use rfe::simulator::{self, Scenario, Simulator};
+use tracing::{info, warn};
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+
+const USAGE: &str = "Usage: rfe-sim [--tcp <ADDR>] [SCENARIO]
+
+Emulates an RF Explorer spectrum analyzer described by a TOML scenario file.
+
+Options:
+  --tcp <ADDR>  Listen for hosts on a TCP address, like 127.0.0.1:5025, instead of a pseudo-terminal
+  -h, --help    Print this message";
+
+fn main() -> ExitCode {
+    let subscriber = FmtSubscriber::builder()
+        .with_env_filter(EnvFilter::from_default_env())
+        .finish();
+    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+
+    let mut args = env::args().skip(1);
+    let mut tcp_addr = None;
+    let mut scenario_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            "--tcp" => match args.next() {
+                Some(addr) => tcp_addr = Some(addr),
+                None => {
+                    eprintln!("--tcp requires an address\n\n{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            _ if scenario_path.is_none() && !arg.starts_with('-') => scenario_path = Some(arg),
+            _ => {
+                eprintln!("Unexpected argument '{arg}'\n\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let scenario = match scenario_path {
+        Some(path) => match Scenario::load(&path) {
+            Ok(scenario) => scenario,
+            Err(error) => {
+                eprintln!("Failed to load {path}: {error}");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Scenario::default(),
+    };
+
+    let result = match tcp_addr {
+        Some(addr) => serve_tcp(&addr, &scenario),
+        None => serve_pty(&scenario),
+    };
+    if let Err(error) = result {
+        eprintln!("{error}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Serves a separate simulator to each host that connects to the address.
+fn serve_tcp(addr: &str, scenario: &Scenario) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    println!("Listening on {}", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let peer_addr = stream.peer_addr()?;
+        info!(%peer_addr, "Host connected");
+        let simulator = Simulator::new(scenario.clone());
+        thread::spawn(move || {
+            let result = stream
+                .try_clone()
+                .and_then(|reader| simulator::serve(simulator, reader, stream));
+            match result {
+                Ok(()) => info!(%peer_addr, "Host disconnected"),
+                Err(error) => warn!(%peer_addr, %error, "Host disconnected after an error"),
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Serves a simulator on a pseudo-terminal that hosts can open like a serial port.
+#[cfg(unix)]
+fn serve_pty(scenario: &Scenario) -> io::Result<()> {
+    use std::{fs::File, os::fd::FromRawFd};
+
+    use nix::sys::termios::{self, SetArg};
+
+    let pty = nix::pty::openpty(None, None)?;
+    // SAFETY: openpty returns newly opened file descriptors that nothing else owns
+    let (master, slave) = unsafe { (File::from_raw_fd(pty.master), File::from_raw_fd(pty.slave)) };
+
+    // Raw mode keeps the terminal from changing the bytes sent to the host, like turning \r
+    // into \n
+    let mut attrs = termios::tcgetattr(pty.slave)?;
+    termios::cfmakeraw(&mut attrs);
+    termios::tcsetattr(pty.slave, SetArg::TCSANOW, &attrs)?;
+
+    println!("Serving on {}", nix::unistd::ttyname(pty.slave)?.display());
+
+    // The slave stays open so the pseudo-terminal isn't hung up when a host closes it. Like a
+    // real RF Explorer, the simulator keeps its config between hosts.
+    let _slave = slave;
+    simulator::serve(
+        Simulator::new(scenario.clone()),
+        master.try_clone()?,
+        master,
+    )
+}
+
+#[cfg(not(unix))]
+fn serve_pty(_: &Scenario) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Pseudo-terminals are only supported on Unix, so use --tcp to serve the simulator",
+    ))
+}